@@ -673,6 +673,9 @@ def external_crates_repository(name, annotations):
             "quickcheck": crate.spec(
                 version = "^1.0.3",
             ),
+            "quinn": crate.spec(
+                version = "^0.7.2",
+            ),
             "quote": crate.spec(
                 version = "^1.0",
             ),
@@ -1115,6 +1118,9 @@ def external_crates_repository(name, annotations):
                     "zeroize_derive",
                 ],
             ),
+            "zstd": crate.spec(
+                version = "^0.11.2",
+            ),
         },
         splicing_config = splicing_config(
             resolver_version = "2",
@@ -1,20 +1,31 @@
+use crate::config::{RetentionPolicy, StorageBackend};
 use crate::notification_client::NotificationClient;
 use crate::util::{block_on, sleep_secs};
-use ic_recovery::command_helper::exec_cmd;
+use ic_config::artifact_pool::BACKUP_GROUP_SIZE;
+use ic_recovery::command_helper::{exec_cmd, pipe_all};
 use ic_recovery::file_sync_helper::download_binary;
 use ic_registry_client::client::{RegistryClient, RegistryClientImpl};
 use ic_registry_client_helpers::node::NodeRegistry;
 use ic_registry_client_helpers::subnet::SubnetRegistry;
+use ic_replay::content_store::{
+    archive_artifact_group, archive_checkpoint, checkpoint_keys, load_checkpoint_manifest,
+    restore_checkpoint, ArtifactIndex, ContentStore, ObjectKey, ARTIFACT_INDEX_FILE,
+    CHECKPOINT_MANIFEST_FILE, CONTENT_STORE_DIR,
+};
+use ic_replay::drill::{checkpoint_state_hash, cup_heights, cup_state_hash};
 use ic_types::{ReplicaVersion, SubnetId};
 
 use rand::seq::SliceRandom;
-use rand::thread_rng;
+use rand::{thread_rng, Rng};
+use serde::Serialize;
 use slog::{error, info, warn, Logger};
+use std::collections::BTreeSet;
 use std::ffi::OsStr;
 use std::fs::File;
 use std::io::Write;
 use std::net::IpAddr;
-use std::path::PathBuf;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
 use std::sync::{Arc, Mutex};
 use std::time::Instant;
@@ -32,6 +43,8 @@ pub struct BackupHelper {
     pub notification_client: NotificationClient,
     pub downloads: Arc<Mutex<bool>>,
     pub disk_threshold_warn: u32,
+    pub storage_backend: StorageBackend,
+    pub retention: RetentionPolicy,
     pub log: Logger,
 }
 
@@ -45,6 +58,29 @@ enum DiskStats {
     Space,
 }
 
+/// An archived checkpoint that a restore drill can replay from, with the
+/// heights of the catch-up packages of the same replica version above it.
+#[derive(Debug, PartialEq)]
+struct DrillCandidate {
+    version: String,
+    checkpoint_height: u64,
+    cup_heights: Vec<u64>,
+}
+
+/// The outcome of a restore drill, written as JSON to the reports directory.
+#[derive(Debug, Serialize)]
+struct DrillReport {
+    subnet_id: String,
+    replica_version: String,
+    checkpoint_height: u64,
+    cup_height: u64,
+    passed: bool,
+    cup_state_hash: Option<String>,
+    replayed_state_hash: Option<String>,
+    error: Option<String>,
+    duration_secs: u64,
+}
+
 impl BackupHelper {
     fn binary_dir(&self, replica_version: &ReplicaVersion) -> PathBuf {
         self.root_dir.join(format!("binaries/{}", replica_version))
@@ -86,9 +122,28 @@ impl BackupHelper {
         self.root_dir.join("archive")
     }
 
+    fn archive_subnet_dir(&self) -> PathBuf {
+        self.archive_root_dir().join(self.subnet_id.to_string())
+    }
+
     fn archive_dir(&self, last_height: u64) -> PathBuf {
-        self.archive_root_dir()
-            .join(format!("{}/{}", self.subnet_id, last_height))
+        self.archive_subnet_dir().join(last_height.to_string())
+    }
+
+    fn drills_dir(&self) -> PathBuf {
+        self.root_dir.join("drills")
+    }
+
+    fn drill_data_dir(&self) -> PathBuf {
+        self.drills_dir().join(self.subnet_id.to_string())
+    }
+
+    fn drill_reports_dir(&self) -> PathBuf {
+        self.drills_dir().join("reports")
+    }
+
+    fn content_store(&self) -> ContentStore {
+        ContentStore::for_spool(&self.spool_root_dir())
     }
 
     fn username(&self) -> String {
@@ -190,11 +245,19 @@ impl BackupHelper {
             node_ip,
             self.subnet_id
         );
+        // Groups moved to the content store must not be synced again.
+        let excludes = self
+            .archived_groups()
+            .iter()
+            .map(|(version, group)| format!("--exclude=/{}/{}/", version, group))
+            .collect::<Vec<_>>();
+        let mut arguments = vec!["-qa", "--append-verify"];
+        arguments.extend(excludes.iter().map(|s| s.as_str()));
         for _ in 0..RETRIES_RSYNC_HOST {
             match self.rsync_cmd(
                 remote_dir.clone(),
                 &self.spool_dir().into_os_string(),
-                &["-qa", "--append-verify"],
+                &arguments,
             ) {
                 Ok(_) => return,
                 Err(e) => warn!(
@@ -470,6 +533,212 @@ impl BackupHelper {
         None
     }
 
+    /// Runs a restore drill: replays the backup from the archived checkpoint
+    /// below a randomly chosen height up to the next catch-up package and
+    /// checks that the state hash of the replayed checkpoint matches the one
+    /// certified in the catch-up package. The outcome is reported to slack,
+    /// exported as metrics and written to a JSON report.
+    pub fn drill(&self) {
+        let (version, checkpoint_height, cup_height) = match self.pick_drill() {
+            Some(drill) => drill,
+            None => {
+                warn!(
+                    self.log,
+                    "No archived checkpoint of subnet {} can be used for a restore drill",
+                    self.subnet_id
+                );
+                return;
+            }
+        };
+        info!(
+            self.log,
+            "Restore drill of subnet {}: replaying from height {} to the CUP at height {}",
+            self.subnet_id,
+            checkpoint_height,
+            cup_height
+        );
+        let start_time = Instant::now();
+        let result = self.run_drill(&version, checkpoint_height, cup_height);
+        let mut report = DrillReport::new(
+            self.subnet_id,
+            version,
+            checkpoint_height,
+            cup_height,
+            result,
+        );
+        report.duration_secs = start_time.elapsed().as_secs();
+        if let Err(err) = std::fs::remove_dir_all(self.drill_data_dir()) {
+            warn!(self.log, "Failed to clean up the restore drill: {}", err);
+        }
+
+        if report.passed {
+            info!(self.log, "Restore drill passed: {:?}", report);
+            self.notification_client.message_slack(format!(
+                "✅ Restore drill passed: replayed the backup from height *{}* to the CUP at height *{}*",
+                checkpoint_height, cup_height
+            ));
+        } else {
+            error!(self.log, "Restore drill failed: {:?}", report);
+            self.notification_client.report_failure_slack(format!(
+                "Restore drill from height {} to the CUP at height {} failed: {}",
+                checkpoint_height,
+                cup_height,
+                report.error.as_deref().unwrap_or_default()
+            ));
+        }
+        self.notification_client
+            .push_metrics_drill(report.passed, cup_height);
+        if let Err(err) = self.write_drill_report(&report) {
+            warn!(
+                self.log,
+                "Failed to write the restore drill report: {}", err
+            );
+        }
+    }
+
+    /// Picks a random height above the lowest archived checkpoint and returns
+    /// the replica version, the height of the archived checkpoint to replay
+    /// from and the height of the catch-up package to verify against.
+    fn pick_drill(&self) -> Option<(String, u64, u64)> {
+        let candidates = self.drill_candidates();
+        let heights = drill_heights(&candidates)?;
+        select_drill(candidates, thread_rng().gen_range(heights))
+    }
+
+    /// Returns the archived checkpoints that can be replayed with the binaries
+    /// at hand, together with the heights of the catch-up packages following
+    /// them.
+    fn drill_candidates(&self) -> Vec<DrillCandidate> {
+        let archived = self
+            .archived_heights()
+            .into_iter()
+            .filter(|height| self.has_archived_checkpoint(*height))
+            .collect::<BTreeSet<_>>();
+        let mut cups_by_version = Vec::new();
+        for version_dir in list_dirs(&self.spool_dir()).unwrap_or_default() {
+            let version = match version_dir.file_name().and_then(|name| name.to_str()) {
+                Some(version) => version.to_string(),
+                None => continue,
+            };
+            match ReplicaVersion::try_from(version.as_str()) {
+                Ok(replica_version)
+                    if self.binary_file("ic-replay", &replica_version).exists()
+                        && self.ic_config_file_local(&replica_version).exists() => {}
+                _ => continue,
+            }
+            match cup_heights(&version_dir) {
+                Ok(cups) => cups_by_version.push((version, cups)),
+                Err(err) => warn!(self.log, "Skipping {} in restore drills: {}", version, err),
+            }
+        }
+        drill_candidates(&archived, cups_by_version)
+    }
+
+    /// Restores the archived checkpoint at `checkpoint_height` into the drill
+    /// directory and replays it up to `cup_height`. Returns the state hash
+    /// certified by the catch-up package and the one of the replayed state.
+    fn run_drill(
+        &self,
+        version: &str,
+        checkpoint_height: u64,
+        cup_height: u64,
+    ) -> Result<(Vec<u8>, Vec<u8>), String> {
+        let data_dir = self.drill_data_dir();
+        if data_dir.exists() {
+            std::fs::remove_dir_all(&data_dir)
+                .map_err(|e| format!("Failed to clean up {}: {}", data_dir.display(), e))?;
+        }
+        let state_dir = data_dir.join("ic_state");
+        self.restore_archived_checkpoint(
+            checkpoint_height,
+            &checkpoint_dir(&state_dir, checkpoint_height),
+        )?;
+
+        let replica_version = ReplicaVersion::try_from(version).map_err(|e| e.to_string())?;
+        let mut cmd = Command::new(self.binary_file("ic-replay", &replica_version));
+        cmd.arg("--data-root")
+            .arg(&data_dir)
+            .arg("--subnet-id")
+            .arg(&self.subnet_id.to_string())
+            .arg("--replay-until-height")
+            .arg(cup_height.to_string())
+            .arg(&self.ic_config_file_local(&replica_version))
+            .arg("restore-from-backup2")
+            .arg(&self.local_store_dir())
+            .arg(&self.spool_root_dir())
+            .arg(version)
+            .arg(checkpoint_height.to_string());
+        info!(self.log, "Will execute: {:?}", cmd);
+        // The replay asks for a confirmation before it stops at a given height.
+        let mut confirm = Command::new("echo");
+        confirm.arg("y");
+        let stdout = pipe_all(&mut [confirm, cmd]).map_err(|e| e.to_string())?;
+        if !self.logs_dir().exists() {
+            std::fs::create_dir_all(self.logs_dir()).expect("Failure creating a directory");
+        }
+        let log_file_name = format!(
+            "drill_{}_{}_{}.log",
+            self.subnet_id, checkpoint_height, cup_height
+        );
+        std::fs::write(
+            self.logs_dir().join(log_file_name),
+            stdout.unwrap_or_default(),
+        )
+        .map_err(|err| format!("Error writing log file: {:?}", err))?;
+
+        let expected = cup_state_hash(
+            &self.spool_dir().join(version),
+            &self.content_store(),
+            cup_height,
+        )?;
+        let replayed_dir = checkpoint_dir(&state_dir, cup_height);
+        if !replayed_dir.exists() {
+            return Err(format!(
+                "The replay didn't create a checkpoint at height {}",
+                cup_height
+            ));
+        }
+        let replayed = checkpoint_state_hash(&replayed_dir)?;
+        Ok((expected, replayed))
+    }
+
+    /// Returns true if the archived state at `height` contains the checkpoint
+    /// at that height, either as a full copy or in the content store.
+    fn has_archived_checkpoint(&self, height: u64) -> bool {
+        let archive_dir = self.archive_dir(height);
+        archive_dir.join(CHECKPOINT_MANIFEST_FILE).exists()
+            || checkpoint_dir(&archive_dir.join("ic_state"), height).exists()
+    }
+
+    /// Copies the archived checkpoint at `height` to `dst`.
+    fn restore_archived_checkpoint(&self, height: u64, dst: &Path) -> Result<(), String> {
+        let archive_dir = self.archive_dir(height);
+        let manifest_path = archive_dir.join(CHECKPOINT_MANIFEST_FILE);
+        if manifest_path.exists() {
+            let manifest = load_checkpoint_manifest(&manifest_path)?;
+            return restore_checkpoint(&self.content_store(), &manifest, dst);
+        }
+        std::fs::create_dir_all(dst)
+            .map_err(|e| format!("Failure creating {}: {}", dst.display(), e))?;
+        let mut cmd = Command::new("rsync");
+        cmd.arg("-a")
+            .arg(checkpoint_dir(&archive_dir.join("ic_state"), height).join("."))
+            .arg(dst);
+        info!(self.log, "Will execute: {:?}", cmd);
+        exec_cmd(&mut cmd).map(|_| ()).map_err(|e| e.to_string())
+    }
+
+    fn write_drill_report(&self, report: &DrillReport) -> Result<(), String> {
+        std::fs::create_dir_all(self.drill_reports_dir())
+            .map_err(|e| format!("Failure creating a directory: {}", e))?;
+        let json = serde_json::to_string_pretty(report)
+            .map_err(|e| format!("Error serializing the report: {}", e))?;
+        let path = self
+            .drill_reports_dir()
+            .join(format!("{}_{}.json", self.subnet_id, report.cup_height));
+        std::fs::write(&path, json).map_err(|e| format!("Error writing {}: {}", path.display(), e))
+    }
+
     fn get_disk_stats(&self, typ: DiskStats) -> Result<u32, String> {
         let mut cmd = Command::new("df");
         cmd.arg(match typ {
@@ -512,6 +781,35 @@ impl BackupHelper {
     }
 
     fn archive_state(&self, last_height: u64) -> Result<(), String> {
+        let archived = match self.storage_backend {
+            StorageBackend::Rsync => self.rsync_state(last_height),
+            StorageBackend::ContentStore => self.store_state(last_height),
+        };
+        if let Err(e) = archived {
+            error!(self.log, "Error: {}", e);
+            self.notification_client
+                .report_failure_slack("Couldn't backup the recovered state!".to_string());
+            return Err(e);
+        }
+        info!(self.log, "State archived!");
+        self.apply_retention(last_height);
+
+        match (
+            self.get_disk_stats(DiskStats::Space),
+            self.get_disk_stats(DiskStats::Inodes),
+        ) {
+            (Ok(space), Ok(inodes)) => {
+                info!(self.log, "Space: {}% Inodes: {}%", space, inodes);
+                self.notification_client
+                    .push_metrics_disk_stats(space, inodes);
+                Ok(())
+            }
+            (Err(err), Ok(_)) => Err(err),
+            (_, Err(err)) => Err(err),
+        }
+    }
+
+    fn rsync_state(&self, last_height: u64) -> Result<(), String> {
         let state_dir = self.data_dir().join(".");
         let archive_dir = self.archive_dir(last_height);
         info!(
@@ -533,27 +831,410 @@ impl BackupHelper {
         }
         cmd.arg(state_dir).arg(&archive_dir);
         info!(self.log, "Will execute: {:?}", cmd);
-        if let Err(e) = exec_cmd(&mut cmd) {
-            error!(self.log, "Error: {}", e);
-            self.notification_client
-                .report_failure_slack("Couldn't backup the recovered state!".to_string());
-            Err(e.to_string())
-        } else {
-            info!(self.log, "State archived!");
+        exec_cmd(&mut cmd).map(|_| ()).map_err(|e| e.to_string())
+    }
 
-            match (
-                self.get_disk_stats(DiskStats::Space),
-                self.get_disk_stats(DiskStats::Inodes),
-            ) {
-                (Ok(space), Ok(inodes)) => {
-                    info!(self.log, "Space: {}% Inodes: {}%", space, inodes);
-                    self.notification_client
-                        .push_metrics_disk_stats(space, inodes);
-                    Ok(())
+    /// Stores the checkpoint at `last_height` and all spool groups below it in
+    /// the content store. Only the manifest of the checkpoint is written to the
+    /// archive directory.
+    fn store_state(&self, last_height: u64) -> Result<(), String> {
+        let store = self.content_store();
+        let checkpoint_dir = checkpoint_dir(&self.state_dir(), last_height);
+        let archive_dir = self.archive_dir(last_height);
+        info!(
+            self.log,
+            "Archiving: {} to the content store {}",
+            checkpoint_dir.to_string_lossy(),
+            store.root().to_string_lossy()
+        );
+        std::fs::create_dir_all(&archive_dir)
+            .map_err(|e| format!("Failure creating archive directory: {}", e))?;
+        let manifest = archive_checkpoint(
+            &store,
+            &checkpoint_dir,
+            &archive_dir.join(CHECKPOINT_MANIFEST_FILE),
+        )?;
+        info!(
+            self.log,
+            "Stored {} chunks of the checkpoint at height {}",
+            manifest.chunk_table.len(),
+            last_height
+        );
+
+        // Groups that may still receive artifacts stay in the spool.
+        for (version, group) in self.spool_groups() {
+            if group + BACKUP_GROUP_SIZE > last_height {
+                continue;
+            }
+            let group_dir = self.spool_dir().join(&version).join(group.to_string());
+            let heights = archive_artifact_group(&store, &group_dir)
+                .map_err(|e| format!("Failed to archive {}: {}", group_dir.display(), e))?;
+            if heights > 0 {
+                info!(
+                    self.log,
+                    "Archived {} heights of {}",
+                    heights,
+                    group_dir.display()
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes the archived states and artifacts that are older than the
+    /// retention policy of the subnet allows and, with the content store,
+    /// all objects that are not referenced anymore.
+    fn apply_retention(&self, last_height: u64) {
+        if let Some(keep) = self.retention.archived_states {
+            let mut heights = self.archived_heights();
+            let expired = heights.len().saturating_sub(keep);
+            for height in heights.drain(..expired) {
+                info!(self.log, "Removing archived state at height {}", height);
+                if let Err(e) = std::fs::remove_dir_all(self.archive_dir(height)) {
+                    warn!(self.log, "Failed to remove archived state: {}", e);
+                }
+            }
+        }
+        if self.storage_backend != StorageBackend::ContentStore {
+            return;
+        }
+        if let Some(keep) = self.retention.artifact_heights {
+            let cutoff = last_height.saturating_sub(keep);
+            for (version, group) in self.archived_groups() {
+                if group + BACKUP_GROUP_SIZE > cutoff {
+                    continue;
+                }
+                // The index is emptied rather than removed, so that the
+                // group is still excluded from the sync.
+                let group_dir = self.spool_dir().join(&version).join(group.to_string());
+                if let Err(e) = std::fs::write(
+                    group_dir.join(ARTIFACT_INDEX_FILE),
+                    serde_json::to_vec(&ArtifactIndex::default())
+                        .expect("Failed to serialize an empty index"),
+                ) {
+                    warn!(
+                        self.log,
+                        "Failed to expire artifacts of {}: {}",
+                        group_dir.display(),
+                        e
+                    );
+                }
+            }
+        }
+        match self.live_objects() {
+            Ok(live) => match self.content_store().collect_garbage(&live) {
+                Ok(removed) => info!(self.log, "Removed {} unreferenced objects", removed),
+                Err(e) => warn!(self.log, "Failed to collect garbage: {}", e),
+            },
+            // Without the complete set of live objects nothing can be removed.
+            Err(e) => warn!(self.log, "Skipping garbage collection: {}", e),
+        }
+    }
+
+    /// Returns the keys of all objects referenced by archived states or
+    /// artifacts of any subnet sharing the content store.
+    fn live_objects(&self) -> Result<BTreeSet<ObjectKey>, String> {
+        let mut live = BTreeSet::new();
+        for subnet_dir in list_dirs(&self.spool_root_dir())? {
+            if subnet_dir.ends_with(CONTENT_STORE_DIR) {
+                continue;
+            }
+            for version_dir in list_dirs(&subnet_dir)? {
+                for group_dir in list_dirs(&version_dir)? {
+                    if let Some(index) = ArtifactIndex::load(&group_dir)
+                        .map_err(|e| format!("Failed to load {}: {}", group_dir.display(), e))?
+                    {
+                        live.extend(index.keys());
+                    }
+                }
+            }
+        }
+        if self.archive_root_dir().exists() {
+            for subnet_dir in list_dirs(&self.archive_root_dir())? {
+                for archive_dir in list_dirs(&subnet_dir)? {
+                    let manifest_path = archive_dir.join(CHECKPOINT_MANIFEST_FILE);
+                    if manifest_path.exists() {
+                        let manifest = load_checkpoint_manifest(&manifest_path)?;
+                        live.extend(checkpoint_keys(&manifest));
+                    }
                 }
-                (Err(err), Ok(_)) => Err(err),
-                (_, Err(err)) => Err(err),
             }
         }
+        Ok(live)
+    }
+
+    /// Returns the heights of the archived states of the subnet in ascending
+    /// order.
+    fn archived_heights(&self) -> Vec<u64> {
+        let mut heights = list_dirs(&self.archive_subnet_dir())
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|dir| dir_number(dir))
+            .collect::<Vec<_>>();
+        heights.sort_unstable();
+        heights
+    }
+
+    /// Returns the replica versions and keys of all groups in the spool of
+    /// the subnet.
+    fn spool_groups(&self) -> Vec<(String, u64)> {
+        let mut groups = Vec::new();
+        for version_dir in list_dirs(&self.spool_dir()).unwrap_or_default() {
+            let version = match version_dir.file_name().and_then(|name| name.to_str()) {
+                Some(version) => version.to_string(),
+                None => continue,
+            };
+            for group_dir in list_dirs(&version_dir).unwrap_or_default() {
+                if let Some(group) = dir_number(&group_dir) {
+                    groups.push((version.clone(), group));
+                }
+            }
+        }
+        groups
+    }
+
+    /// Returns the groups in the spool of the subnet that were moved to the
+    /// content store.
+    fn archived_groups(&self) -> Vec<(String, u64)> {
+        self.spool_groups()
+            .into_iter()
+            .filter(|(version, group)| {
+                self.spool_dir()
+                    .join(version)
+                    .join(group.to_string())
+                    .join(ARTIFACT_INDEX_FILE)
+                    .exists()
+            })
+            .collect()
+    }
+}
+
+impl DrillReport {
+    /// Creates the report of a drill from the state hash certified by the
+    /// catch-up package and the one of the replayed state, or from the error
+    /// that prevented the replay. The drill only passes if both hashes match.
+    fn new(
+        subnet_id: SubnetId,
+        replica_version: String,
+        checkpoint_height: u64,
+        cup_height: u64,
+        result: Result<(Vec<u8>, Vec<u8>), String>,
+    ) -> Self {
+        let mut report = DrillReport {
+            subnet_id: subnet_id.to_string(),
+            replica_version,
+            checkpoint_height,
+            cup_height,
+            passed: false,
+            cup_state_hash: None,
+            replayed_state_hash: None,
+            error: None,
+            duration_secs: 0,
+        };
+        match result {
+            Ok((expected, replayed)) => {
+                report.passed = expected == replayed;
+                if !report.passed {
+                    report.error = Some("The state hashes differ".to_string());
+                }
+                report.cup_state_hash = Some(hex::encode(expected));
+                report.replayed_state_hash = Some(hex::encode(replayed));
+            }
+            Err(err) => report.error = Some(err),
+        }
+        report
+    }
+}
+
+/// Pairs every archived checkpoint height that is also the height of a
+/// catch-up package of some replica version with the later catch-up packages
+/// of that version. Checkpoints without a later catch-up package in the same
+/// version cannot be verified and are skipped.
+fn drill_candidates(
+    archived: &BTreeSet<u64>,
+    cups_by_version: Vec<(String, BTreeSet<u64>)>,
+) -> Vec<DrillCandidate> {
+    let mut candidates = Vec::new();
+    for (version, cups) in cups_by_version {
+        for height in archived.iter().filter(|height| cups.contains(height)) {
+            let cup_heights = cups.range(height + 1..).copied().collect::<Vec<_>>();
+            if !cup_heights.is_empty() {
+                candidates.push(DrillCandidate {
+                    version: version.clone(),
+                    checkpoint_height: *height,
+                    cup_heights,
+                });
+            }
+        }
+    }
+    candidates
+}
+
+/// Returns the heights a drill can be aimed at: above the lowest checkpoint
+/// and up to the highest catch-up package of all candidates.
+fn drill_heights(candidates: &[DrillCandidate]) -> Option<RangeInclusive<u64>> {
+    let lowest = candidates.iter().map(|c| c.checkpoint_height).min()?;
+    let highest = candidates
+        .iter()
+        .filter_map(|c| c.cup_heights.last().copied())
+        .max()?;
+    Some(lowest + 1..=highest)
+}
+
+/// Selects the drill covering `height`: the highest checkpoint below `height`
+/// and its first catch-up package at or above `height`, or its last one if
+/// `height` lies beyond it.
+fn select_drill(candidates: Vec<DrillCandidate>, height: u64) -> Option<(String, u64, u64)> {
+    let candidate = candidates
+        .into_iter()
+        .filter(|c| c.checkpoint_height < height)
+        .max_by_key(|c| c.checkpoint_height)?;
+    let cup_height = candidate
+        .cup_heights
+        .iter()
+        .find(|cup_height| **cup_height >= height)
+        .or_else(|| candidate.cup_heights.last())
+        .copied()?;
+    Some((candidate.version, candidate.checkpoint_height, cup_height))
+}
+
+fn list_dirs(dir: &Path) -> Result<Vec<PathBuf>, String> {
+    let entries =
+        std::fs::read_dir(dir).map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?;
+    Ok(entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .collect())
+}
+
+fn checkpoint_dir(state_dir: &Path, height: u64) -> PathBuf {
+    state_dir.join(format!("checkpoints/{:016x}", height))
+}
+
+fn dir_number(dir: &Path) -> Option<u64> {
+    dir.file_name()?.to_str()?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_types::PrincipalId;
+
+    fn candidates() -> Vec<DrillCandidate> {
+        drill_candidates(
+            &BTreeSet::from([100, 200, 300]),
+            vec![
+                ("v1".to_string(), BTreeSet::from([100, 150, 200])),
+                ("v2".to_string(), BTreeSet::from([300, 350])),
+            ],
+        )
+    }
+
+    #[test]
+    fn no_drill_without_candidates() {
+        assert_eq!(drill_candidates(&BTreeSet::new(), vec![]), vec![]);
+        assert_eq!(drill_heights(&[]), None);
+        assert_eq!(select_drill(vec![], 100), None);
+    }
+
+    #[test]
+    fn checkpoint_with_cup_only_in_a_later_version_is_skipped() {
+        // The checkpoint at height 200 is the last CUP of `v1`, and the next
+        // CUP only exists in `v2`.
+        let candidates = drill_candidates(
+            &BTreeSet::from([200]),
+            vec![
+                ("v1".to_string(), BTreeSet::from([100, 200])),
+                ("v2".to_string(), BTreeSet::from([300])),
+            ],
+        );
+        assert_eq!(candidates, vec![]);
+    }
+
+    #[test]
+    fn drill_candidates_pair_checkpoints_with_later_cups() {
+        // The checkpoint at height 200 has no later CUP in `v1`.
+        assert_eq!(
+            candidates(),
+            vec![
+                DrillCandidate {
+                    version: "v1".to_string(),
+                    checkpoint_height: 100,
+                    cup_heights: vec![150, 200],
+                },
+                DrillCandidate {
+                    version: "v2".to_string(),
+                    checkpoint_height: 300,
+                    cup_heights: vec![350],
+                },
+            ]
+        );
+        assert_eq!(drill_heights(&candidates()), Some(101..=350));
+    }
+
+    #[test]
+    fn select_drill_at_the_range_boundaries() {
+        // The lowest height replays from the lowest checkpoint to its first CUP.
+        assert_eq!(
+            select_drill(candidates(), 101),
+            Some(("v1".to_string(), 100, 150))
+        );
+        // A height between two CUPs is verified against the next one.
+        assert_eq!(
+            select_drill(candidates(), 151),
+            Some(("v1".to_string(), 100, 200))
+        );
+        // A height above the last CUP of the highest checkpoint below it is
+        // verified against that last CUP.
+        assert_eq!(
+            select_drill(candidates(), 250),
+            Some(("v1".to_string(), 100, 200))
+        );
+        // The highest height replays from the highest checkpoint to the last CUP.
+        assert_eq!(
+            select_drill(candidates(), 350),
+            Some(("v2".to_string(), 300, 350))
+        );
+        // There is no checkpoint at or below the lowest one.
+        assert_eq!(select_drill(candidates(), 100), None);
+    }
+
+    #[test]
+    fn drill_report_fails_on_hash_mismatch() {
+        let subnet_id = SubnetId::from(PrincipalId::new_subnet_test_id(1));
+
+        let report = DrillReport::new(
+            subnet_id,
+            "v1".to_string(),
+            100,
+            200,
+            Ok((vec![1; 32], vec![2; 32])),
+        );
+        assert!(!report.passed);
+        assert_eq!(report.error.as_deref(), Some("The state hashes differ"));
+        assert_eq!(report.cup_state_hash, Some(hex::encode([1; 32])));
+        assert_eq!(report.replayed_state_hash, Some(hex::encode([2; 32])));
+
+        let report = DrillReport::new(
+            subnet_id,
+            "v1".to_string(),
+            100,
+            200,
+            Ok((vec![1; 32], vec![1; 32])),
+        );
+        assert!(report.passed);
+        assert_eq!(report.error, None);
+
+        let report = DrillReport::new(
+            subnet_id,
+            "v1".to_string(),
+            100,
+            200,
+            Err("replay failed".to_string()),
+        );
+        assert!(!report.passed);
+        assert_eq!(report.error.as_deref(), Some("replay failed"));
+        assert_eq!(report.replayed_state_hash, None);
     }
 }
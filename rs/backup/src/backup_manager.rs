@@ -30,6 +30,7 @@ struct SubnetBackup {
     pub nodes_syncing: usize,
     pub sync_period: Duration,
     pub replay_period: Duration,
+    pub drill_period: Duration,
     pub backup_helper: BackupHelper,
 }
 
@@ -53,6 +54,8 @@ struct SubnetState {
     pub sync_last_time: Instant,
     #[serde(with = "serde_millis")]
     pub replay_last_time: Instant,
+    #[serde(with = "serde_millis", default = "Instant::now")]
+    pub drill_last_time: Instant,
 }
 
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
@@ -162,10 +165,13 @@ impl BackupManager {
                 notification_client,
                 downloads: downloads.clone(),
                 disk_threshold_warn,
+                storage_backend: config.storage_backend,
+                retention: s.retention,
                 log: log.clone(),
             };
             let sync_period = std::time::Duration::from_secs(s.sync_period_secs);
             let replay_period = std::time::Duration::from_secs(s.replay_period_secs);
+            let drill_period = std::time::Duration::from_secs(s.drill_period_secs);
             let sync_last_time = fetch_value_or_default(
                 &manager_state,
                 &s.subnet_id,
@@ -178,10 +184,17 @@ impl BackupManager {
                 |sub| sub.replay_last_time,
                 Instant::now() - replay_period,
             );
+            let drill_last_time = fetch_value_or_default(
+                &manager_state,
+                &s.subnet_id,
+                |sub| sub.drill_last_time,
+                Instant::now(),
+            );
             backups.push(SubnetBackup {
                 nodes_syncing: s.nodes_syncing,
                 sync_period,
                 replay_period,
+                drill_period,
                 backup_helper,
             });
             save_state.subnet_states.insert(
@@ -190,6 +203,7 @@ impl BackupManager {
                     replica_version,
                     sync_last_time,
                     replay_last_time,
+                    drill_last_time,
                 },
             );
         }
@@ -337,6 +351,15 @@ fn replay_subnets(m: Arc<BackupManager>) {
                     m.set_value(subnet_id, |s, v| s.replay_last_time = v, Instant::now());
                 }
             }
+            // Drills run between the replays, as they share the content store.
+            if b.drill_period >= Duration::from_secs(1) {
+                let subnet_id = &b.backup_helper.subnet_id;
+                let drill_last_time = m.get_value(subnet_id, |s| s.drill_last_time);
+                if drill_last_time.elapsed() > b.drill_period {
+                    b.backup_helper.drill();
+                    m.set_value(subnet_id, |s, v| s.drill_last_time = v, Instant::now());
+                }
+            }
         }
         // Have a small break before the next check for replays
         sleep_secs(30);
@@ -15,6 +15,35 @@ pub struct SubnetConfig {
     pub nodes_syncing: usize,
     pub sync_period_secs: u64,
     pub replay_period_secs: u64,
+    /// How often a restore drill verifies the archived backups of the subnet.
+    /// Drills are disabled if unset.
+    #[serde(default)]
+    pub drill_period_secs: u64,
+    #[serde(default)]
+    pub retention: RetentionPolicy,
+}
+
+/// How long the archived data of a subnet is kept when it is archived in the
+/// content store. Everything is kept if a limit is unset.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetentionPolicy {
+    /// The number of most recent archived states to keep.
+    pub archived_states: Option<usize>,
+    /// The number of heights below the latest archived state for which the
+    /// consensus artifacts are kept.
+    pub artifact_heights: Option<u64>,
+}
+
+/// Where recovered states and synced artifacts are archived.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StorageBackend {
+    /// Full copies of the recovered states; the spool is kept as synced.
+    #[default]
+    Rsync,
+    /// Deduplicated and compressed checkpoint chunks and artifacts in the
+    /// content store of the spool.
+    ContentStore,
 }
 
 #[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
@@ -28,6 +57,8 @@ pub struct Config {
     pub excluded_dirs: Vec<String>,
     pub ssh_private_key: PathBuf,
     pub disk_threshold_warn: u32,
+    #[serde(default)]
+    pub storage_backend: StorageBackend,
     pub slack_token: String,
     pub subnets: Vec<SubnetConfig>,
 }
@@ -27,6 +27,7 @@ use tokio::task::spawn_blocking;
 //     ],
 //     "ssh_private_key": "/home/my_user/.ssh/id_ed25519_backup",
 //     "disk_threshold_warn": 75,
+//     "storage_backend": "content_store",
 //     "slack_token": "ABCD1234"
 //     "subnets": [
 //       {
@@ -34,7 +35,12 @@ use tokio::task::spawn_blocking;
 //         "initial_replica_version": "2f844c50765df0833c075b7340ac5f2dd9d5dc21",
 //         "nodes_syncing": 5,
 //         "sync_period_secs": 1800,
-//         "replay_period_secs": 7200
+//         "replay_period_secs": 7200,
+//         "drill_period_secs": 86400,
+//         "retention": {
+//           "archived_states": 10,
+//           "artifact_heights": 200000
+//         }
 //       },
 //       {
 //         "subnet_id": "qwzvq-hye2n-7o7ey-gllix-3bgyy-lfopp-q22hm-oaoez-yqtyi-qz64d-vqe",
@@ -97,6 +97,22 @@ impl NotificationClient {
         self.push_metrics(message)
     }
 
+    pub fn push_metrics_drill(&self, passed: bool, height: u64) {
+        let message = format!(
+            "# TYPE backup_drill_success gauge\n\
+            # HELP backup_drill_success Whether the last restore drill on a backup pod verified the state.\n\
+            backup_drill_success{{ic=\"mercury\", ic_subnet=\"{}\"}} {}\n\
+            # TYPE backup_drill_height gauge\n\
+            # HELP backup_drill_height The CUP height checked by the last restore drill on a backup pod.\n\
+            backup_drill_height{{ic=\"mercury\", ic_subnet=\"{}\"}} {}\n",
+            self.subnet,
+            u8::from(passed),
+            self.subnet,
+            height,
+        );
+        self.push_metrics(message)
+    }
+
     pub fn push_metrics_disk_stats(&self, space: u32, inodes: u32) {
         let message = format!(
             "# TYPE backup_disk_usage gauge\n\
@@ -163,6 +163,7 @@ mod tests {
         methods::{FuncRef, WasmMethod},
         time::Time,
         CanisterTimer, ComputeAllocation, Cycles, MemoryAllocation, NumBytes, NumInstructions,
+        ResourceSaturation,
     };
     use mockall::*;
     use std::collections::{BTreeMap, BTreeSet};
@@ -180,6 +181,8 @@ mod tests {
                 NumInstructions::new(INSTRUCTION_LIMIT),
             ),
             canister_memory_limit: NumBytes::new(4 << 30),
+            wasm_memory_limit: None,
+            subnet_memory_saturation: ResourceSaturation::default(),
             compute_allocation: ComputeAllocation::default(),
             subnet_type: SubnetType::Application,
             execution_mode: ExecutionMode::Replicated,
@@ -192,6 +195,7 @@ mod tests {
         SandboxSafeSystemState::new_internal(
             canister_test_id(0),
             user_test_id(0).get(),
+            BTreeSet::from([user_test_id(0).get()]),
             CanisterStatusView::Running,
             NumSeconds::from(3600),
             MemoryAllocation::BestEffort,
@@ -211,6 +215,9 @@ mod tests {
             SchedulerConfig::application_subnet().dirty_page_overhead,
             CanisterTimer::Inactive,
             0,
+            0,
+            Cycles::zero(),
+            None,
         )
     }
 
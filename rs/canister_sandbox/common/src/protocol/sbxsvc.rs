@@ -9,8 +9,8 @@ use ic_embedders::{CompilationResult, SerializedModule, SerializedModuleBytes};
 use ic_interfaces::execution_environment::HypervisorResult;
 use ic_replicated_state::{
     page_map::{
-        CheckpointSerialization, MappingSerialization, PageAllocatorSerialization,
-        PageMapSerialization,
+        CheckpointSerialization, MappingSerialization, OverlaySerialization,
+        PageAllocatorSerialization, PageMapSerialization,
     },
     Global, NumWasmPages,
 };
@@ -123,6 +123,19 @@ impl EnumerateInnerFileDescriptors for PageMapSerialization {
 // The trait is implemented here to avoid dependency of relicated-state on
 // canister-sandbox.
 impl EnumerateInnerFileDescriptors for CheckpointSerialization {
+    fn enumerate_fds<'a>(&'a mut self, fds: &mut Vec<&'a mut std::os::unix::io::RawFd>) {
+        if let Some(mapping) = self.mapping.as_mut() {
+            mapping.enumerate_fds(fds)
+        }
+        for overlay in self.overlays.iter_mut() {
+            overlay.enumerate_fds(fds)
+        }
+    }
+}
+
+// The trait is implemented here to avoid dependency of relicated-state on
+// canister-sandbox.
+impl EnumerateInnerFileDescriptors for OverlaySerialization {
     fn enumerate_fds<'a>(&'a mut self, fds: &mut Vec<&'a mut std::os::unix::io::RawFd>) {
         if let Some(mapping) = self.mapping.as_mut() {
             mapping.enumerate_fds(fds)
@@ -23,7 +23,7 @@ use ic_types::{
     crypto::CryptoHash,
     messages::{CallbackId, Payload, RejectContext, Request, RequestOrResponse, Response},
     xnet::StreamHeader,
-    CryptoHashOfPartialState, Cycles, Funds,
+    CoarseTime, CryptoHashOfPartialState, Cycles, Funds,
 };
 use serde_cbor::value::Value;
 use std::collections::{BTreeMap, VecDeque};
@@ -426,6 +426,174 @@ fn canonical_encoding_reject_response() {
     );
 }
 
+/// Canonical CBOR encoding (with certification versions 12 and up) of:
+///
+/// ```no_run
+/// RequestOrResponse::Request(
+///     Request {
+///         receiver: canister_test_id(1),
+///         sender: canister_test_id(2),
+///         sender_reply_callback: CallbackId::from(3),
+///         payment: Cycles::new(4),
+///         method_name: "test".to_string(),
+///         method_payload: vec![6],
+///         deadline: CoarseTime::from_secs_since_unix_epoch(1700000000),
+///     }
+/// )
+/// ```
+///
+/// Expected:
+///
+/// ```text
+/// A1                            # map(1)
+///    00                         # field_index(RequestOrResponse::request)
+///    A7                         # map(7)
+///       00                      # field_index(Request::receiver)
+///       4A                      # bytes(10)
+///          00000000000000010101 # "\x00\x00\x00\x00\x00\x00\x00\x01\x01\x01"
+///       01                      # field_index(Request::sender)
+///       4A                      # bytes(10)
+///          00000000000000020101 # "\x00\x00\x00\x00\x00\x00\x00\x02\x01\x01"
+///       02                      # field_index(Request::sender_reply_callback)
+///       03                      # unsigned(3)
+///       03                      # field_index(Request::payment)
+///       A1                      # map(1)
+///          00                   # field_index(Funds::cycles)
+///          A1                   # map(1)
+///             00                # field_index(Cycles::raw)
+///             04                # unsigned(4)
+///       04                      # field_index(Request::method_name)
+///       64                      # text(4)
+///          74657374             # "test"
+///       05                      # field_index(Request::method_payload)
+///       41                      # bytes(1)
+///          06                   # "\x06"
+///       07                      # field_index(Request::deadline)
+///       1A 6553F100             # unsigned(1700000000)
+/// ```
+#[test]
+fn canonical_encoding_best_effort_request_v12_plus() {
+    for certification_version in
+        all_supported_versions().filter(|v| v >= &CertificationVersion::V12)
+    {
+        let request: RequestOrResponse = RequestBuilder::new()
+            .receiver(canister_test_id(1))
+            .sender(canister_test_id(2))
+            .sender_reply_callback(CallbackId::from(3))
+            .payment(Cycles::new(4))
+            .method_name("test".to_string())
+            .method_payload(vec![6])
+            .deadline(CoarseTime::from_secs_since_unix_epoch(1700000000))
+            .build()
+            .into();
+
+        assert_eq!(
+            "A1 00 A7 00 4A 00 00 00 00 00 00 00 01 01 01 01 4A 00 00 00 00 00 00 00 02 01 01 02 03 03 A1 00 A1 00 04 04 64 74 65 73 74 05 41 06 07 1A 65 53 F1 00",
+            as_hex(&encode_message(&request, certification_version))
+        );
+    }
+}
+
+/// Canonical CBOR encoding (with certification versions 12 and up) of:
+///
+/// ```no_run
+/// RequestOrResponse::Response(
+///     Response {
+///         originator: canister_test_id(6),
+///         respondent: canister_test_id(5),
+///         originator_reply_callback: CallbackId::from(4),
+///         refund: Cycles::new(3),
+///         response_payload: Payload::Reject(RejectContext {
+///             code: RejectCode::SysUnknown,
+///             message: "Oops".into(),
+///         }),
+///         deadline: CoarseTime::from_secs_since_unix_epoch(1700000000),
+///     }
+/// )
+/// ```
+///
+/// Expected:
+///
+/// ```text
+/// A1                            # map(1)
+///    01                         # field_index(RequestOrResponse::response)
+///    A6                         # map(6)
+///       00                      # field_index(Response::originator)
+///       4A                      # bytes(10)
+///          00000000000000060101 # "\x00\x00\x00\x00\x00\x00\x00\x06\x01\x01"
+///       01                      # field_index(Response::respondent)
+///       4A                      # bytes(10)
+///          00000000000000050101 # "\x00\x00\x00\x00\x00\x00\x00\x05\x01\x01"
+///       02                      # field_index(Response::originator_reply_callback)
+///       04                      # unsigned(4)
+///       03                      # field_index(Response::refund)
+///       A1                      # map(1)
+///          00                   # field_index(Funds::cycles)
+///          A1                   # map(1)
+///             00                # field_index(Cycles::raw)
+///             03                # unsigned(3)
+///       04                      # field_index(Response::response_payload)
+///       A1                      # map(1)
+///          01                   # field_index(Payload::reject)
+///          A2                   # map(2)
+///             00                # field_index(RejectContext::code)
+///             06                # unsigned(6)
+///             01                # field_index(RejectContext::message)
+///             64                # text(4)
+///                4F6F7073       # "Oops"
+///       06                      # field_index(Response::deadline)
+///       1A 6553F100             # unsigned(1700000000)
+/// ```
+#[test]
+fn canonical_encoding_best_effort_reject_response_v12_plus() {
+    for certification_version in
+        all_supported_versions().filter(|v| v >= &CertificationVersion::V12)
+    {
+        let reject_response: RequestOrResponse = ResponseBuilder::new()
+            .originator(canister_test_id(6))
+            .respondent(canister_test_id(5))
+            .originator_reply_callback(CallbackId::from(4))
+            .refund(Cycles::new(3))
+            .response_payload(Payload::Reject(RejectContext {
+                code: RejectCode::SysUnknown,
+                message: "Oops".into(),
+            }))
+            .deadline(CoarseTime::from_secs_since_unix_epoch(1700000000))
+            .build()
+            .into();
+
+        assert_eq!(
+            "A1 01 A6 00 4A 00 00 00 00 00 00 00 06 01 01 01 4A 00 00 00 00 00 00 00 05 01 01 02 04 03 A1 00 A1 00 03 04 A1 01 A2 00 06 01 64 4F 6F 70 73 06 1A 65 53 F1 00",
+            as_hex(&encode_message(&reject_response, certification_version))
+        );
+    }
+}
+
+#[test]
+#[should_panic(expected = "should not be producing requests with deadlines")]
+fn encoding_best_effort_request_before_v12_panics() {
+    let request: RequestOrResponse = RequestBuilder::new()
+        .deadline(CoarseTime::from_secs_since_unix_epoch(1700000000))
+        .build()
+        .into();
+
+    encode_message(&request, CertificationVersion::V11);
+}
+
+#[test]
+#[should_panic(expected = "should not be producing `SYS_UNKNOWN` rejects")]
+fn encoding_sys_unknown_reject_before_v12_panics() {
+    let reject_response: RequestOrResponse = ResponseBuilder::new()
+        .response_payload(Payload::Reject(RejectContext {
+            code: RejectCode::SysUnknown,
+            message: "Oops".into(),
+        }))
+        .build()
+        .into();
+
+    encode_message(&reject_response, CertificationVersion::V11);
+}
+
 /// Canonical CBOR encoding of:
 ///
 /// ```no_run
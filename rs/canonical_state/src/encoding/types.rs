@@ -11,9 +11,9 @@
 //! `CanisterIds` are represented as byte vectors.
 
 use crate::CertificationVersion;
-use ic_error_types::TryFromError;
+use ic_error_types::{RejectCode, TryFromError};
 use ic_protobuf::proxy::ProxyDecodeError;
-use ic_types::xnet::StreamIndex;
+use ic_types::{messages::NO_DEADLINE, xnet::StreamIndex, CoarseTime};
 use serde::{Deserialize, Serialize};
 use std::{
     collections::VecDeque,
@@ -65,6 +65,10 @@ pub struct Request {
     pub method_payload: Bytes,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub cycles_payment: Option<Cycles>,
+    /// Deadline in seconds since UNIX epoch; only encoded for best-effort
+    /// requests.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub deadline: Option<u32>,
 }
 
 /// Canonical representation of `ic_types::messages::Response`.
@@ -80,6 +84,10 @@ pub struct Response {
     pub response_payload: Payload,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub cycles_refund: Option<Cycles>,
+    /// Deadline in seconds since UNIX epoch; only encoded for responses to
+    /// best-effort requests.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub deadline: Option<u32>,
 }
 
 /// Canonical representation of `ic_types::funds::Cycles`.
@@ -235,6 +243,13 @@ impl From<(&ic_types::messages::Request, CertificationVersion)> for Request {
     fn from(
         (request, certification_version): (&ic_types::messages::Request, CertificationVersion),
     ) -> Self {
+        // Replicas with certification version < 12 do not produce best-effort
+        // messages.
+        assert!(
+            request.deadline == NO_DEADLINE || certification_version >= CertificationVersion::V12,
+            "Replicas with certification version < 12 should not be producing requests with deadlines"
+        );
+
         let funds = Funds {
             cycles: (&request.payment, certification_version).into(),
             icp: 0,
@@ -247,6 +262,7 @@ impl From<(&ic_types::messages::Request, CertificationVersion)> for Request {
             method_name: request.method_name.clone(),
             method_payload: request.method_payload.clone(),
             cycles_payment: None,
+            deadline: encode_deadline(request.deadline),
         }
     }
 }
@@ -268,6 +284,7 @@ impl TryFrom<Request> for ic_types::messages::Request {
             payment,
             method_name: request.method_name,
             method_payload: request.method_payload,
+            deadline: decode_deadline(request.deadline),
         })
     }
 }
@@ -276,6 +293,13 @@ impl From<(&ic_types::messages::Response, CertificationVersion)> for Response {
     fn from(
         (response, certification_version): (&ic_types::messages::Response, CertificationVersion),
     ) -> Self {
+        // Replicas with certification version < 12 do not produce best-effort
+        // messages.
+        assert!(
+            response.deadline == NO_DEADLINE || certification_version >= CertificationVersion::V12,
+            "Replicas with certification version < 12 should not be producing responses with deadlines"
+        );
+
         let funds = Funds {
             cycles: (&response.refund, certification_version).into(),
             icp: 0,
@@ -287,6 +311,7 @@ impl From<(&ic_types::messages::Response, CertificationVersion)> for Response {
             refund: funds,
             response_payload: (&response.response_payload, certification_version).into(),
             cycles_refund: None,
+            deadline: encode_deadline(response.deadline),
         }
     }
 }
@@ -307,10 +332,27 @@ impl TryFrom<Response> for ic_types::messages::Response {
             originator_reply_callback: response.originator_reply_callback.into(),
             refund,
             response_payload: response.response_payload.try_into()?,
+            deadline: decode_deadline(response.deadline),
         })
     }
 }
 
+/// Encodes a message deadline, omitting `NO_DEADLINE`, so that the encoding of
+/// guaranteed response messages is unchanged.
+fn encode_deadline(deadline: CoarseTime) -> Option<u32> {
+    if deadline == NO_DEADLINE {
+        None
+    } else {
+        Some(deadline.as_secs_since_unix_epoch())
+    }
+}
+
+fn decode_deadline(deadline: Option<u32>) -> CoarseTime {
+    deadline
+        .map(CoarseTime::from_secs_since_unix_epoch)
+        .unwrap_or(NO_DEADLINE)
+}
+
 impl From<(&ic_types::funds::Cycles, CertificationVersion)> for Cycles {
     fn from(
         (cycles, _certification_version): (&ic_types::funds::Cycles, CertificationVersion),
@@ -398,11 +440,19 @@ impl TryFrom<Payload> for ic_types::messages::Payload {
 
 impl From<(&ic_types::messages::RejectContext, CertificationVersion)> for RejectContext {
     fn from(
-        (context, _certification_version): (
+        (context, certification_version): (
             &ic_types::messages::RejectContext,
             CertificationVersion,
         ),
     ) -> Self {
+        // `SYS_UNKNOWN` rejects are only produced for best-effort messages, i.e.
+        // starting with certification version 12.
+        assert!(
+            context.code != RejectCode::SysUnknown
+                || certification_version >= CertificationVersion::V12,
+            "Replicas with certification version < 12 should not be producing `SYS_UNKNOWN` rejects"
+        );
+
         Self {
             code: context.code as u8,
             message: context.message.clone(),
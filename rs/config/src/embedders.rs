@@ -45,6 +45,9 @@ pub struct FeatureFlags {
     pub new_wasm_transform_lib: FlagStatus,
     /// Track dirty pages with a write barrier instead of the signal handler.
     pub write_barrier: FlagStatus,
+    /// Accept modules with a 64-bit Wasm memory (memory64). Requires
+    /// `new_wasm_transform_lib`.
+    pub wasm64: FlagStatus,
 }
 
 impl Default for FeatureFlags {
@@ -53,6 +56,7 @@ impl Default for FeatureFlags {
             rate_limiting_of_debug_prints: FlagStatus::Enabled,
             new_wasm_transform_lib: FlagStatus::Enabled,
             write_barrier: FlagStatus::Disabled,
+            wasm64: FlagStatus::Disabled,
         }
     }
 }
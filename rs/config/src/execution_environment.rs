@@ -24,6 +24,11 @@ const GB: u64 = 1024 * 1024 * 1024;
 /// canister's data and the deltas.
 const SUBNET_MEMORY_CAPACITY: NumBytes = NumBytes::new(450 * GB);
 
+/// Once the memory usage of the subnet exceeds this threshold, new memory
+/// allocations reserve cycles for future storage payments. See
+/// `CyclesAccountManager::storage_reservation_cycles()`.
+const SUBNET_MEMORY_THRESHOLD: NumBytes = NumBytes::new(300 * GB);
+
 /// This is the upper limit on how much memory can be used by all canister
 /// messages on a given subnet.
 ///
@@ -87,6 +92,10 @@ pub struct Config {
     /// the subnet.
     pub subnet_memory_capacity: NumBytes,
 
+    /// The subnet memory usage above which the subnet is considered to be
+    /// under storage pressure and new memory allocations reserve cycles.
+    pub subnet_memory_threshold: NumBytes,
+
     /// The maximum amount of logical storage available to canister messages
     /// across the whole subnet.
     pub subnet_message_memory_capacity: NumBytes,
@@ -173,6 +182,7 @@ impl Default for Config {
             create_funds_whitelist: String::default(),
             max_instructions_for_message_acceptance_calls: MAX_INSTRUCTIONS_PER_MESSAGE_WITHOUT_DTS,
             subnet_memory_capacity: SUBNET_MEMORY_CAPACITY,
+            subnet_memory_threshold: SUBNET_MEMORY_THRESHOLD,
             subnet_message_memory_capacity: SUBNET_MESSAGE_MEMORY_CAPACITY,
             ingress_history_memory_capacity: INGRESS_HISTORY_MEMORY_CAPACITY,
             max_canister_memory_size: NumBytes::new(
@@ -4,14 +4,60 @@ use std::path::PathBuf;
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Config {
     state_root: PathBuf,
+    /// Retention of the files and directories in the state root that are not
+    /// part of any state, which are garbage collected in the background.
+    #[serde(default)]
+    state_layout_gc: StateLayoutGcConfig,
+}
+
+/// Configuration of the garbage collection of the state layout.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StateLayoutGcConfig {
+    /// Minimum time between two garbage collections, in seconds.
+    pub interval_secs: u64,
+    /// Leftovers in `fs_tmp` and abandoned state syncs in `tmp` are removed
+    /// once they are older than this many seconds.
+    pub tmp_max_age_secs: u64,
+    /// The number of diverged checkpoints and of backups to keep.
+    pub max_archived_checkpoints: usize,
+    /// The number of diverged state markers to keep.
+    pub max_diverged_state_markers: usize,
+    /// Diverged checkpoints, backups and diverged state markers are removed
+    /// once they are older than this many seconds.
+    pub archived_checkpoint_max_age_secs: u64,
+}
+
+impl Default for StateLayoutGcConfig {
+    fn default() -> Self {
+        Self {
+            interval_secs: 10 * 60,
+            tmp_max_age_secs: 60 * 60,
+            max_archived_checkpoints: 2,
+            max_diverged_state_markers: 100,
+            archived_checkpoint_max_age_secs: 30 * 24 * 60 * 60, // 30 days
+        }
+    }
 }
 
 impl Config {
     pub fn new(state_root: PathBuf) -> Self {
-        Self { state_root }
+        Self {
+            state_root,
+            state_layout_gc: StateLayoutGcConfig::default(),
+        }
+    }
+
+    pub fn with_state_layout_gc(mut self, state_layout_gc: StateLayoutGcConfig) -> Self {
+        self.state_layout_gc = state_layout_gc;
+        self
     }
 
     pub fn state_root(&self) -> PathBuf {
         self.state_root.clone()
     }
+
+    pub fn state_layout_gc(&self) -> &StateLayoutGcConfig {
+        &self.state_layout_gc
+    }
 }
@@ -125,6 +125,10 @@ pub const ECDSA_SIGNATURE_FEE: Cycles = Cycles::new(10 * B as u128);
 /// IMPORTANT: never set this value to zero.
 const DEFAULT_REFERENCE_SUBNET_SIZE: usize = 13;
 
+/// The duration for which storage is paid in advance when memory is allocated
+/// on a subnet whose memory usage has reached its capacity.
+const SUBNET_MEMORY_RESERVATION: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
 /// Costs for each newly created dirty page in stable memory.
 const DEFAULT_DIRTY_PAGE_OVERHEAD: NumInstructions = NumInstructions::new(1_000);
 const SYSTEM_SUBNET_DIRTY_PAGE_OVERHEAD: NumInstructions = NumInstructions::new(0);
@@ -377,6 +381,13 @@ pub struct CyclesAccountManagerConfig {
 
     /// Fee per byte for networking and consensus work done for a http request or response.
     pub http_request_per_byte_fee: Cycles,
+
+    /// The duration for which the storage of newly allocated memory is paid
+    /// in advance, by moving cycles to the reserved balance of the canister,
+    /// when the subnet memory usage reaches the subnet capacity. The
+    /// reservation scales linearly from zero at the storage pressure threshold
+    /// to this duration at full capacity.
+    pub subnet_memory_reservation: Duration,
 }
 
 impl CyclesAccountManagerConfig {
@@ -405,6 +416,7 @@ impl CyclesAccountManagerConfig {
             ecdsa_signature_fee: ECDSA_SIGNATURE_FEE,
             http_request_baseline_fee: Cycles::new(400_000_000),
             http_request_per_byte_fee: Cycles::new(100_000),
+            subnet_memory_reservation: SUBNET_MEMORY_RESERVATION,
         }
     }
 
@@ -433,6 +445,7 @@ impl CyclesAccountManagerConfig {
             ecdsa_signature_fee: ECDSA_SIGNATURE_FEE,
             http_request_baseline_fee: Cycles::new(0),
             http_request_per_byte_fee: Cycles::new(0),
+            subnet_memory_reservation: SUBNET_MEMORY_RESERVATION,
         }
     }
 }
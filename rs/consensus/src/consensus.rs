@@ -29,7 +29,7 @@ mod proptests;
 pub use block_maker::SubnetRecords;
 pub use crypto::ConsensusCrypto;
 pub use membership::Membership;
-pub use metrics::ValidatorMetrics;
+pub use metrics::{BatchStats, BlockStats, ValidatorMetrics};
 
 #[cfg(test)]
 pub(crate) mod mocks;
@@ -61,6 +61,7 @@ use ic_interfaces::{
     ecdsa::EcdsaPool,
     ingress_manager::IngressSelector,
     messaging::{MessageRouting, XNetPayloadBuilder},
+    query_stats::QueryStatsPayloadBuilder,
     self_validating_payload::SelfValidatingPayloadBuilder,
     time_source::TimeSource,
 };
@@ -141,6 +142,7 @@ impl ConsensusImpl {
         xnet_payload_builder: Arc<dyn XNetPayloadBuilder>,
         self_validating_payload_builder: Arc<dyn SelfValidatingPayloadBuilder>,
         canister_http_payload_builder: Arc<dyn CanisterHttpPayloadBuilder>,
+        query_stats_payload_builder: Arc<dyn QueryStatsPayloadBuilder>,
         dkg_pool: Arc<RwLock<dyn DkgPool>>,
         ecdsa_pool: Arc<RwLock<dyn EcdsaPool>>,
         dkg_key_manager: Arc<Mutex<DkgKeyManager>>,
@@ -160,6 +162,7 @@ impl ConsensusImpl {
             xnet_payload_builder,
             self_validating_payload_builder,
             canister_http_payload_builder,
+            query_stats_payload_builder,
             metrics_registry.clone(),
             logger.clone(),
         ));
@@ -626,6 +629,7 @@ pub fn setup(
     xnet_payload_builder: Arc<dyn XNetPayloadBuilder>,
     self_validating_payload_builder: Arc<dyn SelfValidatingPayloadBuilder>,
     canister_http_payload_builder: Arc<dyn CanisterHttpPayloadBuilder>,
+    query_stats_payload_builder: Arc<dyn QueryStatsPayloadBuilder>,
     dkg_pool: Arc<RwLock<dyn DkgPool>>,
     ecdsa_pool: Arc<RwLock<dyn EcdsaPool>>,
     dkg_key_manager: Arc<Mutex<DkgKeyManager>>,
@@ -660,6 +664,7 @@ pub fn setup(
             xnet_payload_builder,
             self_validating_payload_builder,
             canister_http_payload_builder,
+            query_stats_payload_builder,
             dkg_pool,
             ecdsa_pool,
             dkg_key_manager,
@@ -689,6 +694,7 @@ mod tests {
         canister_http::FakeCanisterHttpPayloadBuilder,
         ingress_selector::FakeIngressSelector,
         message_routing::FakeMessageRouting,
+        query_stats_payload_builder::FakeQueryStatsPayloadBuilder,
         self_validating_payload_builder::FakeSelfValidatingPayloadBuilder,
         types::ids::{node_test_id, subnet_test_id},
         xnet_payload_builder::FakeXNetPayloadBuilder,
@@ -752,6 +758,7 @@ mod tests {
             Arc::new(FakeXNetPayloadBuilder::new()),
             Arc::new(FakeSelfValidatingPayloadBuilder::new()),
             Arc::new(FakeCanisterHttpPayloadBuilder::new()),
+            Arc::new(FakeQueryStatsPayloadBuilder::new()),
             dkg_pool,
             ecdsa_pool,
             Arc::new(Mutex::new(DkgKeyManager::new(
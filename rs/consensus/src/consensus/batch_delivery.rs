@@ -22,7 +22,7 @@ use ic_types::{
     canister_http::*,
     consensus::ecdsa::{CompletedSignature, EcdsaBlockReader},
     crypto::threshold_sig::ni_dkg::{NiDkgId, NiDkgTag, NiDkgTranscript},
-    messages::{CallbackId, Response},
+    messages::{CallbackId, Response, NO_DEADLINE},
     ReplicaVersion,
 };
 use std::collections::BTreeMap;
@@ -238,6 +238,7 @@ pub fn generate_execution_responses_for_canister_http_responses(
                 originator: CanisterId::ic_00(),
                 respondent: CanisterId::ic_00(),
                 originator_reply_callback: content.id,
+                deadline: NO_DEADLINE,
                 refund: Cycles::zero(),
                 response_payload: match &content.content {
                     CanisterHttpResponseContent::Success(data) => {
@@ -258,6 +259,7 @@ pub fn generate_execution_responses_for_canister_http_responses(
                     originator: CanisterId::ic_00(),
                     respondent: CanisterId::ic_00(),
                     originator_reply_callback: *canister_http_timeout,
+                    deadline: NO_DEADLINE,
                     refund: Cycles::zero(),
                     response_payload: ic_types::messages::Payload::Reject(
                         ic_types::messages::RejectContext {
@@ -276,6 +278,7 @@ pub fn generate_execution_responses_for_canister_http_responses(
                         originator: CanisterId::ic_00(),
                         respondent: CanisterId::ic_00(),
                         originator_reply_callback: divergence_response.shares.get(0)?.content.id,
+                        deadline: NO_DEADLINE,
                         refund: Cycles::zero(),
                         response_payload: ic_types::messages::Payload::Reject(
                             ic_types::messages::RejectContext {
@@ -352,6 +355,7 @@ pub fn generate_responses_to_setup_initial_dkg_calls(
                 originator: CanisterId::ic_00(),
                 respondent: CanisterId::ic_00(),
                 originator_reply_callback: callback_id,
+                deadline: NO_DEADLINE,
                 refund: Cycles::zero(),
                 response_payload,
             });
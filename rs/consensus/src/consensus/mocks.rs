@@ -21,7 +21,7 @@ use ic_types::{
     batch::{BatchPayload, ValidationContext},
     consensus::Payload,
     replica_config::ReplicaConfig,
-    Height, RegistryVersion, SubnetId, Time,
+    Height, NodeId, RegistryVersion, SubnetId, Time,
 };
 use mockall::predicate::*;
 use mockall::*;
@@ -44,6 +44,7 @@ mock! {
         fn validate_payload(
             &self,
             height: Height,
+            proposer: NodeId,
             payload: &Payload,
             past_payloads: &[(Height, Time, Payload)],
             context: &ValidationContext,
@@ -4,7 +4,7 @@ use crate::consensus::metrics::{
 use ic_interfaces::{
     canister_http::CanisterHttpPayloadBuilder, consensus::PayloadValidationError,
     ingress_manager::IngressSelector, messaging::XNetPayloadBuilder,
-    self_validating_payload::SelfValidatingPayloadBuilder,
+    query_stats::QueryStatsPayloadBuilder, self_validating_payload::SelfValidatingPayloadBuilder,
 };
 use ic_logger::{error, warn, ReplicaLogger};
 use ic_types::{
@@ -13,7 +13,7 @@ use ic_types::{
         ValidationContext, XNetPayload,
     },
     consensus::Payload,
-    CountBytes, Height, NumBytes, Time,
+    CountBytes, Height, NodeId, NumBytes, Time,
 };
 use std::sync::Arc;
 
@@ -41,6 +41,7 @@ pub(crate) enum BatchPayloadSectionBuilder {
     XNet(Arc<dyn XNetPayloadBuilder>),
     SelfValidating(Arc<dyn SelfValidatingPayloadBuilder>),
     CanisterHttp(Arc<dyn CanisterHttpPayloadBuilder>),
+    QueryStats(Arc<dyn QueryStatsPayloadBuilder>),
 }
 
 impl BatchPayloadSectionBuilder {
@@ -207,12 +208,66 @@ impl BatchPayloadSectionBuilder {
                     }
                 }
             }
+            Self::QueryStats(builder) => {
+                let past_payloads = builder.filter_past_payloads(past_payloads);
+                let query_stats = builder.get_query_stats_payload(
+                    height,
+                    validation_context,
+                    &past_payloads,
+                    max_size,
+                );
+                let size = NumBytes::new(
+                    query_stats
+                        .as_ref()
+                        .map_or(0, |query_stats| query_stats.count_bytes())
+                        as u64,
+                );
+
+                // Check validation as safety measure. The payload was built by
+                // this node, which is also the proposer of the block.
+                if let Some(Err(err)) = query_stats.as_ref().map(|query_stats| {
+                    builder.validate_query_stats_payload(
+                        height,
+                        query_stats.proposer,
+                        Some(query_stats),
+                        validation_context,
+                        &past_payloads,
+                    )
+                }) {
+                    error!(
+                        logger,
+                        "QueryStats payload did not pass validation, this is a bug, {:?} @{}",
+                        err,
+                        CRITICAL_ERROR_VALIDATION_NOT_PASSED
+                    );
+
+                    metrics.critical_error_validation_not_passed.inc();
+                    payload.query_stats = None;
+                    return NumBytes::new(0);
+                }
+
+                if size > max_size {
+                    error!(
+                        logger,
+                        "QueryStatsPayload is larger than byte_limit. This is a bug, @{}",
+                        CRITICAL_ERROR_PAYLOAD_TOO_LARGE
+                    );
+
+                    metrics.critical_error_payload_too_large.inc();
+                    payload.query_stats = None;
+                    return NumBytes::new(0);
+                }
+
+                payload.query_stats = query_stats;
+                size
+            }
         }
     }
 
     /// Called to validate the payload.
     ///
     /// # Argument:
+    /// - `proposer`: The node that proposed the block containing the payload.
     /// - `payload`: The payload to verify.
     /// - `validation_context`: The [`ValidationContext`], under which to validate the payload.
     /// - `past_payloads`: All [`Payload`]s from the certified height to the tip.
@@ -223,6 +278,7 @@ impl BatchPayloadSectionBuilder {
     pub(crate) fn validate_payload(
         &self,
         height: Height,
+        proposer: NodeId,
         payload: &BatchPayload,
         validation_context: &ValidationContext,
         past_payloads: &[(Height, Time, Payload)],
@@ -262,6 +318,16 @@ impl BatchPayloadSectionBuilder {
                     &past_payloads,
                 )?)
             }
+            BatchPayloadSectionBuilder::QueryStats(builder) => {
+                let past_payloads = builder.filter_past_payloads(past_payloads);
+                Ok(builder.validate_query_stats_payload(
+                    height,
+                    proposer,
+                    payload.query_stats.as_ref(),
+                    validation_context,
+                    &past_payloads,
+                )?)
+            }
         }
     }
 }
@@ -11,6 +11,7 @@ use ic_interfaces::{
     consensus::{PayloadPermanentError, PayloadValidationError},
     ingress_manager::IngressSelector,
     messaging::XNetPayloadBuilder,
+    query_stats::QueryStatsPayloadBuilder,
     self_validating_payload::SelfValidatingPayloadBuilder,
     validation::{ValidationError, ValidationResult},
 };
@@ -22,7 +23,7 @@ use ic_types::{
     batch::{BatchPayload, ValidationContext, MAX_BITCOIN_PAYLOAD_IN_BYTES},
     consensus::Payload,
     messages::MAX_XNET_PAYLOAD_IN_BYTES,
-    Height, NumBytes, SubnetId, Time,
+    Height, NodeId, NumBytes, SubnetId, Time,
 };
 use std::sync::Arc;
 
@@ -42,8 +43,8 @@ pub trait PayloadBuilder: Send + Sync {
         subnet_records: &SubnetRecords,
     ) -> BatchPayload;
 
-    /// Checks whether the provided `payload` is valid given `past_payloads` and
-    /// `context`.
+    /// Checks whether the provided `payload`, included in a block proposed by
+    /// `proposer`, is valid given `past_payloads` and `context`.
     ///
     /// `past_payloads` contains the `Payloads` from all blocks above the
     /// certified height provided in `context`, in descending block height
@@ -51,6 +52,7 @@ pub trait PayloadBuilder: Send + Sync {
     fn validate_payload(
         &self,
         height: Height,
+        proposer: NodeId,
         payload: &Payload,
         past_payloads: &[(Height, Time, Payload)],
         context: &ValidationContext,
@@ -75,6 +77,7 @@ impl PayloadBuilderImpl {
         xnet_payload_builder: Arc<dyn XNetPayloadBuilder>,
         self_validating_payload_builder: Arc<dyn SelfValidatingPayloadBuilder>,
         canister_http_payload_builder: Arc<dyn CanisterHttpPayloadBuilder>,
+        query_stats_payload_builder: Arc<dyn QueryStatsPayloadBuilder>,
         metrics: MetricsRegistry,
         logger: ReplicaLogger,
     ) -> Self {
@@ -83,6 +86,7 @@ impl PayloadBuilderImpl {
             BatchPayloadSectionBuilder::SelfValidating(self_validating_payload_builder),
             BatchPayloadSectionBuilder::XNet(xnet_payload_builder),
             BatchPayloadSectionBuilder::CanisterHttp(canister_http_payload_builder),
+            BatchPayloadSectionBuilder::QueryStats(query_stats_payload_builder),
         ];
 
         Self {
@@ -148,6 +152,7 @@ impl PayloadBuilder for PayloadBuilderImpl {
     fn validate_payload(
         &self,
         height: Height,
+        proposer: NodeId,
         payload: &Payload,
         past_payloads: &[(Height, Time, Payload)],
         context: &ValidationContext,
@@ -164,8 +169,13 @@ impl PayloadBuilder for PayloadBuilderImpl {
 
         let mut accumulated_size = NumBytes::new(0);
         for builder in &self.section_builder {
-            accumulated_size +=
-                builder.validate_payload(height, batch_payload, context, past_payloads)?;
+            accumulated_size += builder.validate_payload(
+                height,
+                proposer,
+                batch_payload,
+                context,
+                past_payloads,
+            )?;
             if accumulated_size > max_block_payload_size {
                 return Err(ValidationError::Permanent(
                     PayloadPermanentError::PayloadTooBig {
@@ -231,6 +241,7 @@ pub(crate) mod test {
         consensus::fake::Fake,
         ingress_selector::FakeIngressSelector,
         mock_time,
+        query_stats_payload_builder::FakeQueryStatsPayloadBuilder,
         self_validating_payload_builder::FakeSelfValidatingPayloadBuilder,
         types::ids::{node_test_id, subnet_test_id},
         types::messages::SignedIngressBuilder,
@@ -287,6 +298,7 @@ pub(crate) mod test {
             Arc::new(xnet_payload_builder),
             Arc::new(self_validating_payload_builder),
             Arc::new(canister_http_payload_builder),
+            Arc::new(FakeQueryStatsPayloadBuilder::new()),
             MetricsRegistry::new(),
             no_op_logger(),
         )
@@ -942,6 +942,7 @@ impl Validator {
 
         let parent = get_notarized_parent(pool_reader, proposal)?;
         self.verify_signature(pool_reader, proposal)?;
+        let proposer = proposal.signature.signer;
 
         // Ensure registry_version, certified_height and time are non-decreasing.
         let proposal = proposal.as_ref();
@@ -977,6 +978,7 @@ impl Validator {
         self.payload_builder
             .validate_payload(
                 proposal.height,
+                proposer,
                 &proposal.payload,
                 &payloads,
                 &proposal.context,
@@ -2005,12 +2007,12 @@ pub mod test {
             Arc::get_mut(&mut payload_builder)
                 .unwrap()
                 .expect_validate_payload()
-                .withf(move |_, _, payloads, _| {
+                .withf(move |_, _, _, payloads, _| {
                     // Assert that payloads are from blocks between:
                     // `certified_height` and the current height (`prior_height`)
                     payloads.len() as u64 == (prior_height - certified_height).get()
                 })
-                .returning(|_, _, _, _| Ok(()));
+                .returning(|_, _, _, _, _| Ok(()));
             state_manager
                 .get_mut()
                 .expect_latest_certified_height()
@@ -2118,7 +2120,7 @@ pub mod test {
             Arc::get_mut(&mut payload_builder)
                 .unwrap()
                 .expect_validate_payload()
-                .returning(|_, _, _, _| Ok(()));
+                .returning(|_, _, _, _, _| Ok(()));
             state_manager
                 .get_mut()
                 .expect_latest_certified_height()
@@ -2215,7 +2217,7 @@ pub mod test {
             Arc::get_mut(&mut payload_builder)
                 .unwrap()
                 .expect_validate_payload()
-                .returning(|_, _, _, _| Ok(()));
+                .returning(|_, _, _, _, _| Ok(()));
             state_manager
                 .get_mut()
                 .expect_latest_certified_height()
@@ -2337,7 +2339,7 @@ pub mod test {
             Arc::get_mut(&mut payload_builder)
                 .unwrap()
                 .expect_validate_payload()
-                .returning(|_, _, _, _| Ok(()));
+                .returning(|_, _, _, _, _| Ok(()));
             state_manager
                 .get_mut()
                 .expect_latest_certified_height()
@@ -2413,7 +2415,7 @@ pub mod test {
             Arc::get_mut(&mut payload_builder)
                 .unwrap()
                 .expect_validate_payload()
-                .returning(|_, _, _, _| Ok(()));
+                .returning(|_, _, _, _, _| Ok(()));
             state_manager
                 .get_mut()
                 .expect_latest_certified_height()
@@ -2777,7 +2779,7 @@ pub mod test {
             Arc::get_mut(&mut payload_builder)
                 .unwrap()
                 .expect_validate_payload()
-                .returning(|_, _, _, _| {
+                .returning(|_, _, _, _, _| {
                     Err(ValidationError::Transient(
                         PayloadTransientError::XNetPayloadValidationError(
                             XNetTransientValidationError::StateNotCommittedYet(Height::from(0)),
@@ -4,7 +4,7 @@
 
 use super::pre_signer::{EcdsaTranscriptBuilder, EcdsaTranscriptBuilderImpl};
 use super::signer::{EcdsaSignatureBuilder, EcdsaSignatureBuilderImpl};
-use super::utils::EcdsaBlockReaderImpl;
+use super::utils::{algorithm_for_key_id, EcdsaBlockReaderImpl};
 use crate::consensus::{
     crypto::ConsensusCrypto, metrics::EcdsaPayloadMetrics, pool_reader::PoolReader,
 };
@@ -729,6 +729,7 @@ pub(crate) fn create_data_payload_helper_2(
     if let Some(new_transcript) = update_next_key_transcript(
         receivers,
         next_interval_registry_version,
+        algorithm_for_key_id(&ecdsa_payload.key_transcript.key_id),
         current_key_transcript.as_ref(),
         &mut ecdsa_payload.key_transcript.next_in_creation,
         &mut ecdsa_payload.uid_generator,
@@ -770,6 +771,7 @@ pub(crate) fn create_data_payload_helper_2(
 fn new_random_config(
     subnet_nodes: &[NodeId],
     summary_registry_version: RegistryVersion,
+    algorithm_id: AlgorithmId,
     uid_generator: &mut ecdsa::EcdsaUIDGenerator,
 ) -> Result<ecdsa::RandomTranscriptParams, EcdsaPayloadError> {
     let transcript_id = uid_generator.next_transcript_id();
@@ -780,7 +782,7 @@ fn new_random_config(
         dealers,
         receivers,
         summary_registry_version,
-        AlgorithmId::ThresholdEcdsaSecp256k1,
+        algorithm_id,
     ))
 }
 
@@ -797,6 +799,7 @@ fn make_new_quadruples_if_needed(
         make_new_quadruples_if_needed_helper(
             &node_ids,
             key_transcript.registry_version(),
+            key_transcript.algorithm_id(),
             ecdsa_config,
             ecdsa_payload,
         )
@@ -808,6 +811,7 @@ fn make_new_quadruples_if_needed(
 fn make_new_quadruples_if_needed_helper(
     subnet_nodes: &[NodeId],
     registry_version: RegistryVersion,
+    algorithm_id: AlgorithmId,
     ecdsa_config: &EcdsaConfig,
     ecdsa_payload: &mut ecdsa::EcdsaPayload,
 ) -> Result<(), EcdsaPayloadError> {
@@ -817,8 +821,10 @@ fn make_new_quadruples_if_needed_helper(
         let quadruples_in_creation = &mut ecdsa_payload.quadruples_in_creation;
         let uid_generator = &mut ecdsa_payload.uid_generator;
         for _ in 0..(quadruples_to_create - unassigned_quadruples) {
-            let kappa_config = new_random_config(subnet_nodes, registry_version, uid_generator)?;
-            let lambda_config = new_random_config(subnet_nodes, registry_version, uid_generator)?;
+            let kappa_config =
+                new_random_config(subnet_nodes, registry_version, algorithm_id, uid_generator)?;
+            let lambda_config =
+                new_random_config(subnet_nodes, registry_version, algorithm_id, uid_generator)?;
             quadruples_in_creation.insert(
                 uid_generator.next_quadruple_id(),
                 ecdsa::QuadrupleInCreation::new(kappa_config, lambda_config),
@@ -949,6 +955,7 @@ pub(crate) fn get_signing_requests<'a>(
                 originator: context.request.sender,
                 respondent: ic_types::CanisterId::ic_00(),
                 originator_reply_callback: *callback_id,
+                deadline: context.request.deadline,
                 refund: context.request.payment,
                 response_payload: ic_types::messages::Payload::Reject(RejectContext {
                     code: RejectCode::CanisterReject,
@@ -1005,6 +1012,7 @@ pub(crate) fn get_signing_requests<'a>(
                     originator: context.request.sender,
                     respondent: ic_types::CanisterId::ic_00(),
                     originator_reply_callback: *callback_id,
+                    deadline: context.request.deadline,
                     refund: context.request.payment,
                     response_payload: ic_types::messages::Payload::Reject(RejectContext {
                         code: RejectCode::CanisterReject,
@@ -1083,6 +1091,7 @@ pub(crate) fn update_signature_agreements(
             originator: context.request.sender,
             respondent: ic_types::CanisterId::ic_00(),
             originator_reply_callback: **callback_id,
+            deadline: context.request.deadline,
             // Execution is responsible for burning the appropriate cycles
             // before pushing the new context, so any remaining cycles can
             // be refunded to the canister.
@@ -1140,6 +1149,7 @@ pub(crate) fn update_ongoing_signatures(
 fn update_next_key_transcript(
     receivers: &[NodeId],
     registry_version: RegistryVersion,
+    algorithm_id: AlgorithmId,
     current_key_transcript: Option<&ecdsa::UnmaskedTranscriptWithAttributes>,
     next_key_transcript_creation: &mut ecdsa::KeyTranscriptCreation,
     uid_generator: &mut ecdsa::EcdsaUIDGenerator,
@@ -1201,7 +1211,7 @@ fn update_next_key_transcript(
                     dealers_set,
                     receivers_set,
                     registry_version,
-                    AlgorithmId::ThresholdEcdsaSecp256k1,
+                    algorithm_id,
                 ),
             );
         }
@@ -1549,6 +1559,7 @@ fn make_reshare_dealings_response(
                         originator: context.request.sender,
                         respondent: ic_types::CanisterId::ic_00(),
                         originator_reply_callback: *callback_id,
+                        deadline: context.request.deadline,
                         refund: context.request.payment,
                         response_payload: ic_types::messages::Payload::Data(
                             ComputeInitialEcdsaDealingsResponse {
@@ -1815,10 +1826,20 @@ mod tests {
         uid_generator: &mut ecdsa::EcdsaUIDGenerator,
         quadruples_in_creation: &mut BTreeMap<ecdsa::QuadrupleId, ecdsa::QuadrupleInCreation>,
     ) -> (ecdsa::RandomTranscriptParams, ecdsa::RandomTranscriptParams) {
-        let kappa_config_ref =
-            new_random_config(subnet_nodes, registry_version, uid_generator).unwrap();
-        let lambda_config_ref =
-            new_random_config(subnet_nodes, registry_version, uid_generator).unwrap();
+        let kappa_config_ref = new_random_config(
+            subnet_nodes,
+            registry_version,
+            AlgorithmId::ThresholdEcdsaSecp256k1,
+            uid_generator,
+        )
+        .unwrap();
+        let lambda_config_ref = new_random_config(
+            subnet_nodes,
+            registry_version,
+            AlgorithmId::ThresholdEcdsaSecp256k1,
+            uid_generator,
+        )
+        .unwrap();
         quadruples_in_creation.insert(
             uid_generator.next_quadruple_id(),
             ecdsa::QuadrupleInCreation::new(kappa_config_ref.clone(), lambda_config_ref.clone()),
@@ -1858,6 +1879,7 @@ mod tests {
         let result = make_new_quadruples_if_needed_helper(
             &subnet_nodes,
             summary_registry_version,
+            AlgorithmId::ThresholdEcdsaSecp256k1,
             &ecdsa_config,
             &mut ecdsa_payload,
         );
@@ -1882,6 +1904,36 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_ecdsa_make_new_quadruples_for_secp256r1_key() {
+        let subnet_id = subnet_test_id(1);
+        let subnet_nodes = (0..4).map(node_test_id).collect::<Vec<_>>();
+        let mut ecdsa_payload = empty_ecdsa_payload(subnet_id);
+        let update_res = ecdsa_payload.uid_generator.update_height(Height::new(1));
+        assert!(update_res.is_ok());
+        let key_id = EcdsaKeyId::from_str("Secp256r1:some_key").unwrap();
+        let algorithm_id = algorithm_for_key_id(&key_id);
+        assert_eq!(algorithm_id, AlgorithmId::ThresholdEcdsaSecp256r1);
+        let ecdsa_config = EcdsaConfig {
+            quadruples_to_create_in_advance: 2,
+            key_ids: vec![key_id],
+            ..EcdsaConfig::default()
+        };
+        let result = make_new_quadruples_if_needed_helper(
+            &subnet_nodes,
+            RegistryVersion::new(10),
+            algorithm_id,
+            &ecdsa_config,
+            &mut ecdsa_payload,
+        );
+        assert!(result.is_ok());
+        assert_eq!(ecdsa_payload.quadruples_in_creation.len(), 2);
+        for quadruple in ecdsa_payload.quadruples_in_creation.values() {
+            assert_eq!(quadruple.kappa_config.as_ref().algorithm_id, algorithm_id);
+            assert_eq!(quadruple.lambda_config.as_ref().algorithm_id, algorithm_id);
+        }
+    }
+
     #[test]
     fn test_ecdsa_signing_request_order() {
         let subnet_id = subnet_test_id(1);
@@ -2288,6 +2340,7 @@ mod tests {
         let result = update_next_key_transcript(
             &subnet_nodes,
             registry_version,
+            AlgorithmId::ThresholdEcdsaSecp256k1,
             None,
             &mut payload.key_transcript.next_in_creation,
             &mut payload.uid_generator,
@@ -2321,6 +2374,7 @@ mod tests {
         let result = update_next_key_transcript(
             &subnet_nodes,
             registry_version,
+            AlgorithmId::ThresholdEcdsaSecp256k1,
             None,
             &mut payload.key_transcript.next_in_creation,
             &mut payload.uid_generator,
@@ -2360,6 +2414,7 @@ mod tests {
         let result = update_next_key_transcript(
             &subnet_nodes,
             registry_version,
+            AlgorithmId::ThresholdEcdsaSecp256k1,
             None,
             &mut payload.key_transcript.next_in_creation,
             &mut payload.uid_generator,
@@ -2397,6 +2452,7 @@ mod tests {
         let result = update_next_key_transcript(
             &subnet_nodes,
             registry_version,
+            AlgorithmId::ThresholdEcdsaSecp256k1,
             Some(&current_key_transcript),
             &mut payload.key_transcript.next_in_creation,
             &mut payload.uid_generator,
@@ -2431,6 +2487,7 @@ mod tests {
         let result = update_next_key_transcript(
             &subnet_nodes,
             registry_version,
+            AlgorithmId::ThresholdEcdsaSecp256k1,
             Some(&current_key_transcript),
             &mut payload.key_transcript.next_in_creation,
             &mut payload.uid_generator,
@@ -2481,6 +2538,7 @@ mod tests {
         let result = update_next_key_transcript(
             &subnet_nodes,
             registry_version,
+            AlgorithmId::ThresholdEcdsaSecp256k1,
             None,
             &mut payload.key_transcript.next_in_creation,
             &mut payload.uid_generator,
@@ -2514,6 +2572,7 @@ mod tests {
         let result = update_next_key_transcript(
             &subnet_nodes,
             registry_version,
+            AlgorithmId::ThresholdEcdsaSecp256k1,
             None,
             &mut payload.key_transcript.next_in_creation,
             &mut payload.uid_generator,
@@ -2553,6 +2612,7 @@ mod tests {
         let result = update_next_key_transcript(
             &subnet_nodes,
             registry_version,
+            AlgorithmId::ThresholdEcdsaSecp256k1,
             None,
             &mut payload.key_transcript.next_in_creation,
             &mut payload.uid_generator,
@@ -2602,6 +2662,7 @@ mod tests {
         let result = update_next_key_transcript(
             &subnet_nodes,
             registry_version,
+            AlgorithmId::ThresholdEcdsaSecp256k1,
             None,
             &mut payload.key_transcript.next_in_creation,
             &mut payload.uid_generator,
@@ -2640,6 +2701,7 @@ mod tests {
         let result = update_next_key_transcript(
             &target_subnet_nodes,
             registry_version,
+            AlgorithmId::ThresholdEcdsaSecp256k1,
             None,
             &mut payload.key_transcript.next_in_creation,
             &mut payload.uid_generator,
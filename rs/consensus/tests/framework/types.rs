@@ -10,6 +10,7 @@ use ic_interfaces::{
     certification::Certifier,
     ingress_manager::IngressSelector,
     messaging::{MessageRouting, XNetPayloadBuilder},
+    query_stats::QueryStatsPayloadBuilder,
     self_validating_payload::SelfValidatingPayloadBuilder,
     time_source::TimeSource,
 };
@@ -22,7 +23,7 @@ use ic_replicated_state::ReplicatedState;
 use ic_test_artifact_pool::ingress_pool::TestIngressPool;
 use ic_test_utilities::{
     canister_http::FakeCanisterHttpPayloadBuilder, ingress_selector::FakeIngressSelector,
-    message_routing::FakeMessageRouting,
+    message_routing::FakeMessageRouting, query_stats_payload_builder::FakeQueryStatsPayloadBuilder,
     self_validating_payload_builder::FakeSelfValidatingPayloadBuilder,
     state_manager::FakeStateManager, xnet_payload_builder::FakeXNetPayloadBuilder,
 };
@@ -142,6 +143,7 @@ pub struct ConsensusDependencies {
     pub(crate) ingress_selector: Arc<dyn IngressSelector>,
     pub(crate) self_validating_payload_builder: Arc<dyn SelfValidatingPayloadBuilder>,
     pub(crate) canister_http_payload_builder: Arc<dyn CanisterHttpPayloadBuilder>,
+    pub(crate) query_stats_payload_builder: Arc<dyn QueryStatsPayloadBuilder>,
     pub consensus_pool: Arc<RwLock<ConsensusPoolImpl>>,
     pub dkg_pool: Arc<RwLock<dkg_pool::DkgPoolImpl>>,
     pub ecdsa_pool: Arc<RwLock<ecdsa_pool::EcdsaPoolImpl>>,
@@ -193,6 +195,7 @@ impl ConsensusDependencies {
             xnet_payload_builder: Arc::new(xnet_payload_builder),
             self_validating_payload_builder: Arc::new(FakeSelfValidatingPayloadBuilder::new()),
             canister_http_payload_builder: Arc::new(FakeCanisterHttpPayloadBuilder::new()),
+            query_stats_payload_builder: Arc::new(FakeQueryStatsPayloadBuilder::new()),
             state_manager,
             metrics_registry,
             replica_config,
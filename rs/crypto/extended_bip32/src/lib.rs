@@ -22,6 +22,22 @@ impl From<ThresholdEcdsaError> for ExtendedBip32DerivationError {
     }
 }
 
+/// The curve a key derivation is performed over
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ExtendedBip32Curve {
+    Secp256k1,
+    Secp256r1,
+}
+
+impl From<ExtendedBip32Curve> for EccCurveType {
+    fn from(curve: ExtendedBip32Curve) -> Self {
+        match curve {
+            ExtendedBip32Curve::Secp256k1 => EccCurveType::K256,
+            ExtendedBip32Curve::Secp256r1 => EccCurveType::P256,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ExtendedBip32DerivationOutput {
     pub derived_public_key: Vec<u8>,
@@ -73,14 +89,27 @@ impl DerivationPath {
         &self,
         public_key: &[u8],
         chain_code: &[u8],
+    ) -> ExtendedBip32DerivationResult<ExtendedBip32DerivationOutput> {
+        self.key_derivation_on_curve(ExtendedBip32Curve::Secp256k1, public_key, chain_code)
+    }
+
+    /// Perform extended BIP32 key derivation on the specified path and curve
+    ///
+    /// Identical to [`Self::key_derivation`] except that `public_key` and
+    /// the derived public key are compressed SEC1 points on `curve`. BIP32
+    /// itself is only defined for secp256k1; for secp256r1 the same
+    /// construction is applied.
+    pub fn key_derivation_on_curve(
+        &self,
+        curve: ExtendedBip32Curve,
+        public_key: &[u8],
+        chain_code: &[u8],
     ) -> ExtendedBip32DerivationResult<ExtendedBip32DerivationOutput> {
         if chain_code.len() != 32 {
             return Err(ExtendedBip32DerivationError::InvalidChainCodeLength);
         }
 
-        let curve_type = EccCurveType::K256;
-
-        let public_key = EccPoint::deserialize(curve_type, public_key)
+        let public_key = EccPoint::deserialize(curve.into(), public_key)
             .map_err(|_| ExtendedBip32DerivationError::InvalidPublicKeyEncoding)?;
 
         let (offset, chain_code) = self
@@ -101,3 +101,21 @@ fn verify_bip32_standard_key_derivation() -> ExtendedBip32DerivationResult<()> {
 
     Ok(())
 }
+
+#[test]
+fn verify_secp256r1_extended_key_derivation() -> ExtendedBip32DerivationResult<()> {
+    // The master key is the P-256 generator
+    let master_key =
+        hex::decode("036b17d1f2e12c4247f8bce6e563a440f277037d812deb33a0f4a13945d898c296").unwrap();
+    let chain_code = [0u8; 32];
+
+    let path = DerivationPath::new_bip32(&[1, 2, 3]);
+
+    assert_ebip32_result(
+        path.key_derivation_on_curve(ExtendedBip32Curve::Secp256r1, &master_key, &chain_code)?,
+        "03b3919219b0adb4ebc6ca8f69bbc750c2d2101590b1f79da4c1cfd2356699122c",
+        "57d20a91e1591881f112b8db3d7338475301c60726fb28ca2c66b08ebadabefc",
+    );
+
+    Ok(())
+}
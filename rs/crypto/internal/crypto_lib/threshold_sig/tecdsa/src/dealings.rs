@@ -173,9 +173,15 @@ pub struct IDkgDealingInternal {
 }
 
 impl IDkgDealingInternal {
+    /// Create a new dealing
+    ///
+    /// The shared secret lives on `curve`, while the shares are encrypted to
+    /// recipients whose MEGa keys are on `key_curve`.
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         shares: &SecretShares,
         curve: EccCurveType,
+        key_curve: EccCurveType,
         seed: Seed,
         threshold: usize,
         recipients: &[MEGaPublicKey],
@@ -190,7 +196,7 @@ impl IDkgDealingInternal {
         }
 
         for recipient in recipients {
-            if recipient.curve_type() != curve {
+            if recipient.curve_type() != key_curve {
                 return Err(ThresholdEcdsaError::InvalidRecipients);
             }
         }
@@ -318,9 +324,11 @@ impl IDkgDealingInternal {
         })
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn publicly_verify(
         &self,
         curve_type: EccCurveType,
+        key_curve: EccCurveType,
         transcript_type: &IDkgTranscriptOperationInternal,
         reconstruction_threshold: NumberOfNodes,
         dealer_index: NodeIndex,
@@ -349,7 +357,7 @@ impl IDkgDealingInternal {
                 self.commitment
                     .verify_is(PolynomialCommitmentType::Pedersen, curve_type)?;
                 self.ciphertext
-                    .verify_is(MEGaCiphertextType::Pairs, curve_type)?;
+                    .verify_is(MEGaCiphertextType::Pairs, key_curve, curve_type)?;
                 // no ZK proof for this transcript type
                 Ok(())
             }
@@ -361,7 +369,7 @@ impl IDkgDealingInternal {
                     .verify_is(PolynomialCommitmentType::Simple, curve_type)?;
                 previous_commitment.verify_is(PolynomialCommitmentType::Pedersen, curve_type)?;
                 self.ciphertext
-                    .verify_is(MEGaCiphertextType::Single, curve_type)?;
+                    .verify_is(MEGaCiphertextType::Single, key_curve, curve_type)?;
 
                 proof.verify(
                     &previous_commitment.evaluate_at(dealer_index)?,
@@ -377,7 +385,7 @@ impl IDkgDealingInternal {
                     .verify_is(PolynomialCommitmentType::Simple, curve_type)?;
                 previous_commitment.verify_is(PolynomialCommitmentType::Simple, curve_type)?;
                 self.ciphertext
-                    .verify_is(MEGaCiphertextType::Single, curve_type)?;
+                    .verify_is(MEGaCiphertextType::Single, key_curve, curve_type)?;
 
                 match previous_commitment {
                     PolynomialCommitment::Pedersen(_) => {
@@ -399,7 +407,7 @@ impl IDkgDealingInternal {
                 self.commitment
                     .verify_is(PolynomialCommitmentType::Pedersen, curve_type)?;
                 self.ciphertext
-                    .verify_is(MEGaCiphertextType::Pairs, curve_type)?;
+                    .verify_is(MEGaCiphertextType::Pairs, key_curve, curve_type)?;
                 lhs.verify_is(PolynomialCommitmentType::Simple, curve_type)?;
                 rhs.verify_is(PolynomialCommitmentType::Pedersen, curve_type)?;
 
@@ -420,13 +428,14 @@ impl IDkgDealingInternal {
     pub fn privately_verify(
         &self,
         curve_type: EccCurveType,
+        key_curve: EccCurveType,
         private_key: &MEGaPrivateKey,
         public_key: &MEGaPublicKey,
         associated_data: &[u8],
         dealer_index: NodeIndex,
         recipient_index: NodeIndex,
     ) -> ThresholdEcdsaResult<()> {
-        if private_key.curve_type() != curve_type || public_key.curve_type() != curve_type {
+        if private_key.curve_type() != key_curve || public_key.curve_type() != key_curve {
             return Err(ThresholdEcdsaError::CurveMismatch);
         }
 
@@ -63,16 +63,15 @@ impl DerivationPath {
     ///
     /// Extended to support larger inputs, which is needed for
     /// deriving the canister public key
+    ///
+    /// BIP32 is only defined for secp256k1; for secp256r1 the same
+    /// construction is used, which is well defined since the curve also
+    /// has 256-bit scalars and 33 byte compressed points.
     fn bip32_ckdpub(
         public_key: &EccPoint,
         chain_key: &[u8],
         index: &DerivationIndex,
     ) -> ThresholdEcdsaResult<(EccPoint, Vec<u8>, EccScalar)> {
-        // BIP32 is only defined for secp256k1
-        if public_key.curve_type() != EccCurveType::K256 {
-            return Err(ThresholdEcdsaError::CurveMismatch);
-        }
-
         let mut hmac = Hmac::<Sha512>::new(chain_key);
 
         hmac.write(&public_key.serialize());
@@ -116,27 +115,19 @@ impl DerivationPath {
 
         let curve_type = master_public_key.curve_type();
 
-        if curve_type == EccCurveType::K256 {
-            let mut derived_key = master_public_key.clone();
-            let mut derived_chain_key = chain_code.to_vec();
-            let mut derived_offset = EccScalar::zero(curve_type);
+        let mut derived_key = master_public_key.clone();
+        let mut derived_chain_key = chain_code.to_vec();
+        let mut derived_offset = EccScalar::zero(curve_type);
 
-            for idx in &self.path {
-                let (next_derived_key, next_chain_key, next_offset) =
-                    Self::bip32_ckdpub(&derived_key, &derived_chain_key, idx)?;
+        for idx in &self.path {
+            let (next_derived_key, next_chain_key, next_offset) =
+                Self::bip32_ckdpub(&derived_key, &derived_chain_key, idx)?;
 
-                derived_key = next_derived_key;
-                derived_chain_key = next_chain_key;
-                derived_offset = derived_offset.add(&next_offset)?;
-            }
-
-            Ok((derived_offset, derived_chain_key))
-        } else {
-            // Key derivation is not currently defined for curves other than secp256k1
-            Err(ThresholdEcdsaError::InvalidArguments(format!(
-                "Currently key derivation not defined for {}",
-                curve_type
-            )))
+            derived_key = next_derived_key;
+            derived_chain_key = next_chain_key;
+            derived_offset = derived_offset.add(&next_offset)?;
         }
+
+        Ok((derived_offset, derived_chain_key))
     }
 }
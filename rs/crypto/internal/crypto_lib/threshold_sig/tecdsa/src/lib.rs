@@ -305,6 +305,18 @@ impl From<ThresholdEcdsaError> for IdkgCreateDealingInternalError {
     }
 }
 
+/// The curve of the secret shared by an IDKG instance of `algorithm_id`,
+/// and the curve of the MEGa keys the shares are encrypted to
+///
+/// MEGa keys are always secp256k1, also for P-256 key material.
+fn idkg_curve_types(algorithm_id: AlgorithmId) -> Option<(EccCurveType, EccCurveType)> {
+    match algorithm_id {
+        AlgorithmId::ThresholdEcdsaSecp256k1 => Some((EccCurveType::K256, EccCurveType::K256)),
+        AlgorithmId::ThresholdEcdsaSecp256r1 => Some((EccCurveType::P256, EccCurveType::K256)),
+        _ => None,
+    }
+}
+
 /// Create a dealing for threshold ECDSA
 pub fn create_dealing(
     algorithm_id: ic_types::crypto::AlgorithmId,
@@ -315,14 +327,13 @@ pub fn create_dealing(
     shares: &SecretShares,
     seed: Seed,
 ) -> Result<IDkgDealingInternal, IdkgCreateDealingInternalError> {
-    let curve = match algorithm_id {
-        AlgorithmId::ThresholdEcdsaSecp256k1 => Ok(EccCurveType::K256),
-        _ => Err(IdkgCreateDealingInternalError::UnsupportedAlgorithm),
-    }?;
+    let (curve, key_curve) = idkg_curve_types(algorithm_id)
+        .ok_or(IdkgCreateDealingInternalError::UnsupportedAlgorithm)?;
 
     IDkgDealingInternal::new(
         shares,
         curve,
+        key_curve,
         seed,
         threshold.get() as usize,
         recipients,
@@ -366,10 +377,8 @@ pub fn create_transcript(
     verified_dealings: &BTreeMap<NodeIndex, IDkgDealingInternal>,
     operation_mode: &IDkgTranscriptOperationInternal,
 ) -> Result<IDkgTranscriptInternal, IDkgCreateTranscriptInternalError> {
-    let curve = match algorithm_id {
-        AlgorithmId::ThresholdEcdsaSecp256k1 => Ok(EccCurveType::K256),
-        _ => Err(IDkgCreateTranscriptInternalError::UnsupportedAlgorithm),
-    }?;
+    let (curve, _key_curve) = idkg_curve_types(algorithm_id)
+        .ok_or(IDkgCreateTranscriptInternalError::UnsupportedAlgorithm)?;
 
     IDkgTranscriptInternal::new(
         curve,
@@ -563,14 +572,13 @@ pub fn publicly_verify_dealing(
     number_of_receivers: NumberOfNodes,
     associated_data: &[u8],
 ) -> Result<(), IDkgVerifyDealingInternalError> {
-    let curve = match algorithm_id {
-        AlgorithmId::ThresholdEcdsaSecp256k1 => Ok(EccCurveType::K256),
-        _ => Err(IDkgVerifyDealingInternalError::UnsupportedAlgorithm),
-    }?;
+    let (curve, key_curve) = idkg_curve_types(algorithm_id)
+        .ok_or(IDkgVerifyDealingInternalError::UnsupportedAlgorithm)?;
 
     dealing
         .publicly_verify(
             curve,
+            key_curve,
             transcript_type,
             reconstruction_threshold,
             dealer_index,
@@ -595,14 +603,13 @@ pub fn privately_verify_dealing(
     dealer_index: NodeIndex,
     recipient_index: NodeIndex,
 ) -> Result<(), IDkgVerifyDealingInternalError> {
-    let curve = match algorithm_id {
-        AlgorithmId::ThresholdEcdsaSecp256k1 => Ok(EccCurveType::K256),
-        _ => Err(IDkgVerifyDealingInternalError::UnsupportedAlgorithm),
-    }?;
+    let (curve, key_curve) = idkg_curve_types(algorithm_id)
+        .ok_or(IDkgVerifyDealingInternalError::UnsupportedAlgorithm)?;
 
     dealing
         .privately_verify(
             curve,
+            key_curve,
             private_key,
             public_key,
             associated_data,
@@ -660,6 +667,9 @@ fn signature_parameters(algorithm_id: AlgorithmId) -> Option<(EccCurveType, usiz
         AlgorithmId::ThresholdEcdsaSecp256k1 => {
             Some((EccCurveType::K256, EccCurveType::K256.scalar_bytes()))
         }
+        AlgorithmId::ThresholdEcdsaSecp256r1 => {
+            Some((EccCurveType::P256, EccCurveType::P256.scalar_bytes()))
+        }
         _ => None,
     }
 }
@@ -808,6 +818,7 @@ pub fn combine_sig_shares(
 ) -> Result<ThresholdEcdsaCombinedSigInternal, ThresholdEcdsaCombineSigSharesInternalError> {
     let curve_type = match algorithm_id {
         AlgorithmId::ThresholdEcdsaSecp256k1 => EccCurveType::K256,
+        AlgorithmId::ThresholdEcdsaSecp256r1 => EccCurveType::P256,
         _ => return Err(ThresholdEcdsaCombineSigSharesInternalError::UnsupportedAlgorithm),
     };
 
@@ -205,30 +205,32 @@ impl MEGaCiphertext {
 
     /// Simple type verification for MEGa ciphertexts
     ///
-    /// Verifies that the ciphertext is of the expected type (single or pairs)
-    /// and is for the expected curve.
+    /// Verifies that the ciphertext is of the expected type (single or pairs),
+    /// that the ephemeral key and proof of possession are on the curve of
+    /// the recipients' keys (`key_curve`), and that the encrypted values are
+    /// on the expected curve (`plaintext_curve`).
     pub fn verify_is(
         &self,
         ctype: MEGaCiphertextType,
-        curve: EccCurveType,
+        key_curve: EccCurveType,
+        plaintext_curve: EccCurveType,
     ) -> ThresholdEcdsaResult<()> {
-        if self.ephemeral_key().curve_type() != curve {
+        if self.ephemeral_key().curve_type() != key_curve {
             return Err(ThresholdEcdsaError::CurveMismatch);
         }
 
-        if self.pop_public_key().curve_type() != curve {
+        if self.pop_public_key().curve_type() != key_curve {
             return Err(ThresholdEcdsaError::CurveMismatch);
         }
-        if self.pop_proof().curve_type()? != curve {
+        if self.pop_proof().curve_type()? != key_curve {
             return Err(ThresholdEcdsaError::CurveMismatch);
         }
 
         let curves_ok = match self {
-            MEGaCiphertext::Single(c) => c.ctexts.iter().all(|x| x.curve_type() == curve),
-            MEGaCiphertext::Pairs(c) => c
-                .ctexts
-                .iter()
-                .all(|(x, y)| x.curve_type() == curve && y.curve_type() == curve),
+            MEGaCiphertext::Single(c) => c.ctexts.iter().all(|x| x.curve_type() == plaintext_curve),
+            MEGaCiphertext::Pairs(c) => c.ctexts.iter().all(|(x, y)| {
+                x.curve_type() == plaintext_curve && y.curve_type() == plaintext_curve
+            }),
         };
 
         if !curves_ok {
@@ -308,10 +310,11 @@ impl From<MEGaCiphertextPair> for MEGaCiphertext {
     }
 }
 
+/// Returns the curve of the recipients' keys and the curve of the plaintexts
 fn check_plaintexts(
     plaintexts: &[EccScalar],
     recipients: &[MEGaPublicKey],
-) -> ThresholdEcdsaResult<EccCurveType> {
+) -> ThresholdEcdsaResult<(EccCurveType, EccCurveType)> {
     if plaintexts.len() != recipients.len() {
         return Err(ThresholdEcdsaError::InvalidArguments(
             "Must be as many plaintexts as recipients".to_string(),
@@ -332,19 +335,22 @@ fn check_plaintexts(
         }
     }
 
+    let key_curve = recipients[0].curve_type();
+
     for recipient in recipients {
-        if recipient.curve_type() != curve_type {
+        if recipient.curve_type() != key_curve {
             return Err(ThresholdEcdsaError::CurveMismatch);
         }
     }
 
-    Ok(curve_type)
+    Ok((key_curve, curve_type))
 }
 
+/// Returns the curve of the recipients' keys and the curve of the plaintexts
 fn check_plaintexts_pair(
     plaintexts: &[(EccScalar, EccScalar)],
     recipients: &[MEGaPublicKey],
-) -> ThresholdEcdsaResult<EccCurveType> {
+) -> ThresholdEcdsaResult<(EccCurveType, EccCurveType)> {
     if plaintexts.len() != recipients.len() {
         return Err(ThresholdEcdsaError::InvalidArguments(
             "Must be as many plaintexts as recipients".to_string(),
@@ -365,15 +371,18 @@ fn check_plaintexts_pair(
         }
     }
 
+    let key_curve = recipients[0].curve_type();
+
     for recipient in recipients {
-        if recipient.curve_type() != curve_type {
+        if recipient.curve_type() != key_curve {
             return Err(ThresholdEcdsaError::CurveMismatch);
         }
     }
 
-    Ok(curve_type)
+    Ok((key_curve, curve_type))
 }
 
+#[allow(clippy::too_many_arguments)]
 fn mega_hash_to_scalars(
     ctype: MEGaCiphertextType,
     dealer_index: NodeIndex,
@@ -382,9 +391,8 @@ fn mega_hash_to_scalars(
     public_key: &EccPoint,
     ephemeral_key: &EccPoint,
     shared_secret: &EccPoint,
+    plaintext_curve: EccCurveType,
 ) -> ThresholdEcdsaResult<Vec<EccScalar>> {
-    let curve_type = public_key.curve_type();
-
     let count = match ctype {
         MEGaCiphertextType::Single => 1,
         MEGaCiphertextType::Pairs => 2,
@@ -397,7 +405,7 @@ fn mega_hash_to_scalars(
     ro.add_point("public_key", public_key)?;
     ro.add_point("ephemeral_key", ephemeral_key)?;
     ro.add_point("shared_secret", shared_secret)?;
-    ro.output_scalars(curve_type, count)
+    ro.output_scalars(plaintext_curve, count)
 }
 
 /// Compute the Proof Of Possession (PoP) base element
@@ -489,12 +497,12 @@ impl MEGaCiphertextSingle {
         dealer_index: NodeIndex,
         associated_data: &[u8],
     ) -> ThresholdEcdsaResult<Self> {
-        let curve_type = check_plaintexts(plaintexts, recipients)?;
+        let (key_curve, plaintext_curve) = check_plaintexts(plaintexts, recipients)?;
 
         let ctype = MEGaCiphertextType::Single;
 
         let (beta, v, pop_public_key, pop_proof) =
-            compute_eph_key_and_pop(ctype, curve_type, seed, associated_data, dealer_index)?;
+            compute_eph_key_and_pop(ctype, key_curve, seed, associated_data, dealer_index)?;
 
         let mut ctexts = Vec::with_capacity(recipients.len());
 
@@ -509,6 +517,7 @@ impl MEGaCiphertextSingle {
                 &pubkey.point,
                 &v,
                 &ubeta,
+                plaintext_curve,
             )?;
 
             let ctext = hm[0].add(ptext)?;
@@ -553,6 +562,8 @@ impl MEGaCiphertextSingle {
             ));
         }
 
+        let ctext = &self.ctexts[recipient_index as usize];
+
         let hm = mega_hash_to_scalars(
             MEGaCiphertextType::Single,
             dealer_index,
@@ -561,9 +572,10 @@ impl MEGaCiphertextSingle {
             &recipient_public_key.point,
             &self.ephemeral_key,
             shared_secret,
+            ctext.curve_type(),
         )?;
 
-        self.ctexts[recipient_index as usize].sub(&hm[0])
+        ctext.sub(&hm[0])
     }
 
     pub fn decrypt(
@@ -600,12 +612,12 @@ impl MEGaCiphertextPair {
         dealer_index: NodeIndex,
         associated_data: &[u8],
     ) -> ThresholdEcdsaResult<Self> {
-        let curve_type = check_plaintexts_pair(plaintexts, recipients)?;
+        let (key_curve, plaintext_curve) = check_plaintexts_pair(plaintexts, recipients)?;
 
         let ctype = MEGaCiphertextType::Pairs;
 
         let (beta, v, pop_public_key, pop_proof) =
-            compute_eph_key_and_pop(ctype, curve_type, seed, associated_data, dealer_index)?;
+            compute_eph_key_and_pop(ctype, key_curve, seed, associated_data, dealer_index)?;
 
         let mut ctexts = Vec::with_capacity(recipients.len());
 
@@ -620,6 +632,7 @@ impl MEGaCiphertextPair {
                 &pubkey.point,
                 &v,
                 &ubeta,
+                plaintext_curve,
             )?;
 
             let ctext0 = hm[0].add(&ptext.0)?;
@@ -665,6 +678,8 @@ impl MEGaCiphertextPair {
             ));
         }
 
+        let ctext = &self.ctexts[recipient_index as usize];
+
         let hm = mega_hash_to_scalars(
             MEGaCiphertextType::Pairs,
             dealer_index,
@@ -673,10 +688,11 @@ impl MEGaCiphertextPair {
             &recipient_public_key.point,
             &self.ephemeral_key,
             shared_secret,
+            ctext.0.curve_type(),
         )?;
 
-        let ptext0 = self.ctexts[recipient_index as usize].0.sub(&hm[0])?;
-        let ptext1 = self.ctexts[recipient_index as usize].1.sub(&hm[1])?;
+        let ptext0 = ctext.0.sub(&hm[0])?;
+        let ptext1 = ctext.1.sub(&hm[1])?;
 
         Ok((ptext0, ptext1))
     }
@@ -22,6 +22,7 @@ fn should_complaint_system_work() -> ThresholdEcdsaResult<()> {
     let dealing = IDkgDealingInternal::new(
         &SecretShares::Random,
         curve,
+        curve,
         Seed::from_rng(&mut rng),
         threshold,
         &[pk0.clone(), pk1.clone()],
@@ -170,6 +171,7 @@ fn should_complaint_system_work() -> ThresholdEcdsaResult<()> {
     let dealing2 = IDkgDealingInternal::new(
         &SecretShares::Random,
         curve,
+        curve,
         Seed::from_rng(&mut rng),
         threshold,
         &[pk0.clone(), pk1],
@@ -218,6 +220,7 @@ fn should_complaint_verification_reject_spurious_complaints() -> ThresholdEcdsaR
     let dealing = IDkgDealingInternal::new(
         &SecretShares::Random,
         curve,
+        curve,
         Seed::from_rng(&mut rng),
         threshold,
         &[pk.clone()],
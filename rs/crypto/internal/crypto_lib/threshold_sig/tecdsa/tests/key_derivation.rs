@@ -1,6 +1,5 @@
 use ic_crypto_internal_threshold_sig_ecdsa::*;
 use ic_crypto_test_utils_reproducible_rng::reproducible_rng;
-use rand::Rng;
 use std::convert::{TryFrom, TryInto};
 
 #[allow(dead_code)]
@@ -24,20 +23,25 @@ fn test_index_next_behavior() {
 }
 
 #[test]
-fn test_that_key_derivation_on_secp256r1_currently_fails() -> Result<(), ThresholdEcdsaError> {
-    let mut rng = reproducible_rng();
+fn test_key_derivation_on_secp256r1() -> Result<(), ThresholdEcdsaError> {
     let path = DerivationPath::new_bip32(&[1, 2, 3]);
-    let master_key = EccPoint::hash_to_point(
-        EccCurveType::P256,
-        &rng.gen::<[u8; 32]>(),
-        "public_key".as_bytes(),
-    )?;
+    let master_key = EccPoint::generator_g(EccCurveType::P256)?;
 
+    let (tweak, chain_key) = path.derive_tweak(&master_key)?;
+
+    assert_eq!(
+        hex::encode(tweak.serialize()),
+        "fa3adc39ce98622f2b34034d3f3488716f16dfc1e53169e66b021ece7d5ac703"
+    );
+    assert_eq!(
+        hex::encode(chain_key),
+        "57d20a91e1591881f112b8db3d7338475301c60726fb28ca2c66b08ebadabefc"
+    );
+
+    let derived_key = master_key.add_points(&EccPoint::mul_by_g(&tweak)?)?;
     assert_eq!(
-        path.derive_tweak(&master_key),
-        Err(ThresholdEcdsaError::InvalidArguments(
-            "Currently key derivation not defined for secp256r1".to_string()
-        ))
+        hex::encode(derived_key.serialize()),
+        "03b3919219b0adb4ebc6ca8f69bbc750c2d2101590b1f79da4c1cfd2356699122c"
     );
 
     Ok(())
@@ -4,7 +4,9 @@ use crate::tls::rustls::node_cert_verifier::NodeServerCertVerifier;
 use crate::tls::rustls::{certified_key, RustlsTlsStream};
 use crate::tls::{tls_cert_from_registry, TlsCertFromRegistryError};
 use ic_crypto_internal_csp::api::CspTlsHandshakeSignerProvider;
-use ic_crypto_tls_interfaces::{SomeOrAllNodes, TlsClientHandshakeError, TlsStream};
+use ic_crypto_tls_interfaces::{
+    SomeOrAllNodes, TlsClientHandshakeError, TlsConfigError, TlsStream,
+};
 use ic_interfaces_registry::RegistryClient;
 use ic_types::{NodeId, RegistryVersion};
 use std::sync::Arc;
@@ -23,6 +25,43 @@ pub async fn perform_tls_client_handshake<P: CspTlsHandshakeSignerProvider>(
     server: NodeId,
     registry_version: RegistryVersion,
 ) -> Result<Box<dyn TlsStream>, TlsClientHandshakeError> {
+    let config = client_config_with_tls13_and_aes_ciphersuites_and_ed25519_signing_key(
+        signer_provider,
+        self_node_id,
+        registry_client,
+        server,
+        registry_version,
+    )?;
+
+    connect(tcp_stream, config).await
+}
+
+pub fn client_config<P: CspTlsHandshakeSignerProvider>(
+    signer_provider: &P,
+    self_node_id: NodeId,
+    registry_client: &Arc<dyn RegistryClient>,
+    server: NodeId,
+    registry_version: RegistryVersion,
+) -> Result<ClientConfig, TlsConfigError> {
+    client_config_with_tls13_and_aes_ciphersuites_and_ed25519_signing_key(
+        signer_provider,
+        self_node_id,
+        registry_client,
+        server,
+        registry_version,
+    )
+    .map_err(TlsConfigError::from)
+}
+
+fn client_config_with_tls13_and_aes_ciphersuites_and_ed25519_signing_key<
+    P: CspTlsHandshakeSignerProvider,
+>(
+    signer_provider: &P,
+    self_node_id: NodeId,
+    registry_client: &Arc<dyn RegistryClient>,
+    server: NodeId,
+    registry_version: RegistryVersion,
+) -> Result<ClientConfig, TlsCertFromRegistryError> {
     let self_tls_cert = tls_cert_from_registry(registry_client, self_node_id, registry_version)?;
     let mut config = ClientConfig::new();
     config.versions = vec![ProtocolVersion::TLSv1_3];
@@ -41,8 +80,7 @@ pub async fn perform_tls_client_handshake<P: CspTlsHandshakeSignerProvider>(
     config
         .dangerous()
         .set_certificate_verifier(Arc::new(server_cert_verifier));
-
-    connect(tcp_stream, config).await
+    Ok(config)
 }
 
 fn static_cert_resolver(key: CertifiedKey, scheme: SignatureScheme) -> Arc<dyn ResolvesClientCert> {
@@ -1,5 +1,5 @@
 use crate::tls::{node_id_from_cert_subject_common_name, tls_cert_from_registry};
-use ic_crypto_tls_interfaces::{SomeOrAllNodes, TlsPublicKeyCert};
+use ic_crypto_tls_interfaces::{RegistryVersionProvider, SomeOrAllNodes, TlsPublicKeyCert};
 use ic_interfaces_registry::RegistryClient;
 use ic_protobuf::registry::crypto::v1::X509PublicKeyCert;
 use ic_types::{NodeId, RegistryVersion};
@@ -64,7 +64,9 @@ impl NodeServerCertVerifier {
 /// * The presented certificate equals the node's certificate fetched from the
 ///   `registry_client` at version `registry_version` for the `NodeId` parsed
 ///   from the presented certificate. (The `registry_client` and
-///   `registry_version` are passed to the constructors.)
+///   `registry_version` are passed to the constructors.) If the registry
+///   version is given by a `RegistryVersionProvider`, it is queried for every
+///   verification.
 ///
 /// If any of these conditions does not hold, a `TLSError` is returned.
 ///
@@ -72,7 +74,7 @@ impl NodeServerCertVerifier {
 pub struct NodeClientCertVerifier {
     allowed_nodes: SomeOrAllNodes,
     registry_client: Arc<dyn RegistryClient>,
-    registry_version: RegistryVersion,
+    registry_version: RegistryVersionProvider,
 }
 
 impl NodeClientCertVerifier {
@@ -85,6 +87,24 @@ impl NodeClientCertVerifier {
         allowed_nodes: SomeOrAllNodes,
         registry_client: Arc<dyn RegistryClient>,
         registry_version: RegistryVersion,
+    ) -> Self {
+        Self::new_with_mandatory_client_auth_and_version_provider(
+            allowed_nodes,
+            registry_client,
+            Arc::new(move || registry_version),
+        )
+    }
+
+    /// Creates a verifier that considers only certificates for the
+    /// `allowed_nodes` fetched from the `registry_client` at the registry
+    /// version returned by `registry_version` at the time of the verification
+    /// as trusted.
+    ///
+    /// Client authentication is mandatory.
+    pub fn new_with_mandatory_client_auth_and_version_provider(
+        allowed_nodes: SomeOrAllNodes,
+        registry_client: Arc<dyn RegistryClient>,
+        registry_version: RegistryVersionProvider,
     ) -> Self {
         Self {
             allowed_nodes,
@@ -139,7 +159,7 @@ impl ClientCertVerifier for NodeClientCertVerifier {
             presented_certs,
             &self.allowed_nodes,
             &self.registry_client,
-            self.registry_version,
+            (self.registry_version)(),
         )
         .map(|_| ClientCertVerified::assertion())
     }
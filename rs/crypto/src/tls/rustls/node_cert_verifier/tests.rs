@@ -11,7 +11,10 @@ use tokio_rustls::rustls::{RootCertStore, ServerCertVerifier};
 
 mod client_cert_verifier_tests {
     use super::*;
+    use ic_types::RegistryVersion;
     use std::collections::BTreeSet;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::Arc;
     use tokio_rustls::rustls::DistinguishedNames;
 
     #[test]
@@ -238,6 +241,33 @@ mod client_cert_verifier_tests {
         );
     }
 
+    #[test]
+    fn should_fetch_certificate_at_registry_version_returned_by_provider_on_each_verification() {
+        let node_1_cert = CertWithPrivateKey::builder()
+            .cn(NODE_1.to_string())
+            .build_ed25519();
+        let registry = TlsRegistry::new();
+        let registry_version = Arc::new(AtomicU64::new(0));
+        let verifier = {
+            let registry_version = Arc::clone(&registry_version);
+            NodeClientCertVerifier::new_with_mandatory_client_auth_and_version_provider(
+                SomeOrAllNodes::All,
+                registry.get(),
+                Arc::new(move || RegistryVersion::from(registry_version.load(Ordering::SeqCst))),
+            )
+        };
+        registry
+            .add_cert(NODE_1, x509_public_key_cert(&node_1_cert.x509()))
+            .update();
+
+        let result = verifier.verify_client_cert(&[Certificate(node_1_cert.cert_der())], None);
+        assert!(result.is_err());
+
+        registry_version.store(REG_V1.get(), Ordering::SeqCst);
+        let result = verifier.verify_client_cert(&[Certificate(node_1_cert.cert_der())], None);
+        assert!(result.is_ok());
+    }
+
     #[test]
     fn should_set_client_auth_to_mandatory_in_new_with_mandatory_client_auth() {
         let verifier = NodeClientCertVerifier::new_with_mandatory_client_auth(
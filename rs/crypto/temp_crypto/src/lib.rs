@@ -10,8 +10,8 @@ use ic_crypto_node_key_generation::{
     generate_idkg_dealing_encryption_keys, generate_node_signing_keys, generate_tls_keys,
 };
 use ic_crypto_tls_interfaces::{
-    AllowedClients, AuthenticatedPeer, TlsClientHandshakeError, TlsHandshake, TlsPublicKeyCert,
-    TlsServerHandshakeError, TlsStream,
+    AllowedClients, AuthenticatedPeer, RegistryVersionProvider, TlsClientHandshakeError, TlsConfig,
+    TlsConfigError, TlsHandshake, TlsPublicKeyCert, TlsServerHandshakeError, TlsStream,
 };
 use ic_interfaces::crypto::{
     BasicSigVerifier, BasicSigVerifierByPublicKey, BasicSigner, CanisterSigVerifier,
@@ -69,6 +69,7 @@ use std::sync::{Arc, RwLock};
 use std::time::Duration;
 use tempfile::TempDir;
 use tokio::net::{TcpStream, UnixListener};
+use tokio_rustls::rustls::{Certificate, ClientConfig, ServerConfig};
 
 /// A crypto component set up in a temporary directory. The directory is
 /// automatically deleted when this component goes out of scope.
@@ -879,6 +880,33 @@ impl<C: CryptoServiceProvider + Send + Sync> TlsHandshake for TempCryptoComponen
     }
 }
 
+impl<C: CryptoServiceProvider + Send + Sync> TlsConfig for TempCryptoComponentGeneric<C> {
+    fn server_config(
+        &self,
+        allowed_clients: AllowedClients,
+        registry_version: RegistryVersionProvider,
+    ) -> Result<ServerConfig, TlsConfigError> {
+        self.crypto_component
+            .server_config(allowed_clients, registry_version)
+    }
+
+    fn client_config(
+        &self,
+        server: NodeId,
+        registry_version: RegistryVersion,
+    ) -> Result<ClientConfig, TlsConfigError> {
+        self.crypto_component
+            .client_config(server, registry_version)
+    }
+
+    fn authenticated_peer(
+        &self,
+        peer_certificates: &[Certificate],
+    ) -> Result<AuthenticatedPeer, TlsServerHandshakeError> {
+        self.crypto_component.authenticated_peer(peer_certificates)
+    }
+}
+
 impl<C: CryptoServiceProvider, T: Signable> BasicSigVerifier<T> for TempCryptoComponentGeneric<C> {
     fn verify_basic_sig(
         &self,
@@ -14,8 +14,10 @@ use std::cmp::Ordering;
 use std::collections::BTreeSet;
 use std::fmt::{Display, Formatter};
 use std::hash::{Hash, Hasher};
+use std::sync::Arc;
 use tokio::io::{AsyncRead, AsyncWrite};
 use tokio::net::TcpStream;
+use tokio_rustls::rustls::{Certificate, ClientConfig, ServerConfig};
 
 #[cfg(test)]
 mod tests;
@@ -390,6 +392,94 @@ pub trait TlsHandshake {
     ) -> Result<Box<dyn TlsStream>, TlsClientHandshakeError>;
 }
 
+/// Implementors provide TLS configurations for protocols that perform the
+/// TLS handshake themselves, such as QUIC, rather than on a TCP stream.
+///
+/// The returned configurations are equivalent to the ones used by the
+/// respective methods of `TlsHandshake`: TLS 1.3 only, the ed25519 node
+/// certificate from the registry, and verification of the peer's certificate
+/// against the registry. Callers must not weaken the returned configurations;
+/// they may only adjust protocol-specific settings such as ALPN.
+pub trait TlsConfig {
+    /// Returns a server configuration that requires client authentication
+    /// and only accepts certificates of nodes in `allowed_clients`.
+    ///
+    /// The node's own certificate is fetched at the registry version returned
+    /// by `registry_version` when the configuration is created. The client
+    /// certificates are fetched at the registry version returned by
+    /// `registry_version` at the time of each handshake, so a long-lived
+    /// configuration keeps up with registry changes.
+    ///
+    /// # Errors
+    /// * TlsConfigError::RegistryError if the registry cannot be accessed.
+    /// * TlsConfigError::CertificateNotInRegistry if the node's own
+    ///   certificate is not found in the registry.
+    /// * TlsConfigError::MalformedSelfCertificate if the node's own
+    ///   certificate is malformed.
+    fn server_config(
+        &self,
+        allowed_clients: AllowedClients,
+        registry_version: RegistryVersionProvider,
+    ) -> Result<ServerConfig, TlsConfigError>;
+
+    /// Returns a client configuration that authenticates with the node's own
+    /// certificate and only accepts the certificate of `server`.
+    ///
+    /// # Errors
+    /// * TlsConfigError::RegistryError if the registry cannot be accessed.
+    /// * TlsConfigError::CertificateNotInRegistry if the node's own
+    ///   certificate is not found in the registry.
+    /// * TlsConfigError::MalformedSelfCertificate if the node's own
+    ///   certificate is malformed.
+    fn client_config(
+        &self,
+        server: NodeId,
+        registry_version: RegistryVersion,
+    ) -> Result<ClientConfig, TlsConfigError>;
+
+    /// Returns the peer that authenticated with `peer_certificates` in a
+    /// handshake that was performed with a configuration returned by
+    /// `server_config`. The certificates are not verified again, since this
+    /// already happened during the handshake.
+    ///
+    /// # Errors
+    /// * TlsServerHandshakeError::HandshakeError if `peer_certificates` does
+    ///   not contain exactly one certificate or the certificate cannot be
+    ///   parsed.
+    /// * TlsServerHandshakeError::MalformedClientCertificate if the node ID
+    ///   cannot be parsed from the certificate's subject common name.
+    fn authenticated_peer(
+        &self,
+        peer_certificates: &[Certificate],
+    ) -> Result<AuthenticatedPeer, TlsServerHandshakeError>;
+}
+
+/// Returns the registry version to use for a TLS handshake. See
+/// `TlsConfig::server_config`.
+pub type RegistryVersionProvider = Arc<dyn Fn() -> RegistryVersion + Send + Sync>;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+/// Errors from creating a TLS configuration. Please refer to the `TlsConfig`
+/// methods for detailed error variant descriptions.
+pub enum TlsConfigError {
+    RegistryError(RegistryClientError),
+    CertificateNotInRegistry {
+        node_id: NodeId,
+        registry_version: RegistryVersion,
+    },
+    MalformedSelfCertificate {
+        internal_error: String,
+    },
+}
+
+impl Display for TlsConfigError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl std::error::Error for TlsConfigError {}
+
 #[derive(Clone, Debug)]
 /// A list of allowed TLS peers, which can be `All` to allow any node to connect.
 pub struct AllowedClients {
@@ -25,7 +25,8 @@ use ic_types::{
     canister_http::MAX_CANISTER_HTTP_RESPONSE_BYTES,
     messages::{Request, Response, SignedIngressContent, MAX_INTER_CANISTER_PAYLOAD_IN_BYTES},
     nominal_cycles::NominalCycles,
-    CanisterId, ComputeAllocation, Cycles, MemoryAllocation, NumBytes, NumInstructions, SubnetId,
+    CanisterId, ComputeAllocation, Cycles, MemoryAllocation, NumBytes, NumInstructions,
+    ResourceSaturation, SubnetId,
 };
 use prometheus::IntCounter;
 use serde::{Deserialize, Serialize};
@@ -513,8 +514,54 @@ impl CyclesAccountManager {
     ) -> Result<(), CanisterOutOfCyclesError> {
         let cycles_amount = self.memory_cost(bytes, duration, subnet_size);
 
+        // Storage is paid from the reserved balance first.
+        let from_reserved_balance = cycles_amount.min(system_state.reserved_balance());
+
         // Can charge all the way to the empty account (zero cycles)
-        self.consume_with_threshold(system_state, cycles_amount, Cycles::zero())
+        self.consume_with_threshold(
+            system_state,
+            cycles_amount - from_reserved_balance,
+            Cycles::zero(),
+        )?;
+        system_state.remove_reserved_cycles(from_reserved_balance);
+        self.observe_consumed_cycles(system_state, from_reserved_balance);
+        Ok(())
+    }
+
+    /// Returns the amount of cycles that a canister allocating
+    /// `allocated_bytes` of new memory has to move to its reserved balance,
+    /// given the current saturation of the subnet memory.
+    ///
+    /// The pricing curve is zero while the subnet memory usage is below the
+    /// threshold and then grows linearly: a byte allocated at usage `u` costs
+    /// its storage fee for a duration of
+    /// `subnet_memory_reservation * (u - threshold) / (capacity - threshold)`.
+    /// The result is the integral of the curve over the allocated bytes.
+    pub fn storage_reservation_cycles(
+        &self,
+        allocated_bytes: NumBytes,
+        subnet_memory_saturation: &ResourceSaturation,
+        subnet_size: usize,
+    ) -> Cycles {
+        let capacity_above_threshold = subnet_memory_saturation.capacity_above_threshold() as u128;
+        if capacity_above_threshold == 0 {
+            return Cycles::zero();
+        }
+        let usage_before = subnet_memory_saturation.usage_above_threshold() as u128;
+        let usage_after = subnet_memory_saturation
+            .add(allocated_bytes.get())
+            .usage_above_threshold() as u128;
+        // The number of bytes that pay for the full reservation duration and
+        // are equivalent to the allocated bytes under the pricing curve.
+        let weighted_bytes = (usage_after
+            .saturating_mul(usage_after)
+            .saturating_sub(usage_before.saturating_mul(usage_before)))
+            / (2 * capacity_above_threshold);
+        self.memory_cost(
+            NumBytes::from(weighted_bytes.min(u64::MAX as u128) as u64),
+            self.config.subnet_memory_reservation,
+            subnet_size,
+        )
     }
 
     /// The cost of using `bytes` worth of memory.
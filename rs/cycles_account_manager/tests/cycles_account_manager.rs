@@ -20,6 +20,7 @@ use ic_types::{
     messages::{extract_effective_canister_id, SignedIngressContent},
     nominal_cycles::NominalCycles,
     CanisterId, ComputeAllocation, Cycles, MemoryAllocation, NumBytes, NumInstructions,
+    ResourceSaturation,
 };
 use prometheus::IntCounter;
 use std::{convert::TryFrom, time::Duration};
@@ -344,6 +345,90 @@ fn canister_charge_for_memory_until_zero_works() {
         .is_err());
 }
 
+#[test]
+fn charge_for_memory_uses_reserved_balance_first() {
+    let subnet_size = SMALL_APP_SUBNET_MAX_SIZE;
+    let mut system_state = SystemStateBuilder::new().build();
+    let cycles_account_manager = CyclesAccountManagerBuilder::new()
+        .with_subnet_type(SubnetType::Application)
+        .build();
+    let bytes = NumBytes::from(1 << 30);
+    let duration = Duration::from_secs(100);
+    let fee = cycles_account_manager.memory_cost(bytes, duration, subnet_size);
+    system_state.reserve_cycles(fee + fee / 2).unwrap();
+    let balance_before = system_state.balance();
+
+    // The first charge is covered entirely by the reserved balance.
+    cycles_account_manager
+        .charge_for_memory(&mut system_state, bytes, duration, subnet_size)
+        .unwrap();
+    assert_eq!(system_state.balance(), balance_before);
+    assert_eq!(system_state.reserved_balance(), fee / 2);
+
+    // The second charge uses up the reserved balance and takes the rest from
+    // the main balance.
+    cycles_account_manager
+        .charge_for_memory(&mut system_state, bytes, duration, subnet_size)
+        .unwrap();
+    assert_eq!(system_state.reserved_balance(), Cycles::zero());
+    assert_eq!(system_state.balance(), balance_before - (fee - fee / 2));
+}
+
+#[test]
+fn storage_reservation_follows_subnet_memory_saturation() {
+    let subnet_size = SMALL_APP_SUBNET_MAX_SIZE;
+    let subnet_type = SubnetType::Application;
+    let config = SubnetConfigs::default()
+        .own_subnet_config(subnet_type)
+        .cycles_account_manager_config;
+    let cycles_account_manager = CyclesAccountManagerBuilder::new()
+        .with_subnet_type(subnet_type)
+        .build();
+    let gib = 1 << 30;
+    let allocated_bytes = NumBytes::from(gib);
+
+    // Nothing is reserved below the threshold.
+    let below_threshold = ResourceSaturation::new(0, 10 * gib, 20 * gib);
+    assert_eq!(
+        cycles_account_manager.storage_reservation_cycles(
+            allocated_bytes,
+            &below_threshold,
+            subnet_size
+        ),
+        Cycles::zero()
+    );
+
+    // At full saturation, the allocated memory is paid for the full
+    // reservation duration.
+    let saturated = ResourceSaturation::new(20 * gib, 10 * gib, 20 * gib);
+    let at_capacity =
+        cycles_account_manager.storage_reservation_cycles(allocated_bytes, &saturated, subnet_size);
+    let full_cost = cycles_account_manager.memory_cost(
+        allocated_bytes,
+        config.subnet_memory_reservation,
+        subnet_size,
+    );
+    assert!(at_capacity >= full_cost);
+
+    // Half-way between the threshold and the capacity, it costs less.
+    let half_saturated = ResourceSaturation::new(15 * gib, 10 * gib, 20 * gib);
+    let half_way = cycles_account_manager.storage_reservation_cycles(
+        allocated_bytes,
+        &half_saturated,
+        subnet_size,
+    );
+    assert!(half_way > Cycles::zero());
+    assert!(half_way < at_capacity);
+
+    // Without any room between the threshold and the capacity, nothing is
+    // reserved.
+    let no_range = ResourceSaturation::new(20 * gib, 20 * gib, 20 * gib);
+    assert_eq!(
+        cycles_account_manager.storage_reservation_cycles(allocated_bytes, &no_range, subnet_size),
+        Cycles::zero()
+    );
+}
+
 #[test]
 fn ingress_induction_cost_valid_subnet_message() {
     let subnet_id = subnet_test_id(0);
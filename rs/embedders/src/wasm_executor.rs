@@ -27,7 +27,7 @@ use ic_sys::{page_bytes_from_ptr, PageBytes, PageIndex, PAGE_SIZE};
 use ic_system_api::{
     system_api_empty::SystemApiEmpty, ExecutionParameters, ModificationTracking, SystemApiImpl,
 };
-use ic_types::{CanisterId, NumBytes, NumInstructions};
+use ic_types::{CanisterId, NumBytes, NumInstructions, MAX_WASM64_MEMORY_IN_BYTES};
 use ic_wasm_types::{BinaryEncodedWasm, CanisterModule};
 use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
@@ -472,6 +472,7 @@ pub fn wasm_execution_error(
                 accessed_pages: 0,
                 dirty_pages: 0,
             },
+            canister_log: Default::default(),
         },
         None,
     )
@@ -634,6 +635,7 @@ pub fn process(
                         accessed_pages: 0,
                         dirty_pages: 0,
                     },
+                    canister_log: Default::default(),
                 },
                 None,
                 Err(system_api),
@@ -675,10 +677,15 @@ pub fn process(
         .store_data_mut()
         .system_api
         .take_execution_result(run_result.as_ref().err());
+    let canister_log = instance.store_data_mut().system_api.take_canister_log();
 
     let wasm_heap_size_after = instance.heap_size();
-    let wasm_heap_limit =
-        NumWasmPages::from(wasmtime_environ::WASM32_MAX_PAGES as usize) - wasm_reserved_pages;
+    let wasm_heap_max_pages = if instance.is_wasm64() {
+        (MAX_WASM64_MEMORY_IN_BYTES / wasmtime_environ::WASM_PAGE_SIZE as u64) as usize
+    } else {
+        wasmtime_environ::WASM32_MAX_PAGES as usize
+    };
+    let wasm_heap_limit = NumWasmPages::from(wasm_heap_max_pages) - wasm_reserved_pages;
 
     if wasm_heap_size_after > wasm_heap_limit {
         wasm_result = Err(HypervisorError::WasmReservedPages);
@@ -733,6 +740,7 @@ pub fn process(
             allocated_bytes,
             allocated_message_bytes,
             instance_stats,
+            canister_log,
         },
         wasm_state_changes,
         Ok(instance),
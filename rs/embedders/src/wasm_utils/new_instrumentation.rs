@@ -99,7 +99,7 @@ use ic_config::flag_status::FlagStatus;
 use ic_replicated_state::NumWasmPages;
 use ic_sys::PAGE_SIZE;
 use ic_types::NumInstructions;
-use ic_types::{methods::WasmMethod, MAX_WASM_MEMORY_IN_BYTES};
+use ic_types::{methods::WasmMethod, MAX_WASM64_MEMORY_IN_BYTES, MAX_WASM_MEMORY_IN_BYTES};
 use ic_wasm_types::{BinaryEncodedWasm, WasmError, WasmInstrumentationError};
 use wasmtime_environ::WASM_PAGE_SIZE;
 
@@ -158,6 +158,19 @@ const CANISTER_START_STR: &str = "canister_start";
 const BYTEMAP_SIZE_IN_WASM_PAGES: u64 =
     MAX_WASM_MEMORY_IN_BYTES / (PAGE_SIZE as u64) / (WASM_PAGE_SIZE as u64);
 
+/// The size of the bytemap for a 64-bit wasm heap.
+const WASM64_BYTEMAP_SIZE_IN_WASM_PAGES: u64 =
+    MAX_WASM64_MEMORY_IN_BYTES / (PAGE_SIZE as u64) / (WASM_PAGE_SIZE as u64);
+
+/// Returns the value type of heap addresses and sizes.
+fn address_type(is_wasm64: bool) -> ValType {
+    if is_wasm64 {
+        ValType::I64
+    } else {
+        ValType::I32
+    }
+}
+
 fn add_type(module: &mut Module, ty: Type) -> u32 {
     let Type::Func(sig) = &ty;
     for (idx, Type::Func(msig)) in module.types.iter().enumerate() {
@@ -169,10 +182,12 @@ fn add_type(module: &mut Module, ty: Type) -> u32 {
     (module.types.len() - 1) as u32
 }
 
-fn inject_helper_functions(mut module: Module) -> Module {
+fn inject_helper_functions(mut module: Module, is_wasm64: bool) -> Module {
     // insert types
     let ooi_type = Type::Func(FuncType::new([], []));
-    let uam_type = Type::Func(FuncType::new([ValType::I32, ValType::I32], [ValType::I32]));
+    // The argument and result of `memory.grow` are `i64` for 64-bit memories.
+    let address_type = address_type(is_wasm64);
+    let uam_type = Type::Func(FuncType::new([address_type, address_type], [address_type]));
 
     let ooi_type_idx = add_type(&mut module, ooi_type);
     let uam_type_idx = add_type(&mut module, uam_type);
@@ -236,6 +251,9 @@ fn inject_helper_functions(mut module: Module) -> Module {
 pub struct ExportModuleData {
     pub instructions_counter_ix: u32,
     pub decr_instruction_counter_fn: u32,
+    /// The function decrementing the instruction counter by an `i64` amount.
+    /// Only present in 64-bit modules where bulk memory sizes are `i64`.
+    pub decr_instruction_counter_i64_fn: Option<u32>,
     pub start_fn_ix: Option<u32>,
 }
 
@@ -249,9 +267,10 @@ pub(super) fn instrument(
     cost_to_compile_wasm_instruction: NumInstructions,
     write_barrier: FlagStatus,
 ) -> Result<InstrumentationOutput, WasmInstrumentationError> {
-    let mut module = inject_helper_functions(module);
+    let is_wasm64 = super::new_validation::is_wasm64(&module);
+    let mut module = inject_helper_functions(module, is_wasm64);
     module = export_table(module);
-    module = export_memory(module, write_barrier);
+    module = export_memory(module, write_barrier, is_wasm64);
 
     let mut extra_strs: Vec<String> = Vec::new();
     module = export_mutable_globals(module, &mut extra_strs);
@@ -276,6 +295,11 @@ pub(super) fn instrument(
     let export_module_data = ExportModuleData {
         instructions_counter_ix: num_globals,
         decr_instruction_counter_fn: num_functions,
+        decr_instruction_counter_i64_fn: if is_wasm64 {
+            Some(num_functions + 1)
+        } else {
+            None
+        },
         start_fn_ix: module.start,
     };
 
@@ -305,7 +329,7 @@ pub(super) fn instrument(
     if !func_types.is_empty() {
         let func_bodies = &mut module.code_sections;
         for (func_ix, func_type) in func_types.into_iter().enumerate() {
-            inject_update_available_memory(&mut func_bodies[func_ix], &func_type);
+            inject_update_available_memory(&mut func_bodies[func_ix], &func_type, is_wasm64);
         }
     }
 
@@ -413,6 +437,60 @@ pub fn export_additional_symbols<'a>(
     module.functions.push(type_idx);
     module.code_sections.push(func_body);
 
+    if let Some(decr_instruction_counter_i64_fn) =
+        export_module_data.decr_instruction_counter_i64_fn
+    {
+        debug_assert_eq!(
+            decr_instruction_counter_i64_fn,
+            export_module_data.decr_instruction_counter_fn + 1
+        );
+        // The amount is capped at the maximum size of a 64-bit memory, so that
+        // a size with the highest bit set does not increase the counter. Bulk
+        // memory operations with such sizes trap anyway.
+        let func_type = Type::Func(FuncType::new([ValType::I64], [ValType::I64]));
+        let instructions = vec![
+            GlobalGet {
+                global_index: export_module_data.instructions_counter_ix,
+            },
+            I64Const {
+                value: MAX_WASM64_MEMORY_IN_BYTES as i64,
+            },
+            LocalGet { local_index: 0 },
+            LocalGet { local_index: 0 },
+            I64Const {
+                value: MAX_WASM64_MEMORY_IN_BYTES as i64,
+            },
+            I64GtU,
+            Select,
+            I64Sub,
+            GlobalSet {
+                global_index: export_module_data.instructions_counter_ix,
+            },
+            // Call out_of_instructions() if `counter < 0`.
+            GlobalGet {
+                global_index: export_module_data.instructions_counter_ix,
+            },
+            I64Const { value: 0 },
+            I64LtS,
+            If {
+                blockty: BlockType::Empty,
+            },
+            Call {
+                function_index: InjectedImports::OutOfInstructionsFn as u32,
+            },
+            End,
+            // Return the original param so this function doesn't alter the stack
+            LocalGet { local_index: 0 },
+            End,
+        ];
+        let type_idx = add_type(&mut module, func_type);
+        module.functions.push(type_idx);
+        module.code_sections.push(wasm_transform::Body {
+            locals: vec![],
+            instructions,
+        });
+    }
+
     // globals must be exported to be accessible to hypervisor or persisted
     let counter_export = Export {
         name: CANISTER_COUNTER_INSTRUCTIONS_STR,
@@ -465,10 +543,12 @@ enum Scope {
 // `StaticCost` injection points contain information about the cost of the
 // following basic block. `DynamicCost` injection points assume there is an i32
 // on the stack which should be decremented from the instruction counter.
+// `DynamicCost64` injection points are the same but with an i64 on the stack.
 #[derive(Copy, Clone, Debug, PartialEq)]
 enum InjectionPointCostDetail {
     StaticCost { scope: Scope, cost: u64 },
     DynamicCost,
+    DynamicCost64,
 }
 
 impl InjectionPointCostDetail {
@@ -477,7 +557,7 @@ impl InjectionPointCostDetail {
     fn increment_cost(&mut self, additonal_cost: u64) {
         match self {
             Self::StaticCost { scope: _, cost } => *cost += additonal_cost,
-            Self::DynamicCost => {}
+            Self::DynamicCost | Self::DynamicCost64 => {}
         }
     }
 }
@@ -503,6 +583,13 @@ impl InjectionPoint {
             position,
         }
     }
+
+    fn new_dynamic_cost_64(position: usize) -> Self {
+        InjectionPoint {
+            cost_detail: InjectionPointCostDetail::DynamicCost64,
+            position,
+        }
+    }
 }
 
 // This function iterates over the injection points, and inserts three different
@@ -515,14 +602,15 @@ impl InjectionPoint {
 //   performs an overflow check and then decrements the counter by the value at
 //   the top of the stack.
 fn inject_metering(code: &mut Vec<Operator>, export_data_module: &ExportModuleData) {
-    let points = injections(code);
+    let is_wasm64 = export_data_module.decr_instruction_counter_i64_fn.is_some();
+    let points = injections(code, is_wasm64);
     let points = points.iter().filter(|point| match point.cost_detail {
         InjectionPointCostDetail::StaticCost {
             scope: Scope::ReentrantBlockStart,
             cost: _,
         } => true,
         InjectionPointCostDetail::StaticCost { scope: _, cost } => cost > 0,
-        InjectionPointCostDetail::DynamicCost => true,
+        InjectionPointCostDetail::DynamicCost | InjectionPointCostDetail::DynamicCost64 => true,
     });
     let orig_elems = code;
     let mut elems: Vec<Operator> = Vec::new();
@@ -566,6 +654,13 @@ fn inject_metering(code: &mut Vec<Operator>, export_data_module: &ExportModuleDa
                     function_index: export_data_module.decr_instruction_counter_fn,
                 }]);
             }
+            InjectionPointCostDetail::DynamicCost64 => {
+                elems.extend_from_slice(&[Call {
+                    function_index: export_data_module
+                        .decr_instruction_counter_i64_fn
+                        .expect("64-bit modules have an i64 decrement function"),
+                }]);
+            }
         }
         last_injection_position = point.position;
     }
@@ -577,7 +672,11 @@ fn inject_metering(code: &mut Vec<Operator>, export_data_module: &ExportModuleDa
 // instruction to make sure that there's enough available memory left to support
 // the requested extra memory. If no `memory.grow` instructions are present then
 // the function's code remains unchanged.
-fn inject_update_available_memory(func_body: &mut wasm_transform::Body, func_type: &FuncType) {
+fn inject_update_available_memory(
+    func_body: &mut wasm_transform::Body,
+    func_type: &FuncType,
+    is_wasm64: bool,
+) {
     use Operator::*;
     let mut injection_points: Vec<usize> = Vec::new();
     {
@@ -597,7 +696,7 @@ fn inject_update_available_memory(func_body: &mut wasm_transform::Body, func_typ
         // the total number of locals.
         let n_locals: u32 = func_body.locals.iter().map(|x| x.0).sum();
         let memory_local_ix = func_type.params().len() as u32 + n_locals;
-        func_body.locals.push((1, ValType::I32));
+        func_body.locals.push((1, address_type(is_wasm64)));
 
         let orig_elems = &func_body.instructions;
         let mut elems: Vec<Operator> = Vec::new();
@@ -632,7 +731,10 @@ fn inject_update_available_memory(func_body: &mut wasm_transform::Body, func_typ
 // with no branches) and before each bulk memory instruction. An injection point
 // contains a "hint" about the context of every basic block, specifically if
 // it's re-entrant or not.
-fn injections(code: &[Operator]) -> Vec<InjectionPoint> {
+//
+// In 64-bit modules the sizes of `memory.fill` and `memory.copy` are i64,
+// while `memory.init` and the table instructions still take i32 sizes.
+fn injections(code: &[Operator], is_wasm64: bool) -> Vec<InjectionPoint> {
     let mut res = Vec::new();
     let mut stack = Vec::new();
     use Operator::*;
@@ -667,6 +769,9 @@ fn injections(code: &[Operator]) -> Vec<InjectionPoint> {
             }
             // Bulk memory instructions require injected metering __before__ the instruction
             // executes so that size arguments can be read from the stack at runtime.
+            MemoryFill { .. } | MemoryCopy { .. } if is_wasm64 => {
+                res.push(InjectionPoint::new_dynamic_cost_64(position));
+            }
             MemoryFill { .. }
             | MemoryCopy { .. }
             | MemoryInit { .. }
@@ -697,6 +802,7 @@ fn get_data(
                     offset_expr,
                 } => match offset_expr {
                     Operator::I32Const { value } => *value as usize,
+                    Operator::I64Const { value } => *value as u64 as usize,
                     _ => return Err(WasmInstrumentationError::WasmDeserializeError(WasmError::new(
                         "complex initialization expressions for data segments are not supported!".into()
                     ))),
@@ -736,7 +842,7 @@ fn export_table(mut module: Module) -> Module {
     module
 }
 
-fn export_memory(mut module: Module, write_barrier: FlagStatus) -> Module {
+fn export_memory(mut module: Module, write_barrier: FlagStatus, is_wasm64: bool) -> Module {
     let mut memory_already_exported = false;
     for export in &mut module.exports {
         if let ExternalKind::Memory = export.kind {
@@ -755,11 +861,18 @@ fn export_memory(mut module: Module, write_barrier: FlagStatus) -> Module {
     }
 
     if write_barrier == FlagStatus::Enabled && !module.memories.is_empty() {
+        let bytemap_size_in_wasm_pages = if is_wasm64 {
+            WASM64_BYTEMAP_SIZE_IN_WASM_PAGES
+        } else {
+            BYTEMAP_SIZE_IN_WASM_PAGES
+        };
+        // The bytemap is accessed only by the runtime, so it remains a 32-bit
+        // memory even for 64-bit modules.
         module.memories.push(MemoryType {
             memory64: false,
             shared: false,
-            initial: BYTEMAP_SIZE_IN_WASM_PAGES,
-            maximum: Some(BYTEMAP_SIZE_IN_WASM_PAGES),
+            initial: bytemap_size_in_wasm_pages,
+            maximum: Some(bytemap_size_in_wasm_pages),
         });
 
         module.exports.push(Export {
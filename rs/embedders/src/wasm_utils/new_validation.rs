@@ -3,11 +3,11 @@
 
 use super::{WasmImportsDetails, WasmValidationDetails};
 
-use ic_config::embedders::Config as EmbeddersConfig;
+use ic_config::{embedders::Config as EmbeddersConfig, flag_status::FlagStatus};
 use ic_replicated_state::canister_state::execution_state::{
     CustomSection, CustomSectionType, WasmMetadata,
 };
-use ic_types::{NumBytes, NumInstructions};
+use ic_types::{NumBytes, NumInstructions, MAX_WASM64_MEMORY_IN_BYTES};
 use ic_wasm_types::{BinaryEncodedWasm, WasmValidationError};
 use std::{
     cmp,
@@ -20,6 +20,7 @@ use crate::{
     wasmtime_embedder::WASM_HEAP_MEMORY_NAME,
 };
 use wasmparser::{ExternalKind, Operator, Type, TypeRef, ValType};
+use wasmtime_environ::WASM_PAGE_SIZE;
 
 /// Symbols that are reserved and cannot be exported by canisters.
 #[doc(hidden)] // pub for usage in tests
@@ -46,7 +47,10 @@ const API_VERSION_IC0: &str = "ic0";
 // user tries to import a function that doesn't exist in any of the expected
 // modules vs the case where the function exists but is imported from the wrong
 // module.
-fn get_valid_system_apis() -> HashMap<String, HashMap<String, FunctionSignature>> {
+//
+// For 64-bit modules the signatures are adjusted by
+// `wasm64_system_api_signature`.
+fn get_valid_system_apis(is_wasm64: bool) -> HashMap<String, HashMap<String, FunctionSignature>> {
     let valid_system_apis = vec![
         (
             // Public methods
@@ -290,6 +294,16 @@ fn get_valid_system_apis() -> HashMap<String, HashMap<String, FunctionSignature>
                 },
             )],
         ),
+        (
+            "call_with_best_effort_response",
+            vec![(
+                API_VERSION_IC0,
+                FunctionSignature {
+                    param_types: vec![ValType::I32],
+                    return_type: vec![],
+                },
+            )],
+        ),
         (
             "call_cycles_add",
             vec![(
@@ -441,6 +455,26 @@ fn get_valid_system_apis() -> HashMap<String, HashMap<String, FunctionSignature>
                 },
             )],
         ),
+        (
+            "is_controller",
+            vec![(
+                API_VERSION_IC0,
+                FunctionSignature {
+                    param_types: vec![ValType::I32, ValType::I32],
+                    return_type: vec![ValType::I32],
+                },
+            )],
+        ),
+        (
+            "in_replicated_execution",
+            vec![(
+                API_VERSION_IC0,
+                FunctionSignature {
+                    param_types: vec![],
+                    return_type: vec![ValType::I32],
+                },
+            )],
+        ),
         (
             "trap",
             vec![(
@@ -605,18 +639,89 @@ fn get_valid_system_apis() -> HashMap<String, HashMap<String, FunctionSignature>
 
     valid_system_apis
         .into_iter()
-        .map(|(func_name, signatures)| {
-            (
-                func_name.to_string(),
-                signatures
-                    .into_iter()
-                    .map(|(module, signature)| (module.to_string(), signature))
-                    .collect(),
-            )
+        .filter_map(|(func_name, signatures)| {
+            let signatures: HashMap<String, FunctionSignature> = signatures
+                .into_iter()
+                .filter_map(|(module, signature)| {
+                    let signature = if is_wasm64 && module == API_VERSION_IC0 {
+                        wasm64_system_api_signature(func_name, signature)?
+                    } else {
+                        signature
+                    };
+                    Some((module.to_string(), signature))
+                })
+                .collect();
+            if signatures.is_empty() {
+                None
+            } else {
+                Some((func_name.to_string(), signatures))
+            }
         })
         .collect()
 }
 
+// Returns the signature of the given `ic0` System API for 64-bit modules, or
+// `None` if the System API is not available to them.
+//
+// 64-bit modules pass heap addresses and sizes as `i64` instead of `i32`. The
+// deprecated `call_simple` and the 32-bit stable memory functions that write
+// to or read from the heap are not available to 64-bit modules.
+fn wasm64_system_api_signature(
+    func_name: &str,
+    signature: FunctionSignature,
+) -> Option<FunctionSignature> {
+    let pointers = |n| FunctionSignature {
+        param_types: vec![ValType::I64; n],
+        return_type: vec![],
+    };
+    match func_name {
+        "call_simple" | "stable_read" | "stable_write" => None,
+        "msg_caller_size"
+        | "msg_arg_data_size"
+        | "msg_method_name_size"
+        | "msg_reject_msg_size"
+        | "canister_self_size"
+        | "controller_size"
+        | "data_certificate_size" => Some(FunctionSignature {
+            param_types: vec![],
+            return_type: vec![ValType::I64],
+        }),
+        "msg_caller_copy"
+        | "msg_arg_data_copy"
+        | "msg_method_name_copy"
+        | "msg_reject_msg_copy"
+        | "canister_self_copy"
+        | "controller_copy"
+        | "data_certificate_copy" => Some(pointers(3)),
+        "msg_reply_data_append"
+        | "msg_reject"
+        | "call_data_append"
+        | "call_on_cleanup"
+        | "debug_print"
+        | "trap"
+        | "certified_data_set" => Some(pointers(2)),
+        "call_new" => Some(pointers(8)),
+        "canister_cycle_balance128" | "msg_cycles_available128" | "msg_cycles_refunded128" => {
+            Some(pointers(1))
+        }
+        "msg_cycles_accept128" => Some(pointers(3)),
+        "is_controller" => Some(FunctionSignature {
+            param_types: vec![ValType::I64, ValType::I64],
+            return_type: vec![ValType::I32],
+        }),
+        _ => Some(signature),
+    }
+}
+
+/// Returns true if the module defines or imports a 64-bit memory.
+pub(super) fn is_wasm64(module: &Module) -> bool {
+    module.memories.iter().any(|memory| memory.memory64)
+        || module
+            .imports
+            .iter()
+            .any(|import| matches!(import.ty, TypeRef::Memory(memory) if memory.memory64))
+}
+
 // Constructs a map of function name -> `FunctionSignature` based on the
 // special user exported functions allowed in the interface spec.
 fn get_valid_exported_functions() -> HashMap<String, FunctionSignature> {
@@ -749,7 +854,7 @@ fn validate_import_section(module: &Module) -> Result<WasmImportsDetails, WasmVa
     let mut imports_details = WasmImportsDetails::default();
 
     if !module.imports.is_empty() {
-        let valid_system_apis = get_valid_system_apis();
+        let valid_system_apis = get_valid_system_apis(is_wasm64(module));
         for entry in &module.imports {
             let import_module = entry.module;
             let field = entry.name;
@@ -908,7 +1013,9 @@ fn validate_data_section(module: &Module) -> Result<(), WasmValidationError> {
                 memory_index: _,
                 offset_expr,
             } => match offset_expr {
-                Operator::I32Const { .. } => Ok(()),
+                // The type of the offset is checked against the memory type
+                // by the Wasmtime validation.
+                Operator::I32Const { .. } | Operator::I64Const { .. } => Ok(()),
                 _ => Err(WasmValidationError::InvalidDataSection(format!(
                     "Invalid offset expression in data segment: {:?}",
                     offset_expr
@@ -923,6 +1030,32 @@ fn validate_data_section(module: &Module) -> Result<(), WasmValidationError> {
     Ok(())
 }
 
+// Checks that 64-bit memories are enabled if the module uses them and that
+// their initial size does not exceed the maximum size of a 64-bit memory.
+fn validate_memory_section(
+    module: &Module,
+    config: &EmbeddersConfig,
+) -> Result<(), WasmValidationError> {
+    if !is_wasm64(module) {
+        return Ok(());
+    }
+    if config.feature_flags.wasm64 == FlagStatus::Disabled {
+        return Err(WasmValidationError::InvalidMemorySection(
+            "64-bit memories are not supported.".to_string(),
+        ));
+    }
+    let max_pages = MAX_WASM64_MEMORY_IN_BYTES / WASM_PAGE_SIZE as u64;
+    for memory in &module.memories {
+        if memory.initial > max_pages {
+            return Err(WasmValidationError::InvalidMemorySection(format!(
+                "Initial size of {} Wasm pages exceeds the maximum of {} Wasm pages.",
+                memory.initial, max_pages
+            )));
+        }
+    }
+    Ok(())
+}
+
 // Checks that no more than `max_globals` are defined in the module.
 fn validate_global_section(module: &Module, max_globals: usize) -> Result<(), WasmValidationError> {
     if module.globals.len() > max_globals {
@@ -1147,9 +1280,15 @@ pub fn ensure_determinism(config: &mut Config) {
         .cranelift_nan_canonicalization(true);
 }
 
-fn can_compile(wasm: &BinaryEncodedWasm) -> Result<(), WasmValidationError> {
+fn can_compile(
+    wasm: &BinaryEncodedWasm,
+    embedders_config: &EmbeddersConfig,
+) -> Result<(), WasmValidationError> {
     let mut config = wasmtime::Config::default();
     ensure_determinism(&mut config);
+    if embedders_config.feature_flags.wasm64 == FlagStatus::Enabled {
+        config.wasm_memory64(true);
+    }
     let engine = wasmtime::Engine::new(&config).map_err(|_| {
         WasmValidationError::WasmtimeValidation(String::from("Failed to initialize Wasm engine"))
     })?;
@@ -1171,6 +1310,7 @@ fn can_compile(wasm: &BinaryEncodedWasm) -> Result<(), WasmValidationError> {
 /// * Export
 /// * Code
 /// * Data
+/// * Memory
 /// * Global
 /// * Function
 /// * CustomSections
@@ -1180,9 +1320,10 @@ pub(super) fn validate_wasm_binary<'a>(
     wasm: &'a BinaryEncodedWasm,
     config: &EmbeddersConfig,
 ) -> Result<(WasmValidationDetails, Module<'a>), WasmValidationError> {
-    can_compile(wasm)?;
+    can_compile(wasm, config)?;
     let module = Module::parse(wasm.as_slice(), false)
         .map_err(|err| WasmValidationError::DecodingError(format!("{}", err)))?;
+    validate_memory_section(&module, config)?;
     let imports_details = validate_import_section(&module)?;
     let reserved_exports = validate_export_section(&module)?;
     validate_data_section(&module)?;
@@ -289,6 +289,16 @@ fn get_valid_system_apis() -> HashMap<String, HashMap<String, FunctionSignature>
                 },
             )],
         ),
+        (
+            "call_with_best_effort_response",
+            vec![(
+                API_VERSION_IC0,
+                FunctionSignature {
+                    param_types: vec![ValueType::I32],
+                    return_type: vec![],
+                },
+            )],
+        ),
         (
             "call_cycles_add",
             vec![(
@@ -440,6 +450,26 @@ fn get_valid_system_apis() -> HashMap<String, HashMap<String, FunctionSignature>
                 },
             )],
         ),
+        (
+            "is_controller",
+            vec![(
+                API_VERSION_IC0,
+                FunctionSignature {
+                    param_types: vec![ValueType::I32, ValueType::I32],
+                    return_type: vec![ValueType::I32],
+                },
+            )],
+        ),
+        (
+            "in_replicated_execution",
+            vec![(
+                API_VERSION_IC0,
+                FunctionSignature {
+                    param_types: vec![],
+                    return_type: vec![ValueType::I32],
+                },
+            )],
+        ),
         (
             "trap",
             vec![(
@@ -133,6 +133,9 @@ impl WasmtimeEmbedder {
         if embedder_config.feature_flags.write_barrier == FlagStatus::Enabled {
             config.wasm_multi_memory(true);
         }
+        if embedder_config.feature_flags.wasm64 == FlagStatus::Enabled {
+            config.wasm_memory64(true);
+        }
         config
             // maximum size in bytes where a linear memory is considered
             // static. setting this to maximum Wasm memory size will guarantee
@@ -214,12 +217,18 @@ impl WasmtimeEmbedder {
             },
         );
 
+        let is_wasm64 = module
+            .get_export(WASM_HEAP_MEMORY_NAME)
+            .and_then(|export| export.memory().map(|memory| memory.is_64()))
+            .unwrap_or(false);
+
         let linker = system_api::syscalls(
             self.log.clone(),
             canister_id,
             &store,
             self.config.feature_flags.rate_limiting_of_debug_prints,
             self.config.stable_memory_dirty_page_limit,
+            is_wasm64,
         );
 
         let instance = match linker.instantiate(&mut store, module) {
@@ -401,6 +410,7 @@ impl WasmtimeEmbedder {
 
         Ok(WasmtimeInstance {
             instance,
+            is_wasm64,
             memory_trackers,
             signal_stack,
             log: self.log.clone(),
@@ -499,6 +509,7 @@ pub struct PageAccessResults {
 /// Encapsulates a Wasmtime instance on the Internet Computer.
 pub struct WasmtimeInstance<S: SystemApi> {
     instance: wasmtime::Instance,
+    is_wasm64: bool,
     memory_trackers: HashMap<CanisterMemoryType, Arc<Mutex<SigsegvMemoryTracker>>>,
     signal_stack: WasmtimeSignalStack,
     log: ReplicaLogger,
@@ -519,6 +530,11 @@ impl<S: SystemApi> WasmtimeInstance<S> {
         self.store.data()
     }
 
+    /// Returns true if the Wasm heap of the instance is a 64-bit memory.
+    pub fn is_wasm64(&self) -> bool {
+        self.is_wasm64
+    }
+
     fn invoke_export(&mut self, export: &str, args: &[Val]) -> HypervisorResult<()> {
         self.instance
             .get_export(&mut self.store, export)
@@ -585,6 +601,7 @@ impl<S: SystemApi> WasmtimeInstance<S> {
     /// consists of one of the prefixes and method_name.
     pub fn run(&mut self, func_ref: FuncRef) -> HypervisorResult<InstanceRunResult> {
         let _alt_sig_stack = unsafe { self.signal_stack.register() };
+        let is_wasm64 = self.is_wasm64;
 
         let result = match &func_ref {
             FuncRef::Method(wasm_method) => self.invoke_export(&wasm_method.to_string(), &[]),
@@ -607,7 +624,15 @@ impl<S: SystemApi> WasmtimeInstance<S> {
                         "unexpected null function reference".to_string(),
                     )
                 })?
-                .call(&mut self.store, &[Val::I32(closure.env as i32)], &mut [])
+                .call(
+                    &mut self.store,
+                    &[if is_wasm64 {
+                        Val::I64(closure.env as i64)
+                    } else {
+                        Val::I32(closure.env as i32)
+                    }],
+                    &mut [],
+                )
                 .map_err(wasmtime_error_to_hypervisor_error),
         }
         .map_err(|e| {
@@ -680,7 +705,8 @@ impl<S: SystemApi> WasmtimeInstance<S> {
     }
 
     /// Returns the heap size.
-    /// Result is guaranteed to fit in a `u32`.
+    /// Result is guaranteed to fit in a `u32` (also for 64-bit memories which
+    /// are limited to `MAX_WASM64_MEMORY_IN_BYTES`).
     pub fn heap_size(&mut self) -> NumWasmPages {
         NumWasmPages::from(self.memory().map_or(0, |mem| mem.size(&self.store)) as usize)
     }
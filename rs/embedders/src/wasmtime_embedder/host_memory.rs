@@ -1,4 +1,5 @@
 use anyhow::bail;
+use ic_types::MAX_WASM64_MEMORY_IN_BYTES;
 use wasmtime::MemoryType;
 use wasmtime_environ::{WASM32_MAX_PAGES, WASM_PAGE_SIZE};
 
@@ -28,8 +29,13 @@ fn round_up_to_os_page_size(size: usize) -> usize {
     round_up_to_page_size(size, PAGE_SIZE)
 }
 
-fn wasm_max_mem_size_in_bytes() -> usize {
-    WASM32_MAX_PAGES as usize * WASM_PAGE_SIZE as usize
+/// The maximum number of pages of a memory of the given type.
+fn wasm_max_pages(ty: &MemoryType) -> u64 {
+    if ty.is_64() {
+        MAX_WASM64_MEMORY_IN_BYTES / WASM_PAGE_SIZE as u64
+    } else {
+        WASM32_MAX_PAGES
+    }
 }
 
 #[derive(Hash, PartialEq, Eq)]
@@ -68,11 +74,14 @@ unsafe impl wasmtime::MemoryCreator for WasmtimeMemoryCreator {
         // and has asserts for that in its Memory implementation
         // but let's just clip to that without panicking in case they change
         // something...
-        let min = std::cmp::min(ty.minimum(), WASM32_MAX_PAGES) as usize;
-        let max =
-            std::cmp::min(ty.maximum().unwrap_or(WASM32_MAX_PAGES), WASM32_MAX_PAGES) as usize;
-
-        let mem_size = reserved_size_in_bytes.unwrap_or_else(wasm_max_mem_size_in_bytes);
+        // 64-bit memories are clipped to the maximum size supported by the
+        // replica.
+        let max_pages = wasm_max_pages(&ty);
+        let min = std::cmp::min(ty.minimum(), max_pages) as usize;
+        let max = std::cmp::min(ty.maximum().unwrap_or(max_pages), max_pages) as usize;
+
+        let mem_size =
+            reserved_size_in_bytes.unwrap_or_else(|| max_pages as usize * WASM_PAGE_SIZE as usize);
 
         let mem = MmapMemory::new(mem_size, guard_size);
 
@@ -3,6 +3,7 @@ use crate::wasmtime_embedder::{system_api_complexity, StoreData, WASM_HEAP_MEMOR
 use ic_config::flag_status::FlagStatus;
 use ic_interfaces::execution_environment::{
     ExecutionComplexity, HypervisorError, HypervisorResult, PerformanceCounterType, SystemApi,
+    TrapCode,
 };
 use ic_logger::{error, ReplicaLogger};
 use ic_registry_subnet_type::SubnetType;
@@ -11,7 +12,8 @@ use ic_types::{CanisterId, Cycles, NumBytes, NumInstructions, NumPages, Time};
 
 use wasmtime::{AsContextMut, Caller, Global, Linker, Store, Val};
 
-use std::convert::TryFrom;
+use std::convert::{TryFrom, TryInto};
+use std::ops::Range;
 
 fn process_err<S: SystemApi>(
     store: &mut impl AsContextMut<Data = StoreData<S>>,
@@ -324,46 +326,43 @@ fn ic0_performance_counter_helper<S: SystemApi>(
     }
 }
 
+fn with_system_api<S, T>(caller: &mut Caller<'_, StoreData<S>>, f: impl Fn(&mut S) -> T) -> T {
+    f(&mut caller.as_context_mut().data_mut().system_api)
+}
+
+fn with_memory_and_system_api<S: SystemApi, T>(
+    mut caller: &mut Caller<'_, StoreData<S>>,
+    f: impl Fn(&mut S, &mut [u8]) -> HypervisorResult<T>,
+) -> Result<T, anyhow::Error> {
+    let result = caller
+        .get_export(WASM_HEAP_MEMORY_NAME)
+        .ok_or_else(|| {
+            HypervisorError::ContractViolation("WebAssembly module must define memory".to_string())
+        })
+        .and_then(|ext| {
+            ext.into_memory().ok_or_else(|| {
+                HypervisorError::ContractViolation("export 'memory' is not a memory".to_string())
+            })
+        })
+        .and_then(|mem| {
+            let (mem, store) = mem.data_and_store_mut(&mut caller);
+            f(&mut store.system_api, mem)
+        });
+
+    match result {
+        Err(e) => Err(process_err(caller, e)),
+        Ok(r) => Ok(r),
+    }
+}
+
 pub(crate) fn syscalls<S: SystemApi>(
     log: ReplicaLogger,
     canister_id: CanisterId,
     store: &Store<StoreData<S>>,
     rate_limiting_of_debug_prints: FlagStatus,
     stable_memory_dirty_page_limit: NumPages,
+    is_wasm64: bool,
 ) -> Linker<StoreData<S>> {
-    fn with_system_api<S, T>(caller: &mut Caller<'_, StoreData<S>>, f: impl Fn(&mut S) -> T) -> T {
-        f(&mut caller.as_context_mut().data_mut().system_api)
-    }
-
-    fn with_memory_and_system_api<S: SystemApi, T>(
-        mut caller: &mut Caller<'_, StoreData<S>>,
-        f: impl Fn(&mut S, &mut [u8]) -> HypervisorResult<T>,
-    ) -> Result<T, anyhow::Error> {
-        let result = caller
-            .get_export(WASM_HEAP_MEMORY_NAME)
-            .ok_or_else(|| {
-                HypervisorError::ContractViolation(
-                    "WebAssembly module must define memory".to_string(),
-                )
-            })
-            .and_then(|ext| {
-                ext.into_memory().ok_or_else(|| {
-                    HypervisorError::ContractViolation(
-                        "export 'memory' is not a memory".to_string(),
-                    )
-                })
-            })
-            .and_then(|mem| {
-                let (mem, store) = mem.data_and_store_mut(&mut caller);
-                f(&mut store.system_api, mem)
-            });
-
-        match result {
-            Err(e) => Err(process_err(caller, e)),
-            Ok(r) => Ok(r),
-        }
-    }
-
     let mut linker = Linker::new(store.engine());
 
     linker
@@ -692,6 +691,12 @@ pub(crate) fn syscalls<S: SystemApi>(
                     NumInstructions::from(0),
                     stable_memory_dirty_page_limit,
                 )?;
+                // The message is always kept in the canister log, even if
+                // printing it is rate limited below.
+                with_memory_and_system_api(&mut caller, |system_api, memory| {
+                    system_api.save_log_message(offset as u32, length as u32, memory);
+                    Ok(())
+                })?;
                 match (
                     caller.data().system_api.subnet_type(),
                     rate_limiting_of_debug_prints,
@@ -855,6 +860,17 @@ pub(crate) fn syscalls<S: SystemApi>(
         })
         .unwrap();
 
+    linker
+        .func_wrap("ic0", "call_with_best_effort_response", {
+            move |mut caller: Caller<'_, StoreData<S>>, timeout_seconds: i32| {
+                with_system_api(&mut caller, |s| {
+                    s.ic0_call_with_best_effort_response(timeout_seconds as u32)
+                })
+                .map_err(|e| process_err(&mut caller, e))
+            }
+        })
+        .unwrap();
+
     linker
         .func_wrap("ic0", "call_cycles_add", {
             move |mut caller: Caller<'_, StoreData<S>>, amount: i64| {
@@ -1127,6 +1143,36 @@ pub(crate) fn syscalls<S: SystemApi>(
         })
         .unwrap();
 
+    linker
+        .func_wrap("ic0", "is_controller", {
+            let log = log.clone();
+            move |mut caller: Caller<'_, StoreData<S>>, src: u32, size: u32| {
+                observe_execution_complexity(
+                    &log,
+                    canister_id,
+                    &mut caller,
+                    &ExecutionComplexity {
+                        cpu: system_api_complexity::cpu::IS_CONTROLLER,
+                        ..Default::default()
+                    },
+                    stable_memory_dirty_page_limit,
+                )?;
+                with_memory_and_system_api(&mut caller, |system_api, memory| {
+                    system_api.ic0_is_controller(src, size, memory)
+                })
+            }
+        })
+        .unwrap();
+
+    linker
+        .func_wrap("ic0", "in_replicated_execution", {
+            move |mut caller: Caller<'_, StoreData<S>>| {
+                with_system_api(&mut caller, |s| s.ic0_in_replicated_execution())
+                    .map_err(|e| process_err(&mut caller, e))
+            }
+        })
+        .unwrap();
+
     linker
         .func_wrap("ic0", "canister_cycle_balance", {
             move |mut caller: Caller<'_, StoreData<S>>| {
@@ -1346,6 +1392,7 @@ pub(crate) fn syscalls<S: SystemApi>(
 
     linker
         .func_wrap("ic0", "data_certificate_copy", {
+            let log = log.clone();
             move |mut caller: Caller<'_, StoreData<S>>, dst: u32, offset: u32, size: u32| {
                 observe_execution_complexity(
                     &log,
@@ -1378,5 +1425,677 @@ pub(crate) fn syscalls<S: SystemApi>(
         })
         .unwrap();
 
+    if is_wasm64 {
+        // 64-bit modules use the same System API, except that heap addresses
+        // and sizes are `i64`. Replace the affected functions.
+        linker.allow_shadowing(true);
+        syscalls_wasm64(
+            &mut linker,
+            log,
+            canister_id,
+            rate_limiting_of_debug_prints,
+            stable_memory_dirty_page_limit,
+        );
+    }
+
+    linker
+}
+
+/// Returns the range of the heap of a 64-bit module covered by the given
+/// address and size.
+fn wasm64_heap_range(addr: i64, size: i64, heap_size: usize) -> HypervisorResult<Range<usize>> {
+    let (addr, size) = (addr as u64, size as u64);
+    match addr.checked_add(size) {
+        Some(end) if end <= heap_size as u64 => Ok(addr as usize..end as usize),
+        _ => Err(HypervisorError::Trapped(TrapCode::HeapOutOfBounds)),
+    }
+}
+
+/// Converts an `i64` argument of a 64-bit module that is not a heap address
+/// to `u32`.
+fn wasm64_arg_to_u32(name: &str, value: i64) -> HypervisorResult<u32> {
+    u32::try_from(value).map_err(|_| {
+        HypervisorError::ContractViolation(format!(
+            "{} = {} exceeds the maximum of {}",
+            name,
+            value,
+            u32::MAX
+        ))
+    })
+}
+
+/// Returns the number of bytes to charge for an `i64` size argument.
+fn wasm64_num_bytes(size: i64) -> u32 {
+    u32::try_from(size).unwrap_or(u32::MAX)
+}
+
+/// Calls `f` with the region of the heap at `addr` of the given size.
+fn with_heap_region_and_system_api<S: SystemApi, T>(
+    caller: &mut Caller<'_, StoreData<S>>,
+    addr: i64,
+    size: i64,
+    f: impl Fn(&mut S, u32, &mut [u8]) -> HypervisorResult<T>,
+) -> Result<T, anyhow::Error> {
+    with_memory_and_system_api(caller, |system_api, memory| {
+        let range = wasm64_heap_range(addr, size, memory.len())?;
+        let size = wasm64_arg_to_u32("size", size)?;
+        f(system_api, size, &mut memory[range])
+    })
+}
+
+/// Calls `f` with the destination region of a `*_copy` System API call.
+fn with_copy_region_and_system_api<S: SystemApi>(
+    caller: &mut Caller<'_, StoreData<S>>,
+    dst: i64,
+    offset: i64,
+    size: i64,
+    f: impl Fn(&mut S, u32, u32, &mut [u8]) -> HypervisorResult<()>,
+) -> Result<(), anyhow::Error> {
+    let offset = wasm64_arg_to_u32("offset", offset).map_err(|e| process_err(&mut *caller, e))?;
+    with_heap_region_and_system_api(caller, dst, size, |system_api, size, region| {
+        f(system_api, offset, size, region)
+    })
+}
+
+/// Converts the size returned by a `*_size` System API call to `i64`.
+fn wasm64_size<S: SystemApi, T: TryInto<i64>>(
+    caller: &mut Caller<'_, StoreData<S>>,
+    name: &str,
+    size: HypervisorResult<T>,
+) -> Result<i64, anyhow::Error>
+where
+    T::Error: std::fmt::Display,
+{
+    size.map_err(|e| process_err(caller, e)).and_then(|s| {
+        s.try_into()
+            .map_err(|e| anyhow::Error::msg(format!("ic0::{} failed: {}", name, e)))
+    })
+}
+
+/// Registers the versions of the System API functions for 64-bit modules that
+/// take `i64` heap addresses and sizes.
+///
+/// The heap region accessed by a call is resolved here, and the System API is
+/// called with the region instead of the whole heap, so that offsets into the
+/// heap always fit into a `u32`.
+fn syscalls_wasm64<S: SystemApi>(
+    linker: &mut Linker<StoreData<S>>,
+    log: ReplicaLogger,
+    canister_id: CanisterId,
+    rate_limiting_of_debug_prints: FlagStatus,
+    stable_memory_dirty_page_limit: NumPages,
+) {
+    linker
+        .func_wrap("ic0", "msg_caller_size", {
+            move |mut caller: Caller<'_, StoreData<S>>| {
+                let size = with_system_api(&mut caller, |s| s.ic0_msg_caller_size());
+                wasm64_size(&mut caller, "msg_caller_size", size)
+            }
+        })
+        .unwrap();
+
+    linker
+        .func_wrap("ic0", "msg_caller_copy", {
+            let log = log.clone();
+            move |mut caller: Caller<'_, StoreData<S>>, dst: i64, offset: i64, size: i64| {
+                observe_execution_complexity(
+                    &log,
+                    canister_id,
+                    &mut caller,
+                    &ExecutionComplexity {
+                        cpu: system_api_complexity::cpu::MSG_CALLER_COPY,
+                        ..Default::default()
+                    },
+                    stable_memory_dirty_page_limit,
+                )?;
+                with_copy_region_and_system_api(&mut caller, dst, offset, size, |s, o, n, heap| {
+                    s.ic0_msg_caller_copy(0, o, n, heap)
+                })
+            }
+        })
+        .unwrap();
+
+    linker
+        .func_wrap("ic0", "msg_arg_data_size", {
+            move |mut caller: Caller<'_, StoreData<S>>| {
+                let size = with_system_api(&mut caller, |s| s.ic0_msg_arg_data_size());
+                wasm64_size(&mut caller, "msg_arg_data_size", size)
+            }
+        })
+        .unwrap();
+
+    linker
+        .func_wrap("ic0", "msg_arg_data_copy", {
+            let log = log.clone();
+            move |mut caller: Caller<'_, StoreData<S>>, dst: i64, offset: i64, size: i64| {
+                charge_for_system_api_call(
+                    &log,
+                    canister_id,
+                    &mut caller,
+                    system_api_complexity::overhead::MSG_ARG_DATA_COPY,
+                    wasm64_num_bytes(size),
+                    &ExecutionComplexity {
+                        cpu: system_api_complexity::cpu::MSG_ARG_DATA_COPY,
+                        ..Default::default()
+                    },
+                    NumInstructions::from(0),
+                    stable_memory_dirty_page_limit,
+                )?;
+                with_copy_region_and_system_api(&mut caller, dst, offset, size, |s, o, n, heap| {
+                    s.ic0_msg_arg_data_copy(0, o, n, heap)
+                })
+            }
+        })
+        .unwrap();
+
+    linker
+        .func_wrap("ic0", "msg_method_name_size", {
+            move |mut caller: Caller<'_, StoreData<S>>| {
+                let size = with_system_api(&mut caller, |s| s.ic0_msg_method_name_size());
+                wasm64_size(&mut caller, "msg_method_name_size", size)
+            }
+        })
+        .unwrap();
+
+    linker
+        .func_wrap("ic0", "msg_method_name_copy", {
+            let log = log.clone();
+            move |mut caller: Caller<'_, StoreData<S>>, dst: i64, offset: i64, size: i64| {
+                charge_for_system_api_call(
+                    &log,
+                    canister_id,
+                    &mut caller,
+                    system_api_complexity::overhead::MSG_METHOD_NAME_COPY,
+                    wasm64_num_bytes(size),
+                    &ExecutionComplexity {
+                        cpu: system_api_complexity::cpu::MSG_METHOD_NAME_COPY,
+                        ..Default::default()
+                    },
+                    NumInstructions::from(0),
+                    stable_memory_dirty_page_limit,
+                )?;
+                with_copy_region_and_system_api(&mut caller, dst, offset, size, |s, o, n, heap| {
+                    s.ic0_msg_method_name_copy(0, o, n, heap)
+                })
+            }
+        })
+        .unwrap();
+
+    linker
+        .func_wrap("ic0", "msg_reply_data_append", {
+            let log = log.clone();
+            move |mut caller: Caller<'_, StoreData<S>>, src: i64, size: i64| {
+                charge_for_system_api_call(
+                    &log,
+                    canister_id,
+                    &mut caller,
+                    system_api_complexity::overhead::MSG_REPLY_DATA_APPEND,
+                    wasm64_num_bytes(size),
+                    &ExecutionComplexity {
+                        cpu: system_api_complexity::cpu::MSG_REPLY_DATA_APPEND,
+                        ..Default::default()
+                    },
+                    NumInstructions::from(0),
+                    stable_memory_dirty_page_limit,
+                )?;
+                with_heap_region_and_system_api(&mut caller, src, size, |s, n, heap| {
+                    s.ic0_msg_reply_data_append(0, n, heap)
+                })
+            }
+        })
+        .unwrap();
+
+    linker
+        .func_wrap("ic0", "msg_reject", {
+            let log = log.clone();
+            move |mut caller: Caller<'_, StoreData<S>>, src: i64, size: i64| {
+                charge_for_system_api_call(
+                    &log,
+                    canister_id,
+                    &mut caller,
+                    system_api_complexity::overhead::MSG_REJECT,
+                    wasm64_num_bytes(size),
+                    &ExecutionComplexity {
+                        cpu: system_api_complexity::cpu::MSG_REJECT,
+                        ..Default::default()
+                    },
+                    NumInstructions::from(0),
+                    stable_memory_dirty_page_limit,
+                )?;
+                with_heap_region_and_system_api(&mut caller, src, size, |s, n, heap| {
+                    s.ic0_msg_reject(0, n, heap)
+                })
+            }
+        })
+        .unwrap();
+
+    linker
+        .func_wrap("ic0", "msg_reject_msg_size", {
+            move |mut caller: Caller<'_, StoreData<S>>| {
+                let size = with_system_api(&mut caller, |s| s.ic0_msg_reject_msg_size());
+                wasm64_size(&mut caller, "msg_reject_msg_size", size)
+            }
+        })
+        .unwrap();
+
+    linker
+        .func_wrap("ic0", "msg_reject_msg_copy", {
+            let log = log.clone();
+            move |mut caller: Caller<'_, StoreData<S>>, dst: i64, offset: i64, size: i64| {
+                charge_for_system_api_call(
+                    &log,
+                    canister_id,
+                    &mut caller,
+                    system_api_complexity::overhead::MSG_REJECT_MSG_COPY,
+                    wasm64_num_bytes(size),
+                    &ExecutionComplexity {
+                        cpu: system_api_complexity::cpu::MSG_REJECT_MSG_COPY,
+                        ..Default::default()
+                    },
+                    NumInstructions::from(0),
+                    stable_memory_dirty_page_limit,
+                )?;
+                with_copy_region_and_system_api(&mut caller, dst, offset, size, |s, o, n, heap| {
+                    s.ic0_msg_reject_msg_copy(0, o, n, heap)
+                })
+            }
+        })
+        .unwrap();
+
+    linker
+        .func_wrap("ic0", "canister_self_size", {
+            move |mut caller: Caller<'_, StoreData<S>>| {
+                let size = with_system_api(&mut caller, |s| s.ic0_canister_self_size());
+                wasm64_size(&mut caller, "canister_self_size", size)
+            }
+        })
+        .unwrap();
+
     linker
+        .func_wrap("ic0", "canister_self_copy", {
+            let log = log.clone();
+            move |mut caller: Caller<'_, StoreData<S>>, dst: i64, offset: i64, size: i64| {
+                observe_execution_complexity(
+                    &log,
+                    canister_id,
+                    &mut caller,
+                    &ExecutionComplexity {
+                        cpu: system_api_complexity::cpu::CANISTER_SELF_COPY,
+                        ..Default::default()
+                    },
+                    stable_memory_dirty_page_limit,
+                )?;
+                with_copy_region_and_system_api(&mut caller, dst, offset, size, |s, o, n, heap| {
+                    s.ic0_canister_self_copy(0, o, n, heap)
+                })
+            }
+        })
+        .unwrap();
+
+    linker
+        .func_wrap("ic0", "controller_size", {
+            move |mut caller: Caller<'_, StoreData<S>>| {
+                let size = with_system_api(&mut caller, |s| s.ic0_controller_size());
+                wasm64_size(&mut caller, "controller_size", size)
+            }
+        })
+        .unwrap();
+
+    linker
+        .func_wrap("ic0", "controller_copy", {
+            let log = log.clone();
+            move |mut caller: Caller<'_, StoreData<S>>, dst: i64, offset: i64, size: i64| {
+                observe_execution_complexity(
+                    &log,
+                    canister_id,
+                    &mut caller,
+                    &ExecutionComplexity {
+                        cpu: system_api_complexity::cpu::CONTROLLER_COPY,
+                        ..Default::default()
+                    },
+                    stable_memory_dirty_page_limit,
+                )?;
+                with_copy_region_and_system_api(&mut caller, dst, offset, size, |s, o, n, heap| {
+                    s.ic0_controller_copy(0, o, n, heap)
+                })
+            }
+        })
+        .unwrap();
+
+    linker
+        .func_wrap("ic0", "debug_print", {
+            let log = log.clone();
+            move |mut caller: Caller<'_, StoreData<S>>, offset: i64, length: i64| {
+                charge_for_system_api_call(
+                    &log,
+                    canister_id,
+                    &mut caller,
+                    system_api_complexity::overhead::DEBUG_PRINT,
+                    wasm64_num_bytes(length),
+                    &ExecutionComplexity {
+                        cpu: system_api_complexity::cpu::DEBUG_PRINT,
+                        ..Default::default()
+                    },
+                    NumInstructions::from(0),
+                    stable_memory_dirty_page_limit,
+                )?;
+                // The message is always kept in the canister log, even if
+                // printing it is rate limited below.
+                with_heap_region_and_system_api(&mut caller, offset, length, |s, n, heap| {
+                    s.save_log_message(0, n, heap);
+                    Ok(())
+                })?;
+                match (
+                    caller.data().system_api.subnet_type(),
+                    rate_limiting_of_debug_prints,
+                ) {
+                    (SubnetType::Application, FlagStatus::Enabled) => Ok(()),
+                    (SubnetType::VerifiedApplication, FlagStatus::Enabled) => Ok(()),
+                    (_, FlagStatus::Disabled) | (SubnetType::System, FlagStatus::Enabled) => {
+                        with_heap_region_and_system_api(
+                            &mut caller,
+                            offset,
+                            length,
+                            |s, n, heap| s.ic0_debug_print(0, n, heap),
+                        )
+                    }
+                }
+            }
+        })
+        .unwrap();
+
+    linker
+        .func_wrap("ic0", "trap", {
+            let log = log.clone();
+            move |mut caller: Caller<'_, StoreData<S>>, offset: i64, length: i64| -> Result<(), _> {
+                charge_for_system_api_call(
+                    &log,
+                    canister_id,
+                    &mut caller,
+                    system_api_complexity::overhead::TRAP,
+                    wasm64_num_bytes(length),
+                    &ExecutionComplexity {
+                        cpu: system_api_complexity::cpu::TRAP,
+                        ..Default::default()
+                    },
+                    NumInstructions::from(0),
+                    stable_memory_dirty_page_limit,
+                )?;
+                with_heap_region_and_system_api(&mut caller, offset, length, |s, n, heap| {
+                    s.ic0_trap(0, n, heap)
+                })
+            }
+        })
+        .unwrap();
+
+    linker
+        .func_wrap("ic0", "call_new", {
+            let log = log.clone();
+            move |mut caller: Caller<'_, StoreData<S>>,
+                  callee_src: i64,
+                  callee_size: i64,
+                  name_src: i64,
+                  name_len: i64,
+                  reply_fun: i64,
+                  reply_env: i64,
+                  reject_fun: i64,
+                  reject_env: i64| {
+                observe_execution_complexity(
+                    &log,
+                    canister_id,
+                    &mut caller,
+                    &ExecutionComplexity {
+                        cpu: system_api_complexity::cpu::CALL_NEW,
+                        ..Default::default()
+                    },
+                    stable_memory_dirty_page_limit,
+                )?;
+                with_memory_and_system_api(&mut caller, |system_api, memory| {
+                    // The callee and the method name may be anywhere in the
+                    // heap, so they are copied next to each other.
+                    let callee = wasm64_heap_range(callee_src, callee_size, memory.len())?;
+                    let name = wasm64_heap_range(name_src, name_len, memory.len())?;
+                    let callee_size = wasm64_arg_to_u32("callee_size", callee_size)?;
+                    let name_len = wasm64_arg_to_u32("name_len", name_len)?;
+                    let mut buffer = memory[callee].to_vec();
+                    buffer.extend_from_slice(&memory[name]);
+                    system_api.ic0_call_new(
+                        0,
+                        callee_size,
+                        callee_size,
+                        name_len,
+                        wasm64_arg_to_u32("reply_fun", reply_fun)?,
+                        wasm64_arg_to_u32("reply_env", reply_env)?,
+                        wasm64_arg_to_u32("reject_fun", reject_fun)?,
+                        wasm64_arg_to_u32("reject_env", reject_env)?,
+                        &buffer,
+                    )
+                })
+            }
+        })
+        .unwrap();
+
+    linker
+        .func_wrap("ic0", "call_data_append", {
+            let log = log.clone();
+            move |mut caller: Caller<'_, StoreData<S>>, src: i64, size: i64| {
+                charge_for_system_api_call(
+                    &log,
+                    canister_id,
+                    &mut caller,
+                    system_api_complexity::overhead::CALL_DATA_APPEND,
+                    wasm64_num_bytes(size),
+                    &ExecutionComplexity {
+                        cpu: system_api_complexity::cpu::CALL_DATA_APPEND,
+                        ..Default::default()
+                    },
+                    NumInstructions::from(0),
+                    stable_memory_dirty_page_limit,
+                )?;
+                with_heap_region_and_system_api(&mut caller, src, size, |s, n, heap| {
+                    s.ic0_call_data_append(0, n, heap)
+                })
+            }
+        })
+        .unwrap();
+
+    linker
+        .func_wrap("ic0", "call_on_cleanup", {
+            move |mut caller: Caller<'_, StoreData<S>>, fun: i64, env: i64| {
+                with_system_api(&mut caller, |s| {
+                    s.ic0_call_on_cleanup(
+                        wasm64_arg_to_u32("fun", fun)?,
+                        wasm64_arg_to_u32("env", env)?,
+                    )
+                })
+                .map_err(|e| process_err(&mut caller, e))
+            }
+        })
+        .unwrap();
+
+    linker
+        .func_wrap("ic0", "canister_cycle_balance128", {
+            let log = log.clone();
+            move |mut caller: Caller<'_, StoreData<S>>, dst: i64| {
+                observe_execution_complexity(
+                    &log,
+                    canister_id,
+                    &mut caller,
+                    &ExecutionComplexity {
+                        cpu: system_api_complexity::cpu::CANISTER_CYCLES_BALANCE128,
+                        ..Default::default()
+                    },
+                    stable_memory_dirty_page_limit,
+                )?;
+                with_heap_region_and_system_api(&mut caller, dst, 16, |s, _, heap| {
+                    s.ic0_canister_cycles_balance128(0, heap)
+                })
+            }
+        })
+        .unwrap();
+
+    linker
+        .func_wrap("ic0", "msg_cycles_available128", {
+            let log = log.clone();
+            move |mut caller: Caller<'_, StoreData<S>>, dst: i64| {
+                observe_execution_complexity(
+                    &log,
+                    canister_id,
+                    &mut caller,
+                    &ExecutionComplexity {
+                        cpu: system_api_complexity::cpu::MSG_CYCLES_AVAILABLE128,
+                        ..Default::default()
+                    },
+                    stable_memory_dirty_page_limit,
+                )?;
+                with_heap_region_and_system_api(&mut caller, dst, 16, |s, _, heap| {
+                    s.ic0_msg_cycles_available128(0, heap)
+                })
+            }
+        })
+        .unwrap();
+
+    linker
+        .func_wrap("ic0", "msg_cycles_refunded128", {
+            let log = log.clone();
+            move |mut caller: Caller<'_, StoreData<S>>, dst: i64| {
+                observe_execution_complexity(
+                    &log,
+                    canister_id,
+                    &mut caller,
+                    &ExecutionComplexity {
+                        cpu: system_api_complexity::cpu::MSG_CYCLES_REFUNDED128,
+                        ..Default::default()
+                    },
+                    stable_memory_dirty_page_limit,
+                )?;
+                with_heap_region_and_system_api(&mut caller, dst, 16, |s, _, heap| {
+                    s.ic0_msg_cycles_refunded128(0, heap)
+                })
+            }
+        })
+        .unwrap();
+
+    linker
+        .func_wrap("ic0", "msg_cycles_accept128", {
+            let log = log.clone();
+            move |mut caller: Caller<'_, StoreData<S>>,
+                  amount_high: i64,
+                  amount_low: i64,
+                  dst: i64| {
+                observe_execution_complexity(
+                    &log,
+                    canister_id,
+                    &mut caller,
+                    &ExecutionComplexity {
+                        cpu: system_api_complexity::cpu::MSG_CYCLES_ACCEPT128,
+                        ..Default::default()
+                    },
+                    stable_memory_dirty_page_limit,
+                )?;
+                with_heap_region_and_system_api(&mut caller, dst, 16, |s, _, heap| {
+                    s.ic0_msg_cycles_accept128(
+                        Cycles::from_parts(amount_high as u64, amount_low as u64),
+                        0,
+                        heap,
+                    )
+                })
+            }
+        })
+        .unwrap();
+
+    linker
+        .func_wrap("ic0", "certified_data_set", {
+            let log = log.clone();
+            move |mut caller: Caller<'_, StoreData<S>>, src: i64, size: i64| {
+                observe_execution_complexity(
+                    &log,
+                    canister_id,
+                    &mut caller,
+                    &ExecutionComplexity {
+                        cpu: system_api_complexity::cpu::CERTIFIED_DATA_SET,
+                        ..Default::default()
+                    },
+                    stable_memory_dirty_page_limit,
+                )?;
+                with_heap_region_and_system_api(&mut caller, src, size, |s, n, heap| {
+                    s.ic0_certified_data_set(0, n, heap)
+                })
+            }
+        })
+        .unwrap();
+
+    linker
+        .func_wrap("ic0", "is_controller", {
+            let log = log.clone();
+            move |mut caller: Caller<'_, StoreData<S>>, src: i64, size: i64| {
+                observe_execution_complexity(
+                    &log,
+                    canister_id,
+                    &mut caller,
+                    &ExecutionComplexity {
+                        cpu: system_api_complexity::cpu::IS_CONTROLLER,
+                        ..Default::default()
+                    },
+                    stable_memory_dirty_page_limit,
+                )?;
+                with_heap_region_and_system_api(&mut caller, src, size, |s, n, heap| {
+                    s.ic0_is_controller(0, n, heap)
+                })
+            }
+        })
+        .unwrap();
+
+    linker
+        .func_wrap("ic0", "data_certificate_size", {
+            move |mut caller: Caller<'_, StoreData<S>>| {
+                let size = with_system_api(&mut caller, |s| s.ic0_data_certificate_size());
+                wasm64_size(&mut caller, "data_certificate_size", size)
+            }
+        })
+        .unwrap();
+
+    linker
+        .func_wrap("ic0", "data_certificate_copy", {
+            move |mut caller: Caller<'_, StoreData<S>>, dst: i64, offset: i64, size: i64| {
+                observe_execution_complexity(
+                    &log,
+                    canister_id,
+                    &mut caller,
+                    &ExecutionComplexity {
+                        cpu: system_api_complexity::cpu::DATA_CERTIFICATE_COPY,
+                        ..Default::default()
+                    },
+                    stable_memory_dirty_page_limit,
+                )?;
+                with_copy_region_and_system_api(&mut caller, dst, offset, size, |s, o, n, heap| {
+                    s.ic0_data_certificate_copy(0, o, n, heap)
+                })
+            }
+        })
+        .unwrap();
+
+    linker
+        .func_wrap("__", "update_available_memory", {
+            move |mut caller: Caller<'_, StoreData<S>>,
+                  native_memory_grow_res: i64,
+                  additional_pages: i64| {
+                // A successful `memory.grow` on a 64-bit memory returns the
+                // previous size and grows by at most the maximum number of
+                // pages of a 64-bit memory, so both values fit into 32 bits.
+                if native_memory_grow_res == -1 {
+                    return Ok(-1);
+                }
+                with_system_api(&mut caller, |s| {
+                    s.update_available_memory(
+                        i32::try_from(native_memory_grow_res)
+                            .map_err(|_| HypervisorError::OutOfMemory)?,
+                        wasm64_arg_to_u32("additional_pages", additional_pages)?,
+                    )
+                })
+                .map(i64::from)
+                .map_err(|e| process_err(&mut caller, e))
+            }
+        })
+        .unwrap();
 }
@@ -104,4 +104,5 @@ pub mod cpu {
     pub const MSG_CYCLES_ACCEPT128: NumInstructions = from_nanos(80);
     pub const CERTIFIED_DATA_SET: NumInstructions = from_nanos(70);
     pub const PERFORMANCE_COUNTER: NumInstructions = from_nanos(50);
+    pub const IS_CONTROLLER: NumInstructions = from_nanos(100);
 }
@@ -15,7 +15,7 @@ use ic_system_api::{
 use ic_test_utilities::{
     cycles_account_manager::CyclesAccountManagerBuilder, types::ids::canister_test_id,
 };
-use ic_types::{ComputeAllocation, NumBytes, NumInstructions};
+use ic_types::{ComputeAllocation, NumBytes, NumInstructions, ResourceSaturation};
 use ic_wasm_types::BinaryEncodedWasm;
 
 use lazy_static::lazy_static;
@@ -54,6 +54,8 @@ fn test_wasmtime_system_api() {
                 MAX_NUM_INSTRUCTIONS,
             ),
             canister_memory_limit,
+            wasm_memory_limit: None,
+            subnet_memory_saturation: ResourceSaturation::default(),
             compute_allocation: ComputeAllocation::default(),
             subnet_type: SubnetType::Application,
             execution_mode: ExecutionMode::Replicated,
@@ -101,6 +103,7 @@ fn test_wasmtime_system_api() {
         &store,
         FlagStatus::Enabled,
         config.stable_memory_dirty_page_limit,
+        false,
     );
     let instance = linker
         .instantiate(&mut store, &module)
@@ -921,3 +921,95 @@ fn complex_function_rejected() {
         })
     )
 }
+
+fn wat64_to_wasm(wat: &str) -> BinaryEncodedWasm {
+    let buf = wast::parser::ParseBuffer::new(wat).unwrap();
+    let mut wat: wast::Wat = wast::parser::parse(&buf).unwrap();
+    BinaryEncodedWasm::new(wat.encode().unwrap())
+}
+
+// The old validation does not support 64-bit memories, so the new validation
+// is called directly.
+fn validate_wasm64_binary(
+    wasm: &BinaryEncodedWasm,
+    wasm64: FlagStatus,
+) -> Result<WasmValidationDetails, WasmValidationError> {
+    let mut config = EmbeddersConfig::default();
+    config.feature_flags.new_wasm_transform_lib = FlagStatus::Enabled;
+    config.feature_flags.wasm64 = wasm64;
+    let embedder = WasmtimeEmbedder::new(config, no_op_logger());
+    match validate_and_instrument_for_testing(&embedder, wasm) {
+        Ok((validation_details, instrumentation_output)) => {
+            // The instrumented module must still be a valid 64-bit module.
+            embedder.compile(&instrumentation_output.binary).unwrap();
+            Ok(validation_details)
+        }
+        Err(HypervisorError::InvalidWasm(err)) => Err(err),
+        Err(other_error) => panic!("unexpected error {}", other_error),
+    }
+}
+
+#[test]
+fn wasm64_rejected_when_disabled() {
+    let wasm = wat64_to_wasm(r#"(module (memory i64 1))"#);
+    assert!(validate_wasm64_binary(&wasm, FlagStatus::Disabled).is_err());
+}
+
+#[test]
+fn can_validate_wasm64_module() {
+    let wasm = wat64_to_wasm(
+        r#"(module
+            (import "ic0" "msg_arg_data_size" (func $msg_arg_data_size (result i64)))
+            (import "ic0" "msg_arg_data_copy" (func $msg_arg_data_copy (param i64 i64 i64)))
+            (import "ic0" "msg_reply_data_append" (func $msg_reply_data_append (param i64 i64)))
+            (import "ic0" "msg_reply" (func $msg_reply))
+            (import "ic0" "stable64_read" (func $stable64_read (param i64 i64 i64)))
+            (func (export "canister_update echo")
+              (local $size i64)
+              (local.set $size (call $msg_arg_data_size))
+              (drop (memory.grow (i64.const 1)))
+              (memory.fill (i64.const 0) (i32.const 0) (local.get $size))
+              (memory.copy (i64.const 100) (i64.const 0) (local.get $size))
+              (call $msg_arg_data_copy (i64.const 0) (i64.const 0) (local.get $size))
+              (call $msg_reply_data_append (i64.const 0) (local.get $size))
+              (call $msg_reply))
+            (memory i64 1)
+            (data (i64.const 10) "abc"))"#,
+    );
+    assert!(validate_wasm64_binary(&wasm, FlagStatus::Enabled).is_ok());
+}
+
+#[test]
+fn wasm64_rejects_32_bit_system_api_signatures() {
+    let wasm = wat64_to_wasm(
+        r#"(module
+            (import "ic0" "msg_reply_data_append" (func (param i32 i32)))
+            (memory i64 1))"#,
+    );
+    assert_matches!(
+        validate_wasm64_binary(&wasm, FlagStatus::Enabled),
+        Err(WasmValidationError::InvalidImportSection(_))
+    );
+}
+
+#[test]
+fn wasm64_rejects_32_bit_only_system_api() {
+    let wasm = wat64_to_wasm(
+        r#"(module
+            (import "ic0" "stable_read" (func (param i64 i64 i64)))
+            (memory i64 1))"#,
+    );
+    assert_matches!(
+        validate_wasm64_binary(&wasm, FlagStatus::Enabled),
+        Err(WasmValidationError::InvalidImportSection(_))
+    );
+}
+
+#[test]
+fn wasm64_rejects_too_large_initial_memory() {
+    let wasm = wat64_to_wasm(r#"(module (memory i64 262145))"#);
+    assert_matches!(
+        validate_wasm64_binary(&wasm, FlagStatus::Enabled),
+        Err(WasmValidationError::InvalidMemorySection(_))
+    );
+}
@@ -19,7 +19,7 @@ use ic_test_utilities::{
 use ic_test_utilities_logger::with_test_replica_logger;
 use ic_types::{
     methods::{FuncRef, WasmMethod},
-    ComputeAllocation, Cycles, NumBytes, NumInstructions, PrincipalId,
+    ComputeAllocation, Cycles, NumBytes, NumInstructions, PrincipalId, ResourceSaturation,
 };
 use ic_wasm_types::BinaryEncodedWasm;
 use lazy_static::lazy_static;
@@ -75,6 +75,8 @@ fn test_api_for_update(
                 instruction_limit,
             ),
             canister_memory_limit,
+            wasm_memory_limit: None,
+            subnet_memory_saturation: ResourceSaturation::default(),
             compute_allocation: ComputeAllocation::default(),
             subnet_type: SubnetType::Application,
             execution_mode: ExecutionMode::Replicated,
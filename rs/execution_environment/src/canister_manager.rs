@@ -1,5 +1,6 @@
 use crate::execution::install_code::{
-    validate_compute_allocation, validate_controller, validate_memory_allocation, OriginalContext,
+    canister_layout, validate_compute_allocation, validate_controller, validate_memory_allocation,
+    OriginalContext,
 };
 use crate::execution::{install::execute_install, upgrade::execute_upgrade};
 use crate::execution_environment::{CompilationCostHandling, RoundContext, RoundLimits};
@@ -7,15 +8,16 @@ use crate::{
     canister_settings::CanisterSettings,
     hypervisor::Hypervisor,
     types::{IngressResponse, Response},
-    util::GOVERNANCE_CANISTER_ID,
+    util::{subnet_memory_saturation, GOVERNANCE_CANISTER_ID},
 };
 use ic_base_types::NumSeconds;
 use ic_config::flag_status::FlagStatus;
 use ic_cycles_account_manager::CyclesAccountManager;
 use ic_error_types::{ErrorCode, RejectCode, UserError};
 use ic_ic00_types::{
-    CanisterInstallMode, CanisterStatusResultV2, CanisterStatusType, InstallCodeArgs,
-    Method as Ic00Method,
+    CanisterChangeDetails, CanisterChangeOrigin, CanisterInstallMode, CanisterSnapshotResponse,
+    CanisterStatusResultV2, CanisterStatusType, ChunkHash, InstallChunkedCodeArgs, InstallCodeArgs,
+    LogVisibility, Method as Ic00Method, QueryStats, StoredChunksReply, UploadChunkReply,
 };
 use ic_interfaces::execution_environment::{
     CanisterOutOfCyclesError, HypervisorError, IngressHistoryWriter, SubnetAvailableMemory,
@@ -24,9 +26,12 @@ use ic_interfaces::messages::RequestOrIngress;
 use ic_logger::{error, fatal, info, ReplicaLogger};
 use ic_registry_provisional_whitelist::ProvisionalWhitelist;
 use ic_registry_subnet_type::SubnetType;
+use ic_replicated_state::canister_state::system_state::wasm_chunk_store::{
+    self, WasmChunkHash, CHUNK_SIZE,
+};
 use ic_replicated_state::{
-    CallOrigin, CanisterState, CanisterStatus, NetworkTopology, ReplicatedState, SchedulerState,
-    SystemState,
+    CallOrigin, CanisterSnapshot, CanisterState, CanisterStatus, ExecutionState, Memory,
+    NetworkTopology, ReplicatedState, ReservationError, SchedulerState, SnapshotId, SystemState,
 };
 use ic_system_api::ExecutionParameters;
 use ic_types::messages::{MessageId, SignedIngressContent};
@@ -46,6 +51,9 @@ use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use std::{collections::BTreeSet, convert::TryFrom, str::FromStr, sync::Arc};
 
+/// The maximum number of snapshots that can be kept for a single canister.
+pub(crate) const MAX_NUMBER_OF_SNAPSHOTS_PER_CANISTER: usize = 1;
+
 #[derive(Debug, PartialEq, Eq)]
 pub(crate) struct InstallCodeResult {
     pub heap_delta: NumBytes,
@@ -94,6 +102,7 @@ pub(crate) enum StopCanisterResult {
 #[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
 pub(crate) struct CanisterMgrConfig {
     pub(crate) subnet_memory_capacity: NumBytes,
+    pub(crate) subnet_memory_threshold: NumBytes,
     pub(crate) default_provisional_cycles_balance: Cycles,
     pub(crate) default_freeze_threshold: NumSeconds,
     pub(crate) compute_capacity: u64,
@@ -107,6 +116,7 @@ impl CanisterMgrConfig {
     #[allow(clippy::too_many_arguments)]
     pub(crate) fn new(
         subnet_memory_capacity: NumBytes,
+        subnet_memory_threshold: NumBytes,
         default_provisional_cycles_balance: Cycles,
         default_freeze_threshold: NumSeconds,
         own_subnet_id: SubnetId,
@@ -118,6 +128,7 @@ impl CanisterMgrConfig {
     ) -> Self {
         Self {
             subnet_memory_capacity,
+            subnet_memory_threshold,
             default_provisional_cycles_balance,
             default_freeze_threshold,
             own_subnet_id,
@@ -132,7 +143,7 @@ impl CanisterMgrConfig {
 
 #[derive(Clone, Debug)]
 pub struct InstallCodeContext {
-    pub sender: PrincipalId,
+    pub origin: CanisterChangeOrigin,
     pub mode: CanisterInstallMode,
     pub canister_id: CanisterId,
     pub wasm_module: CanisterModule,
@@ -142,7 +153,13 @@ pub struct InstallCodeContext {
     pub query_allocation: QueryAllocation,
 }
 
-/// Errors that can occur when converting from (sender, [`InstallCodeArgs`]) to
+impl InstallCodeContext {
+    pub fn sender(&self) -> PrincipalId {
+        self.origin.origin()
+    }
+}
+
+/// Errors that can occur when converting from (origin, [`InstallCodeArgs`]) to
 /// an [`InstallCodeContext`].
 #[derive(Debug)]
 pub enum InstallCodeContextError {
@@ -207,11 +224,11 @@ impl From<InvalidMemoryAllocationError> for InstallCodeContextError {
     }
 }
 
-impl TryFrom<(PrincipalId, InstallCodeArgs)> for InstallCodeContext {
+impl TryFrom<(CanisterChangeOrigin, InstallCodeArgs)> for InstallCodeContext {
     type Error = InstallCodeContextError;
 
-    fn try_from(input: (PrincipalId, InstallCodeArgs)) -> Result<Self, Self::Error> {
-        let (sender, args) = input;
+    fn try_from(input: (CanisterChangeOrigin, InstallCodeArgs)) -> Result<Self, Self::Error> {
+        let (origin, args) = input;
         let canister_id = CanisterId::new(args.canister_id).map_err(|err| {
             InstallCodeContextError::InvalidCanisterId(format!(
                 "Converting canister id {} failed with {}",
@@ -241,7 +258,7 @@ impl TryFrom<(PrincipalId, InstallCodeArgs)> for InstallCodeContext {
         let query_allocation = QueryAllocation::default();
 
         Ok(InstallCodeContext {
-            sender,
+            origin,
             mode: args.mode,
             canister_id,
             wasm_module: CanisterModule::new(args.wasm_module),
@@ -302,6 +319,9 @@ impl CanisterManager {
             | Ok(Ic00Method::SetupInitialDKG)
             | Ok(Ic00Method::SignWithECDSA)
             | Ok(Ic00Method::ComputeInitialEcdsaDealings)
+            | Ok(Ic00Method::CanisterInfo)
+            // `fetch_canister_logs` is only available as a query.
+            | Ok(Ic00Method::FetchCanisterLogs)
             // "DepositCycles" can be called by anyone however as ingress message
             // cannot carry cycles, it does not make sense to allow them from users.
             | Ok(Ic00Method::DepositCycles)
@@ -329,7 +349,15 @@ impl CanisterManager {
             | Ok(Ic00Method::DeleteCanister) |
             Ok(Ic00Method::UpdateSettings)|
             Ok(Ic00Method::InstallCode) |
-            Ok(Ic00Method::SetController) => {
+            Ok(Ic00Method::SetController) |
+            Ok(Ic00Method::TakeCanisterSnapshot) |
+            Ok(Ic00Method::LoadCanisterSnapshot) |
+            Ok(Ic00Method::ListCanisterSnapshots) |
+            Ok(Ic00Method::DeleteCanisterSnapshot) |
+            Ok(Ic00Method::UploadChunk) |
+            Ok(Ic00Method::StoredChunks) |
+            Ok(Ic00Method::ClearChunkStore) |
+            Ok(Ic00Method::InstallChunkedCode) => {
                 match effective_canister_id {
                     Some(canister_id) => {
                         let canister = state.canister_state(&canister_id).ok_or_else(|| UserError::new(
@@ -450,17 +478,36 @@ impl CanisterManager {
         if let Some(freezing_threshold) = settings.freezing_threshold {
             canister.system_state.freeze_threshold = freezing_threshold;
         }
+        if let Some(log_visibility) = settings.log_visibility {
+            canister.system_state.log_visibility = log_visibility;
+        }
+        if let Some(wasm_memory_limit) = settings.wasm_memory_limit {
+            // A limit of 0 removes the limit.
+            canister.system_state.wasm_memory_limit = if wasm_memory_limit.get() == 0 {
+                None
+            } else {
+                Some(wasm_memory_limit)
+            };
+        }
+        if let Some(reserved_cycles_limit) = settings.reserved_cycles_limit {
+            canister
+                .system_state
+                .set_reserved_balance_limit(reserved_cycles_limit);
+        }
     }
 
     /// Tries to apply the requested settings on the canister identified by
     /// `canister_id`.
     pub(crate) fn update_settings(
         &self,
-        sender: PrincipalId,
+        timestamp_nanos: Time,
+        origin: CanisterChangeOrigin,
         settings: CanisterSettings,
         canister: &mut CanisterState,
+        subnet_size: usize,
         round_limits: &mut RoundLimits,
     ) -> Result<(), CanisterManagerError> {
+        let sender = origin.origin();
         // Verify controller.
         validate_controller(canister, &sender)?;
         validate_compute_allocation(
@@ -486,9 +533,58 @@ impl CanisterManager {
             .bytes()
             .max(old_usage);
         let old_compute_allocation = canister.scheduler_state.compute_allocation.as_percent();
+        let controllers_changed =
+            validated_settings.controller.is_some() || validated_settings.controllers.is_some();
+
+        // Increasing the memory allocation on a busy subnet moves cycles from
+        // the main balance to the reserved balance.
+        let reservation_cycles = match validated_settings.memory_allocation {
+            Some(memory_allocation @ MemoryAllocation::Reserved(bytes)) if bytes > old_mem => {
+                let reservation_cycles = self.cycles_account_manager.storage_reservation_cycles(
+                    bytes - old_mem,
+                    &subnet_memory_saturation(
+                        &round_limits.subnet_available_memory,
+                        self.config.subnet_memory_threshold,
+                        self.config.subnet_memory_capacity,
+                    ),
+                    subnet_size,
+                );
+                let reserved_cycles_limit = validated_settings
+                    .reserved_cycles_limit
+                    .or_else(|| canister.system_state.reserved_balance_limit());
+                canister
+                    .system_state
+                    .check_reservation(reservation_cycles, reserved_cycles_limit)
+                    .map_err(|err| match err {
+                        ReservationError::ReservedLimitExceeded { requested, limit } => {
+                            CanisterManagerError::ReservedCyclesLimitExceededInMemoryAllocation {
+                                memory_allocation,
+                                requested,
+                                limit,
+                            }
+                        }
+                        ReservationError::InsufficientCycles {
+                            requested,
+                            available,
+                        } => CanisterManagerError::InsufficientCyclesInMemoryAllocation {
+                            memory_allocation,
+                            available,
+                            threshold: requested,
+                        },
+                    })?;
+                reservation_cycles
+            }
+            _ => Cycles::zero(),
+        };
 
         self.do_update_settings(validated_settings, canister);
 
+        // The reservation was checked against the new limit above.
+        canister
+            .system_state
+            .reserve_cycles(reservation_cycles)
+            .expect("Reservation of cycles must succeed after validation");
+
         let new_compute_allocation = canister.scheduler_state.compute_allocation.as_percent();
         if old_compute_allocation < new_compute_allocation {
             round_limits.compute_allocation_used = round_limits
@@ -520,6 +616,14 @@ impl CanisterManager {
         }
 
         canister.system_state.canister_version += 1;
+        if controllers_changed {
+            let new_controllers = canister.system_state.controllers.iter().copied().collect();
+            canister.system_state.add_canister_change(
+                timestamp_nanos,
+                origin,
+                CanisterChangeDetails::controllers_change(new_controllers),
+            );
+        }
 
         Ok(())
     }
@@ -529,7 +633,7 @@ impl CanisterManager {
     /// Returns the auto-generated id the new canister that has been created.
     pub(crate) fn create_canister(
         &self,
-        sender: PrincipalId,
+        origin: CanisterChangeOrigin,
         sender_subnet_id: SubnetId,
         cycles: Cycles,
         settings: CanisterSettings,
@@ -573,7 +677,7 @@ impl CanisterManager {
             Err(err) => (Err(err), cycles),
             Ok(validate_settings) => {
                 let canister_id = match self.create_canister_helper(
-                    sender,
+                    origin,
                     cycles,
                     fee,
                     validate_settings,
@@ -697,7 +801,7 @@ impl CanisterManager {
         execution_refund_error_counter: &IntCounter,
         subnet_size: usize,
     ) -> DtsInstallCodeResult {
-        if let Err(err) = validate_controller(&canister, &context.sender) {
+        if let Err(err) = validate_controller(&canister, &context.sender()) {
             return DtsInstallCodeResult::Finished {
                 canister,
                 message,
@@ -742,7 +846,7 @@ impl CanisterManager {
             subnet_size,
             requested_compute_allocation: context.compute_allocation,
             requested_memory_allocation: context.memory_allocation,
-            sender: context.sender,
+            origin: context.origin.clone(),
             canister_id: canister.canister_id(),
         };
 
@@ -770,10 +874,11 @@ impl CanisterManager {
     /// See https://sdk.dfinity.org/docs/interface-spec/index.html#ic-uninstall_code
     pub(crate) fn uninstall_code(
         &self,
+        origin: CanisterChangeOrigin,
         canister_id: CanisterId,
-        sender: PrincipalId,
         state: &mut ReplicatedState,
     ) -> Result<(), CanisterManagerError> {
+        let sender = origin.origin();
         let time = state.time();
         let canister = match state.canister_state_mut(&canister_id) {
             Some(canister) => canister,
@@ -788,6 +893,11 @@ impl CanisterManager {
         }
 
         let rejects = uninstall_canister(&self.log, canister, time);
+        canister.system_state.add_canister_change(
+            time,
+            origin,
+            CanisterChangeDetails::CanisterCodeUninstall,
+        );
         crate::util::process_responses(
             rejects,
             state,
@@ -932,6 +1042,7 @@ impl CanisterManager {
         let compute_allocation = canister.scheduler_state.compute_allocation;
         let memory_allocation = canister.memory_allocation();
         let freeze_threshold = canister.system_state.freeze_threshold;
+        let total_query_stats = &canister.scheduler_state.total_query_stats;
 
         Ok(CanisterStatusResultV2::new(
             canister.status(),
@@ -946,6 +1057,15 @@ impl CanisterManager {
             compute_allocation.as_percent(),
             Some(memory_allocation.bytes().get()),
             freeze_threshold.get(),
+            canister
+                .system_state
+                .wasm_memory_limit
+                .map(|limit| limit.get()),
+            canister.system_state.reserved_balance().get(),
+            canister
+                .system_state
+                .reserved_balance_limit()
+                .map(|limit| limit.get()),
             self.cycles_account_manager
                 .idle_cycles_burned_rate(
                     memory_allocation,
@@ -954,6 +1074,12 @@ impl CanisterManager {
                     subnet_size,
                 )
                 .get(),
+            QueryStats::new(
+                total_query_stats.num_calls,
+                total_query_stats.num_instructions,
+                total_query_stats.ingress_payload_size,
+                total_query_stats.egress_payload_size,
+            ),
         ))
     }
 
@@ -961,18 +1087,29 @@ impl CanisterManager {
     /// the canister is able to run this, otherwise an error is returned.
     pub(crate) fn set_controller(
         &self,
-        sender: PrincipalId,
+        origin: CanisterChangeOrigin,
         canister_id: CanisterId,
         new_controller: PrincipalId,
         state: &mut ReplicatedState,
+        subnet_size: usize,
         round_limits: &mut RoundLimits,
     ) -> Result<(), CanisterManagerError> {
+        let time = state.time();
         let canister = state
             .canister_state_mut(&canister_id)
             .ok_or(CanisterManagerError::CanisterNotFound(canister_id))?;
 
-        let settings = CanisterSettings::new(Some(new_controller), None, None, None, None);
-        self.update_settings(sender, settings, canister, round_limits)
+        let settings = CanisterSettings::new(
+            Some(new_controller),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        );
+        self.update_settings(time, origin, settings, canister, subnet_size, round_limits)
     }
 
     /// Permanently deletes a canister from `ReplicatedState`.
@@ -1018,6 +1155,10 @@ impl CanisterManager {
 
         // Take out the canister from `ReplicatedState`.
         let canister_to_delete = state.take_canister_state(&canister_id_to_delete).unwrap();
+        // Its snapshots are deleted along with it.
+        state
+            .canister_snapshots
+            .remove_snapshots(canister_id_to_delete);
         // Leftover cycles in the balance are considered `consumed`.
         let consumed_cycles_by_canister_to_delete =
             NominalCycles::from(canister_to_delete.system_state.balance())
@@ -1036,6 +1177,432 @@ impl CanisterManager {
         Ok(())
     }
 
+    /// Takes a snapshot of the Wasm module, heap, stable memory and certified
+    /// data of the canister.
+    ///
+    /// If `replace_snapshot` is given, the new snapshot replaces the existing
+    /// snapshot with that id. Otherwise the canister must not have reached
+    /// `MAX_NUMBER_OF_SNAPSHOTS_PER_CANISTER`.
+    ///
+    /// The memory of the snapshot counts towards the memory usage of the
+    /// canister and the canister is charged cycles proportional to the size of
+    /// the snapshot.
+    pub(crate) fn take_canister_snapshot(
+        &self,
+        subnet_size: usize,
+        sender: PrincipalId,
+        canister_id: CanisterId,
+        replace_snapshot: Option<&[u8]>,
+        state: &mut ReplicatedState,
+        round_limits: &mut RoundLimits,
+    ) -> Result<CanisterSnapshotResponse, CanisterManagerError> {
+        let time = state.time();
+        let canister = self.validate_canister_exists(state, canister_id)?;
+        validate_controller(canister, &sender)?;
+
+        let replace_snapshot = match replace_snapshot {
+            Some(raw_snapshot_id) => {
+                Some(self.get_canister_snapshot(state, canister_id, raw_snapshot_id)?)
+            }
+            None => {
+                let num_snapshots = state.canister_snapshots.snapshot_ids(canister_id).len();
+                if num_snapshots >= MAX_NUMBER_OF_SNAPSHOTS_PER_CANISTER {
+                    return Err(CanisterManagerError::CanisterSnapshotLimitExceeded {
+                        canister_id,
+                        limit: MAX_NUMBER_OF_SNAPSHOTS_PER_CANISTER,
+                    });
+                }
+                None
+            }
+        };
+
+        let new_snapshot = CanisterSnapshot::from_canister(canister, time).ok_or(
+            CanisterManagerError::CanisterSnapshotEmptyCanister(canister_id),
+        )?;
+        let new_snapshot_size = new_snapshot.size();
+        let replaced_snapshot_size = replace_snapshot
+            .as_ref()
+            .map_or(NumBytes::from(0), |(_, snapshot)| snapshot.size());
+        let additional_memory = new_snapshot_size
+            .get()
+            .saturating_sub(replaced_snapshot_size.get());
+
+        let canister = state.canister_state_mut(&canister_id).unwrap();
+        self.charge_for_canister_snapshot(
+            canister,
+            NumBytes::from(additional_memory),
+            new_snapshot_size,
+            round_limits,
+            subnet_size,
+        )?;
+
+        let snapshot_id = SnapshotId::new(canister_id, canister.system_state.next_snapshot_id);
+        canister.system_state.next_snapshot_id += 1;
+
+        if let Some((replaced_snapshot_id, _)) = replace_snapshot {
+            state.canister_snapshots.remove(&replaced_snapshot_id);
+        }
+        state
+            .canister_snapshots
+            .push(snapshot_id, Arc::new(new_snapshot));
+        self.update_snapshots_memory_usage(canister_id, state);
+
+        Ok(CanisterSnapshotResponse::new(
+            snapshot_id.to_vec(),
+            time.as_nanos_since_unix_epoch(),
+            new_snapshot_size,
+        ))
+    }
+
+    /// Replaces the Wasm module, heap, stable memory and certified data of the
+    /// canister with the ones stored in the given snapshot.
+    ///
+    /// The canister is charged cycles proportional to the size of the
+    /// snapshot.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn load_canister_snapshot(
+        &self,
+        subnet_size: usize,
+        sender: PrincipalId,
+        canister_id: CanisterId,
+        snapshot_id: &[u8],
+        canister_layout_path: PathBuf,
+        state: &mut ReplicatedState,
+        round_limits: &mut RoundLimits,
+    ) -> Result<(), CanisterManagerError> {
+        let canister = self.validate_canister_exists(state, canister_id)?;
+        validate_controller(canister, &sender)?;
+        let (snapshot_id, snapshot) =
+            self.get_canister_snapshot(state, canister_id, snapshot_id)?;
+
+        let execution_snapshot = snapshot.execution_snapshot();
+        let execution_state = ExecutionState::new(
+            canister_layout(&canister_layout_path, &canister_id).raw_path(),
+            Arc::clone(&execution_snapshot.wasm_binary),
+            execution_snapshot.exports.clone(),
+            // The memories of the snapshot must not be shared with the sandbox
+            // memory of the canister, so we create fresh `Memory` objects.
+            Memory::new(
+                execution_snapshot.wasm_memory.page_map.clone(),
+                execution_snapshot.wasm_memory.size,
+            ),
+            Memory::new(
+                execution_snapshot.stable_memory.page_map.clone(),
+                execution_snapshot.stable_memory.size,
+            ),
+            execution_snapshot.exported_globals.clone(),
+            execution_snapshot.metadata.clone(),
+        );
+        let additional_memory = execution_state
+            .memory_usage()
+            .get()
+            .saturating_sub(canister.execution_memory_usage().get());
+
+        let canister = state.canister_state_mut(&canister_id).unwrap();
+        self.charge_for_canister_snapshot(
+            canister,
+            NumBytes::from(additional_memory),
+            snapshot.size(),
+            round_limits,
+            subnet_size,
+        )?;
+
+        canister.execution_state = Some(execution_state);
+        canister.system_state.certified_data = snapshot.certified_data().clone();
+        canister.system_state.canister_version += 1;
+        state
+            .canister_snapshots
+            .add_restore_operation(canister_id, snapshot_id);
+
+        Ok(())
+    }
+
+    /// Returns the snapshots of the canister, ordered by snapshot id.
+    pub(crate) fn list_canister_snapshots(
+        &self,
+        sender: PrincipalId,
+        canister_id: CanisterId,
+        state: &ReplicatedState,
+    ) -> Result<Vec<CanisterSnapshotResponse>, CanisterManagerError> {
+        let canister = self.validate_canister_exists(state, canister_id)?;
+        validate_controller(canister, &sender)?;
+
+        Ok(state
+            .canister_snapshots
+            .list_snapshots(canister_id)
+            .into_iter()
+            .map(|(snapshot_id, snapshot)| {
+                CanisterSnapshotResponse::new(
+                    snapshot_id.to_vec(),
+                    snapshot.taken_at_timestamp().as_nanos_since_unix_epoch(),
+                    snapshot.size(),
+                )
+            })
+            .collect())
+    }
+
+    /// Deletes the given snapshot of the canister and releases its memory.
+    pub(crate) fn delete_canister_snapshot(
+        &self,
+        sender: PrincipalId,
+        canister_id: CanisterId,
+        snapshot_id: &[u8],
+        state: &mut ReplicatedState,
+        round_limits: &mut RoundLimits,
+    ) -> Result<(), CanisterManagerError> {
+        let canister = self.validate_canister_exists(state, canister_id)?;
+        validate_controller(canister, &sender)?;
+        let (snapshot_id, snapshot) =
+            self.get_canister_snapshot(state, canister_id, snapshot_id)?;
+
+        if canister.system_state.memory_allocation == MemoryAllocation::BestEffort {
+            round_limits
+                .subnet_available_memory
+                .increment(snapshot.size(), NumBytes::from(0));
+        }
+        state.canister_snapshots.remove(&snapshot_id);
+        self.update_snapshots_memory_usage(canister_id, state);
+
+        Ok(())
+    }
+
+    /// Adds a chunk to the Wasm chunk store of the canister and returns its
+    /// hash.
+    ///
+    /// Every stored chunk counts as `CHUNK_SIZE` bytes towards the memory
+    /// usage of the canister. Uploading a chunk that is already stored does
+    /// not allocate new memory, but is still charged.
+    pub(crate) fn upload_chunk(
+        &self,
+        sender: PrincipalId,
+        canister_id: CanisterId,
+        chunk: Vec<u8>,
+        state: &mut ReplicatedState,
+        round_limits: &mut RoundLimits,
+        subnet_size: usize,
+    ) -> Result<UploadChunkReply, CanisterManagerError> {
+        let canister = self.validate_canister_exists(state, canister_id)?;
+        validate_controller(canister, &sender)?;
+
+        let chunk_store = &canister.system_state.wasm_chunk_store;
+        chunk_store
+            .can_insert_chunk(wasm_chunk_store::DEFAULT_MAX_NUMBER_OF_CHUNKS, &chunk)
+            .map_err(|message| CanisterManagerError::WasmChunkStoreError { message })?;
+        let chunk_hash: WasmChunkHash = ic_crypto_sha::Sha256::hash(&chunk);
+        let additional_memory = if chunk_store.keys().any(|hash| *hash == chunk_hash) {
+            NumBytes::from(0)
+        } else {
+            NumBytes::from(CHUNK_SIZE)
+        };
+
+        let canister = state.canister_state_mut(&canister_id).unwrap();
+        let memory_allocation = canister.system_state.memory_allocation;
+        let new_memory_usage =
+            canister.memory_usage(self.config.own_subnet_type) + additional_memory;
+
+        // Like `memory.grow`, a new chunk of a canister without a reserved
+        // memory allocation moves cycles to the reserved balance when the
+        // subnet is under storage pressure.
+        let reservation_cycles = match memory_allocation {
+            MemoryAllocation::Reserved(_) => Cycles::zero(),
+            MemoryAllocation::BestEffort => self.cycles_account_manager.storage_reservation_cycles(
+                additional_memory,
+                &subnet_memory_saturation(
+                    &round_limits.subnet_available_memory,
+                    self.config.subnet_memory_threshold,
+                    self.config.subnet_memory_capacity,
+                ),
+                subnet_size,
+            ),
+        };
+        let reservation_error = |err: ReservationError| match err {
+            ReservationError::ReservedLimitExceeded { requested, limit } => {
+                CanisterManagerError::ReservedCyclesLimitExceededInMemoryGrow {
+                    bytes: additional_memory,
+                    requested,
+                    limit,
+                }
+            }
+            ReservationError::InsufficientCycles {
+                requested,
+                available,
+            } => CanisterManagerError::InsufficientCyclesInMemoryGrow {
+                bytes: additional_memory,
+                available,
+                required: requested,
+            },
+        };
+        canister
+            .system_state
+            .check_reservation(
+                reservation_cycles,
+                canister.system_state.reserved_balance_limit(),
+            )
+            .map_err(reservation_error)?;
+
+        // The upload is charged last, after the reservation, so check up front
+        // that the balance covers both. A rejected upload is then not billed.
+        let cycles = self
+            .cycles_account_manager
+            .execution_cost(NumInstructions::from(chunk.len() as u64), subnet_size);
+        let compute_allocation = canister.scheduler_state.compute_allocation;
+        self.cycles_account_manager
+            .can_withdraw_cycles(
+                &canister.system_state,
+                cycles + reservation_cycles,
+                new_memory_usage,
+                compute_allocation,
+                subnet_size,
+            )
+            .map_err(|err| CanisterManagerError::WasmChunkStoreError {
+                message: format!("Uploading the chunk failed with `{}`", err),
+            })?;
+
+        match memory_allocation {
+            MemoryAllocation::Reserved(bytes) => {
+                if new_memory_usage > bytes {
+                    return Err(CanisterManagerError::NotEnoughMemoryAllocationGiven {
+                        canister_id,
+                        memory_allocation_given: memory_allocation,
+                        memory_usage_needed: new_memory_usage,
+                    });
+                }
+            }
+            MemoryAllocation::BestEffort => {
+                if round_limits
+                    .subnet_available_memory
+                    .try_decrement(additional_memory, NumBytes::from(0))
+                    .is_err()
+                {
+                    return Err(CanisterManagerError::SubnetMemoryCapacityOverSubscribed {
+                        requested: additional_memory,
+                        available: NumBytes::from(
+                            round_limits
+                                .subnet_available_memory
+                                .get_total_memory()
+                                .max(0) as u64,
+                        ),
+                    });
+                }
+            }
+        }
+
+        canister
+            .system_state
+            .reserve_cycles(reservation_cycles)
+            .expect("Reservation of cycles must succeed after validation");
+        self.cycles_account_manager
+            .consume_cycles(
+                &mut canister.system_state,
+                new_memory_usage,
+                compute_allocation,
+                cycles,
+                subnet_size,
+            )
+            .expect("Charging for the upload must succeed after validation");
+
+        let hash = canister
+            .system_state
+            .wasm_chunk_store
+            .insert_chunk(wasm_chunk_store::DEFAULT_MAX_NUMBER_OF_CHUNKS, &chunk)
+            .map_err(|message| CanisterManagerError::WasmChunkStoreError { message })?;
+        Ok(UploadChunkReply {
+            hash: hash.to_vec(),
+        })
+    }
+
+    /// Removes all chunks from the Wasm chunk store of the canister.
+    pub(crate) fn clear_chunk_store(
+        &self,
+        sender: PrincipalId,
+        canister_id: CanisterId,
+        state: &mut ReplicatedState,
+        round_limits: &mut RoundLimits,
+    ) -> Result<(), CanisterManagerError> {
+        let canister = self.validate_canister_exists(state, canister_id)?;
+        validate_controller(canister, &sender)?;
+
+        let canister = state.canister_state_mut(&canister_id).unwrap();
+        if canister.system_state.memory_allocation == MemoryAllocation::BestEffort {
+            round_limits.subnet_available_memory.increment(
+                canister.system_state.wasm_chunk_store.memory_usage(),
+                NumBytes::from(0),
+            );
+        }
+        canister.system_state.wasm_chunk_store.clear();
+        Ok(())
+    }
+
+    /// Returns the hashes of all chunks in the Wasm chunk store of the
+    /// canister.
+    pub(crate) fn stored_chunks(
+        &self,
+        sender: PrincipalId,
+        canister_id: CanisterId,
+        state: &ReplicatedState,
+    ) -> Result<StoredChunksReply, CanisterManagerError> {
+        let canister = self.validate_canister_exists(state, canister_id)?;
+        validate_controller(canister, &sender)?;
+
+        Ok(StoredChunksReply(
+            canister
+                .system_state
+                .wasm_chunk_store
+                .keys()
+                .map(|hash| ChunkHash {
+                    hash: hash.to_vec(),
+                })
+                .collect(),
+        ))
+    }
+
+    /// Assembles the Wasm module of an `install_chunked_code` call from the
+    /// chunk store of the store canister and checks it against the expected
+    /// module hash.
+    ///
+    /// The sender must be a controller of the store canister or the store
+    /// canister itself, and the store canister must be on this subnet.
+    pub(crate) fn assemble_chunked_wasm_module(
+        &self,
+        sender: PrincipalId,
+        args: &InstallChunkedCodeArgs,
+        state: &ReplicatedState,
+    ) -> Result<Vec<u8>, CanisterManagerError> {
+        let store_canister_id = args.store_canister_id();
+        let store_canister = self.validate_canister_exists(state, store_canister_id)?;
+        if sender != store_canister_id.get() {
+            validate_controller(store_canister, &sender)?;
+        }
+
+        let chunk_store = &store_canister.system_state.wasm_chunk_store;
+        let mut wasm_module = Vec::new();
+        for ChunkHash { hash } in &args.chunk_hashes_list {
+            let chunk = WasmChunkHash::try_from(hash.as_slice())
+                .ok()
+                .and_then(|hash| chunk_store.get_chunk_data(&hash))
+                .ok_or_else(|| CanisterManagerError::WasmChunkStoreError {
+                    message: format!(
+                        "Chunk with hash {} not found in the chunk store of canister {}",
+                        hex::encode(hash),
+                        store_canister_id
+                    ),
+                })?;
+            wasm_module.extend(chunk);
+        }
+
+        let module_hash = ic_crypto_sha::Sha256::hash(&wasm_module);
+        if module_hash[..] != args.wasm_module_hash[..] {
+            return Err(CanisterManagerError::WasmChunkStoreError {
+                message: format!(
+                    "Wasm module hash {} does not match the hash {} of the assembled chunks",
+                    hex::encode(&args.wasm_module_hash),
+                    hex::encode(module_hash)
+                ),
+            });
+        }
+        Ok(wasm_module)
+    }
+
     /// Creates a new canister with the cycles amount specified and inserts it
     /// into `ReplicatedState`.
     ///
@@ -1045,7 +1612,7 @@ impl CanisterManager {
     /// Returns the auto-generated id the new canister that has been created.
     pub(crate) fn create_canister_with_cycles(
         &self,
-        sender: PrincipalId,
+        origin: CanisterChangeOrigin,
         cycles_amount: Option<u128>,
         settings: CanisterSettings,
         specified_id: Option<PrincipalId>,
@@ -1054,6 +1621,7 @@ impl CanisterManager {
         max_number_of_canisters: u64,
         round_limits: &mut RoundLimits,
     ) -> Result<CanisterId, CanisterManagerError> {
+        let sender = origin.origin();
         if !provisional_whitelist.contains(&sender) {
             return Err(CanisterManagerError::SenderNotInWhitelist(sender));
         }
@@ -1072,7 +1640,7 @@ impl CanisterManager {
         ) {
             Err(err) => Err(err),
             Ok(validated_settings) => self.create_canister_helper(
-                sender,
+                origin,
                 cycles,
                 Cycles::new(0),
                 validated_settings,
@@ -1120,7 +1688,7 @@ impl CanisterManager {
 
     fn create_canister_helper(
         &self,
-        sender: PrincipalId,
+        origin: CanisterChangeOrigin,
         cycles: Cycles,
         creation_fee: Cycles,
         settings: ValidatedCanisterSettings,
@@ -1148,6 +1716,7 @@ impl CanisterManager {
         // initial balance.
         let cycles = cycles - creation_fee;
 
+        let sender = origin.origin();
         // Canister id available. Create the new canister.
         let mut system_state = SystemState::new_running(
             new_canister_id,
@@ -1162,6 +1731,17 @@ impl CanisterManager {
         let mut new_canister = CanisterState::new(system_state, None, scheduler_state);
 
         self.do_update_settings(settings, &mut new_canister);
+        let controllers = new_canister
+            .system_state
+            .controllers
+            .iter()
+            .copied()
+            .collect();
+        new_canister.system_state.add_canister_change(
+            state.time(),
+            origin,
+            CanisterChangeDetails::canister_creation(controllers),
+        );
         let new_usage = new_canister.memory_usage(self.config.own_subnet_type);
         let new_mem = new_canister
             .system_state
@@ -1262,6 +1842,112 @@ impl CanisterManager {
             .canister_state(&canister_id)
             .ok_or(CanisterManagerError::CanisterNotFound(canister_id))
     }
+
+    /// Looks up the snapshot with the given raw id and checks that it belongs
+    /// to the given canister.
+    fn get_canister_snapshot(
+        &self,
+        state: &ReplicatedState,
+        canister_id: CanisterId,
+        raw_snapshot_id: &[u8],
+    ) -> Result<(SnapshotId, Arc<CanisterSnapshot>), CanisterManagerError> {
+        let snapshot_id = SnapshotId::try_from(raw_snapshot_id)
+            .map_err(|message| CanisterManagerError::InvalidCanisterSnapshotId { message })?;
+        if snapshot_id.get_canister_id() != canister_id {
+            return Err(CanisterManagerError::CanisterSnapshotInvalidOwnership {
+                canister_id,
+                snapshot_id,
+            });
+        }
+        match state.canister_snapshots.get(&snapshot_id) {
+            Some(snapshot) => Ok((snapshot_id, Arc::clone(snapshot))),
+            None => Err(CanisterManagerError::CanisterSnapshotNotFound {
+                canister_id,
+                snapshot_id,
+            }),
+        }
+    }
+
+    /// Reserves `additional_memory` for the canister and charges it for
+    /// processing a snapshot of `snapshot_size` bytes.
+    ///
+    /// If the canister has a reserved memory allocation, the additional memory
+    /// must fit into it. Otherwise, it is taken from the available subnet
+    /// memory.
+    fn charge_for_canister_snapshot(
+        &self,
+        canister: &mut CanisterState,
+        additional_memory: NumBytes,
+        snapshot_size: NumBytes,
+        round_limits: &mut RoundLimits,
+        subnet_size: usize,
+    ) -> Result<(), CanisterManagerError> {
+        let canister_id = canister.canister_id();
+        let memory_allocation = canister.system_state.memory_allocation;
+        let new_memory_usage =
+            canister.memory_usage(self.config.own_subnet_type) + additional_memory;
+        match memory_allocation {
+            MemoryAllocation::Reserved(bytes) => {
+                if new_memory_usage > bytes {
+                    return Err(CanisterManagerError::NotEnoughMemoryAllocationGiven {
+                        canister_id,
+                        memory_allocation_given: memory_allocation,
+                        memory_usage_needed: new_memory_usage,
+                    });
+                }
+            }
+            MemoryAllocation::BestEffort => {
+                if round_limits
+                    .subnet_available_memory
+                    .try_decrement(additional_memory, NumBytes::from(0))
+                    .is_err()
+                {
+                    return Err(
+                        CanisterManagerError::CanisterSnapshotSubnetMemoryOverSubscribed {
+                            canister_id,
+                            requested: additional_memory,
+                            available: NumBytes::from(
+                                round_limits
+                                    .subnet_available_memory
+                                    .get_total_memory()
+                                    .max(0) as u64,
+                            ),
+                        },
+                    );
+                }
+            }
+        }
+
+        let cycles = self
+            .cycles_account_manager
+            .execution_cost(NumInstructions::from(snapshot_size.get()), subnet_size);
+        let compute_allocation = canister.scheduler_state.compute_allocation;
+        if let Err(err) = self.cycles_account_manager.consume_cycles(
+            &mut canister.system_state,
+            new_memory_usage,
+            compute_allocation,
+            cycles,
+            subnet_size,
+        ) {
+            if memory_allocation == MemoryAllocation::BestEffort {
+                round_limits
+                    .subnet_available_memory
+                    .increment(additional_memory, NumBytes::from(0));
+            }
+            return Err(CanisterManagerError::CanisterSnapshotNotEnoughCycles(err));
+        }
+        Ok(())
+    }
+
+    /// Recomputes the memory used by the snapshots of the canister.
+    fn update_snapshots_memory_usage(&self, canister_id: CanisterId, state: &mut ReplicatedState) {
+        let snapshots_memory_usage = state
+            .canister_snapshots
+            .compute_memory_usage_by_canister(canister_id);
+        if let Some(canister) = state.canister_state_mut(&canister_id) {
+            canister.system_state.snapshots_memory_usage = snapshots_memory_usage;
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -1311,6 +1997,51 @@ pub(crate) enum CanisterManagerError {
     CanisterNotHostedBySubnet {
         message: String,
     },
+    CanisterSnapshotNotFound {
+        canister_id: CanisterId,
+        snapshot_id: SnapshotId,
+    },
+    CanisterSnapshotInvalidOwnership {
+        canister_id: CanisterId,
+        snapshot_id: SnapshotId,
+    },
+    InvalidCanisterSnapshotId {
+        message: String,
+    },
+    CanisterSnapshotLimitExceeded {
+        canister_id: CanisterId,
+        limit: usize,
+    },
+    CanisterSnapshotEmptyCanister(CanisterId),
+    CanisterSnapshotSubnetMemoryOverSubscribed {
+        canister_id: CanisterId,
+        requested: NumBytes,
+        available: NumBytes,
+    },
+    CanisterSnapshotNotEnoughCycles(CanisterOutOfCyclesError),
+    ReservedCyclesLimitExceededInMemoryAllocation {
+        memory_allocation: MemoryAllocation,
+        requested: Cycles,
+        limit: Cycles,
+    },
+    InsufficientCyclesInMemoryAllocation {
+        memory_allocation: MemoryAllocation,
+        available: Cycles,
+        threshold: Cycles,
+    },
+    ReservedCyclesLimitExceededInMemoryGrow {
+        bytes: NumBytes,
+        requested: Cycles,
+        limit: Cycles,
+    },
+    InsufficientCyclesInMemoryGrow {
+        bytes: NumBytes,
+        available: Cycles,
+        required: Cycles,
+    },
+    WasmChunkStoreError {
+        message: String,
+    },
 }
 
 impl From<CanisterManagerError> for UserError {
@@ -1467,6 +2198,93 @@ impl From<CanisterManagerError> for UserError {
                     format!("Unsuccessful validation of specified ID: {}", message),
                 )
             }
+            CanisterSnapshotNotFound { canister_id, snapshot_id } => {
+                Self::new(
+                    ErrorCode::CanisterSnapshotNotFound,
+                    format!("Could not find the snapshot ID {} for canister {}", snapshot_id, canister_id),
+                )
+            }
+            CanisterSnapshotInvalidOwnership { canister_id, snapshot_id } => {
+                Self::new(
+                    ErrorCode::CanisterRejectedMessage,
+                    format!("The snapshot {} does not belong to canister {}", snapshot_id, canister_id),
+                )
+            }
+            InvalidCanisterSnapshotId { message } => {
+                Self::new(
+                    ErrorCode::InvalidManagementPayload,
+                    format!("Invalid snapshot ID: {}", message),
+                )
+            }
+            CanisterSnapshotLimitExceeded { canister_id, limit } => {
+                Self::new(
+                    ErrorCode::CanisterRejectedMessage,
+                    format!("Canister {} has reached the maximum number of {} snapshots. Delete or replace an existing snapshot.", canister_id, limit),
+                )
+            }
+            CanisterSnapshotEmptyCanister(canister_id) => {
+                Self::new(
+                    ErrorCode::CanisterWasmModuleNotFound,
+                    format!("Canister {} is empty and cannot be snapshotted", canister_id),
+                )
+            }
+            CanisterSnapshotSubnetMemoryOverSubscribed { canister_id, requested, available } => {
+                Self::new(
+                    ErrorCode::SubnetOversubscribed,
+                    format!(
+                        "Snapshot of canister {} requires {} bytes of memory but the Subnet's remaining memory capacity is {} bytes",
+                        canister_id, requested, available,
+                    ),
+                )
+            }
+            CanisterSnapshotNotEnoughCycles(err) => {
+                Self::new(
+                    ErrorCode::CanisterOutOfCycles,
+                    format!("Canister snapshot operation failed with `{}`", err),
+                )
+            }
+            ReservedCyclesLimitExceededInMemoryAllocation { memory_allocation, requested, limit } => {
+                Self::new(
+                    ErrorCode::ReservedCyclesLimitExceededInMemoryAllocation,
+                    format!(
+                        "Cannot increase memory allocation to {} due to its reserved cycles limit. The current limit ({}) would be exceeded by {}.",
+                        memory_allocation, limit, requested - limit,
+                    ),
+                )
+            }
+            InsufficientCyclesInMemoryAllocation { memory_allocation, available, threshold } => {
+                Self::new(
+                    ErrorCode::InsufficientCyclesInMemoryAllocation,
+                    format!(
+                        "Cannot increase memory allocation to {} due to insufficient cycles. At least {} additional cycles are required.",
+                        memory_allocation, threshold - available,
+                    ),
+                )
+            }
+            ReservedCyclesLimitExceededInMemoryGrow { bytes, requested, limit } => {
+                Self::new(
+                    ErrorCode::ReservedCyclesLimitExceededInMemoryGrow,
+                    format!(
+                        "Cannot grow memory by {} bytes due to the reserved cycles limit. The current limit ({}) would be exceeded by {}.",
+                        bytes, limit, requested - limit,
+                    ),
+                )
+            }
+            InsufficientCyclesInMemoryGrow { bytes, available, required } => {
+                Self::new(
+                    ErrorCode::InsufficientCyclesInMemoryGrow,
+                    format!(
+                        "Cannot grow memory by {} bytes due to insufficient cycles. At least {} additional cycles are required.",
+                        bytes, required - available,
+                    ),
+                )
+            }
+            WasmChunkStoreError { message } => {
+                Self::new(
+                    ErrorCode::CanisterContractViolation,
+                    format!("Error from Wasm chunk store: {}", message),
+                )
+            }
         }
     }
 }
@@ -1537,11 +2355,12 @@ pub fn uninstall_canister(
                         },
                     }));
                 }
-                CallOrigin::CanisterUpdate(caller_canister_id, callback_id) => {
+                CallOrigin::CanisterUpdate(caller_canister_id, callback_id, deadline) => {
                     rejects.push(Response::Canister(CanisterResponse {
                         originator: *caller_canister_id,
                         respondent: canister_id,
                         originator_reply_callback: *callback_id,
+                        deadline: *deadline,
                         refund: call_context.available_cycles(),
                         response_payload: Payload::Reject(RejectContext {
                             code: RejectCode::CanisterReject,
@@ -1572,6 +2391,9 @@ struct ValidatedCanisterSettings {
     pub compute_allocation: Option<ComputeAllocation>,
     pub memory_allocation: Option<MemoryAllocation>,
     pub freezing_threshold: Option<NumSeconds>,
+    pub log_visibility: Option<LogVisibility>,
+    pub wasm_memory_limit: Option<NumBytes>,
+    pub reserved_cycles_limit: Option<Cycles>,
 }
 
 impl TryFrom<(CanisterSettings, usize)> for ValidatedCanisterSettings {
@@ -1607,6 +2429,9 @@ impl TryFrom<(CanisterSettings, usize)> for ValidatedCanisterSettings {
             compute_allocation: settings.compute_allocation(),
             memory_allocation: settings.memory_allocation(),
             freezing_threshold: settings.freezing_threshold(),
+            log_visibility: settings.log_visibility(),
+            wasm_memory_limit: settings.wasm_memory_limit(),
+            reserved_cycles_limit: settings.reserved_cycles_limit(),
         })
     }
 }
@@ -2,7 +2,7 @@ use crate::{
     as_num_instructions,
     canister_manager::{
         uninstall_canister, CanisterManager, CanisterManagerError, CanisterMgrConfig,
-        InstallCodeContext, StopCanisterResult,
+        InstallCodeContext, StopCanisterResult, MAX_NUMBER_OF_SNAPSHOTS_PER_CANISTER,
     },
     canister_settings::CanisterSettings,
     execution::test_utilities::{
@@ -24,8 +24,12 @@ use ic_constants::SMALL_APP_SUBNET_MAX_SIZE;
 use ic_cycles_account_manager::CyclesAccountManager;
 use ic_error_types::{ErrorCode, UserError};
 use ic_ic00_types::{
-    CanisterIdRecord, CanisterInstallMode, CanisterSettingsArgs, CanisterStatusType,
-    CreateCanisterArgs, EmptyBlob, InstallCodeArgs, Method, Payload, UpdateSettingsArgs,
+    CanisterChangeDetails, CanisterChangeOrigin, CanisterIdRecord, CanisterInfoRequest,
+    CanisterInfoResponse, CanisterInstallMode, CanisterSettingsArgs, CanisterSnapshotArgs,
+    CanisterSnapshotResponse, CanisterStatusType, CreateCanisterArgs, EmptyBlob,
+    InstallChunkedCodeArgs, InstallCodeArgs, ListCanisterSnapshotsResponse, Method, Payload,
+    StoredChunksReply, TakeCanisterSnapshotArgs, UpdateSettingsArgs, UploadChunkArgs,
+    UploadChunkReply, IC_00,
 };
 use ic_interfaces::{
     execution_environment::{ExecutionMode, HypervisorError, SubnetAvailableMemory},
@@ -37,8 +41,11 @@ use ic_registry_provisional_whitelist::ProvisionalWhitelist;
 use ic_registry_routing_table::{CanisterIdRange, RoutingTable, CANISTER_IDS_PER_SUBNET};
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::{
-    page_map, testing::CanisterQueuesTesting, CallContextManager, CallOrigin, CanisterState,
-    CanisterStatus, NumWasmPages, PageMap, ReplicatedState,
+    canister_state::system_state::{wasm_chunk_store, MAX_CANISTER_HISTORY_CHANGES},
+    page_map,
+    testing::CanisterQueuesTesting,
+    CallContextManager, CallOrigin, CanisterState, CanisterStatus, NumWasmPages, PageMap,
+    ReplicatedState,
 };
 use ic_system_api::{ExecutionParameters, InstructionLimits};
 use ic_test_utilities::{
@@ -58,10 +65,10 @@ use ic_test_utilities::{
 };
 use ic_types::{
     ingress::{IngressState, IngressStatus, WasmResult},
-    messages::{CallbackId, StopCanisterContext},
+    messages::{CallbackId, StopCanisterContext, NO_DEADLINE},
     nominal_cycles::NominalCycles,
     CanisterId, CanisterTimer, ComputeAllocation, Cycles, MemoryAllocation, NumBytes,
-    NumInstructions, QueryAllocation, SubnetId, UserId,
+    NumInstructions, QueryAllocation, ResourceSaturation, SubnetId, UserId,
 };
 use ic_wasm_types::{CanisterModule, WasmValidationError};
 use lazy_static::lazy_static;
@@ -97,6 +104,8 @@ lazy_static! {
             MAX_NUM_INSTRUCTIONS
         ),
         canister_memory_limit: NumBytes::new(u64::MAX / 2),
+        wasm_memory_limit: None,
+        subnet_memory_saturation: ResourceSaturation::default(),
         compute_allocation: ComputeAllocation::default(),
         subnet_type: SubnetType::Application,
         execution_mode: ExecutionMode::Replicated,
@@ -109,7 +118,7 @@ pub struct InstallCodeContextBuilder {
 
 impl InstallCodeContextBuilder {
     pub fn sender(mut self, sender: PrincipalId) -> Self {
-        self.ctx.sender = sender;
+        self.ctx.origin = canister_change_origin_from_principal(&sender);
         self
     }
 
@@ -158,7 +167,7 @@ impl Default for InstallCodeContextBuilder {
     fn default() -> Self {
         Self {
             ctx: InstallCodeContext {
-                sender: PrincipalId::new_user_test_id(0),
+                origin: canister_change_origin_from_principal(&PrincipalId::new_user_test_id(0)),
                 canister_id: canister_test_id(0),
                 wasm_module: CanisterModule::new(wabt::wat2wasm(EMPTY_WAT).unwrap()),
                 arg: vec![],
@@ -231,12 +240,17 @@ impl Default for CanisterManagerBuilder {
     }
 }
 
+fn canister_change_origin_from_principal(sender: &PrincipalId) -> CanisterChangeOrigin {
+    CanisterChangeOrigin::from_user(*sender)
+}
+
 fn canister_manager_config(
     subnet_id: SubnetId,
     subnet_type: SubnetType,
     rate_limiting_of_instructions: FlagStatus,
 ) -> CanisterMgrConfig {
     CanisterMgrConfig::new(
+        MEMORY_CAPACITY,
         MEMORY_CAPACITY,
         DEFAULT_PROVISIONAL_BALANCE,
         NumSeconds::from(100_000),
@@ -302,7 +316,7 @@ fn install_code(
         None,
     );
     let ingress = IngressBuilder::new()
-        .source(UserId::from(context.sender))
+        .source(UserId::from(context.sender()))
         .receiver(CanisterId::ic_00())
         .method_name(Method::InstallCode)
         .method_payload(args.encode())
@@ -345,7 +359,7 @@ fn install_canister_makes_subnet_oversubscribed() {
         };
         let canister_id1 = canister_manager
             .create_canister(
-                sender,
+                canister_change_origin_from_principal(&sender),
                 sender_subnet_id,
                 *INITIAL_CYCLES,
                 CanisterSettings::default(),
@@ -358,7 +372,7 @@ fn install_canister_makes_subnet_oversubscribed() {
             .unwrap();
         let canister_id2 = canister_manager
             .create_canister(
-                sender,
+                canister_change_origin_from_principal(&sender),
                 sender_subnet_id,
                 *INITIAL_CYCLES,
                 CanisterSettings::default(),
@@ -371,7 +385,7 @@ fn install_canister_makes_subnet_oversubscribed() {
             .unwrap();
         let canister_id3 = canister_manager
             .create_canister(
-                sender,
+                canister_change_origin_from_principal(&sender),
                 sender_subnet_id,
                 *INITIAL_CYCLES,
                 CanisterSettings::default(),
@@ -481,7 +495,7 @@ fn upgrade_canister_with_no_wasm_fails() {
         };
         let canister_id = canister_manager
             .create_canister(
-                sender,
+                canister_change_origin_from_principal(&sender),
                 sender_subnet_id,
                 *INITIAL_CYCLES,
                 CanisterSettings::default(),
@@ -531,7 +545,7 @@ fn can_update_compute_allocation_during_upgrade() {
         };
         let canister_id1 = canister_manager
             .create_canister(
-                sender,
+                canister_change_origin_from_principal(&sender),
                 sender_subnet_id,
                 Cycles::new(2_000_000_000_000_000),
                 CanisterSettings::default(),
@@ -601,7 +615,7 @@ fn upgrading_canister_makes_subnet_oversubscribed() {
         let initial_cycles = Cycles::new(30_000_000_000_000);
         let canister_id1 = canister_manager
             .create_canister(
-                sender,
+                canister_change_origin_from_principal(&sender),
                 sender_subnet_id,
                 initial_cycles,
                 CanisterSettings::default(),
@@ -614,7 +628,7 @@ fn upgrading_canister_makes_subnet_oversubscribed() {
             .unwrap();
         let canister_id2 = canister_manager
             .create_canister(
-                sender,
+                canister_change_origin_from_principal(&sender),
                 sender_subnet_id,
                 initial_cycles,
                 CanisterSettings::default(),
@@ -627,7 +641,7 @@ fn upgrading_canister_makes_subnet_oversubscribed() {
             .unwrap();
         let canister_id3 = canister_manager
             .create_canister(
-                sender,
+                canister_change_origin_from_principal(&sender),
                 sender_subnet_id,
                 initial_cycles,
                 CanisterSettings::default(),
@@ -802,7 +816,7 @@ fn can_update_memory_allocation_during_upgrade() {
         };
         let canister_id = canister_manager
             .create_canister(
-                sender,
+                canister_change_origin_from_principal(&sender),
                 sender_subnet_id,
                 *INITIAL_CYCLES,
                 CanisterSettings::default(),
@@ -936,14 +950,14 @@ fn can_create_canister() {
         assert_eq!(
             canister_manager
                 .create_canister(
-                    canister,
+                    canister_change_origin_from_principal(&canister),
                     sender_subnet_id,
                     *INITIAL_CYCLES,
                     CanisterSettings::default(),
                     MAX_NUMBER_OF_CANISTERS,
                     &mut state,
                     SMALL_APP_SUBNET_MAX_SIZE,
-                    &mut round_limits,
+                    &mut round_limits
                 )
                 .0
                 .unwrap(),
@@ -952,14 +966,14 @@ fn can_create_canister() {
         assert_eq!(
             canister_manager
                 .create_canister(
-                    canister,
+                    canister_change_origin_from_principal(&canister),
                     sender_subnet_id,
                     *INITIAL_CYCLES,
                     CanisterSettings::default(),
                     MAX_NUMBER_OF_CANISTERS,
                     &mut state,
                     SMALL_APP_SUBNET_MAX_SIZE,
-                    &mut round_limits,
+                    &mut round_limits
                 )
                 .0
                 .unwrap(),
@@ -982,14 +996,14 @@ fn create_canister_fails_if_not_enough_cycles_are_sent_with_the_request() {
 
         assert_eq!(
             canister_manager.create_canister(
-                canister,
+                canister_change_origin_from_principal(&canister),
                 sender_subnet_id,
                 Cycles::new(100),
                 CanisterSettings::default(),
                 MAX_NUMBER_OF_CANISTERS,
                 &mut state,
                 SMALL_APP_SUBNET_MAX_SIZE,
-                &mut round_limits,
+                &mut round_limits
             ),
             (
                 Err(CanisterManagerError::CreateCanisterNotEnoughCycles {
@@ -1018,14 +1032,14 @@ fn can_create_canister_with_extra_cycles() {
         assert_eq!(
             canister_manager
                 .create_canister(
-                    canister,
+                    canister_change_origin_from_principal(&canister),
                     sender_subnet_id,
                     Cycles::from(cycles),
                     CanisterSettings::default(),
                     MAX_NUMBER_OF_CANISTERS,
                     &mut state,
                     SMALL_APP_SUBNET_MAX_SIZE,
-                    &mut round_limits,
+                    &mut round_limits
                 )
                 .0
                 .unwrap(),
@@ -1047,7 +1061,7 @@ fn cannot_install_non_empty_canister() {
         };
         let canister_id = canister_manager
             .create_canister(
-                sender,
+                canister_change_origin_from_principal(&sender),
                 sender_subnet_id,
                 *INITIAL_CYCLES,
                 CanisterSettings::default(),
@@ -1111,7 +1125,7 @@ fn install_code_with_wrong_controller_fails() {
         // Create a canister with canister_test_id 1 as controller.
         let canister_id = canister_manager
             .create_canister(
-                canister_test_id(1).get(),
+                canister_change_origin_from_principal(&canister_test_id(1).get()),
                 subnet_test_id(1),
                 *INITIAL_CYCLES,
                 CanisterSettings::default(),
@@ -1175,7 +1189,7 @@ fn create_canister_sets_correct_allocations() {
         };
         let canister_id = canister_manager
             .create_canister(
-                canister_test_id(1).get(),
+                canister_change_origin_from_principal(&canister_test_id(1).get()),
                 subnet_test_id(1),
                 *INITIAL_CYCLES,
                 settings,
@@ -1203,7 +1217,7 @@ fn create_canister_updates_consumed_cycles_metric_correctly() {
         };
         let canister_id = canister_manager
             .create_canister(
-                canister_test_id(1).get(),
+                canister_change_origin_from_principal(&canister_test_id(1).get()),
                 subnet_test_id(1),
                 *INITIAL_CYCLES,
                 CanisterSettings::default(),
@@ -1243,7 +1257,7 @@ fn provisional_create_canister_has_no_creation_fee() {
         };
         let canister_id = canister_manager
             .create_canister_with_cycles(
-                canister_test_id(1).get(),
+                canister_change_origin_from_principal(&canister_test_id(1).get()),
                 Some(INITIAL_CYCLES.get()),
                 CanisterSettings::default(),
                 None,
@@ -1278,7 +1292,7 @@ fn reinstall_on_empty_canister_succeeds() {
         let sender = canister_test_id(42).get();
         let canister_id = canister_manager
             .create_canister(
-                sender,
+                canister_change_origin_from_principal(&sender),
                 subnet_test_id(1),
                 *INITIAL_CYCLES,
                 CanisterSettings::default(),
@@ -1385,7 +1399,7 @@ fn install_puts_canister_back_after_invalid_wasm() {
         let sender_subnet_id = subnet_test_id(1);
         let canister_id = canister_manager
             .create_canister(
-                sender,
+                canister_change_origin_from_principal(&sender),
                 sender_subnet_id,
                 *INITIAL_CYCLES,
                 CanisterSettings::default(),
@@ -1440,7 +1454,7 @@ fn reinstall_clears_stable_memory() {
         let sender_subnet_id = subnet_test_id(1);
         let canister_id = canister_manager
             .create_canister(
-                sender,
+                canister_change_origin_from_principal(&sender),
                 sender_subnet_id,
                 *INITIAL_CYCLES,
                 CanisterSettings::default(),
@@ -1533,7 +1547,7 @@ fn stop_a_running_canister() {
         let sender_subnet_id = subnet_test_id(1);
         let canister_id = canister_manager
             .create_canister(
-                sender.get(),
+                canister_change_origin_from_principal(&sender.get()),
                 sender_subnet_id,
                 *INITIAL_CYCLES,
                 CanisterSettings::default(),
@@ -1556,6 +1570,7 @@ fn stop_a_running_canister() {
             sender,
             reply_callback: CallbackId::new(0),
             cycles: Cycles::zero(),
+            deadline: NO_DEADLINE,
         };
         assert_eq!(
             canister_manager.stop_canister(canister_id, stop_context.clone(), &mut state),
@@ -1636,6 +1651,7 @@ fn stop_a_stopped_canister_from_another_canister() {
             sender: controller,
             reply_callback: CallbackId::from(0),
             cycles: Cycles::from(cycles),
+            deadline: NO_DEADLINE,
         };
         assert_eq!(
             canister_manager.stop_canister(canister_id, stop_context, &mut state),
@@ -1665,7 +1681,7 @@ fn stop_a_canister_with_incorrect_controller() {
         let sender_subnet_id = subnet_test_id(1);
         let canister_id = canister_manager
             .create_canister(
-                sender,
+                canister_change_origin_from_principal(&sender),
                 sender_subnet_id,
                 *INITIAL_CYCLES,
                 CanisterSettings::default(),
@@ -1738,7 +1754,7 @@ fn start_a_canister_with_incorrect_controller() {
         let sender_subnet_id = subnet_test_id(1);
         let canister_id = canister_manager
             .create_canister(
-                sender,
+                canister_change_origin_from_principal(&sender),
                 sender_subnet_id,
                 *INITIAL_CYCLES,
                 CanisterSettings::default(),
@@ -1776,7 +1792,7 @@ fn starting_an_already_running_canister_keeps_it_running() {
         let sender_subnet_id = subnet_test_id(1);
         let canister_id = canister_manager
             .create_canister(
-                sender,
+                canister_change_origin_from_principal(&sender),
                 sender_subnet_id,
                 *INITIAL_CYCLES,
                 CanisterSettings::default(),
@@ -1883,7 +1899,7 @@ fn get_canister_status_with_incorrect_controller() {
         let sender_subnet_id = subnet_test_id(1);
         let canister_id = canister_manager
             .create_canister(
-                sender,
+                canister_change_origin_from_principal(&sender),
                 sender_subnet_id,
                 *INITIAL_CYCLES,
                 CanisterSettings::default(),
@@ -1921,7 +1937,7 @@ fn get_canister_status_of_running_canister() {
         let sender_subnet_id = subnet_test_id(1);
         let canister_id = canister_manager
             .create_canister(
-                sender,
+                canister_change_origin_from_principal(&sender),
                 sender_subnet_id,
                 *INITIAL_CYCLES,
                 CanisterSettings::default(),
@@ -1954,7 +1970,7 @@ fn get_canister_status_of_self() {
         let sender_subnet_id = subnet_test_id(1);
         let canister_id = canister_manager
             .create_canister(
-                sender,
+                canister_change_origin_from_principal(&sender),
                 sender_subnet_id,
                 *INITIAL_CYCLES,
                 CanisterSettings::default(),
@@ -2029,11 +2045,12 @@ fn set_controller_with_incorrect_controller() {
         // Set the controller from the wrong controller. Should fail.
         assert_eq!(
             canister_manager.set_controller(
-                wrong_controller,
+                canister_change_origin_from_principal(&wrong_controller),
                 canister_id,
                 new_controller,
                 &mut state,
-                &mut round_limits,
+                SMALL_APP_SUBNET_MAX_SIZE,
+                &mut round_limits
             ),
             Err(CanisterManagerError::CanisterInvalidController {
                 canister_id,
@@ -2072,10 +2089,11 @@ fn set_controller_with_correct_controller() {
         // Set the controller from the correct controller. Should succeed.
         assert!(canister_manager
             .set_controller(
-                controller,
+                canister_change_origin_from_principal(&controller),
                 canister_id,
                 new_controller,
                 &mut state,
+                SMALL_APP_SUBNET_MAX_SIZE,
                 &mut round_limits
             )
             .is_ok());
@@ -2215,7 +2233,7 @@ fn install_canister_with_query_allocation() {
         let sender_subnet_id = subnet_test_id(1);
         let canister_id = canister_manager
             .create_canister(
-                sender,
+                canister_change_origin_from_principal(&sender),
                 sender_subnet_id,
                 *INITIAL_CYCLES,
                 CanisterSettings::default(),
@@ -2286,7 +2304,7 @@ fn create_canister_with_cycles_sender_in_whitelist() {
     let sender = canister_test_id(1).get();
     let canister_id = canister_manager
         .create_canister_with_cycles(
-            sender,
+            canister_change_origin_from_principal(&sender),
             Some(123),
             CanisterSettings::default(),
             None,
@@ -2321,7 +2339,7 @@ fn create_canister_with_specified_id(
     let creator = canister_test_id(1).get();
 
     let creation_result = canister_manager.create_canister_with_cycles(
-        creator,
+        canister_change_origin_from_principal(&creator),
         Some(123),
         CanisterSettings::default(),
         Some(specified_id),
@@ -2452,7 +2470,7 @@ fn installing_a_canister_with_not_enough_memory_allocation_fails() {
         let sender_subnet_id = subnet_test_id(1);
         let canister_id = canister_manager
             .create_canister(
-                sender,
+                canister_change_origin_from_principal(&sender),
                 sender_subnet_id,
                 *INITIAL_CYCLES,
                 CanisterSettings::default(),
@@ -2545,7 +2563,7 @@ fn upgrading_canister_with_not_enough_memory_allocation_fails() {
         let sender_subnet_id = subnet_test_id(1);
         let canister_id = canister_manager
             .create_canister(
-                sender,
+                canister_change_origin_from_principal(&sender),
                 sender_subnet_id,
                 *INITIAL_CYCLES,
                 CanisterSettings::default(),
@@ -2669,7 +2687,7 @@ fn installing_a_canister_with_not_enough_cycles_fails() {
         let sender_subnet_id = subnet_test_id(1);
         let canister_id = canister_manager
             .create_canister(
-                sender,
+                canister_change_origin_from_principal(&sender),
                 sender_subnet_id,
                 // Give the new canister a relatively small number of cycles so it doesn't have
                 // enough to be installed.
@@ -2778,7 +2796,7 @@ fn failed_upgrade_hooks_consume_instructions() {
         let sender = canister_test_id(100).get();
         let canister_id = canister_manager
             .create_canister(
-                sender,
+                canister_change_origin_from_principal(&sender),
                 subnet_id,
                 *INITIAL_CYCLES,
                 CanisterSettings::default(),
@@ -2793,7 +2811,7 @@ fn failed_upgrade_hooks_consume_instructions() {
         let res = install_code(
             &canister_manager,
             InstallCodeContext {
-                sender,
+                origin: canister_change_origin_from_principal(&sender),
                 canister_id,
                 wasm_module: CanisterModule::new(initial_wasm),
                 arg: vec![],
@@ -2818,7 +2836,7 @@ fn failed_upgrade_hooks_consume_instructions() {
         let (instructions_left, result, _) = install_code(
             &canister_manager,
             InstallCodeContext {
-                sender,
+                origin: canister_change_origin_from_principal(&sender),
                 canister_id,
                 wasm_module: CanisterModule::new(upgrade_wasm),
                 arg: vec![],
@@ -2920,7 +2938,7 @@ fn failed_install_hooks_consume_instructions() {
         let sender = canister_test_id(100).get();
         let canister_id = canister_manager
             .create_canister(
-                sender,
+                canister_change_origin_from_principal(&sender),
                 subnet_id,
                 *INITIAL_CYCLES,
                 CanisterSettings::default(),
@@ -2936,7 +2954,7 @@ fn failed_install_hooks_consume_instructions() {
         let (instructions_left, result, _) = install_code(
             &canister_manager,
             InstallCodeContext {
-                sender,
+                origin: canister_change_origin_from_principal(&sender),
                 canister_id,
                 wasm_module: CanisterModule::new(wasm),
                 arg: vec![],
@@ -3004,7 +3022,7 @@ fn install_code_respects_instruction_limit() {
     let sender = canister_test_id(100).get();
     let canister_id = canister_manager
         .create_canister(
-            sender,
+            canister_change_origin_from_principal(&sender),
             subnet_id,
             *INITIAL_CYCLES,
             CanisterSettings::default(),
@@ -3052,7 +3070,7 @@ fn install_code_respects_instruction_limit() {
     let (instructions_left, result, canister) = install_code(
         &canister_manager,
         InstallCodeContext {
-            sender,
+            origin: canister_change_origin_from_principal(&sender),
             canister_id,
             wasm_module: CanisterModule::new(wasm.clone()),
             arg: vec![],
@@ -3083,7 +3101,7 @@ fn install_code_respects_instruction_limit() {
     let (instructions_left, result, canister) = install_code(
         &canister_manager,
         InstallCodeContext {
-            sender,
+            origin: canister_change_origin_from_principal(&sender),
             canister_id,
             wasm_module: CanisterModule::new(wasm.clone()),
             arg: vec![],
@@ -3108,7 +3126,7 @@ fn install_code_respects_instruction_limit() {
     let (instructions_left, result, canister) = install_code(
         &canister_manager,
         InstallCodeContext {
-            sender,
+            origin: canister_change_origin_from_principal(&sender),
             canister_id,
             wasm_module: CanisterModule::new(wasm.clone()),
             arg: vec![],
@@ -3139,7 +3157,7 @@ fn install_code_respects_instruction_limit() {
     let (instructions_left, result, _) = install_code(
         &canister_manager,
         InstallCodeContext {
-            sender,
+            origin: canister_change_origin_from_principal(&sender),
             canister_id,
             wasm_module: CanisterModule::new(wasm),
             arg: vec![],
@@ -3348,7 +3366,7 @@ fn lower_memory_allocation_than_usage_fails() {
         let sender = canister_test_id(100).get();
         let canister_id = canister_manager
             .create_canister(
-                sender,
+                canister_change_origin_from_principal(&sender),
                 subnet_id,
                 *INITIAL_CYCLES,
                 CanisterSettings::default(),
@@ -3363,7 +3381,7 @@ fn lower_memory_allocation_than_usage_fails() {
         let res = install_code(
             &canister_manager,
             InstallCodeContext {
-                sender,
+                origin: canister_change_origin_from_principal(&sender),
                 canister_id,
                 wasm_module: CanisterModule::new(wasm),
                 arg: vec![],
@@ -3384,12 +3402,22 @@ fn lower_memory_allocation_than_usage_fails() {
             None,
             Some(MemoryAllocation::try_from(NumBytes::from(2)).unwrap()),
             None,
+            None,
+            None,
+            None,
         );
 
         let canister = state.canister_state_mut(&canister_id).unwrap();
 
         assert_matches!(
-            canister_manager.update_settings(sender, settings, canister, &mut round_limits,),
+            canister_manager.update_settings(
+                mock_time(),
+                canister_change_origin_from_principal(&sender),
+                settings,
+                canister,
+                SMALL_APP_SUBNET_MAX_SIZE,
+                &mut round_limits
+            ),
             Err(CanisterManagerError::NotEnoughMemoryAllocationGiven { .. })
         );
     })
@@ -3412,10 +3440,13 @@ fn test_install_when_updating_memory_allocation_via_canister_settings() {
             None,
             Some(MemoryAllocation::try_from(NumBytes::from(2)).unwrap()),
             None,
+            None,
+            None,
+            None,
         );
         let canister_id = canister_manager
             .create_canister(
-                sender,
+                canister_change_origin_from_principal(&sender),
                 subnet_id,
                 *INITIAL_CYCLES,
                 settings,
@@ -3431,7 +3462,7 @@ fn test_install_when_updating_memory_allocation_via_canister_settings() {
         let res = install_code(
             &canister_manager,
             InstallCodeContext {
-                sender,
+                origin: canister_change_origin_from_principal(&sender),
                 canister_id,
                 wasm_module: CanisterModule::new(wasm.clone()),
                 arg: vec![],
@@ -3457,18 +3488,28 @@ fn test_install_when_updating_memory_allocation_via_canister_settings() {
             None,
             Some(MemoryAllocation::try_from(NumBytes::from(MEMORY_CAPACITY.get() / 2)).unwrap()),
             None,
+            None,
+            None,
+            None,
         );
 
         let canister = state.canister_state_mut(&canister_id).unwrap();
 
         canister_manager
-            .update_settings(sender, settings, canister, &mut round_limits)
+            .update_settings(
+                mock_time(),
+                canister_change_origin_from_principal(&sender),
+                settings,
+                canister,
+                SMALL_APP_SUBNET_MAX_SIZE,
+                &mut round_limits,
+            )
             .unwrap();
 
         install_code(
             &canister_manager,
             InstallCodeContext {
-                sender,
+                origin: canister_change_origin_from_principal(&sender),
                 canister_id,
                 wasm_module: CanisterModule::new(wasm),
                 arg: vec![],
@@ -3502,6 +3543,9 @@ fn test_upgrade_when_updating_memory_allocation_via_canister_settings() {
                 MemoryAllocation::try_from(NumBytes::from(WASM_PAGE_SIZE_IN_BYTES + 100)).unwrap(),
             ),
             None,
+            None,
+            None,
+            None,
         );
         let wat = r#"
         (module
@@ -3510,7 +3554,7 @@ fn test_upgrade_when_updating_memory_allocation_via_canister_settings() {
         let wasm = wabt::wat2wasm(wat).unwrap();
         let canister_id = canister_manager
             .create_canister(
-                sender,
+                canister_change_origin_from_principal(&sender),
                 subnet_id,
                 *INITIAL_CYCLES,
                 settings,
@@ -3525,7 +3569,7 @@ fn test_upgrade_when_updating_memory_allocation_via_canister_settings() {
         let res = install_code(
             &canister_manager,
             InstallCodeContext {
-                sender,
+                origin: canister_change_origin_from_principal(&sender),
                 canister_id,
                 wasm_module: CanisterModule::new(wasm),
                 arg: vec![],
@@ -3551,7 +3595,7 @@ fn test_upgrade_when_updating_memory_allocation_via_canister_settings() {
         let res = install_code(
             &canister_manager,
             InstallCodeContext {
-                sender,
+                origin: canister_change_origin_from_principal(&sender),
                 canister_id,
                 wasm_module: CanisterModule::new(wasm.clone()),
                 arg: vec![],
@@ -3580,18 +3624,28 @@ fn test_upgrade_when_updating_memory_allocation_via_canister_settings() {
                     .unwrap(),
             ),
             None,
+            None,
+            None,
+            None,
         );
 
         let canister = state.canister_state_mut(&canister_id).unwrap();
 
         canister_manager
-            .update_settings(sender, settings, canister, &mut round_limits)
+            .update_settings(
+                mock_time(),
+                canister_change_origin_from_principal(&sender),
+                settings,
+                canister,
+                SMALL_APP_SUBNET_MAX_SIZE,
+                &mut round_limits,
+            )
             .unwrap();
 
         install_code(
             &canister_manager,
             InstallCodeContext {
-                sender,
+                origin: canister_change_origin_from_principal(&sender),
                 canister_id,
                 wasm_module: CanisterModule::new(wasm),
                 arg: vec![],
@@ -3632,8 +3686,8 @@ fn uninstall_code_can_be_invoked_by_governance_canister() {
 
     canister_manager
         .uninstall_code(
+            canister_change_origin_from_principal(&GOVERNANCE_CANISTER_ID.get()),
             canister_test_id(0),
-            GOVERNANCE_CANISTER_ID.get(),
             &mut state,
         )
         .unwrap();
@@ -3659,10 +3713,10 @@ fn test_install_when_setting_memory_allocation_to_zero() {
         let wasm = ic_test_utilities::universal_canister::UNIVERSAL_CANISTER_WASM.to_vec();
 
         let sender = canister_test_id(100).get();
-        let settings = CanisterSettings::new(None, None, None, None, None);
+        let settings = CanisterSettings::new(None, None, None, None, None, None, None, None);
         let canister_id = canister_manager
             .create_canister(
-                sender,
+                canister_change_origin_from_principal(&sender),
                 subnet_id,
                 *INITIAL_CYCLES,
                 settings,
@@ -3681,16 +3735,21 @@ fn test_install_when_setting_memory_allocation_to_zero() {
             None,
             Some(MemoryAllocation::try_from(NumBytes::from(0)).unwrap()),
             None,
+            None,
+            None,
+            None,
         );
 
         let canister = state.canister_state_mut(&canister_id).unwrap();
 
         canister_manager
             .update_settings(
-                sender,
+                mock_time(),
+                canister_change_origin_from_principal(&sender),
                 settings,
                 canister,
                 //memory_allocation_used,
+                SMALL_APP_SUBNET_MAX_SIZE,
                 &mut round_limits,
             )
             .unwrap();
@@ -3698,7 +3757,7 @@ fn test_install_when_setting_memory_allocation_to_zero() {
         install_code(
             &canister_manager,
             InstallCodeContext {
-                sender,
+                origin: canister_change_origin_from_principal(&sender),
                 canister_id,
                 wasm_module: CanisterModule::new(wasm),
                 arg: vec![],
@@ -3732,10 +3791,13 @@ fn test_upgrade_when_setting_memory_allocation_to_zero() {
             None,
             Some(MemoryAllocation::try_from(NumBytes::from(MEMORY_CAPACITY.get() / 2)).unwrap()),
             None,
+            None,
+            None,
+            None,
         );
         let canister_id = canister_manager
             .create_canister(
-                sender,
+                canister_change_origin_from_principal(&sender),
                 subnet_id,
                 *INITIAL_CYCLES,
                 settings,
@@ -3750,7 +3812,7 @@ fn test_upgrade_when_setting_memory_allocation_to_zero() {
         let res = install_code(
             &canister_manager,
             InstallCodeContext {
-                sender,
+                origin: canister_change_origin_from_principal(&sender),
                 canister_id,
                 wasm_module: CanisterModule::new(wasm.clone()),
                 arg: vec![],
@@ -3772,18 +3834,28 @@ fn test_upgrade_when_setting_memory_allocation_to_zero() {
             None,
             Some(MemoryAllocation::try_from(NumBytes::from(0)).unwrap()),
             None,
+            None,
+            None,
+            None,
         );
 
         let canister = state.canister_state_mut(&canister_id).unwrap();
 
         canister_manager
-            .update_settings(sender, settings, canister, &mut round_limits)
+            .update_settings(
+                mock_time(),
+                canister_change_origin_from_principal(&sender),
+                settings,
+                canister,
+                SMALL_APP_SUBNET_MAX_SIZE,
+                &mut round_limits,
+            )
             .unwrap();
 
         install_code(
             &canister_manager,
             InstallCodeContext {
-                sender,
+                origin: canister_change_origin_from_principal(&sender),
                 canister_id,
                 wasm_module: CanisterModule::new(wasm),
                 arg: vec![],
@@ -3814,7 +3886,7 @@ fn max_number_of_canisters_is_respected_when_creating_canisters() {
         // Create 3 canisters with `max_number_of_canisters = 3`, should succeed.
         canister_manager
             .create_canister(
-                sender,
+                canister_change_origin_from_principal(&sender),
                 sender_subnet_id,
                 *INITIAL_CYCLES,
                 CanisterSettings::default(),
@@ -3827,7 +3899,7 @@ fn max_number_of_canisters_is_respected_when_creating_canisters() {
             .unwrap();
         canister_manager
             .create_canister(
-                sender,
+                canister_change_origin_from_principal(&sender),
                 sender_subnet_id,
                 *INITIAL_CYCLES,
                 CanisterSettings::default(),
@@ -3840,7 +3912,7 @@ fn max_number_of_canisters_is_respected_when_creating_canisters() {
             .unwrap();
         canister_manager
             .create_canister(
-                sender,
+                canister_change_origin_from_principal(&sender),
                 sender_subnet_id,
                 *INITIAL_CYCLES,
                 CanisterSettings::default(),
@@ -3856,7 +3928,7 @@ fn max_number_of_canisters_is_respected_when_creating_canisters() {
         // Creating a fourth canister with 3 already created and
         // `max_number_of_canisters = 3` should fail.
         let (res, _) = canister_manager.create_canister(
-            sender,
+            canister_change_origin_from_principal(&sender),
             sender_subnet_id,
             *INITIAL_CYCLES,
             CanisterSettings::default(),
@@ -3874,7 +3946,7 @@ fn max_number_of_canisters_is_respected_when_creating_canisters() {
         // `max_number_of_canisters = 10` should succeed.
         canister_manager
             .create_canister(
-                sender,
+                canister_change_origin_from_principal(&sender),
                 sender_subnet_id,
                 *INITIAL_CYCLES,
                 CanisterSettings::default(),
@@ -4068,6 +4140,7 @@ fn install_code_context_conversion_u128() {
         compute_allocation: Some(candid::Nat::from(u128::MAX)),
         memory_allocation: Some(candid::Nat::from(u128::MAX)),
         query_allocation: Some(candid::Nat::from(u128::MAX)),
+        sender_canister_version: None,
     };
 
     assert!(InstallCodeContext::try_from((
@@ -4091,6 +4164,7 @@ fn unfreezing_of_frozen_canister() {
             freezing_threshold: Some(candid::Nat::from(1_000_000_000_000_u64)),
             ..Default::default()
         },
+        sender_canister_version: None,
     }
     .encode();
     let balance_before = test.canister_state(canister_id).system_state.balance();
@@ -4113,6 +4187,7 @@ fn unfreezing_of_frozen_canister() {
             freezing_threshold: Some(candid::Nat::from(0_u64)),
             ..Default::default()
         },
+        sender_canister_version: None,
     }
     .encode();
     let ingress_bytes =
@@ -4146,6 +4221,7 @@ fn create_canister_fails_if_memory_capacity_exceeded() {
     };
     let args = CreateCanisterArgs {
         settings: Some(settings),
+        sender_canister_version: None,
     };
     let create_canister = wasm()
         .call_with_cycles(
@@ -4169,6 +4245,7 @@ fn create_canister_fails_if_memory_capacity_exceeded() {
     };
     let args = CreateCanisterArgs {
         settings: Some(settings),
+        sender_canister_version: None,
     };
     let create_canister = wasm()
         .call_with_cycles(
@@ -4208,6 +4285,7 @@ fn create_canister_makes_subnet_oversubscribed() {
     };
     let args = CreateCanisterArgs {
         settings: Some(settings),
+        sender_canister_version: None,
     };
     let create_canister = wasm()
         .call_with_cycles(
@@ -4229,6 +4307,7 @@ fn create_canister_makes_subnet_oversubscribed() {
     };
     let args = CreateCanisterArgs {
         settings: Some(settings),
+        sender_canister_version: None,
     };
     let create_canister = wasm()
         .call_with_cycles(
@@ -4251,6 +4330,7 @@ fn create_canister_makes_subnet_oversubscribed() {
     };
     let args = CreateCanisterArgs {
         settings: Some(settings),
+        sender_canister_version: None,
     };
     let create_canister = wasm()
         .call_with_cycles(
@@ -4289,6 +4369,7 @@ fn update_settings_makes_subnet_oversubscribed() {
             compute_allocation: Some(candid::Nat::from(50_u32)),
             ..Default::default()
         },
+        sender_canister_version: None,
     };
     test.subnet_message(Method::UpdateSettings, args.encode())
         .unwrap();
@@ -4299,6 +4380,7 @@ fn update_settings_makes_subnet_oversubscribed() {
             compute_allocation: Some(candid::Nat::from(25_u32)),
             ..Default::default()
         },
+        sender_canister_version: None,
     };
     test.subnet_message(Method::UpdateSettings, args.encode())
         .unwrap();
@@ -4310,6 +4392,7 @@ fn update_settings_makes_subnet_oversubscribed() {
             compute_allocation: Some(candid::Nat::from(30_u32)),
             ..Default::default()
         },
+        sender_canister_version: None,
     };
     let err = test
         .subnet_message(Method::UpdateSettings, args.encode())
@@ -4323,6 +4406,7 @@ fn update_settings_makes_subnet_oversubscribed() {
             memory_allocation: Some(candid::Nat::from(10 * 1024 * 1024)),
             ..Default::default()
         },
+        sender_canister_version: None,
     };
     test.subnet_message(Method::UpdateSettings, args.encode())
         .unwrap();
@@ -4333,6 +4417,7 @@ fn update_settings_makes_subnet_oversubscribed() {
             memory_allocation: Some(candid::Nat::from(30 * 1024 * 1024)),
             ..Default::default()
         },
+        sender_canister_version: None,
     };
     test.subnet_message(Method::UpdateSettings, args.encode())
         .unwrap();
@@ -4344,6 +4429,7 @@ fn update_settings_makes_subnet_oversubscribed() {
             memory_allocation: Some(candid::Nat::from(65 * 1024 * 1024)),
             ..Default::default()
         },
+        sender_canister_version: None,
     };
     let err = test
         .subnet_message(Method::UpdateSettings, args.encode())
@@ -4387,6 +4473,7 @@ fn create_canister_when_compute_capacity_is_oversubscribed() {
     };
     let args = CreateCanisterArgs {
         settings: Some(settings),
+        sender_canister_version: None,
     };
     let create_canister = wasm()
         .call_with_cycles(
@@ -4409,6 +4496,7 @@ fn create_canister_when_compute_capacity_is_oversubscribed() {
     };
     let args = CreateCanisterArgs {
         settings: Some(settings),
+        sender_canister_version: None,
     };
     let create_canister = wasm()
         .call_with_cycles(
@@ -4515,6 +4603,7 @@ fn update_settings_when_compute_capacity_is_oversubscribed() {
             compute_allocation: Some(candid::Nat::from(61_u32)),
             ..Default::default()
         },
+        sender_canister_version: None,
     };
     let err = test
         .subnet_message(Method::UpdateSettings, args.encode())
@@ -4534,6 +4623,7 @@ fn update_settings_when_compute_capacity_is_oversubscribed() {
             compute_allocation: Some(candid::Nat::from(60_u32)),
             ..Default::default()
         },
+        sender_canister_version: None,
     };
     test.subnet_message(Method::UpdateSettings, args.encode())
         .unwrap();
@@ -4551,6 +4641,7 @@ fn update_settings_when_compute_capacity_is_oversubscribed() {
             compute_allocation: Some(candid::Nat::from(59_u32)),
             ..Default::default()
         },
+        sender_canister_version: None,
     };
     test.subnet_message(Method::UpdateSettings, args.encode())
         .unwrap();
@@ -5063,3 +5154,650 @@ fn install_code_cannot_switch_from_reserved_to_best_effort_memory_allocation() {
         NumWasmPages::from(10)
     )
 }
+
+fn take_snapshot(
+    test: &mut ExecutionTest,
+    canister_id: CanisterId,
+    replace_snapshot: Option<Vec<u8>>,
+) -> Result<CanisterSnapshotResponse, UserError> {
+    test.take_canister_snapshot(TakeCanisterSnapshotArgs::new(canister_id, replace_snapshot))
+        .map(|result| CanisterSnapshotResponse::decode(&get_reply(Ok(result))).unwrap())
+}
+
+fn list_snapshots(
+    test: &mut ExecutionTest,
+    canister_id: CanisterId,
+) -> Vec<CanisterSnapshotResponse> {
+    let reply = get_reply(test.list_canister_snapshots(canister_id));
+    ListCanisterSnapshotsResponse::decode(&reply).unwrap()
+}
+
+#[test]
+fn load_canister_snapshot_restores_stable_memory_and_certified_data() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.universal_canister().unwrap();
+    let update = wasm()
+        .stable_grow(1)
+        .stable_write(42, &[1, 2, 3])
+        .reply()
+        .build();
+    test.ingress(canister_id, "update", update).unwrap();
+    test.canister_state_mut(canister_id)
+        .system_state
+        .certified_data = vec![4, 5, 6];
+
+    let snapshot = take_snapshot(&mut test, canister_id, None).unwrap();
+    assert_eq!(
+        snapshot.taken_at_timestamp,
+        test.state().time().as_nanos_since_unix_epoch()
+    );
+    assert!(snapshot.total_size > 0);
+    assert_eq!(
+        test.canister_state(canister_id)
+            .system_state
+            .snapshots_memory_usage,
+        NumBytes::from(snapshot.total_size)
+    );
+
+    let update = wasm().stable_write(42, &[7, 8, 9]).reply().build();
+    test.ingress(canister_id, "update", update).unwrap();
+    test.canister_state_mut(canister_id)
+        .system_state
+        .certified_data = vec![7, 8, 9];
+
+    let version_before_load = test
+        .canister_state(canister_id)
+        .system_state
+        .canister_version;
+    test.load_canister_snapshot(CanisterSnapshotArgs::new(canister_id, snapshot.id))
+        .unwrap();
+    let canister = test.canister_state(canister_id);
+    assert_eq!(
+        canister.system_state.canister_version,
+        version_before_load + 1
+    );
+    assert_eq!(canister.system_state.certified_data, vec![4, 5, 6]);
+
+    let query = wasm().stable_read(42, 3).append_and_reply().build();
+    let result = test.ingress(canister_id, "query", query);
+    assert_eq!(get_reply(result), vec![1, 2, 3]);
+}
+
+#[test]
+fn take_canister_snapshot_charges_cycles_and_accounts_memory() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.universal_canister().unwrap();
+    let balance_before = test.canister_state(canister_id).system_state.balance();
+    let memory_usage_before = test
+        .canister_state(canister_id)
+        .memory_usage(SubnetType::Application);
+
+    let snapshot = take_snapshot(&mut test, canister_id, None).unwrap();
+
+    let canister = test.canister_state(canister_id);
+    assert!(canister.system_state.balance() < balance_before);
+    assert_eq!(
+        canister.memory_usage(SubnetType::Application),
+        memory_usage_before + NumBytes::from(snapshot.total_size)
+    );
+}
+
+#[test]
+fn take_canister_snapshot_fails_when_limit_is_reached() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.universal_canister().unwrap();
+    for _ in 0..MAX_NUMBER_OF_SNAPSHOTS_PER_CANISTER {
+        take_snapshot(&mut test, canister_id, None).unwrap();
+    }
+
+    let err = take_snapshot(&mut test, canister_id, None).unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterRejectedMessage);
+
+    // Replacing an existing snapshot is still possible.
+    let old_snapshot = list_snapshots(&mut test, canister_id).pop().unwrap();
+    let new_snapshot =
+        take_snapshot(&mut test, canister_id, Some(old_snapshot.id.clone())).unwrap();
+    let snapshots = list_snapshots(&mut test, canister_id);
+    assert_eq!(snapshots.len(), MAX_NUMBER_OF_SNAPSHOTS_PER_CANISTER);
+    assert!(snapshots.contains(&new_snapshot));
+    assert!(!snapshots.contains(&old_snapshot));
+}
+
+#[test]
+fn take_canister_snapshot_of_empty_canister_fails() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.create_canister(Cycles::new(1_000_000_000_000));
+    let err = take_snapshot(&mut test, canister_id, None).unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterWasmModuleNotFound);
+}
+
+#[test]
+fn canister_snapshot_cannot_be_used_by_another_canister() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id_1 = test.universal_canister().unwrap();
+    let canister_id_2 = test.universal_canister().unwrap();
+    let snapshot = take_snapshot(&mut test, canister_id_1, None).unwrap();
+
+    let err = test
+        .load_canister_snapshot(CanisterSnapshotArgs::new(
+            canister_id_2,
+            snapshot.id.clone(),
+        ))
+        .unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterRejectedMessage);
+
+    let err = test
+        .delete_canister_snapshot(CanisterSnapshotArgs::new(canister_id_2, snapshot.id))
+        .unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterRejectedMessage);
+}
+
+#[test]
+fn delete_canister_snapshot_releases_memory() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.universal_canister().unwrap();
+    let snapshot = take_snapshot(&mut test, canister_id, None).unwrap();
+
+    test.delete_canister_snapshot(CanisterSnapshotArgs::new(canister_id, snapshot.id.clone()))
+        .unwrap();
+    assert!(list_snapshots(&mut test, canister_id).is_empty());
+    assert_eq!(
+        test.canister_state(canister_id)
+            .system_state
+            .snapshots_memory_usage,
+        NumBytes::from(0)
+    );
+
+    let err = test
+        .load_canister_snapshot(CanisterSnapshotArgs::new(canister_id, snapshot.id))
+        .unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterSnapshotNotFound);
+}
+
+#[test]
+fn delete_canister_removes_its_snapshots() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.universal_canister().unwrap();
+    take_snapshot(&mut test, canister_id, None).unwrap();
+
+    test.stop_canister(canister_id);
+    test.process_stopping_canisters();
+    test.subnet_message(
+        Method::DeleteCanister,
+        CanisterIdRecord::from(canister_id).encode(),
+    )
+    .unwrap();
+    assert_eq!(test.state().canister_snapshots.iter().count(), 0);
+}
+
+fn upload_chunk(test: &mut ExecutionTest, canister_id: CanisterId, chunk: &[u8]) -> Vec<u8> {
+    let reply = get_reply(test.upload_chunk(canister_id, chunk.to_vec()));
+    UploadChunkReply::decode(&reply).unwrap().hash
+}
+
+fn upload_chunk_from(
+    test: &mut ExecutionTest,
+    caller: CanisterId,
+    canister_id: CanisterId,
+    chunk: &[u8],
+) -> Vec<u8> {
+    let args = UploadChunkArgs {
+        canister_id: canister_id.get(),
+        chunk: chunk.to_vec(),
+    };
+    let payload = wasm()
+        .call_simple(
+            IC_00,
+            Method::UploadChunk,
+            call_args().other_side(args.encode()),
+        )
+        .build();
+    let reply = get_reply(test.ingress(caller, "update", payload));
+    UploadChunkReply::decode(&reply).unwrap().hash
+}
+
+fn stored_chunks(test: &mut ExecutionTest, canister_id: CanisterId) -> Vec<Vec<u8>> {
+    let reply = get_reply(test.stored_chunks(canister_id));
+    StoredChunksReply::decode(&reply)
+        .unwrap()
+        .0
+        .into_iter()
+        .map(|chunk| chunk.hash)
+        .collect()
+}
+
+#[test]
+fn upload_chunk_accounts_memory_and_clear_chunk_store_releases_it() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.create_canister(Cycles::new(1_000_000_000_000));
+    let memory_usage_before = test
+        .canister_state(canister_id)
+        .memory_usage(SubnetType::Application);
+
+    let hash = upload_chunk(&mut test, canister_id, &[1, 2, 3]);
+    assert_eq!(hash, ic_crypto_sha::Sha256::hash(&[1, 2, 3]).to_vec());
+    // Uploading the same chunk again does not use more memory.
+    assert_eq!(upload_chunk(&mut test, canister_id, &[1, 2, 3]), hash);
+    assert_eq!(stored_chunks(&mut test, canister_id), vec![hash]);
+    assert_eq!(
+        test.canister_state(canister_id)
+            .memory_usage(SubnetType::Application),
+        memory_usage_before + NumBytes::from(wasm_chunk_store::CHUNK_SIZE)
+    );
+
+    test.clear_chunk_store(canister_id).unwrap();
+    assert!(stored_chunks(&mut test, canister_id).is_empty());
+    assert_eq!(
+        test.canister_state(canister_id)
+            .memory_usage(SubnetType::Application),
+        memory_usage_before
+    );
+}
+
+#[test]
+fn upload_chunk_fails_for_oversized_chunk() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.create_canister(Cycles::new(1_000_000_000_000));
+    let chunk = vec![0; wasm_chunk_store::CHUNK_SIZE as usize + 1];
+    let err = test.upload_chunk(canister_id, chunk).unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterContractViolation);
+}
+
+#[test]
+fn upload_chunk_reserves_cycles_above_subnet_memory_threshold() {
+    let mut test = ExecutionTestBuilder::new()
+        .with_subnet_total_memory(100 * 1024 * 1024)
+        .with_subnet_memory_threshold(0)
+        .build();
+    let canister_id = test.create_canister(Cycles::new(1_000_000_000_000));
+    assert_eq!(
+        test.canister_state(canister_id)
+            .system_state
+            .reserved_balance(),
+        Cycles::zero()
+    );
+
+    upload_chunk(&mut test, canister_id, &[1, 2, 3]);
+    assert!(
+        test.canister_state(canister_id)
+            .system_state
+            .reserved_balance()
+            > Cycles::zero()
+    );
+}
+
+#[test]
+fn upload_chunk_fails_if_reserved_cycles_limit_is_exceeded() {
+    let mut test = ExecutionTestBuilder::new()
+        .with_subnet_total_memory(100 * 1024 * 1024)
+        .with_subnet_memory_threshold(0)
+        .build();
+    let canister_id = test.create_canister(Cycles::new(1_000_000_000_000));
+    test.update_reserved_cycles_limit(canister_id, Cycles::zero())
+        .unwrap();
+
+    let err = test.upload_chunk(canister_id, vec![1, 2, 3]).unwrap_err();
+    assert_eq!(
+        err.code(),
+        ErrorCode::ReservedCyclesLimitExceededInMemoryGrow
+    );
+    assert!(stored_chunks(&mut test, canister_id).is_empty());
+    assert_eq!(
+        test.canister_state(canister_id)
+            .system_state
+            .reserved_balance(),
+        Cycles::zero()
+    );
+}
+
+#[test]
+fn upload_chunk_is_not_billed_if_cycles_do_not_cover_reservation_and_charge() {
+    fn setup(cycles: Cycles) -> (ExecutionTest, CanisterId) {
+        let mut test = ExecutionTestBuilder::new()
+            .with_subnet_total_memory(100 * 1024 * 1024)
+            .with_subnet_memory_threshold(0)
+            .build();
+        let canister_id = test.create_canister(cycles);
+        test.update_freezing_threshold(canister_id, NumSeconds::from(0))
+            .unwrap();
+        (test, canister_id)
+    }
+    let initial_cycles = Cycles::new(1_000_000_000_000);
+
+    // Measure the reservation and the charge of a successful upload.
+    let (mut test, canister_id) = setup(initial_cycles);
+    let balance_before = test.canister_state(canister_id).system_state.balance();
+    upload_chunk(&mut test, canister_id, &[1, 2, 3]);
+    let system_state = &test.canister_state(canister_id).system_state;
+    let reservation = system_state.reserved_balance();
+    let charge = balance_before - system_state.balance() - reservation;
+    assert!(reservation > Cycles::zero());
+    assert!(charge > Cycles::zero());
+
+    // The balance covers the reservation, but not the charge on top of it.
+    let balance = reservation + charge - Cycles::new(1);
+    let (mut test, canister_id) = setup(initial_cycles - (balance_before - balance));
+    assert_eq!(
+        test.canister_state(canister_id).system_state.balance(),
+        balance
+    );
+    let subnet_available_memory = test.subnet_available_memory().get_total_memory();
+
+    let err = test.upload_chunk(canister_id, vec![1, 2, 3]).unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterContractViolation);
+    assert!(stored_chunks(&mut test, canister_id).is_empty());
+    let system_state = &test.canister_state(canister_id).system_state;
+    assert_eq!(system_state.balance(), balance);
+    assert_eq!(system_state.reserved_balance(), Cycles::zero());
+    assert_eq!(
+        test.subnet_available_memory().get_total_memory(),
+        subnet_available_memory
+    );
+}
+
+#[test]
+fn install_chunked_code_records_sender_canister_version() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let caller = test.universal_canister().unwrap();
+    let canister_id = test.create_canister(Cycles::new(1_000_000_000_000));
+    test.set_controller(canister_id, caller.get()).unwrap();
+    let hash = upload_chunk_from(&mut test, caller, canister_id, UNIVERSAL_CANISTER_WASM);
+
+    let args = InstallChunkedCodeArgs {
+        sender_canister_version: Some(3),
+        ..InstallChunkedCodeArgs::new(
+            CanisterInstallMode::Install,
+            canister_id,
+            None,
+            vec![hash],
+            ic_crypto_sha::Sha256::hash(UNIVERSAL_CANISTER_WASM).to_vec(),
+            vec![],
+        )
+    };
+    let payload = wasm()
+        .call_simple(
+            IC_00,
+            Method::InstallChunkedCode,
+            call_args().other_side(args.encode()),
+        )
+        .build();
+    get_reply(test.ingress(caller, "update", payload));
+
+    let history = test
+        .canister_state(canister_id)
+        .system_state
+        .get_canister_history();
+    let last_change = history.get_changes(1).next().unwrap();
+    assert_eq!(
+        last_change.origin(),
+        &CanisterChangeOrigin::from_canister(caller.get(), Some(3))
+    );
+}
+
+#[test]
+fn install_chunked_code_installs_assembled_module() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.create_canister(Cycles::new(1_000_000_000_000));
+    let (first, second) = UNIVERSAL_CANISTER_WASM.split_at(UNIVERSAL_CANISTER_WASM.len() / 2);
+    let hashes = vec![
+        upload_chunk(&mut test, canister_id, first),
+        upload_chunk(&mut test, canister_id, second),
+    ];
+
+    test.install_chunked_code(InstallChunkedCodeArgs::new(
+        CanisterInstallMode::Install,
+        canister_id,
+        None,
+        hashes,
+        ic_crypto_sha::Sha256::hash(UNIVERSAL_CANISTER_WASM).to_vec(),
+        vec![],
+    ))
+    .unwrap();
+
+    let result = test.ingress(canister_id, "update", wasm().reply_data(&[42]).build());
+    assert_eq!(get_reply(result), vec![42]);
+}
+
+#[test]
+fn install_chunked_code_fails_on_hash_mismatch_or_missing_chunk() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.create_canister(Cycles::new(1_000_000_000_000));
+    let hash = upload_chunk(&mut test, canister_id, UNIVERSAL_CANISTER_WASM);
+
+    let err = test
+        .install_chunked_code(InstallChunkedCodeArgs::new(
+            CanisterInstallMode::Install,
+            canister_id,
+            None,
+            vec![hash.clone()],
+            vec![0; 32],
+            vec![],
+        ))
+        .unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterContractViolation);
+
+    let err = test
+        .install_chunked_code(InstallChunkedCodeArgs::new(
+            CanisterInstallMode::Install,
+            canister_id,
+            None,
+            vec![hash, vec![0; 32]],
+            ic_crypto_sha::Sha256::hash(UNIVERSAL_CANISTER_WASM).to_vec(),
+            vec![],
+        ))
+        .unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterContractViolation);
+    assert!(test.canister_state(canister_id).execution_state.is_none());
+}
+
+fn canister_info(
+    test: &mut ExecutionTest,
+    caller: CanisterId,
+    canister_id: CanisterId,
+    num_requested_changes: Option<u64>,
+) -> CanisterInfoResponse {
+    let args = CanisterInfoRequest::new(canister_id, num_requested_changes).encode();
+    let payload = wasm()
+        .call_simple(IC_00, Method::CanisterInfo, call_args().other_side(args))
+        .build();
+    let result = test.ingress(caller, "update", payload);
+    CanisterInfoResponse::decode(&get_reply(result)).unwrap()
+}
+
+#[test]
+fn canister_history_records_changes() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let user_id = test.user_id().get();
+    let canister_id = test.create_canister(Cycles::new(1_000_000_000_000));
+    test.install_canister(canister_id, UNIVERSAL_CANISTER_WASM.to_vec())
+        .unwrap();
+    test.upgrade_canister(canister_id, UNIVERSAL_CANISTER_WASM.to_vec())
+        .unwrap();
+    let controller = canister_test_id(42).get();
+    test.set_controller(canister_id, controller).unwrap();
+
+    let module_hash = test
+        .execution_state(canister_id)
+        .wasm_binary
+        .binary
+        .module_hash();
+    let origin = CanisterChangeOrigin::from_user(user_id);
+    let history = test
+        .canister_state(canister_id)
+        .system_state
+        .get_canister_history();
+    assert_eq!(history.get_total_num_changes(), 4);
+    let changes: Vec<_> = history
+        .get_changes(usize::MAX)
+        .map(|change| (change.canister_version(), change.details().clone()))
+        .collect();
+    assert_eq!(
+        changes,
+        vec![
+            (0, CanisterChangeDetails::canister_creation(vec![user_id])),
+            (
+                1,
+                CanisterChangeDetails::code_deployment(CanisterInstallMode::Install, module_hash)
+            ),
+            (
+                2,
+                CanisterChangeDetails::code_deployment(CanisterInstallMode::Upgrade, module_hash)
+            ),
+            (
+                3,
+                CanisterChangeDetails::controllers_change(vec![controller])
+            ),
+        ]
+    );
+    assert!(history
+        .get_changes(usize::MAX)
+        .all(|change| change.origin() == &origin));
+}
+
+#[test]
+fn canister_history_records_uninstall_code() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.universal_canister().unwrap();
+    test.uninstall_code(canister_id).unwrap();
+
+    let history = test
+        .canister_state(canister_id)
+        .system_state
+        .get_canister_history();
+    let last_change = history.get_changes(1).next().unwrap();
+    assert_eq!(
+        last_change.details(),
+        &CanisterChangeDetails::CanisterCodeUninstall
+    );
+    assert_eq!(
+        last_change.canister_version(),
+        test.canister_state(canister_id)
+            .system_state
+            .canister_version
+    );
+}
+
+#[test]
+fn canister_history_records_sender_canister_version() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let caller = test.universal_canister().unwrap();
+    let canister_id = test.universal_canister().unwrap();
+    test.set_controller(canister_id, caller.get()).unwrap();
+
+    let args = UpdateSettingsArgs {
+        canister_id: canister_id.get(),
+        settings: CanisterSettingsArgs {
+            controllers: Some(vec![caller.get(), test.user_id().get()]),
+            ..Default::default()
+        },
+        sender_canister_version: Some(7),
+    };
+    let payload = wasm()
+        .call_simple(
+            IC_00,
+            Method::UpdateSettings,
+            call_args().other_side(args.encode()),
+        )
+        .build();
+    get_reply(test.ingress(caller, "update", payload));
+
+    let history = test
+        .canister_state(canister_id)
+        .system_state
+        .get_canister_history();
+    let last_change = history.get_changes(1).next().unwrap();
+    assert_eq!(
+        last_change.origin(),
+        &CanisterChangeOrigin::from_canister(caller.get(), Some(7))
+    );
+}
+
+#[test]
+fn canister_history_is_not_changed_by_failed_install() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.create_canister(Cycles::new(1_000_000_000_000));
+    let invalid_wasm = vec![0, 1, 2, 3];
+    test.install_canister(canister_id, invalid_wasm)
+        .unwrap_err();
+
+    let history = test
+        .canister_state(canister_id)
+        .system_state
+        .get_canister_history();
+    assert_eq!(history.get_total_num_changes(), 1);
+}
+
+#[test]
+fn canister_history_is_bounded() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let user_id = test.user_id().get();
+    let canister_id = test.create_canister(Cycles::new(1_000_000_000_000));
+    let num_updates = MAX_CANISTER_HISTORY_CHANGES + 5;
+    for _ in 0..num_updates {
+        test.set_controller(canister_id, user_id).unwrap();
+    }
+
+    let history = test
+        .canister_state(canister_id)
+        .system_state
+        .get_canister_history();
+    assert_eq!(history.get_total_num_changes(), num_updates + 1);
+    assert_eq!(
+        history.get_changes(usize::MAX).count() as u64,
+        MAX_CANISTER_HISTORY_CHANGES
+    );
+    // The oldest changes (including the creation) have been dropped.
+    assert!(history.get_changes(usize::MAX).all(|change| matches!(
+        change.details(),
+        CanisterChangeDetails::CanisterControllersChange(_)
+    )));
+}
+
+#[test]
+fn canister_info_returns_recent_changes() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let caller = test.universal_canister().unwrap();
+    let canister_id = test.universal_canister().unwrap();
+    test.set_controller(canister_id, caller.get()).unwrap();
+
+    let info = canister_info(&mut test, caller, canister_id, Some(1));
+    assert_eq!(info.total_num_changes(), 3);
+    assert_eq!(
+        info.changes(),
+        test.canister_state(canister_id)
+            .system_state
+            .get_canister_history()
+            .get_changes(1)
+            .cloned()
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        info.module_hash(),
+        Some(
+            test.execution_state(canister_id)
+                .wasm_binary
+                .binary
+                .module_hash()
+                .to_vec()
+        )
+    );
+    assert_eq!(info.controllers(), vec![caller.get()]);
+
+    // No changes are returned by default.
+    let info = canister_info(&mut test, caller, canister_id, None);
+    assert_eq!(info.total_num_changes(), 3);
+    assert!(info.changes().is_empty());
+}
+
+#[test]
+fn canister_info_cannot_be_called_via_ingress() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.universal_canister().unwrap();
+    let err = test
+        .subnet_message(
+            Method::CanisterInfo,
+            CanisterInfoRequest::new(canister_id, None).encode(),
+        )
+        .unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterContractViolation);
+}
@@ -1,13 +1,17 @@
 use ic_base_types::{NumBytes, NumSeconds};
 use ic_error_types::{ErrorCode, UserError};
-use ic_ic00_types::CanisterSettingsArgs;
+use ic_ic00_types::{CanisterSettingsArgs, LogVisibility};
 use ic_types::{
-    ComputeAllocation, InvalidComputeAllocationError, InvalidMemoryAllocationError,
+    ComputeAllocation, Cycles, InvalidComputeAllocationError, InvalidMemoryAllocationError,
     MemoryAllocation, PrincipalId,
 };
 use num_traits::cast::ToPrimitive;
 use std::convert::TryFrom;
 
+/// The largest Wasm memory limit that can be set, which is the maximum size
+/// of a 64-bit Wasm memory.
+const MAX_WASM_MEMORY_LIMIT: u64 = 1 << 48;
+
 /// Struct used for decoding CanisterSettingsArgs
 #[derive(Default)]
 pub(crate) struct CanisterSettings {
@@ -16,6 +20,9 @@ pub(crate) struct CanisterSettings {
     pub(crate) compute_allocation: Option<ComputeAllocation>,
     pub(crate) memory_allocation: Option<MemoryAllocation>,
     pub(crate) freezing_threshold: Option<NumSeconds>,
+    pub(crate) log_visibility: Option<LogVisibility>,
+    pub(crate) wasm_memory_limit: Option<NumBytes>,
+    pub(crate) reserved_cycles_limit: Option<Cycles>,
 }
 
 impl CanisterSettings {
@@ -25,6 +32,9 @@ impl CanisterSettings {
         compute_allocation: Option<ComputeAllocation>,
         memory_allocation: Option<MemoryAllocation>,
         freezing_threshold: Option<NumSeconds>,
+        log_visibility: Option<LogVisibility>,
+        wasm_memory_limit: Option<NumBytes>,
+        reserved_cycles_limit: Option<Cycles>,
     ) -> Self {
         Self {
             controller,
@@ -32,6 +42,9 @@ impl CanisterSettings {
             compute_allocation,
             memory_allocation,
             freezing_threshold,
+            log_visibility,
+            wasm_memory_limit,
+            reserved_cycles_limit,
         }
     }
 
@@ -54,6 +67,18 @@ impl CanisterSettings {
     pub fn freezing_threshold(&self) -> Option<NumSeconds> {
         self.freezing_threshold
     }
+
+    pub fn log_visibility(&self) -> Option<LogVisibility> {
+        self.log_visibility
+    }
+
+    pub fn wasm_memory_limit(&self) -> Option<NumBytes> {
+        self.wasm_memory_limit
+    }
+
+    pub fn reserved_cycles_limit(&self) -> Option<Cycles> {
+        self.reserved_cycles_limit
+    }
 }
 
 impl TryFrom<CanisterSettingsArgs> for CanisterSettings {
@@ -83,12 +108,32 @@ impl TryFrom<CanisterSettingsArgs> for CanisterSettings {
             None => None,
         };
 
+        let wasm_memory_limit = match input.wasm_memory_limit {
+            Some(limit) => match limit.0.to_u64() {
+                Some(bytes) if bytes <= MAX_WASM_MEMORY_LIMIT => Some(NumBytes::from(bytes)),
+                _ => {
+                    return Err(UpdateSettingsError::WasmMemoryLimitOutOfRange { provided: limit })
+                }
+            },
+            None => None,
+        };
+
+        let reserved_cycles_limit = match input.reserved_cycles_limit {
+            Some(limit) => Some(Cycles::from(limit.0.to_u128().ok_or(
+                UpdateSettingsError::ReservedCyclesLimitOutOfRange { provided: limit },
+            )?)),
+            None => None,
+        };
+
         Ok(CanisterSettings::new(
             input.controller,
             input.controllers,
             compute_allocation,
             memory_allocation,
             freezing_threshold,
+            input.log_visibility,
+            wasm_memory_limit,
+            reserved_cycles_limit,
         ))
     }
 }
@@ -108,6 +153,8 @@ pub enum UpdateSettingsError {
     ComputeAllocation(InvalidComputeAllocationError),
     MemoryAllocation(InvalidMemoryAllocationError),
     FreezingThresholdOutOfRange { provided: candid::Nat },
+    WasmMemoryLimitOutOfRange { provided: candid::Nat },
+    ReservedCyclesLimitOutOfRange { provided: candid::Nat },
 }
 
 impl From<UpdateSettingsError> for UserError {
@@ -136,6 +183,20 @@ impl From<UpdateSettingsError> for UserError {
                     provided
                 ),
             ),
+            UpdateSettingsError::WasmMemoryLimitOutOfRange { provided } => UserError::new(
+                ErrorCode::CanisterContractViolation,
+                format!(
+                    "Wasm memory limit expected to be in the range of [0..2^48], got {}",
+                    provided
+                ),
+            ),
+            UpdateSettingsError::ReservedCyclesLimitOutOfRange { provided } => UserError::new(
+                ErrorCode::CanisterContractViolation,
+                format!(
+                    "Reserved cycles limit expected to be in the range of [0..2^128-1], got {}",
+                    provided
+                ),
+            ),
         }
     }
 }
@@ -18,7 +18,7 @@ use ic_system_api::sandbox_safe_system_state::SystemStateChanges;
 use ic_types::ingress::{IngressState, IngressStatus, WasmResult};
 use ic_types::messages::{CallContextId, CallbackId, MessageId, Payload, RejectContext, Response};
 use ic_types::methods::{Callback, WasmMethod};
-use ic_types::{Cycles, MemoryAllocation, NumInstructions, Time, UserId};
+use ic_types::{CoarseTime, Cycles, MemoryAllocation, NumInstructions, Time, UserId};
 
 use crate::execution_environment::ExecutionResponse;
 use crate::{as_round_instructions, ExecuteMessageResult, RoundLimits};
@@ -53,8 +53,8 @@ pub(crate) fn action_to_response(
             time,
             log,
         ),
-        CallOrigin::CanisterUpdate(caller_canister_id, callback_id) => {
-            action_to_request_response(canister, action, caller_canister_id, callback_id)
+        CallOrigin::CanisterUpdate(caller_canister_id, callback_id, deadline) => {
+            action_to_request_response(canister, action, caller_canister_id, callback_id, deadline)
         }
         CallOrigin::CanisterQuery(_, _) | CallOrigin::Query(_) => fatal!(
             log,
@@ -75,6 +75,7 @@ pub(crate) fn action_to_request_response(
     action: CallContextAction,
     originator: CanisterId,
     reply_callback_id: CallbackId,
+    deadline: CoarseTime,
 ) -> ExecutionResponse {
     let response_payload_and_refund = match action {
         CallContextAction::NotYetResponded | CallContextAction::AlreadyResponded => None,
@@ -115,6 +116,7 @@ pub(crate) fn action_to_request_response(
             originator_reply_callback: reply_callback_id,
             refund,
             response_payload,
+            deadline,
         })
     } else {
         ExecutionResponse::Empty
@@ -224,13 +226,14 @@ pub(crate) fn wasm_result_to_query_response(
         CallOrigin::Ingress(user_id, message_id) => {
             wasm_result_to_ingress_response(result, canister, user_id, message_id, time)
         }
-        CallOrigin::CanisterUpdate(caller_canister_id, callback_id) => {
+        CallOrigin::CanisterUpdate(caller_canister_id, callback_id, deadline) => {
             let response = Response {
                 originator: caller_canister_id,
                 respondent: canister.canister_id(),
                 originator_reply_callback: callback_id,
                 refund: Cycles::zero(),
                 response_payload: Payload::from(result),
+                deadline,
             };
             ExecutionResponse::Request(response)
         }
@@ -325,7 +328,11 @@ pub fn get_call_context_and_callback(
             // A canister by definition can only be stopped when no open call contexts.
             // Hence, if we receive a response for a stopped canister then that is
             // a either a bug in the code or potentially a faulty (or
-            // malicious) subnet generating spurious messages.
+            // malicious) subnet generating spurious messages. Best-effort
+            // responses are the exception, they may legitimately arrive late.
+            if response.is_best_effort() {
+                return None;
+            }
             error!(
                 logger,
                 "[EXC-BUG] Stopped canister got a response.  originator {} respondent {}.",
@@ -345,7 +352,11 @@ pub fn get_call_context_and_callback(
     let callback = match call_context_manager.peek_callback(callback_id) {
         Some(callback) => callback.clone(),
         None => {
-            // Received an unknown callback ID. Nothing to do.
+            // Received an unknown callback ID. Nothing to do. Expected for
+            // best-effort responses that arrive after the callback was closed.
+            if response.is_best_effort() {
+                return None;
+            }
             error!(
                 logger,
                 "[EXC-BUG] Canister got a response with unknown callback ID {}.  originator {} respondent {}.",
@@ -475,6 +486,10 @@ pub fn apply_canister_state_changes(
             }
         }
     }
+    // The log records are kept even if the execution failed.
+    system_state
+        .canister_log
+        .append_delta_log(&mut output.canister_log);
 }
 
 pub(crate) fn finish_call_with_error(
@@ -490,6 +505,7 @@ pub(crate) fn finish_call_with_error(
                 originator: request.sender,
                 respondent: canister.canister_id(),
                 originator_reply_callback: request.sender_reply_callback,
+                deadline: request.deadline,
                 refund: request.payment,
                 response_payload: Payload::from(Err(user_error)),
             };
@@ -17,6 +17,7 @@ use ic_interfaces::messages::RequestOrIngress;
 use ic_logger::{info, warn, ReplicaLogger};
 use ic_replicated_state::{CanisterState, SystemState};
 use ic_system_api::ApiType;
+use ic_types::canister_log::CanisterLog;
 use ic_types::funds::Cycles;
 use ic_types::methods::{FuncRef, SystemMethod, WasmMethod};
 
@@ -79,7 +80,14 @@ pub(crate) fn execute_install(
 
     if let Err(err) = helper.validate_input(&original, round_limits) {
         let instructions_left = helper.instructions_left();
-        return finish_err(clean_canister, instructions_left, original, round, err);
+        return finish_err(
+            clean_canister,
+            instructions_left,
+            original,
+            round,
+            err,
+            helper.canister_log_delta(),
+        );
     }
 
     // Stage 1: create a new execution state based on the new Wasm binary, deactivate global timer, and bump canister version.
@@ -99,7 +107,14 @@ pub(crate) fn execute_install(
         &original,
     ) {
         let instructions_left = helper.instructions_left();
-        return finish_err(clean_canister, instructions_left, original, round, err);
+        return finish_err(
+            clean_canister,
+            instructions_left,
+            original,
+            round,
+            err,
+            helper.canister_log_delta(),
+        );
     }
     helper.deactivate_global_timer();
     helper.bump_canister_version();
@@ -113,7 +128,7 @@ pub(crate) fn execute_install(
         // If the Wasm module does not export the method, then this execution
         // succeeds as a no-op.
         install_stage_2b_continue_install_after_start(
-            context.sender,
+            context.sender(),
             context.arg,
             clean_canister,
             helper,
@@ -139,7 +154,7 @@ pub(crate) fn execute_install(
                 install_stage_2a_process_start_result(
                     canister_state_changes,
                     output,
-                    context.sender,
+                    context.sender(),
                     context.arg,
                     clean_canister,
                     helper,
@@ -161,7 +176,7 @@ pub(crate) fn execute_install(
                 let paused_execution = Box::new(PausedStartExecutionDuringInstall {
                     paused_wasm_execution,
                     paused_helper: helper.pause(),
-                    context_sender: context.sender,
+                    context_sender: context.sender(),
                     context_arg: context.arg,
                     original,
                 });
@@ -199,7 +214,14 @@ fn install_stage_2a_process_start_result(
 
     if let Err(err) = result {
         let instructions_left = helper.instructions_left();
-        return finish_err(clean_canister, instructions_left, original, round, err);
+        return finish_err(
+            clean_canister,
+            instructions_left,
+            original,
+            round,
+            err,
+            helper.canister_log_delta(),
+        );
     }
 
     install_stage_2b_continue_install_after_start(
@@ -300,7 +322,14 @@ fn install_stage_3_process_init_result(
     );
     if let Err(err) = result {
         let instructions_left = helper.instructions_left();
-        return finish_err(clean_canister, instructions_left, original, round, err);
+        return finish_err(
+            clean_canister,
+            instructions_left,
+            original,
+            round,
+            err,
+            helper.canister_log_delta(),
+        );
     }
     helper.finish(clean_canister, original, round, round_limits)
 }
@@ -342,7 +371,14 @@ impl PausedInstallCodeExecution for PausedInitExecution {
                     err
                 );
                 self.paused_wasm_execution.abort();
-                return finish_err(clean_canister, instructions_left, self.original, round, err);
+                return finish_err(
+                    clean_canister,
+                    instructions_left,
+                    self.original,
+                    round,
+                    err,
+                    CanisterLog::default(),
+                );
             }
         };
 
@@ -437,7 +473,14 @@ impl PausedInstallCodeExecution for PausedStartExecutionDuringInstall {
                     err
                 );
                 self.paused_wasm_execution.abort();
-                return finish_err(clean_canister, instructions_left, self.original, round, err);
+                return finish_err(
+                    clean_canister,
+                    instructions_left,
+                    self.original,
+                    round,
+                    err,
+                    CanisterLog::default(),
+                );
             }
         };
         let execution_state = helper.canister().execution_state.as_ref().unwrap();
@@ -6,7 +6,7 @@ use std::path::{Path, PathBuf};
 use ic_base_types::{CanisterId, NumBytes, PrincipalId};
 use ic_config::flag_status::FlagStatus;
 use ic_embedders::wasm_executor::CanisterStateChanges;
-use ic_ic00_types::CanisterInstallMode;
+use ic_ic00_types::{CanisterChangeDetails, CanisterChangeOrigin, CanisterInstallMode};
 use ic_interfaces::{
     execution_environment::{
         HypervisorError, HypervisorResult, SubnetAvailableMemory, SubnetAvailableMemoryError,
@@ -20,8 +20,8 @@ use ic_state_layout::{CanisterLayout, CheckpointLayout, ReadOnly};
 use ic_sys::PAGE_SIZE;
 use ic_system_api::ExecutionParameters;
 use ic_types::{
-    funds::Cycles, CanisterTimer, ComputeAllocation, Height, MemoryAllocation, NumInstructions,
-    Time,
+    canister_log::CanisterLog, funds::Cycles, CanisterTimer, ComputeAllocation, Height,
+    MemoryAllocation, NumInstructions, Time,
 };
 
 use crate::{
@@ -85,6 +85,8 @@ pub(crate) struct InstallCodeHelper {
     deallocated_bytes: NumBytes,
     // The total heap delta of all steps.
     total_heap_delta: NumBytes,
+    // The index of the first log record added by the steps.
+    canister_log_start_idx: u64,
 }
 
 impl InstallCodeHelper {
@@ -98,6 +100,7 @@ impl InstallCodeHelper {
             allocated_message_bytes: NumBytes::from(0),
             deallocated_bytes: NumBytes::from(0),
             total_heap_delta: NumBytes::from(0),
+            canister_log_start_idx: clean_canister.system_state.canister_log.next_idx(),
         }
     }
 
@@ -125,6 +128,21 @@ impl InstallCodeHelper {
         self.message_instruction_limit - self.instructions_left()
     }
 
+    /// Returns the log records added by the steps so far. They are kept in the
+    /// clean canister state if `install_code` fails.
+    pub fn canister_log_delta(&self) -> CanisterLog {
+        let canister_log = &self.canister.system_state.canister_log;
+        CanisterLog::new(
+            canister_log.next_idx(),
+            canister_log
+                .records()
+                .iter()
+                .filter(|record| record.idx >= self.canister_log_start_idx)
+                .cloned()
+                .collect(),
+        )
+    }
+
     pub fn canister_memory_usage(&self) -> NumBytes {
         self.canister
             .memory_usage(self.execution_parameters.subnet_type)
@@ -207,6 +225,7 @@ impl InstallCodeHelper {
                             requested: requested_total,
                             available: NumBytes::new(available_total.max(0) as u64),
                         },
+                        self.canister_log_delta(),
                     );
                 }
             }
@@ -229,6 +248,7 @@ impl InstallCodeHelper {
                         requested: new_compute_allocation,
                         available: available.max(old_compute_allocation.as_percent()),
                     },
+                    self.canister_log_delta(),
                 );
             }
             round_limits.compute_allocation_used = others + new_compute_allocation.as_percent();
@@ -266,6 +286,13 @@ impl InstallCodeHelper {
 
         let old_wasm_hash = get_wasm_hash(&clean_canister);
         let new_wasm_hash = get_wasm_hash(&self.canister);
+        if let Some(module_hash) = new_wasm_hash {
+            self.canister.system_state.add_canister_change(
+                original.time,
+                original.origin.clone(),
+                CanisterChangeDetails::code_deployment(original.mode, module_hash),
+            );
+        }
         DtsInstallCodeResult::Finished {
             canister: self.canister,
             message: original.message,
@@ -303,7 +330,7 @@ impl InstallCodeHelper {
             config,
         )?;
 
-        validate_controller(&self.canister, &original.sender)?;
+        validate_controller(&self.canister, &original.origin.origin())?;
 
         match original.mode {
             CanisterInstallMode::Install => {
@@ -428,7 +455,7 @@ impl InstallCodeHelper {
     pub fn handle_wasm_execution(
         &mut self,
         canister_state_changes: Option<CanisterStateChanges>,
-        output: WasmExecutionOutput,
+        mut output: WasmExecutionOutput,
         original: &OriginalContext,
         round: &RoundContext,
     ) -> Result<(), CanisterManagerError> {
@@ -437,6 +464,11 @@ impl InstallCodeHelper {
             output: output.clone(),
         });
 
+        self.canister
+            .system_state
+            .canister_log
+            .append_delta_log(&mut output.canister_log);
+
         self.execution_parameters
             .instruction_limits
             .update(output.num_instructions_left);
@@ -562,7 +594,7 @@ pub(crate) struct OriginalContext {
     pub subnet_size: usize,
     pub requested_compute_allocation: Option<ComputeAllocation>,
     pub requested_memory_allocation: Option<MemoryAllocation>,
-    pub sender: PrincipalId,
+    pub origin: CanisterChangeOrigin,
     pub canister_id: CanisterId,
 }
 
@@ -661,17 +693,25 @@ pub(crate) fn canister_layout(
 }
 
 /// Finishes an `install_code` execution early due to an error. The only state
-/// change that is applied to the clean canister state is refunding the prepaid
-/// execution cycles.
+/// changes that are applied to the clean canister state are refunding the
+/// prepaid execution cycles and appending the log records of the failed
+/// execution.
 pub(crate) fn finish_err(
     clean_canister: CanisterState,
     instructions_left: NumInstructions,
     original: OriginalContext,
     round: RoundContext,
     err: CanisterManagerError,
+    mut canister_log: CanisterLog,
 ) -> DtsInstallCodeResult {
     let mut new_canister = clean_canister;
 
+    // The log records are kept even if the execution failed.
+    new_canister
+        .system_state
+        .canister_log
+        .append_delta_log(&mut canister_log);
+
     new_canister
         .system_state
         .apply_cycles_debit(new_canister.canister_id(), round.log);
@@ -3,7 +3,7 @@ use crate::util::process_stopping_canisters;
 use crate::{
     execute_canister, CompilationCostHandling, ExecuteMessageResult, ExecutionEnvironment,
     ExecutionResponse, Hypervisor, IngressHistoryWriterImpl, InternalHttpQueryHandler,
-    RoundInstructions, RoundLimits,
+    QueryStatsCollector, RoundInstructions, RoundLimits,
 };
 use ic_base_types::{NumBytes, NumSeconds, PrincipalId, SubnetId};
 use ic_config::subnet_config::SchedulerConfig;
@@ -17,9 +17,11 @@ use ic_cycles_account_manager::CyclesAccountManager;
 use ic_embedders::{wasm_utils::compile, WasmtimeEmbedder};
 use ic_error_types::{ErrorCode, RejectCode, UserError};
 use ic_ic00_types::{
-    CanisterIdRecord, CanisterInstallMode, CanisterSettingsArgs, CanisterStatusType, EcdsaKeyId,
-    EmptyBlob, InstallCodeArgs, Method, Payload, ProvisionalCreateCanisterWithCyclesArgs,
-    UpdateSettingsArgs,
+    CanisterIdRecord, CanisterInstallMode, CanisterSettingsArgs, CanisterStatusType,
+    DeleteCanisterSnapshotArgs, EcdsaKeyId, EmptyBlob, InstallChunkedCodeArgs, InstallCodeArgs,
+    LoadCanisterSnapshotArgs, LogVisibility, Method, Payload,
+    ProvisionalCreateCanisterWithCyclesArgs, TakeCanisterSnapshotArgs, UpdateSettingsArgs,
+    UploadChunkArgs,
 };
 use ic_interfaces::{
     execution_environment::{
@@ -428,6 +430,7 @@ impl ExecutionTest {
                 memory_allocation,
                 None,
             ),
+            sender_canister_version: None,
         }
         .encode();
         self.subnet_message(Method::UpdateSettings, payload)
@@ -454,6 +457,72 @@ impl ExecutionTest {
         self.subnet_message(Method::UninstallCode, payload)
     }
 
+    /// Sends a `take_canister_snapshot` message to the IC management canister.
+    pub fn take_canister_snapshot(
+        &mut self,
+        args: TakeCanisterSnapshotArgs,
+    ) -> Result<WasmResult, UserError> {
+        self.subnet_message(Method::TakeCanisterSnapshot, args.encode())
+    }
+
+    /// Sends a `load_canister_snapshot` message to the IC management canister.
+    pub fn load_canister_snapshot(
+        &mut self,
+        args: LoadCanisterSnapshotArgs,
+    ) -> Result<WasmResult, UserError> {
+        self.subnet_message(Method::LoadCanisterSnapshot, args.encode())
+    }
+
+    /// Sends a `list_canister_snapshots` message to the IC management canister.
+    pub fn list_canister_snapshots(
+        &mut self,
+        canister_id: CanisterId,
+    ) -> Result<WasmResult, UserError> {
+        let payload = CanisterIdRecord::from(canister_id).encode();
+        self.subnet_message(Method::ListCanisterSnapshots, payload)
+    }
+
+    /// Sends a `delete_canister_snapshot` message to the IC management canister.
+    pub fn delete_canister_snapshot(
+        &mut self,
+        args: DeleteCanisterSnapshotArgs,
+    ) -> Result<WasmResult, UserError> {
+        self.subnet_message(Method::DeleteCanisterSnapshot, args.encode())
+    }
+
+    /// Sends an `upload_chunk` message to the IC management canister.
+    pub fn upload_chunk(
+        &mut self,
+        canister_id: CanisterId,
+        chunk: Vec<u8>,
+    ) -> Result<WasmResult, UserError> {
+        let args = UploadChunkArgs {
+            canister_id: canister_id.get(),
+            chunk,
+        };
+        self.subnet_message(Method::UploadChunk, args.encode())
+    }
+
+    /// Sends a `stored_chunks` message to the IC management canister.
+    pub fn stored_chunks(&mut self, canister_id: CanisterId) -> Result<WasmResult, UserError> {
+        let payload = CanisterIdRecord::from(canister_id).encode();
+        self.subnet_message(Method::StoredChunks, payload)
+    }
+
+    /// Sends a `clear_chunk_store` message to the IC management canister.
+    pub fn clear_chunk_store(&mut self, canister_id: CanisterId) -> Result<WasmResult, UserError> {
+        let payload = CanisterIdRecord::from(canister_id).encode();
+        self.subnet_message(Method::ClearChunkStore, payload)
+    }
+
+    /// Sends an `install_chunked_code` message to the IC management canister.
+    pub fn install_chunked_code(
+        &mut self,
+        args: InstallChunkedCodeArgs,
+    ) -> Result<WasmResult, UserError> {
+        self.subnet_message(Method::InstallChunkedCode, args.encode())
+    }
+
     /// Starts running the given canister.
     /// Consider using higher-level helpers like `canister_from_wat()`.
     pub fn start_canister(&mut self, canister_id: CanisterId) -> Result<WasmResult, UserError> {
@@ -499,6 +568,62 @@ impl ExecutionTest {
                 None,
                 Some(freezing_threshold.get()),
             ),
+            sender_canister_version: None,
+        }
+        .encode();
+        self.subnet_message(Method::UpdateSettings, payload)
+    }
+
+    /// Updates the log visibility of the given canister.
+    pub fn update_log_visibility(
+        &mut self,
+        canister_id: CanisterId,
+        log_visibility: LogVisibility,
+    ) -> Result<WasmResult, UserError> {
+        let payload = UpdateSettingsArgs {
+            canister_id: canister_id.into(),
+            settings: CanisterSettingsArgs {
+                log_visibility: Some(log_visibility),
+                ..CanisterSettingsArgs::new(None, None, None, None, None)
+            },
+            sender_canister_version: None,
+        }
+        .encode();
+        self.subnet_message(Method::UpdateSettings, payload)
+    }
+
+    /// Updates the Wasm memory limit of the given canister. A limit of 0
+    /// removes the limit.
+    pub fn update_wasm_memory_limit(
+        &mut self,
+        canister_id: CanisterId,
+        wasm_memory_limit: NumBytes,
+    ) -> Result<WasmResult, UserError> {
+        let payload = UpdateSettingsArgs {
+            canister_id: canister_id.into(),
+            settings: CanisterSettingsArgs {
+                wasm_memory_limit: Some(candid::Nat::from(wasm_memory_limit.get())),
+                ..CanisterSettingsArgs::new(None, None, None, None, None)
+            },
+            sender_canister_version: None,
+        }
+        .encode();
+        self.subnet_message(Method::UpdateSettings, payload)
+    }
+
+    /// Updates the limit on the reserved cycles balance of the given canister.
+    pub fn update_reserved_cycles_limit(
+        &mut self,
+        canister_id: CanisterId,
+        reserved_cycles_limit: Cycles,
+    ) -> Result<WasmResult, UserError> {
+        let payload = UpdateSettingsArgs {
+            canister_id: canister_id.into(),
+            settings: CanisterSettingsArgs {
+                reserved_cycles_limit: Some(candid::Nat::from(reserved_cycles_limit.get())),
+                ..CanisterSettingsArgs::new(None, None, None, None, None)
+            },
+            sender_canister_version: None,
         }
         .encode();
         self.subnet_message(Method::UpdateSettings, payload)
@@ -513,6 +638,7 @@ impl ExecutionTest {
         let payload = UpdateSettingsArgs {
             canister_id: canister_id.into(),
             settings: CanisterSettingsArgs::new(None, Some(vec![controller]), None, None, None),
+            sender_canister_version: None,
         }
         .encode();
         self.subnet_message(Method::UpdateSettings, payload)
@@ -1269,6 +1395,7 @@ pub struct ExecutionTestBuilder {
     instruction_limit_without_dts: NumInstructions,
     initial_canister_cycles: Cycles,
     subnet_total_memory: i64,
+    subnet_memory_threshold: i64,
     subnet_message_memory: i64,
     registry_settings: RegistryExecutionSettings,
     manual_execution: bool,
@@ -1292,6 +1419,9 @@ impl Default for ExecutionTestBuilder {
         let subnet_total_memory = ic_config::execution_environment::Config::default()
             .subnet_memory_capacity
             .get() as i64;
+        let subnet_memory_threshold = ic_config::execution_environment::Config::default()
+            .subnet_memory_threshold
+            .get() as i64;
         let subnet_message_memory = ic_config::execution_environment::Config::default()
             .subnet_message_memory_capacity
             .get() as i64;
@@ -1316,6 +1446,7 @@ impl Default for ExecutionTestBuilder {
                 .max_instructions_per_message_without_dts,
             initial_canister_cycles: INITIAL_CANISTER_CYCLES,
             subnet_total_memory,
+            subnet_memory_threshold,
             subnet_message_memory,
             registry_settings: test_registry_settings(),
             manual_execution: false,
@@ -1447,6 +1578,13 @@ impl ExecutionTestBuilder {
         }
     }
 
+    pub fn with_subnet_memory_threshold(self, subnet_memory_threshold: i64) -> Self {
+        Self {
+            subnet_memory_threshold,
+            ..self
+        }
+    }
+
     pub fn with_subnet_message_memory(self, subnet_message_memory: i64) -> Self {
         Self {
             subnet_message_memory,
@@ -1646,6 +1784,7 @@ impl ExecutionTestBuilder {
             composite_queries,
             allocatable_compute_capacity_in_percent: self.allocatable_compute_capacity_in_percent,
             subnet_memory_capacity: NumBytes::from(self.subnet_total_memory as u64),
+            subnet_memory_threshold: NumBytes::from(self.subnet_memory_threshold as u64),
             subnet_message_memory_capacity: NumBytes::from(self.subnet_message_memory as u64),
             bitcoin: BitcoinConfig {
                 privileged_access: self.bitcoin_privileged_access,
@@ -1700,6 +1839,7 @@ impl ExecutionTestBuilder {
             self.instruction_limit_without_dts,
             Arc::clone(&cycles_account_manager),
             composite_queries,
+            Arc::new(QueryStatsCollector::new()),
         );
         ExecutionTest {
             state: Some(state),
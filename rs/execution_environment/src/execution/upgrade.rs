@@ -17,7 +17,8 @@ use ic_interfaces::execution_environment::{HypervisorError, WasmExecutionOutput}
 use ic_interfaces::messages::RequestOrIngress;
 use ic_logger::{info, warn, ReplicaLogger};
 use ic_replicated_state::{CanisterState, SystemState};
-use ic_system_api::ApiType;
+use ic_system_api::{ApiType, ExecutionParameters};
+use ic_types::canister_log::CanisterLog;
 use ic_types::funds::Cycles;
 use ic_types::methods::{FuncRef, SystemMethod, WasmMethod};
 
@@ -97,6 +98,7 @@ pub(crate) fn execute_upgrade(
             original,
             round,
             err,
+            helper.canister_log_delta(),
         );
     }
 
@@ -113,6 +115,7 @@ pub(crate) fn execute_upgrade(
                 original,
                 round,
                 (canister_id, HypervisorError::WasmModuleNotFound).into(),
+                helper.canister_log_delta(),
             );
         }
     };
@@ -131,11 +134,11 @@ pub(crate) fn execute_upgrade(
         )
     } else {
         let wasm_execution_result = round.hypervisor.execute_dts(
-            ApiType::pre_upgrade(original.time, context.sender),
+            ApiType::pre_upgrade(original.time, context.sender()),
             execution_state,
             &helper.canister().system_state,
             helper.canister_memory_usage(),
-            helper.execution_parameters().clone(),
+            upgrade_execution_parameters(&helper),
             FuncRef::Method(method),
             round_limits,
             round.network_topology,
@@ -204,7 +207,14 @@ fn upgrade_stage_1_process_pre_upgrade_result(
 
     if let Err(err) = result {
         let instructions_left = helper.instructions_left();
-        return finish_err(clean_canister, instructions_left, original, round, err);
+        return finish_err(
+            clean_canister,
+            instructions_left,
+            original,
+            round,
+            err,
+            helper.canister_log_delta(),
+        );
     }
 
     upgrade_stage_2_and_3a_create_execution_state_and_call_start(
@@ -246,7 +256,14 @@ fn upgrade_stage_2_and_3a_create_execution_state_and_call_start(
         &original,
     ) {
         let instructions_left = helper.instructions_left();
-        return finish_err(clean_canister, instructions_left, original, round, err);
+        return finish_err(
+            clean_canister,
+            instructions_left,
+            original,
+            round,
+            err,
+            helper.canister_log_delta(),
+        );
     }
 
     helper.deactivate_global_timer();
@@ -261,7 +278,7 @@ fn upgrade_stage_2_and_3a_create_execution_state_and_call_start(
         // If the Wasm module does not export the method, then this execution
         // succeeds as a no-op.
         upgrade_stage_4a_call_post_upgrade(
-            context.sender,
+            context.sender(),
             context.arg,
             clean_canister,
             helper,
@@ -275,7 +292,7 @@ fn upgrade_stage_2_and_3a_create_execution_state_and_call_start(
             execution_state,
             &SystemState::new_for_start(canister_id),
             helper.canister_memory_usage(),
-            helper.execution_parameters().clone(),
+            upgrade_execution_parameters(&helper),
             FuncRef::Method(method),
             round_limits,
             round.network_topology,
@@ -287,7 +304,7 @@ fn upgrade_stage_2_and_3a_create_execution_state_and_call_start(
                 upgrade_stage_3b_process_start_result(
                     canister_state_changes,
                     output,
-                    context.sender,
+                    context.sender(),
                     context.arg,
                     clean_canister,
                     helper,
@@ -309,7 +326,7 @@ fn upgrade_stage_2_and_3a_create_execution_state_and_call_start(
                 let paused_execution = Box::new(PausedStartExecutionDuringUpgrade {
                     paused_wasm_execution,
                     paused_helper: helper.pause(),
-                    context_sender: context.sender,
+                    context_sender: context.sender(),
                     context_arg: context.arg,
                     original,
                 });
@@ -347,7 +364,14 @@ fn upgrade_stage_3b_process_start_result(
 
     if let Err(err) = result {
         let instructions_left = helper.instructions_left();
-        return finish_err(clean_canister, instructions_left, original, round, err);
+        return finish_err(
+            clean_canister,
+            instructions_left,
+            original,
+            round,
+            err,
+            helper.canister_log_delta(),
+        );
     }
 
     upgrade_stage_4a_call_post_upgrade(
@@ -389,7 +413,7 @@ fn upgrade_stage_4a_call_post_upgrade(
         execution_state,
         &helper.canister().system_state,
         helper.canister_memory_usage(),
-        helper.execution_parameters().clone(),
+        upgrade_execution_parameters(&helper),
         FuncRef::Method(method),
         round_limits,
         round.network_topology,
@@ -451,11 +475,28 @@ fn upgrade_stage_4b_process_post_upgrade_result(
     );
     if let Err(err) = result {
         let instructions_left = helper.instructions_left();
-        return finish_err(clean_canister, instructions_left, original, round, err);
+        return finish_err(
+            clean_canister,
+            instructions_left,
+            original,
+            round,
+            err,
+            helper.canister_log_delta(),
+        );
     }
     helper.finish(clean_canister, original, round, round_limits)
 }
 
+/// Returns the execution parameters for the Wasm executions of an upgrade.
+/// The Wasm memory limit does not apply to upgrades so that a canister that
+/// has reached its limit can always migrate its data.
+fn upgrade_execution_parameters(helper: &InstallCodeHelper) -> ExecutionParameters {
+    ExecutionParameters {
+        wasm_memory_limit: None,
+        ..helper.execution_parameters().clone()
+    }
+}
+
 /// Struct used to hold necessary information for the
 /// deterministic time slicing execution of canister upgrade.
 /// Pre upgrade is the first stage of the upgrade procedure.
@@ -495,7 +536,14 @@ impl PausedInstallCodeExecution for PausedPreUpgradeExecution {
                     err
                 );
                 self.paused_wasm_execution.abort();
-                return finish_err(clean_canister, instructions_left, self.original, round, err);
+                return finish_err(
+                    clean_canister,
+                    instructions_left,
+                    self.original,
+                    round,
+                    err,
+                    CanisterLog::default(),
+                );
             }
         };
         let execution_state = helper.canister().execution_state.as_ref().unwrap();
@@ -592,7 +640,14 @@ impl PausedInstallCodeExecution for PausedStartExecutionDuringUpgrade {
                     err
                 );
                 self.paused_wasm_execution.abort();
-                return finish_err(clean_canister, instructions_left, self.original, round, err);
+                return finish_err(
+                    clean_canister,
+                    instructions_left,
+                    self.original,
+                    round,
+                    err,
+                    CanisterLog::default(),
+                );
             }
         };
         let execution_state = helper.canister().execution_state.as_ref().unwrap();
@@ -688,7 +743,14 @@ impl PausedInstallCodeExecution for PausedPostUpgradeExecution {
                     err
                 );
                 self.paused_wasm_execution.abort();
-                return finish_err(clean_canister, instructions_left, self.original, round, err);
+                return finish_err(
+                    clean_canister,
+                    instructions_left,
+                    self.original,
+                    round,
+                    err,
+                    CanisterLog::default(),
+                );
             }
         };
         let execution_state = helper.canister().execution_state.as_ref().unwrap();
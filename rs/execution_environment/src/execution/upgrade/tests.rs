@@ -878,3 +878,76 @@ fn dts_uninstall_with_aborted_upgrade() {
         )
     );
 }
+
+#[test]
+fn upgrade_keeps_canister_log_if_post_upgrade_traps() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let old_wat = r#"
+        (module
+            (import "ic0" "debug_print" (func $debug_print (param i32 i32)))
+            (func (export "canister_pre_upgrade")
+                (call $debug_print (i32.const 0) (i32.const 11))
+            )
+            (memory 1)
+            (data (i32.const 0) "pre_upgrade")
+        )"#;
+    let canister_id = test.canister_from_wat(old_wat).unwrap();
+    let new_wat = r#"
+        (module
+            (import "ic0" "debug_print" (func $debug_print (param i32 i32)))
+            (import "ic0" "trap" (func $trap (param i32 i32)))
+            (func (export "canister_post_upgrade")
+                (call $debug_print (i32.const 0) (i32.const 12))
+                (call $trap (i32.const 12) (i32.const 4))
+            )
+            (memory 1)
+            (data (i32.const 0) "post_upgradeoops")
+        )"#;
+
+    let err = test
+        .upgrade_canister(canister_id, wabt::wat2wasm(new_wat).unwrap())
+        .unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterCalledTrap);
+
+    let contents: Vec<_> = test
+        .canister_state(canister_id)
+        .system_state
+        .canister_log
+        .records()
+        .iter()
+        .map(|record| String::from_utf8_lossy(&record.content).to_string())
+        .collect();
+    assert_eq!(contents, ["pre_upgrade", "post_upgrade", "[TRAP]: oops"]);
+}
+
+#[test]
+fn upgrade_keeps_canister_log_if_pre_upgrade_traps() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let old_wat = r#"
+        (module
+            (import "ic0" "debug_print" (func $debug_print (param i32 i32)))
+            (import "ic0" "trap" (func $trap (param i32 i32)))
+            (func (export "canister_pre_upgrade")
+                (call $debug_print (i32.const 0) (i32.const 11))
+                (call $trap (i32.const 11) (i32.const 4))
+            )
+            (memory 1)
+            (data (i32.const 0) "pre_upgradeoops")
+        )"#;
+    let canister_id = test.canister_from_wat(old_wat).unwrap();
+
+    let err = test
+        .upgrade_canister(canister_id, new_empty_binary())
+        .unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterCalledTrap);
+
+    let contents: Vec<_> = test
+        .canister_state(canister_id)
+        .system_state
+        .canister_log
+        .records()
+        .iter()
+        .map(|record| String::from_utf8_lossy(&record.content).to_string())
+        .collect();
+    assert_eq!(contents, ["pre_upgrade", "[TRAP]: oops"]);
+}
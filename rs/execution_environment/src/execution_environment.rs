@@ -16,7 +16,7 @@ use crate::{
         ExecutionEnvironmentMetrics, SUBMITTED_OUTCOME_LABEL, SUCCESS_STATUS_LABEL,
     },
     hypervisor::Hypervisor,
-    util::candid_error_to_user_error,
+    util::{candid_error_to_user_error, subnet_memory_saturation},
     NonReplicatedQueryKind,
 };
 use candid::Encode;
@@ -28,11 +28,13 @@ use ic_crypto_tecdsa::derive_tecdsa_public_key;
 use ic_cycles_account_manager::{CyclesAccountManager, IngressInductionCost};
 use ic_error_types::{ErrorCode, RejectCode, UserError};
 use ic_ic00_types::{
-    CanisterHttpRequestArgs, CanisterIdRecord, CanisterSettingsArgs,
-    ComputeInitialEcdsaDealingsArgs, CreateCanisterArgs, ECDSAPublicKeyArgs,
-    ECDSAPublicKeyResponse, EcdsaKeyId, EmptyBlob, InstallCodeArgs, Method as Ic00Method,
-    Payload as Ic00Payload, ProvisionalCreateCanisterWithCyclesArgs, ProvisionalTopUpCanisterArgs,
-    SetControllerArgs, SetupInitialDKGArgs, SignWithECDSAArgs, UpdateSettingsArgs, IC_00,
+    CanisterChangeOrigin, CanisterHttpRequestArgs, CanisterIdRecord, CanisterInfoRequest,
+    CanisterInfoResponse, CanisterSettingsArgs, ComputeInitialEcdsaDealingsArgs,
+    CreateCanisterArgs, DeleteCanisterSnapshotArgs, ECDSAPublicKeyArgs, ECDSAPublicKeyResponse,
+    EcdsaKeyId, EmptyBlob, InstallChunkedCodeArgs, InstallCodeArgs, LoadCanisterSnapshotArgs,
+    Method as Ic00Method, Payload as Ic00Payload, ProvisionalCreateCanisterWithCyclesArgs,
+    ProvisionalTopUpCanisterArgs, SetControllerArgs, SetupInitialDKGArgs, SignWithECDSAArgs,
+    TakeCanisterSnapshotArgs, UninstallCodeArgs, UpdateSettingsArgs, UploadChunkArgs, IC_00,
 };
 use ic_interfaces::{
     execution_environment::{
@@ -63,8 +65,8 @@ use ic_types::{
         extract_effective_canister_id, AnonymousQuery, Payload, RejectContext, Request, Response,
         SignedIngressContent, StopCanisterContext,
     },
-    CanisterId, CanisterTimer, Cycles, LongExecutionMode, NumBytes, NumInstructions, SubnetId,
-    Time,
+    CanisterId, CanisterTimer, Cycles, LongExecutionMode, NumBytes, NumInstructions,
+    ResourceSaturation, SubnetId, Time,
 };
 use ic_types::{messages::MessageId, methods::SystemMethod, methods::WasmMethod};
 use ic_wasm_types::WasmHash;
@@ -294,6 +296,7 @@ impl ExecutionEnvironment {
         );
         let canister_manager_config: CanisterMgrConfig = CanisterMgrConfig::new(
             config.subnet_memory_capacity,
+            config.subnet_memory_threshold,
             config.default_provisional_cycles_balance,
             config.default_freeze_threshold,
             own_subnet_id,
@@ -394,6 +397,7 @@ impl ExecutionEnvironment {
                                 originator: request.sender,
                                 respondent: CanisterId::from(self.own_subnet_id),
                                 originator_reply_callback: request.sender_reply_callback,
+                                deadline: request.deadline,
                                 refund: request.payment,
                                 response_payload: response.response_payload.clone(),
                             }
@@ -412,7 +416,7 @@ impl ExecutionEnvironment {
         let method = Ic00Method::from_str(msg.method_name());
         let payload = msg.method_payload();
         let result = match method {
-            Ok(Ic00Method::InstallCode) => {
+            Ok(Ic00Method::InstallCode) | Ok(Ic00Method::InstallChunkedCode) => {
                 // Tail call is needed for deterministic time slicing here to
                 // properly handle the case of a paused execution.
                 return self.execute_install_code(
@@ -441,6 +445,7 @@ impl ExecutionEnvironment {
                                 originator: request.sender,
                                 respondent: CanisterId::from(self.own_subnet_id),
                                 originator_reply_callback: request.sender_reply_callback,
+                                deadline: request.deadline,
                                 refund: request.payment,
                                 response_payload: messages::Payload::Reject(
                                     messages::RejectContext {
@@ -506,6 +511,7 @@ impl ExecutionEnvironment {
                                         // Start logging execution time for `create_canister`.
                                         let timer = Timer::start();
 
+                                        let sender_canister_version = args.get_sender_canister_version();
                                         let settings = match args.settings {
                                             None => CanisterSettingsArgs::default(),
                                             Some(settings) => settings,
@@ -513,7 +519,7 @@ impl ExecutionEnvironment {
                                         let result = match CanisterSettings::try_from(settings) {
                                             Err(err) => Some((Err(err.into()), cycles)),
                                             Ok(settings) =>
-                                                Some(self.create_canister(*msg.sender(), cycles, settings, registry_settings.max_number_of_canisters, &mut state, registry_settings.subnet_size, round_limits))
+                                                Some(self.create_canister(msg.canister_change_origin(sender_canister_version), cycles, settings, registry_settings.max_number_of_canisters, &mut state, registry_settings.subnet_size, round_limits))
                                         };
                                         info!(
                                             self.log,
@@ -532,11 +538,15 @@ impl ExecutionEnvironment {
             }
 
             Ok(Ic00Method::UninstallCode) => {
-                let res = match CanisterIdRecord::decode(payload) {
+                let res = match UninstallCodeArgs::decode(payload) {
                     Err(err) => Err(candid_error_to_user_error(err)),
                     Ok(args) => self
                         .canister_manager
-                        .uninstall_code(args.get_canister_id(), *msg.sender(), &mut state)
+                        .uninstall_code(
+                            msg.canister_change_origin(args.get_sender_canister_version()),
+                            args.get_canister_id(),
+                            &mut state,
+                        )
                         .map(|()| EmptyBlob.encode())
                         .map_err(|err| err.into()),
                 };
@@ -551,13 +561,15 @@ impl ExecutionEnvironment {
                         let timer = Timer::start();
 
                         let canister_id = args.get_canister_id();
+                        let origin = msg.canister_change_origin(args.get_sender_canister_version());
                         let result = match CanisterSettings::try_from(args.settings) {
                             Err(err) => Err(err.into()),
                             Ok(settings) => self.update_settings(
-                                *msg.sender(),
+                                origin,
                                 settings,
                                 canister_id,
                                 &mut state,
+                                registry_settings.subnet_size,
                                 round_limits,
                             ),
                         };
@@ -606,10 +618,11 @@ impl ExecutionEnvironment {
                     Ok(args) => self
                         .canister_manager
                         .set_controller(
-                            *msg.sender(),
+                            msg.canister_change_origin(None),
                             args.get_canister_id(),
                             args.get_new_controller(),
                             &mut state,
+                            registry_settings.subnet_size,
                             round_limits,
                         )
                         .map(|()| EmptyBlob.encode())
@@ -844,6 +857,158 @@ impl ExecutionEnvironment {
                 }
             }
 
+            Ok(Ic00Method::CanisterInfo) => match &msg {
+                RequestOrIngress::Request(_) => {
+                    let res = match CanisterInfoRequest::decode(payload) {
+                        Err(err) => Err(candid_error_to_user_error(err)),
+                        Ok(record) => self.get_canister_info(
+                            record.canister_id(),
+                            record.num_requested_changes(),
+                            &state,
+                        ),
+                    };
+                    Some((res, msg.take_cycles()))
+                }
+                RequestOrIngress::Ingress(_) => {
+                    self.reject_unexpected_ingress(Ic00Method::CanisterInfo)
+                }
+            },
+
+            Ok(Ic00Method::FetchCanisterLogs) => match &msg {
+                RequestOrIngress::Request(_) => Some((
+                    Err(UserError::new(
+                        ErrorCode::CanisterContractViolation,
+                        format!(
+                            "{} API is only accessible in non-replicated mode",
+                            Ic00Method::FetchCanisterLogs
+                        ),
+                    )),
+                    msg.take_cycles(),
+                )),
+                RequestOrIngress::Ingress(_) => {
+                    self.reject_unexpected_ingress(Ic00Method::FetchCanisterLogs)
+                }
+            },
+
+            Ok(Ic00Method::TakeCanisterSnapshot) => {
+                let res = match TakeCanisterSnapshotArgs::decode(payload) {
+                    Err(err) => Err(candid_error_to_user_error(err)),
+                    Ok(args) => self
+                        .canister_manager
+                        .take_canister_snapshot(
+                            registry_settings.subnet_size,
+                            *msg.sender(),
+                            args.get_canister_id(),
+                            args.replace_snapshot(),
+                            &mut state,
+                            round_limits,
+                        )
+                        .map(|response| response.encode())
+                        .map_err(|err| err.into()),
+                };
+                Some((res, msg.take_cycles()))
+            }
+
+            Ok(Ic00Method::LoadCanisterSnapshot) => {
+                let res = match LoadCanisterSnapshotArgs::decode(payload) {
+                    Err(err) => Err(candid_error_to_user_error(err)),
+                    Ok(args) => self
+                        .canister_manager
+                        .load_canister_snapshot(
+                            registry_settings.subnet_size,
+                            *msg.sender(),
+                            args.get_canister_id(),
+                            args.snapshot_id(),
+                            "NOT_USED".into(),
+                            &mut state,
+                            round_limits,
+                        )
+                        .map(|()| EmptyBlob.encode())
+                        .map_err(|err| err.into()),
+                };
+                Some((res, msg.take_cycles()))
+            }
+
+            Ok(Ic00Method::ListCanisterSnapshots) => {
+                let res = match CanisterIdRecord::decode(payload) {
+                    Err(err) => Err(candid_error_to_user_error(err)),
+                    Ok(args) => self
+                        .canister_manager
+                        .list_canister_snapshots(*msg.sender(), args.get_canister_id(), &state)
+                        .map(|response| response.encode())
+                        .map_err(|err| err.into()),
+                };
+                Some((res, msg.take_cycles()))
+            }
+
+            Ok(Ic00Method::DeleteCanisterSnapshot) => {
+                let res = match DeleteCanisterSnapshotArgs::decode(payload) {
+                    Err(err) => Err(candid_error_to_user_error(err)),
+                    Ok(args) => self
+                        .canister_manager
+                        .delete_canister_snapshot(
+                            *msg.sender(),
+                            args.get_canister_id(),
+                            args.snapshot_id(),
+                            &mut state,
+                            round_limits,
+                        )
+                        .map(|()| EmptyBlob.encode())
+                        .map_err(|err| err.into()),
+                };
+                Some((res, msg.take_cycles()))
+            }
+
+            Ok(Ic00Method::UploadChunk) => {
+                let res = match UploadChunkArgs::decode(payload) {
+                    Err(err) => Err(candid_error_to_user_error(err)),
+                    Ok(args) => {
+                        let canister_id = args.get_canister_id();
+                        self.canister_manager
+                            .upload_chunk(
+                                *msg.sender(),
+                                canister_id,
+                                args.chunk,
+                                &mut state,
+                                round_limits,
+                                registry_settings.subnet_size,
+                            )
+                            .map(|reply| reply.encode())
+                            .map_err(|err| err.into())
+                    }
+                };
+                Some((res, msg.take_cycles()))
+            }
+
+            Ok(Ic00Method::ClearChunkStore) => {
+                let res = match CanisterIdRecord::decode(payload) {
+                    Err(err) => Err(candid_error_to_user_error(err)),
+                    Ok(args) => self
+                        .canister_manager
+                        .clear_chunk_store(
+                            *msg.sender(),
+                            args.get_canister_id(),
+                            &mut state,
+                            round_limits,
+                        )
+                        .map(|()| EmptyBlob.encode())
+                        .map_err(|err| err.into()),
+                };
+                Some((res, msg.take_cycles()))
+            }
+
+            Ok(Ic00Method::StoredChunks) => {
+                let res = match CanisterIdRecord::decode(payload) {
+                    Err(err) => Err(candid_error_to_user_error(err)),
+                    Ok(args) => self
+                        .canister_manager
+                        .stored_chunks(*msg.sender(), args.get_canister_id(), &state)
+                        .map(|reply| reply.encode())
+                        .map_err(|err| err.into()),
+                };
+                Some((res, msg.take_cycles()))
+            }
+
             Ok(Ic00Method::ProvisionalCreateCanisterWithCycles) => {
                 let res = match ProvisionalCreateCanisterWithCyclesArgs::decode(payload) {
                     Err(err) => Err(candid_error_to_user_error(err)),
@@ -853,7 +1018,7 @@ impl ExecutionEnvironment {
                             Ok(settings) => self
                                 .canister_manager
                                 .create_canister_with_cycles(
-                                    *msg.sender(),
+                                    msg.canister_change_origin(None),
                                     cycles_amount,
                                     settings,
                                     args.specified_id,
@@ -1086,6 +1251,11 @@ impl ExecutionEnvironment {
                     &canister,
                     instruction_limits,
                     ExecutionMode::Replicated,
+                    subnet_memory_saturation(
+                        &round_limits.subnet_available_memory,
+                        self.config.subnet_memory_threshold,
+                        self.config.subnet_memory_capacity,
+                    ),
                 );
                 execute_replicated_query(
                     canister,
@@ -1103,6 +1273,11 @@ impl ExecutionEnvironment {
                     &canister,
                     instruction_limits,
                     ExecutionMode::Replicated,
+                    subnet_memory_saturation(
+                        &round_limits.subnet_available_memory,
+                        self.config.subnet_memory_threshold,
+                        self.config.subnet_memory_capacity,
+                    ),
                 );
                 execute_update(
                     canister,
@@ -1138,8 +1313,16 @@ impl ExecutionEnvironment {
         NumInstructions,
         Result<NumBytes, CanisterSystemTaskError>,
     ) {
-        let execution_parameters =
-            self.execution_parameters(&canister, instruction_limits, ExecutionMode::Replicated);
+        let execution_parameters = self.execution_parameters(
+            &canister,
+            instruction_limits,
+            ExecutionMode::Replicated,
+            subnet_memory_saturation(
+                &round_limits.subnet_available_memory,
+                self.config.subnet_memory_threshold,
+                self.config.subnet_memory_capacity,
+            ),
+        );
         let (canister, instructions_used, result) = execute_system_task(
             canister,
             system_task.clone(),
@@ -1196,10 +1379,13 @@ impl ExecutionEnvironment {
         canister: &CanisterState,
         instruction_limits: InstructionLimits,
         execution_mode: ExecutionMode,
+        subnet_memory_saturation: ResourceSaturation,
     ) -> ExecutionParameters {
         ExecutionParameters {
             instruction_limits,
             canister_memory_limit: canister.memory_limit(self.config.max_canister_memory_size),
+            wasm_memory_limit: canister.system_state.wasm_memory_limit,
+            subnet_memory_saturation,
             compute_allocation: canister.scheduler_state.compute_allocation,
             subnet_type: self.own_subnet_type,
             execution_mode,
@@ -1208,7 +1394,7 @@ impl ExecutionEnvironment {
 
     fn create_canister(
         &self,
-        sender: PrincipalId,
+        origin: CanisterChangeOrigin,
         cycles: Cycles,
         settings: CanisterSettings,
         max_number_of_canisters: u64,
@@ -1216,10 +1402,10 @@ impl ExecutionEnvironment {
         subnet_size: usize,
         round_limits: &mut RoundLimits,
     ) -> (Result<Vec<u8>, UserError>, Cycles) {
-        match state.find_subnet_id(sender) {
+        match state.find_subnet_id(origin.origin()) {
             Ok(sender_subnet_id) => {
                 let (res, cycles) = self.canister_manager.create_canister(
-                    sender,
+                    origin,
                     sender_subnet_id,
                     cycles,
                     settings,
@@ -1240,15 +1426,24 @@ impl ExecutionEnvironment {
 
     fn update_settings(
         &self,
-        sender: PrincipalId,
+        origin: CanisterChangeOrigin,
         settings: CanisterSettings,
         canister_id: CanisterId,
         state: &mut ReplicatedState,
+        subnet_size: usize,
         round_limits: &mut RoundLimits,
     ) -> Result<Vec<u8>, UserError> {
+        let timestamp_nanos = state.time();
         let canister = get_canister_mut(canister_id, state)?;
         self.canister_manager
-            .update_settings(sender, settings, canister, round_limits)
+            .update_settings(
+                timestamp_nanos,
+                origin,
+                settings,
+                canister,
+                subnet_size,
+                round_limits,
+            )
             .map(|()| EmptyBlob.encode())
             .map_err(|err| err.into())
     }
@@ -1306,6 +1501,35 @@ impl ExecutionEnvironment {
         }
     }
 
+    fn get_canister_info(
+        &self,
+        canister_id: CanisterId,
+        num_requested_changes: Option<u64>,
+        state: &ReplicatedState,
+    ) -> Result<Vec<u8>, UserError> {
+        let canister = state.canister_state(&canister_id).ok_or_else(|| {
+            UserError::new(
+                ErrorCode::CanisterNotFound,
+                format!("Canister {} not found.", &canister_id),
+            )
+        })?;
+        let canister_history = canister.system_state.get_canister_history();
+        let total_num_changes = canister_history.get_total_num_changes();
+        let changes = canister_history
+            .get_changes(num_requested_changes.unwrap_or(0) as usize)
+            .cloned()
+            .collect();
+        let module_hash = canister
+            .execution_state
+            .as_ref()
+            .map(|execution_state| execution_state.wasm_binary.binary.module_hash().to_vec());
+        let controllers = canister.controllers().iter().copied().collect();
+        Ok(
+            CanisterInfoResponse::new(total_num_changes, changes, module_hash, controllers)
+                .encode(),
+        )
+    }
+
     fn get_canister_status(
         &self,
         sender: PrincipalId,
@@ -1373,8 +1597,16 @@ impl ExecutionEnvironment {
         round_limits: &mut RoundLimits,
         subnet_size: usize,
     ) -> ExecuteMessageResult {
-        let execution_parameters =
-            self.execution_parameters(&canister, instruction_limits, ExecutionMode::Replicated);
+        let execution_parameters = self.execution_parameters(
+            &canister,
+            instruction_limits,
+            ExecutionMode::Replicated,
+            subnet_memory_saturation(
+                &round_limits.subnet_available_memory,
+                self.config.subnet_memory_threshold,
+                self.config.subnet_memory_capacity,
+            ),
+        );
         let round = RoundContext {
             network_topology: &network_topology,
             hypervisor: &self.hypervisor,
@@ -1467,8 +1699,13 @@ impl ExecutionEnvironment {
             self.config.max_instructions_for_message_acceptance_calls,
             self.config.max_instructions_for_message_acceptance_calls,
         );
-        let execution_parameters =
-            self.execution_parameters(canister_state, instruction_limits, execution_mode);
+        let execution_parameters = self.execution_parameters(
+            canister_state,
+            instruction_limits,
+            execution_mode,
+            // The changes of inspect message executions are discarded.
+            ResourceSaturation::default(),
+        );
 
         // Letting the canister grow arbitrarily when executing the
         // query is fine as we do not persist state modifications.
@@ -1506,8 +1743,12 @@ impl ExecutionEnvironment {
             max_instructions_per_query,
             max_instructions_per_query,
         );
-        let execution_parameters =
-            self.execution_parameters(&canister, instruction_limits, ExecutionMode::NonReplicated);
+        let execution_parameters = self.execution_parameters(
+            &canister,
+            instruction_limits,
+            ExecutionMode::NonReplicated,
+            ResourceSaturation::default(),
+        );
         let subnet_available_memory = subnet_memory_capacity(&self.config);
         let mut round_limits = RoundLimits {
             instructions: as_round_instructions(max_instructions_per_query),
@@ -1567,6 +1808,7 @@ impl ExecutionEnvironment {
                     originator: req.sender,
                     respondent: subnet_id_as_canister_id,
                     originator_reply_callback: req.sender_reply_callback,
+                    deadline: req.deadline,
                     refund,
                     response_payload: payload,
                 };
@@ -1638,6 +1880,7 @@ impl ExecutionEnvironment {
                     sender,
                     reply_callback,
                     cycles,
+                    deadline,
                 } => {
                     // Rejecting a stop_canister request from a canister.
                     let subnet_id_as_canister_id = CanisterId::from(self.own_subnet_id);
@@ -1645,6 +1888,7 @@ impl ExecutionEnvironment {
                         originator: sender,
                         respondent: subnet_id_as_canister_id,
                         originator_reply_callback: reply_callback,
+                        deadline,
                         refund: cycles,
                         response_payload: Payload::Reject(RejectContext {
                             code: RejectCode::CanisterReject,
@@ -1851,12 +2095,41 @@ impl ExecutionEnvironment {
     ) -> (ReplicatedState, Option<NumInstructions>) {
         // A helper function to make error handling more compact using `?`.
         fn decode_input_and_take_canister(
+            canister_manager: &CanisterManager,
             msg: &RequestOrIngress,
             state: &mut ReplicatedState,
         ) -> Result<(InstallCodeContext, CanisterState), UserError> {
             let payload = msg.method_payload();
-            let args = InstallCodeArgs::decode(payload).map_err(candid_error_to_user_error)?;
-            let install_context = InstallCodeContext::try_from((*msg.sender(), args))?;
+            let args = match Ic00Method::from_str(msg.method_name()) {
+                // The chunks are assembled into a regular `install_code` call,
+                // which then takes the same path as a directly sent module.
+                Ok(Ic00Method::InstallChunkedCode) => {
+                    let args = InstallChunkedCodeArgs::decode(payload)
+                        .map_err(candid_error_to_user_error)?;
+                    let wasm_module = canister_manager.assemble_chunked_wasm_module(
+                        *msg.sender(),
+                        &args,
+                        state,
+                    )?;
+                    InstallCodeArgs {
+                        sender_canister_version: args.sender_canister_version,
+                        ..InstallCodeArgs::new(
+                            args.mode,
+                            args.target_canister_id(),
+                            wasm_module,
+                            args.arg,
+                            None,
+                            None,
+                            None,
+                        )
+                    }
+                }
+                _ => InstallCodeArgs::decode(payload).map_err(candid_error_to_user_error)?,
+            };
+            let install_context = InstallCodeContext::try_from((
+                msg.canister_change_origin(args.get_sender_canister_version()),
+                args,
+            ))?;
             let canister = state
                 .take_canister_state(&install_context.canister_id)
                 .ok_or(CanisterManagerError::CanisterNotFound(
@@ -1868,16 +2141,16 @@ impl ExecutionEnvironment {
         // Start logging execution time for `install_code`.
         let timer = Timer::start();
 
-        let (install_context, old_canister) = match decode_input_and_take_canister(&msg, &mut state)
-        {
-            Ok(result) => result,
-            Err(err) => {
-                let refund = msg.take_cycles();
-                let state =
-                    self.finish_subnet_message_execution(state, msg, Err(err), refund, timer);
-                return (state, Some(NumInstructions::from(0)));
-            }
-        };
+        let (install_context, old_canister) =
+            match decode_input_and_take_canister(&self.canister_manager, &msg, &mut state) {
+                Ok(result) => result,
+                Err(err) => {
+                    let refund = msg.take_cycles();
+                    let state =
+                        self.finish_subnet_message_execution(state, msg, Err(err), refund, timer);
+                    return (state, Some(NumInstructions::from(0)));
+                }
+            };
 
         // Check the precondition.
         match old_canister.next_execution() {
@@ -1906,8 +2179,16 @@ impl ExecutionEnvironment {
             install_context.wasm_module.is_empty().to_string(),
         );
 
-        let execution_parameters =
-            self.execution_parameters(&old_canister, instruction_limits, ExecutionMode::Replicated);
+        let execution_parameters = self.execution_parameters(
+            &old_canister,
+            instruction_limits,
+            ExecutionMode::Replicated,
+            subnet_memory_saturation(
+                &round_limits.subnet_available_memory,
+                self.config.subnet_memory_threshold,
+                self.config.subnet_memory_capacity,
+            ),
+        );
 
         let dts_result = self.canister_manager.install_code_dts(
             install_context,
@@ -28,6 +28,7 @@ use ic_types::{
     ingress::{IngressState, IngressStatus, WasmResult},
     messages::{
         CallbackId, Payload, RejectContext, RequestOrResponse, Response, MAX_RESPONSE_COUNT_BYTES,
+        NO_DEADLINE,
     },
     CanisterId, Cycles, PrincipalId, RegistryVersion,
 };
@@ -692,6 +693,64 @@ fn get_running_canister_status_from_another_canister() {
     );
 }
 
+#[test]
+fn canister_status_reports_wasm_memory_limit() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister = test.universal_canister().unwrap();
+    let result = test.canister_status(canister);
+    let csr = CanisterStatusResultV2::decode(&get_reply(result)).unwrap();
+    assert_eq!(csr.wasm_memory_limit(), 0);
+
+    test.update_wasm_memory_limit(canister, NumBytes::from(1 << 30))
+        .unwrap();
+    let result = test.canister_status(canister);
+    let csr = CanisterStatusResultV2::decode(&get_reply(result)).unwrap();
+    assert_eq!(csr.wasm_memory_limit(), 1 << 30);
+}
+
+#[test]
+fn memory_allocation_reserves_cycles_above_subnet_memory_threshold() {
+    let mut test = ExecutionTestBuilder::new()
+        .with_subnet_total_memory(100 * 1024 * 1024)
+        .with_subnet_memory_threshold(0)
+        .build();
+    let canister = test.universal_canister().unwrap();
+    test.canister_update_allocations_settings(canister, None, Some(10 * 1024 * 1024))
+        .unwrap();
+    let result = test.canister_status(canister);
+    let csr = CanisterStatusResultV2::decode(&get_reply(result)).unwrap();
+    assert!(csr.reserved_cycles() > 0);
+    assert_eq!(
+        csr.reserved_cycles(),
+        test.canister_state(canister)
+            .system_state
+            .reserved_balance()
+            .get()
+    );
+}
+
+#[test]
+fn memory_allocation_fails_if_reserved_cycles_limit_is_exceeded() {
+    let mut test = ExecutionTestBuilder::new()
+        .with_subnet_total_memory(100 * 1024 * 1024)
+        .with_subnet_memory_threshold(0)
+        .build();
+    let canister = test.universal_canister().unwrap();
+    test.update_reserved_cycles_limit(canister, Cycles::zero())
+        .unwrap();
+    let err = test
+        .canister_update_allocations_settings(canister, None, Some(10 * 1024 * 1024))
+        .unwrap_err();
+    assert_eq!(
+        ErrorCode::ReservedCyclesLimitExceededInMemoryAllocation,
+        err.code()
+    );
+    let result = test.canister_status(canister);
+    let csr = CanisterStatusResultV2::decode(&get_reply(result)).unwrap();
+    assert_eq!(csr.reserved_cycles(), 0);
+    assert_eq!(csr.reserved_cycles_limit(), Some(0));
+}
+
 #[test]
 fn get_canister_status_from_another_canister_when_memory_low() {
     let mut test = ExecutionTestBuilder::new().build();
@@ -1080,6 +1139,7 @@ fn setup_initial_dkg_sender_not_on_nns() {
             originator: other_canister,
             respondent: CanisterId::from(own_subnet),
             originator_reply_callback: CallbackId::new(0),
+            deadline: NO_DEADLINE,
             refund: test.canister_creation_fee(),
             response_payload: Payload::Reject(RejectContext {
                 code: RejectCode::CanisterError,
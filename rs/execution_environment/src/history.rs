@@ -256,6 +256,7 @@ fn dashboard_label_value_from(code: ErrorCode) -> &'static str {
         CanisterFunctionNotFound => "Canister Function Not Found",
         CanisterAlreadyInstalled => "Canister Already Installed",
         CanisterWasmModuleNotFound => "Canister WASM Module Not Found",
+        CanisterSnapshotNotFound => "Canister Snapshot Not Found",
         CanisterNonEmpty => "Canister Non-Empty",
         CanisterOutOfCycles => "Canister Out Of Cycles",
         CanisterTrapped => "Canister Trapped",
@@ -293,6 +294,17 @@ fn dashboard_label_value_from(code: ErrorCode) -> &'static str {
         QueryCallGraphTooDeep => "Query call graph contains too many nested calls",
         QueryCallGraphTotalInstructionLimitExceeded => "Total instructions limit exceeded for query call graph",
         CompositeQueryCalledInReplicatedMode => "Composite query cannot be called in replicated mode",
+        CanisterWasmMemoryLimitExceeded => "Canister exceeded its Wasm memory limit",
+        ReservedCyclesLimitExceededInMemoryAllocation => {
+            "Canister exceeded its reserved cycles limit when changing its memory allocation"
+        }
+        ReservedCyclesLimitExceededInMemoryGrow => {
+            "Canister exceeded its reserved cycles limit when growing its memory"
+        }
+        InsufficientCyclesInMemoryGrow => "Canister did not have enough cycles to grow its memory",
+        InsufficientCyclesInMemoryAllocation => {
+            "Canister did not have enough cycles to change its memory allocation"
+        }
         CanisterNotHostedBySubnet => "Canister is not hosted by subnet",
     }
 }
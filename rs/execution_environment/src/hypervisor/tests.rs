@@ -19,6 +19,7 @@ use ic_replicated_state::{
 };
 use ic_sys::PAGE_SIZE;
 use ic_test_utilities::assert_utils::assert_balance_equals;
+use ic_test_utilities::types::ids::user_test_id;
 use ic_test_utilities_metrics::fetch_int_counter;
 use ic_test_utilities_metrics::{fetch_histogram_stats, HistogramStats};
 use ic_types::ingress::{IngressState, IngressStatus};
@@ -55,6 +56,63 @@ fn ic0_canister_status_works() {
     assert_empty_reply(result);
 }
 
+#[test]
+fn ic0_is_controller_works() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let wat = r#"
+        (module
+            (import "ic0" "msg_caller_copy"
+                (func $msg_caller_copy (param i32 i32 i32))
+            )
+            (import "ic0" "msg_caller_size"
+                (func $msg_caller_size (result i32))
+            )
+            (import "ic0" "is_controller"
+                (func $is_controller (param i32 i32) (result i32))
+            )
+            (func (export "canister_update test")
+                (call $msg_caller_copy (i32.const 0) (i32.const 0) (call $msg_caller_size))
+                (if (i32.ne (call $is_controller (i32.const 0) (call $msg_caller_size)) (i32.const 1))
+                    (then unreachable)
+                )
+            )
+            (memory 1)
+        )"#;
+    let canister_id = test.canister_from_wat(wat).unwrap();
+    let result = test.ingress(canister_id, "test", vec![]);
+    assert_empty_reply(result);
+
+    test.set_user_id(user_test_id(42));
+    let err = test.ingress(canister_id, "test", vec![]).unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterTrapped);
+}
+
+#[test]
+fn ic0_in_replicated_execution_works() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let wat = r#"
+        (module
+            (import "ic0" "in_replicated_execution"
+                (func $in_replicated_execution (result i32))
+            )
+            (import "ic0" "msg_reply" (func $msg_reply))
+            (import "ic0" "msg_reply_data_append"
+                (func $msg_reply_data_append (param i32 i32))
+            )
+            (func (export "canister_query test")
+                (i32.store8 (i32.const 0) (call $in_replicated_execution))
+                (call $msg_reply_data_append (i32.const 0) (i32.const 1))
+                (call $msg_reply)
+            )
+            (memory 1)
+        )"#;
+    let canister_id = test.canister_from_wat(wat).unwrap();
+    let result = test.ingress(canister_id, "test", vec![]);
+    assert_eq!(result, Ok(WasmResult::Reply(vec![1])));
+    let result = test.anonymous_query(canister_id, "test", vec![]);
+    assert_eq!(result, Ok(WasmResult::Reply(vec![0])));
+}
+
 #[test]
 fn ic0_msg_arg_data_size_works() {
     let mut test = ExecutionTestBuilder::new().build();
@@ -2140,6 +2198,133 @@ fn subnet_available_memory_is_not_updated_by_canister_pre_upgrade_wasm_memory()
     )
 }
 
+#[test]
+fn memory_grow_respects_wasm_memory_limit() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let wat = r#"
+        (module
+            (func (export "canister_update test")
+                (drop (memory.grow (i32.const 10)))
+            )
+            (memory 1 20)
+        )"#;
+    let canister_id = test.canister_from_wat(wat).unwrap();
+    test.update_wasm_memory_limit(canister_id, NumBytes::from(5 * WASM_PAGE_SIZE as u64))
+        .unwrap();
+    let err = test.ingress(canister_id, "test", vec![]).unwrap_err();
+    assert_eq!(ErrorCode::CanisterWasmMemoryLimitExceeded, err.code());
+
+    // A limit of 0 removes the limit.
+    test.update_wasm_memory_limit(canister_id, NumBytes::from(0))
+        .unwrap();
+    let result = test.ingress(canister_id, "test", vec![]);
+    assert_empty_reply(result);
+}
+
+#[test]
+fn wasm_memory_limit_does_not_apply_to_upgrades() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let wat = r#"
+        (module
+            (func (export "canister_pre_upgrade")
+                (drop (memory.grow (i32.const 10)))
+            )
+            (func (export "canister_post_upgrade")
+                (drop (memory.grow (i32.const 10)))
+            )
+            (memory 1 20)
+        )"#;
+    let canister_id = test.canister_from_wat(wat).unwrap();
+    test.update_wasm_memory_limit(canister_id, NumBytes::from(5 * WASM_PAGE_SIZE as u64))
+        .unwrap();
+    let result = test.upgrade_canister(canister_id, wabt::wat2wasm(wat).unwrap());
+    assert_eq!(Ok(()), result);
+}
+
+#[test]
+fn memory_grow_reserves_cycles_above_subnet_memory_threshold() {
+    let mut test = ExecutionTestBuilder::new()
+        .with_subnet_total_memory(100 * 1024 * 1024)
+        .with_subnet_memory_threshold(0)
+        .build();
+    let wat = r#"
+        (module
+            (func (export "canister_update test")
+                (drop (memory.grow (i32.const 100)))
+            )
+            (memory 1 200)
+        )"#;
+    let canister_id = test.canister_from_wat(wat).unwrap();
+    assert_eq!(
+        Cycles::zero(),
+        test.canister_state(canister_id)
+            .system_state
+            .reserved_balance()
+    );
+    let balance_before = test.canister_state(canister_id).system_state.balance();
+    let result = test.ingress(canister_id, "test", vec![]);
+    assert_empty_reply(result);
+    let reserved_balance = test
+        .canister_state(canister_id)
+        .system_state
+        .reserved_balance();
+    assert!(reserved_balance > Cycles::zero());
+    assert!(
+        test.canister_state(canister_id).system_state.balance() + reserved_balance
+            <= balance_before
+    );
+}
+
+#[test]
+fn memory_grow_fails_if_reserved_cycles_limit_is_exceeded() {
+    let mut test = ExecutionTestBuilder::new()
+        .with_subnet_total_memory(100 * 1024 * 1024)
+        .with_subnet_memory_threshold(0)
+        .build();
+    let wat = r#"
+        (module
+            (func (export "canister_update test")
+                (drop (memory.grow (i32.const 100)))
+            )
+            (memory 1 200)
+        )"#;
+    let canister_id = test.canister_from_wat(wat).unwrap();
+    test.update_reserved_cycles_limit(canister_id, Cycles::zero())
+        .unwrap();
+    let err = test.ingress(canister_id, "test", vec![]).unwrap_err();
+    assert_eq!(
+        ErrorCode::ReservedCyclesLimitExceededInMemoryGrow,
+        err.code()
+    );
+    assert_eq!(
+        Cycles::zero(),
+        test.canister_state(canister_id)
+            .system_state
+            .reserved_balance()
+    );
+}
+
+#[test]
+fn memory_grow_does_not_reserve_cycles_below_subnet_memory_threshold() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let wat = r#"
+        (module
+            (func (export "canister_update test")
+                (drop (memory.grow (i32.const 100)))
+            )
+            (memory 1 200)
+        )"#;
+    let canister_id = test.canister_from_wat(wat).unwrap();
+    let result = test.ingress(canister_id, "test", vec![]);
+    assert_empty_reply(result);
+    assert_eq!(
+        Cycles::zero(),
+        test.canister_state(canister_id)
+            .system_state
+            .reserved_balance()
+    );
+}
+
 #[test]
 fn subnet_available_memory_is_updated_by_canister_post_upgrade() {
     let mut test = ExecutionTestBuilder::new().build();
@@ -4554,3 +4739,47 @@ fn cycles_correct_if_update_fails() {
         initial_cycles - test.canister_execution_cost(b_id)
     );
 }
+
+#[test]
+fn debug_print_is_recorded_in_canister_log() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.universal_canister().unwrap();
+    let payload = wasm().debug_print(b"hello").build();
+    let result = test.ingress(canister_id, "update", payload);
+    assert_empty_reply(result);
+    let records = test
+        .canister_state(canister_id)
+        .system_state
+        .canister_log
+        .records();
+    let record = records.back().unwrap();
+    assert_eq!(record.content, b"hello".to_vec());
+    assert_eq!(
+        record.timestamp_nanos,
+        test.time().as_nanos_since_unix_epoch()
+    );
+}
+
+#[test]
+fn canister_log_is_kept_if_execution_traps() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.universal_canister().unwrap();
+    let payload = wasm()
+        .debug_print(b"before trap")
+        .trap_with_blob(b"oops")
+        .build();
+    let err = test.ingress(canister_id, "update", payload).unwrap_err();
+    assert_eq!(ErrorCode::CanisterCalledTrap, err.code());
+    let contents: Vec<_> = test
+        .canister_state(canister_id)
+        .system_state
+        .canister_log
+        .records()
+        .iter()
+        .map(|record| String::from_utf8_lossy(&record.content).to_string())
+        .collect();
+    assert_eq!(
+        contents[contents.len() - 2..],
+        ["before trap".to_string(), "[TRAP]: oops".to_string()]
+    );
+}
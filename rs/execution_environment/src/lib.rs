@@ -38,9 +38,11 @@ use ic_replicated_state::{CallOrigin, NetworkTopology, ReplicatedState};
 use ic_types::{messages::CallContextId, SubnetId};
 use ingress_filter::IngressFilter;
 use query_handler::HttpQueryHandler;
-pub use query_handler::InternalHttpQueryHandler;
-pub use scheduler::RoundSchedule;
+pub use query_handler::{
+    InternalHttpQueryHandler, QueryStatsCollector, QueryStatsPayloadBuilderImpl,
+};
 use scheduler::SchedulerImpl;
+pub use scheduler::{ExecutedInstructionsRecorder, RoundSchedule};
 use std::sync::{Arc, Mutex};
 use tower::limit::GlobalConcurrencyLimitLayer;
 
@@ -86,6 +88,8 @@ pub struct ExecutionServices {
     pub async_query_handler: QueryExecutionService,
     pub anonymous_query_handler: AnonymousQueryService,
     pub scheduler: Box<dyn Scheduler<State = ReplicatedState>>,
+    pub query_stats_collector: Arc<QueryStatsCollector>,
+    pub executed_instructions_recorder: Arc<ExecutedInstructionsRecorder>,
 }
 
 impl ExecutionServices {
@@ -131,6 +135,7 @@ impl ExecutionServices {
             config.clone(),
             Arc::clone(&cycles_account_manager),
         ));
+        let query_stats_collector = Arc::new(QueryStatsCollector::new());
         let sync_query_handler = Arc::new(InternalHttpQueryHandler::new(
             logger.clone(),
             hypervisor,
@@ -140,6 +145,7 @@ impl ExecutionServices {
             scheduler_config.max_instructions_per_message_without_dts,
             Arc::clone(&cycles_account_manager),
             config.composite_queries,
+            Arc::clone(&query_stats_collector),
         ));
         let threadpool = threadpool::Builder::new()
             .num_threads(config.query_execution_threads)
@@ -159,6 +165,7 @@ impl ExecutionServices {
             Arc::clone(&sync_query_handler) as Arc<_>,
             Arc::clone(&threadpool),
             Arc::clone(&state_reader),
+            Arc::clone(&query_stats_collector),
         );
         let ingress_filter = IngressFilter::new_service(
             concurrency_buffer.clone(),
@@ -189,6 +196,7 @@ impl ExecutionServices {
             config.rate_limiting_of_instructions,
             config.deterministic_time_slicing,
         ));
+        let executed_instructions_recorder = scheduler.executed_instructions_recorder();
 
         Self {
             ingress_filter,
@@ -198,6 +206,8 @@ impl ExecutionServices {
             async_query_handler,
             anonymous_query_handler,
             scheduler,
+            query_stats_collector,
+            executed_instructions_recorder,
         }
     }
 
@@ -212,6 +222,7 @@ impl ExecutionServices {
         QueryExecutionService,
         AnonymousQueryService,
         Box<dyn Scheduler<State = ReplicatedState>>,
+        Arc<QueryStatsCollector>,
     ) {
         (
             self.ingress_filter,
@@ -221,6 +232,7 @@ impl ExecutionServices {
             self.async_query_handler,
             self.anonymous_query_handler,
             self.scheduler,
+            self.query_stats_collector,
         )
     }
 }
@@ -2,6 +2,7 @@
 //! query methods via query calls.
 
 mod query_context;
+mod query_stats;
 #[cfg(test)]
 mod tests;
 
@@ -15,6 +16,10 @@ use ic_config::flag_status::FlagStatus;
 use ic_crypto_tree_hash::{flatmap, Label, LabeledTree, LabeledTree::SubTree};
 use ic_cycles_account_manager::CyclesAccountManager;
 use ic_error_types::{ErrorCode, RejectCode, UserError};
+use ic_ic00_types::{
+    FetchCanisterLogsRequest, FetchCanisterLogsResponse, LogVisibility, Method as Ic00Method,
+    Payload,
+};
 use ic_interfaces::execution_environment::{QueryExecutionService, QueryHandler};
 use ic_interfaces_state_manager::StateReader;
 use ic_logger::ReplicaLogger;
@@ -22,18 +27,21 @@ use ic_metrics::MetricsRegistry;
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::ReplicatedState;
 use ic_types::{
+    batch::QueryStats,
     ingress::WasmResult,
     messages::{
         Blob, Certificate, CertificateDelegation, HttpQueryResponse, HttpQueryResponseReply,
         UserQuery,
     },
-    CanisterId, NumInstructions,
+    CanisterId, Height, NumInstructions, PrincipalId,
 };
+pub use query_stats::{QueryStatsCollector, QueryStatsPayloadBuilderImpl};
 use serde::Serialize;
 use std::{
     convert::Infallible,
     future::Future,
     pin::Pin,
+    str::FromStr,
     sync::{Arc, Mutex},
     task::{Context, Poll},
 };
@@ -52,7 +60,7 @@ fn get_latest_certified_state_and_data_certificate(
     state_reader: Arc<dyn StateReader<State = ReplicatedState>>,
     certificate_delegation: Option<CertificateDelegation>,
     canister_id: CanisterId,
-) -> Option<(Arc<ReplicatedState>, Vec<u8>)> {
+) -> Option<(Arc<ReplicatedState>, Height, Vec<u8>)> {
     // The path to fetch the data certificate for the canister.
     let path = SubTree(flatmap! {
         label("canister") => SubTree(
@@ -72,6 +80,7 @@ fn get_latest_certified_state_and_data_certificate(
         .map(|(state, tree, cert)| {
             (
                 state,
+                cert.height,
                 into_cbor(&Certificate {
                     tree,
                     signature: Blob(cert.signed.signature.signature.get().0),
@@ -85,6 +94,76 @@ fn label<T: Into<Label>>(t: T) -> Label {
     t.into()
 }
 
+/// Handles a query sent to the management canister. Only
+/// `fetch_canister_logs` is available as a query.
+fn query_management_canister(
+    query: &UserQuery,
+    state: &ReplicatedState,
+) -> Result<WasmResult, UserError> {
+    match Ic00Method::from_str(&query.method_name) {
+        Ok(Ic00Method::FetchCanisterLogs) => {
+            let args = FetchCanisterLogsRequest::decode(&query.method_payload).map_err(|err| {
+                UserError::new(
+                    ErrorCode::InvalidManagementPayload,
+                    format!("Error decoding candid: {}", err),
+                )
+            })?;
+            fetch_canister_logs(query.source.get(), state, args)
+        }
+        Ok(_) | Err(_) => Err(UserError::new(
+            ErrorCode::CanisterMethodNotFound,
+            format!(
+                "Query method {} not found on the management canister.",
+                query.method_name
+            ),
+        )),
+    }
+}
+
+/// Returns the log records of a canister if `sender` is allowed to see them
+/// according to the canister's log visibility.
+fn fetch_canister_logs(
+    sender: PrincipalId,
+    state: &ReplicatedState,
+    args: FetchCanisterLogsRequest,
+) -> Result<WasmResult, UserError> {
+    let canister_id = args.get_canister_id();
+    let canister = state.canister_state(&canister_id).ok_or_else(|| {
+        UserError::new(
+            ErrorCode::CanisterNotFound,
+            format!("Canister {} not found.", canister_id),
+        )
+    })?;
+
+    match canister.system_state.log_visibility {
+        LogVisibility::Public => {}
+        LogVisibility::Controllers => {
+            if !canister.system_state.controllers.contains(&sender) {
+                return Err(UserError::new(
+                    ErrorCode::CanisterRejectedMessage,
+                    format!(
+                        "Caller {} is not allowed to query {} logs of canister {}.",
+                        sender,
+                        Ic00Method::FetchCanisterLogs,
+                        canister_id
+                    ),
+                ));
+            }
+        }
+    }
+
+    let response = FetchCanisterLogsResponse {
+        canister_log_records: canister
+            .system_state
+            .canister_log
+            .records()
+            .iter()
+            .cloned()
+            .collect(),
+    };
+    Ok(WasmResult::Reply(response.encode()))
+}
+
 pub struct InternalHttpQueryHandler {
     log: ReplicaLogger,
     hypervisor: Arc<Hypervisor>,
@@ -94,6 +173,7 @@ pub struct InternalHttpQueryHandler {
     max_instructions_per_query: NumInstructions,
     cycles_account_manager: Arc<CyclesAccountManager>,
     composite_queries: FlagStatus,
+    query_stats_collector: Arc<QueryStatsCollector>,
 }
 
 #[derive(Clone)]
@@ -102,6 +182,7 @@ pub(crate) struct HttpQueryHandler {
     internal: Arc<dyn QueryHandler<State = ReplicatedState>>,
     state_reader: Arc<dyn StateReader<State = ReplicatedState>>,
     threadpool: Arc<Mutex<threadpool::ThreadPool>>,
+    query_stats_collector: Arc<QueryStatsCollector>,
 }
 
 impl InternalHttpQueryHandler {
@@ -114,6 +195,7 @@ impl InternalHttpQueryHandler {
         max_instructions_per_query: NumInstructions,
         cycles_account_manager: Arc<CyclesAccountManager>,
         composite_queries: FlagStatus,
+        query_stats_collector: Arc<QueryStatsCollector>,
     ) -> Self {
         Self {
             log,
@@ -124,6 +206,7 @@ impl InternalHttpQueryHandler {
             max_instructions_per_query,
             cycles_account_manager,
             composite_queries,
+            query_stats_collector,
         }
     }
 }
@@ -137,6 +220,10 @@ impl QueryHandler for InternalHttpQueryHandler {
         state: Arc<ReplicatedState>,
         data_certificate: Vec<u8>,
     ) -> Result<WasmResult, UserError> {
+        if query.receiver == CanisterId::ic_00() {
+            return query_management_canister(&query, &state);
+        }
+
         let measurement_scope = MeasurementScope::root(&self.metrics.query);
 
         // Letting the canister grow arbitrarily when executing the
@@ -158,12 +245,29 @@ impl QueryHandler for InternalHttpQueryHandler {
             self.config.instruction_overhead_per_query_call,
             self.composite_queries,
         );
-        context.run(
+        let canister_id = query.receiver;
+        let ingress_payload_size = query.method_payload.len() as u64;
+        let result = context.run(
             query,
             &self.metrics,
             Arc::clone(&self.cycles_account_manager),
             &measurement_scope,
-        )
+        );
+        let egress_payload_size = match &result {
+            Ok(WasmResult::Reply(reply)) => reply.len(),
+            Ok(WasmResult::Reject(message)) => message.len(),
+            Err(err) => err.description().len(),
+        } as u64;
+        self.query_stats_collector.register_query_statistics(
+            canister_id,
+            &QueryStats {
+                num_calls: 1,
+                num_instructions: context.total_instructions_executed().get(),
+                ingress_payload_size,
+                egress_payload_size,
+            },
+        );
+        result
     }
 }
 
@@ -173,11 +277,13 @@ impl HttpQueryHandler {
         internal: Arc<dyn QueryHandler<State = ReplicatedState>>,
         threadpool: Arc<Mutex<threadpool::ThreadPool>>,
         state_reader: Arc<dyn StateReader<State = ReplicatedState>>,
+        query_stats_collector: Arc<QueryStatsCollector>,
     ) -> QueryExecutionService {
         let base_service = BoxCloneService::new(Self {
             internal,
             state_reader,
             threadpool,
+            query_stats_collector,
         });
         ServiceBuilder::new()
             .layer(concurrency_buffer)
@@ -214,6 +320,7 @@ impl Service<(UserQuery, Option<CertificateDelegation>)> for HttpQueryHandler {
     ) -> Self::Future {
         let internal = Arc::clone(&self.internal);
         let state_reader = Arc::clone(&self.state_reader);
+        let query_stats_collector = Arc::clone(&self.query_stats_collector);
         let (tx, rx) = oneshot::channel();
         let threadpool = self.threadpool.lock().unwrap().clone();
         threadpool.execute(move || {
@@ -226,7 +333,10 @@ impl Service<(UserQuery, Option<CertificateDelegation>)> for HttpQueryHandler {
                     certificate_delegation,
                     query.receiver,
                 ) {
-                    Some((state, cert)) => internal.query(query, state, cert),
+                    Some((state, height, cert)) => {
+                        query_stats_collector.set_epoch_from_height(height);
+                        internal.query(query, state, cert)
+                    }
                     None => Err(UserError::new(
                         ErrorCode::CertifiedStateUnavailable,
                         "Certified state is not available yet. Please try again...",
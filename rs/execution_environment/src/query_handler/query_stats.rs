@@ -0,0 +1,347 @@
+//! Collection of per-canister query statistics and their dissemination to the
+//! other replicas of the subnet via consensus.
+//!
+//! Every replica collects statistics about the queries it executes locally.
+//! Once an epoch is over, the replica includes its statistics for that epoch
+//! in a block it proposes. Message routing then aggregates the contributions
+//! of all replicas deterministically in the replicated state.
+
+use ic_interfaces::query_stats::{
+    InvalidQueryStatsPayload, QueryStatsPayloadBuilder, QueryStatsPayloadValidationError,
+    QueryStatsTransientValidationError,
+};
+use ic_interfaces::validation::ValidationError;
+use ic_interfaces_state_manager::StateReader;
+use ic_replicated_state::ReplicatedState;
+use ic_types::{
+    batch::{
+        epoch_from_height, CanisterQueryStats, QueryStats, QueryStatsEpoch, QueryStatsPayload,
+        ValidationContext,
+    },
+    CanisterId, CountBytes, Height, NodeId, NumBytes,
+};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::{Arc, Mutex},
+};
+
+#[derive(Default)]
+struct LocalQueryStats {
+    /// The epoch statistics are currently collected for.
+    current_epoch: QueryStatsEpoch,
+    /// Statistics collected during `current_epoch`.
+    current: BTreeMap<CanisterId, QueryStats>,
+    /// Statistics of past epochs that were not yet finalized by the subnet.
+    finished: BTreeMap<QueryStatsEpoch, BTreeMap<CanisterId, QueryStats>>,
+}
+
+/// Collects the statistics of the queries executed by this replica.
+#[derive(Default)]
+pub struct QueryStatsCollector {
+    stats: Mutex<LocalQueryStats>,
+}
+
+impl QueryStatsCollector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Moves on to the epoch of `height` (the height of the state queries are
+    /// executed on), closing the current epoch if it is older.
+    pub(crate) fn set_epoch_from_height(&self, height: Height) {
+        let epoch = epoch_from_height(height);
+        let mut stats = self.stats.lock().unwrap();
+        if epoch > stats.current_epoch {
+            let current = std::mem::take(&mut stats.current);
+            if !current.is_empty() {
+                let current_epoch = stats.current_epoch;
+                stats.finished.insert(current_epoch, current);
+            }
+            stats.current_epoch = epoch;
+        }
+    }
+
+    /// Records the statistics of a single query executed on `canister_id`.
+    pub(crate) fn register_query_statistics(&self, canister_id: CanisterId, query: &QueryStats) {
+        self.stats
+            .lock()
+            .unwrap()
+            .current
+            .entry(canister_id)
+            .or_default()
+            .saturating_accumulate(query);
+    }
+}
+
+/// Builds and validates the query statistics section of block payloads.
+pub struct QueryStatsPayloadBuilderImpl {
+    collector: Arc<QueryStatsCollector>,
+    state_reader: Arc<dyn StateReader<State = ReplicatedState>>,
+    node_id: NodeId,
+}
+
+impl QueryStatsPayloadBuilderImpl {
+    pub fn new(
+        collector: Arc<QueryStatsCollector>,
+        state_reader: Arc<dyn StateReader<State = ReplicatedState>>,
+        node_id: NodeId,
+    ) -> Self {
+        Self {
+            collector,
+            state_reader,
+            node_id,
+        }
+    }
+}
+
+fn has_contributed(
+    past_payloads: &[&QueryStatsPayload],
+    epoch: QueryStatsEpoch,
+    node_id: &NodeId,
+) -> bool {
+    past_payloads
+        .iter()
+        .any(|payload| payload.epoch == epoch && &payload.proposer == node_id)
+}
+
+impl QueryStatsPayloadBuilder for QueryStatsPayloadBuilderImpl {
+    fn get_query_stats_payload(
+        &self,
+        height: Height,
+        validation_context: &ValidationContext,
+        past_payloads: &[&QueryStatsPayload],
+        byte_limit: NumBytes,
+    ) -> Option<QueryStatsPayload> {
+        let current_epoch = epoch_from_height(height);
+        let state = self
+            .state_reader
+            .get_state_at(validation_context.certified_height)
+            .ok()?;
+        let raw_query_stats = &state.get_ref().metadata.raw_query_stats;
+
+        let mut local = self.collector.stats.lock().unwrap();
+        // Statistics of epochs the subnet already finalized can no longer be
+        // contributed.
+        local
+            .finished
+            .retain(|epoch, _| *epoch >= raw_query_stats.epoch);
+        let (epoch, canister_stats) = local.finished.iter().find(|(epoch, _)| {
+            **epoch < current_epoch
+                && !(**epoch == raw_query_stats.epoch
+                    && raw_query_stats.has_contribution(&self.node_id))
+                && !has_contributed(past_payloads, **epoch, &self.node_id)
+        })?;
+
+        let mut payload = QueryStatsPayload {
+            epoch: *epoch,
+            proposer: self.node_id,
+            stats: Vec::with_capacity(canister_stats.len()),
+        };
+        for (canister_id, stats) in canister_stats {
+            payload.stats.push(CanisterQueryStats {
+                canister_id: *canister_id,
+                stats: *stats,
+            });
+            if payload.count_bytes() as u64 > byte_limit.get() {
+                payload.stats.pop();
+                break;
+            }
+        }
+        if payload.stats.is_empty() {
+            return None;
+        }
+        Some(payload)
+    }
+
+    fn validate_query_stats_payload(
+        &self,
+        height: Height,
+        proposer: NodeId,
+        payload: Option<&QueryStatsPayload>,
+        validation_context: &ValidationContext,
+        past_payloads: &[&QueryStatsPayload],
+    ) -> Result<NumBytes, QueryStatsPayloadValidationError> {
+        let payload = match payload {
+            Some(payload) => payload,
+            None => return Ok(NumBytes::new(0)),
+        };
+
+        // Only the block maker may contribute statistics, otherwise it could
+        // use up the contributions of other nodes.
+        if payload.proposer != proposer {
+            return Err(ValidationError::Permanent(
+                InvalidQueryStatsPayload::ProposerMismatch {
+                    expected: proposer,
+                    reported: payload.proposer,
+                },
+            ));
+        }
+
+        let current_epoch = epoch_from_height(height);
+        if payload.epoch >= current_epoch {
+            return Err(ValidationError::Permanent(
+                InvalidQueryStatsPayload::EpochNotFinished {
+                    epoch: payload.epoch,
+                    current_epoch,
+                },
+            ));
+        }
+
+        let state = self
+            .state_reader
+            .get_state_at(validation_context.certified_height)
+            .map_err(|err| {
+                ValidationError::Transient(QueryStatsTransientValidationError::GetStateFailed(
+                    validation_context.certified_height,
+                    err,
+                ))
+            })?;
+        let state = state.get_ref();
+        let raw_query_stats = &state.metadata.raw_query_stats;
+        if payload.epoch < raw_query_stats.epoch {
+            return Err(ValidationError::Permanent(
+                InvalidQueryStatsPayload::EpochAlreadyAggregated {
+                    epoch: payload.epoch,
+                    aggregated_epoch: raw_query_stats.epoch,
+                },
+            ));
+        }
+
+        let is_subnet_member = state
+            .metadata
+            .network_topology
+            .subnets
+            .get(&state.metadata.own_subnet_id)
+            .map_or(false, |subnet| subnet.nodes.contains_key(&payload.proposer));
+        if !is_subnet_member {
+            return Err(ValidationError::Permanent(
+                InvalidQueryStatsPayload::InvalidProposer(payload.proposer),
+            ));
+        }
+
+        if (payload.epoch == raw_query_stats.epoch
+            && raw_query_stats.has_contribution(&payload.proposer))
+            || has_contributed(past_payloads, payload.epoch, &payload.proposer)
+        {
+            return Err(ValidationError::Permanent(
+                InvalidQueryStatsPayload::DuplicateContribution {
+                    epoch: payload.epoch,
+                    proposer: payload.proposer,
+                },
+            ));
+        }
+
+        let mut canister_ids = BTreeSet::new();
+        for entry in &payload.stats {
+            if !canister_ids.insert(entry.canister_id) {
+                return Err(ValidationError::Permanent(
+                    InvalidQueryStatsPayload::DuplicateCanisterId(entry.canister_id),
+                ));
+            }
+        }
+
+        Ok(NumBytes::new(payload.count_bytes() as u64))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_interfaces_state_manager::Labeled;
+    use ic_interfaces_state_manager_mocks::MockStateManager;
+    use ic_registry_subnet_type::SubnetType;
+    use ic_replicated_state::{NodeTopology, SubnetTopology};
+    use ic_test_utilities::{
+        mock_time,
+        types::ids::{canister_test_id, node_test_id, subnet_test_id},
+    };
+    use ic_types::{batch::QUERY_STATS_EPOCH_LENGTH, RegistryVersion};
+
+    /// Returns a payload builder of node 1 on a subnet of nodes 1 and 2.
+    fn payload_builder() -> QueryStatsPayloadBuilderImpl {
+        let subnet_id = subnet_test_id(1);
+        let mut state = ReplicatedState::new(subnet_id, SubnetType::Application);
+        state.metadata.network_topology.subnets.insert(
+            subnet_id,
+            SubnetTopology {
+                nodes: (1..=2)
+                    .map(|node| (node_test_id(node), NodeTopology::default()))
+                    .collect(),
+                ..Default::default()
+            },
+        );
+        let state = Arc::new(state);
+        let mut state_manager = MockStateManager::new();
+        state_manager
+            .expect_get_state_at()
+            .returning(move |height| Ok(Labeled::new(height, Arc::clone(&state))));
+        QueryStatsPayloadBuilderImpl::new(
+            Arc::new(QueryStatsCollector::new()),
+            Arc::new(state_manager),
+            node_test_id(1),
+        )
+    }
+
+    fn payload(proposer: u64) -> QueryStatsPayload {
+        QueryStatsPayload {
+            epoch: QueryStatsEpoch::from(0),
+            proposer: node_test_id(proposer),
+            stats: vec![CanisterQueryStats {
+                canister_id: canister_test_id(1),
+                stats: QueryStats {
+                    num_calls: 1,
+                    ..Default::default()
+                },
+            }],
+        }
+    }
+
+    fn validation_context() -> ValidationContext {
+        ValidationContext {
+            registry_version: RegistryVersion::from(1),
+            certified_height: Height::from(0),
+            time: mock_time(),
+        }
+    }
+
+    #[test]
+    fn validate_accepts_stats_of_the_block_maker() {
+        let builder = payload_builder();
+        let payload = payload(2);
+        assert_eq!(
+            builder
+                .validate_query_stats_payload(
+                    Height::from(QUERY_STATS_EPOCH_LENGTH),
+                    node_test_id(2),
+                    Some(&payload),
+                    &validation_context(),
+                    &[],
+                )
+                .unwrap(),
+            NumBytes::new(payload.count_bytes() as u64)
+        );
+    }
+
+    #[test]
+    fn validate_rejects_stats_impersonating_another_node() {
+        let builder = payload_builder();
+        // Node 1 makes the block but claims the stats were collected by node 2.
+        let impersonating_payload = payload(2);
+        match builder.validate_query_stats_payload(
+            Height::from(QUERY_STATS_EPOCH_LENGTH),
+            node_test_id(1),
+            Some(&impersonating_payload),
+            &validation_context(),
+            &[],
+        ) {
+            Err(ValidationError::Permanent(InvalidQueryStatsPayload::ProposerMismatch {
+                expected,
+                reported,
+            })) => {
+                assert_eq!(expected, node_test_id(1));
+                assert_eq!(reported, node_test_id(2));
+            }
+            other => panic!("Expected ProposerMismatch, got {:?}", other),
+        }
+    }
+}
@@ -3,12 +3,18 @@ use crate::InternalHttpQueryHandler;
 use ic_base_types::NumSeconds;
 use ic_config::execution_environment::INSTRUCTION_OVERHEAD_PER_QUERY_CALL;
 use ic_error_types::{ErrorCode, UserError};
+use ic_ic00_types::{
+    FetchCanisterLogsRequest, FetchCanisterLogsResponse, LogVisibility, Method as Ic00Method,
+    Payload,
+};
 use ic_registry_subnet_type::SubnetType;
 use ic_test_utilities::{
     types::ids::user_test_id,
     universal_canister::{call_args, wasm},
 };
-use ic_types::{ingress::WasmResult, messages::UserQuery, Cycles, NumInstructions};
+use ic_types::{
+    ingress::WasmResult, messages::UserQuery, CanisterId, Cycles, NumInstructions, UserId,
+};
 use std::sync::Arc;
 
 const CYCLES_BALANCE: Cycles = Cycles::new(100_000_000_000_000);
@@ -809,3 +815,73 @@ fn composite_query_fails_in_replicated_mode() {
     // Verify that we consume some cycles.
     assert!(balance_before > balance_after);
 }
+
+fn fetch_canister_logs(
+    test: &ExecutionTest,
+    source: UserId,
+    canister_id: CanisterId,
+) -> Result<WasmResult, UserError> {
+    test.query(
+        UserQuery {
+            source,
+            receiver: CanisterId::ic_00(),
+            method_name: Ic00Method::FetchCanisterLogs.to_string(),
+            method_payload: FetchCanisterLogsRequest::new(canister_id).encode(),
+            ingress_expiry: 0,
+            nonce: None,
+        },
+        Arc::new(test.state().clone()),
+        vec![],
+    )
+}
+
+#[test]
+fn fetch_canister_logs_returns_log_records() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.universal_canister().unwrap();
+    test.ingress(canister_id, "update", wasm().debug_print(b"hello").build())
+        .unwrap_err();
+
+    let result = fetch_canister_logs(&test, test.user_id(), canister_id).unwrap();
+    let response = match result {
+        WasmResult::Reply(bytes) => FetchCanisterLogsResponse::decode(&bytes).unwrap(),
+        WasmResult::Reject(msg) => panic!("Unexpected reject: {}", msg),
+    };
+    assert_eq!(
+        response.canister_log_records.last().unwrap().content,
+        b"hello".to_vec()
+    );
+}
+
+#[test]
+fn fetch_canister_logs_respects_log_visibility() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.universal_canister().unwrap();
+    let other_user = user_test_id(42);
+
+    // By default, only controllers can fetch the logs.
+    let err = fetch_canister_logs(&test, other_user, canister_id).unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterRejectedMessage);
+    assert!(fetch_canister_logs(&test, test.user_id(), canister_id).is_ok());
+
+    test.update_log_visibility(canister_id, LogVisibility::Public)
+        .unwrap();
+    assert_eq!(
+        test.canister_state(canister_id).system_state.log_visibility,
+        LogVisibility::Public
+    );
+    assert!(fetch_canister_logs(&test, other_user, canister_id).is_ok());
+}
+
+#[test]
+fn fetch_canister_logs_cannot_be_called_via_ingress() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.universal_canister().unwrap();
+    let err = test
+        .subnet_message(
+            Ic00Method::FetchCanisterLogs,
+            FetchCanisterLogsRequest::new(canister_id).encode(),
+        )
+        .unwrap_err();
+    assert_eq!(err.code(), ErrorCode::CanisterContractViolation);
+}
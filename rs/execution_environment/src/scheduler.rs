@@ -40,7 +40,10 @@ use std::{
     cmp::Reverse,
     collections::{BTreeMap, BTreeSet},
     str::FromStr,
-    sync::Arc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
 };
 
 mod scheduler_metrics;
@@ -62,6 +65,38 @@ pub(crate) mod test_utilities;
 #[cfg(test)]
 pub(crate) mod tests;
 
+/// Records the number of instructions that each canister executed in the
+/// execution rounds. The records are not part of the replicated state and are
+/// only kept while recording is enabled, e.g. by the replay tool.
+#[derive(Default)]
+pub struct ExecutedInstructionsRecorder {
+    enabled: AtomicBool,
+    instructions: Mutex<BTreeMap<CanisterId, NumInstructions>>,
+}
+
+impl ExecutedInstructionsRecorder {
+    /// Starts recording the executed instructions.
+    pub fn enable(&self) {
+        self.enabled.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns the instructions executed by each canister since the previous
+    /// call and resets the records.
+    pub fn take(&self) -> BTreeMap<CanisterId, NumInstructions> {
+        std::mem::take(&mut *self.instructions.lock().unwrap())
+    }
+
+    fn record(&self, instructions_by_canister: &BTreeMap<CanisterId, NumInstructions>) {
+        if !self.enabled.load(Ordering::Relaxed) {
+            return;
+        }
+        let mut instructions = self.instructions.lock().unwrap();
+        for (canister_id, executed) in instructions_by_canister {
+            *instructions.entry(*canister_id).or_default() += *executed;
+        }
+    }
+}
+
 ////////////////////////////////////////////////////////////////////////
 /// Scheduler Implementation
 
@@ -73,6 +108,7 @@ pub(crate) struct SchedulerImpl {
     cycles_account_manager: Arc<CyclesAccountManager>,
     bitcoin_canister: Arc<BitcoinCanister>,
     metrics: Arc<SchedulerMetrics>,
+    executed_instructions_recorder: Arc<ExecutedInstructionsRecorder>,
     log: ReplicaLogger,
     thread_pool: RefCell<scoped_threadpool::Pool>,
     rate_limiting_of_heap_delta: FlagStatus,
@@ -287,6 +323,7 @@ impl SchedulerImpl {
             cycles_account_manager,
             bitcoin_canister,
             metrics: Arc::new(SchedulerMetrics::new(metrics_registry)),
+            executed_instructions_recorder: Default::default(),
             log,
             rate_limiting_of_heap_delta,
             rate_limiting_of_instructions,
@@ -294,6 +331,11 @@ impl SchedulerImpl {
         }
     }
 
+    /// Returns the recorder of the instructions executed by each canister.
+    pub(crate) fn executed_instructions_recorder(&self) -> Arc<ExecutedInstructionsRecorder> {
+        Arc::clone(&self.executed_instructions_recorder)
+    }
+
     /// Makes progress in executing long-running `install_code` messages.
     fn advance_long_running_install_code(
         &self,
@@ -775,6 +817,8 @@ impl SchedulerImpl {
                 result.messages_executed,
             );
             heap_delta += result.heap_delta;
+            self.executed_instructions_recorder
+                .record(&result.instructions_by_canister);
         }
 
         // Since there are multiple threads, we update the global limit using
@@ -1592,6 +1636,7 @@ struct ExecutionThreadResult {
     slices_executed: NumSlices,
     messages_executed: NumMessages,
     heap_delta: NumBytes,
+    instructions_by_canister: BTreeMap<CanisterId, NumInstructions>,
     round_limits: RoundLimits,
 }
 
@@ -1626,6 +1671,7 @@ fn execute_canisters_on_thread(
     let mut total_slices_executed = NumSlices::from(0);
     let mut total_messages_executed = NumMessages::from(0);
     let mut total_heap_delta = NumBytes::from(0);
+    let mut instructions_by_canister = BTreeMap::new();
 
     let instruction_limits = InstructionLimits::new(
         deterministic_time_slicing,
@@ -1704,6 +1750,9 @@ fn execute_canisters_on_thread(
             }
             total_slices_executed.inc_assign();
             canister = new_canister;
+            *instructions_by_canister
+                .entry(canister.canister_id())
+                .or_default() += round_instructions_executed;
             round_limits.instructions -=
                 as_round_instructions(config.instruction_overhead_per_message);
             total_heap_delta += heap_delta;
@@ -1749,6 +1798,7 @@ fn execute_canisters_on_thread(
         slices_executed: total_slices_executed,
         messages_executed: total_messages_executed,
         heap_delta: total_heap_delta,
+        instructions_by_canister,
         round_limits,
     }
 }
@@ -1940,7 +1990,9 @@ fn can_execute_msg(
         };
 
         // Only one install code message allowed at a time.
-        if let Some(Ic00Method::InstallCode) = maybe_instal_code_method {
+        if let Some(Ic00Method::InstallCode) | Some(Ic00Method::InstallChunkedCode) =
+            maybe_instal_code_method
+        {
             return false;
         }
     }
@@ -1985,6 +2037,8 @@ fn get_instructions_limits_for_subnet_message(
             | SetupInitialDKG
             | SignWithECDSA
             | ComputeInitialEcdsaDealings
+            | CanisterInfo
+            | FetchCanisterLogs
             | StartCanister
             | StopCanister
             | UninstallCode
@@ -1996,8 +2050,15 @@ fn get_instructions_limits_for_subnet_message(
             | BitcoinGetCurrentFeePercentiles
             | BitcoinGetSuccessors
             | ProvisionalCreateCanisterWithCycles
-            | ProvisionalTopUpCanister => default_limits,
-            InstallCode => InstructionLimits::new(
+            | ProvisionalTopUpCanister
+            | TakeCanisterSnapshot
+            | LoadCanisterSnapshot
+            | ListCanisterSnapshots
+            | DeleteCanisterSnapshot
+            | UploadChunk
+            | StoredChunks
+            | ClearChunkStore => default_limits,
+            InstallCode | InstallChunkedCode => InstructionLimits::new(
                 dts,
                 config.max_instructions_per_install_code,
                 config.max_instructions_per_install_code_slice,
@@ -2031,13 +2092,23 @@ fn is_bitcoin_request(msg: &CanisterInputMessage) -> bool {
                 | SetupInitialDKG
                 | SignWithECDSA
                 | ComputeInitialEcdsaDealings
+                | CanisterInfo
+                | FetchCanisterLogs
                 | StartCanister
                 | StopCanister
                 | UninstallCode
                 | UpdateSettings
                 | ProvisionalCreateCanisterWithCycles
                 | ProvisionalTopUpCanister
-                | InstallCode => false,
+                | InstallCode
+                | TakeCanisterSnapshot
+                | LoadCanisterSnapshot
+                | ListCanisterSnapshots
+                | DeleteCanisterSnapshot
+                | UploadChunk
+                | StoredChunks
+                | ClearChunkStore
+                | InstallChunkedCode => false,
             },
             Err(_) => false,
         },
@@ -48,7 +48,9 @@ use ic_test_utilities::{
 };
 use ic_types::{
     ingress::{IngressState, IngressStatus},
-    messages::{CallContextId, Ingress, MessageId, Request, RequestOrResponse, Response},
+    messages::{
+        CallContextId, Ingress, MessageId, Request, RequestOrResponse, Response, NO_DEADLINE,
+    },
     methods::{Callback, FuncRef, SystemMethod, WasmClosure, WasmMethod},
     CanisterTimer, ComputeAllocation, Cycles, ExecutionRound, MemoryAllocation, NumInstructions,
     Randomness, Time, UserId,
@@ -334,6 +336,7 @@ impl SchedulerTest {
             compute_allocation: None,
             memory_allocation: None,
             query_allocation: None,
+            sender_canister_version: None,
         };
 
         let caller = self.xnet_canister_id();
@@ -958,6 +961,7 @@ impl TestWasmExecutorCore {
                     accessed_pages: 0,
                     dirty_pages: 0,
                 },
+                canister_log: Default::default(),
             };
             self.schedule
                 .push((self.round, canister_id, instructions_to_execute));
@@ -996,6 +1000,7 @@ impl TestWasmExecutorCore {
             allocated_message_bytes: NumBytes::from(0),
             num_instructions_left: instructions_left,
             instance_stats,
+            canister_log: Default::default(),
         };
         self.schedule
             .push((self.round, canister_id, instructions_to_execute));
@@ -1092,12 +1097,14 @@ impl TestWasmExecutorCore {
                 on_reply: closure.clone(),
                 on_reject: closure,
                 on_cleanup: None,
+                deadline: NO_DEADLINE,
             })
             .map_err(|err| err.to_string())?;
         let request = Request {
             receiver,
             sender,
             sender_reply_callback: callback,
+            deadline: NO_DEADLINE,
             payment: Cycles::zero(),
             method_name: "update".into(),
             method_payload: encode_message_id_as_payload(call_message_id),
@@ -2,18 +2,35 @@ use crate::types::Response;
 use ic_base_types::SubnetId;
 use ic_error_types::{ErrorCode, UserError};
 use ic_ic00_types::{CanisterStatusType, EmptyBlob, Payload as Ic00Payload, IC_00};
-use ic_interfaces::execution_environment::IngressHistoryWriter;
+use ic_interfaces::execution_environment::{IngressHistoryWriter, SubnetAvailableMemory};
 use ic_logger::{error, ReplicaLogger};
 use ic_replicated_state::{CanisterStatus, ReplicatedState};
 use ic_types::{
     ingress::{IngressState, IngressStatus, WasmResult},
     messages::{Payload, StopCanisterContext},
-    CanisterId,
+    CanisterId, NumBytes, ResourceSaturation,
 };
 use std::{mem, sync::Arc};
 
 pub(crate) const GOVERNANCE_CANISTER_ID: CanisterId = CanisterId::from_u64(1);
 
+/// Returns the current memory usage of the subnet relative to the threshold
+/// above which storage reservations kick in, given the memory that is still
+/// available on the subnet.
+pub(crate) fn subnet_memory_saturation(
+    subnet_available_memory: &SubnetAvailableMemory,
+    subnet_memory_threshold: NumBytes,
+    subnet_memory_capacity: NumBytes,
+) -> ResourceSaturation {
+    let capacity = subnet_memory_capacity.get();
+    let available = subnet_available_memory.get_total_memory().max(0) as u64;
+    ResourceSaturation::new(
+        capacity.saturating_sub(available),
+        subnet_memory_threshold.get(),
+        capacity,
+    )
+}
+
 /// Sends responses to their callers.
 ///
 /// * Ingress responses are written to ingress history.
@@ -89,6 +106,7 @@ pub fn process_stopping_canisters(
                         sender,
                         reply_callback,
                         cycles,
+                        deadline,
                     } => {
                         // Responding to stop_canister request from a canister.
                         let subnet_id_as_canister_id = CanisterId::from(own_subnet_id);
@@ -96,6 +114,7 @@ pub fn process_stopping_canisters(
                             originator: sender,
                             respondent: subnet_id_as_canister_id,
                             originator_reply_callback: reply_callback,
+                            deadline,
                             refund: cycles,
                             response_payload: Payload::Data(EmptyBlob.encode()),
                         };
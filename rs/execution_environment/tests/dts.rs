@@ -232,6 +232,9 @@ fn setup_dts_install_code(
             compute_allocation: Some(1u32.into()),
             memory_allocation: None,
             freezing_threshold: Some(freezing_threshold_in_seconds.into()),
+            log_visibility: None,
+            wasm_memory_limit: None,
+            reserved_cycles_limit: None,
         }),
     );
 
@@ -436,6 +439,9 @@ fn dts_pending_upgrade_with_heartbeat() {
         compute_allocation: None,
         memory_allocation: None,
         freezing_threshold: None,
+        log_visibility: None,
+        wasm_memory_limit: None,
+        reserved_cycles_limit: None,
     });
 
     let canister = env
@@ -533,6 +539,9 @@ fn dts_scheduling_of_install_code() {
         compute_allocation: None,
         memory_allocation: None,
         freezing_threshold: None,
+        log_visibility: None,
+        wasm_memory_limit: None,
+        reserved_cycles_limit: None,
     });
 
     let n = 10;
@@ -695,6 +704,9 @@ fn dts_pending_install_code_does_not_block_subnet_messages_of_other_canisters()
         compute_allocation: None,
         memory_allocation: None,
         freezing_threshold: None,
+        log_visibility: None,
+        wasm_memory_limit: None,
+        reserved_cycles_limit: None,
     });
 
     let mut canister = vec![];
@@ -814,6 +826,9 @@ fn dts_pending_execution_blocks_subnet_messages_to_the_same_canister() {
         compute_allocation: None,
         memory_allocation: None,
         freezing_threshold: None,
+        log_visibility: None,
+        wasm_memory_limit: None,
+        reserved_cycles_limit: None,
     });
 
     let canister = env
@@ -905,6 +920,9 @@ fn dts_pending_install_code_blocks_update_messages_to_the_same_canister() {
         compute_allocation: None,
         memory_allocation: None,
         freezing_threshold: None,
+        log_visibility: None,
+        wasm_memory_limit: None,
+        reserved_cycles_limit: None,
     });
 
     let canister = env.create_canister_with_cycles(INITIAL_CYCLES_BALANCE, settings);
@@ -991,6 +1009,9 @@ fn dts_long_running_install_and_update() {
             compute_allocation: None,
             memory_allocation: None,
             freezing_threshold: None,
+            log_visibility: None,
+            wasm_memory_limit: None,
+            reserved_cycles_limit: None,
         });
 
         let id = env
@@ -1201,6 +1222,9 @@ fn dts_unrelated_subnet_messages_make_progress() {
         compute_allocation: None,
         memory_allocation: None,
         freezing_threshold: None,
+        log_visibility: None,
+        wasm_memory_limit: None,
+        reserved_cycles_limit: None,
     });
 
     let canister = env
@@ -1609,6 +1633,9 @@ fn dts_canister_uninstalled_due_to_resource_charges_with_aborted_updrade() {
         compute_allocation: Some(1u32.into()),
         memory_allocation: None,
         freezing_threshold: None,
+        log_visibility: None,
+        wasm_memory_limit: None,
+        reserved_cycles_limit: None,
     });
 
     let canister = env
@@ -1676,6 +1703,9 @@ fn dts_canister_uninstalled_due_resource_charges_with_aborted_update() {
             compute_allocation: Some(1u32.into()),
             memory_allocation: None,
             freezing_threshold: None,
+            log_visibility: None,
+            wasm_memory_limit: None,
+            reserved_cycles_limit: None,
         });
 
         let id = env
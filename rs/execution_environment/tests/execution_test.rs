@@ -321,6 +321,9 @@ fn test_canister_out_of_cycles() {
             compute_allocation: Some(candid::Nat::from(1)),
             memory_allocation: None,
             freezing_threshold: None,
+            log_visibility: None,
+            wasm_memory_limit: None,
+            reserved_cycles_limit: None,
         }),
     );
 
@@ -384,6 +387,9 @@ fn canister_has_zero_balance_when_uninstalled_due_to_low_cycles() {
                 compute_allocation: Some(candid::Nat::from(1)),
                 memory_allocation: None,
                 freezing_threshold: None,
+                log_visibility: None,
+                wasm_memory_limit: None,
+                reserved_cycles_limit: None,
             }),
             INITIAL_CYCLES_BALANCE,
         )
@@ -679,6 +685,9 @@ fn exceeding_memory_capacity_fails_when_memory_allocation_changes() {
                 compute_allocation: None,
                 memory_allocation: None,
                 freezing_threshold: None,
+                log_visibility: None,
+                wasm_memory_limit: None,
+                reserved_cycles_limit: None,
             }),
             INITIAL_CYCLES_BALANCE,
         )
@@ -694,6 +703,9 @@ fn exceeding_memory_capacity_fails_when_memory_allocation_changes() {
                 compute_allocation: None,
                 memory_allocation: Some(candid::Nat::from(20u64 * 1024 * 1024 + 1)),
                 freezing_threshold: None,
+                log_visibility: None,
+                wasm_memory_limit: None,
+                reserved_cycles_limit: None,
             },
         )
         .unwrap_err();
@@ -708,6 +720,9 @@ fn exceeding_memory_capacity_fails_when_memory_allocation_changes() {
             compute_allocation: None,
             memory_allocation: Some(candid::Nat::from(20u64 * 1024 * 1024)),
             freezing_threshold: None,
+            log_visibility: None,
+            wasm_memory_limit: None,
+            reserved_cycles_limit: None,
         },
     )
     .unwrap();
@@ -756,6 +771,9 @@ fn exceeding_memory_capacity_fails_during_message_execution() {
                 compute_allocation: None,
                 memory_allocation: None,
                 freezing_threshold: None,
+                log_visibility: None,
+                wasm_memory_limit: None,
+                reserved_cycles_limit: None,
             }),
             INITIAL_CYCLES_BALANCE,
         )
@@ -821,6 +839,9 @@ fn max_canister_memory_respected_even_when_no_memory_allocation_is_set() {
                 compute_allocation: None,
                 memory_allocation: None,
                 freezing_threshold: None,
+                log_visibility: None,
+                wasm_memory_limit: None,
+                reserved_cycles_limit: None,
             }),
             INITIAL_CYCLES_BALANCE,
         )
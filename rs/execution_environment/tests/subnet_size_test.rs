@@ -193,6 +193,9 @@ fn simulate_one_gib_per_second_cost(
             compute_allocation: Some(candid::Nat::from(compute_allocation.as_percent())),
             memory_allocation: Some(candid::Nat::from(one_gib)),
             freezing_threshold: None,
+            log_visibility: None,
+            wasm_memory_limit: None,
+            reserved_cycles_limit: None,
         }),
     );
 
@@ -546,7 +549,13 @@ fn simulate_create_canister_cost(subnet_type: SubnetType, subnet_size: usize) ->
         .call_with_cycles(
             ic00::IC_00,
             ic00::Method::CreateCanister,
-            call_args().other_side(Encode!(&ic00::CreateCanisterArgs { settings: None }).unwrap()),
+            call_args().other_side(
+                Encode!(&ic00::CreateCanisterArgs {
+                    settings: None,
+                    sender_canister_version: None
+                })
+                .unwrap(),
+            ),
             canister_b_initial_balance.into_parts(),
         )
         .build();
@@ -652,6 +661,7 @@ fn get_cycles_account_manager_config(subnet_type: SubnetType) -> CyclesAccountMa
             ecdsa_signature_fee: ECDSA_SIGNATURE_FEE,
             http_request_baseline_fee: Cycles::new(0),
             http_request_per_byte_fee: Cycles::new(0),
+            subnet_memory_reservation: Duration::from_secs(30 * 24 * 60 * 60),
         },
         SubnetType::Application | SubnetType::VerifiedApplication => CyclesAccountManagerConfig {
             reference_subnet_size: DEFAULT_REFERENCE_SUBNET_SIZE,
@@ -673,6 +683,7 @@ fn get_cycles_account_manager_config(subnet_type: SubnetType) -> CyclesAccountMa
             ecdsa_signature_fee: ECDSA_SIGNATURE_FEE,
             http_request_baseline_fee: Cycles::new(400_000_000),
             http_request_per_byte_fee: Cycles::new(100_000),
+            subnet_memory_reservation: Duration::from_secs(30 * 24 * 60 * 60),
         },
     }
 }
@@ -87,6 +87,7 @@ pub(crate) fn make_response(user_error: UserError) -> Response<Body> {
         C::CanisterMethodNotFound => StatusCode::NOT_FOUND,
         C::CanisterAlreadyInstalled => StatusCode::PRECONDITION_FAILED,
         C::CanisterWasmModuleNotFound => StatusCode::SERVICE_UNAVAILABLE,
+        C::CanisterSnapshotNotFound => StatusCode::NOT_FOUND,
         C::InsufficientMemoryAllocation => StatusCode::SERVICE_UNAVAILABLE,
         C::InsufficientCyclesForCreateCanister => StatusCode::SERVICE_UNAVAILABLE,
         C::SubnetNotFound => StatusCode::NOT_FOUND,
@@ -117,6 +118,11 @@ pub(crate) fn make_response(user_error: UserError) -> Response<Body> {
         C::QueryCallGraphTooDeep => StatusCode::INTERNAL_SERVER_ERROR,
         C::QueryCallGraphTotalInstructionLimitExceeded => StatusCode::INTERNAL_SERVER_ERROR,
         C::CompositeQueryCalledInReplicatedMode => StatusCode::INTERNAL_SERVER_ERROR,
+        C::CanisterWasmMemoryLimitExceeded => StatusCode::INTERNAL_SERVER_ERROR,
+        C::ReservedCyclesLimitExceededInMemoryAllocation => StatusCode::INTERNAL_SERVER_ERROR,
+        C::ReservedCyclesLimitExceededInMemoryGrow => StatusCode::INTERNAL_SERVER_ERROR,
+        C::InsufficientCyclesInMemoryGrow => StatusCode::INTERNAL_SERVER_ERROR,
+        C::InsufficientCyclesInMemoryAllocation => StatusCode::INTERNAL_SERVER_ERROR,
         C::CanisterNotHostedBySubnet => StatusCode::NOT_FOUND,
     };
     make_plaintext_response(status, user_error.description().to_string())
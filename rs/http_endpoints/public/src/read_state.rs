@@ -416,7 +416,9 @@ mod test {
     use ic_interfaces_state_manager_mocks::MockStateManager;
     use ic_metrics::MetricsRegistry;
     use ic_registry_subnet_type::SubnetType;
-    use ic_replicated_state::{BitcoinState, CanisterQueues, ReplicatedState, SystemMetadata};
+    use ic_replicated_state::{
+        BitcoinState, CanisterQueues, CanisterSnapshots, ReplicatedState, SystemMetadata,
+    };
     use ic_test_utilities::{
         mock_time,
         state::insert_dummy_canister,
@@ -554,6 +556,7 @@ mod test {
                         CanisterQueues::default(),
                         Vec::new(),
                         BitcoinState::default(),
+                        CanisterSnapshots::default(),
                     )),
                 )
             });
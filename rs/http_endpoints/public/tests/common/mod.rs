@@ -18,7 +18,8 @@ use ic_registry_provisional_whitelist::ProvisionalWhitelist;
 use ic_registry_routing_table::{CanisterMigrations, RoutingTable};
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::{
-    BitcoinState, CanisterQueues, NetworkTopology, ReplicatedState, SystemMetadata,
+    BitcoinState, CanisterQueues, CanisterSnapshots, NetworkTopology, ReplicatedState,
+    SystemMetadata,
 };
 use ic_test_utilities::{
     consensus::MockConsensusCache, mock_time, state::ReplicatedStateBuilder,
@@ -163,6 +164,7 @@ pub(crate) fn basic_state_manager_mock() -> MockStateManager {
                     CanisterQueues::default(),
                     Vec::new(),
                     BitcoinState::default(),
+                    CanisterSnapshots::default(),
                 )),
             )
         });
@@ -33,7 +33,8 @@ use ic_registry_keys::make_crypto_threshold_signing_pubkey_key;
 use ic_registry_routing_table::{CanisterMigrations, RoutingTable};
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::{
-    BitcoinState, CanisterQueues, NetworkTopology, ReplicatedState, SystemMetadata,
+    BitcoinState, CanisterQueues, CanisterSnapshots, NetworkTopology, ReplicatedState,
+    SystemMetadata,
 };
 use ic_test_utilities::{
     consensus::MockConsensusCache,
@@ -197,6 +198,7 @@ fn test_healthy_behind() {
                     CanisterQueues::default(),
                     Vec::new(),
                     BitcoinState::default(),
+                    CanisterSnapshots::default(),
                 )),
             )
         });
@@ -15,7 +15,7 @@ use ic_types::{
         AnonymousQuery, AnonymousQueryResponse, CertificateDelegation, HttpQueryResponse,
         MessageId, SignedIngressContent, UserQuery,
     },
-    Cycles, ExecutionRound, Height, NumInstructions, NumPages, Randomness, Time,
+    CanisterLog, Cycles, ExecutionRound, Height, NumInstructions, NumPages, Randomness, Time,
 };
 use serde::{Deserialize, Serialize};
 use std::convert::TryFrom;
@@ -462,6 +462,12 @@ pub trait SystemApi {
     /// Outputs the specified bytes on the heap as a string on STDOUT.
     fn ic0_debug_print(&self, src: u32, size: u32, heap: &[u8]) -> HypervisorResult<()>;
 
+    /// Saves the specified bytes on the heap as a record in the canister log.
+    ///
+    /// Unlike `ic0_debug_print()`, this is never rate limited and never fails:
+    /// an out of bounds message is replaced by a placeholder.
+    fn save_log_message(&mut self, src: u32, size: u32, heap: &[u8]);
+
     /// Traps, with a possibly helpful message
     fn ic0_trap(&self, src: u32, size: u32, heap: &[u8]) -> HypervisorResult<()>;
 
@@ -518,6 +524,12 @@ pub trait SystemApi {
     /// See https://sdk.dfinity.org/docs/interface-spec/index.html#system-api-call
     fn ic0_call_on_cleanup(&mut self, fun: u32, env: u32) -> HypervisorResult<()>;
 
+    /// Turns the call under construction into a best-effort call, whose
+    /// callback expires with a `SYS_UNKNOWN` reject if no response arrives
+    /// within `timeout_seconds` (capped at `MAX_CALL_TIMEOUT_SECONDS`). Can be
+    /// called at most once between `ic0.call_new` and `ic0.call_perform`.
+    fn ic0_call_with_best_effort_response(&mut self, timeout_seconds: u32) -> HypervisorResult<()>;
+
     /// (deprecated) Please use `ic0_call_cycles_add128` instead, as this API
     /// can only add a 64-bit value.
     ///
@@ -651,6 +663,15 @@ pub trait SystemApi {
     /// The canister can query the IC for its version.
     fn ic0_canister_version(&self) -> HypervisorResult<u64>;
 
+    /// Checks whether the principal given by the blob `heap[src..src+size]`
+    /// is one of the controllers of the canister. Returns 1 if it is and 0
+    /// otherwise. Traps if the blob is not a valid principal.
+    fn ic0_is_controller(&self, src: u32, size: u32, heap: &[u8]) -> HypervisorResult<i32>;
+
+    /// Returns 1 if the canister is executing in replicated mode (e.g. an
+    /// update call) and 0 otherwise (e.g. a non-replicated query).
+    fn ic0_in_replicated_execution(&self) -> HypervisorResult<i32>;
+
     /// The canister can query the "performance counter", which is
     /// a deterministic monotonically increasing integer approximating
     /// the amount of work the canister has done since the beginning of
@@ -904,6 +925,9 @@ pub struct WasmExecutionOutput {
     pub allocated_bytes: NumBytes,
     pub allocated_message_bytes: NumBytes,
     pub instance_stats: InstanceStats,
+    /// The log records produced by the execution, including the ones
+    /// produced before a trap.
+    pub canister_log: CanisterLog,
 }
 
 impl fmt::Display for WasmExecutionOutput {
@@ -1,6 +1,6 @@
 use ic_base_types::{CanisterIdError, PrincipalIdBlobParseError};
 use ic_error_types::UserError;
-use ic_types::{methods::WasmMethod, CanisterId, Cycles, NumInstructions};
+use ic_types::{methods::WasmMethod, CanisterId, Cycles, NumBytes, NumInstructions};
 use ic_wasm_types::{WasmEngineError, WasmInstrumentationError, WasmValidationError};
 use serde::{Deserialize, Serialize};
 
@@ -133,6 +133,26 @@ pub enum HypervisorError {
     },
     /// A canister has written too much new data in a single message.
     MemoryAccessLimitExceeded(String),
+    /// The canister attempted to grow its Wasm memory beyond the
+    /// `wasm_memory_limit` canister setting.
+    WasmMemoryLimitExceeded {
+        bytes: NumBytes,
+        limit: NumBytes,
+    },
+    /// Growing the memory of the canister would require reserving more cycles
+    /// than the `reserved_cycles_limit` canister setting allows.
+    ReservedCyclesLimitExceededInMemoryGrow {
+        bytes: NumBytes,
+        requested: Cycles,
+        limit: Cycles,
+    },
+    /// The canister does not have enough cycles to reserve for growing its
+    /// memory while the subnet is under storage pressure.
+    InsufficientCyclesInMemoryGrow {
+        bytes: NumBytes,
+        available: Cycles,
+        required: Cycles,
+    },
 }
 
 impl From<WasmInstrumentationError> for HypervisorError {
@@ -308,6 +328,43 @@ impl HypervisorError {
                 format!("Canister exceeded memory access limits: {}", s)
 
             ),
+            Self::WasmMemoryLimitExceeded { bytes, limit } => UserError::new(
+                E::CanisterWasmMemoryLimitExceeded,
+                format!(
+                    "Canister {} attempted to grow its Wasm memory to {} bytes, \
+                    which exceeds its Wasm memory limit of {} bytes.",
+                    canister_id, bytes, limit
+                ),
+            ),
+            Self::ReservedCyclesLimitExceededInMemoryGrow {
+                bytes,
+                requested,
+                limit,
+            } => UserError::new(
+                E::ReservedCyclesLimitExceededInMemoryGrow,
+                format!(
+                    "Canister {} cannot grow memory by {} bytes due to its reserved cycles \
+                    limit. The current limit ({}) would be exceeded by {}.",
+                    canister_id,
+                    bytes,
+                    limit,
+                    requested - limit
+                ),
+            ),
+            Self::InsufficientCyclesInMemoryGrow {
+                bytes,
+                available,
+                required,
+            } => UserError::new(
+                E::InsufficientCyclesInMemoryGrow,
+                format!(
+                    "Canister {} cannot grow memory by {} bytes due to insufficient cycles. \
+                    At least {} additional cycles are required.",
+                    canister_id,
+                    bytes,
+                    required - available
+                ),
+            ),
         }
     }
 
@@ -337,6 +394,13 @@ impl HypervisorError {
             HypervisorError::Aborted => "Aborted",
             HypervisorError::SliceOverrun { .. } => "SliceOverrun",
             HypervisorError::MemoryAccessLimitExceeded(_) => "MemoryAccessLimitExceeded",
+            HypervisorError::WasmMemoryLimitExceeded { .. } => "WasmMemoryLimitExceeded",
+            HypervisorError::ReservedCyclesLimitExceededInMemoryGrow { .. } => {
+                "ReservedCyclesLimitExceededInMemoryGrow"
+            }
+            HypervisorError::InsufficientCyclesInMemoryGrow { .. } => {
+                "InsufficientCyclesInMemoryGrow"
+            }
         }
     }
 
@@ -368,7 +432,10 @@ impl HypervisorError {
             | HypervisorError::MessageRejected
             | HypervisorError::InsufficientCyclesBalance(_)
             | HypervisorError::WasmReservedPages
-            | HypervisorError::MemoryAccessLimitExceeded(_) => false,
+            | HypervisorError::MemoryAccessLimitExceeded(_)
+            | HypervisorError::WasmMemoryLimitExceeded { .. }
+            | HypervisorError::ReservedCyclesLimitExceededInMemoryGrow { .. }
+            | HypervisorError::InsufficientCyclesInMemoryGrow { .. } => false,
         }
     }
 }
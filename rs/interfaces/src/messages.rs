@@ -1,4 +1,5 @@
 //! Messages used in various components.
+use ic_ic00_types::CanisterChangeOrigin;
 use ic_types::{
     messages::{Ingress, Request, Response, StopCanisterContext},
     CanisterId, Cycles, PrincipalId,
@@ -84,6 +85,24 @@ impl RequestOrIngress {
             RequestOrIngress::Ingress(_) => Cycles::zero(),
         }
     }
+
+    /// Returns the origin of a canister change triggered by this message.
+    ///
+    /// `sender_canister_version` is the version the calling canister reported
+    /// in the call arguments; it is ignored for ingress messages.
+    pub fn canister_change_origin(
+        &self,
+        sender_canister_version: Option<u64>,
+    ) -> CanisterChangeOrigin {
+        match self {
+            RequestOrIngress::Request(request) => {
+                CanisterChangeOrigin::from_canister(request.sender.get(), sender_canister_version)
+            }
+            RequestOrIngress::Ingress(ingress) => {
+                CanisterChangeOrigin::from_user(ingress.source.get())
+            }
+        }
+    }
 }
 
 impl From<RequestOrIngress> for StopCanisterContext {
@@ -94,6 +113,7 @@ impl From<RequestOrIngress> for StopCanisterContext {
                 sender: req.sender,
                 reply_callback: req.sender_reply_callback,
                 cycles: Arc::make_mut(&mut req).payment.take(),
+                deadline: req.deadline,
             },
             RequestOrIngress::Ingress(ingress) => StopCanisterContext::Ingress {
                 sender: ingress.source,
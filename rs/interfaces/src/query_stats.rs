@@ -0,0 +1,92 @@
+//! Query statistics related public interfaces.
+use crate::validation::ValidationError;
+use ic_interfaces_state_manager::StateManagerError;
+use ic_types::{
+    batch::{QueryStatsEpoch, QueryStatsPayload, ValidationContext},
+    consensus::Payload,
+    CanisterId, Height, NodeId, NumBytes, Time,
+};
+
+/// A QueryStatsPayload error from which it is not possible to recover.
+#[derive(Debug)]
+pub enum InvalidQueryStatsPayload {
+    /// The payload contains statistics for an epoch that has not ended yet.
+    EpochNotFinished {
+        epoch: QueryStatsEpoch,
+        current_epoch: QueryStatsEpoch,
+    },
+    /// The subnet already aggregated the statistics of this epoch.
+    EpochAlreadyAggregated {
+        epoch: QueryStatsEpoch,
+        aggregated_epoch: QueryStatsEpoch,
+    },
+    /// The proposer is not a member of this subnet.
+    InvalidProposer(NodeId),
+    /// The payload claims to be contributed by a node other than the one that
+    /// proposed the block.
+    ProposerMismatch { expected: NodeId, reported: NodeId },
+    /// The proposer already contributed statistics for this epoch.
+    DuplicateContribution {
+        epoch: QueryStatsEpoch,
+        proposer: NodeId,
+    },
+    /// The payload contains the same canister more than once.
+    DuplicateCanisterId(CanisterId),
+}
+
+/// A QueryStatsPayload error from which it may be possible to recover.
+#[derive(Debug)]
+pub enum QueryStatsTransientValidationError {
+    GetStateFailed(Height, StateManagerError),
+}
+
+/// A QueryStatsPayload error that results from payload validation.
+pub type QueryStatsPayloadValidationError =
+    ValidationError<InvalidQueryStatsPayload, QueryStatsTransientValidationError>;
+
+pub trait QueryStatsPayloadBuilder: Send + Sync {
+    /// Produces a `QueryStatsPayload` of maximum byte size `byte_limit`
+    /// containing the query statistics this replica collected during an epoch
+    /// that ended before `height`, unless this replica already contributed
+    /// statistics for that epoch in the state at the certified height or in
+    /// `past_payloads`.
+    fn get_query_stats_payload(
+        &self,
+        height: Height,
+        validation_context: &ValidationContext,
+        past_payloads: &[&QueryStatsPayload],
+        byte_limit: NumBytes,
+    ) -> Option<QueryStatsPayload>;
+
+    /// Checks whether the provided `QueryStatsPayload`, included in a block
+    /// proposed by `proposer`, is valid at `height` given a
+    /// `ValidationContext` and `past_payloads`.
+    ///
+    /// If valid, returns the payload's `CountBytes` size; else returns a
+    /// permanent or transient `ValidationError`.
+    fn validate_query_stats_payload(
+        &self,
+        height: Height,
+        proposer: NodeId,
+        payload: Option<&QueryStatsPayload>,
+        validation_context: &ValidationContext,
+        past_payloads: &[&QueryStatsPayload],
+    ) -> Result<NumBytes, QueryStatsPayloadValidationError>;
+
+    /// Extracts the sequence of past `QueryStatsPayloads` from `past_payloads`.
+    fn filter_past_payloads<'a>(
+        &self,
+        past_payloads: &'a [(Height, Time, Payload)],
+    ) -> Vec<&'a QueryStatsPayload> {
+        past_payloads
+            .iter()
+            .filter_map(|(_, _, payload)| {
+                if payload.is_summary() {
+                    None
+                } else {
+                    payload.as_ref().as_data().batch.query_stats.as_ref()
+                }
+            })
+            .collect()
+    }
+}
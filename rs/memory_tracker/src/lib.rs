@@ -752,13 +752,17 @@ mod checksum {
             access_addr: *const libc::c_void,
             access_kind: AccessKind,
         ) {
+            // Offsets into 64-bit memories may exceed 4GiB, so the checksum
+            // relies on wrapping arithmetic.
             self.index += 1;
-            self.value += self.index
-                * (access_addr as usize - base_addr)
-                * match access_kind {
-                    AccessKind::Read => 1,
-                    AccessKind::Write => 1 << 32,
-                };
+            self.value = self.value.wrapping_add(
+                self.index
+                    .wrapping_mul(access_addr as usize - base_addr)
+                    .wrapping_mul(match access_kind {
+                        AccessKind::Read => 1,
+                        AccessKind::Write => 1 << 32,
+                    }),
+            );
         }
     }
 
@@ -606,6 +606,35 @@ fn prefetch_for_writes_after_reads_unordered() {
     );
 }
 
+#[test]
+fn tracks_pages_beyond_4gib() {
+    // 64-bit Wasm memories can be larger than 4GiB.
+    let memory_pages = (5 << 30) / PAGE_SIZE;
+    let page = PageIndex::new(((4 << 30) / PAGE_SIZE + 10) as u64);
+    with_setup(
+        50,
+        memory_pages,
+        vec![page],
+        DirtyPageTracking::Track,
+        |tracker, _| {
+            sigsegv(&tracker, page, AccessKind::Read);
+            assert_eq!(tracker.num_accessed_pages(), 1);
+            let contents = unsafe {
+                std::slice::from_raw_parts(
+                    (tracker.memory_area.addr as *const u8).add(page.get() as usize * PAGE_SIZE),
+                    PAGE_SIZE,
+                )
+            };
+            assert!(contents.iter().all(|byte| *byte == page.get() as u8));
+            sigsegv(&tracker, page, AccessKind::Write);
+            let dirty_pages = tracker.take_dirty_pages();
+            if new_signal_handler_available() {
+                assert_eq!(dirty_pages, vec![page]);
+            }
+        },
+    );
+}
+
 #[test]
 fn page_bitmap_restrict_to_unaccessed() {
     let mut bitmap = PageBitmap::new(10);
@@ -69,6 +69,7 @@ const PHASE_COMMIT: &str = "commit";
 const METRIC_PROCESS_BATCH_DURATION: &str = "mr_process_batch_duration_seconds";
 const METRIC_PROCESS_BATCH_PHASE_DURATION: &str = "mr_process_batch_phase_duration_seconds";
 const METRIC_TIMED_OUT_REQUESTS_TOTAL: &str = "mr_timed_out_requests_total";
+const METRIC_EXPIRED_CALLBACKS_TOTAL: &str = "mr_expired_callbacks_total";
 
 const CRITICAL_ERROR_MISSING_SUBNET_SIZE: &str = "cycles_account_manager_missing_subnet_size_error";
 const CRITICAL_ERROR_NO_CANISTER_ALLOCATION_RANGE: &str = "mr_empty_canister_allocation_range";
@@ -249,6 +250,8 @@ pub(crate) struct MessageRoutingMetrics {
     critical_error_no_canister_allocation_range: IntCounter,
     /// Number of timed out requests.
     pub timed_out_requests_total: IntCounter,
+    /// Number of expired best-effort callbacks.
+    pub expired_callbacks_total: IntCounter,
 }
 
 impl MessageRoutingMetrics {
@@ -296,6 +299,10 @@ impl MessageRoutingMetrics {
                 METRIC_TIMED_OUT_REQUESTS_TOTAL,
                 "Count of timed out requests.",
             ),
+            expired_callbacks_total: metrics_registry.int_counter(
+                METRIC_EXPIRED_CALLBACKS_TOTAL,
+                "Count of expired best-effort callbacks.",
+            ),
         }
     }
 
@@ -190,6 +190,7 @@ impl StreamBuilderImpl {
                     originator: req.sender,
                     respondent: req.receiver,
                     originator_reply_callback: req.sender_reply_callback,
+                    deadline: req.deadline,
                     refund: req.payment,
                     response_payload: Payload::Reject(
                         RejectContext::new_with_message_length_limit(
@@ -24,7 +24,7 @@ use ic_test_utilities_metrics::{
 use ic_types::{
     messages::{
         CallbackId, Payload, RejectContext, Request, RequestOrResponse, Response,
-        MAX_INTER_CANISTER_PAYLOAD_IN_BYTES_U64,
+        MAX_INTER_CANISTER_PAYLOAD_IN_BYTES_U64, NO_DEADLINE,
     },
     xnet::{StreamIndex, StreamIndexedQueue},
     CanisterId, Cycles, SubnetId, Time,
@@ -113,6 +113,7 @@ fn reject_local_request() {
                     originator: msg.sender,
                     respondent: msg.receiver,
                     originator_reply_callback: msg.sender_reply_callback,
+                    deadline: msg.deadline,
                     refund: msg.payment,
                     response_payload: Payload::Reject(RejectContext {
                         code: RejectCode::SysFatal,
@@ -179,6 +180,7 @@ fn reject_local_request_for_subnet() {
                     originator: msg.sender,
                     respondent: msg.receiver,
                     originator_reply_callback: msg.sender_reply_callback,
+                    deadline: msg.deadline,
                     refund: msg.payment,
                     response_payload: Payload::Reject(RejectContext {
                         code: RejectCode::SysFatal,
@@ -707,6 +709,7 @@ fn build_streams_with_oversized_payloads() {
             sender: local_canister,
             receiver: local_canister,
             sender_reply_callback: CallbackId::from(1),
+            deadline: NO_DEADLINE,
             payment: Cycles::new(1),
             method_name: method_name.clone(),
             method_payload: oversized_request_payload.clone(),
@@ -718,6 +721,7 @@ fn build_streams_with_oversized_payloads() {
             sender: local_canister,
             receiver: remote_canister,
             sender_reply_callback: CallbackId::from(2),
+            deadline: NO_DEADLINE,
             payment: Cycles::new(2),
             method_name,
             method_payload: oversized_request_payload,
@@ -727,6 +731,7 @@ fn build_streams_with_oversized_payloads() {
             originator: local_canister,
             respondent: remote_canister,
             originator_reply_callback: CallbackId::from(2),
+            deadline: NO_DEADLINE,
             refund: Cycles::new(2),
             response_payload: Payload::Reject(RejectContext::new(
                 RejectCode::CanisterError,
@@ -744,6 +749,7 @@ fn build_streams_with_oversized_payloads() {
             originator: local_canister,
             respondent: local_canister,
             originator_reply_callback: CallbackId::from(3),
+            deadline: NO_DEADLINE,
             refund: Cycles::new(3),
             response_payload: Payload::Data(oversized_response_payload),
         };
@@ -752,6 +758,7 @@ fn build_streams_with_oversized_payloads() {
             originator: local_canister,
             respondent: local_canister,
             originator_reply_callback: CallbackId::from(3),
+            deadline: NO_DEADLINE,
             refund: Cycles::new(3),
             response_payload: Payload::Reject(RejectContext::new(
                 RejectCode::CanisterError,
@@ -769,6 +776,7 @@ fn build_streams_with_oversized_payloads() {
             originator: local_canister,
             respondent: local_canister,
             originator_reply_callback: CallbackId::from(4),
+            deadline: NO_DEADLINE,
             refund: Cycles::new(4),
             response_payload: Payload::Reject(RejectContext::new(
                 RejectCode::SysTransient,
@@ -780,6 +788,7 @@ fn build_streams_with_oversized_payloads() {
             originator: local_canister,
             respondent: local_canister,
             originator_reply_callback: CallbackId::from(4),
+            deadline: NO_DEADLINE,
             refund: Cycles::new(4),
             response_payload: Payload::Reject(RejectContext::new(
                 RejectCode::SysTransient,
@@ -574,7 +574,8 @@ impl StreamHandlerImpl {
     ///     * the receiver is not hosted by or being migrated off of this
     ///       subnet; or
     ///     * enqueuing a `Response` failed due to the canister having been
-    ///       removed.
+    ///       removed; or
+    ///     * enqueuing a best-effort `Response` failed for any reason.
     ///
     /// Updates `subnet_available_memory` to reflect any change in memory usage.
     fn induct_message(
@@ -630,8 +631,18 @@ impl StreamHandlerImpl {
                                 let code = reject_code_for_state_error(&err);
                                 stream.push(generate_reject_response(msg, code, err.to_string()))
                             }
+                            RequestOrResponse::Response(response) if response.is_best_effort() => {
+                                // Best-effort responses may be dropped.
+                                debug!(
+                                    self.log,
+                                    "Induction failed with error '{}', dropping best-effort Response {:?}",
+                                    &err,
+                                    response
+                                );
+                            }
                             RequestOrResponse::Response(response) => {
-                                // Critical error, responses should always be inducted successfully.
+                                // Critical error, guaranteed responses should always be inducted
+                                // successfully.
                                 error!(
                                     self.log,
                                     "{}: Inducting response failed: {:?}",
@@ -851,6 +862,7 @@ fn generate_reject_response(
             originator: msg.sender,
             respondent: msg.receiver,
             originator_reply_callback: msg.sender_reply_callback,
+            deadline: msg.deadline,
             refund: msg.payment,
             response_payload: Payload::Reject(RejectContext::new_with_message_length_limit(
                 reject_code,
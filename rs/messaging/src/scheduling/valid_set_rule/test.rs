@@ -806,6 +806,7 @@ fn management_message_update_setting_is_inducted_but_not_charged() {
             freezing_threshold: Some(Nat::from(1 << 20)),
             ..Default::default()
         },
+        sender_canister_version: None,
     }
     .encode();
     let ingress = SignedIngressBuilder::new()
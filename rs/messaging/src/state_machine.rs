@@ -140,8 +140,19 @@ impl StateMachine for StateMachineImpl {
         self.metrics
             .timed_out_requests_total
             .inc_by(timed_out_requests);
+
+        // Expire best-effort callbacks.
+        let expired_callbacks = state.time_out_callbacks(batch.time);
+        self.metrics
+            .expired_callbacks_total
+            .inc_by(expired_callbacks);
         self.observe_phase_duration(PHASE_TIME_OUT_REQUESTS, &phase_timer);
 
+        // Record the query statistics contributed by the block maker.
+        if let Some(query_stats) = batch.payload.query_stats.take() {
+            state.deliver_query_stats(query_stats);
+        }
+
         // Preprocess messages and add messages to the induction pool through the Demux.
         let phase_timer = Timer::start();
         let mut state_with_messages = self.demux.process_payload(state, batch.payload);
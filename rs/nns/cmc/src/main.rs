@@ -1526,6 +1526,7 @@ async fn create_canister(
                     controller: Some(controller_id),
                     ..CanisterSettingsArgs::default()
                 }),
+                sender_canister_version: None,
             },
             dfn_core::api::Funds::new(cycles.get().try_into().unwrap()),
         )
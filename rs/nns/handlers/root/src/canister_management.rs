@@ -126,6 +126,7 @@ async fn try_to_create_and_install_canister(
         compute_allocation: proposal.compute_allocation,
         memory_allocation: proposal.memory_allocation,
         query_allocation: proposal.query_allocation,
+        sender_canister_version: None,
     };
     let install_res: Result<(), (Option<i32>, String)> = call(
         CanisterId::ic_00(),
@@ -66,6 +66,7 @@ impl CanisterApi for CanisterApiImpl {
                     controller: Some(controller_id),
                     ..CanisterSettingsArgs::default()
                 }),
+                sender_canister_version: None,
             },
             dfn_core::api::Funds::new(cycles.get().try_into().unwrap()),
         )
@@ -114,6 +115,7 @@ impl CanisterApi for CanisterApiImpl {
             compute_allocation: None,
             memory_allocation: None,
             query_allocation: None,
+            sender_canister_version: None,
         };
         let install_res: Result<(), (Option<i32>, String)> = dfn_core::call(
             CanisterId::ic_00(),
@@ -143,7 +145,11 @@ impl CanisterApi for CanisterApiImpl {
                 compute_allocation: None,
                 memory_allocation: None,
                 freezing_threshold: None,
+                log_visibility: None,
+                wasm_memory_limit: None,
+                reserved_cycles_limit: None,
             },
+            sender_canister_version: None,
         };
 
         let result: Result<(), (Option<i32>, String)> =
@@ -197,6 +197,7 @@ pub fn set_controllers(
         UpdateSettingsArgs {
             canister_id: target.into(),
             settings: CanisterSettingsArgs::new(None, Some(controllers), None, None, None),
+            sender_canister_version: None,
         },
         sender,
     )
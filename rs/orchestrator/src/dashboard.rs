@@ -5,7 +5,9 @@ use crate::{
 use async_trait::async_trait;
 pub use ic_dashboard::Dashboard;
 use ic_logger::{info, warn, ReplicaLogger};
+use ic_state_layout::gc_report::{GcAction, GcReport, GC_REPORT_FILE};
 use ic_types::{consensus::HasHeight, NodeId, RegistryVersion, ReplicaVersion, SubnetId};
+use std::path::PathBuf;
 use std::process::Command;
 use std::sync::{Arc, Mutex};
 use tokio::sync::RwLock;
@@ -22,6 +24,7 @@ pub(crate) struct OrchestratorDashboard {
     subnet_id: Arc<RwLock<Option<SubnetId>>>,
     replica_version: ReplicaVersion,
     cup_provider: Arc<CatchUpPackageProvider>,
+    state_root: PathBuf,
     logger: ReplicaLogger,
 }
 
@@ -43,6 +46,7 @@ impl Dashboard for OrchestratorDashboard {
              {}\n\
              firewall config registry version: {}\n\
              {}\n\
+             {}\n\
              readonly keys: {}\n\
              backup keys: {}\n\
              admin keys: {}",
@@ -56,6 +60,7 @@ impl Dashboard for OrchestratorDashboard {
             self.get_local_cup_info(),
             *self.last_applied_firewall_version.read().await,
             self.display_last_applied_ssh_parameters().await,
+            self.get_state_layout_gc_info(),
             self.get_authorized_keys("readonly"),
             self.get_authorized_keys("backup"),
             self.get_authorized_keys("admin"),
@@ -77,6 +82,7 @@ impl OrchestratorDashboard {
         subnet_id: Arc<RwLock<Option<SubnetId>>>,
         replica_version: ReplicaVersion,
         cup_provider: Arc<CatchUpPackageProvider>,
+        state_root: PathBuf,
         logger: ReplicaLogger,
     ) -> Self {
         Self {
@@ -88,6 +94,7 @@ impl OrchestratorDashboard {
             subnet_id,
             replica_version,
             cup_provider,
+            state_root,
             logger,
         }
     }
@@ -156,6 +163,40 @@ impl OrchestratorDashboard {
         };
         format!("cup height: {}\ncup signed: {}", height, signed)
     }
+
+    fn get_state_layout_gc_info(&self) -> String {
+        // The state manager stores the report of its latest garbage collection
+        // in the state root.
+        let report = match GcReport::load(&self.state_root.join(GC_REPORT_FILE)) {
+            Ok(report) => report,
+            Err(_) => return "state layout gc: None".to_string(),
+        };
+        let entries: String = report
+            .entries
+            .iter()
+            .map(|e| {
+                format!(
+                    "\n  {} {} ({}, {} bytes, {}s old)",
+                    e.action.as_str(),
+                    e.path.display(),
+                    e.reason.as_str(),
+                    e.size_bytes,
+                    e.age_secs
+                )
+            })
+            .collect();
+        format!(
+            "state layout gc timestamp: {}\n\
+             state layout gc removed bytes: {}\n\
+             state layout gc kept for forensics bytes: {}\n\
+             state layout gc failed bytes: {}{}",
+            report.timestamp_secs,
+            report.total_size_bytes(GcAction::Removed),
+            report.total_size_bytes(GcAction::KeptForForensics),
+            report.total_size_bytes(GcAction::Failed),
+            entries
+        )
+    }
 }
 
 fn try_to_get_authorized_keys(account: &str) -> Result<String, String> {
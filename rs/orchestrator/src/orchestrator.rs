@@ -216,6 +216,7 @@ impl Orchestrator {
             Arc::clone(&subnet_id),
             replica_version,
             cup_provider,
+            config.state_manager.state_root(),
             logger.clone(),
         ));
 
@@ -332,6 +332,7 @@ mod tests {
         let artifact_manager = TestArtifactManager {
             quota: std::usize::MAX,
             num_chunks: 0,
+            ..Default::default()
         };
         let logger = p2p_test_setup_logger();
         let log: ReplicaLogger = logger.root.clone().into();
@@ -369,6 +370,7 @@ mod tests {
         let artifact_manager = TestArtifactManager {
             quota: std::usize::MAX,
             num_chunks: 0,
+            ..Default::default()
         };
         let logger = p2p_test_setup_logger();
         let log: ReplicaLogger = logger.root.clone().into();
@@ -69,8 +69,11 @@ extern crate lru;
 use crate::{
     artifact_download_list::ArtifactDownloadList,
     download_prioritization::{AdvertTracker, AdvertTrackerFinalAction, DownloadAttemptTracker},
-    gossip_protocol::{GossipAdvertAction, GossipAdvertSendRequest, GossipImpl, ReceiveCheckCache},
-    gossip_types::{GossipChunk, GossipChunkRequest, GossipMessage},
+    gossip_protocol::{
+        fetch_gossip_config, GossipAdvertAction, GossipAdvertSendRequest, GossipImpl,
+        ReceiveCheckCache,
+    },
+    gossip_types::{GossipArtifact, GossipChunk, GossipChunkRequest, GossipMessage},
     peer_context::{
         GossipChunkRequestTracker, GossipChunkRequestTrackerKey, PeerContext, PeerContextMap,
     },
@@ -85,12 +88,17 @@ use ic_logger::{error, info, trace, warn};
 use ic_protobuf::{p2p::v1 as pb, proxy::ProtoProxy, registry::node::v1::NodeRecord};
 use ic_registry_client_helpers::subnet::SubnetTransportRegistry;
 use ic_types::{
-    artifact::{Artifact, ArtifactFilter, ArtifactId, ArtifactTag},
-    chunkable::{ArtifactErrorCode, ChunkId},
+    artifact::{
+        Artifact, ArtifactAttribute, ArtifactFilter, ArtifactId, ArtifactTag,
+        CertificationMessageAttribute, Priority,
+    },
+    chunkable::{ArtifactChunkData, ArtifactErrorCode, ChunkId, CHUNKID_UNIT_CHUNK},
+    consensus::ConsensusMessageAttribute,
     crypto::CryptoHash,
     p2p::GossipAdvert,
-    NodeId, RegistryVersion,
+    CountBytes, NodeId, RegistryVersion,
 };
+use serde::Serialize;
 use std::{
     collections::BTreeMap,
     error::Error,
@@ -103,6 +111,10 @@ use std::{
 /// `DownloadManagerImpl` implements the `DownloadManager` trait.
 impl GossipImpl {
     /// The method sends adverts to peers.
+    ///
+    /// If pushing is enabled and the artifact qualifies, the artifact is sent
+    /// together with its advert, saving the peers the round trips of
+    /// requesting it.
     pub fn send_advert_to_peers(&self, advert_request: GossipAdvertSendRequest) {
         let (peers, label) = match advert_request.action {
             GossipAdvertAction::SendToAllPeers => (self.get_current_peer_ids(), "all_peers"),
@@ -111,7 +123,16 @@ impl GossipImpl {
             .adverts_by_action
             .with_label_values(&[label])
             .inc_by(peers.len() as u64);
-        self.send_advert_to_peer_list(advert_request.advert, peers);
+        match self.get_artifact_to_push(&advert_request.advert) {
+            Some(artifact) => self.send_artifact_to_peer_list(
+                GossipArtifact {
+                    advert: advert_request.advert,
+                    artifact,
+                },
+                peers,
+            ),
+            None => self.send_advert_to_peer_list(advert_request.advert, peers),
+        }
     }
 
     /// The method downloads chunks for adverts with the highest priority from
@@ -139,6 +160,115 @@ impl GossipImpl {
         self.metrics.adverts_received.inc();
     }
 
+    /// The method hands an artifact pushed by the given peer over to the
+    /// artifact manager.
+    ///
+    /// Pushed artifacts that are not needed right now, or that exceed the
+    /// quota of the peer, are handled like their adverts, so that they can be
+    /// requested later on. Pushed artifacts whose size differs from their
+    /// advert, or that do not qualify for pushing, are dropped.
+    pub fn on_pushed_artifact(&self, gossip_artifact: GossipArtifact, peer_id: NodeId) {
+        self.metrics.pushed_artifacts_received.inc();
+        let GossipArtifact { advert, artifact } = gossip_artifact;
+        // The precondition ensured by gossip_protocol.on_gossip_artifact() is
+        // that the artifact is not in the artifact pool.
+        // Check if we have seen this artifact before:
+        if self
+            .receive_check_caches
+            .read()
+            .values()
+            .any(|cache| cache.contains(&advert.integrity_hash))
+        {
+            // If yes, the artifact is ignored.
+            return;
+        }
+
+        let expected_ih = integrity_hash(&artifact);
+        if expected_ih != advert.integrity_hash {
+            warn!(
+                self.log,
+                "The integrity hash for pushed {:?} from peer {:?} does not match. Expected {:?}, got {:?}.",
+                advert.artifact_id,
+                peer_id.get(),
+                expected_ih,
+                advert.integrity_hash;
+            );
+            self.metrics.integrity_hash_check_failed.inc();
+            return;
+        }
+
+        // The advertised size drives the download quotas, so it must be that
+        // of the pushed artifact, which in turn must qualify for pushing.
+        let size = artifact_size(&artifact);
+        if size != Some(advert.size) || !self.is_pushable(&advert) {
+            warn!(
+                every_n_seconds => 30,
+                self.log,
+                "Dropping pushed {:?} from peer {:?} of size {:?}, advertised with size {}.",
+                advert.artifact_id,
+                peer_id.get(),
+                size,
+                advert.size
+            );
+            self.metrics.pushed_artifacts_rejected.inc();
+            return;
+        }
+
+        let wanted = matches!(
+            self.prioritizer.peek_priority(&advert),
+            Ok(Priority::Later | Priority::Fetch | Priority::FetchNow)
+        );
+        let within_quota = matches!(
+            self.artifact_manager
+                .get_remaining_quota((&advert.artifact_id).into(), peer_id),
+            Some(quota_size) if quota_size >= advert.size
+        );
+        if !wanted || !within_quota {
+            self.on_advert(advert, peer_id);
+            return;
+        }
+
+        let current_peers = self.current_peers.lock();
+        if !current_peers.contains_key(&peer_id) {
+            warn!(every_n_seconds => 30, self.log, "Dropping pushed artifact from unknown node {:?}", peer_id);
+            return;
+        }
+        match self.receive_check_caches.write().get_mut(&peer_id) {
+            Some(v) => {
+                v.put(advert.integrity_hash.clone(), ());
+            }
+            None => warn!(
+                every_n_seconds => 5,
+                self.log,
+                "Peer {:?} has no receive check cache", peer_id
+            ),
+        }
+
+        // The artifact may have been advertised or be under construction
+        // already. Clean up the adverts for all peers and stop the download:
+        let mut artifacts_under_construction = self.artifacts_under_construction.write();
+        let _ = self.prioritizer.delete_advert(
+            &advert.artifact_id,
+            &advert.integrity_hash,
+            AdvertTrackerFinalAction::Success,
+        );
+        artifacts_under_construction.remove_tracker(&advert.integrity_hash);
+
+        // Drop the locks before calling client callbacks.
+        std::mem::drop(artifacts_under_construction);
+        std::mem::drop(current_peers);
+
+        self.metrics.artifacts_received.inc();
+        trace!(
+            self.log,
+            "Node-{:?} received pushed artifact from Node-{:?} ->{:?}",
+            self.node_id,
+            peer_id,
+            advert.artifact_id
+        );
+        self.deliver_artifact(artifact, advert, peer_id);
+    }
+
     /// The method starts downloading a chunk of the highest-priority
     /// artifact in the request queue if sufficient bandwidth is
     /// available.
@@ -356,20 +486,7 @@ impl GossipImpl {
             }
         };
         // Check if the artifact's integrity hash matches the advertised hash
-        // This construction to compute the integrity hash over all variants of an enum
-        // may be updated in the future.
-        let expected_ih = match &completed_artifact {
-            Artifact::ConsensusMessage(msg) => ic_types::crypto::crypto_hash(msg).get(),
-            Artifact::IngressMessage(msg) => ic_types::crypto::crypto_hash(msg.binary()).get(),
-            Artifact::CertificationMessage(msg) => ic_types::crypto::crypto_hash(msg).get(),
-            Artifact::DkgMessage(msg) => ic_types::crypto::crypto_hash(msg).get(),
-            Artifact::EcdsaMessage(msg) => ic_types::crypto::crypto_hash(msg).get(),
-            Artifact::CanisterHttpMessage(msg) => ic_types::crypto::crypto_hash(msg).get(),
-            // FileTreeSync is not of ArtifactKind kind, and it's used only for testing.
-            // Thus, we make up the integrity_hash.
-            Artifact::FileTreeSync(_msg) => CryptoHash(vec![]),
-            Artifact::StateSync(msg) => ic_types::crypto::crypto_hash(msg).get(),
-        };
+        let expected_ih = integrity_hash(&completed_artifact);
 
         if expected_ih != advert.integrity_hash {
             warn!(
@@ -427,24 +544,7 @@ impl GossipImpl {
             peer_id,
             gossip_chunk.artifact_id
         );
-        match self
-            .artifact_manager
-            .on_artifact(completed_artifact, advert, &peer_id)
-        {
-            Ok(_) => (),
-            // If this Replica is running an unexpected version, it will log
-            // an unhelpfully large volume of `ArtifactReplicaVersionError`s.
-            // Here we set the log rate at a more appropriate level.
-            Err(ArtifactPoolError(ArtifactReplicaVersionError(err))) => warn!(
-                every_n_seconds => 5,
-                self.log,
-                "Artifact is not processed successfully by Artifact Manager: {:?}", err
-            ),
-            Err(err) => warn!(
-                self.log,
-                "Artifact is not processed successfully by Artifact Manager: {:?}", err
-            ),
-        }
+        self.deliver_artifact(completed_artifact, advert, peer_id);
     }
 
     /// The method reacts to a disconnect event event for the peer with the
@@ -530,7 +630,7 @@ impl GossipImpl {
                     .last_retransmission_request_processed_time
                     .elapsed()
                     .as_millis();
-                if elapsed_ms < self.gossip_config.retransmission_request_ms as u128 {
+                if elapsed_ms < self.gossip_config.read().retransmission_request_ms as u128 {
                     BUSY_ERR
                 } else {
                     peer_context.last_retransmission_request_processed_time = Instant::now();
@@ -686,7 +786,7 @@ impl GossipImpl {
         {
             let mut pfn_invocation_instant = self.pfn_invocation_instant.lock();
             if pfn_invocation_instant.elapsed().as_millis()
-                >= self.gossip_config.pfn_evaluation_period_ms as u128
+                >= self.gossip_config.read().pfn_evaluation_period_ms as u128
             {
                 update_priority_fns = true;
                 *pfn_invocation_instant = Instant::now();
@@ -697,7 +797,7 @@ impl GossipImpl {
         {
             let mut retransmission_request_instant = self.retransmission_request_instant.lock();
             if retransmission_request_instant.elapsed().as_millis()
-                >= self.gossip_config.retransmission_request_ms as u128
+                >= self.gossip_config.read().retransmission_request_ms as u128
             {
                 retransmission_request = true;
                 *retransmission_request_instant = Instant::now();
@@ -708,7 +808,7 @@ impl GossipImpl {
         {
             let mut registry_refresh_instant = self.registry_refresh_instant.lock();
             if registry_refresh_instant.elapsed().as_millis()
-                >= self.gossip_config.pfn_evaluation_period_ms as u128
+                >= self.gossip_config.read().pfn_evaluation_period_ms as u128
             {
                 refresh_registry = true;
                 *registry_refresh_instant = Instant::now();
@@ -727,6 +827,8 @@ impl GossipImpl {
         self.metrics
             .registry_version_used
             .set(latest_registry_version.get() as i64);
+        *self.gossip_config.write() =
+            fetch_gossip_config(self.registry_client.clone(), self.subnet_id);
 
         let subnet_nodes = self.merge_subnet_membership(latest_registry_version);
         let self_not_in_subnet = !subnet_nodes.contains_key(&self.node_id);
@@ -764,7 +866,7 @@ impl GossipImpl {
                         self.receive_check_caches.write().insert(
                             *node_id,
                             ReceiveCheckCache::new(
-                                self.gossip_config.receive_check_cache_size as usize,
+                                self.gossip_config.read().receive_check_cache_size as usize,
                             ),
                         );
                     }
@@ -878,6 +980,83 @@ impl GossipImpl {
         }
     }
 
+    /// The method sends the given pushed artifact to the given list of peers.
+    fn send_artifact_to_peer_list(&self, gossip_artifact: GossipArtifact, peer_ids: Vec<NodeId>) {
+        let artifact_id = gossip_artifact.advert.artifact_id.clone();
+        let message = GossipMessage::Artifact(gossip_artifact);
+        let transport_channel = self.transport_channel_mapper.map(&message);
+        for peer_id in peer_ids {
+            self.transport_send(message.clone(), peer_id, transport_channel)
+                .map(|_| {
+                    self.metrics.adverts_sent.inc();
+                    self.metrics.artifacts_pushed.inc();
+                })
+                .unwrap_or_else(|_e| {
+                    // Ignore push failures, like advert send failures
+                    self.metrics.adverts_send_failed.inc();
+                });
+            trace!(
+                self.log,
+                "Node-{:?} pushed artifact ->{:?} {:?}",
+                self.node_id,
+                peer_id,
+                artifact_id
+            );
+        }
+    }
+
+    /// The method returns the artifact of the given advert if it is to be
+    /// pushed together with the advert.
+    ///
+    /// Pushing is enabled by a non-zero `artifact_push_threshold_bytes` in
+    /// the gossip config. Then artifacts up to that size and all consensus and
+    /// certification shares are pushed. Only single-chunked artifacts can be
+    /// pushed, all others (e.g., state sync) are always downloaded in chunks.
+    fn get_artifact_to_push(&self, advert: &GossipAdvert) -> Option<Artifact> {
+        if !self.is_pushable(advert) {
+            return None;
+        }
+        let chunk = self
+            .artifact_manager
+            .get_validated_by_identifier(&advert.artifact_id)?
+            .get_chunk(ChunkId::from(CHUNKID_UNIT_CHUNK))?;
+        match chunk.artifact_chunk_data {
+            ArtifactChunkData::UnitChunkData(artifact) => Some(artifact),
+            ArtifactChunkData::SemiStructuredChunkData(_) => None,
+        }
+    }
+
+    /// The method returns true if pushing is enabled and the artifact of the
+    /// given advert may be pushed, i.e., it is a share or its size does not
+    /// exceed the push threshold.
+    fn is_pushable(&self, advert: &GossipAdvert) -> bool {
+        let push_threshold = self.gossip_config.read().artifact_push_threshold_bytes as usize;
+        push_threshold != 0 && (advert.size <= push_threshold || is_share(&advert.attribute))
+    }
+
+    /// The method hands over the given completed artifact to the artifact
+    /// manager.
+    fn deliver_artifact(&self, artifact: Artifact, advert: GossipAdvert, peer_id: NodeId) {
+        match self
+            .artifact_manager
+            .on_artifact(artifact, advert, &peer_id)
+        {
+            Ok(_) => (),
+            // If this Replica is running an unexpected version, it will log
+            // an unhelpfully large volume of `ArtifactReplicaVersionError`s.
+            // Here we set the log rate at a more appropriate level.
+            Err(ArtifactPoolError(ArtifactReplicaVersionError(err))) => warn!(
+                every_n_seconds => 5,
+                self.log,
+                "Artifact is not processed successfully by Artifact Manager: {:?}", err
+            ),
+            Err(err) => warn!(
+                self.log,
+                "Artifact is not processed successfully by Artifact Manager: {:?}", err
+            ),
+        }
+    }
+
     /// The method sends the given chunk requests to the given peer.
     fn send_chunk_requests(&self, requests: Vec<GossipChunkRequest>, peer_id: NodeId) {
         for request in requests {
@@ -918,7 +1097,7 @@ impl GossipImpl {
             // there is available capacity to stream chunks from this peer.
             Some(peer_context)
                 if peer_context.requested.len()
-                    < self.gossip_config.max_artifact_streams_per_peer as usize =>
+                    < self.gossip_config.read().max_artifact_streams_per_peer as usize =>
             {
                 Ok(peer_context)
             }
@@ -981,7 +1160,7 @@ impl GossipImpl {
             })
             .count();
 
-        if duplicity >= self.gossip_config.max_duplicity as usize {
+        if duplicity >= self.gossip_config.read().max_duplicity as usize {
             None?
         }
 
@@ -1004,7 +1183,8 @@ impl GossipImpl {
         let mut current_peers = self.current_peers.lock();
         let peer_context = self.is_peer_ready_for_download(peer_id, &current_peers)?;
         let requested_instant = Instant::now(); // function granularity for instant is good enough
-        let max_streams_per_peer = self.gossip_config.max_artifact_streams_per_peer as usize;
+        let gossip_config = self.gossip_config.read().clone();
+        let max_streams_per_peer = gossip_config.max_artifact_streams_per_peer as usize;
 
         assert!(peer_context.requested.len() <= max_streams_per_peer);
         let num_downloadable_chunks = max_streams_per_peer - peer_context.requested.len();
@@ -1034,7 +1214,7 @@ impl GossipImpl {
             if let Some(artifact_tracker) = artifacts_under_construction.schedule_download(
                 peer_id,
                 &advert_tracker.advert,
-                &self.gossip_config,
+                &gossip_config,
                 current_peers.len() as u32,
                 self.artifact_manager.as_ref(),
             ) {
@@ -1144,7 +1324,7 @@ impl GossipImpl {
         let mut peer_timed_out: bool = false;
         peer_context.requested.retain(|key, tracker| {
             let timed_out = tracker.requested_instant.elapsed().as_millis()
-                >= self.gossip_config.max_chunk_wait_ms as u128;
+                >= self.gossip_config.read().max_chunk_wait_ms as u128;
             if timed_out {
                 self.metrics.chunks_timed_out.inc();
                 timed_out_chunks.push((
@@ -1205,6 +1385,59 @@ impl GossipImpl {
     }
 }
 
+/// Returns the integrity hash of the given artifact.
+///
+/// This construction to compute the integrity hash over all variants of an enum
+/// may be updated in the future.
+fn integrity_hash(artifact: &Artifact) -> CryptoHash {
+    match artifact {
+        Artifact::ConsensusMessage(msg) => ic_types::crypto::crypto_hash(msg).get(),
+        Artifact::IngressMessage(msg) => ic_types::crypto::crypto_hash(msg.binary()).get(),
+        Artifact::CertificationMessage(msg) => ic_types::crypto::crypto_hash(msg).get(),
+        Artifact::DkgMessage(msg) => ic_types::crypto::crypto_hash(msg).get(),
+        Artifact::EcdsaMessage(msg) => ic_types::crypto::crypto_hash(msg).get(),
+        Artifact::CanisterHttpMessage(msg) => ic_types::crypto::crypto_hash(msg).get(),
+        // FileTreeSync is not of ArtifactKind kind, and it's used only for testing.
+        // Thus, we make up the integrity_hash.
+        Artifact::FileTreeSync(_msg) => CryptoHash(vec![]),
+        Artifact::StateSync(msg) => ic_types::crypto::crypto_hash(msg).get(),
+    }
+}
+
+/// Returns the size of the given artifact as computed for its advert, or
+/// `None` for artifacts that are downloaded in chunks and never pushed.
+fn artifact_size(artifact: &Artifact) -> Option<usize> {
+    fn serialized_size<T: Serialize>(msg: &T) -> Option<usize> {
+        bincode::serialized_size(msg).ok().map(|size| size as usize)
+    }
+    match artifact {
+        Artifact::ConsensusMessage(msg) => serialized_size(msg),
+        Artifact::IngressMessage(msg) => Some(msg.count_bytes()),
+        Artifact::CertificationMessage(msg) => serialized_size(msg),
+        Artifact::DkgMessage(msg) => serialized_size(msg),
+        Artifact::EcdsaMessage(msg) => serialized_size(msg),
+        Artifact::CanisterHttpMessage(msg) => serialized_size(msg),
+        Artifact::FileTreeSync(_) | Artifact::StateSync(_) => None,
+    }
+}
+
+/// Returns true if the given attribute belongs to a consensus or
+/// certification share.
+fn is_share(attribute: &ArtifactAttribute) -> bool {
+    matches!(
+        attribute,
+        ArtifactAttribute::ConsensusMessage(
+            ConsensusMessageAttribute::RandomBeaconShare(_)
+                | ConsensusMessageAttribute::NotarizationShare(_)
+                | ConsensusMessageAttribute::FinalizationShare(_)
+                | ConsensusMessageAttribute::RandomTapeShare(_)
+                | ConsensusMessageAttribute::CatchUpPackageShare(_)
+        ) | ArtifactAttribute::CertificationMessage(
+            CertificationMessageAttribute::CertificationShare(_)
+        )
+    )
+}
+
 fn get_peer_addr(node_record: &NodeRecord) -> Result<SocketAddr, String> {
     let socket_addr: (IpAddr, u16) = node_record
         .p2p_flow_endpoints
@@ -1274,6 +1507,10 @@ pub mod tests {
         pub quota: usize,
         /// The number of chunks.
         pub num_chunks: u32,
+        /// The priority of all artifacts, Priority::FetchNow if not set.
+        pub priority: Option<Priority>,
+        /// The validated artifact returned for every artifact ID, if any.
+        pub validated_artifact: Option<DkgMessage>,
     }
 
     /// The test artifact.
@@ -1331,13 +1568,14 @@ pub mod tests {
             unimplemented!()
         }
 
-        /// The method to return a validated artifact is not implemented as
-        /// it is not used.
+        /// The method returns the validated artifact, if any.
         fn get_validated_by_identifier(
             &self,
             _message_id: &artifact::ArtifactId,
         ) -> Option<Box<dyn ChunkableArtifact + '_>> {
-            unimplemented!()
+            self.validated_artifact
+                .clone()
+                .map(|msg| Box::new(msg) as Box<dyn ChunkableArtifact>)
         }
 
         /// The method to get the artifact filter is not implemented as
@@ -1363,10 +1601,15 @@ pub mod tests {
             Some(self.quota)
         }
 
-        /// The method returns the priority function that always uses
-        /// Priority::FetchAll.
+        /// The method returns the priority function that always uses the
+        /// configured priority, or Priority::FetchNow if none is set.
         fn get_priority_function(&self, _: artifact::ArtifactTag) -> Option<ArtifactPriorityFn> {
-            Some(Box::new(priority_fn_fetch_now_all))
+            match self.priority {
+                Some(priority) => Some(Box::new(move |_: &ArtifactId, _: &ArtifactAttribute| {
+                    priority
+                })),
+                None => Some(Box::new(priority_fn_fetch_now_all)),
+            }
         }
 
         /// The method returns a new TestArtifact instance.
@@ -1405,6 +1648,7 @@ pub mod tests {
         let artifact_manager = TestArtifactManager {
             quota: 2 * 1024 * 1024 * 1024,
             num_chunks: 1,
+            ..Default::default()
         };
 
         // Set up transport.
@@ -1424,7 +1668,11 @@ pub mod tests {
         // Set up the prioritizer.
         let metrics_registry = MetricsRegistry::new();
 
-        let transport_channels = vec![TransportChannelId::from(0)];
+        let transport_channels = vec![
+            TransportChannelId::from(0),
+            TransportChannelId::from(1),
+            TransportChannelId::from(2),
+        ];
 
         // Create fake peers.
         let artifact_manager = Arc::new(artifact_manager);
@@ -1487,8 +1735,9 @@ pub mod tests {
     /// The functions tests that the peer context drops all requests after a
     /// time-out.
     fn test_timeout_peer(gossip: &GossipImpl, node_id: &NodeId) {
-        let sleep_duration =
-            std::time::Duration::from_millis((gossip.gossip_config.max_chunk_wait_ms * 2) as u64);
+        let sleep_duration = std::time::Duration::from_millis(
+            (gossip.gossip_config.read().max_chunk_wait_ms * 2) as u64,
+        );
         std::thread::sleep(sleep_duration);
         let mut current_peers = gossip.current_peers.lock();
         let peer_context = current_peers.get_mut(node_id).unwrap();
@@ -1625,7 +1874,7 @@ pub mod tests {
             .unwrap();
         assert_eq!(
             chunks_to_be_downloaded.len(),
-            gossip.gossip_config.max_artifact_streams_per_peer as usize
+            gossip.gossip_config.read().max_artifact_streams_per_peer as usize
         );
         for (i, chunk_req) in chunks_to_be_downloaded.iter().enumerate() {
             assert_eq!(
@@ -1649,8 +1898,8 @@ pub mod tests {
         // The total number of replicas is 4 in this test.
         let num_replicas = 4;
         let logger = p2p_test_setup_logger();
-        let mut gossip = new_test_gossip(num_replicas, &logger, tokio::runtime::Handle::current());
-        gossip.gossip_config.max_chunk_wait_ms = 1000;
+        let gossip = new_test_gossip(num_replicas, &logger, tokio::runtime::Handle::current());
+        gossip.gossip_config.write().max_chunk_wait_ms = 1000;
 
         let test_assert_compute_work_len =
             |gossip: &GossipImpl, node_id, compute_work_count: usize| {
@@ -1664,7 +1913,7 @@ pub mod tests {
                     assert_eq!(chunk_req.chunk_id, ChunkId::from(0));
                 }
             };
-        let request_queue_size = gossip.gossip_config.max_artifact_streams_per_peer as usize;
+        let request_queue_size = gossip.gossip_config.read().max_artifact_streams_per_peer as usize;
 
         // Skip the first peer at index 0 as it is the requesting node.
         for peer_id in 1..num_replicas {
@@ -1716,9 +1965,9 @@ pub mod tests {
         // There are 3 nodes in total, Node 1 and 2 are actively used in the test.
         let num_replicas = 3;
         let logger = p2p_test_setup_logger();
-        let mut gossip = new_test_gossip(num_replicas, &logger, tokio::runtime::Handle::current());
-        gossip.gossip_config.max_artifact_streams_per_peer = 1;
-        gossip.gossip_config.max_chunk_wait_ms = 1000;
+        let gossip = new_test_gossip(num_replicas, &logger, tokio::runtime::Handle::current());
+        gossip.gossip_config.write().max_artifact_streams_per_peer = 1;
+        gossip.gossip_config.write().max_chunk_wait_ms = 1000;
         let advert_range = 1..num_replicas as u32;
         // Node 1 and 2 both advertise advert 1 and 2.
         for i in 1..num_replicas {
@@ -1732,13 +1981,14 @@ pub mod tests {
                 .unwrap();
             assert_eq!(
                 chunks_to_be_downloaded.len(),
-                gossip.gossip_config.max_artifact_streams_per_peer as usize
+                gossip.gossip_config.read().max_artifact_streams_per_peer as usize
             );
         }
 
         // Time out the artifact as well as the chunks.
-        let sleep_duration =
-            std::time::Duration::from_millis((gossip.gossip_config.max_chunk_wait_ms * 2) as u64);
+        let sleep_duration = std::time::Duration::from_millis(
+            (gossip.gossip_config.read().max_chunk_wait_ms * 2) as u64,
+        );
         std::thread::sleep(sleep_duration);
 
         // Node 1 and 2 now both have moved forward and advertise advert 3 and
@@ -1771,7 +2021,7 @@ pub mod tests {
                 .unwrap();
             assert_eq!(
                 chunks_to_be_downloaded.len(),
-                gossip.gossip_config.max_artifact_streams_per_peer as usize
+                gossip.gossip_config.read().max_artifact_streams_per_peer as usize
             );
         }
 
@@ -1800,10 +2050,11 @@ pub mod tests {
         let num_peers = 3;
         let logger = p2p_test_setup_logger();
         let mut gossip = new_test_gossip(num_peers, &logger, tokio::runtime::Handle::current());
-        let request_queue_size = gossip.gossip_config.max_artifact_streams_per_peer;
+        let request_queue_size = gossip.gossip_config.read().max_artifact_streams_per_peer;
         gossip.artifact_manager = Arc::new(TestArtifactManager {
             quota: 2 * 1024 * 1024 * 1024,
             num_chunks: request_queue_size * num_peers,
+            ..Default::default()
         });
 
         // Each peer should download the node_id'th range of chunks, i.e.,
@@ -1881,6 +2132,9 @@ pub mod tests {
         }
     }
 
+    /// Push threshold enabling the push of the test artifacts.
+    const TEST_PUSH_THRESHOLD_BYTES: u32 = 1024 * 1024;
+
     /// The function returns the given number of adverts.
     fn receive_check_test_create_adverts(range: Range<u32>) -> Vec<GossipAdvert> {
         let mut result = vec![];
@@ -1893,7 +2147,7 @@ pub mod tests {
             let gossip_advert = GossipAdvert {
                 artifact_id: ArtifactId::DkgMessage(artifact_id),
                 attribute: ArtifactAttribute::DkgMessage(attribute),
-                size: 0,
+                size: bincode::serialized_size(&msg).unwrap() as usize,
                 integrity_hash: ic_types::crypto::crypto_hash(&msg).get(),
             };
             result.push(gossip_advert);
@@ -1916,7 +2170,7 @@ pub mod tests {
         let logger = p2p_test_setup_logger();
         let gossip = new_test_gossip(2, &logger, tokio::runtime::Handle::current());
         let node_id = node_test_id(1);
-        let max_adverts = gossip.gossip_config.max_artifact_streams_per_peer;
+        let max_adverts = gossip.gossip_config.read().max_artifact_streams_per_peer;
         let mut adverts = receive_check_test_create_adverts(0..max_adverts);
         let msg = receive_check_test_create_message(0);
         let artifact_id = ArtifactId::DkgMessage(CryptoHashOf::from(
@@ -2016,6 +2270,345 @@ pub mod tests {
         );
     }
 
+    /// This test verifies that pushed artifacts are handed over without being
+    /// downloaded and that pushed artifacts with incorrect integrity hashes
+    /// are dropped.
+    #[tokio::test]
+    async fn pushed_artifact_test() {
+        // Initialize the logger and download manager for the test.
+        let logger = p2p_test_setup_logger();
+        let gossip = new_test_gossip(2, &logger, tokio::runtime::Handle::current());
+        gossip.gossip_config.write().artifact_push_threshold_bytes = TEST_PUSH_THRESHOLD_BYTES;
+        let node_id = node_test_id(1);
+        let adverts = receive_check_test_create_adverts(0..2);
+
+        // Push the first artifact with the correct integrity hash, the second
+        // one with the artifact of the first one.
+        for gossip_advert in &adverts {
+            let artifact = Artifact::DkgMessage(receive_check_test_create_message(0));
+            gossip.on_pushed_artifact(
+                GossipArtifact {
+                    advert: gossip_advert.clone(),
+                    artifact,
+                },
+                node_id,
+            );
+        }
+
+        {
+            let receive_check_caches = gossip.receive_check_caches.read();
+            let cache = &receive_check_caches.get(&node_id).unwrap();
+            assert!(cache.contains(&adverts[0].integrity_hash));
+            assert!(!cache.contains(&adverts[1].integrity_hash));
+        }
+        assert_eq!(gossip.metrics.pushed_artifacts_received.get(), 2);
+        assert_eq!(gossip.metrics.artifacts_received.get(), 1);
+        assert_eq!(gossip.metrics.integrity_hash_check_failed.get(), 1);
+
+        // Nothing is left to download, and the advert of the pushed artifact
+        // is ignored.
+        gossip.on_advert(adverts[0].clone(), node_id);
+        let chunks_to_be_downloaded = gossip.download_next_compute_work(node_id).unwrap();
+        assert!(chunks_to_be_downloaded.is_empty());
+    }
+
+    /// The function replaces the artifact manager of the given download
+    /// manager and updates the priority functions accordingly.
+    fn set_test_artifact_manager(gossip: &mut GossipImpl, artifact_manager: TestArtifactManager) {
+        gossip.artifact_manager = Arc::new(artifact_manager);
+        let _ = gossip
+            .prioritizer
+            .update_priority_functions(gossip.artifact_manager.as_ref());
+    }
+
+    /// This test verifies that only validated single-chunked artifacts up to
+    /// the push threshold are pushed, and only if pushing is enabled.
+    #[tokio::test]
+    async fn get_artifact_to_push_test() {
+        let logger = p2p_test_setup_logger();
+        let mut gossip = new_test_gossip(2, &logger, tokio::runtime::Handle::current());
+        let msg = receive_check_test_create_message(0);
+        let mut advert = receive_check_test_create_adverts(0..1).pop().unwrap();
+        advert.size = 100;
+
+        // Nothing is pushed if the artifact is not validated.
+        gossip.gossip_config.write().artifact_push_threshold_bytes = 1024;
+        assert_eq!(gossip.get_artifact_to_push(&advert), None);
+
+        set_test_artifact_manager(
+            &mut gossip,
+            TestArtifactManager {
+                quota: 2 * 1024 * 1024 * 1024,
+                num_chunks: 1,
+                validated_artifact: Some(msg.clone()),
+                ..Default::default()
+            },
+        );
+        assert_eq!(
+            gossip.get_artifact_to_push(&advert),
+            Some(Artifact::DkgMessage(msg))
+        );
+
+        // Artifacts exceeding the threshold are not pushed.
+        gossip.gossip_config.write().artifact_push_threshold_bytes = 99;
+        assert_eq!(gossip.get_artifact_to_push(&advert), None);
+
+        // Nothing is pushed if pushing is disabled.
+        gossip.gossip_config.write().artifact_push_threshold_bytes = 0;
+        advert.size = 0;
+        assert_eq!(gossip.get_artifact_to_push(&advert), None);
+    }
+
+    /// This test verifies that a pushed artifact with an incorrect integrity
+    /// hash leaves the advert of the artifact tracked, so that it can still
+    /// be downloaded.
+    #[tokio::test]
+    async fn pushed_artifact_incorrect_integrity_hash_test() {
+        let logger = p2p_test_setup_logger();
+        let gossip = new_test_gossip(2, &logger, tokio::runtime::Handle::current());
+        let node_id = node_test_id(1);
+        let advert = receive_check_test_create_adverts(1..2).pop().unwrap();
+
+        gossip.on_advert(advert.clone(), node_id);
+        gossip.on_pushed_artifact(
+            GossipArtifact {
+                advert: advert.clone(),
+                artifact: Artifact::DkgMessage(receive_check_test_create_message(0)),
+            },
+            node_id,
+        );
+
+        assert_eq!(gossip.metrics.integrity_hash_check_failed.get(), 1);
+        assert_eq!(gossip.metrics.artifacts_received.get(), 0);
+        assert_eq!(
+            gossip.prioritizer.get_advert_from_peer(
+                &advert.artifact_id,
+                &advert.integrity_hash,
+                &node_id
+            ),
+            Ok(Some(advert))
+        );
+        let chunks_to_be_downloaded = gossip.download_next_compute_work(node_id).unwrap();
+        assert_eq!(chunks_to_be_downloaded.len(), 1);
+    }
+
+    /// This test verifies that pushed artifacts that are not wanted right
+    /// now, or that exceed the quota of the peer, are handled like adverts.
+    #[tokio::test]
+    async fn pushed_artifact_falls_back_to_advert_test() {
+        let logger = p2p_test_setup_logger();
+        let node_id = node_test_id(1);
+        let test_artifact_managers = vec![
+            // The artifact is not wanted right now.
+            TestArtifactManager {
+                quota: 2 * 1024 * 1024 * 1024,
+                num_chunks: 1,
+                priority: Some(Priority::Stash),
+                ..Default::default()
+            },
+            // The artifact exceeds the quota.
+            TestArtifactManager {
+                quota: 0,
+                num_chunks: 1,
+                ..Default::default()
+            },
+        ];
+        for artifact_manager in test_artifact_managers {
+            let mut gossip = new_test_gossip(2, &logger, tokio::runtime::Handle::current());
+            gossip.gossip_config.write().artifact_push_threshold_bytes = TEST_PUSH_THRESHOLD_BYTES;
+            set_test_artifact_manager(&mut gossip, artifact_manager);
+            let advert = receive_check_test_create_adverts(0..1).pop().unwrap();
+
+            gossip.on_pushed_artifact(
+                GossipArtifact {
+                    advert: advert.clone(),
+                    artifact: Artifact::DkgMessage(receive_check_test_create_message(0)),
+                },
+                node_id,
+            );
+
+            {
+                let receive_check_caches = gossip.receive_check_caches.read();
+                let cache = &receive_check_caches.get(&node_id).unwrap();
+                assert!(!cache.contains(&advert.integrity_hash));
+            }
+            assert_eq!(gossip.metrics.artifacts_received.get(), 0);
+            assert_eq!(gossip.metrics.adverts_received.get(), 1);
+            assert_eq!(
+                gossip.prioritizer.get_advert_from_peer(
+                    &advert.artifact_id,
+                    &advert.integrity_hash,
+                    &node_id
+                ),
+                Ok(Some(advert))
+            );
+        }
+    }
+
+    /// This test verifies that pushed artifacts are dropped if their size
+    /// does not match their advert or exceeds the push threshold, or if
+    /// pushing is disabled.
+    #[tokio::test]
+    async fn pushed_artifact_rejected_by_size_test() {
+        let logger = p2p_test_setup_logger();
+        let gossip = new_test_gossip(2, &logger, tokio::runtime::Handle::current());
+        let node_id = node_test_id(1);
+        let advert = receive_check_test_create_adverts(0..1).pop().unwrap();
+        let push = |advert: GossipAdvert| {
+            gossip.on_pushed_artifact(
+                GossipArtifact {
+                    advert,
+                    artifact: Artifact::DkgMessage(receive_check_test_create_message(0)),
+                },
+                node_id,
+            )
+        };
+
+        // Pushing is disabled.
+        push(advert.clone());
+        assert_eq!(gossip.metrics.pushed_artifacts_rejected.get(), 1);
+
+        // The artifact is larger than advertised.
+        gossip.gossip_config.write().artifact_push_threshold_bytes = TEST_PUSH_THRESHOLD_BYTES;
+        let mut understated_advert = advert.clone();
+        understated_advert.size = 1;
+        push(understated_advert);
+        assert_eq!(gossip.metrics.pushed_artifacts_rejected.get(), 2);
+
+        // The artifact exceeds the push threshold.
+        gossip.gossip_config.write().artifact_push_threshold_bytes = advert.size as u32 - 1;
+        push(advert.clone());
+        assert_eq!(gossip.metrics.pushed_artifacts_rejected.get(), 3);
+
+        assert_eq!(gossip.metrics.pushed_artifacts_received.get(), 3);
+        assert_eq!(gossip.metrics.artifacts_received.get(), 0);
+        assert!(!gossip
+            .receive_check_caches
+            .read()
+            .get(&node_id)
+            .unwrap()
+            .contains(&advert.integrity_hash));
+
+        // The artifact is accepted once it is within the push threshold.
+        gossip.gossip_config.write().artifact_push_threshold_bytes = advert.size as u32;
+        push(advert);
+        assert_eq!(gossip.metrics.pushed_artifacts_rejected.get(), 3);
+        assert_eq!(gossip.metrics.artifacts_received.get(), 1);
+    }
+
+    /// This test verifies that a pushed artifact removes the adverts of the
+    /// artifact received from all peers and stops its ongoing download.
+    #[tokio::test]
+    async fn pushed_artifact_cleans_up_adverts_test() {
+        let logger = p2p_test_setup_logger();
+        let gossip = new_test_gossip(3, &logger, tokio::runtime::Handle::current());
+        gossip.gossip_config.write().artifact_push_threshold_bytes = TEST_PUSH_THRESHOLD_BYTES;
+        let advert = receive_check_test_create_adverts(0..1).pop().unwrap();
+
+        // Both peers advertise the artifact, and the download from the
+        // first peer is started.
+        for peer_id in 1..3 {
+            gossip.on_advert(advert.clone(), node_test_id(peer_id));
+        }
+        let chunks_to_be_downloaded = gossip.download_next_compute_work(node_test_id(1)).unwrap();
+        assert_eq!(chunks_to_be_downloaded.len(), 1);
+        assert!(gossip
+            .artifacts_under_construction
+            .write()
+            .get_tracker(&advert.integrity_hash)
+            .is_some());
+
+        // The second peer pushes the artifact.
+        gossip.on_pushed_artifact(
+            GossipArtifact {
+                advert: advert.clone(),
+                artifact: Artifact::DkgMessage(receive_check_test_create_message(0)),
+            },
+            node_test_id(2),
+        );
+
+        assert_eq!(gossip.metrics.artifacts_received.get(), 1);
+        assert!(gossip
+            .artifacts_under_construction
+            .write()
+            .get_tracker(&advert.integrity_hash)
+            .is_none());
+        for peer_id in 1..3 {
+            assert_eq!(
+                gossip.prioritizer.get_advert_from_peer(
+                    &advert.artifact_id,
+                    &advert.integrity_hash,
+                    &node_test_id(peer_id)
+                ),
+                Err(DownloadPrioritizerError::NotFound)
+            );
+            let chunks_to_be_downloaded = gossip
+                .download_next_compute_work(node_test_id(peer_id))
+                .unwrap();
+            assert!(chunks_to_be_downloaded.is_empty());
+        }
+    }
+
+    /// This test verifies that the gossip configuration, in particular the
+    /// push threshold, is updated when the registry is refreshed.
+    #[tokio::test]
+    async fn refresh_registry_updates_gossip_config_test() {
+        let logger = p2p_test_setup_logger();
+        let num_replicas = 2;
+
+        let allocated_ports = allocate_ports("127.0.0.1", num_replicas as u16)
+            .expect("Port allocation for test failed");
+        let node_port_allocation: Vec<u16> = allocated_ports.iter().map(|np| np.port).collect();
+        let data_provider = test_group_set_registry(
+            subnet_test_id(P2P_SUBNET_ID_DEFAULT),
+            Arc::new(node_port_allocation),
+        );
+        let registry_client = Arc::new(FakeRegistryClient::new(data_provider.clone()));
+        registry_client.update_to_latest_version();
+
+        let mut mock_consensus_cache = MockConsensusCache::new();
+        mock_consensus_cache
+            .expect_get_oldest_registry_version_in_use()
+            .returning(move || RegistryVersion::from(1));
+        let consensus_pool_cache = Arc::new(mock_consensus_cache);
+
+        let gossip = new_test_gossip_impl_with_registry(
+            num_replicas,
+            &logger,
+            Arc::clone(&registry_client) as Arc<_>,
+            Arc::clone(&consensus_pool_cache) as Arc<_>,
+            tokio::runtime::Handle::current(),
+        );
+        assert_eq!(
+            gossip.gossip_config.read().artifact_push_threshold_bytes,
+            ic_types::p2p::ARTIFACT_PUSH_THRESHOLD_BYTES
+        );
+
+        // Enable pushing in the subnet record at version 2.
+        let mut gossip_config = ic_types::p2p::build_default_gossip_config();
+        gossip_config.artifact_push_threshold_bytes = 1024;
+        let node_ids: Vec<NodeId> = (0..num_replicas as u64).map(node_test_id).collect();
+        let mut subnet_record = SubnetRecordBuilder::from(&node_ids).build();
+        subnet_record.gossip_config = Some(gossip_config);
+        add_subnet_record(
+            &data_provider,
+            2,
+            subnet_test_id(P2P_SUBNET_ID_DEFAULT),
+            subnet_record,
+        );
+        registry_client.update_to_latest_version();
+
+        gossip.refresh_registry();
+        assert_eq!(
+            gossip.gossip_config.read().artifact_push_threshold_bytes,
+            1024
+        );
+        assert_eq!(
+            gossip.get_current_peer_ids().len(),
+            num_replicas as usize - 1
+        );
+    }
+
     #[test]
     fn test_get_peer_addr() {
         {
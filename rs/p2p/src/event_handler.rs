@@ -25,7 +25,8 @@
 //! number of buffers. There are 5 flows: advert, request,
 //! re-transmission, chunk, and ingress. The first four flows are
 //! received from the *Gossip* peer network and ingress flow is received
-//! from the http handler.
+//! from the http handler. Artifacts pushed together with their adverts are
+//! received on the chunk flow.
 //!
 //! Flow control/back pressure for transport throttles/suspends the
 //! inflow of messages to match the p2p flow consumption rate.
@@ -70,7 +71,7 @@
 use crate::{
     advert_utils::AdvertRequestBuilder,
     gossip_protocol::{Gossip, GossipAdvertSendRequest},
-    gossip_types::{GossipChunk, GossipChunkRequest, GossipMessage},
+    gossip_types::{GossipArtifact, GossipChunk, GossipChunkRequest, GossipMessage},
     metrics::FlowWorkerMetrics,
 };
 use ic_interfaces_transport::{TransportEvent, TransportMessage};
@@ -116,6 +117,7 @@ type GossipArc = Arc<
             GossipAdvert = GossipAdvert,
             GossipChunkRequest = GossipChunkRequest,
             GossipChunk = GossipChunk,
+            GossipArtifact = GossipArtifact,
             GossipRetransmissionRequest = ArtifactFilter,
             GossipAdvertSendRequest = GossipAdvertSendRequest,
             NodeId = NodeId,
@@ -359,6 +361,16 @@ impl Service<TransportEvent> for AsyncTransportEventHandlerImpl {
                             Ok(())
                         })
                     }
+                    GossipMessage::Artifact(msg) => {
+                        let consume_fn = move |item, peer_id| {
+                            c_gossip.on_gossip_artifact(item, peer_id);
+                        };
+                        let chunk = self.chunk.clone();
+                        Box::pin(async move {
+                            chunk.execute(peer_id, msg, consume_fn).await;
+                            Ok(())
+                        })
+                    }
                     GossipMessage::RetransmissionRequest(msg) => {
                         let consume_fn = move |item, peer_id| {
                             c_gossip.on_gossip_retransmission_request(item, peer_id);
@@ -600,6 +612,7 @@ pub mod tests {
         type GossipAdvert = GossipAdvert;
         type GossipChunkRequest = GossipChunkRequest;
         type GossipChunk = GossipChunk;
+        type GossipArtifact = GossipArtifact;
         type GossipRetransmissionRequest = ArtifactFilter;
         type GossipAdvertSendRequest = GossipAdvertSendRequest;
         type NodeId = NodeId;
@@ -620,6 +633,11 @@ pub mod tests {
             TestGossip::increment_or_set(&self.num_chunks, peer_id);
         }
 
+        /// The method is called when a pushed artifact is received.
+        fn on_gossip_artifact(&self, _gossip_artifact: Self::GossipArtifact, peer_id: NodeId) {
+            TestGossip::increment_or_set(&self.num_chunks, peer_id);
+        }
+
         /// The method broadcasts the given advert.
         fn broadcast_advert(&self, _advert: GossipAdvertSendRequest) {
             TestGossip::increment_or_set(&self.num_advert_bcasts, self.node_id);
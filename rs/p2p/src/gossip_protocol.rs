@@ -54,7 +54,7 @@
 use crate::{
     artifact_download_list::ArtifactDownloadListImpl,
     download_prioritization::{DownloadPrioritizer, DownloadPrioritizerImpl},
-    gossip_types::{GossipChunk, GossipChunkRequest},
+    gossip_types::{GossipArtifact, GossipChunk, GossipChunkRequest},
     metrics::{DownloadManagementMetrics, DownloadPrioritizerMetrics, GossipMetrics},
     peer_context::PeerContextMap,
     utils::TransportChannelIdMapper,
@@ -85,6 +85,8 @@ pub trait Gossip {
     type GossipChunkRequest;
     /// The *Gossip* chunk type.
     type GossipChunk;
+    /// The *Gossip* pushed artifact type.
+    type GossipArtifact;
     /// The *Gossip* retranmision request type.
     type GossipRetransmissionRequest;
     /// The *Gossip* advert send request type.
@@ -107,6 +109,13 @@ pub trait Gossip {
     /// the artifact manager.DownloadPrioritizer
     fn on_gossip_chunk(&self, gossip_chunk: Self::GossipChunk, peer_id: Self::NodeId);
 
+    /// The method handles the given artifact pushed by the peer with the
+    /// given node ID.
+    ///
+    /// If the artifact is needed, it is handed over to the artifact manager
+    /// without requesting it. Otherwise, it is handled like its advert.
+    fn on_gossip_artifact(&self, gossip_artifact: Self::GossipArtifact, peer_id: Self::NodeId);
+
     /// The method broadcasts the given advert to other peers.
     fn broadcast_advert(&self, advert_request: Self::GossipAdvertSendRequest);
 
@@ -181,8 +190,8 @@ pub(crate) struct GossipImpl {
     pub artifacts_under_construction: RwLock<ArtifactDownloadListImpl>,
     /// The download management metrics.
     pub metrics: DownloadManagementMetrics,
-    /// The *Gossip* configuration.
-    pub gossip_config: GossipConfig,
+    /// The *Gossip* configuration, refreshed from the registry.
+    pub gossip_config: RwLock<GossipConfig>,
     /// The cache that is used to check if an artifact has been downloaded
     /// recently.
     pub receive_check_caches: RwLock<HashMap<NodeId, ReceiveCheckCache>>,
@@ -231,7 +240,7 @@ impl GossipImpl {
             transport_channel_mapper: TransportChannelIdMapper::new(transport_channels),
             artifacts_under_construction: RwLock::new(ArtifactDownloadListImpl::new(log)),
             metrics: DownloadManagementMetrics::new(metrics_registry),
-            gossip_config,
+            gossip_config: RwLock::new(gossip_config),
             receive_check_caches: RwLock::new(HashMap::new()),
             pfn_invocation_instant: Mutex::new(Instant::now()),
             registry_refresh_instant: Mutex::new(Instant::now()),
@@ -267,6 +276,7 @@ impl Gossip for GossipImpl {
     type GossipAdvert = GossipAdvert;
     type GossipChunkRequest = GossipChunkRequest;
     type GossipChunk = GossipChunk;
+    type GossipArtifact = GossipArtifact;
     type GossipRetransmissionRequest = ArtifactFilter;
     type GossipAdvertSendRequest = GossipAdvertSendRequest;
     type NodeId = NodeId;
@@ -316,6 +326,23 @@ impl Gossip for GossipImpl {
         let _ = self.download_next(peer_id);
     }
 
+    /// The method handles the given artifact pushed by the peer with the
+    /// given node ID.
+    ///
+    /// Pushed artifacts that are available locally are dropped, like their
+    /// adverts.
+    fn on_gossip_artifact(&self, gossip_artifact: GossipArtifact, peer_id: NodeId) {
+        if self
+            .artifact_manager
+            .has_artifact(&gossip_artifact.advert.artifact_id)
+        {
+            return;
+        }
+
+        self.on_pushed_artifact(gossip_artifact, peer_id);
+        let _ = self.download_next(peer_id);
+    }
+
     /// The method broadcasts the given advert to other peers.
     fn broadcast_advert(&self, advert_request: GossipAdvertSendRequest) {
         self.send_advert_to_peers(advert_request);
@@ -363,7 +390,7 @@ impl Gossip for GossipImpl {
 }
 
 /// Fetch the Gossip configuration from the registry.
-fn fetch_gossip_config(
+pub(crate) fn fetch_gossip_config(
     registry_client: Arc<dyn RegistryClient>,
     subnet_id: SubnetId,
 ) -> GossipConfig {
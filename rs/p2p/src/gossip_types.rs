@@ -1,12 +1,11 @@
 use crate::{P2PError, P2PErrorCode, P2PResult};
 use bincode::{deserialize, serialize};
-use ic_interfaces_transport::TransportChannelId;
 use ic_protobuf::p2p::v1 as pb;
 use ic_protobuf::p2p::v1::gossip_chunk::Response;
 use ic_protobuf::p2p::v1::gossip_message::Body;
 use ic_protobuf::proxy::{try_from_option_field, ProxyDecodeError, ProxyDecodeError::*};
 use ic_types::{
-    artifact::{ArtifactFilter, ArtifactId},
+    artifact::{Artifact, ArtifactFilter, ArtifactId},
     chunkable::{ArtifactChunk, ChunkId},
     crypto::CryptoHash,
     p2p::GossipAdvert,
@@ -38,6 +37,16 @@ pub(crate) struct GossipChunk {
     pub(crate) artifact_chunk: P2PResult<ArtifactChunk>,
 }
 
+/// An artifact pushed to a peer together with its advert, so that the peer
+/// does not have to request it.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct GossipArtifact {
+    /// The advert of the artifact.
+    pub(crate) advert: GossipAdvert,
+    /// The artifact.
+    pub(crate) artifact: Artifact,
+}
+
 /// This is the message exchanged on the wire with other peers.  This
 /// enum is private to the gossip layer because lower layers like
 /// *Transport* do not need to interpret the content.
@@ -52,17 +61,8 @@ pub(crate) enum GossipMessage {
     Chunk(GossipChunk),
     /// The retransmission request variant.
     RetransmissionRequest(ArtifactFilter),
-}
-
-/// A *Gossip* message can be converted into a
-/// `TransportChannelId`.
-impl From<&GossipMessage> for TransportChannelId {
-    /// The method returns the flow tag corresponding to the gossip message.
-    ///
-    /// Currently, the flow tag is always 0.
-    fn from(_: &GossipMessage) -> Self {
-        TransportChannelId::from(0)
-    }
+    /// The pushed artifact variant.
+    Artifact(GossipArtifact),
 }
 
 /// A *Gossip* message can be converted into a
@@ -84,6 +84,9 @@ impl From<GossipMessage> for pb::GossipMessage {
             GossipMessage::RetransmissionRequest(r) => Self {
                 body: Some(Body::RetransmissionRequest(r.into())),
             },
+            GossipMessage::Artifact(a) => Self {
+                body: Some(Body::Artifact(a.into())),
+            },
         }
     }
 }
@@ -100,11 +103,38 @@ impl TryFrom<pb::GossipMessage> for GossipMessage {
             Body::ChunkRequest(r) => Self::ChunkRequest(r.try_into()?),
             Body::Chunk(c) => Self::Chunk(c.try_into()?),
             Body::RetransmissionRequest(r) => Self::RetransmissionRequest(r.try_into()?),
+            Body::Artifact(a) => Self::Artifact(a.try_into()?),
         };
         Ok(message)
     }
 }
 
+/// A pushed artifact can be converted into a `pb::GossipArtifact`.
+impl From<GossipArtifact> for pb::GossipArtifact {
+    /// The function converts the given pushed artifact into the Protobuf
+    /// equivalent.
+    fn from(gossip_artifact: GossipArtifact) -> Self {
+        Self {
+            advert: Some(gossip_artifact.advert.into()),
+            artifact: serialize(&gossip_artifact.artifact)
+                .expect("Local value serialization should succeed"),
+        }
+    }
+}
+
+/// A `pb::GossipArtifact` can be converted into a pushed artifact.
+impl TryFrom<pb::GossipArtifact> for GossipArtifact {
+    type Error = ProxyDecodeError;
+    /// The function attempts to convert the given Protobuf pushed artifact
+    /// into a GossipArtifact.
+    fn try_from(gossip_artifact: pb::GossipArtifact) -> Result<Self, Self::Error> {
+        Ok(Self {
+            advert: try_from_option_field(gossip_artifact.advert, "GossipArtifact.advert")?,
+            artifact: deserialize(&gossip_artifact.artifact)?,
+        })
+    }
+}
+
 /// A chunk request can be converted into a `pb::GossipChunkRequest`.
 impl From<GossipChunkRequest> for pb::GossipChunkRequest {
     /// The function converts the given chunk request into the Protobuf
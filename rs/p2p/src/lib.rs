@@ -128,8 +128,20 @@ pub(crate) mod utils {
     //! corresponding flow tag.
     use crate::gossip_types::GossipMessage;
     use ic_interfaces_transport::TransportChannelId;
-
-    /// An ordered collection of transport channels.
+    use ic_types::artifact::ArtifactId;
+
+    /// The index of the channel used for consensus and all other messages
+    /// that are neither ingress nor state sync messages.
+    const CONSENSUS_CHANNEL: usize = 0;
+    /// The index of the channel used for ingress messages.
+    const INGRESS_CHANNEL: usize = 1;
+    /// The index of the channel used for state sync messages.
+    const STATE_SYNC_CHANNEL: usize = 2;
+
+    /// An ordered collection of transport channels: one for consensus, one for
+    /// ingress and one for state sync messages. Transports that send each
+    /// channel over its own stream thus do not delay consensus messages
+    /// behind state sync chunks or bursts of ingress messages.
     pub(crate) struct TransportChannelIdMapper {
         transport_channels: Vec<TransportChannelId>,
     }
@@ -137,13 +149,31 @@ pub(crate) mod utils {
     impl TransportChannelIdMapper {
         /// The function creates a new TransportChannelIdMapper instance.
         pub(crate) fn new(transport_channels: Vec<TransportChannelId>) -> Self {
-            assert_eq!(transport_channels.len(), 1);
+            assert_eq!(transport_channels.len(), 3);
             Self { transport_channels }
         }
 
         /// The function returns the flow tag of the flow the message maps to.
-        pub(crate) fn map(&self, _msg: &GossipMessage) -> TransportChannelId {
-            self.transport_channels[0]
+        pub(crate) fn map(&self, msg: &GossipMessage) -> TransportChannelId {
+            let artifact_id = match msg {
+                GossipMessage::Advert(advert) => &advert.artifact_id,
+                GossipMessage::ChunkRequest(request) => &request.artifact_id,
+                GossipMessage::Chunk(chunk) => &chunk.artifact_id,
+                GossipMessage::Artifact(artifact) => &artifact.advert.artifact_id,
+                GossipMessage::RetransmissionRequest(_) => {
+                    return self.transport_channels[CONSENSUS_CHANNEL]
+                }
+            };
+            let channel = match artifact_id {
+                ArtifactId::IngressMessage(_) => INGRESS_CHANNEL,
+                ArtifactId::FileTreeSync(_) | ArtifactId::StateSync(_) => STATE_SYNC_CHANNEL,
+                ArtifactId::ConsensusMessage(_)
+                | ArtifactId::CertificationMessage(_)
+                | ArtifactId::CanisterHttpMessage(_)
+                | ArtifactId::DkgMessage(_)
+                | ArtifactId::EcdsaMessage(_) => CONSENSUS_CHANNEL,
+            };
+            self.transport_channels[channel]
         }
     }
 }
@@ -162,7 +192,13 @@ pub fn start_p2p(
     artifact_manager: Arc<dyn ArtifactManager>,
     advert_broadcaster: &AdvertBroadcaster,
 ) -> P2PThreadJoiner {
-    let p2p_transport_channels = vec![TransportChannelId::from(transport_config.legacy_flow_tag)];
+    // Consensus, ingress and state sync messages are sent on separate
+    // channels, see `utils::TransportChannelIdMapper`.
+    let p2p_transport_channels = vec![
+        TransportChannelId::from(transport_config.legacy_flow_tag),
+        TransportChannelId::from(transport_config.legacy_flow_tag + 1),
+        TransportChannelId::from(transport_config.legacy_flow_tag + 2),
+    ];
     let gossip = Arc::new(gossip_protocol::GossipImpl::new(
         node_id,
         subnet_id,
@@ -190,9 +226,16 @@ pub fn start_p2p(
 
 pub(crate) mod advert_utils {
     use crate::gossip_protocol::{GossipAdvertAction, GossipAdvertSendRequest};
+    use crate::gossip_types::{GossipChunkRequest, GossipMessage};
+    use crate::utils::TransportChannelIdMapper;
+    use ic_interfaces_transport::TransportChannelId;
     use ic_metrics::MetricsRegistry;
-    use ic_types::artifact::AdvertClass;
+    use ic_types::artifact::{AdvertClass, ArtifactFilter, ArtifactId, IngressMessageId};
+    use ic_types::chunkable::ChunkId;
+    use ic_types::crypto::CryptoHashOf;
+    use ic_types::messages::MessageId;
     use ic_types::p2p::GossipAdvert;
+    use ic_types::time::UNIX_EPOCH;
     use prometheus::IntCounterVec;
 
     /// Maps the P2P client advert send requests to the internal format,
@@ -322,4 +365,54 @@ pub mod tests {
             assert!(result.is_none());
         }
     }
+
+    #[test]
+    fn test_transport_channel_mapping() {
+        let consensus_channel = TransportChannelId::from(10);
+        let ingress_channel = TransportChannelId::from(11);
+        let state_sync_channel = TransportChannelId::from(12);
+        let mapper = TransportChannelIdMapper::new(vec![
+            consensus_channel,
+            ingress_channel,
+            state_sync_channel,
+        ]);
+
+        // `make_gossip_advert` creates state sync adverts.
+        let state_sync_advert = make_gossip_advert(10);
+        let state_sync_chunk_request = GossipChunkRequest {
+            artifact_id: state_sync_advert.artifact_id.clone(),
+            integrity_hash: state_sync_advert.integrity_hash.clone(),
+            chunk_id: ChunkId::from(1),
+        };
+        assert_eq!(
+            mapper.map(&GossipMessage::Advert(state_sync_advert)),
+            state_sync_channel
+        );
+        assert_eq!(
+            mapper.map(&GossipMessage::ChunkRequest(state_sync_chunk_request)),
+            state_sync_channel
+        );
+
+        let mut ingress_advert = make_gossip_advert(11);
+        ingress_advert.artifact_id =
+            ArtifactId::IngressMessage(IngressMessageId::new(UNIX_EPOCH, MessageId::from([0; 32])));
+        assert_eq!(
+            mapper.map(&GossipMessage::Advert(ingress_advert)),
+            ingress_channel
+        );
+
+        let mut dkg_advert = make_gossip_advert(12);
+        dkg_advert.artifact_id =
+            ArtifactId::DkgMessage(CryptoHashOf::from(dkg_advert.integrity_hash.clone()));
+        assert_eq!(
+            mapper.map(&GossipMessage::Advert(dkg_advert)),
+            consensus_channel
+        );
+        assert_eq!(
+            mapper.map(&GossipMessage::RetransmissionRequest(
+                ArtifactFilter::default()
+            )),
+            consensus_channel
+        );
+    }
 }
@@ -52,6 +52,13 @@ pub struct DownloadManagementMetrics {
     pub integrity_hash_check_failed: IntCounter,
     // The time to download an artifact
     pub artifact_download_time: Histogram,
+    /// The number of artifacts pushed to peers together with their advert.
+    pub artifacts_pushed: IntCounter,
+    /// The number of pushed artifacts received from peers.
+    pub pushed_artifacts_received: IntCounter,
+    /// The number of pushed artifacts dropped because their size does not
+    /// match their advert or they do not qualify for pushing.
+    pub pushed_artifacts_rejected: IntCounter,
 
     // Chunking fields.
     /// The number of requested chunks.
@@ -158,6 +165,18 @@ impl DownloadManagementMetrics {
                 "integrity_hash_check_failed",
                 "Number of times the integrity check failed for artifacts",
             ),
+            artifacts_pushed: metrics_registry.int_counter(
+                "gossip_artifacts_pushed",
+                "Number of artifacts pushed to peers together with their advert",
+            ),
+            pushed_artifacts_received: metrics_registry.int_counter(
+                "gossip_pushed_artifacts_received",
+                "Number of pushed artifacts received from peers",
+            ),
+            pushed_artifacts_rejected: metrics_registry.int_counter(
+                "gossip_pushed_artifacts_rejected",
+                "Number of pushed artifacts dropped because of their size",
+            ),
 
             // Chunking fields.
             chunks_requested: metrics_registry.int_counter(
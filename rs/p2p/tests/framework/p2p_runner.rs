@@ -18,6 +18,7 @@ use ic_test_utilities::{
     message_routing::FakeMessageRouting,
     p2p::*,
     port_allocation::allocate_ports,
+    query_stats_payload_builder::FakeQueryStatsPayloadBuilder,
     self_validating_payload_builder::FakeSelfValidatingPayloadBuilder,
     state_manager::FakeStateManager,
     thread_transport::*,
@@ -108,10 +109,12 @@ fn execute_test(
             subnet_id,
             Some(transport),
             Arc::new(FakeTlsHandshake::new()),
+            Arc::new(FakeTlsHandshake::new()),
             Arc::clone(&state_manager) as Arc<_>,
             no_state_sync_client,
             xnet_payload_builder as Arc<_>,
             self_validating_payload_builder as Arc<_>,
+            Arc::new(FakeQueryStatsPayloadBuilder::new()) as Arc<_>,
             message_router as Arc<_>,
             Arc::clone(&fake_crypto) as Arc<_>,
             Arc::clone(&fake_crypto) as Arc<_>,
@@ -269,10 +272,12 @@ fn execute_test_chunking_pool(
             subnet_id,
             Some(transport),
             Arc::new(FakeTlsHandshake::new()),
+            Arc::new(FakeTlsHandshake::new()),
             Arc::clone(&state_manager) as Arc<_>,
             state_sync_client,
             xnet_payload_builder,
             self_validating_payload_builder,
+            Arc::new(FakeQueryStatsPayloadBuilder::new()) as Arc<_>,
             message_router,
             Arc::clone(&fake_crypto) as Arc<_>,
             Arc::clone(&fake_crypto) as Arc<_>,
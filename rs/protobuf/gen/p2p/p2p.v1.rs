@@ -2,7 +2,7 @@
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct GossipMessage {
-    #[prost(oneof = "gossip_message::Body", tags = "1, 2, 3, 5")]
+    #[prost(oneof = "gossip_message::Body", tags = "1, 2, 3, 5, 6")]
     pub body: ::core::option::Option<gossip_message::Body>,
 }
 /// Nested message and enum types in `GossipMessage`.
@@ -19,6 +19,8 @@ pub mod gossip_message {
         Chunk(super::GossipChunk),
         #[prost(message, tag = "5")]
         RetransmissionRequest(super::ArtifactFilter),
+        #[prost(message, tag = "6")]
+        Artifact(super::GossipArtifact),
     }
 }
 #[derive(serde::Serialize, serde::Deserialize)]
@@ -34,6 +36,18 @@ pub struct GossipAdvert {
     #[prost(bytes = "vec", tag = "4")]
     pub integrity_hash: ::prost::alloc::vec::Vec<u8>,
 }
+/// An artifact pushed together with its advert, so that the receiver does not
+/// have to request it.
+#[derive(serde::Serialize, serde::Deserialize)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GossipArtifact {
+    #[prost(message, optional, tag = "1")]
+    pub advert: ::core::option::Option<GossipAdvert>,
+    /// TODO(P2P-483): bincode-encoded Artifact to proto-encoding
+    #[prost(bytes = "vec", tag = "2")]
+    pub artifact: ::prost::alloc::vec::Vec<u8>,
+}
 #[derive(serde::Serialize, serde::Deserialize)]
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -315,6 +315,10 @@ pub struct GossipConfig {
     /// config for advert distribution.
     #[prost(uint32, tag = "8")]
     pub retransmission_request_ms: u32,
+    /// artifacts up to this size (and all consensus/certification shares) are
+    /// pushed together with their advert. 0 disables pushing. 0/0/1_048_576
+    #[prost(uint32, tag = "11")]
+    pub artifact_push_threshold_bytes: u32,
 }
 #[derive(serde::Serialize, serde::Deserialize)]
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -351,6 +355,21 @@ pub struct SubnetFeatures {
     /// Status of the SEV-SNP feature.
     #[prost(enumeration = "SevFeatureStatus", optional, tag = "7")]
     pub sev_status: ::core::option::Option<i32>,
+    /// This feature flag controls whether the nodes of this subnet talk to
+    /// each other over QUIC instead of TLS/TCP. It is disabled by default.
+    #[prost(bool, tag = "8")]
+    pub quic_transport: bool,
+    /// This feature flag controls whether the states of this subnet are synced
+    /// with compressed chunks. It must only be enabled once all replicas of the
+    /// subnet support it. It is disabled by default.
+    #[prost(bool, tag = "9")]
+    pub compressed_state_sync: bool,
+    /// This feature flag controls whether checkpoints of this subnet only write
+    /// the dirty pages of page maps to overlay files. It affects the state
+    /// manifest, so it must only be enabled once all replicas of the subnet
+    /// support it; and it cannot be disabled again. It is disabled by default.
+    #[prost(bool, tag = "10")]
+    pub overlay_checkpoints: bool,
 }
 /// Per subnet ECDSA configuration
 #[derive(serde::Serialize, serde::Deserialize, candid::CandidType, Eq)]
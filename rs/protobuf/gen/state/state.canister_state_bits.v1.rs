@@ -29,6 +29,9 @@ pub mod call_context {
         pub canister_id: ::core::option::Option<super::super::super::super::types::v1::CanisterId>,
         #[prost(uint64, tag = "2")]
         pub callback_id: u64,
+        /// If non-zero, the deadline of the best-effort call.
+        #[prost(uint32, tag = "3")]
+        pub deadline_seconds: u32,
     }
     /// System task is either a Heartbeat or a GlobalTimer.
     #[allow(clippy::derive_partial_eq_without_eq)]
@@ -87,6 +90,9 @@ pub struct Callback {
     #[prost(message, optional, tag = "9")]
     pub prepayment_for_response_transmission:
         ::core::option::Option<super::super::queues::v1::Cycles>,
+    /// If non-zero, the deadline of the best-effort call.
+    #[prost(uint32, tag = "10")]
+    pub deadline_seconds: u32,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -107,6 +113,9 @@ pub struct CallContextManager {
     pub call_contexts: ::prost::alloc::vec::Vec<CallContextEntry>,
     #[prost(message, repeated, tag = "4")]
     pub callbacks: ::prost::alloc::vec::Vec<CallbackEntry>,
+    /// Best-effort callbacks for which a response was already enqueued.
+    #[prost(uint64, repeated, tag = "5")]
+    pub closed_callbacks: ::prost::alloc::vec::Vec<u64>,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -248,6 +257,8 @@ pub mod stop_canister_context {
         pub funds: ::core::option::Option<super::super::super::queues::v1::Funds>,
         #[prost(message, optional, tag = "4")]
         pub cycles: ::core::option::Option<super::super::super::queues::v1::Cycles>,
+        #[prost(uint32, tag = "5")]
+        pub deadline_seconds: u32,
     }
     #[allow(clippy::derive_partial_eq_without_eq)]
     #[derive(Clone, PartialEq, ::prost::Oneof)]
@@ -400,6 +411,37 @@ pub struct CanisterStateBits {
     /// Canister version.
     #[prost(uint64, tag = "34")]
     pub canister_version: u64,
+    /// The local id to be assigned to the next snapshot of this canister.
+    #[prost(uint64, tag = "35")]
+    pub next_snapshot_id: u64,
+    /// Total memory used by the snapshots of this canister, in bytes.
+    #[prost(uint64, tag = "36")]
+    pub snapshots_memory_usage: u64,
+    /// The most recent changes to the canister and the total number of changes.
+    #[prost(message, optional, tag = "37")]
+    pub canister_history: ::core::option::Option<CanisterHistory>,
+    #[prost(enumeration = "LogVisibility", tag = "38")]
+    pub log_visibility: i32,
+    #[prost(message, repeated, tag = "39")]
+    pub canister_log_records: ::prost::alloc::vec::Vec<CanisterLogRecord>,
+    /// The index to be assigned to the next canister log record.
+    #[prost(uint64, tag = "40")]
+    pub next_canister_log_record_idx: u64,
+    /// The limit on the Wasm memory size of the canister, in bytes.
+    #[prost(uint64, optional, tag = "41")]
+    pub wasm_memory_limit: ::core::option::Option<u64>,
+    /// Cycles reserved for future storage payments.
+    #[prost(message, optional, tag = "42")]
+    pub reserved_balance: ::core::option::Option<super::super::queues::v1::Cycles>,
+    /// The limit on the reserved balance. Unset means that there is no limit.
+    #[prost(message, optional, tag = "43")]
+    pub reserved_balance_limit: ::core::option::Option<super::super::queues::v1::Cycles>,
+    /// The location of the chunks in the canister's Wasm chunk store.
+    #[prost(message, optional, tag = "44")]
+    pub wasm_chunk_store_metadata: ::core::option::Option<WasmChunkStoreMetadata>,
+    /// Query statistics aggregated across the replicas of the subnet.
+    #[prost(message, optional, tag = "45")]
+    pub total_query_stats: ::core::option::Option<TotalQueryStats>,
     #[prost(oneof = "canister_state_bits::CanisterStatus", tags = "11, 12, 13")]
     pub canister_status: ::core::option::Option<canister_state_bits::CanisterStatus>,
 }
@@ -416,6 +458,148 @@ pub mod canister_state_bits {
         Stopped(super::CanisterStatusStopped),
     }
 }
+/// Bits of a canister snapshot that are not stored in separate files.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CanisterSnapshotBits {
+    #[prost(message, optional, tag = "1")]
+    pub canister_id: ::core::option::Option<super::super::super::types::v1::CanisterId>,
+    #[prost(uint64, tag = "2")]
+    pub taken_at_timestamp: u64,
+    #[prost(uint64, tag = "3")]
+    pub canister_version: u64,
+    #[prost(bytes = "vec", tag = "4")]
+    pub certified_data: ::prost::alloc::vec::Vec<u8>,
+    #[prost(message, optional, tag = "5")]
+    pub execution_state_bits: ::core::option::Option<ExecutionStateBits>,
+    /// The size of the snapshot's stable memory in Wasm pages.
+    #[prost(uint64, tag = "6")]
+    pub stable_memory_size: u64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CanisterChangeFromUser {
+    #[prost(message, optional, tag = "1")]
+    pub user_id: ::core::option::Option<super::super::super::types::v1::PrincipalId>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CanisterChangeFromCanister {
+    #[prost(message, optional, tag = "1")]
+    pub canister_id: ::core::option::Option<super::super::super::types::v1::PrincipalId>,
+    #[prost(uint64, optional, tag = "2")]
+    pub canister_version: ::core::option::Option<u64>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CanisterCreation {
+    #[prost(message, repeated, tag = "1")]
+    pub controllers: ::prost::alloc::vec::Vec<super::super::super::types::v1::PrincipalId>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CanisterCodeUninstall {}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CanisterCodeDeployment {
+    #[prost(enumeration = "CanisterInstallMode", tag = "1")]
+    pub mode: i32,
+    #[prost(bytes = "vec", tag = "2")]
+    pub module_hash: ::prost::alloc::vec::Vec<u8>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CanisterControllersChange {
+    #[prost(message, repeated, tag = "1")]
+    pub controllers: ::prost::alloc::vec::Vec<super::super::super::types::v1::PrincipalId>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CanisterChange {
+    #[prost(uint64, tag = "1")]
+    pub timestamp_nanos: u64,
+    #[prost(uint64, tag = "2")]
+    pub canister_version: u64,
+    #[prost(oneof = "canister_change::ChangeOrigin", tags = "3, 4")]
+    pub change_origin: ::core::option::Option<canister_change::ChangeOrigin>,
+    #[prost(oneof = "canister_change::ChangeDetails", tags = "5, 6, 7, 8")]
+    pub change_details: ::core::option::Option<canister_change::ChangeDetails>,
+}
+/// Nested message and enum types in `CanisterChange`.
+pub mod canister_change {
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum ChangeOrigin {
+        #[prost(message, tag = "3")]
+        CanisterChangeFromUser(super::CanisterChangeFromUser),
+        #[prost(message, tag = "4")]
+        CanisterChangeFromCanister(super::CanisterChangeFromCanister),
+    }
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum ChangeDetails {
+        #[prost(message, tag = "5")]
+        CanisterCreation(super::CanisterCreation),
+        #[prost(message, tag = "6")]
+        CanisterCodeUninstall(super::CanisterCodeUninstall),
+        #[prost(message, tag = "7")]
+        CanisterCodeDeployment(super::CanisterCodeDeployment),
+        #[prost(message, tag = "8")]
+        CanisterControllersChange(super::CanisterControllersChange),
+    }
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct WasmChunkData {
+    /// The SHA-256 hash of the chunk.
+    #[prost(bytes = "vec", tag = "1")]
+    pub hash: ::prost::alloc::vec::Vec<u8>,
+    /// The chunk is stored at offset `index * CHUNK_SIZE` in the chunk store.
+    #[prost(uint64, tag = "2")]
+    pub index: u64,
+    /// The length of the chunk in bytes.
+    #[prost(uint64, tag = "3")]
+    pub length: u64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct WasmChunkStoreMetadata {
+    #[prost(message, repeated, tag = "1")]
+    pub chunks: ::prost::alloc::vec::Vec<WasmChunkData>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TotalQueryStats {
+    #[prost(uint64, tag = "1")]
+    pub num_calls: u64,
+    #[prost(uint64, tag = "2")]
+    pub num_instructions: u64,
+    #[prost(uint64, tag = "3")]
+    pub ingress_payload_size: u64,
+    #[prost(uint64, tag = "4")]
+    pub egress_payload_size: u64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CanisterHistory {
+    /// The most recent changes, oldest first.
+    #[prost(message, repeated, tag = "1")]
+    pub changes: ::prost::alloc::vec::Vec<CanisterChange>,
+    /// The total number of changes ever recorded, including those that have
+    /// been dropped from `changes`.
+    #[prost(uint64, tag = "2")]
+    pub total_num_changes: u64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CanisterLogRecord {
+    #[prost(uint64, tag = "1")]
+    pub idx: u64,
+    #[prost(uint64, tag = "2")]
+    pub timestamp_nanos: u64,
+    #[prost(bytes = "vec", tag = "3")]
+    pub content: ::prost::alloc::vec::Vec<u8>,
+}
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
 #[repr(i32)]
@@ -437,3 +621,47 @@ impl CustomSectionType {
         }
     }
 }
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum CanisterInstallMode {
+    Unspecified = 0,
+    Install = 1,
+    Reinstall = 2,
+    Upgrade = 3,
+}
+impl CanisterInstallMode {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            CanisterInstallMode::Unspecified => "CANISTER_INSTALL_MODE_UNSPECIFIED",
+            CanisterInstallMode::Install => "CANISTER_INSTALL_MODE_INSTALL",
+            CanisterInstallMode::Reinstall => "CANISTER_INSTALL_MODE_REINSTALL",
+            CanisterInstallMode::Upgrade => "CANISTER_INSTALL_MODE_UPGRADE",
+        }
+    }
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum LogVisibility {
+    Unspecified = 0,
+    Controllers = 1,
+    Public = 2,
+}
+impl LogVisibility {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            LogVisibility::Unspecified => "LOG_VISIBILITY_UNSPECIFIED",
+            LogVisibility::Controllers => "LOG_VISIBILITY_CONTROLLERS",
+            LogVisibility::Public => "LOG_VISIBILITY_PUBLIC",
+        }
+    }
+}
@@ -253,6 +253,38 @@ pub struct SubnetMetrics {
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CanisterQueryStats {
+    #[prost(message, optional, tag = "1")]
+    pub canister_id: ::core::option::Option<super::super::super::types::v1::CanisterId>,
+    #[prost(uint32, tag = "2")]
+    pub num_calls: u32,
+    #[prost(uint64, tag = "3")]
+    pub num_instructions: u64,
+    #[prost(uint64, tag = "4")]
+    pub ingress_payload_size: u64,
+    #[prost(uint64, tag = "5")]
+    pub egress_payload_size: u64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct NodeQueryStats {
+    #[prost(message, optional, tag = "1")]
+    pub node_id: ::core::option::Option<super::super::super::types::v1::NodeId>,
+    #[prost(message, repeated, tag = "2")]
+    pub canister_stats: ::prost::alloc::vec::Vec<CanisterQueryStats>,
+}
+/// Query statistics contributed by the replicas of the subnet for the epoch
+/// that is currently being aggregated.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RawQueryStats {
+    #[prost(uint64, tag = "1")]
+    pub epoch: u64,
+    #[prost(message, repeated, tag = "2")]
+    pub node_stats: ::prost::alloc::vec::Vec<NodeQueryStats>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct BitcoinGetSuccessorsFollowUpResponses {
     #[prost(message, optional, tag = "1")]
     pub sender: ::core::option::Option<super::super::super::types::v1::CanisterId>,
@@ -307,6 +339,8 @@ pub struct SystemMetadata {
     #[prost(message, repeated, tag = "18")]
     pub bitcoin_get_successors_follow_up_responses:
         ::prost::alloc::vec::Vec<BitcoinGetSuccessorsFollowUpResponses>,
+    #[prost(message, optional, tag = "19")]
+    pub raw_query_stats: ::core::option::Option<RawQueryStats>,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -8,6 +8,7 @@ use crate::{error::RecoveryError, RecoveryArgs};
 use clap::Parser;
 use ic_base_types::{NodeId, SubnetId};
 use ic_types::ReplicaVersion;
+use serde::Deserialize;
 use slog::{info, Logger};
 use std::net::IpAddr;
 use strum::IntoEnumIterator;
@@ -30,18 +31,22 @@ pub enum StepType {
     Cleanup,
 }
 
-#[derive(Parser)]
+#[derive(Parser, Deserialize)]
 #[clap(version = "1.0")]
+#[serde(deny_unknown_fields)]
 pub struct AppSubnetRecoveryArgs {
     /// Id of the broken subnet
     #[clap(long, parse(try_from_str=crate::util::subnet_id_from_str))]
+    #[serde(deserialize_with = "crate::util::deserialize_subnet_id")]
     pub subnet_id: SubnetId,
 
     /// Replica version to upgrade the broken subnet to
     #[clap(long, parse(try_from_str=::std::convert::TryFrom::try_from))]
+    #[serde(default, deserialize_with = "crate::util::deserialize_opt_version")]
     pub upgrade_version: Option<ReplicaVersion>,
 
     #[clap(long, multiple_values(true), parse(try_from_str=crate::util::node_id_from_str))]
+    #[serde(default, deserialize_with = "crate::util::deserialize_opt_node_ids")]
     /// Replace the members of the given subnet with these nodes
     pub replacement_nodes: Option<Vec<NodeId>>,
 
@@ -63,6 +68,7 @@ pub struct AppSubnetRecoveryArgs {
 
     /// Id of the ecdsa subnet used for resharing ecdsa key of subnet to be recovered
     #[clap(long, parse(try_from_str=crate::util::subnet_id_from_str))]
+    #[serde(default, deserialize_with = "crate::util::deserialize_opt_subnet_id")]
     pub ecdsa_subnet_id: Option<SubnetId>,
 }
 
@@ -4,6 +4,7 @@ use crate::app_subnet_recovery::{AppSubnetRecovery, AppSubnetRecoveryArgs};
 use crate::get_node_heights_from_metrics;
 use crate::nns_recovery_failover_nodes::{NNSRecoveryFailoverNodes, NNSRecoveryFailoverNodesArgs};
 use crate::nns_recovery_same_nodes::{NNSRecoverySameNodes, NNSRecoverySameNodesArgs};
+use crate::recovery_plan::{execute_plan, RecoveryPlanArgs};
 use crate::steps::Step;
 use crate::util;
 use crate::util::subnet_id_from_str;
@@ -124,6 +125,22 @@ pub fn nns_recovery_failover_nodes(
     }
 }
 
+/// A recovery plan is executed by running the listed steps of its procedure
+/// without prompting. Completed steps are journaled, so that rerunning an
+/// interrupted plan resumes with the first step that did not complete.
+/// A dry run prints the commands of all steps instead.
+pub fn recovery_plan(logger: Logger, args: RecoveryArgs, plan_args: RecoveryPlanArgs, test: bool) {
+    print_step(&logger, "Recovery Plan");
+    if let Err(e) = execute_plan(&logger, args, &plan_args.plan_file, plan_args.dry_run, test) {
+        warn!(logger, "Error: {}", e);
+        warn!(
+            logger,
+            "Rerun the plan to resume the recovery with the failed step."
+        );
+        std::process::exit(1);
+    }
+}
+
 pub fn execute_step_after_consent(logger: &Logger, step: Box<dyn Step>) {
     info!(logger, "{}", step.descr());
     if consent_given(logger, "Execute now?") {
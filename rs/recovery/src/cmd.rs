@@ -6,7 +6,7 @@ use url::Url;
 use crate::{
     app_subnet_recovery::AppSubnetRecoveryArgs,
     nns_recovery_failover_nodes::NNSRecoveryFailoverNodesArgs,
-    nns_recovery_same_nodes::NNSRecoverySameNodesArgs,
+    nns_recovery_same_nodes::NNSRecoverySameNodesArgs, recovery_plan::RecoveryPlanArgs,
 };
 
 /// Subcommands for recovery procedures (application subnets, NNS with failover nodes, etc...)
@@ -18,6 +18,8 @@ pub enum SubCommand {
     NNSRecoveryFailoverNodes(Box<NNSRecoveryFailoverNodesArgs>),
     /// NNS recovery on the same nodes.
     NNSRecoverySameNodes(NNSRecoverySameNodesArgs),
+    /// Any of the above recoveries, as described by a recovery plan file.
+    Plan(RecoveryPlanArgs),
 }
 
 #[derive(Parser)]
@@ -45,6 +47,11 @@ pub struct RecoveryToolArgs {
     #[clap(long, parse(from_os_str))]
     pub key_file: Option<PathBuf>,
 
+    /// Instead of accessing nodes using SSH, operate on copies of their data
+    /// previously downloaded to this directory, one subdirectory per node IP
+    #[clap(long, parse(from_os_str))]
+    pub local_nodes: Option<PathBuf>,
+
     /// Flag to enter test mode
     #[clap(long)]
     pub test: bool,
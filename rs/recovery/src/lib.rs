@@ -27,6 +27,7 @@ use ic_replay::cmd::{AddAndBlessReplicaVersionCmd, AddRegistryContentCmd, SubCom
 use ic_replay::player::StateParams;
 use ic_types::messages::HttpStatusResponse;
 use ic_types::{Height, ReplicaVersion, SubnetId};
+use node_access::{LocalNodeAccess, NodeAccess, SshNodeAccess};
 use prost::Message;
 use slog::{info, warn, Logger};
 use ssh_helper::SshHelper;
@@ -53,7 +54,9 @@ pub mod error;
 pub mod file_sync_helper;
 pub mod nns_recovery_failover_nodes;
 pub mod nns_recovery_same_nodes;
+pub mod node_access;
 pub mod recovery_iterator;
+pub mod recovery_plan;
 pub mod replay_helper;
 pub(crate) mod ssh_helper;
 pub mod steps;
@@ -91,12 +94,16 @@ pub struct RecoveryArgs {
     pub nns_url: Url,
     pub replica_version: Option<ReplicaVersion>,
     pub key_file: Option<PathBuf>,
+    /// If set, nodes are not accessed using SSH, but through copies of their
+    /// data in this directory. See [LocalNodeAccess].
+    pub local_nodes: Option<PathBuf>,
 }
 
 /// The recovery struct comprises working directories for the recovery of a
 /// given replica version and NNS. It offers several functions useful for subnet
 /// recovery, by providing an interface to tools such as `ic-replay` and
-/// `ic-recovery`, as well as ssh and rsync procedures.
+/// `ic-recovery`, as well as access to the nodes of the subnet (see
+/// [NodeAccess]).
 /// Although operations on subnets and the downloaded state are idempotent, certain
 /// orders of execution will naturally lead to errors (i.e. replaying the state
 /// before downloading it).
@@ -112,6 +119,7 @@ pub struct Recovery {
     pub admin_helper: AdminHelper,
     pub registry_client: Arc<RegistryClientImpl>,
     pub local_store: Arc<LocalStoreImpl>,
+    pub node_access: Arc<dyn NodeAccess>,
 
     pub key_file: Option<PathBuf>,
     ssh_confirmation: bool,
@@ -136,6 +144,14 @@ impl Recovery {
         let nns_pem = recovery_dir.join("nns.pem");
         let local_store = Arc::new(LocalStoreImpl::new(local_store_path.clone()));
         let registry_client = Arc::new(RegistryClientImpl::new(local_store.clone(), None));
+        let node_access: Arc<dyn NodeAccess> = match args.local_nodes {
+            Some(dir) => Arc::new(LocalNodeAccess::new(logger.clone(), dir)),
+            None => Arc::new(SshNodeAccess::new(
+                logger.clone(),
+                ssh_confirmation,
+                args.key_file.clone(),
+            )),
+        };
         let r = Self {
             recovery_dir,
             binary_dir: binary_dir.clone(),
@@ -146,6 +162,7 @@ impl Recovery {
             admin_helper: AdminHelper::new(binary_dir.clone(), args.nns_url, neuron_args),
             registry_client,
             local_store,
+            node_access,
             key_file: args.key_file,
             ssh_confirmation,
             logger,
@@ -392,8 +409,7 @@ impl Recovery {
             target: self.data_dir.display().to_string(),
             keep_downloaded_state,
             working_dir: self.work_dir.display().to_string(),
-            require_confirmation: self.ssh_confirmation,
-            key_file: self.key_file.clone(),
+            node_access: self.node_access.clone(),
         }
     }
 
@@ -527,8 +543,7 @@ impl Recovery {
             node_ip,
             work_dir: self.work_dir.clone(),
             data_src,
-            require_confirmation: self.ssh_confirmation,
-            key_file: self.key_file.clone(),
+            node_access: self.node_access.clone(),
         }
     }
 
@@ -815,10 +830,8 @@ impl Recovery {
     /// Return a [StopReplicaStep] to stop the replica with the given IP
     pub fn get_stop_replica_step(&self, node_ip: IpAddr) -> impl Step {
         StopReplicaStep {
-            logger: self.logger.clone(),
             node_ip,
-            require_confirmation: self.ssh_confirmation,
-            key_file: self.key_file.clone(),
+            node_access: self.node_access.clone(),
         }
     }
 
@@ -876,9 +889,8 @@ impl Recovery {
             logger: self.logger.clone(),
             registry_client: self.registry_client.clone(),
             subnet_id,
+            node_access: self.node_access.clone(),
             work_dir: self.work_dir.clone(),
-            require_confirmation: self.ssh_confirmation,
-            key_file: self.key_file.clone(),
         }
     }
 
@@ -19,6 +19,7 @@ fn main() {
         nns_url: args.nns_url,
         replica_version: args.replica_version,
         key_file: args.key_file,
+        local_nodes: args.local_nodes,
     };
 
     match args.subcmd {
@@ -42,5 +43,8 @@ fn main() {
                 args.test,
             )
         }
+        SubCommand::Plan(plan_args) => {
+            cli::recovery_plan(logger.clone(), recovery_args, plan_args, args.test)
+        }
     }
 }
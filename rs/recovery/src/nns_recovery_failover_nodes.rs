@@ -10,6 +10,7 @@ use crate::{NeuronArgs, RecoveryResult, IC_REGISTRY_LOCAL_STORE};
 use clap::Parser;
 use ic_base_types::SubnetId;
 use ic_types::{NodeId, ReplicaVersion};
+use serde::Deserialize;
 use slog::Logger;
 use std::net::IpAddr;
 use std::path::PathBuf;
@@ -40,15 +41,18 @@ pub enum StepType {
     Cleanup,
 }
 
-#[derive(Parser)]
+#[derive(Parser, Deserialize)]
 #[clap(version = "1.0")]
+#[serde(deny_unknown_fields)]
 pub struct NNSRecoveryFailoverNodesArgs {
     /// Id of the broken subnet
     #[clap(long, parse(try_from_str=crate::util::subnet_id_from_str))]
+    #[serde(deserialize_with = "crate::util::deserialize_subnet_id")]
     pub subnet_id: SubnetId,
 
     /// Replica version to start the new NNS with (has to be blessed by parent NNS)
     #[clap(long, parse(try_from_str=::std::convert::TryFrom::try_from))]
+    #[serde(default, deserialize_with = "crate::util::deserialize_opt_version")]
     pub replica_version: Option<ReplicaVersion>,
 
     /// Public ssh key to be deployed to the subnet for read only access
@@ -84,6 +88,7 @@ pub struct NNSRecoveryFailoverNodesArgs {
     pub parent_nns_host_ip: Option<IpAddr>,
 
     #[clap(long, multiple_values(true), parse(try_from_str=crate::util::node_id_from_str))]
+    #[serde(default, deserialize_with = "crate::util::deserialize_opt_node_ids")]
     /// Replace the members of the given subnet with these nodes
     pub replacement_nodes: Option<Vec<NodeId>>,
 }
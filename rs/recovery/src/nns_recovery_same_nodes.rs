@@ -6,6 +6,7 @@ use crate::{error::RecoveryError, RecoveryArgs};
 use clap::Parser;
 use ic_base_types::SubnetId;
 use ic_types::ReplicaVersion;
+use serde::Deserialize;
 use slog::Logger;
 use std::net::IpAddr;
 use std::path::PathBuf;
@@ -30,15 +31,18 @@ pub enum StepType {
     Cleanup,
 }
 
-#[derive(Parser)]
+#[derive(Parser, Deserialize)]
 #[clap(version = "1.0")]
+#[serde(deny_unknown_fields)]
 pub struct NNSRecoverySameNodesArgs {
     /// Id of the broken subnet
     #[clap(long, parse(try_from_str=crate::util::subnet_id_from_str))]
+    #[serde(deserialize_with = "crate::util::deserialize_subnet_id")]
     pub subnet_id: SubnetId,
 
     /// Replica version to upgrade the broken subnet to
     #[clap(long, parse(try_from_str=::std::convert::TryFrom::try_from))]
+    #[serde(default, deserialize_with = "crate::util::deserialize_opt_version")]
     pub upgrade_version: Option<ReplicaVersion>,
 
     /// Public ssh key to be deployed to the subnet for read only access
@@ -0,0 +1,399 @@
+//! Access to the nodes of a subnet under recovery. All steps operating on nodes
+//! of the broken subnet go through a [NodeAccess] backend: [SshNodeAccess]
+//! operates on live nodes using SSH and rsync, while [LocalNodeAccess] operates
+//! on copies of the nodes' data previously downloaded to the local file system,
+//! which allows running recoveries without any access to the nodes.
+use crate::command_helper::exec_cmd;
+use crate::error::{RecoveryError, RecoveryResult};
+use crate::file_sync_helper::{create_dir, remove_dir, rsync};
+use crate::ssh_helper::SshHelper;
+use crate::{
+    Recovery, ADMIN, CHECKPOINTS, IC_CHECKPOINTS_PATH, IC_DATA_PATH, IC_JSON5_PATH,
+    IC_REGISTRY_LOCAL_STORE, IC_STATE, NEW_IC_STATE,
+};
+use slog::{info, Logger};
+use std::fs;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Name of the uploaded CUP file.
+pub const CUP_FILE: &str = "cup.proto";
+/// Name of the uploaded tar file of the registry local store.
+pub const REGISTRY_TAR_FILE: &str = "ic_registry_local_store.tar.gz";
+/// Path of the CUP used by the replica on start, relative to [IC_DATA_PATH].
+pub const CUP_PATH: &str = "cups/cup.types.v1.CatchUpPackage.pb";
+
+/// Operations executed on the nodes of a subnet during recovery. Paths on the
+/// nodes are given as on a live node, e.g. [IC_DATA_PATH], and are mapped by
+/// the backend.
+pub trait NodeAccess: Send + Sync {
+    /// Returns `true` if the node can be accessed using the given account.
+    fn can_connect(&self, node_ip: IpAddr, account: &str) -> bool;
+
+    /// Returns the names of all checkpoints in the state of the node.
+    fn get_checkpoint_names(&self, node_ip: IpAddr, account: &str) -> RecoveryResult<Vec<String>>;
+
+    /// Copies `src` on the node to the local `target` with the semantics of
+    /// [rsync], skipping files and directories with names in `excludes`.
+    fn download(
+        &self,
+        node_ip: IpAddr,
+        account: &str,
+        excludes: Vec<&str>,
+        src: &str,
+        target: &str,
+    ) -> RecoveryResult<()>;
+
+    /// Copies the local `src` to `target` on the node with the semantics of
+    /// [rsync], skipping files and directories with names in `excludes`.
+    fn upload(
+        &self,
+        node_ip: IpAddr,
+        account: &str,
+        excludes: Vec<&str>,
+        src: &str,
+        target: &str,
+    ) -> RecoveryResult<()>;
+
+    /// Stops the replica running on the node.
+    fn stop_replica(&self, node_ip: IpAddr) -> RecoveryResult<()>;
+
+    /// Creates the [NEW_IC_STATE] directory on the node, holding a copy of the
+    /// latest checkpoint of the node named `checkpoint`, such that uploading
+    /// a state only transfers the files that changed since.
+    fn prepare_state_upload(&self, node_ip: IpAddr, checkpoint: &str) -> RecoveryResult<()>;
+
+    /// Replaces the state of the node by the uploaded [NEW_IC_STATE] and
+    /// restarts the replica.
+    fn replace_state(&self, node_ip: IpAddr) -> RecoveryResult<()>;
+
+    /// Creates the empty directory `dir` on the node, removing previous
+    /// contents.
+    fn prepare_upload_dir(&self, node_ip: IpAddr, dir: &str) -> RecoveryResult<()>;
+
+    /// Installs the [CUP_FILE] and [REGISTRY_TAR_FILE] uploaded to `dir` on the
+    /// node and restarts the replica.
+    fn install_cup_and_registry(&self, node_ip: IpAddr, dir: &str) -> RecoveryResult<()>;
+}
+
+/// Returns the commands installing the CUP and registry local store uploaded to
+/// `dir` on a live node and restarting the replica.
+pub fn get_install_cup_and_registry_commands(dir: &str) -> String {
+    format!(
+        r#"
+cd {dir};
+OWNER_UID=$(sudo stat -c '%u' {data}/{store});
+GROUP_UID=$(sudo stat -c '%g' {data}/{store});
+mkdir {store};
+tar zxf {tar} -C {store};
+sudo chown -R "$OWNER_UID:$GROUP_UID" {store};
+OWNER_UID=$(sudo stat -c '%u' {data}/cups);
+GROUP_UID=$(sudo stat -c '%g' {data}/cups);
+sudo chown -R "$OWNER_UID:$GROUP_UID" {cup};
+sudo systemctl stop ic-replica;
+sudo rsync -a --delete {store}/ {data}/{store}/;
+sudo cp {cup} {data}/{cup_path};
+sudo systemctl restart setup-permissions || true ;
+sudo systemctl start ic-replica;
+sudo systemctl status ic-replica;
+"#,
+        dir = dir,
+        data = IC_DATA_PATH,
+        store = IC_REGISTRY_LOCAL_STORE,
+        tar = REGISTRY_TAR_FILE,
+        cup = CUP_FILE,
+        cup_path = CUP_PATH,
+    )
+}
+
+/// Accesses live nodes using SSH and rsync.
+pub struct SshNodeAccess {
+    logger: Logger,
+    require_confirmation: bool,
+    key_file: Option<PathBuf>,
+}
+
+impl SshNodeAccess {
+    pub fn new(logger: Logger, require_confirmation: bool, key_file: Option<PathBuf>) -> Self {
+        Self {
+            logger,
+            require_confirmation,
+            key_file,
+        }
+    }
+
+    fn ssh_helper(&self, node_ip: IpAddr, account: &str) -> SshHelper {
+        SshHelper::new(
+            self.logger.clone(),
+            account.to_string(),
+            node_ip,
+            self.require_confirmation,
+            self.key_file.clone(),
+        )
+    }
+
+    fn remote_path(node_ip: IpAddr, account: &str, path: &str) -> String {
+        format!("{}@[{}]:{}", account, node_ip, path)
+    }
+}
+
+impl NodeAccess for SshNodeAccess {
+    fn can_connect(&self, node_ip: IpAddr, account: &str) -> bool {
+        self.ssh_helper(node_ip, account).can_connect()
+    }
+
+    fn get_checkpoint_names(&self, node_ip: IpAddr, account: &str) -> RecoveryResult<Vec<String>> {
+        let res = self
+            .ssh_helper(node_ip, account)
+            .ssh(format!("ls {}/{}", IC_DATA_PATH, IC_CHECKPOINTS_PATH))?
+            .unwrap_or_default();
+        Ok(res.split_whitespace().map(String::from).collect())
+    }
+
+    fn download(
+        &self,
+        node_ip: IpAddr,
+        account: &str,
+        excludes: Vec<&str>,
+        src: &str,
+        target: &str,
+    ) -> RecoveryResult<()> {
+        rsync(
+            &self.logger,
+            excludes,
+            &Self::remote_path(node_ip, account, src),
+            target,
+            self.require_confirmation,
+            self.key_file.as_ref(),
+        )?;
+        Ok(())
+    }
+
+    fn upload(
+        &self,
+        node_ip: IpAddr,
+        account: &str,
+        excludes: Vec<&str>,
+        src: &str,
+        target: &str,
+    ) -> RecoveryResult<()> {
+        rsync(
+            &self.logger,
+            excludes,
+            src,
+            &Self::remote_path(node_ip, account, target),
+            self.require_confirmation,
+            self.key_file.as_ref(),
+        )?;
+        Ok(())
+    }
+
+    fn stop_replica(&self, node_ip: IpAddr) -> RecoveryResult<()> {
+        self.ssh_helper(node_ip, ADMIN)
+            .ssh("sudo systemctl stop ic-replica".to_string())?;
+        Ok(())
+    }
+
+    fn prepare_state_upload(&self, node_ip: IpAddr, checkpoint: &str) -> RecoveryResult<()> {
+        let ic_checkpoints_path = format!("{}/{}", IC_DATA_PATH, IC_CHECKPOINTS_PATH);
+        // upload directory to create
+        let upload_dir = format!("{}/{}", IC_DATA_PATH, NEW_IC_STATE);
+        // path of highest checkpoint on upload node
+        let copy_from = format!(
+            "{}/$(ls {} | sort | tail -1)",
+            ic_checkpoints_path, ic_checkpoints_path
+        );
+        // path and name of checkpoint after replay
+        let copy_to = format!("{}/{}/{}", upload_dir, CHECKPOINTS, checkpoint);
+        let cp = format!("sudo cp -r {} {}", copy_from, copy_to);
+
+        if let Some(res) = self.ssh_helper(node_ip, ADMIN).ssh(format!(
+            "sudo mkdir -p {}/{}; {}; sudo chown -R {} {};",
+            upload_dir, CHECKPOINTS, cp, ADMIN, upload_dir
+        ))? {
+            info!(self.logger, "{}", res);
+        }
+        Ok(())
+    }
+
+    fn replace_state(&self, node_ip: IpAddr) -> RecoveryResult<()> {
+        let upload_dir = format!("{}/{}", IC_DATA_PATH, NEW_IC_STATE);
+        let ic_state_path = format!("{}/{}", IC_DATA_PATH, IC_STATE);
+        let mut replace_state = String::new();
+        replace_state.push_str("sudo systemctl stop ic-replica;");
+        replace_state.push_str(&format!(
+            "sudo chmod -R --reference={} {};",
+            ic_state_path, upload_dir
+        ));
+        replace_state.push_str(&format!(
+            "sudo chown -R --reference={} {};",
+            ic_state_path, upload_dir
+        ));
+        replace_state.push_str(&format!("sudo rm -r {};", ic_state_path));
+        replace_state.push_str(&format!("sudo mv {} {};", upload_dir, ic_state_path));
+        replace_state.push_str(&format!(
+            r"sudo find {} -type f -exec chmod a-x {{}} \;;",
+            ic_state_path
+        ));
+        replace_state.push_str(&format!(
+            r"sudo find {} -type f -exec chmod go+r {{}} \;;",
+            ic_state_path
+        ));
+        // Note that on older versions of IC-OS this service does not exist.
+        // So try this operation, but ignore possible failure if service
+        // does not exist on the affected version.
+        replace_state.push_str("(sudo systemctl restart setup-permissions || true);");
+        replace_state.push_str("sudo systemctl start ic-replica;");
+        replace_state.push_str("sudo systemctl status ic-replica;");
+
+        self.ssh_helper(node_ip, ADMIN).ssh(replace_state)?;
+        Ok(())
+    }
+
+    fn prepare_upload_dir(&self, node_ip: IpAddr, dir: &str) -> RecoveryResult<()> {
+        self.ssh_helper(node_ip, ADMIN)
+            .ssh(format!("sudo rm -rf {} && mkdir {}", dir, dir))?;
+        Ok(())
+    }
+
+    fn install_cup_and_registry(&self, node_ip: IpAddr, dir: &str) -> RecoveryResult<()> {
+        self.ssh_helper(node_ip, ADMIN)
+            .ssh(get_install_cup_and_registry_commands(dir))?;
+        Ok(())
+    }
+}
+
+/// Accesses nodes whose data was previously downloaded to the local file
+/// system. The data of each node is expected in a subdirectory of `root` named
+/// after the node's IP, with the layout produced by downloading the node's
+/// state, i.e. the contents of [IC_DATA_PATH] (such as `ic_state` and
+/// `ic_consensus_pool`) in `data` and the replica config in `ic.json5`. Other
+/// paths are mapped to the same path below the node's directory.
+///
+/// There is no replica running on local nodes, and all accounts have access to
+/// every node with a directory.
+pub struct LocalNodeAccess {
+    logger: Logger,
+    root: PathBuf,
+}
+
+impl LocalNodeAccess {
+    pub fn new(logger: Logger, root: PathBuf) -> Self {
+        Self { logger, root }
+    }
+
+    /// Returns the directory holding the data of the given node.
+    pub fn node_dir(&self, node_ip: IpAddr) -> PathBuf {
+        self.root.join(node_ip.to_string())
+    }
+
+    /// Maps the path on a live node to the corresponding local path, keeping a
+    /// trailing slash, which is significant to [rsync].
+    pub fn local_path(&self, node_ip: IpAddr, path: &str) -> String {
+        let node_dir = self.node_dir(node_ip);
+        if path == IC_JSON5_PATH {
+            return node_dir.join("ic.json5").display().to_string();
+        }
+        match path.strip_prefix(IC_DATA_PATH) {
+            Some(rest) => format!("{}{}", node_dir.join("data").display(), rest),
+            None => format!("{}{}", node_dir.display(), path),
+        }
+    }
+
+    fn data_path(&self, node_ip: IpAddr, path: &str) -> PathBuf {
+        PathBuf::from(self.local_path(node_ip, &format!("{}/{}", IC_DATA_PATH, path)))
+    }
+
+    fn copy(&self, excludes: Vec<&str>, src: &str, target: &str) -> RecoveryResult<()> {
+        rsync(&self.logger, excludes, src, target, false, None)?;
+        Ok(())
+    }
+}
+
+impl NodeAccess for LocalNodeAccess {
+    fn can_connect(&self, node_ip: IpAddr, _account: &str) -> bool {
+        self.node_dir(node_ip).is_dir()
+    }
+
+    fn get_checkpoint_names(&self, node_ip: IpAddr, _account: &str) -> RecoveryResult<Vec<String>> {
+        Recovery::get_checkpoint_names(&self.data_path(node_ip, IC_CHECKPOINTS_PATH))
+    }
+
+    fn download(
+        &self,
+        node_ip: IpAddr,
+        _account: &str,
+        excludes: Vec<&str>,
+        src: &str,
+        target: &str,
+    ) -> RecoveryResult<()> {
+        self.copy(excludes, &self.local_path(node_ip, src), target)
+    }
+
+    fn upload(
+        &self,
+        node_ip: IpAddr,
+        _account: &str,
+        excludes: Vec<&str>,
+        src: &str,
+        target: &str,
+    ) -> RecoveryResult<()> {
+        self.copy(excludes, src, &self.local_path(node_ip, target))
+    }
+
+    fn stop_replica(&self, node_ip: IpAddr) -> RecoveryResult<()> {
+        info!(self.logger, "No replica running on local node {}", node_ip);
+        Ok(())
+    }
+
+    fn prepare_state_upload(&self, node_ip: IpAddr, checkpoint: &str) -> RecoveryResult<()> {
+        let checkpoints_dir = self.data_path(node_ip, NEW_IC_STATE).join(CHECKPOINTS);
+        create_dir(&checkpoints_dir)?;
+        if let Some(latest) = self.get_checkpoint_names(node_ip, ADMIN)?.into_iter().max() {
+            let src = self.data_path(node_ip, IC_CHECKPOINTS_PATH).join(latest);
+            self.copy(
+                vec![],
+                &format!("{}/", src.display()),
+                &format!("{}/", checkpoints_dir.join(checkpoint).display()),
+            )?;
+        }
+        Ok(())
+    }
+
+    fn replace_state(&self, node_ip: IpAddr) -> RecoveryResult<()> {
+        let upload_dir = self.data_path(node_ip, NEW_IC_STATE);
+        let ic_state_path = self.data_path(node_ip, IC_STATE);
+        remove_dir(&ic_state_path)?;
+        fs::rename(&upload_dir, &ic_state_path)
+            .map_err(|e| RecoveryError::dir_error(&upload_dir, e))
+    }
+
+    fn prepare_upload_dir(&self, node_ip: IpAddr, dir: &str) -> RecoveryResult<()> {
+        let dir = PathBuf::from(self.local_path(node_ip, dir));
+        remove_dir(&dir)?;
+        create_dir(&dir)
+    }
+
+    fn install_cup_and_registry(&self, node_ip: IpAddr, dir: &str) -> RecoveryResult<()> {
+        let dir = PathBuf::from(self.local_path(node_ip, dir));
+        let store = self.data_path(node_ip, IC_REGISTRY_LOCAL_STORE);
+        remove_dir(&store)?;
+        create_dir(&store)?;
+        let mut tar = Command::new("tar");
+        tar.arg("zxf")
+            .arg(dir.join(REGISTRY_TAR_FILE))
+            .arg("-C")
+            .arg(&store);
+        if let Some(res) = exec_cmd(&mut tar)? {
+            info!(self.logger, "{}", res);
+        }
+
+        let cup = self.data_path(node_ip, CUP_PATH);
+        if let Some(cups_dir) = cup.parent() {
+            create_dir(cups_dir)?;
+        }
+        fs::copy(dir.join(CUP_FILE), &cup).map_err(|e| RecoveryError::file_error(&cup, e))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,616 @@
+//! Declarative recovery plans.
+//!
+//! A recovery plan is a YAML or JSON file naming the recovery procedure to run
+//! together with all of its parameters, for example:
+//!
+//! ```yaml
+//! nns_url: https://ic0.app
+//! replica_version: 2f844c50765df0833c075b7340ac5f2dd9d5dc21
+//! recovery:
+//!   app_subnet_recovery:
+//!     subnet_id: ziu2q-il6zl-3654z-zcdg2-nbtx3-u2ba3-7yzey-flpky-aam7n-x53ip-uqe
+//!     pub_key: ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIB... recovery@dfinity
+//!     download_node: 2a00:fb01:400:42:5000:aaff:fea4:ae46
+//!     keep_downloaded_state: false
+//!     upload_node: 2a00:fb01:400:42:5000:aaff:fea4:ae47
+//!     replacement_nodes:
+//!       - mkxzl-5ckh6-7vl2s-nkqzu-5wbdp-wqjrw-jb6cl-6qxiq-g6oby-ld3uu-mqe
+//! steps: [Halt, DownloadState, ICReplay, ValidateReplayOutput, ProposeCup, UploadState, WaitForCUP, Unhalt]
+//! ```
+//!
+//! The keys of a procedure are the long command line options of its
+//! sub-command. Global options missing in the plan are taken from the command
+//! line, and all steps of the procedure are run if `steps` is missing.
+//!
+//! A plan is executed without prompting for parameters or consent. Every
+//! completed step is recorded in a journal in the recovery directory, so that a
+//! rerun of an interrupted plan resumes with the first step that did not
+//! complete. In dry-run mode, the description of every step, i.e. the commands
+//! it would run, is printed instead of executing it.
+use crate::app_subnet_recovery::{AppSubnetRecovery, AppSubnetRecoveryArgs};
+use crate::cli::{print_step, print_summary, read_input};
+use crate::error::{RecoveryError, RecoveryResult};
+use crate::file_sync_helper::{read_file, write_bytes};
+use crate::nns_recovery_failover_nodes::{NNSRecoveryFailoverNodes, NNSRecoveryFailoverNodesArgs};
+use crate::nns_recovery_same_nodes::{NNSRecoverySameNodes, NNSRecoverySameNodesArgs};
+use crate::recovery_iterator::RecoveryIterator;
+use crate::{NeuronArgs, RecoveryArgs};
+use clap::Parser;
+use ic_base_types::SubnetId;
+use ic_types::ReplicaVersion;
+use serde::{Deserialize, Serialize};
+use slog::{info, warn, Logger};
+use std::ffi::OsStr;
+use std::fmt::Debug;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use strum::IntoEnumIterator;
+use url::Url;
+
+/// The name of the journal file in the recovery directory.
+pub const JOURNAL_FILE: &str = "plan_journal.json";
+
+#[derive(Parser)]
+#[clap(version = "1.0")]
+pub struct RecoveryPlanArgs {
+    /// Path to the recovery plan (YAML, or JSON if the extension is `json`)
+    #[clap(parse(from_os_str))]
+    pub plan_file: PathBuf,
+
+    /// Print the commands of all steps instead of executing them
+    #[clap(long)]
+    pub dry_run: bool,
+}
+
+/// A recovery procedure together with all of its parameters.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RecoveryPlan {
+    /// The URL of an NNS entry point
+    pub nns_url: Option<Url>,
+    /// Replica version of the ic-admin binary
+    #[serde(default, deserialize_with = "crate::util::deserialize_opt_version")]
+    pub replica_version: Option<ReplicaVersion>,
+    /// The directory to perform recovery in
+    pub dir: Option<PathBuf>,
+    /// The path to a private key to be considered for SSH connections
+    pub key_file: Option<PathBuf>,
+    /// The directory holding local copies of the nodes' data, if nodes are not
+    /// accessed using SSH
+    pub local_nodes: Option<PathBuf>,
+    /// The neuron submitting the proposals of the recovery
+    pub neuron: Option<PlannedNeuron>,
+    /// The recovery procedure and its parameters
+    pub recovery: PlannedRecovery,
+    /// The names of the steps to run, which are run in the order of the
+    /// procedure
+    pub steps: Option<Vec<String>>,
+}
+
+/// The neuron submitting proposals. The HSM PIN is not part of the plan, it is
+/// read when the plan is executed.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PlannedNeuron {
+    pub slot: String,
+    pub neuron_id: String,
+    pub key_id: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlannedRecovery {
+    AppSubnetRecovery(AppSubnetRecoveryArgs),
+    NnsRecoveryFailoverNodes(Box<NNSRecoveryFailoverNodesArgs>),
+    NnsRecoverySameNodes(NNSRecoverySameNodesArgs),
+}
+
+impl PlannedRecovery {
+    fn subnet_id(&self) -> SubnetId {
+        match self {
+            PlannedRecovery::AppSubnetRecovery(args) => args.subnet_id,
+            PlannedRecovery::NnsRecoveryFailoverNodes(args) => args.subnet_id,
+            PlannedRecovery::NnsRecoverySameNodes(args) => args.subnet_id,
+        }
+    }
+}
+
+impl RecoveryPlan {
+    /// Reads the plan at `path`. Returns the plan together with its JSON
+    /// representation, which identifies the plan in the journal.
+    pub fn load(path: &Path) -> RecoveryResult<(Self, serde_json::Value)> {
+        let content = read_file(path)?;
+        let value: serde_json::Value = if path.extension() == Some(OsStr::new("json")) {
+            serde_json::from_str(&content).map_err(|e| e.to_string())
+        } else {
+            serde_yaml::from_str(&content).map_err(|e| e.to_string())
+        }
+        .map_err(|e| plan_error(path, e))?;
+        let plan = serde_json::from_value(value.clone()).map_err(|e| plan_error(path, e))?;
+        Ok((plan, value))
+    }
+}
+
+fn plan_error(path: &Path, e: impl ToString) -> RecoveryError {
+    RecoveryError::UnexpectedError(format!(
+        "Invalid recovery plan {:?}: {}",
+        path,
+        e.to_string()
+    ))
+}
+
+/// The steps of a recovery plan that completed.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Journal {
+    /// The plan the journal belongs to
+    pub plan: serde_json::Value,
+    /// The completed steps, in the order of their completion
+    pub completed: Vec<JournalEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub step: String,
+    pub description: String,
+    pub completed_at_secs: u64,
+}
+
+impl Journal {
+    /// Opens the journal at `path`, or starts a new one if there is none. Fails
+    /// if the journal belongs to a different plan.
+    pub fn open(path: &Path, plan: &serde_json::Value) -> RecoveryResult<Self> {
+        if !path.exists() {
+            return Ok(Journal {
+                plan: plan.clone(),
+                completed: vec![],
+            });
+        }
+        let journal: Journal = serde_json::from_str(&read_file(path)?).map_err(|e| {
+            RecoveryError::UnexpectedError(format!("Invalid journal {:?}: {}", path, e))
+        })?;
+        if journal.plan != *plan {
+            return Err(RecoveryError::UnexpectedError(format!(
+                "The journal {:?} belongs to a different recovery plan, remove it to start over",
+                path
+            )));
+        }
+        Ok(journal)
+    }
+
+    pub fn is_completed(&self, step: &str) -> bool {
+        self.completed.iter().any(|entry| entry.step == step)
+    }
+
+    /// Records the completion of `step` and persists the journal at `path`.
+    pub fn record(&mut self, path: &Path, step: String, description: String) -> RecoveryResult<()> {
+        let completed_at_secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        self.completed.push(JournalEntry {
+            step,
+            description,
+            completed_at_secs,
+        });
+        let bytes = serde_json::to_vec_pretty(self).map_err(|e| {
+            RecoveryError::UnexpectedError(format!("Failed to serialize the journal: {}", e))
+        })?;
+        let tmp_path = path.with_extension("tmp");
+        write_bytes(&tmp_path, bytes)?;
+        fs::rename(&tmp_path, path).map_err(|e| RecoveryError::file_error(path, e))
+    }
+}
+
+/// Executes the recovery plan at `plan_file`. Global arguments missing in the
+/// plan are taken from `args`.
+pub fn execute_plan(
+    logger: &Logger,
+    args: RecoveryArgs,
+    plan_file: &Path,
+    dry_run: bool,
+    test: bool,
+) -> RecoveryResult<()> {
+    let (plan, plan_value) = RecoveryPlan::load(plan_file)?;
+    let args = RecoveryArgs {
+        dir: plan.dir.unwrap_or(args.dir),
+        nns_url: plan.nns_url.unwrap_or(args.nns_url),
+        replica_version: plan.replica_version.or(args.replica_version),
+        key_file: plan.key_file.or(args.key_file),
+        local_nodes: plan.local_nodes.or(args.local_nodes),
+    };
+    print_summary(logger, &args, plan.recovery.subnet_id());
+
+    let journal_path = args.dir.join("recovery").join(JOURNAL_FILE);
+    let mut journal = Journal::open(&journal_path, &plan_value)?;
+    if !journal.completed.is_empty() {
+        info!(
+            logger,
+            "Resuming the recovery plan after {} completed steps",
+            journal.completed.len()
+        );
+    }
+
+    let neuron_args = plan.neuron.map(|neuron| NeuronArgs {
+        // The commands printed in a dry run must not contain the PIN.
+        dfx_hsm_pin: if dry_run {
+            "<DFX HSM PIN>".to_string()
+        } else {
+            read_input(logger, "Enter DFX HSM PIN: ")
+        },
+        slot: neuron.slot,
+        neuron_id: neuron.neuron_id,
+        key_id: neuron.key_id,
+    });
+    let mut runner = PlanRunner {
+        logger,
+        steps: plan.steps.as_deref(),
+        journal: &mut journal,
+        journal_path: &journal_path,
+        dry_run,
+    };
+    match plan.recovery {
+        PlannedRecovery::AppSubnetRecovery(subnet_args) => {
+            let recovery =
+                AppSubnetRecovery::new(logger.clone(), args, neuron_args, subnet_args, false);
+            runner.run(&recovery)
+        }
+        PlannedRecovery::NnsRecoveryFailoverNodes(subnet_args) => {
+            let recovery = NNSRecoveryFailoverNodes::new(
+                logger.clone(),
+                args,
+                neuron_args,
+                *subnet_args,
+                false,
+            );
+            runner.run(&recovery)
+        }
+        PlannedRecovery::NnsRecoverySameNodes(subnet_args) => {
+            let recovery =
+                NNSRecoverySameNodes::new(logger.clone(), args, subnet_args, test, false);
+            runner.run(&recovery)
+        }
+    }
+}
+
+struct PlanRunner<'a> {
+    logger: &'a Logger,
+    steps: Option<&'a [String]>,
+    journal: &'a mut Journal,
+    journal_path: &'a Path,
+    dry_run: bool,
+}
+
+impl PlanRunner<'_> {
+    /// Runs the planned steps of `recovery` that did not complete yet and stops
+    /// at the first failing step.
+    fn run<T, R>(&mut self, recovery: &R) -> RecoveryResult<()>
+    where
+        T: IntoEnumIterator + Copy + Debug,
+        R: RecoveryIterator<T>,
+    {
+        for step_type in self.select_steps::<T>()? {
+            let name = format!("{:?}", step_type);
+            print_step(self.logger, &name);
+            if self.journal.is_completed(&name) {
+                info!(self.logger, "Step {} already completed, skipping.", name);
+                continue;
+            }
+            match recovery.get_step_impl(step_type) {
+                Ok(step) => {
+                    let description = step.descr();
+                    info!(self.logger, "{}", description);
+                    if self.dry_run {
+                        continue;
+                    }
+                    step.exec()?;
+                    self.journal.record(self.journal_path, name, description)?;
+                }
+                Err(RecoveryError::StepSkipped) => {
+                    info!(self.logger, "Skipping step {}", name);
+                }
+                // Some steps depend on the outcome of the previous ones.
+                Err(e) if self.dry_run => {
+                    warn!(
+                        self.logger,
+                        "Step {} can only be generated after the previous steps ran: {}", name, e
+                    );
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the steps of the procedure listed in the plan, in the order of
+    /// the procedure.
+    fn select_steps<T: IntoEnumIterator + Debug>(&self) -> RecoveryResult<Vec<T>> {
+        let all_steps = T::iter().collect::<Vec<_>>();
+        let names = match self.steps {
+            Some(names) => names,
+            None => return Ok(all_steps),
+        };
+        let step_names = all_steps
+            .iter()
+            .map(|step| format!("{:?}", step))
+            .collect::<Vec<_>>();
+        if let Some(unknown) = names.iter().find(|name| !step_names.contains(name)) {
+            return Err(RecoveryError::UnexpectedError(format!(
+                "Unknown step {} in the recovery plan, the procedure consists of {:?}",
+                unknown, step_names
+            )));
+        }
+        Ok(all_steps
+            .into_iter()
+            .zip(step_names)
+            .filter(|(_, name)| names.contains(name))
+            .map(|(step, _)| step)
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::steps::Step;
+    use slog::o;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use strum_macros::EnumIter;
+    use tempfile::TempDir;
+
+    const PLAN: &str = r#"
+nns_url: https://ic0.app
+replica_version: 2f844c50765df0833c075b7340ac5f2dd9d5dc21
+recovery:
+  app_subnet_recovery:
+    subnet_id: ziu2q-il6zl-3654z-zcdg2-nbtx3-u2ba3-7yzey-flpky-aam7n-x53ip-uqe
+    download_node: 2a00:fb01:400:42:5000:aaff:fea4:ae46
+    keep_downloaded_state: false
+steps: [Halt, DownloadState, Unhalt]
+"#;
+
+    #[derive(Debug, Copy, Clone, EnumIter)]
+    enum TestStep {
+        First,
+        Second,
+        Third,
+    }
+
+    /// A step recording its execution, which fails if requested.
+    struct TestStepImpl {
+        name: String,
+        executed: Rc<RefCell<Vec<String>>>,
+        fail: bool,
+    }
+
+    impl Step for TestStepImpl {
+        fn descr(&self) -> String {
+            format!("Run {}", self.name)
+        }
+
+        fn exec(&self) -> RecoveryResult<()> {
+            if self.fail {
+                return Err(RecoveryError::UnexpectedError(format!(
+                    "{} failed",
+                    self.name
+                )));
+            }
+            self.executed.borrow_mut().push(self.name.clone());
+            Ok(())
+        }
+    }
+
+    struct TestRecovery {
+        logger: Logger,
+        step_iterator: Box<dyn Iterator<Item = TestStep>>,
+        executed: Rc<RefCell<Vec<String>>>,
+        failing_step: Option<String>,
+    }
+
+    impl TestRecovery {
+        fn new(failing_step: Option<&str>) -> Self {
+            Self {
+                logger: Logger::root(slog::Discard, o!()),
+                step_iterator: Box::new(TestStep::iter()),
+                executed: Default::default(),
+                failing_step: failing_step.map(String::from),
+            }
+        }
+
+        fn executed(&self) -> Vec<String> {
+            self.executed.borrow().clone()
+        }
+    }
+
+    impl RecoveryIterator<TestStep> for TestRecovery {
+        fn get_step_iterator(&mut self) -> &mut Box<dyn Iterator<Item = TestStep>> {
+            &mut self.step_iterator
+        }
+
+        fn get_step_impl(&self, step_type: TestStep) -> RecoveryResult<Box<dyn Step>> {
+            let name = format!("{:?}", step_type);
+            Ok(Box::new(TestStepImpl {
+                fail: self.failing_step.as_ref() == Some(&name),
+                name,
+                executed: self.executed.clone(),
+            }))
+        }
+
+        fn interactive(&self) -> bool {
+            false
+        }
+
+        fn read_step_params(&mut self, _step_type: TestStep) {}
+
+        fn get_logger(&self) -> &Logger {
+            &self.logger
+        }
+    }
+
+    fn run(
+        recovery: &TestRecovery,
+        steps: Option<Vec<String>>,
+        journal_path: &Path,
+        plan: &serde_json::Value,
+        dry_run: bool,
+    ) -> RecoveryResult<()> {
+        let logger = Logger::root(slog::Discard, o!());
+        let mut journal = Journal::open(journal_path, plan)?;
+        let mut runner = PlanRunner {
+            logger: &logger,
+            steps: steps.as_deref(),
+            journal: &mut journal,
+            journal_path,
+            dry_run,
+        };
+        runner.run(recovery)
+    }
+
+    fn write_plan(dir: &TempDir, name: &str, content: &str) -> PathBuf {
+        let path = dir.path().join(name);
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn yaml_and_json_plans_are_parsed() {
+        let dir = TempDir::new().unwrap();
+        let (plan, value) = RecoveryPlan::load(&write_plan(&dir, "plan.yaml", PLAN)).unwrap();
+        assert_eq!(plan.nns_url, Some(Url::parse("https://ic0.app").unwrap()));
+        assert_eq!(
+            plan.steps,
+            Some(vec![
+                "Halt".to_string(),
+                "DownloadState".to_string(),
+                "Unhalt".to_string()
+            ])
+        );
+        assert!(plan.neuron.is_none());
+        match plan.recovery {
+            PlannedRecovery::AppSubnetRecovery(args) => {
+                assert_eq!(
+                    args.subnet_id.to_string(),
+                    "ziu2q-il6zl-3654z-zcdg2-nbtx3-u2ba3-7yzey-flpky-aam7n-x53ip-uqe"
+                );
+                assert_eq!(args.keep_downloaded_state, Some(false));
+                assert!(args.upload_node.is_none());
+            }
+            _ => panic!("Expected an app subnet recovery"),
+        }
+
+        // The same plan in JSON has the same identity in the journal.
+        let json = serde_json::to_string(&value).unwrap();
+        let (_, json_value) = RecoveryPlan::load(&write_plan(&dir, "plan.json", &json)).unwrap();
+        assert_eq!(json_value, value);
+    }
+
+    #[test]
+    fn invalid_plans_are_rejected() {
+        let dir = TempDir::new().unwrap();
+        let unknown_field = format!("{}unknown: 1\n", PLAN);
+        assert!(RecoveryPlan::load(&write_plan(&dir, "unknown.yaml", &unknown_field)).is_err());
+        let unknown_argument = PLAN.replace("keep_downloaded_state", "keep_state");
+        assert!(RecoveryPlan::load(&write_plan(&dir, "argument.yaml", &unknown_argument)).is_err());
+        let missing_recovery = "nns_url: https://ic0.app\n";
+        assert!(RecoveryPlan::load(&write_plan(&dir, "missing.yaml", missing_recovery)).is_err());
+    }
+
+    #[test]
+    fn planned_steps_run_in_the_order_of_the_procedure() {
+        let dir = TempDir::new().unwrap();
+        let journal_path = dir.path().join(JOURNAL_FILE);
+        let plan = serde_json::json!({ "plan": 1 });
+        let recovery = TestRecovery::new(None);
+        let steps = vec!["Third".to_string(), "First".to_string()];
+
+        run(&recovery, Some(steps), &journal_path, &plan, false).unwrap();
+
+        assert_eq!(recovery.executed(), vec!["First", "Third"]);
+        let journal = Journal::open(&journal_path, &plan).unwrap();
+        assert!(journal.is_completed("First"));
+        assert!(!journal.is_completed("Second"));
+        assert!(journal.is_completed("Third"));
+    }
+
+    #[test]
+    fn unknown_steps_are_rejected() {
+        let dir = TempDir::new().unwrap();
+        let recovery = TestRecovery::new(None);
+        let steps = vec!["First".to_string(), "Fourth".to_string()];
+
+        let result = run(
+            &recovery,
+            Some(steps),
+            &dir.path().join(JOURNAL_FILE),
+            &serde_json::json!({}),
+            false,
+        );
+
+        assert!(result.is_err());
+        assert!(recovery.executed().is_empty());
+    }
+
+    #[test]
+    fn interrupted_plan_resumes_from_the_journal() {
+        let dir = TempDir::new().unwrap();
+        let journal_path = dir.path().join(JOURNAL_FILE);
+        let plan = serde_json::json!({ "plan": 1 });
+
+        let recovery = TestRecovery::new(Some("Second"));
+        assert!(run(&recovery, None, &journal_path, &plan, false).is_err());
+        assert_eq!(recovery.executed(), vec!["First"]);
+
+        let recovery = TestRecovery::new(None);
+        run(&recovery, None, &journal_path, &plan, false).unwrap();
+        assert_eq!(recovery.executed(), vec!["Second", "Third"]);
+
+        let journal = Journal::open(&journal_path, &plan).unwrap();
+        let completed = journal
+            .completed
+            .iter()
+            .map(|entry| entry.step.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(completed, vec!["First", "Second", "Third"]);
+        assert_eq!(journal.completed[1].description, "Run Second");
+    }
+
+    #[test]
+    fn journal_of_a_different_plan_is_rejected() {
+        let dir = TempDir::new().unwrap();
+        let journal_path = dir.path().join(JOURNAL_FILE);
+        let recovery = TestRecovery::new(None);
+        run(
+            &recovery,
+            None,
+            &journal_path,
+            &serde_json::json!({ "plan": 1 }),
+            false,
+        )
+        .unwrap();
+
+        let recovery = TestRecovery::new(None);
+        let result = run(
+            &recovery,
+            None,
+            &journal_path,
+            &serde_json::json!({ "plan": 2 }),
+            false,
+        );
+
+        assert!(result.is_err());
+        assert!(recovery.executed().is_empty());
+    }
+
+    #[test]
+    fn dry_run_executes_and_records_nothing() {
+        let dir = TempDir::new().unwrap();
+        let journal_path = dir.path().join(JOURNAL_FILE);
+        let recovery = TestRecovery::new(None);
+
+        run(&recovery, None, &journal_path, &serde_json::json!({}), true).unwrap();
+
+        assert!(recovery.executed().is_empty());
+        assert!(!journal_path.exists());
+    }
+}
@@ -31,6 +31,9 @@ pub async fn replay(
         replay_until_height: None,
         subcmd,
         data_root: Some(data_root),
+        step: None,
+        step_canisters: vec![],
+        dump_instructions: false,
     };
     // Since replay output needs to be persisted anyway in case the recovery process
     // is restarted, we avoid declaring a return value and moving out of the
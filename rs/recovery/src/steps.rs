@@ -2,11 +2,14 @@ use crate::admin_helper::IcAdmin;
 use crate::command_helper::exec_cmd;
 use crate::error::{RecoveryError, RecoveryResult};
 use crate::file_sync_helper::{remove_dir, rsync};
+use crate::node_access::{
+    get_install_cup_and_registry_commands, NodeAccess, CUP_FILE, REGISTRY_TAR_FILE,
+};
 use crate::ssh_helper::SshHelper;
 use crate::util::{block_on, parse_hex_str};
 use crate::{
-    get_member_ips, get_node_heights_from_metrics, replay_helper, ADMIN, CHECKPOINTS, IC_STATE,
-    NEW_IC_STATE, READONLY,
+    get_member_ips, get_node_heights_from_metrics, replay_helper, ADMIN, CHECKPOINTS, NEW_IC_STATE,
+    READONLY,
 };
 use crate::{
     Recovery, IC_CHECKPOINTS_PATH, IC_DATA_PATH, IC_JSON5_PATH, IC_REGISTRY_LOCAL_STORE,
@@ -61,8 +64,7 @@ pub struct DownloadIcStateStep {
     pub target: String,
     pub working_dir: String,
     pub keep_downloaded_state: bool,
-    pub require_confirmation: bool,
-    pub key_file: Option<PathBuf>,
+    pub node_access: Arc<dyn NodeAccess>,
 }
 
 impl Step for DownloadIcStateStep {
@@ -83,21 +85,14 @@ impl Step for DownloadIcStateStep {
     }
 
     fn exec(&self) -> RecoveryResult<()> {
-        let account = if self.try_readonly {
+        let mut account = if self.try_readonly {
             READONLY.to_string()
         } else {
             ADMIN.to_string()
         };
-        let mut ssh_helper = SshHelper::new(
-            self.logger.clone(),
-            account,
-            self.node_ip,
-            self.require_confirmation,
-            self.key_file.clone(),
-        );
         let mut access_granted = false;
         for _ in 0..20 {
-            if ssh_helper.can_connect() {
+            if self.node_access.can_connect(self.node_ip, &account) {
                 access_granted = true;
                 break;
             }
@@ -105,35 +100,24 @@ impl Step for DownloadIcStateStep {
             thread::sleep(time::Duration::from_secs(5));
         }
         if !access_granted {
-            ssh_helper.account = ADMIN.to_string();
-            if !ssh_helper.can_connect() {
+            account = ADMIN.to_string();
+            if !self.node_access.can_connect(self.node_ip, &account) {
                 return Err(RecoveryError::invalid_output_error(
                     "SSH access denied".to_string(),
                 ));
             }
         }
 
-        info!(
-            self.logger,
-            "Continuing with account: {}", ssh_helper.account
-        );
-
-        let data_src = format!("{}@[{}]:{}", ssh_helper.account, self.node_ip, IC_DATA_PATH);
-        let config_src = format!(
-            "{}@[{}]:{}",
-            ssh_helper.account, self.node_ip, IC_JSON5_PATH
-        );
+        info!(self.logger, "Continuing with account: {}", account);
 
+        // Only download the latest checkpoint
         let mut excludes = IC_STATE_EXCLUDES.to_vec();
-        let res = ssh_helper
-            .ssh(format!(
-                r"echo $(ls {}/{} | sort | awk 'n>=1 {{ print a[n%1] }} {{ a[n++%1]=$0 }}');",
-                IC_DATA_PATH, IC_CHECKPOINTS_PATH
-            ))?
-            .unwrap_or_default();
-        res.trim().split(' ').for_each(|cp| {
-            excludes.push(cp);
-        });
+        let mut checkpoints = self
+            .node_access
+            .get_checkpoint_names(self.node_ip, &account)?;
+        checkpoints.sort();
+        checkpoints.pop();
+        excludes.extend(checkpoints.iter().map(String::as_str));
 
         let target = if self.keep_downloaded_state {
             &self.target
@@ -141,23 +125,11 @@ impl Step for DownloadIcStateStep {
             &self.working_dir
         };
 
-        rsync(
-            &self.logger,
-            excludes,
-            &data_src,
-            target,
-            self.require_confirmation,
-            self.key_file.as_ref(),
-        )?;
+        self.node_access
+            .download(self.node_ip, &account, excludes, IC_DATA_PATH, target)?;
 
-        rsync(
-            &self.logger,
-            vec![],
-            &config_src,
-            target,
-            self.require_confirmation,
-            self.key_file.as_ref(),
-        )?;
+        self.node_access
+            .download(self.node_ip, &account, vec![], IC_JSON5_PATH, target)?;
 
         if self.keep_downloaded_state {
             rsync(
@@ -336,8 +308,7 @@ pub struct UploadAndRestartStep {
     pub node_ip: IpAddr,
     pub work_dir: PathBuf,
     pub data_src: PathBuf,
-    pub require_confirmation: bool,
-    pub key_file: Option<PathBuf>,
+    pub node_access: Arc<dyn NodeAccess>,
 }
 
 impl Step for UploadAndRestartStep {
@@ -346,15 +317,6 @@ impl Step for UploadAndRestartStep {
     }
 
     fn exec(&self) -> RecoveryResult<()> {
-        let account = ADMIN;
-        let ssh_helper = SshHelper::new(
-            self.logger.clone(),
-            account.to_string(),
-            self.node_ip,
-            self.require_confirmation,
-            self.key_file.clone(),
-        );
-
         let checkpoint_path = self.data_src.join(CHECKPOINTS);
         let checkpoints = Recovery::get_checkpoint_names(&checkpoint_path)?;
 
@@ -377,72 +339,26 @@ impl Step for UploadAndRestartStep {
             )));
         }
 
-        let ic_checkpoints_path = format!("{}/{}", IC_DATA_PATH, IC_CHECKPOINTS_PATH);
-        // upload directory to create
-        let upload_dir = format!("{}/{}", IC_DATA_PATH, NEW_IC_STATE);
-        // path of highest checkpoint on upload node
-        let copy_from = format!(
-            "{}/$(ls {} | sort | tail -1)",
-            ic_checkpoints_path, ic_checkpoints_path
-        );
-        // path and name of checkpoint after replay
-        let copy_to = format!("{}/{}/{}", upload_dir, CHECKPOINTS, max_checkpoint);
-        let cp = format!("sudo cp -r {} {}", copy_from, copy_to);
-
         info!(
             self.logger,
             "Creating remote directory and copying previous checkpoint..."
         );
-        if let Some(res) = ssh_helper.ssh(format!(
-            "sudo mkdir -p {}/{}; {}; sudo chown -R {} {};",
-            upload_dir, CHECKPOINTS, cp, account, upload_dir
-        ))? {
-            info!(self.logger, "{}", res);
-        }
+        self.node_access
+            .prepare_state_upload(self.node_ip, &max_checkpoint)?;
 
-        let target = format!("{}@[{}]:{}/", account, self.node_ip, upload_dir);
+        let target = format!("{}/{}/", IC_DATA_PATH, NEW_IC_STATE);
         let src = format!("{}/", self.data_src.display());
         info!(self.logger, "Uploading state...");
-        rsync(
-            &self.logger,
+        self.node_access.upload(
+            self.node_ip,
+            ADMIN,
             IC_STATE_EXCLUDES.to_vec(),
             &src,
             &target,
-            self.require_confirmation,
-            self.key_file.as_ref(),
         )?;
 
-        let ic_state_path = format!("{}/{}", IC_DATA_PATH, IC_STATE);
         info!(self.logger, "Restarting replica...");
-        let mut replace_state = String::new();
-        replace_state.push_str("sudo systemctl stop ic-replica;");
-        replace_state.push_str(&format!(
-            "sudo chmod -R --reference={} {};",
-            ic_state_path, upload_dir
-        ));
-        replace_state.push_str(&format!(
-            "sudo chown -R --reference={} {};",
-            ic_state_path, upload_dir
-        ));
-        replace_state.push_str(&format!("sudo rm -r {};", ic_state_path));
-        replace_state.push_str(&format!("sudo mv {} {};", upload_dir, ic_state_path));
-        replace_state.push_str(&format!(
-            r"sudo find {} -type f -exec chmod a-x {{}} \;;",
-            ic_state_path
-        ));
-        replace_state.push_str(&format!(
-            r"sudo find {} -type f -exec chmod go+r {{}} \;;",
-            ic_state_path
-        ));
-        // Note that on older versions of IC-OS this service does not exist.
-        // So try this operation, but ignore possible failure if service
-        // does not exist on the affected version.
-        replace_state.push_str("(sudo systemctl restart setup-permissions || true);");
-        replace_state.push_str("sudo systemctl start ic-replica;");
-        replace_state.push_str("sudo systemctl status ic-replica;");
-
-        ssh_helper.ssh(replace_state)?;
-        Ok(())
+        self.node_access.replace_state(self.node_ip)
     }
 }
 
@@ -489,10 +405,8 @@ impl Step for CleanupStep {
 }
 
 pub struct StopReplicaStep {
-    pub logger: Logger,
     pub node_ip: IpAddr,
-    pub require_confirmation: bool,
-    pub key_file: Option<PathBuf>,
+    pub node_access: Arc<dyn NodeAccess>,
 }
 
 impl Step for StopReplicaStep {
@@ -501,15 +415,7 @@ impl Step for StopReplicaStep {
     }
 
     fn exec(&self) -> RecoveryResult<()> {
-        let ssh_helper = SshHelper::new(
-            self.logger.clone(),
-            ADMIN.to_string(),
-            self.node_ip,
-            self.require_confirmation,
-            self.key_file.clone(),
-        );
-        ssh_helper.ssh("sudo systemctl stop ic-replica".to_string())?;
-        Ok(())
+        self.node_access.stop_replica(self.node_ip)
     }
 }
 
@@ -617,33 +523,13 @@ pub struct UploadCUPAndTar {
     pub logger: Logger,
     pub registry_client: Arc<RegistryClientImpl>,
     pub subnet_id: SubnetId,
-    pub require_confirmation: bool,
-    pub key_file: Option<PathBuf>,
+    pub node_access: Arc<dyn NodeAccess>,
     pub work_dir: PathBuf,
 }
 
 impl UploadCUPAndTar {
     pub fn get_restart_commands(&self) -> String {
-        format!(
-            r#"
-cd {};
-OWNER_UID=$(sudo stat -c '%u' /var/lib/ic/data/ic_registry_local_store);
-GROUP_UID=$(sudo stat -c '%g' /var/lib/ic/data/ic_registry_local_store);
-mkdir ic_registry_local_store;
-tar zxf ic_registry_local_store.tar.gz -C ic_registry_local_store;
-sudo chown -R "$OWNER_UID:$GROUP_UID" ic_registry_local_store;
-OWNER_UID=$(sudo stat -c '%u' /var/lib/ic/data/cups);
-GROUP_UID=$(sudo stat -c '%g' /var/lib/ic/data/cups);
-sudo chown -R "$OWNER_UID:$GROUP_UID" cup.proto;
-sudo systemctl stop ic-replica;
-sudo rsync -a --delete ic_registry_local_store/ /var/lib/ic/data/ic_registry_local_store/;
-sudo cp cup.proto /var/lib/ic/data/cups/cup.types.v1.CatchUpPackage.pb;
-sudo systemctl restart setup-permissions || true ;
-sudo systemctl start ic-replica;
-sudo systemctl status ic-replica;
-"#,
-            UploadCUPAndTar::get_upload_dir_name(),
-        )
+        get_install_cup_and_registry_commands(&UploadCUPAndTar::get_upload_dir_name())
     }
 
     pub fn get_upload_dir_name() -> String {
@@ -661,50 +547,37 @@ impl Step for UploadCUPAndTar {
 
         ips.into_iter()
             .map(|ip| {
-                let ssh_helper = SshHelper::new(
-                    self.logger.clone(),
-                    ADMIN.to_string(),
-                    ip,
-                    self.require_confirmation,
-                    self.key_file.clone(),
-                );
-
-                if !ssh_helper.can_connect() {
+                if !self.node_access.can_connect(ip, ADMIN) {
                     info!(
                         self.logger,
                         "No admin access to: {}, skipping upload...", ip
                     );
-                    return Ok(None);
+                    return Ok(());
                 }
 
                 info!(self.logger, "Uploading to {}", ip);
                 let upload_dir = UploadCUPAndTar::get_upload_dir_name();
-                ssh_helper.ssh(format!(
-                    "sudo rm -rf {} && mkdir {}",
-                    upload_dir, upload_dir
-                ))?;
+                self.node_access.prepare_upload_dir(ip, &upload_dir)?;
 
-                let target = format!("{}@[{}]:{}/", ADMIN, ip, upload_dir);
+                let target = format!("{}/", upload_dir);
 
-                rsync(
-                    &self.logger,
+                self.node_access.upload(
+                    ip,
+                    ADMIN,
                     vec![],
-                    &format!("{}/cup.proto", self.work_dir.display()),
+                    &format!("{}/{}", self.work_dir.display(), CUP_FILE),
                     &target,
-                    self.require_confirmation,
-                    self.key_file.as_ref(),
                 )?;
 
-                rsync(
-                    &self.logger,
+                self.node_access.upload(
+                    ip,
+                    ADMIN,
                     vec![],
-                    &format!("{}/ic_registry_local_store.tar.gz", self.work_dir.display()),
+                    &format!("{}/{}", self.work_dir.display(), REGISTRY_TAR_FILE),
                     &target,
-                    self.require_confirmation,
-                    self.key_file.as_ref(),
                 )?;
 
-                ssh_helper.ssh(self.get_restart_commands())
+                self.node_access.install_cup_and_registry(ip, &upload_dir)
             })
             .collect::<RecoveryResult<Vec<_>>>()?;
 
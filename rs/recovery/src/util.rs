@@ -1,5 +1,8 @@
 use crate::error::{RecoveryError, RecoveryResult};
 use ic_base_types::{NodeId, PrincipalId, SubnetId};
+use ic_types::ReplicaVersion;
+use serde::{de::Error, Deserialize, Deserializer};
+use std::convert::TryFrom;
 use std::future::Future;
 use std::str::FromStr;
 use tokio::runtime::Runtime;
@@ -29,3 +32,38 @@ pub fn node_id_from_str(s: &str) -> Result<NodeId, String> {
         .map_err(|e| format!("Unable to parse node_id {:?}", e))
         .map(NodeId::from)
 }
+
+// The following functions deserialize the textual representations accepted on
+// the command line, so that recovery arguments can be read from a plan file.
+
+pub fn deserialize_subnet_id<'de, D: Deserializer<'de>>(d: D) -> Result<SubnetId, D::Error> {
+    subnet_id_from_str(&String::deserialize(d)?).map_err(D::Error::custom)
+}
+
+pub fn deserialize_opt_subnet_id<'de, D: Deserializer<'de>>(
+    d: D,
+) -> Result<Option<SubnetId>, D::Error> {
+    Option::<String>::deserialize(d)?
+        .map(|s| subnet_id_from_str(&s).map_err(D::Error::custom))
+        .transpose()
+}
+
+pub fn deserialize_opt_node_ids<'de, D: Deserializer<'de>>(
+    d: D,
+) -> Result<Option<Vec<NodeId>>, D::Error> {
+    Option::<Vec<String>>::deserialize(d)?
+        .map(|ids| {
+            ids.iter()
+                .map(|s| node_id_from_str(s).map_err(D::Error::custom))
+                .collect()
+        })
+        .transpose()
+}
+
+pub fn deserialize_opt_version<'de, D: Deserializer<'de>>(
+    d: D,
+) -> Result<Option<ReplicaVersion>, D::Error> {
+    Option::<String>::deserialize(d)?
+        .map(|s| ReplicaVersion::try_from(s).map_err(D::Error::custom))
+        .transpose()
+}
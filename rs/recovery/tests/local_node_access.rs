@@ -0,0 +1,336 @@
+use ic_base_types::{NodeId, PrincipalId, SubnetId};
+use ic_protobuf::registry::node::v1::{ConnectionEndpoint, NodeRecord};
+use ic_protobuf::registry::subnet::v1::SubnetRecord;
+use ic_recovery::app_subnet_recovery::{self, AppSubnetRecovery, AppSubnetRecoveryArgs};
+use ic_recovery::error::{RecoveryError, RecoveryResult};
+use ic_recovery::nns_recovery_same_nodes::{self, NNSRecoverySameNodes, NNSRecoverySameNodesArgs};
+use ic_recovery::recovery_iterator::RecoveryIterator;
+use ic_recovery::steps::Step;
+use ic_recovery::{replay_helper, Recovery, RecoveryArgs};
+use ic_registry_keys::{make_node_record_key, make_subnet_record_key};
+use ic_registry_local_store::{KeyMutation, LocalStoreImpl, LocalStoreWriter};
+use ic_replay::player::StateParams;
+use ic_types::{Height, RegistryVersion};
+use prost::Message;
+use slog::{o, Logger};
+use std::fs;
+use std::net::IpAddr;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use strum::IntoEnumIterator;
+use tempfile::TempDir;
+
+const NODE_IP: &str = "2a00:fb01:400:42::1";
+
+const OLD_CHECKPOINT: &str = "0000000000000064";
+const LATEST_CHECKPOINT: &str = "00000000000000c8";
+const REPLAYED_CHECKPOINT: &str = "000000000000012c";
+
+fn write_file(path: &Path, content: &str) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, content).unwrap();
+}
+
+/// Creates the data of a node holding two checkpoints in `nodes_dir`.
+fn create_node_fixture(nodes_dir: &Path, node_ip: IpAddr) {
+    let node_dir = nodes_dir.join(node_ip.to_string());
+    let checkpoints = node_dir.join("data/ic_state/checkpoints");
+    write_file(
+        &checkpoints
+            .join(OLD_CHECKPOINT)
+            .join("system_metadata.pbuf"),
+        "old",
+    );
+    write_file(
+        &checkpoints
+            .join(LATEST_CHECKPOINT)
+            .join("system_metadata.pbuf"),
+        "latest",
+    );
+    write_file(
+        &node_dir.join("data/ic_state/tip/system_metadata.pbuf"),
+        "tip",
+    );
+    write_file(&node_dir.join("data/ic_consensus_pool/data.mdb"), "pool");
+    write_file(
+        &node_dir.join("data/ic_registry_local_store/0000000000/00/00/01.pb"),
+        "registry",
+    );
+    write_file(&node_dir.join("ic.json5"), "{}");
+}
+
+fn recovery_args(dir: &TempDir, nodes_dir: &Path) -> RecoveryArgs {
+    RecoveryArgs {
+        dir: dir.path().to_path_buf(),
+        nns_url: "http://localhost:8080".parse().unwrap(),
+        replica_version: None,
+        key_file: None,
+        local_nodes: Some(nodes_dir.to_path_buf()),
+    }
+}
+
+fn new_recovery(dir: &TempDir, nodes_dir: &Path) -> Recovery {
+    let logger = Logger::root(slog::Discard, o!());
+    Recovery::new(logger, recovery_args(dir, nodes_dir), None, false).unwrap()
+}
+
+/// Installs an `ic-admin` in the binary directory of the recovery in `dir`
+/// that appends its arguments to `log` instead of submitting proposals.
+fn install_fake_ic_admin(dir: &TempDir, log: &Path) {
+    let ic_admin = dir.path().join("recovery/binaries/ic-admin");
+    write_file(
+        &ic_admin,
+        &format!("#!/bin/sh\necho \"$@\" >> {}\n", log.display()),
+    );
+    fs::set_permissions(&ic_admin, fs::Permissions::from_mode(0o755)).unwrap();
+}
+
+/// Writes a registry local store to `path` in which `subnet_id` consists of a
+/// single node listening on `node_ip`.
+fn write_registry_local_store(path: &Path, subnet_id: SubnetId, node_ip: IpAddr) {
+    let node_id = NodeId::from(PrincipalId::new_node_test_id(1));
+    let subnet_record = SubnetRecord {
+        membership: vec![node_id.get().to_vec()],
+        ..Default::default()
+    };
+    let node_record = NodeRecord {
+        http: Some(ConnectionEndpoint {
+            ip_addr: node_ip.to_string(),
+            port: 8080,
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    LocalStoreImpl::new(path)
+        .store(
+            RegistryVersion::from(1),
+            vec![
+                KeyMutation {
+                    key: make_subnet_record_key(subnet_id),
+                    value: Some(subnet_record.encode_to_vec()),
+                },
+                KeyMutation {
+                    key: make_node_record_key(node_id),
+                    value: Some(node_record.encode_to_vec()),
+                },
+            ],
+        )
+        .unwrap();
+}
+
+/// Stands in for `ic-replay` on the state downloaded by `recovery`: turns the
+/// latest checkpoint into one at height 300 and writes the replay output.
+fn simulate_replay(recovery: &Recovery) -> StateParams {
+    let checkpoints = recovery.work_dir.join("data/ic_state/checkpoints");
+    fs::rename(
+        checkpoints.join(LATEST_CHECKPOINT),
+        checkpoints.join(REPLAYED_CHECKPOINT),
+    )
+    .unwrap();
+    write_file(
+        &checkpoints
+            .join(REPLAYED_CHECKPOINT)
+            .join("system_metadata.pbuf"),
+        "replayed",
+    );
+    let state_params = StateParams {
+        height: Height::from(300),
+        hash: "ab".repeat(32),
+        registry_version: RegistryVersion::from(1),
+        invalid_artifacts: vec![],
+    };
+    write_file(
+        &recovery.work_dir.join(replay_helper::OUTPUT_FILE_NAME),
+        &serde_json::to_string(&state_params).unwrap(),
+    );
+    state_params
+}
+
+/// Executes the step of a recovery procedure, unless the procedure skips it.
+fn exec_step(step: RecoveryResult<Box<dyn Step>>) {
+    match step {
+        Ok(step) => step.exec().unwrap(),
+        Err(RecoveryError::StepSkipped) => {}
+        Err(err) => panic!("Failed to create step: {:?}", err),
+    }
+}
+
+/// Asserts that the state of the local node was replaced by the replayed one.
+fn assert_node_has_replayed_state(nodes_dir: &Path) {
+    let node_data = nodes_dir.join(NODE_IP).join("data");
+    assert!(!node_data.join("new_ic_state").exists());
+    let node_checkpoints = node_data.join("ic_state/checkpoints");
+    assert_eq!(
+        Recovery::get_checkpoint_names(&node_checkpoints).unwrap(),
+        vec![REPLAYED_CHECKPOINT.to_string()]
+    );
+    assert_eq!(
+        fs::read_to_string(
+            node_checkpoints
+                .join(REPLAYED_CHECKPOINT)
+                .join("system_metadata.pbuf")
+        )
+        .unwrap(),
+        "replayed"
+    );
+}
+
+#[test]
+fn download_state_from_local_node_keeps_only_latest_checkpoint() {
+    let nodes_dir = TempDir::new().unwrap();
+    let recovery_dir = TempDir::new().unwrap();
+    let node_ip: IpAddr = NODE_IP.parse().unwrap();
+    create_node_fixture(nodes_dir.path(), node_ip);
+    let recovery = new_recovery(&recovery_dir, nodes_dir.path());
+
+    recovery
+        .get_download_state_step(node_ip, true, false)
+        .exec()
+        .unwrap();
+
+    let checkpoints = recovery.work_dir.join("data/ic_state/checkpoints");
+    assert_eq!(
+        Recovery::get_checkpoint_names(&checkpoints).unwrap(),
+        vec![LATEST_CHECKPOINT.to_string()]
+    );
+    assert!(!recovery.work_dir.join("data/ic_state/tip").exists());
+    assert!(recovery
+        .work_dir
+        .join("data/ic_consensus_pool/data.mdb")
+        .exists());
+    assert!(recovery.work_dir.join("ic.json5").exists());
+}
+
+#[test]
+fn upload_replayed_state_replaces_state_of_local_node() {
+    let nodes_dir = TempDir::new().unwrap();
+    let recovery_dir = TempDir::new().unwrap();
+    let node_ip: IpAddr = NODE_IP.parse().unwrap();
+    create_node_fixture(nodes_dir.path(), node_ip);
+    let recovery = new_recovery(&recovery_dir, nodes_dir.path());
+
+    recovery
+        .get_download_state_step(node_ip, false, false)
+        .exec()
+        .unwrap();
+
+    simulate_replay(&recovery);
+
+    recovery.get_stop_replica_step(node_ip).exec().unwrap();
+    recovery
+        .get_upload_and_restart_step(node_ip)
+        .exec()
+        .unwrap();
+
+    assert_node_has_replayed_state(nodes_dir.path());
+}
+
+#[test]
+fn app_subnet_recovery_runs_end_to_end_against_local_node() {
+    let nodes_dir = TempDir::new().unwrap();
+    let recovery_dir = TempDir::new().unwrap();
+    let log_dir = TempDir::new().unwrap();
+    let node_ip: IpAddr = NODE_IP.parse().unwrap();
+    create_node_fixture(nodes_dir.path(), node_ip);
+    let admin_log = log_dir.path().join("ic-admin.log");
+    install_fake_ic_admin(&recovery_dir, &admin_log);
+
+    let subnet_id = SubnetId::from(PrincipalId::new_subnet_test_id(1));
+    let subnet_recovery = AppSubnetRecovery::new(
+        Logger::root(slog::Discard, o!()),
+        recovery_args(&recovery_dir, nodes_dir.path()),
+        None,
+        AppSubnetRecoveryArgs {
+            subnet_id,
+            upgrade_version: None,
+            replacement_nodes: None,
+            pub_key: None,
+            download_node: Some(node_ip),
+            keep_downloaded_state: Some(false),
+            upload_node: Some(node_ip),
+            ecdsa_subnet_id: None,
+        },
+        false,
+    );
+
+    let mut state_params = None;
+    for step_type in app_subnet_recovery::StepType::iter() {
+        match step_type {
+            // Replaying, validating the replay and waiting for the recovery
+            // CUP need a registry and a running replica.
+            app_subnet_recovery::StepType::ICReplay => {
+                state_params = Some(simulate_replay(subnet_recovery.get_recovery_api()))
+            }
+            app_subnet_recovery::StepType::ValidateReplayOutput
+            | app_subnet_recovery::StepType::WaitForCUP => {}
+            _ => exec_step(subnet_recovery.get_step_impl(step_type)),
+        }
+    }
+
+    let state_hash = state_params.unwrap().hash;
+    let proposals = fs::read_to_string(&admin_log).unwrap();
+    let proposals: Vec<_> = proposals.lines().collect();
+    assert_eq!(proposals.len(), 3, "{:?}", proposals);
+    assert!(proposals[0].contains(&format!("--subnet {}", subnet_id)));
+    assert!(proposals[0].contains("--is-halted=true"));
+    assert!(proposals[1].contains("propose-to-update-recovery-cup"));
+    assert!(proposals[1].contains("--height 1000"));
+    assert!(proposals[1].contains(&format!("--state-hash {}", state_hash)));
+    assert!(proposals[2].contains("--is-halted=false"));
+    assert_node_has_replayed_state(nodes_dir.path());
+    assert!(!recovery_dir.path().join("recovery").exists());
+}
+
+#[test]
+fn nns_recovery_on_same_nodes_runs_end_to_end_against_local_node() {
+    let nodes_dir = TempDir::new().unwrap();
+    let recovery_dir = TempDir::new().unwrap();
+    let node_ip: IpAddr = NODE_IP.parse().unwrap();
+    create_node_fixture(nodes_dir.path(), node_ip);
+
+    let subnet_id = SubnetId::from(PrincipalId::new_subnet_test_id(1));
+    let subnet_recovery = NNSRecoverySameNodes::new(
+        Logger::root(slog::Discard, o!()),
+        recovery_args(&recovery_dir, nodes_dir.path()),
+        NNSRecoverySameNodesArgs {
+            subnet_id,
+            upgrade_version: None,
+            pub_key: None,
+            download_node: Some(node_ip),
+            upload_node: Some(node_ip),
+        },
+        true,
+        false,
+    );
+    let recovery = subnet_recovery.get_recovery_api();
+    write_registry_local_store(&recovery.local_store_path, subnet_id, node_ip);
+
+    for step_type in nns_recovery_same_nodes::StepType::iter() {
+        match step_type {
+            // Replaying, validating the replay, creating the recovery CUP and
+            // waiting for it need a registry and a running replica.
+            nns_recovery_same_nodes::StepType::ICReplay => {
+                simulate_replay(recovery);
+            }
+            nns_recovery_same_nodes::StepType::GetRecoveryCUP => {
+                write_file(&recovery.work_dir.join("cup.proto"), "recovery cup");
+            }
+            nns_recovery_same_nodes::StepType::ValidateReplayOutput
+            | nns_recovery_same_nodes::StepType::WaitForCUP => {}
+            _ => exec_step(subnet_recovery.get_step_impl(step_type)),
+        }
+    }
+
+    let node_data = nodes_dir.path().join(NODE_IP).join("data");
+    assert_eq!(
+        fs::read_to_string(node_data.join("cups/cup.types.v1.CatchUpPackage.pb")).unwrap(),
+        "recovery cup"
+    );
+    assert_eq!(
+        fs::read_to_string(node_data.join("ic_registry_local_store/0000000000/00/00/01.pb"))
+            .unwrap(),
+        "registry"
+    );
+    assert_node_has_replayed_state(nodes_dir.path());
+    assert!(!recovery_dir.path().join("recovery").exists());
+}
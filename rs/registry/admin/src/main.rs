@@ -1565,6 +1565,12 @@ struct ProposeToUpdateSubnetCmd {
     /// of this field.
     pub gossip_retransmission_request_ms: Option<u32>,
 
+    #[clap(long)]
+    /// If set, the created proposal will contain a desired override of the
+    /// size up to which artifacts are pushed together with their advert. 0
+    /// disables pushing.
+    pub gossip_artifact_push_threshold_bytes: Option<u32>,
+
     #[clap(long)]
     /// If set, it will set a default value for the entire gossip config. Useful
     /// when you want to only set some fields for the gossip config and there's
@@ -1803,6 +1809,7 @@ impl ProposalTitleAndPayload<UpdateSubnetPayload> for ProposeToUpdateSubnetCmd {
             pfn_evaluation_period_ms: self.gossip_pfn_evaluation_period_ms,
             registry_poll_period_ms: self.gossip_registry_poll_period_ms,
             retransmission_request_ms: self.gossip_retransmission_request_ms,
+            artifact_push_threshold_bytes: self.gossip_artifact_push_threshold_bytes,
             set_gossip_config_to_default: self.set_gossip_config_to_default,
             start_as_nns: self.start_as_nns,
 
@@ -143,6 +143,7 @@ pub(crate) fn get_subnet_records_map(
 ///    * 50ms < priority function interval < 6 * consensus unit delay
 ///    * registry poll period > 3000 milliseconds
 ///    * 10s < retranmission request interval < 2min
+///    * artifact push threshold <= 1 MiB
 fn check_gossip_config_invariants(subnet_id: SubnetId, subnet_record: SubnetRecord) {
     match subnet_record.gossip_config {
         Some(gossip_config) => {
@@ -210,6 +211,14 @@ fn check_gossip_config_invariants(subnet_id: SubnetId, subnet_record: SubnetReco
                     subnet_id, gossip_config.retransmission_request_ms
                 )
             }
+            if gossip_config.artifact_push_threshold_bytes > 1024 * 1024 {
+                panic!(
+                    "Gossip config value for artifact_push_threshold_bytes for subnet {:} is \
+                    currently {:} but it must be at most 1_048_576. Larger artifacts must be \
+                    downloaded in chunks, so that they do not delay the messages of other peers.",
+                    subnet_id, gossip_config.artifact_push_threshold_bytes
+                )
+            }
         }
         None => panic!("No gossip config defined in subnet record {:}.", subnet_id),
     }
@@ -27,6 +27,7 @@ use ic_registry_keys::{
 use ic_registry_subnet_features::{SubnetFeatures, DEFAULT_ECDSA_MAX_QUEUE_SIZE};
 use ic_registry_subnet_type::SubnetType;
 use ic_registry_transport::pb::v1::{registry_mutation, RegistryMutation, RegistryValue};
+use ic_types::p2p::ARTIFACT_PUSH_THRESHOLD_BYTES;
 
 use on_wire::bytes;
 
@@ -324,6 +325,8 @@ impl From<CreateSubnetPayload> for SubnetRecord {
                 pfn_evaluation_period_ms: val.gossip_pfn_evaluation_period_ms,
                 registry_poll_period_ms: val.gossip_registry_poll_period_ms,
                 retransmission_request_ms: val.gossip_retransmission_request_ms,
+                // Pushing can only be enabled with an update proposal.
+                artifact_push_threshold_bytes: ARTIFACT_PUSH_THRESHOLD_BYTES,
             }),
 
             start_as_nns: val.start_as_nns,
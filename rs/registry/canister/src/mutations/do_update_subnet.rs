@@ -24,6 +24,7 @@ impl Registry {
         println!("{}do_update_subnet: {:?}", LOG_PREFIX, payload);
 
         self.validate_update_payload_ecdsa_config(&payload);
+        self.validate_update_payload_features(&payload);
 
         let subnet_id = payload.subnet_id;
 
@@ -110,6 +111,32 @@ impl Registry {
         }
     }
 
+    /// Validates that the proposal does not disable features that cannot be
+    /// disabled once enabled on a subnet.
+    /// Panics if it does.
+    fn validate_update_payload_features(&self, payload: &UpdateSubnetPayload) {
+        let new_features = match payload.features {
+            Some(features) => features,
+            None => return,
+        };
+        let subnet_id = payload.subnet_id;
+        let current_features: SubnetFeatures = self
+            .get_subnet_or_panic(subnet_id)
+            .features
+            .map(SubnetFeatures::from)
+            .unwrap_or_default();
+
+        // Overlay checkpoints change the layout and manifest of the checkpoints that
+        // replicas have already written, so they cannot be turned off again.
+        if current_features.overlay_checkpoints && !new_features.overlay_checkpoints {
+            panic!(
+                "{}update_subnet aborted: Proposal attempts to disable overlay checkpoints on \
+                Subnet '{}', but they cannot be disabled once enabled.",
+                LOG_PREFIX, subnet_id
+            );
+        }
+    }
+
     fn mutations_to_enable_subnet_signing(
         &self,
         subnet_id: SubnetId,
@@ -175,6 +202,7 @@ pub struct UpdateSubnetPayload {
     pub pfn_evaluation_period_ms: Option<u32>,
     pub registry_poll_period_ms: Option<u32>,
     pub retransmission_request_ms: Option<u32>,
+    pub artifact_push_threshold_bytes: Option<u32>,
 
     pub set_gossip_config_to_default: bool,
 
@@ -233,6 +261,7 @@ fn is_any_gossip_field_set(payload: &UpdateSubnetPayload) -> bool {
         || payload.pfn_evaluation_period_ms.is_some()
         || payload.registry_poll_period_ms.is_some()
         || payload.retransmission_request_ms.is_some()
+        || payload.artifact_push_threshold_bytes.is_some()
 }
 
 // Merges the changes included in the `UpdateSubnetPayload` to the given
@@ -272,6 +301,7 @@ fn merge_subnet_record(
         pfn_evaluation_period_ms,
         registry_poll_period_ms,
         retransmission_request_ms,
+        artifact_push_threshold_bytes,
         set_gossip_config_to_default,
         start_as_nns,
         subnet_type,
@@ -311,6 +341,7 @@ fn merge_subnet_record(
     maybe_set!(gossip_config, pfn_evaluation_period_ms);
     maybe_set!(gossip_config, registry_poll_period_ms);
     maybe_set!(gossip_config, retransmission_request_ms);
+    maybe_set!(gossip_config, artifact_push_threshold_bytes);
     subnet_record.gossip_config = Some(gossip_config);
 
     maybe_set!(subnet_record, start_as_nns);
@@ -351,8 +382,9 @@ mod tests {
     use ic_registry_subnet_type::SubnetType;
     use ic_test_utilities::types::ids::subnet_test_id;
     use ic_types::p2p::{
-        MAX_ARTIFACT_STREAMS_PER_PEER, MAX_CHUNK_WAIT_MS, MAX_DUPLICITY, PFN_EVALUATION_PERIOD_MS,
-        RECEIVE_CHECK_PEER_SET_SIZE, REGISTRY_POLL_PERIOD_MS, RETRANSMISSION_REQUEST_MS,
+        ARTIFACT_PUSH_THRESHOLD_BYTES, MAX_ARTIFACT_STREAMS_PER_PEER, MAX_CHUNK_WAIT_MS,
+        MAX_DUPLICITY, PFN_EVALUATION_PERIOD_MS, RECEIVE_CHECK_PEER_SET_SIZE,
+        REGISTRY_POLL_PERIOD_MS, RETRANSMISSION_REQUEST_MS,
     };
     use ic_types::{PrincipalId, SubnetId};
     use std::str::FromStr;
@@ -387,6 +419,7 @@ mod tests {
             pfn_evaluation_period_ms: Some(5000),
             registry_poll_period_ms: Some(4000),
             retransmission_request_ms: Some(7000),
+            artifact_push_threshold_bytes: None,
             set_gossip_config_to_default: false,
             start_as_nns: Some(true),
             subnet_type: None,
@@ -399,6 +432,9 @@ mod tests {
                 http_requests: false,
                 bitcoin: None,
                 sev_status: None,
+                quic_transport: false,
+                compressed_state_sync: false,
+                overlay_checkpoints: false,
             }),
             ecdsa_config: Some(EcdsaConfig {
                 quadruples_to_create_in_advance: 10,
@@ -433,6 +469,7 @@ mod tests {
             pfn_evaluation_period_ms: None,
             registry_poll_period_ms: None,
             retransmission_request_ms: None,
+            artifact_push_threshold_bytes: None,
             set_gossip_config_to_default: false,
             start_as_nns: None,
             subnet_type: None,
@@ -471,6 +508,7 @@ mod tests {
                 pfn_evaluation_period_ms: 100,
                 registry_poll_period_ms: 100,
                 retransmission_request_ms: 100,
+                artifact_push_threshold_bytes: 0,
             }),
             start_as_nns: false,
             subnet_type: SubnetType::Application.into(),
@@ -507,6 +545,7 @@ mod tests {
             pfn_evaluation_period_ms: Some(5000),
             registry_poll_period_ms: Some(4000),
             retransmission_request_ms: Some(7000),
+            artifact_push_threshold_bytes: Some(1024),
             set_gossip_config_to_default: false,
             start_as_nns: Some(true),
             subnet_type: None,
@@ -519,6 +558,9 @@ mod tests {
                 http_requests: false,
                 bitcoin: None,
                 sev_status: None,
+                quic_transport: false,
+                compressed_state_sync: false,
+                overlay_checkpoints: false,
             }),
             ecdsa_config: Some(EcdsaConfig {
                 quadruples_to_create_in_advance: 10,
@@ -555,6 +597,7 @@ mod tests {
                     pfn_evaluation_period_ms: 5000,
                     registry_poll_period_ms: 4000,
                     retransmission_request_ms: 7000,
+                    artifact_push_threshold_bytes: 1024,
                 }),
                 start_as_nns: true,
                 subnet_type: SubnetType::Application.into(),
@@ -568,6 +611,9 @@ mod tests {
                         http_requests: false,
                         bitcoin: None,
                         sev_status: None,
+                        quic_transport: false,
+                        compressed_state_sync: false,
+                        overlay_checkpoints: false,
                     }
                     .into()
                 ),
@@ -609,6 +655,7 @@ mod tests {
                 pfn_evaluation_period_ms: 100,
                 registry_poll_period_ms: 100,
                 retransmission_request_ms: 100,
+                artifact_push_threshold_bytes: 0,
             }),
             start_as_nns: false,
             subnet_type: SubnetType::Application.into(),
@@ -645,6 +692,7 @@ mod tests {
             pfn_evaluation_period_ms: None,
             registry_poll_period_ms: None,
             retransmission_request_ms: None,
+            artifact_push_threshold_bytes: None,
             set_gossip_config_to_default: false,
             start_as_nns: None,
             subnet_type: None,
@@ -682,6 +730,7 @@ mod tests {
                     pfn_evaluation_period_ms: 100,
                     registry_poll_period_ms: 100,
                     retransmission_request_ms: 100,
+                    artifact_push_threshold_bytes: 0,
                 }),
                 start_as_nns: false,
                 subnet_type: SubnetType::Application.into(),
@@ -770,6 +819,7 @@ mod tests {
             pfn_evaluation_period_ms: None,
             registry_poll_period_ms: None,
             retransmission_request_ms: None,
+            artifact_push_threshold_bytes: None,
             set_gossip_config_to_default: false,
             start_as_nns: None,
             subnet_type: Some(SubnetType::Application),
@@ -837,6 +887,7 @@ mod tests {
             pfn_evaluation_period_ms: Some(PFN_EVALUATION_PERIOD_MS),
             registry_poll_period_ms: Some(REGISTRY_POLL_PERIOD_MS),
             retransmission_request_ms: Some(RETRANSMISSION_REQUEST_MS),
+            artifact_push_threshold_bytes: Some(ARTIFACT_PUSH_THRESHOLD_BYTES),
             set_gossip_config_to_default: true,
             start_as_nns: None,
             subnet_type: None,
@@ -874,6 +925,7 @@ mod tests {
                     pfn_evaluation_period_ms: PFN_EVALUATION_PERIOD_MS,
                     registry_poll_period_ms: REGISTRY_POLL_PERIOD_MS,
                     retransmission_request_ms: RETRANSMISSION_REQUEST_MS,
+                    artifact_push_threshold_bytes: ARTIFACT_PUSH_THRESHOLD_BYTES,
                 }),
                 start_as_nns: false,
                 subnet_type: SubnetType::Application.into(),
@@ -911,6 +963,7 @@ mod tests {
                 pfn_evaluation_period_ms: 100,
                 registry_poll_period_ms: 100,
                 retransmission_request_ms: 100,
+                artifact_push_threshold_bytes: 0,
             }),
             start_as_nns: false,
             subnet_type: SubnetType::Application.into(),
@@ -947,6 +1000,7 @@ mod tests {
             pfn_evaluation_period_ms: None,
             registry_poll_period_ms: None,
             retransmission_request_ms: None,
+            artifact_push_threshold_bytes: None,
             set_gossip_config_to_default: false,
             start_as_nns: None,
             subnet_type: None,
@@ -984,6 +1038,7 @@ mod tests {
                     pfn_evaluation_period_ms: 100,
                     registry_poll_period_ms: 100,
                     retransmission_request_ms: 100,
+                    artifact_push_threshold_bytes: 0,
                 }),
                 start_as_nns: false,
                 subnet_type: SubnetType::Application.into(),
@@ -1113,6 +1168,52 @@ mod tests {
         registry.do_update_subnet(payload);
     }
 
+    #[test]
+    #[should_panic(
+        expected = "Proposal attempts to disable overlay checkpoints on Subnet \
+        'ge6io-epiam-aaaaa-aaaap-yai', but they cannot be disabled once enabled."
+    )]
+    fn overlay_checkpoints_cannot_be_disabled_once_enabled() {
+        let mut registry = invariant_compliant_registry();
+
+        let (mutate_request, mut node_ids) = prepare_registry_with_nodes(1);
+        registry.maybe_apply_mutation_internal(mutate_request.mutations);
+
+        let mut subnet_list_record = registry.get_subnet_list_record();
+
+        // Create the subnet we will update with overlay checkpoints enabled.
+        let mut subnet_record =
+            get_invariant_compliant_subnet_record(vec![node_ids.pop().unwrap()]);
+        subnet_record.features = Some(
+            SubnetFeatures {
+                overlay_checkpoints: true,
+                ..SubnetFeatures::default()
+            }
+            .into(),
+        );
+
+        let subnet_id = subnet_test_id(1000);
+        registry.maybe_apply_mutation_internal(add_fake_subnet(
+            subnet_id,
+            &mut subnet_list_record,
+            subnet_record,
+        ));
+
+        // Enabling other features while keeping overlay checkpoints is fine.
+        let mut payload = make_empty_update_payload(subnet_id);
+        payload.features = Some(SubnetFeatures {
+            overlay_checkpoints: true,
+            http_requests: true,
+            ..SubnetFeatures::default()
+        });
+        registry.do_update_subnet(payload);
+
+        // Should panic because we are trying to disable overlay checkpoints.
+        let mut payload = make_empty_update_payload(subnet_id);
+        payload.features = Some(SubnetFeatures::default());
+        registry.do_update_subnet(payload);
+    }
+
     #[test]
     fn can_add_a_second_key_in_subsequent_request() {
         let mut registry = invariant_compliant_registry();
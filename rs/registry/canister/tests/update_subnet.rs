@@ -20,9 +20,9 @@ use ic_registry_subnet_features::{EcdsaConfig, DEFAULT_ECDSA_MAX_QUEUE_SIZE};
 use ic_registry_subnet_type::SubnetType;
 use ic_registry_transport::{insert, pb::v1::RegistryAtomicMutateRequest};
 use ic_types::p2p::{
-    build_default_gossip_config, MAX_ARTIFACT_STREAMS_PER_PEER, MAX_CHUNK_SIZE, MAX_CHUNK_WAIT_MS,
-    MAX_DUPLICITY, PFN_EVALUATION_PERIOD_MS, RECEIVE_CHECK_PEER_SET_SIZE, REGISTRY_POLL_PERIOD_MS,
-    RETRANSMISSION_REQUEST_MS,
+    build_default_gossip_config, ARTIFACT_PUSH_THRESHOLD_BYTES, MAX_ARTIFACT_STREAMS_PER_PEER,
+    MAX_CHUNK_SIZE, MAX_CHUNK_WAIT_MS, MAX_DUPLICITY, PFN_EVALUATION_PERIOD_MS,
+    RECEIVE_CHECK_PEER_SET_SIZE, REGISTRY_POLL_PERIOD_MS, RETRANSMISSION_REQUEST_MS,
 };
 use registry_canister::{
     init::RegistryCanisterInitPayloadBuilder, mutations::do_update_subnet::UpdateSubnetPayload,
@@ -78,6 +78,7 @@ fn test_the_anonymous_user_cannot_update_a_subnets_configuration() {
             pfn_evaluation_period_ms: Some(PFN_EVALUATION_PERIOD_MS),
             registry_poll_period_ms: Some(REGISTRY_POLL_PERIOD_MS),
             retransmission_request_ms: Some(RETRANSMISSION_REQUEST_MS),
+            artifact_push_threshold_bytes: Some(ARTIFACT_PUSH_THRESHOLD_BYTES),
             set_gossip_config_to_default: false,
             start_as_nns: None,
             subnet_type: None,
@@ -205,6 +206,7 @@ fn test_a_canister_other_than_the_governance_canister_cannot_update_a_subnets_co
             pfn_evaluation_period_ms: Some(PFN_EVALUATION_PERIOD_MS),
             registry_poll_period_ms: Some(REGISTRY_POLL_PERIOD_MS),
             retransmission_request_ms: Some(RETRANSMISSION_REQUEST_MS),
+            artifact_push_threshold_bytes: Some(ARTIFACT_PUSH_THRESHOLD_BYTES),
             set_gossip_config_to_default: true,
             start_as_nns: None,
             subnet_type: None,
@@ -322,6 +324,7 @@ fn test_the_governance_canister_can_update_a_subnets_configuration() {
             pfn_evaluation_period_ms: Some(PFN_EVALUATION_PERIOD_MS),
             registry_poll_period_ms: Some(REGISTRY_POLL_PERIOD_MS),
             retransmission_request_ms: Some(RETRANSMISSION_REQUEST_MS),
+            artifact_push_threshold_bytes: Some(ARTIFACT_PUSH_THRESHOLD_BYTES),
             set_gossip_config_to_default: false,
             start_as_nns: None,
             subnet_type: Some(SubnetType::Application),
@@ -377,6 +380,7 @@ fn test_the_governance_canister_can_update_a_subnets_configuration() {
                     pfn_evaluation_period_ms: PFN_EVALUATION_PERIOD_MS,
                     registry_poll_period_ms: REGISTRY_POLL_PERIOD_MS,
                     retransmission_request_ms: RETRANSMISSION_REQUEST_MS,
+                    artifact_push_threshold_bytes: ARTIFACT_PUSH_THRESHOLD_BYTES,
                 }),
                 start_as_nns: false,
                 subnet_type: SubnetType::Application.into(),
@@ -640,6 +644,7 @@ fn empty_update_subnet_payload(subnet_id: SubnetId) -> UpdateSubnetPayload {
         pfn_evaluation_period_ms: None,
         registry_poll_period_ms: None,
         retransmission_request_ms: None,
+        artifact_push_threshold_bytes: None,
         set_gossip_config_to_default: false,
         start_as_nns: None,
         subnet_type: None,
@@ -21,6 +21,21 @@ pub struct SubnetFeatures {
     pub bitcoin: Option<BitcoinFeature>,
 
     pub sev_status: Option<SevFeatureStatus>,
+
+    /// This feature flag controls whether the nodes of this subnet talk to
+    /// each other over QUIC instead of TLS/TCP. It is disabled by default.
+    pub quic_transport: bool,
+
+    /// This feature flag controls whether the states of this subnet are synced
+    /// with compressed chunks. It must only be enabled once all replicas of the
+    /// subnet support it. It is disabled by default.
+    pub compressed_state_sync: bool,
+
+    /// This feature flag controls whether checkpoints of this subnet only write
+    /// the dirty pages of page maps to overlay files. It affects the state
+    /// manifest, so it must only be enabled once all replicas of the subnet
+    /// support it; and it cannot be disabled again. It is disabled by default.
+    pub overlay_checkpoints: bool,
 }
 
 impl SubnetFeatures {
@@ -58,6 +73,9 @@ impl From<SubnetFeatures> for pb::SubnetFeatures {
                 SevFeatureStatus::SecureNoUpgradeEnabled => 3,
                 SevFeatureStatus::SecureEnabled => 4,
             }),
+            quic_transport: features.quic_transport,
+            compressed_state_sync: features.compressed_state_sync,
+            overlay_checkpoints: features.overlay_checkpoints,
         }
     }
 }
@@ -97,6 +115,9 @@ impl From<pb::SubnetFeatures> for SubnetFeatures {
                 4 => SevFeatureStatus::SecureEnabled,
                 _ => SevFeatureStatus::Disabled,
             }),
+            quic_transport: features.quic_transport,
+            compressed_state_sync: features.compressed_state_sync,
+            overlay_checkpoints: features.overlay_checkpoints,
         }
     }
 }
@@ -116,6 +137,9 @@ impl FromStr for SubnetFeatures {
             match feature {
                 "canister_sandboxing" => features.canister_sandboxing = true,
                 "http_requests" => features.http_requests = true,
+                "quic_transport" => features.quic_transport = true,
+                "compressed_state_sync" => features.compressed_state_sync = true,
+                "overlay_checkpoints" => features.overlay_checkpoints = true,
                 "bitcoin_testnet" => {
                     if features.bitcoin.is_some() {
                         // Feature was already set. Return an error.
@@ -315,6 +339,9 @@ mod tests {
                     status: BitcoinFeatureStatus::Enabled
                 }),
                 sev_status: None,
+                quic_transport: false,
+                compressed_state_sync: false,
+                overlay_checkpoints: false,
             }
         );
     }
@@ -334,6 +361,9 @@ mod tests {
                     status: BitcoinFeatureStatus::Paused
                 }),
                 sev_status: None,
+                quic_transport: false,
+                compressed_state_sync: false,
+                overlay_checkpoints: false,
             }
         );
     }
@@ -352,6 +382,9 @@ mod tests {
                     status: BitcoinFeatureStatus::Enabled
                 }),
                 sev_status: None,
+                quic_transport: false,
+                compressed_state_sync: false,
+                overlay_checkpoints: false,
             }
         );
     }
@@ -375,6 +408,36 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_quic_transport_to_from_proto() {
+        let subnet_feature = SubnetFeatures::from_str("quic_transport").unwrap();
+        assert!(subnet_feature.quic_transport);
+        assert_eq!(
+            subnet_feature,
+            SubnetFeatures::from(pb::SubnetFeatures::from(subnet_feature))
+        );
+    }
+
+    #[test]
+    fn test_compressed_state_sync_to_from_proto() {
+        let subnet_feature = SubnetFeatures::from_str("compressed_state_sync").unwrap();
+        assert!(subnet_feature.compressed_state_sync);
+        assert_eq!(
+            subnet_feature,
+            SubnetFeatures::from(pb::SubnetFeatures::from(subnet_feature))
+        );
+    }
+
+    #[test]
+    fn test_overlay_checkpoints_to_from_proto() {
+        let subnet_feature = SubnetFeatures::from_str("overlay_checkpoints").unwrap();
+        assert!(subnet_feature.overlay_checkpoints);
+        assert_eq!(
+            subnet_feature,
+            SubnetFeatures::from(pb::SubnetFeatures::from(subnet_feature))
+        );
+    }
+
     #[test]
     fn test_bitcoin_to_from_proto() {
         for feature in [
@@ -27,18 +27,73 @@ use std::{
     sync::Arc,
 };
 
+use crate::content_store::{parse_key, ArtifactIndex, ContentStore, ObjectKey};
 use crate::player::ReplayError;
 use crate::validator::{InvalidArtifact, ReplayValidator};
 
 // A set of backup artifacts corresponding to a single height.
 pub(super) struct HeightArtifacts {
     path: PathBuf,
+    // The content store keys of the artifacts if the height is archived.
+    archived: Option<BTreeMap<String, ObjectKey>>,
     contains_cup: bool,
     proposals: Vec<String>,
     finalizations: Vec<String>,
     notarizations: Vec<String>,
 }
 
+impl HeightArtifacts {
+    // Groups the artifact files of the height at `path` by their type.
+    fn new(
+        path: PathBuf,
+        archived: Option<BTreeMap<String, ObjectKey>>,
+        files: Vec<String>,
+    ) -> Self {
+        let get_files = |s| {
+            files
+                .iter()
+                .filter(|file| file.starts_with(s))
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+        Self {
+            path,
+            archived,
+            contains_cup: !get_files("catch_up_package").is_empty(),
+            proposals: get_files("block_proposal"),
+            finalizations: get_files("finalization"),
+            notarizations: get_files("notarization"),
+        }
+    }
+
+    // Returns true if the height contains a catch-up package.
+    pub(crate) fn contains_cup(&self) -> bool {
+        self.contains_cup
+    }
+
+    // Returns true if the artifact file with the given name exists.
+    fn contains(&self, file_name: &str) -> bool {
+        match &self.archived {
+            Some(keys) => keys.contains_key(file_name),
+            None => self.path.join(file_name).exists(),
+        }
+    }
+
+    // Reads the artifact file with the given name, from the content store if
+    // the height is archived.
+    fn read(&self, store: &ContentStore, file_name: &str) -> Vec<u8> {
+        match &self.archived {
+            Some(keys) => {
+                let key = keys.get(file_name).unwrap_or_else(|| {
+                    panic!("Missing archived file {:?}", self.path.join(file_name))
+                });
+                read_object(store, key)
+            }
+            None => read_file(&self.path.join(file_name)),
+        }
+    }
+}
+
 // Reads the file at `path` and the returns the content as bytes.
 fn read_file(path: &Path) -> Vec<u8> {
     let mut buffer = Vec::new();
@@ -49,6 +104,13 @@ fn read_file(path: &Path) -> Vec<u8> {
     buffer
 }
 
+// Reads the object with the given key from the content store.
+fn read_object(store: &ContentStore, key: &ObjectKey) -> Vec<u8> {
+    store
+        .get(key)
+        .unwrap_or_else(|err| panic!("Couldn't read object {}: {:?}", hex::encode(key), err))
+}
+
 /// All possible exits from the deserialization loop of the artifacts. All
 /// exits except for `Done` require for the upper layers to catch up.
 pub(crate) enum ExitPoint {
@@ -75,29 +137,50 @@ pub(crate) enum ExitPoint {
 pub(crate) fn insert_cup_at_height(
     pool: &mut dyn MutableConsensusPool,
     backup_dir: &Path,
+    store: &ContentStore,
     height: Height,
 ) {
-    let cup = read_cup_at_height(backup_dir, height);
+    let cup = read_cup_at_height(backup_dir, store, height).unwrap_or_else(|err| panic!("{}", err));
     pool.apply_changes(
         &SysTimeSource::new(),
         ChangeAction::AddToValidated(cup.into_message()).into(),
     );
 }
 
-/// Deserializes the CUP at the given height and returns it.
-pub(crate) fn read_cup_at_height(backup_dir: &Path, height: Height) -> CatchUpPackage {
+/// Deserializes the CUP at the given height and returns it, or an error if the
+/// CUP is missing or can't be deserialized.
+pub(crate) fn read_cup_at_height(
+    backup_dir: &Path,
+    store: &ContentStore,
+    height: Height,
+) -> Result<CatchUpPackage, String> {
     let group_key = (height.get() / BACKUP_GROUP_SIZE) * BACKUP_GROUP_SIZE;
-    let file = &backup_dir
-        .join(group_key.to_string())
+    let group_dir = backup_dir.join(group_key.to_string());
+    let file = &group_dir
         .join(height.to_string())
         .join("catch_up_package.bin");
-    let buffer = read_file(file);
+    let buffer = if file.exists() {
+        fs::read(file).map_err(|err| format!("Couldn't read file {:?}: {:?}", file, err))?
+    } else {
+        let key = ArtifactIndex::load(&group_dir)
+            .map_err(|err| format!("Couldn't load the artifact index: {:?}", err))?
+            .and_then(|index| {
+                index
+                    .heights
+                    .get(&height.get())
+                    .and_then(|files| files.get("catch_up_package.bin"))
+                    .and_then(|hex| parse_key(hex))
+            })
+            .ok_or_else(|| format!("Couldn't find file {:?}", file))?;
+        store
+            .get(&key)
+            .map_err(|err| format!("Couldn't read object {}: {:?}", hex::encode(key), err))?
+    };
 
     let protobuf = ic_protobuf::types::v1::CatchUpPackage::decode(buffer.as_slice())
-        .expect("Protobuf decoding failed");
+        .map_err(|err| deserialization_error(file, err.to_string()))?;
 
-    CatchUpPackage::try_from(&protobuf)
-        .unwrap_or_else(|err| panic!("{}", deserialization_error(file, err)))
+    CatchUpPackage::try_from(&protobuf).map_err(|err| deserialization_error(file, err))
 }
 
 /// Read all files from the backup folder starting from the `start_height` and
@@ -108,7 +191,35 @@ pub(super) fn heights_to_artifacts_metadata(
 ) -> Result<BTreeMap<Height, HeightArtifacts>, std::io::Error> {
     let mut results = Vec::new();
     for group_dir in fs::read_dir(backup_dir)? {
-        for height_dir in fs::read_dir(group_dir?.path())? {
+        let group_dir = group_dir?.path();
+        // Skip all groups below the start height.
+        let group_key = group_dir
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.parse::<u64>().ok());
+        if let Some(group_key) = group_key {
+            if group_key + BACKUP_GROUP_SIZE <= start_height.get() {
+                continue;
+            }
+        }
+        if let Some(index) = ArtifactIndex::load(&group_dir)? {
+            for (height, files) in index.heights {
+                if height < start_height.get() {
+                    continue;
+                }
+                let keys = files
+                    .into_iter()
+                    .filter_map(|(name, hex)| parse_key(&hex).map(|key| (name, key)))
+                    .collect::<BTreeMap<_, _>>();
+                let files = keys.keys().cloned().collect::<Vec<_>>();
+                results.push((
+                    Height::from(height),
+                    HeightArtifacts::new(group_dir.join(height.to_string()), Some(keys), files),
+                ));
+            }
+            continue;
+        }
+        for height_dir in fs::read_dir(&group_dir)? {
             let path = height_dir?.path();
             let height = Height::from(
                 path.file_name()
@@ -135,23 +246,7 @@ pub(super) fn heights_to_artifacts_metadata(
                         .to_string(),
                 );
             }
-            let get_files = |s| {
-                files
-                    .iter()
-                    .filter(|file| file.starts_with(s))
-                    .cloned()
-                    .collect::<Vec<_>>()
-            };
-            results.push((
-                height,
-                HeightArtifacts {
-                    path,
-                    contains_cup: !get_files("catch_up_package").is_empty(),
-                    proposals: get_files("block_proposal"),
-                    finalizations: get_files("finalization"),
-                    notarizations: get_files("notarization"),
-                },
-            ));
+            results.push((height, HeightArtifacts::new(path, None, files)));
         }
     }
     Ok(results.into_iter().collect())
@@ -164,6 +259,7 @@ pub(crate) fn deserialize_consensus_artifacts(
     registry_client: Arc<dyn RegistryClient>,
     pool: &mut ConsensusPoolImpl,
     height_to_batches: &mut BTreeMap<Height, HeightArtifacts>,
+    store: &ContentStore,
     subnet_id: SubnetId,
     latest_state_height: Height,
     validator: &ReplayValidator,
@@ -216,7 +312,7 @@ pub(crate) fn deserialize_consensus_artifacts(
             // Save the hash of the finalized block proposal.
             finalized_block_hash = file_name.split('_').nth(1);
             let file = &path.join(file_name);
-            let buffer = read_file(file);
+            let buffer = height_artifacts.read(store, file_name);
             let finalization = Finalization::try_from(
                 pb::Finalization::decode(buffer.as_slice()).expect("Protobuf decoding failed"),
             )
@@ -248,7 +344,7 @@ pub(crate) fn deserialize_consensus_artifacts(
             .filter(|name| name.contains(finalized_block_hash.unwrap_or("")))
         {
             let file = &path.join(file_name);
-            let buffer = read_file(file);
+            let buffer = height_artifacts.read(store, file_name);
             let proposal = BlockProposal::try_from(
                 pb::BlockProposal::decode(buffer.as_slice()).expect("Protobuf decoding failed"),
             )
@@ -289,14 +385,14 @@ pub(crate) fn deserialize_consensus_artifacts(
 
         // Insert the random beacon and the random tape.
         let rb_path = path.join("random_beacon.bin");
-        if !rb_path.exists() {
+        if !height_artifacts.contains("random_beacon.bin") {
             println!(
                 "Stopping deserialization at height {:?} as this height contains no random beacon.",
                 height,
             );
             return ExitPoint::Done;
         }
-        let buffer = read_file(&rb_path);
+        let buffer = height_artifacts.read(store, "random_beacon.bin");
         artifacts.push(
             RandomBeacon::try_from(
                 pb::RandomBeacon::decode(buffer.as_slice()).expect("Protobuf decoding failed"),
@@ -306,14 +402,14 @@ pub(crate) fn deserialize_consensus_artifacts(
         );
 
         let rt_path = path.join("random_tape.bin");
-        if !rt_path.exists() {
+        if !height_artifacts.contains("random_tape.bin") {
             println!(
                 "Stopping deserialization at height {:?} as this height contains no random tape.",
                 height,
             );
             return ExitPoint::Done;
         }
-        let buffer = read_file(&rt_path);
+        let buffer = height_artifacts.read(store, "random_tape.bin");
         artifacts.push(
             RandomTape::try_from(
                 pb::RandomTape::decode(buffer.as_slice()).expect("Protobuf decoding failed"),
@@ -325,7 +421,7 @@ pub(crate) fn deserialize_consensus_artifacts(
         // Insert the notarizations.
         for file_name in &height_artifacts.notarizations {
             let file = &path.join(file_name);
-            let buffer = read_file(file);
+            let buffer = height_artifacts.read(store, file_name);
             artifacts.push(
                 Notarization::try_from(
                     pb::Notarization::decode(buffer.as_slice()).expect("Protobuf decoding failed"),
@@ -363,7 +459,7 @@ pub(crate) fn deserialize_consensus_artifacts(
         invalid.iter().for_each(|i| match i.get_file_name() {
             Some(name) => {
                 assert!(
-                    path.join(&name).exists(),
+                    height_artifacts.contains(&name),
                     "Path to invalid artifact doesn't exist."
                 );
                 println!("Invalid artifact detected: {:?}", path.join(name));
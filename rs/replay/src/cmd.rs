@@ -1,4 +1,4 @@
-use clap::Parser;
+use clap::{ArgEnum, Parser};
 use ic_types::{CanisterId, PrincipalId, SubnetId};
 use icp_ledger::AccountIdentifier;
 use std::path::PathBuf;
@@ -39,6 +39,30 @@ pub struct ReplayToolArgs {
     #[clap(long)]
     /// The replay will stop at this height and make a checkpoint.
     pub replay_until_height: Option<u64>,
+
+    /// Print the executed ingress messages and the changes of the certified
+    /// state after each replayed batch. In interactive mode, the replay waits
+    /// for the user before delivering the next batch.
+    #[clap(long, arg_enum)]
+    pub step: Option<StepMode>,
+
+    /// Restrict the changes printed in step mode to these canisters.
+    #[clap(long, multiple_values = true, requires = "step")]
+    pub step_canisters: Vec<CanisterId>,
+
+    /// In step mode, also print the number of instructions executed by each
+    /// canister.
+    #[clap(long, requires = "step")]
+    pub dump_instructions: bool,
+}
+
+/// How the replay proceeds after each batch in step mode.
+#[derive(ArgEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StepMode {
+    /// Wait for the user after each batch.
+    Interactive,
+    /// Print the changes of each batch without stopping.
+    Scripted,
 }
 
 #[derive(Clone, Parser)]
@@ -0,0 +1,480 @@
+//! A content-addressed store for backed up consensus artifacts and checkpoint
+//! chunks.
+//!
+//! Every object is stored once, compressed, under the hex encoding of its
+//! hash. Consensus artifacts are keyed by the SHA-256 hash of their content,
+//! checkpoint chunks by their hash in the manifest of the checkpoint, so that
+//! chunks shared by consecutive checkpoints of a subnet are stored only once.
+//!
+//! The store lives in the directory `CONTENT_STORE_DIR` of the backup spool:
+//!
+//! ```text
+//! <spool>
+//! ├── content_store
+//! │   └── objects
+//! │       └── <first 2 hex digits of the hash>
+//! │           └── <hex hash>
+//! └── <subnet_id>
+//!     └── <replica_version>
+//!         └── <group>
+//!             ├── <height>              not yet archived heights
+//!             │   └── <artifact>.bin
+//!             └── artifact_index.json   archived heights
+//! ```
+//!
+//! Once a group of heights is archived, its height directories are replaced by
+//! an `ArtifactIndex` mapping the artifact files of each height to the hashes
+//! of their content. The replay reads archived artifacts from the store, so
+//! that archiving is transparent to it.
+
+use ic_crypto_sha::Sha256;
+use ic_logger::replica_logger::no_op_logger;
+use ic_metrics::MetricsRegistry;
+use ic_state_layout::{CheckpointLayout, ReadOnly};
+use ic_state_manager::{
+    manifest::{compute_manifest, validate_chunk, DEFAULT_CHUNK_SIZE},
+    ManifestMetrics, NUMBER_OF_CHECKPOINT_THREADS,
+};
+use ic_types::{
+    state_sync::{decode_manifest, encode_manifest, Manifest},
+    Height,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    io::{self, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+/// The directory of the backup spool holding the content store.
+pub const CONTENT_STORE_DIR: &str = "content_store";
+
+/// The name of the file listing the artifacts of an archived group.
+pub const ARTIFACT_INDEX_FILE: &str = "artifact_index.json";
+
+/// The name of the file holding the manifest of an archived checkpoint.
+pub const CHECKPOINT_MANIFEST_FILE: &str = "checkpoint.manifest";
+
+/// The zstd compression level of stored objects.
+const COMPRESSION_LEVEL: i32 = 3;
+
+/// The hash an object is stored under.
+pub type ObjectKey = [u8; 32];
+
+/// A content-addressed store of compressed objects rooted at a directory.
+#[derive(Clone, Debug)]
+pub struct ContentStore {
+    root: PathBuf,
+}
+
+impl ContentStore {
+    /// Creates a handle of the store rooted at `root`.
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    /// Returns the handle of the store of the backup spool at `spool_root`.
+    pub fn for_spool(spool_root: &Path) -> Self {
+        Self::new(spool_root.join(CONTENT_STORE_DIR))
+    }
+
+    /// Returns the root directory of the store.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn objects_dir(&self) -> PathBuf {
+        self.root.join("objects")
+    }
+
+    fn object_path(&self, key: &ObjectKey) -> PathBuf {
+        let hex = hex::encode(key);
+        self.objects_dir().join(&hex[..2]).join(hex)
+    }
+
+    /// Returns true if the store contains an object with the given key.
+    pub fn contains(&self, key: &ObjectKey) -> bool {
+        self.object_path(key).exists()
+    }
+
+    /// Stores `bytes` under `key` unless the store already contains the key.
+    /// Returns the number of bytes written to disk.
+    pub fn put(&self, key: &ObjectKey, bytes: &[u8]) -> io::Result<u64> {
+        let path = self.object_path(key);
+        if path.exists() {
+            return Ok(0);
+        }
+        let dir = path.parent().expect("object path has a parent");
+        fs::create_dir_all(dir)?;
+        let compressed = zstd::encode_all(bytes, COMPRESSION_LEVEL)?;
+        // Write to a temporary file first, so that a crash never leaves a
+        // truncated object behind.
+        let tmp_path = path.with_extension("tmp");
+        let mut file = fs::File::create(&tmp_path)?;
+        file.write_all(&compressed)?;
+        file.sync_all()?;
+        fs::rename(&tmp_path, &path)?;
+        Ok(compressed.len() as u64)
+    }
+
+    /// Stores `bytes` under their SHA-256 hash and returns the hash.
+    pub fn put_content(&self, bytes: &[u8]) -> io::Result<ObjectKey> {
+        let key = Sha256::hash(bytes);
+        self.put(&key, bytes)?;
+        Ok(key)
+    }
+
+    /// Returns the decompressed object stored under `key`.
+    pub fn get(&self, key: &ObjectKey) -> io::Result<Vec<u8>> {
+        let file = fs::File::open(self.object_path(key))?;
+        zstd::decode_all(file)
+    }
+
+    /// Returns the keys of all objects in the store.
+    pub fn keys(&self) -> io::Result<BTreeSet<ObjectKey>> {
+        let mut keys = BTreeSet::new();
+        let objects_dir = self.objects_dir();
+        if !objects_dir.exists() {
+            return Ok(keys);
+        }
+        for prefix_dir in fs::read_dir(objects_dir)? {
+            for object in fs::read_dir(prefix_dir?.path())? {
+                // Skips leftovers of interrupted writes.
+                if let Some(key) = parse_key(&object?.file_name().to_string_lossy()) {
+                    keys.insert(key);
+                }
+            }
+        }
+        Ok(keys)
+    }
+
+    /// Removes all objects whose keys are not in `live`. Returns the number of
+    /// removed objects.
+    pub fn collect_garbage(&self, live: &BTreeSet<ObjectKey>) -> io::Result<usize> {
+        let mut removed = 0;
+        for key in self.keys()?.difference(live) {
+            fs::remove_file(self.object_path(key))?;
+            removed += 1;
+        }
+        Ok(removed)
+    }
+}
+
+/// The artifacts of an archived group of heights: the hex encoded key of each
+/// artifact file, by height and file name.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArtifactIndex {
+    pub heights: BTreeMap<u64, BTreeMap<String, String>>,
+}
+
+impl ArtifactIndex {
+    /// Loads the index of the group at `group_dir`, if the group is archived.
+    pub fn load(group_dir: &Path) -> io::Result<Option<Self>> {
+        let path = group_dir.join(ARTIFACT_INDEX_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let bytes = fs::read(&path)?;
+        serde_json::from_slice(&bytes)
+            .map(Some)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    /// Returns the keys of all artifacts in the index.
+    pub fn keys(&self) -> impl Iterator<Item = ObjectKey> + '_ {
+        self.heights
+            .values()
+            .flat_map(|files| files.values())
+            .filter_map(|hex| parse_key(hex))
+    }
+}
+
+/// Parses a hex encoded object key.
+pub fn parse_key(hex: &str) -> Option<ObjectKey> {
+    hex::decode(hex)
+        .ok()
+        .and_then(|bytes| ObjectKey::try_from(bytes).ok())
+}
+
+/// Moves all artifacts of the group at `group_dir` into `store` and replaces
+/// the height directories of the group with an `ArtifactIndex`. Heights that
+/// are already archived are kept. Returns the number of archived heights.
+pub fn archive_artifact_group(store: &ContentStore, group_dir: &Path) -> io::Result<usize> {
+    let mut index = ArtifactIndex::load(group_dir)?.unwrap_or_default();
+    let mut height_dirs = Vec::new();
+    for entry in fs::read_dir(group_dir)? {
+        let path = entry?.path();
+        if !path.is_dir() {
+            continue;
+        }
+        let height = match path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.parse::<u64>().ok())
+        {
+            Some(height) => height,
+            None => continue,
+        };
+        let files = index.heights.entry(height).or_default();
+        for file in fs::read_dir(&path)? {
+            let file = file?;
+            let key = store.put_content(&fs::read(file.path())?)?;
+            files.insert(
+                file.file_name().to_string_lossy().to_string(),
+                hex::encode(key),
+            );
+        }
+        height_dirs.push(path);
+    }
+
+    let bytes = serde_json::to_vec(&index)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+    let index_path = group_dir.join(ARTIFACT_INDEX_FILE);
+    let tmp_path = index_path.with_extension("tmp");
+    let mut file = fs::File::create(&tmp_path)?;
+    file.write_all(&bytes)?;
+    file.sync_all()?;
+    fs::rename(&tmp_path, &index_path)?;
+
+    // Only remove the artifacts once the index referencing them is persisted.
+    for dir in height_dirs.iter() {
+        fs::remove_dir_all(dir)?;
+    }
+    Ok(height_dirs.len())
+}
+
+/// Computes the manifest of the checkpoint at `checkpoint_dir`.
+pub fn compute_checkpoint_manifest(checkpoint_dir: &Path) -> Result<Manifest, String> {
+    let cp_layout = CheckpointLayout::<ReadOnly>::new(checkpoint_dir.to_path_buf(), Height::new(0))
+        .map_err(|e| format!("Failed to create checkpoint layout: {}", e))?;
+    let metadata = cp_layout
+        .system_metadata()
+        .deserialize()
+        .map_err(|e| format!("Failed to deserialize system metadata: {}", e))?;
+    let mut thread_pool = scoped_threadpool::Pool::new(NUMBER_OF_CHECKPOINT_THREADS);
+    let manifest_metrics = ManifestMetrics::new(&MetricsRegistry::new());
+    compute_manifest(
+        &mut thread_pool,
+        &manifest_metrics,
+        &no_op_logger(),
+        metadata.state_sync_version,
+        checkpoint_dir,
+        DEFAULT_CHUNK_SIZE,
+        None,
+    )
+    .map_err(|e| format!("Failed to compute manifest: {}", e))
+}
+
+/// Stores the chunks of the checkpoint at `checkpoint_dir` in `store` and
+/// writes the manifest of the checkpoint to `manifest_path`.
+pub fn archive_checkpoint(
+    store: &ContentStore,
+    checkpoint_dir: &Path,
+    manifest_path: &Path,
+) -> Result<Manifest, String> {
+    let manifest = compute_checkpoint_manifest(checkpoint_dir)?;
+    for chunk in manifest.chunk_table.iter() {
+        if store.contains(&chunk.hash) {
+            continue;
+        }
+        let file_info = &manifest.file_table[chunk.file_index as usize];
+        let path = checkpoint_dir.join(&file_info.relative_path);
+        let mut bytes = vec![0; chunk.size_bytes as usize];
+        fs::File::open(&path)
+            .and_then(|mut file| {
+                file.seek(SeekFrom::Start(chunk.offset))?;
+                file.read_exact(&mut bytes)
+            })
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        store
+            .put(&chunk.hash, &bytes)
+            .map_err(|e| format!("Failed to store a chunk of {}: {}", path.display(), e))?;
+    }
+
+    fs::write(manifest_path, encode_manifest(&manifest))
+        .map_err(|e| format!("Failed to write {}: {}", manifest_path.display(), e))?;
+    Ok(manifest)
+}
+
+/// Reads the manifest of an archived checkpoint.
+pub fn load_checkpoint_manifest(manifest_path: &Path) -> Result<Manifest, String> {
+    let bytes = fs::read(manifest_path)
+        .map_err(|e| format!("Failed to read {}: {}", manifest_path.display(), e))?;
+    decode_manifest(&bytes)
+}
+
+/// Recreates the checkpoint described by `manifest` in the directory `dst`
+/// from the chunks in `store`, validating every chunk against the manifest.
+pub fn restore_checkpoint(
+    store: &ContentStore,
+    manifest: &Manifest,
+    dst: &Path,
+) -> Result<(), String> {
+    let mut files = Vec::with_capacity(manifest.file_table.len());
+    for file_info in manifest.file_table.iter() {
+        let path = dst.join(&file_info.relative_path);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        }
+        let file = fs::File::create(&path)
+            .map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+        files.push((path, file));
+    }
+
+    for (ix, chunk) in manifest.chunk_table.iter().enumerate() {
+        let bytes = store
+            .get(&chunk.hash)
+            .map_err(|e| format!("Failed to load chunk {}: {}", hex::encode(chunk.hash), e))?;
+        validate_chunk(ix, &bytes, manifest).map_err(|e| format!("{}", e))?;
+        let (path, file) = &mut files[chunk.file_index as usize];
+        file.seek(SeekFrom::Start(chunk.offset))
+            .and_then(|_| file.write_all(&bytes))
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    }
+
+    for (path, file) in files.iter() {
+        file.sync_all()
+            .map_err(|e| format!("Failed to sync {}: {}", path.display(), e))?;
+    }
+    Ok(())
+}
+
+/// Returns the keys of all chunks referenced by `manifest`.
+pub fn checkpoint_keys(manifest: &Manifest) -> impl Iterator<Item = ObjectKey> + '_ {
+    manifest.chunk_table.iter().map(|chunk| chunk.hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_protobuf::state::system_metadata::v1 as pb_metadata;
+    use ic_state_manager::manifest::STATE_SYNC_V1;
+    use prost::Message;
+
+    fn write_file(path: &Path, bytes: &[u8]) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, bytes).unwrap();
+    }
+
+    #[test]
+    fn put_and_get_round_trip() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = ContentStore::for_spool(tmp.path());
+        let bytes = vec![7; 10_000];
+
+        let key = store.put_content(&bytes).unwrap();
+        assert_eq!(key, Sha256::hash(&bytes));
+        assert!(store.contains(&key));
+        assert_eq!(store.get(&key).unwrap(), bytes);
+        assert_eq!(store.keys().unwrap(), BTreeSet::from([key]));
+
+        // Storing the same content again writes nothing.
+        assert_eq!(store.put(&key, &bytes).unwrap(), 0);
+        assert!(store.get(&[0; 32]).is_err());
+    }
+
+    #[test]
+    fn garbage_collection_keeps_live_objects() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = ContentStore::for_spool(tmp.path());
+        let live = store.put_content(b"live").unwrap();
+        let dead = store.put_content(b"dead").unwrap();
+
+        assert_eq!(store.collect_garbage(&BTreeSet::from([live])).unwrap(), 1);
+        assert_eq!(store.get(&live).unwrap(), b"live");
+        assert!(!store.contains(&dead));
+
+        // Collecting again with the same live set is a no-op.
+        assert_eq!(store.collect_garbage(&BTreeSet::from([live])).unwrap(), 0);
+        assert_eq!(store.keys().unwrap(), BTreeSet::from([live]));
+    }
+
+    #[test]
+    fn archived_artifacts_can_be_read_from_the_store() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = ContentStore::for_spool(tmp.path());
+        let group_dir = tmp.path().join("subnet").join("version").join("0");
+        write_file(&group_dir.join("1").join("finalization.bin"), b"fin 1");
+        write_file(&group_dir.join("1").join("notarization.bin"), b"not 1");
+        write_file(&group_dir.join("2").join("finalization.bin"), b"fin 2");
+
+        assert_eq!(archive_artifact_group(&store, &group_dir).unwrap(), 2);
+        assert!(!group_dir.join("1").exists());
+        assert!(!group_dir.join("2").exists());
+
+        // A height backed up after archiving is added to the existing index.
+        write_file(&group_dir.join("3").join("finalization.bin"), b"fin 3");
+        assert_eq!(archive_artifact_group(&store, &group_dir).unwrap(), 1);
+
+        let index = ArtifactIndex::load(&group_dir).unwrap().unwrap();
+        assert_eq!(
+            index.heights.keys().copied().collect::<Vec<_>>(),
+            vec![1, 2, 3]
+        );
+        let read = |height: u64, file: &str| {
+            let key = parse_key(&index.heights[&height][file]).unwrap();
+            store.get(&key).unwrap()
+        };
+        assert_eq!(read(1, "finalization.bin"), b"fin 1");
+        assert_eq!(read(1, "notarization.bin"), b"not 1");
+        assert_eq!(read(2, "finalization.bin"), b"fin 2");
+        assert_eq!(read(3, "finalization.bin"), b"fin 3");
+
+        // Garbage collection keeps everything the index references.
+        let live = index.keys().collect::<BTreeSet<_>>();
+        assert_eq!(store.collect_garbage(&live).unwrap(), 0);
+        assert_eq!(read(2, "finalization.bin"), b"fin 2");
+    }
+
+    #[test]
+    fn archived_checkpoint_can_be_restored() {
+        let tmp = tempfile::tempdir().unwrap();
+        let store = ContentStore::for_spool(tmp.path());
+        let checkpoint_dir = tmp.path().join("checkpoint");
+        let metadata = pb_metadata::SystemMetadata {
+            state_sync_version: STATE_SYNC_V1,
+            ..Default::default()
+        };
+        write_file(
+            &checkpoint_dir.join("system_metadata.pbuf"),
+            &metadata.encode_to_vec(),
+        );
+        // A file spanning more than one chunk.
+        let big_file: Vec<u8> = (0..DEFAULT_CHUNK_SIZE as usize * 3 / 2)
+            .map(|i| (i % 251) as u8)
+            .collect();
+        write_file(
+            &checkpoint_dir.join("canister_states/1/vmemory_0.bin"),
+            &big_file,
+        );
+        write_file(
+            &checkpoint_dir.join("canister_states/1/queues.pbuf"),
+            b"queues",
+        );
+
+        let manifest_path = tmp.path().join(CHECKPOINT_MANIFEST_FILE);
+        let manifest = archive_checkpoint(&store, &checkpoint_dir, &manifest_path).unwrap();
+        assert!(manifest.chunk_table.len() > manifest.file_table.len());
+        assert!(checkpoint_keys(&manifest).all(|key| store.contains(&key)));
+
+        let loaded = load_checkpoint_manifest(&manifest_path).unwrap();
+        assert_eq!(loaded, manifest);
+
+        let restored_dir = tmp.path().join("restored");
+        restore_checkpoint(&store, &loaded, &restored_dir).unwrap();
+        for file_info in manifest.file_table.iter() {
+            assert_eq!(
+                fs::read(restored_dir.join(&file_info.relative_path)).unwrap(),
+                fs::read(checkpoint_dir.join(&file_info.relative_path)).unwrap(),
+                "{} differs",
+                file_info.relative_path.display()
+            );
+        }
+        assert_eq!(
+            compute_checkpoint_manifest(&restored_dir).unwrap(),
+            manifest
+        );
+    }
+}
@@ -0,0 +1,47 @@
+//! Helpers for restore drills, which check that a backup is usable by
+//! replaying it from an archived checkpoint up to a catch-up package and
+//! comparing the state hash certified in the catch-up package with the hash of
+//! the replayed checkpoint.
+
+use crate::backup::{heights_to_artifacts_metadata, read_cup_at_height};
+use crate::content_store::{compute_checkpoint_manifest, ContentStore};
+use ic_state_manager::manifest::manifest_hash;
+use ic_types::Height;
+use std::{collections::BTreeSet, path::Path};
+
+/// Returns the heights of all catch-up packages in the backup directory of a
+/// replica version, including the archived ones.
+pub fn cup_heights(backup_dir: &Path) -> Result<BTreeSet<u64>, String> {
+    let heights = heights_to_artifacts_metadata(backup_dir, Height::from(0))
+        .map_err(|e| format!("Failed to scan {}: {}", backup_dir.display(), e))?;
+    Ok(heights
+        .into_iter()
+        .filter(|(_, artifacts)| artifacts.contains_cup())
+        .map(|(height, _)| height.get())
+        .collect())
+}
+
+/// Returns the state hash certified in the catch-up package at `height` in the
+/// backup directory of a replica version.
+pub fn cup_state_hash(
+    backup_dir: &Path,
+    store: &ContentStore,
+    height: u64,
+) -> Result<Vec<u8>, String> {
+    if !cup_heights(backup_dir)?.contains(&height) {
+        return Err(format!(
+            "No catch-up package at height {} in {}",
+            height,
+            backup_dir.display()
+        ));
+    }
+    let cup = read_cup_at_height(backup_dir, store, Height::from(height))?;
+    Ok(cup.content.state_hash.get_ref().0.clone())
+}
+
+/// Computes the state hash of the checkpoint at `checkpoint_dir`, which is the
+/// root hash of its manifest.
+pub fn checkpoint_state_hash(checkpoint_dir: &Path) -> Result<Vec<u8>, String> {
+    let manifest = compute_checkpoint_manifest(checkpoint_dir)?;
+    Ok(manifest_hash(&manifest).to_vec())
+}
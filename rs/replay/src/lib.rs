@@ -12,11 +12,16 @@
 //! state (after all past blocks have been executed). All of them are meant to
 //! help recover NNS subnet where the registry canister resides.
 //!
+//! With `--step`, the tool prints the executed ingress messages and the changes
+//! of the certified state after every replayed batch, which helps to find the
+//! first height at which the states of two replicas diverged.
+//!
 //! Use `ic-replay --help` to find out more.
 
 use crate::cmd::{ReplayToolArgs, SubCommand};
 use crate::ingress::*;
 use crate::player::{Player, ReplayResult};
+use crate::step::StepConfig;
 
 use cmd::RestoreFromBackupCmd;
 use ic_canister_client::{Agent, Sender};
@@ -36,9 +41,12 @@ use std::rc::Rc;
 
 mod backup;
 pub mod cmd;
+pub mod content_store;
+pub mod drill;
 pub mod ingress;
 mod mocks;
 pub mod player;
+pub mod step;
 mod validator;
 
 /// Replays the past blocks and creates a checkpoint of the latest state.
@@ -60,6 +68,9 @@ mod validator;
 ///     canister_caller_id: None,
 ///     replay_until_height: None,
 ///     data_root: None,
+///     step: None,
+///     step_canisters: vec![],
+///     dump_instructions: false,
 ///     subcmd: Some(SubCommand::RestoreFromBackup(RestoreFromBackupCmd {
 ///         registry_local_store_path: PathBuf::from("/path/to/ic_registry_local_store"),
 ///         backup_spool_path: PathBuf::from("/path/to/spool"),
@@ -116,6 +127,11 @@ pub fn replay(args: ReplayToolArgs) -> ReplayResult {
             .0;
 
         let target_height = args.replay_until_height;
+        let step = args.step.map(|mode| StepConfig {
+            mode,
+            canisters: args.step_canisters.iter().copied().collect(),
+            dump_instructions: args.dump_instructions,
+        });
         if let Some(h) = target_height {
             let question = format!("The checkpoint created at height {} ", h)
                 + "cannot be used for deterministic state computation if it is not a CUP height.\n"
@@ -150,7 +166,8 @@ pub fn replay(args: ReplayToolArgs) -> ReplayResult {
                 cmd.start_height,
                 is_new,
             )
-            .with_replay_target_height(target_height);
+            .with_replay_target_height(target_height)
+            .with_step_config(step);
             *res_clone.borrow_mut() = player.restore(cmd.start_height + 1);
             return;
         }
@@ -163,9 +180,9 @@ pub fn replay(args: ReplayToolArgs) -> ReplayResult {
                     "Target height cannot be used with any sub-command in subnet-recovery mode."
                 );
                 }
-                (_, target_height) => {
-                    Player::new(cfg, subnet_id).with_replay_target_height(target_height)
-                }
+                (_, target_height) => Player::new(cfg, subnet_id)
+                    .with_replay_target_height(target_height)
+                    .with_step_config(step),
             };
 
             if let Some(SubCommand::GetRecoveryCup(cmd)) = subcmd {
@@ -1,6 +1,8 @@
 use crate::ingress::IngressWithPrinter;
 use crate::{
     backup,
+    content_store::ContentStore,
+    step::{StepConfig, Stepper},
     validator::{InvalidArtifact, ReplayValidator},
 };
 use ic_artifact_pool::{
@@ -15,10 +17,11 @@ use ic_consensus::{
     certification::VerifierImpl,
     consensus::{
         batch_delivery::deliver_batches, pool_reader::PoolReader, utils::crypto_hashable_to_seed,
+        BatchStats, BlockStats,
     },
 };
 use ic_cycles_account_manager::CyclesAccountManager;
-use ic_execution_environment::ExecutionServices;
+use ic_execution_environment::{ExecutedInstructionsRecorder, ExecutionServices};
 use ic_interfaces::crypto::ThresholdSigVerifierByPublicKey;
 use ic_interfaces::{
     certification::CertificationPool,
@@ -107,6 +110,7 @@ pub struct Player {
     validator: Option<ReplayValidator>,
     http_query_handler: Arc<dyn QueryHandler<State = ReplicatedState>>,
     ingress_history_reader: Box<dyn IngressHistoryReader>,
+    executed_instructions_recorder: Arc<ExecutedInstructionsRecorder>,
     certification_pool: Option<CertificationPoolImpl>,
     pub registry: Arc<RegistryClientImpl>,
     local_store_path: Option<PathBuf>,
@@ -116,10 +120,14 @@ pub struct Player {
     /// The id of the subnet where the artifacts are taken from.
     pub subnet_id: SubnetId,
     backup_dir: Option<PathBuf>,
+    // The store holding the archived artifacts of the backup spool.
+    content_store: Option<ContentStore>,
     tmp_dir: Option<TempDir>,
     // The target height until which the state will be replayed.
     // None means finalized height.
     replay_target_height: Option<u64>,
+    // If set, the effects of each delivered batch are printed.
+    step: Option<StepConfig>,
 }
 
 impl Player {
@@ -182,8 +190,11 @@ impl Player {
         let backup_dir = backup_spool_path
             .join(subnet_id.to_string())
             .join(replica_version.to_string());
+        let content_store = ContentStore::for_spool(backup_spool_path);
         // Extract the genesis CUP and instantiate a new pool.
-        let initial_cup = backup::read_cup_at_height(&backup_dir, Height::from(start_height));
+        let initial_cup =
+            backup::read_cup_at_height(&backup_dir, &content_store, Height::from(start_height))
+                .unwrap_or_else(|err| panic!("{}", err));
         // This would create a new pool with just the genesis CUP.
         let pool = ConsensusPoolImpl::new_from_cup_without_bytes(
             subnet_id,
@@ -204,6 +215,7 @@ impl Player {
             _async_log_guard,
         );
         player.tmp_dir = Some(tmp_dir);
+        player.content_store = Some(content_store);
         player
     }
 
@@ -352,16 +364,19 @@ impl Player {
             validator,
             http_query_handler: execution_service.sync_query_handler,
             ingress_history_reader: execution_service.ingress_history_reader,
+            executed_instructions_recorder: execution_service.executed_instructions_recorder,
             certification_pool,
             registry,
             local_store_path,
             subnet_id,
             replica_version,
             backup_dir,
+            content_store: None,
             log,
             _async_log_guard,
             tmp_dir: None,
             replay_target_height: None,
+            step: None,
         }
     }
 
@@ -371,6 +386,12 @@ impl Player {
         self
     }
 
+    /// Set the step-through mode
+    pub fn with_step_config(mut self, step: Option<StepConfig>) -> Self {
+        self.step = step;
+        self
+    }
+
     /// Replay past finalized but un-executed blocks by delivering ingress
     /// messages for execution, and make a full checkpoint of the latest
     /// state when they all finish.
@@ -576,6 +597,25 @@ impl Player {
         replay_target_height: Option<Height>,
     ) -> Height {
         let expected_batch_height = message_routing.expected_batch_height();
+        let stepper = self.step.as_ref().map(|config| {
+            Stepper::new(
+                &self.state_manager,
+                &self.executed_instructions_recorder,
+                config,
+            )
+        });
+        let step =
+            |result: &Result<(), MessageRoutingError>, _: BlockStats, batch_stats: BatchStats| {
+                if let (Some(stepper), Ok(())) = (&stepper, result) {
+                    stepper.step(
+                        Height::from(batch_stats.batch_height),
+                        &batch_stats.ingress_ids,
+                    );
+                }
+            };
+        let result_processor = stepper
+            .as_ref()
+            .map(|_| &step as &dyn Fn(&Result<(), MessageRoutingError>, BlockStats, BatchStats));
         let last_batch_height = loop {
             match deliver_batches(
                 message_routing,
@@ -585,7 +625,7 @@ impl Player {
                 self.replica_version.clone(),
                 &self.log,
                 replay_target_height,
-                None,
+                result_processor,
             ) {
                 Ok(h) => break h,
                 Err(MessageRoutingError::QueueIsFull) => std::thread::sleep(WAIT_DURATION),
@@ -822,6 +862,7 @@ impl Player {
             .as_ref()
             .expect("No backup path found")
             .clone();
+        let content_store = self.content_store.clone().expect("No content store found");
         let start_height = Height::from(start_height);
         let mut height_to_batches =
             backup::heights_to_artifacts_metadata(&backup_dir, start_height)
@@ -846,6 +887,7 @@ impl Player {
                 self.registry.clone(),
                 self.consensus_pool.as_mut().unwrap(),
                 &mut height_to_batches,
+                &content_store,
                 self.subnet_id,
                 self.state_manager.latest_state_height(),
                 self.validator.as_ref().unwrap(),
@@ -873,6 +915,7 @@ impl Player {
                     backup::insert_cup_at_height(
                         self.consensus_pool.as_mut().unwrap(),
                         &backup_dir,
+                        &content_store,
                         cup_height,
                     );
                     self.assert_consistency_and_clean_up()?;
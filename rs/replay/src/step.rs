@@ -0,0 +1,269 @@
+//! Step-through mode of the replay: after each delivered batch, the replay
+//! prints what the batch changed in the certified state, so that the output of
+//! two replays can be compared to find the first height at which they diverge.
+
+use crate::cmd::StepMode;
+use ic_crypto_tree_hash::HashTree;
+use ic_execution_environment::ExecutedInstructionsRecorder;
+use ic_interfaces_state_manager::StateReader;
+use ic_replicated_state::ReplicatedState;
+use ic_state_manager::{
+    tree_diff::{diff, Changes, PrettyPrintedChanges},
+    tree_hash::hash_state,
+    StateManagerImpl,
+};
+use ic_types::{
+    artifact::IngressMessageId,
+    ingress::{IngressState, IngressStatus},
+    CanisterId, Height,
+};
+use std::{
+    cell::{Cell, RefCell},
+    collections::BTreeSet,
+    io::{stdin, stdout, Write},
+    sync::Arc,
+    time::Duration,
+};
+
+// Amount of time we are waiting for the execution of a single batch.
+const WAIT_DURATION: Duration = Duration::from_millis(100);
+
+/// The label of the subtree of the certified state holding the canisters.
+const CANISTER_LABEL: &[u8] = b"canister";
+
+/// Configuration of the step-through mode.
+#[derive(Clone, Debug)]
+pub struct StepConfig {
+    /// Whether the replay waits for the user after each batch.
+    pub mode: StepMode,
+    /// The canisters whose state changes are printed. All changes of the
+    /// certified state are printed if the set is empty.
+    pub canisters: BTreeSet<CanisterId>,
+    /// Whether to print the number of instructions executed by each canister.
+    pub dump_instructions: bool,
+}
+
+impl StepConfig {
+    /// Returns whether changes of the canister with the given raw id should be
+    /// printed.
+    fn is_selected(&self, canister_id: &[u8]) -> bool {
+        self.canisters.is_empty()
+            || self
+                .canisters
+                .iter()
+                .any(|id| id.get_ref().as_slice() == canister_id)
+    }
+
+    /// Restricts `changes` to the subtrees of the selected canisters.
+    fn filter_changes(&self, changes: Changes) -> Changes {
+        if self.canisters.is_empty() {
+            return changes;
+        }
+        changes
+            .into_iter()
+            .filter(|(path, _)| {
+                let mut labels = path.iter();
+                labels.next().map(|label| label.as_bytes()) == Some(CANISTER_LABEL)
+                    && labels
+                        .next()
+                        .map_or(false, |label| self.is_selected(label.as_bytes()))
+            })
+            .collect()
+    }
+}
+
+/// Prints the effects of each batch delivered to message routing.
+pub(crate) struct Stepper<'a> {
+    state_manager: &'a StateManagerImpl,
+    executed_instructions_recorder: &'a ExecutedInstructionsRecorder,
+    config: &'a StepConfig,
+    /// The state after the previous batch and its hash tree.
+    previous: RefCell<(Arc<ReplicatedState>, HashTree)>,
+    /// Set once the user asked to continue without stopping.
+    resumed: Cell<bool>,
+}
+
+impl<'a> Stepper<'a> {
+    /// Creates a stepper that starts from the latest state of `state_manager`.
+    pub fn new(
+        state_manager: &'a StateManagerImpl,
+        executed_instructions_recorder: &'a ExecutedInstructionsRecorder,
+        config: &'a StepConfig,
+    ) -> Self {
+        let state = state_manager.get_latest_state().take();
+        let tree = hash_state(&state);
+        if config.dump_instructions {
+            executed_instructions_recorder.enable();
+        }
+        Self {
+            state_manager,
+            executed_instructions_recorder,
+            config,
+            previous: RefCell::new((state, tree)),
+            resumed: Cell::new(false),
+        }
+    }
+
+    /// Waits until the batch at `height` is executed, then prints the ingress
+    /// messages `inducted` by the batch, the ingress messages that finished
+    /// executing and the changes of the certified state.
+    pub fn step(&self, height: Height, inducted: &[IngressMessageId]) {
+        while self.state_manager.latest_state_height() < height {
+            std::thread::sleep(WAIT_DURATION);
+        }
+        let state = self
+            .state_manager
+            .get_state_at(height)
+            .unwrap_or_else(|err| panic!("Failed to get the state at height {}: {:?}", height, err))
+            .take();
+        let tree = hash_state(&state);
+        let previous = self.previous.borrow();
+        let (previous_state, previous_tree) = &*previous;
+
+        println!("=== Batch {} ===", height);
+        println!("Certified state hash: {}", hex::encode(tree.digest().0));
+
+        println!("Inducted ingress messages: {}", inducted.len());
+        for id in inducted {
+            println!("  {}", id.message_id);
+        }
+
+        println!("Executed ingress messages:");
+        for (message_id, status) in state.metadata.ingress_history.statuses() {
+            if let IngressStatus::Known {
+                receiver,
+                state: ingress_state,
+                ..
+            } = status
+            {
+                let finished = matches!(
+                    ingress_state,
+                    IngressState::Completed(_) | IngressState::Failed(_)
+                );
+                let changed =
+                    previous_state.metadata.ingress_history.get(message_id) != Some(status);
+                if finished && changed && self.config.is_selected(receiver.as_slice()) {
+                    println!("  {} {} {}", message_id, receiver, status.as_str());
+                }
+            }
+        }
+
+        let changes = self.config.filter_changes(diff(previous_tree, &tree));
+        if changes.is_empty() {
+            println!("No changes of the certified state.");
+        } else {
+            println!("Changes of the certified state:");
+            print!("{}", PrettyPrintedChanges(&changes));
+        }
+
+        if self.config.dump_instructions {
+            println!("Executed instructions:");
+            for (canister_id, instructions) in self.executed_instructions_recorder.take() {
+                if self.config.is_selected(canister_id.get_ref().as_slice()) {
+                    println!("  {} {}", canister_id, instructions.get());
+                }
+            }
+        }
+
+        drop(previous);
+        *self.previous.borrow_mut() = (state, tree);
+        self.pause();
+    }
+
+    /// In interactive mode, waits until the user asks for the next batch.
+    fn pause(&self) {
+        if self.config.mode != StepMode::Interactive || self.resumed.get() {
+            return;
+        }
+        println!("Press Enter for the next batch or type `c` to continue without stopping.");
+        let _ = stdout().flush();
+        let mut s = String::new();
+        stdin().read_line(&mut s).expect("Couldn't read user input");
+        if s.trim() == "c" {
+            self.resumed.set(true);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_crypto_tree_hash::{Digest, Label, Path};
+    use ic_state_manager::tree_diff::Change;
+
+    fn config(canisters: &[CanisterId]) -> StepConfig {
+        StepConfig {
+            mode: StepMode::Scripted,
+            canisters: canisters.iter().copied().collect(),
+            dump_instructions: false,
+        }
+    }
+
+    fn path(labels: &[&[u8]]) -> Path {
+        labels.iter().map(|label| Label::from(*label)).collect()
+    }
+
+    #[test]
+    fn all_canisters_are_selected_without_filter() {
+        let config = config(&[]);
+        assert!(config.is_selected(CanisterId::from_u64(1).get_ref().as_slice()));
+        assert!(config.is_selected(b"anything"));
+    }
+
+    #[test]
+    fn only_listed_canisters_are_selected() {
+        let config = config(&[CanisterId::from_u64(1), CanisterId::from_u64(3)]);
+        assert!(config.is_selected(CanisterId::from_u64(1).get_ref().as_slice()));
+        assert!(config.is_selected(CanisterId::from_u64(3).get_ref().as_slice()));
+        assert!(!config.is_selected(CanisterId::from_u64(2).get_ref().as_slice()));
+    }
+
+    #[test]
+    fn filter_changes_keeps_everything_without_filter() {
+        let changes = Changes::from([
+            (path(&[b"time"]), Change::InsertLeaf(Digest([1; 32]))),
+            (
+                path(&[CANISTER_LABEL, CanisterId::from_u64(2).get_ref().as_slice()]),
+                Change::DeleteSubtree,
+            ),
+        ]);
+        assert_eq!(config(&[]).filter_changes(changes.clone()), changes);
+    }
+
+    #[test]
+    fn filter_changes_keeps_only_selected_canister_subtrees() {
+        let selected = CanisterId::from_u64(1);
+        let other = CanisterId::from_u64(2);
+        let selected_change = (
+            path(&[
+                CANISTER_LABEL,
+                selected.get_ref().as_slice(),
+                b"certified_data",
+            ]),
+            Change::InsertLeaf(Digest([1; 32])),
+        );
+        let changes = Changes::from([
+            selected_change.clone(),
+            (
+                path(&[
+                    CANISTER_LABEL,
+                    other.get_ref().as_slice(),
+                    b"certified_data",
+                ]),
+                Change::InsertLeaf(Digest([2; 32])),
+            ),
+            // The canister subtree itself carries no canister id.
+            (path(&[CANISTER_LABEL]), Change::InsertEmptyFork),
+            // Paths outside of the canister subtree are dropped even if they
+            // contain the id of a selected canister.
+            (
+                path(&[b"request_status", selected.get_ref().as_slice()]),
+                Change::DeleteSubtree,
+            ),
+        ]);
+        assert_eq!(
+            config(&[selected]).filter_changes(changes),
+            Changes::from([selected_change])
+        );
+    }
+}
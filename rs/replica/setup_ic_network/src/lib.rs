@@ -19,7 +19,7 @@ use ic_consensus::{
     consensus::{pool_reader::PoolReader, ConsensusCrypto, Membership},
     dkg, ecdsa,
 };
-use ic_crypto_tls_interfaces::TlsHandshake;
+use ic_crypto_tls_interfaces::{TlsConfig, TlsHandshake};
 use ic_cycles_account_manager::CyclesAccountManager;
 use ic_ingress_manager::IngressManager;
 use ic_interfaces::{
@@ -28,6 +28,7 @@ use ic_interfaces::{
     crypto::{Crypto, IngressSigVerifier},
     execution_environment::IngressHistoryReader,
     messaging::{MessageRouting, XNetPayloadBuilder},
+    query_stats::QueryStatsPayloadBuilder,
     self_validating_payload::SelfValidatingPayloadBuilder,
     time_source::SysTimeSource,
 };
@@ -41,7 +42,7 @@ use ic_p2p::{start_p2p, AdvertBroadcaster, P2PThreadJoiner};
 use ic_registry_client_helpers::subnet::SubnetRegistry;
 use ic_replicated_state::ReplicatedState;
 use ic_state_manager::StateManagerImpl;
-use ic_transport::transport::create_transport;
+use ic_transport::transport::{create_switching_transport, SwitchingTransport};
 use ic_types::{
     artifact::{Advert, ArtifactKind, ArtifactTag, FileTreeSyncAttribute},
     consensus::catchup::CUPWithOriginalProtobuf,
@@ -52,7 +53,8 @@ use ic_types::{
     replica_config::ReplicaConfig,
     NodeId, SubnetId,
 };
-use std::sync::{Arc, Mutex, RwLock};
+use std::sync::{Arc, Mutex, RwLock, Weak};
+use std::time::Duration;
 
 /// The P2P state sync client.
 #[derive(Clone)]
@@ -100,10 +102,12 @@ pub fn create_networking_stack(
     // constructs it from the 'transport_config'.
     transport: Option<Arc<dyn Transport>>,
     tls_handshake: Arc<dyn TlsHandshake + Send + Sync>,
+    tls_config: Arc<dyn TlsConfig + Send + Sync>,
     state_manager: Arc<dyn StateManager<State = ReplicatedState>>,
     state_sync_client: P2PStateSyncClient,
     xnet_payload_builder: Arc<dyn XNetPayloadBuilder>,
     self_validating_payload_builder: Arc<dyn SelfValidatingPayloadBuilder>,
+    query_stats_payload_builder: Arc<dyn QueryStatsPayloadBuilder>,
     message_router: Arc<dyn MessageRouting>,
     crypto: Arc<dyn Crypto + Send + Sync>,
     consensus_crypto: Arc<dyn ConsensusCrypto + Send + Sync>,
@@ -136,6 +140,7 @@ pub fn create_networking_stack(
         state_sync_client,
         xnet_payload_builder,
         self_validating_payload_builder,
+        query_stats_payload_builder,
         message_router,
         ingress_history_reader,
         artifact_pools,
@@ -149,16 +154,27 @@ pub fn create_networking_stack(
     .unwrap();
 
     let transport = transport.unwrap_or_else(|| {
-        create_transport(
+        let registry_version = registry_client.get_latest_version();
+        let use_quic = use_quic_transport(registry_client.as_ref(), subnet_id);
+        info!(log, "Creating transport: use_quic = {}", use_quic);
+        let transport = create_switching_transport(
             node_id,
             transport_config.clone(),
-            registry_client.get_latest_version(),
+            registry_version,
             metrics_registry.clone(),
             tls_handshake,
+            tls_config,
             rt_handle.clone(),
             log.clone(),
-            false,
-        )
+            use_quic,
+        );
+        start_transport_switch_thread(
+            Arc::downgrade(&transport),
+            Arc::clone(&registry_client),
+            subnet_id,
+            Duration::from_millis(registry_poll_delay_duration_ms),
+        );
+        transport as Arc<_>
     });
 
     let ingress_event_handler = {
@@ -186,6 +202,39 @@ pub fn create_networking_stack(
     (ingress_event_handler, p2p_thread)
 }
 
+/// Returns whether the `quic_transport` feature is enabled for the subnet at
+/// the latest registry version.
+fn use_quic_transport(registry_client: &dyn RegistryClient, subnet_id: SubnetId) -> bool {
+    registry_client
+        .get_features(subnet_id, registry_client.get_latest_version())
+        .ok()
+        .flatten()
+        .map_or(false, |features| features.quic_transport)
+}
+
+/// Starts a thread that polls the registry and selects the transport
+/// implementation according to the `quic_transport` subnet feature. The
+/// thread terminates once the transport is dropped.
+fn start_transport_switch_thread(
+    transport: Weak<SwitchingTransport>,
+    registry_client: Arc<dyn RegistryClient>,
+    subnet_id: SubnetId,
+    poll_delay: Duration,
+) {
+    std::thread::Builder::new()
+        .name("TransportSwitch".to_string())
+        .spawn(move || loop {
+            std::thread::sleep(poll_delay);
+            match transport.upgrade() {
+                Some(transport) => {
+                    transport.set_use_quic(use_quic_transport(registry_client.as_ref(), subnet_id))
+                }
+                None => return,
+            }
+        })
+        .expect("Failed to spawn the transport switch thread");
+}
+
 /// The function sets up and returns the Artifact Manager and Consensus Pool.
 ///
 /// The Artifact Manager runs all artifact clients as separate actors.
@@ -207,6 +256,7 @@ fn setup_artifact_manager(
     state_sync_client: P2PStateSyncClient,
     xnet_payload_builder: Arc<dyn XNetPayloadBuilder>,
     self_validating_payload_builder: Arc<dyn SelfValidatingPayloadBuilder>,
+    query_stats_payload_builder: Arc<dyn QueryStatsPayloadBuilder>,
     message_router: Arc<dyn MessageRouting>,
     ingress_history_reader: Box<dyn IngressHistoryReader>,
     artifact_pools: &ArtifactPools,
@@ -314,6 +364,7 @@ fn setup_artifact_manager(
                     Arc::clone(&xnet_payload_builder) as Arc<_>,
                     Arc::clone(&self_validating_payload_builder) as Arc<_>,
                     Arc::clone(&canister_http_payload_builder) as Arc<_>,
+                    Arc::clone(&query_stats_payload_builder) as Arc<_>,
                     Arc::clone(&artifact_pools.dkg_pool) as Arc<_>,
                     Arc::clone(&artifact_pools.ecdsa_pool) as Arc<_>,
                     Arc::clone(&dkg_key_manager) as Arc<_>,
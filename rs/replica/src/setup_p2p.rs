@@ -4,7 +4,7 @@ use ic_config::{artifact_pool::ArtifactPoolConfig, subnet_config::SubnetConfig,
 use ic_consensus::certification::VerifierImpl;
 use ic_crypto::CryptoComponent;
 use ic_cycles_account_manager::CyclesAccountManager;
-use ic_execution_environment::ExecutionServices;
+use ic_execution_environment::{ExecutionServices, QueryStatsPayloadBuilderImpl};
 use ic_interfaces::{
     consensus_pool::ConsensusPoolCache,
     execution_environment::{
@@ -224,6 +224,12 @@ pub fn construct_ic_stack(
     );
     let self_validating_payload_builder = Arc::new(self_validating_payload_builder);
 
+    let query_stats_payload_builder = Arc::new(QueryStatsPayloadBuilderImpl::new(
+        Arc::clone(&execution_services.query_stats_collector),
+        Arc::clone(&state_manager) as Arc<_>,
+        node_id,
+    ));
+
     let canister_http_adapter_client = ic_canister_http_adapter_client::setup_canister_http_client(
         rt_handle.clone(),
         &metrics_registry,
@@ -243,10 +249,12 @@ pub fn construct_ic_stack(
         subnet_id,
         None,
         Arc::clone(&crypto) as Arc<_>,
+        Arc::clone(&crypto) as Arc<_>,
         Arc::clone(&state_manager) as Arc<_>,
         P2PStateSyncClient::Client(Arc::clone(&state_manager) as Arc<_>),
         xnet_payload_builder as Arc<_>,
         self_validating_payload_builder as Arc<_>,
+        query_stats_payload_builder as Arc<_>,
         message_router as Arc<_>,
         // TODO(SCL-213)
         Arc::clone(&crypto) as Arc<_>,
@@ -4,7 +4,8 @@ use ic_config::Config;
 use ic_error_types::{ErrorCode, RejectCode};
 use ic_ic00_types::{
     self as ic00, CanisterIdRecord, CanisterInstallMode, CanisterStatusResultV2,
-    CanisterStatusType, EmptyBlob, InstallCodeArgs, Method, Payload, SetControllerArgs, IC_00,
+    CanisterStatusType, EmptyBlob, InstallCodeArgs, Method, Payload, QueryStats, SetControllerArgs,
+    IC_00,
 };
 use ic_registry_provisional_whitelist::ProvisionalWhitelist;
 use ic_replica_tests as utils;
@@ -679,7 +680,11 @@ fn can_get_canister_information() {
                 ComputeAllocation::default().as_percent(),
                 None,
                 2592000,
+                None,
+                0,
+                None,
                 0u128,
+                QueryStats::default(),
             )
         );
 
@@ -730,7 +735,11 @@ fn can_get_canister_information() {
                     ComputeAllocation::default().as_percent(),
                     None,
                     259200,
+                    None,
+                    0,
+                    None,
                     0u128,
+                    QueryStats::default(),
                 ),
                 CanisterStatusResultV2::decode(&res).unwrap(),
                 2 * BALANCE_EPSILON,
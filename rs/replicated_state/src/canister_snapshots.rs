@@ -0,0 +1,387 @@
+use crate::{
+    canister_state::execution_state::{WasmBinary, WasmMetadata},
+    CanisterState, ExportedFunctions, Global, Memory, NumWasmPages,
+};
+use ic_types::{CanisterId, NumBytes, PrincipalId, Time};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    convert::TryFrom,
+    fmt,
+    sync::Arc,
+};
+
+/// The length of the encoded local id prefix of a `SnapshotId`.
+const LOCAL_ID_LEN: usize = std::mem::size_of::<u64>();
+
+/// A unique identifier of a canister snapshot.
+///
+/// Consists of the id of the canister the snapshot was taken of and a local id
+/// that is unique across all snapshots ever taken of that canister. The binary
+/// representation (returned to users and used as directory name in
+/// checkpoints) is the big-endian local id followed by the canister id bytes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SnapshotId {
+    canister_id: CanisterId,
+    local_id: u64,
+}
+
+impl SnapshotId {
+    pub fn new(canister_id: CanisterId, local_id: u64) -> Self {
+        Self {
+            canister_id,
+            local_id,
+        }
+    }
+
+    pub fn get_canister_id(&self) -> CanisterId {
+        self.canister_id
+    }
+
+    pub fn get_local_id(&self) -> u64 {
+        self.local_id
+    }
+
+    pub fn to_vec(&self) -> Vec<u8> {
+        let mut bytes = self.local_id.to_be_bytes().to_vec();
+        bytes.extend_from_slice(self.canister_id.get_ref().as_slice());
+        bytes
+    }
+}
+
+impl fmt::Display for SnapshotId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for byte in self.to_vec() {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+impl TryFrom<&[u8]> for SnapshotId {
+    type Error = String;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        if bytes.len() <= LOCAL_ID_LEN {
+            return Err(format!(
+                "Snapshot id must be longer than {} bytes, got {}",
+                LOCAL_ID_LEN,
+                bytes.len()
+            ));
+        }
+        let (local_id, canister_id) = bytes.split_at(LOCAL_ID_LEN);
+        let local_id = u64::from_be_bytes(local_id.try_into().unwrap());
+        let principal_id = PrincipalId::try_from(canister_id)
+            .map_err(|err| format!("Invalid canister id in snapshot id: {}", err))?;
+        let canister_id = CanisterId::new(principal_id)
+            .map_err(|err| format!("Invalid canister id in snapshot id: {}", err))?;
+        Ok(Self::new(canister_id, local_id))
+    }
+}
+
+/// The parts of a canister's `ExecutionState` captured by a snapshot.
+#[derive(Clone, Debug)]
+pub struct ExecutionStateSnapshot {
+    /// The Wasm module of the canister.
+    pub wasm_binary: Arc<WasmBinary>,
+    /// The state of exported globals.
+    pub exported_globals: Vec<Global>,
+    /// The functions exported by the Wasm module.
+    pub exports: ExportedFunctions,
+    /// Metadata extracted from the Wasm module.
+    pub metadata: WasmMetadata,
+    /// The heap of the canister.
+    pub wasm_memory: Memory,
+    /// The stable memory of the canister.
+    pub stable_memory: Memory,
+}
+
+// We have to implement it by hand as embedder_cache can not be compared for
+// equality (and doesn't need to be).
+impl PartialEq for ExecutionStateSnapshot {
+    fn eq(&self, rhs: &Self) -> bool {
+        (
+            &self.wasm_binary.binary,
+            &self.exported_globals,
+            &self.exports,
+            &self.metadata,
+            &self.wasm_memory,
+            &self.stable_memory,
+        ) == (
+            &rhs.wasm_binary.binary,
+            &rhs.exported_globals,
+            &rhs.exports,
+            &rhs.metadata,
+            &rhs.wasm_memory,
+            &rhs.stable_memory,
+        )
+    }
+}
+
+/// A snapshot of a canister: its Wasm module, heap, stable memory, globals and
+/// certified data at the time the snapshot was taken.
+///
+/// Cloning the memories is cheap, since `PageMap`s share their pages.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CanisterSnapshot {
+    canister_id: CanisterId,
+    taken_at_timestamp: Time,
+    canister_version: u64,
+    certified_data: Vec<u8>,
+    execution_snapshot: ExecutionStateSnapshot,
+}
+
+impl CanisterSnapshot {
+    pub fn new(
+        canister_id: CanisterId,
+        taken_at_timestamp: Time,
+        canister_version: u64,
+        certified_data: Vec<u8>,
+        execution_snapshot: ExecutionStateSnapshot,
+    ) -> Self {
+        Self {
+            canister_id,
+            taken_at_timestamp,
+            canister_version,
+            certified_data,
+            execution_snapshot,
+        }
+    }
+
+    /// Captures the current state of the given canister.
+    ///
+    /// Returns `None` if the canister has no execution state (i.e. it is
+    /// empty), since there is nothing to capture.
+    pub fn from_canister(canister: &CanisterState, taken_at_timestamp: Time) -> Option<Self> {
+        let execution_state = canister.execution_state.as_ref()?;
+        Some(Self::new(
+            canister.canister_id(),
+            taken_at_timestamp,
+            canister.system_state.canister_version,
+            canister.system_state.certified_data.clone(),
+            ExecutionStateSnapshot {
+                wasm_binary: Arc::clone(&execution_state.wasm_binary),
+                exported_globals: execution_state.exported_globals.clone(),
+                exports: execution_state.exports.clone(),
+                metadata: execution_state.metadata.clone(),
+                // The sandbox memory of the canister must not be shared with
+                // the snapshot, so we create fresh `Memory` objects.
+                wasm_memory: Memory::new(
+                    execution_state.wasm_memory.page_map.clone(),
+                    execution_state.wasm_memory.size,
+                ),
+                stable_memory: Memory::new(
+                    execution_state.stable_memory.page_map.clone(),
+                    execution_state.stable_memory.size,
+                ),
+            },
+        ))
+    }
+
+    pub fn canister_id(&self) -> CanisterId {
+        self.canister_id
+    }
+
+    pub fn taken_at_timestamp(&self) -> &Time {
+        &self.taken_at_timestamp
+    }
+
+    pub fn canister_version(&self) -> u64 {
+        self.canister_version
+    }
+
+    pub fn certified_data(&self) -> &Vec<u8> {
+        &self.certified_data
+    }
+
+    pub fn execution_snapshot(&self) -> &ExecutionStateSnapshot {
+        &self.execution_snapshot
+    }
+
+    pub fn execution_snapshot_mut(&mut self) -> &mut ExecutionStateSnapshot {
+        &mut self.execution_snapshot
+    }
+
+    /// Returns the memory used by this snapshot, computed the same way as
+    /// `ExecutionState::memory_usage()`.
+    pub fn size(&self) -> NumBytes {
+        let snapshot = &self.execution_snapshot;
+        // We use 8 bytes per global.
+        let globals_size_bytes = 8 * snapshot.exported_globals.len() as u64;
+        let wasm_binary_size_bytes = snapshot.wasm_binary.binary.len() as u64;
+        memory_size(snapshot.wasm_memory.size)
+            + memory_size(snapshot.stable_memory.size)
+            + NumBytes::from(globals_size_bytes)
+            + NumBytes::from(wasm_binary_size_bytes)
+            + NumBytes::from(self.certified_data.len() as u64)
+    }
+}
+
+fn memory_size(pages: NumWasmPages) -> NumBytes {
+    crate::num_bytes_try_from(pages)
+        .expect("could not convert from snapshot memory number of pages to bytes")
+}
+
+/// A change to the set of snapshots that is not yet reflected on disk.
+///
+/// The state manager replays these operations against the tip before flushing
+/// any page map deltas, so that snapshots on disk stay in sync with the
+/// in-memory state.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SnapshotOperation {
+    /// A snapshot of the canister was taken.
+    Backup(CanisterId, SnapshotId),
+    /// The canister was restored from the snapshot.
+    Restore(CanisterId, SnapshotId),
+    /// The snapshot was deleted.
+    Delete(SnapshotId),
+}
+
+/// All canister snapshots on the subnet, indexed by snapshot id.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CanisterSnapshots {
+    snapshots: BTreeMap<SnapshotId, Arc<CanisterSnapshot>>,
+    /// Operations applied to `snapshots` since the last flush to disk.
+    unflushed_changes: Vec<SnapshotOperation>,
+    /// Canisters restored from a snapshot since the last checkpoint. Their
+    /// memories are no longer backed by their own checkpoint files until the
+    /// next checkpoint is taken.
+    restored_canisters: BTreeSet<CanisterId>,
+}
+
+impl CanisterSnapshots {
+    /// Creates a set of snapshots loaded from a checkpoint, with no pending
+    /// changes.
+    pub fn new(snapshots: BTreeMap<SnapshotId, Arc<CanisterSnapshot>>) -> Self {
+        Self {
+            snapshots,
+            unflushed_changes: Vec::new(),
+            restored_canisters: BTreeSet::new(),
+        }
+    }
+
+    /// Adds a new snapshot and records the change to be flushed to disk.
+    pub fn push(&mut self, snapshot_id: SnapshotId, snapshot: Arc<CanisterSnapshot>) {
+        self.unflushed_changes.push(SnapshotOperation::Backup(
+            snapshot.canister_id(),
+            snapshot_id,
+        ));
+        self.snapshots.insert(snapshot_id, snapshot);
+    }
+
+    /// Returns the snapshot with the given id, if any.
+    pub fn get(&self, snapshot_id: &SnapshotId) -> Option<&Arc<CanisterSnapshot>> {
+        self.snapshots.get(snapshot_id)
+    }
+
+    /// Returns a mutable reference to the snapshot with the given id, if any.
+    pub fn get_mut(&mut self, snapshot_id: &SnapshotId) -> Option<&mut Arc<CanisterSnapshot>> {
+        self.snapshots.get_mut(snapshot_id)
+    }
+
+    /// Removes the snapshot with the given id and records the change to be
+    /// flushed to disk.
+    pub fn remove(&mut self, snapshot_id: &SnapshotId) -> Option<Arc<CanisterSnapshot>> {
+        let removed = self.snapshots.remove(snapshot_id);
+        if removed.is_some() {
+            self.unflushed_changes
+                .push(SnapshotOperation::Delete(*snapshot_id));
+        }
+        removed
+    }
+
+    /// Removes all snapshots of the given canister, e.g. when the canister is
+    /// deleted.
+    pub fn remove_snapshots(&mut self, canister_id: CanisterId) {
+        for snapshot_id in self.snapshot_ids(canister_id) {
+            self.remove(&snapshot_id);
+        }
+    }
+
+    /// Records that the given canister was restored from the given snapshot.
+    pub fn add_restore_operation(&mut self, canister_id: CanisterId, snapshot_id: SnapshotId) {
+        self.unflushed_changes
+            .push(SnapshotOperation::Restore(canister_id, snapshot_id));
+        self.restored_canisters.insert(canister_id);
+    }
+
+    /// Returns the canisters restored from a snapshot since the last
+    /// checkpoint.
+    pub fn restored_canisters(&self) -> &BTreeSet<CanisterId> {
+        &self.restored_canisters
+    }
+
+    /// Forgets about restored canisters once their memories are backed by
+    /// their own checkpoint files again.
+    pub fn clear_restored_canisters(&mut self) {
+        self.restored_canisters.clear();
+    }
+
+    /// Returns the ids of all snapshots of the given canister, in the order
+    /// they were taken.
+    pub fn snapshot_ids(&self, canister_id: CanisterId) -> BTreeSet<SnapshotId> {
+        self.list_snapshots(canister_id)
+            .into_iter()
+            .map(|(snapshot_id, _)| snapshot_id)
+            .collect()
+    }
+
+    /// Returns all snapshots of the given canister, in the order they were
+    /// taken.
+    pub fn list_snapshots(
+        &self,
+        canister_id: CanisterId,
+    ) -> Vec<(SnapshotId, Arc<CanisterSnapshot>)> {
+        self.snapshots
+            .iter()
+            .filter(|(snapshot_id, _)| snapshot_id.get_canister_id() == canister_id)
+            .map(|(snapshot_id, snapshot)| (*snapshot_id, Arc::clone(snapshot)))
+            .collect()
+    }
+
+    /// Returns the total memory used by the snapshots of the given canister.
+    pub fn compute_memory_usage_by_canister(&self, canister_id: CanisterId) -> NumBytes {
+        self.list_snapshots(canister_id)
+            .iter()
+            .map(|(_, snapshot)| snapshot.size())
+            .sum()
+    }
+
+    /// Returns an iterator over all snapshots, ordered by snapshot id.
+    pub fn iter(&self) -> impl Iterator<Item = (&SnapshotId, &Arc<CanisterSnapshot>)> {
+        self.snapshots.iter()
+    }
+
+    /// Returns an iterator over mutable references to all snapshots, ordered by
+    /// snapshot id.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&SnapshotId, &mut Arc<CanisterSnapshot>)> {
+        self.snapshots.iter_mut()
+    }
+
+    /// Returns `true` if there are changes that have not been flushed to disk.
+    pub fn has_unflushed_changes(&self) -> bool {
+        !self.unflushed_changes.is_empty()
+    }
+
+    /// Takes the operations applied since the last flush, leaving none behind.
+    pub fn take_unflushed_changes(&mut self) -> Vec<SnapshotOperation> {
+        std::mem::take(&mut self.unflushed_changes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_test_utilities::types::ids::canister_test_id;
+
+    #[test]
+    fn snapshot_id_roundtrips_through_bytes() {
+        let snapshot_id = SnapshotId::new(canister_test_id(13), 42);
+        let bytes = snapshot_id.to_vec();
+        assert_eq!(SnapshotId::try_from(&bytes[..]).unwrap(), snapshot_id);
+    }
+
+    #[test]
+    fn snapshot_id_rejects_short_input() {
+        assert!(SnapshotId::try_from(&[0_u8; LOCAL_ID_LEN][..]).is_err());
+    }
+}
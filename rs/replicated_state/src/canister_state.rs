@@ -10,6 +10,7 @@ use crate::{InputQueueType, StateError};
 pub use execution_state::{EmbedderCache, ExecutionState, ExportedFunctions, Global};
 use ic_ic00_types::CanisterStatusType;
 use ic_interfaces::messages::CanisterInputMessage;
+use ic_protobuf::state::canister_state_bits::v1 as pb;
 use ic_registry_subnet_type::SubnetType;
 use ic_types::methods::SystemMethod;
 use ic_types::time::UNIX_EPOCH;
@@ -70,6 +71,10 @@ pub struct SchedulerState {
     /// needed to calculate how much time should be considered when charging
     /// occurs.
     pub time_of_last_allocation_charge: Time,
+
+    /// Query statistics of the canister, aggregated across the replicas of
+    /// the subnet.
+    pub total_query_stats: TotalQueryStats,
 }
 
 impl Default for SchedulerState {
@@ -83,6 +88,7 @@ impl Default for SchedulerState {
             heap_delta_debit: 0.into(),
             install_code_debit: 0.into(),
             time_of_last_allocation_charge: UNIX_EPOCH,
+            total_query_stats: TotalQueryStats::default(),
         }
     }
 }
@@ -96,6 +102,56 @@ impl SchedulerState {
     }
 }
 
+/// Lifetime totals of the query calls executed by a canister, as aggregated
+/// deterministically from the statistics reported by all replicas.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TotalQueryStats {
+    /// Number of query calls.
+    pub num_calls: u64,
+    /// Number of instructions executed by the query calls.
+    pub num_instructions: u64,
+    /// Total size of the query call arguments in bytes.
+    pub ingress_payload_size: u64,
+    /// Total size of the query call replies in bytes.
+    pub egress_payload_size: u64,
+}
+
+impl TotalQueryStats {
+    /// Adds `other` to the totals, saturating on overflow.
+    pub fn saturating_accumulate(&mut self, other: &TotalQueryStats) {
+        self.num_calls = self.num_calls.saturating_add(other.num_calls);
+        self.num_instructions = self.num_instructions.saturating_add(other.num_instructions);
+        self.ingress_payload_size = self
+            .ingress_payload_size
+            .saturating_add(other.ingress_payload_size);
+        self.egress_payload_size = self
+            .egress_payload_size
+            .saturating_add(other.egress_payload_size);
+    }
+}
+
+impl From<&TotalQueryStats> for pb::TotalQueryStats {
+    fn from(item: &TotalQueryStats) -> Self {
+        Self {
+            num_calls: item.num_calls,
+            num_instructions: item.num_instructions,
+            ingress_payload_size: item.ingress_payload_size,
+            egress_payload_size: item.egress_payload_size,
+        }
+    }
+}
+
+impl From<pb::TotalQueryStats> for TotalQueryStats {
+    fn from(item: pb::TotalQueryStats) -> Self {
+        Self {
+            num_calls: item.num_calls,
+            num_instructions: item.num_instructions,
+            ingress_payload_size: item.ingress_payload_size,
+            egress_payload_size: item.egress_payload_size,
+        }
+    }
+}
+
 /// The full state of a single canister.
 #[derive(Clone, Debug, PartialEq)]
 pub struct CanisterState {
@@ -424,9 +480,10 @@ impl CanisterState {
 
     /// The amount of memory currently being used by the canister.
     ///
-    /// This only includes execution memory (heap, stable, globals, Wasm) for
-    /// system subnets; and execution memory plus system state memory (canister
-    /// messages) for application subnets.
+    /// This only includes execution memory (heap, stable, globals, Wasm),
+    /// snapshot memory and Wasm chunk store memory for system subnets; and
+    /// additionally system state memory (canister messages) for application
+    /// subnets.
     pub fn memory_usage(&self, own_subnet_type: SubnetType) -> NumBytes {
         let mut result = self.raw_memory_usage();
         if own_subnet_type != SubnetType::System {
@@ -437,8 +494,17 @@ impl CanisterState {
 
     /// Returns the amount of raw memory currently used by the canister in bytes.
     ///
-    /// This only includes execution memory (heap, stable, globals, Wasm).
+    /// This only includes execution memory (heap, stable, globals, Wasm), the
+    /// memory used by canister snapshots and by the Wasm chunk store.
     pub(crate) fn raw_memory_usage(&self) -> NumBytes {
+        self.execution_memory_usage()
+            + self.system_state.snapshots_memory_usage
+            + self.system_state.wasm_chunk_store.memory_usage()
+    }
+
+    /// Returns the amount of execution memory (heap, stable, globals, Wasm)
+    /// currently used by the canister in bytes.
+    pub fn execution_memory_usage(&self) -> NumBytes {
         self.execution_state
             .as_ref()
             .map_or(NumBytes::from(0), |es| es.memory_usage())
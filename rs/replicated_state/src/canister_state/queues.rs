@@ -309,12 +309,13 @@ impl CanisterQueues {
 
     /// Pushes a canister-to-canister message into the induction pool.
     ///
-    /// If the message is a `Request` this will also reserve a slot in the
-    /// corresponding output queue for the eventual response.
+    /// If the message is a guaranteed response `Request` this will also reserve
+    /// a slot in the corresponding output queue for the eventual response.
+    /// Best-effort requests do not reserve a slot.
     ///
-    /// If the message is a `Response` the protocol will have already reserved
-    /// space for it, so the push cannot fail due to the input queue being
-    /// full.
+    /// If the message is a guaranteed `Response` the protocol will have already
+    /// reserved space for it, so the push cannot fail due to the input queue
+    /// being full. Best-effort responses have no reserved slot.
     ///
     /// # Errors
     ///
@@ -324,23 +325,35 @@ impl CanisterQueues {
     ///  * `QueueFull` if pushing a `Request` and the corresponding input or
     ///    output queues are full.
     ///
-    ///  * `QueueFull` if pushing a `Response` and the receiving canister is not
-    ///  expecting one.
+    ///  * `QueueFull` if pushing a best-effort `Response` and the corresponding
+    ///    input queue is full.
+    ///
+    ///  * `QueueFull` if pushing a guaranteed `Response` and the receiving
+    ///  canister is not expecting one.
     pub(super) fn push_input(
         &mut self,
         msg: RequestOrResponse,
         input_queue_type: InputQueueType,
     ) -> Result<(), (StateError, RequestOrResponse)> {
         let sender = msg.sender();
-        let input_queue = match msg {
-            RequestOrResponse::Request(_) => {
+        let input_queue = match &msg {
+            RequestOrResponse::Request(req) => {
                 let (input_queue, output_queue) = self.get_or_insert_queues(&sender);
                 if let Err(e) = input_queue.check_has_slot() {
                     return Err((e, msg));
                 }
                 // Safe to already (attempt to) reserve an output slot here, as the `push()`
                 // below is guaranteed to succeed due to the check above.
-                if let Err(e) = output_queue.reserve_slot() {
+                if !req.is_best_effort() {
+                    if let Err(e) = output_queue.reserve_slot() {
+                        return Err((e, msg));
+                    }
+                }
+                input_queue
+            }
+            RequestOrResponse::Response(rep) if rep.is_best_effort() => {
+                let (input_queue, _) = self.get_or_insert_queues(&sender);
+                if let Err(e) = input_queue.check_has_slot() {
                     return Err((e, msg));
                 }
                 input_queue
@@ -547,13 +560,20 @@ impl CanisterQueues {
         None
     }
 
-    /// Pushes a `Request` type message into the relevant output queue. Also
-    /// reserves a slot for the eventual response on the matching input queue.
+    /// Pushes a `Request` type message into the relevant output queue. For
+    /// guaranteed response requests, also reserves a slot for the eventual
+    /// response on the matching input queue. Best-effort requests do not
+    /// reserve a slot (or the respective memory), as their responses are
+    /// dropped if there is no room for them.
+    ///
+    /// The request times out at `time + REQUEST_LIFETIME` or, for best-effort
+    /// requests, at its deadline, if that is earlier.
     ///
     /// # Errors
     ///
-    /// Returns a `QueueFull` error along with the provided message if either
-    /// the output queue or the matching input queue is full.
+    /// Returns a `QueueFull` error along with the provided message if the
+    /// output queue is full; or, for guaranteed response requests, if the
+    /// matching input queue is full.
     pub fn push_output_request(
         &mut self,
         msg: Arc<Request>,
@@ -564,19 +584,28 @@ impl CanisterQueues {
         if let Err(e) = output_queue.check_has_slot() {
             return Err((e, msg));
         }
-        if let Err(e) = input_queue.reserve_slot() {
-            return Err((e, msg));
+        let best_effort = msg.is_best_effort();
+        if !best_effort {
+            if let Err(e) = input_queue.reserve_slot() {
+                return Err((e, msg));
+            }
         }
 
         let mu_stats_delta = MemoryUsageStats::request_stats_delta(QueueOp::Push, &msg);
         let oq_stats_delta =
             OutputQueuesStats::stats_delta(&RequestOrResponse::Request(msg.clone()));
 
+        let mut deadline = time + REQUEST_LIFETIME;
+        if best_effort {
+            deadline = deadline.min(msg.deadline.as_time());
+        }
         output_queue
-            .push_request(msg, time + REQUEST_LIFETIME)
+            .push_request(msg, deadline)
             .expect("cannot fail due to checks above");
 
-        self.input_queues_stats.reserved_slots += 1;
+        if !best_effort {
+            self.input_queues_stats.reserved_slots += 1;
+        }
         self.output_queues_stats += oq_stats_delta;
         self.memory_usage_stats += mu_stats_delta;
         debug_assert!(self.stats_ok());
@@ -603,16 +632,20 @@ impl CanisterQueues {
             "reject_ic00_output_request can only be used to reject management canister requests"
         );
 
-        let (input_queue, _output_queue) = self.get_or_insert_queues(&request.receiver);
-        input_queue.reserve_slot()?;
-        self.input_queues_stats.reserved_slots += 1;
-        self.memory_usage_stats += MemoryUsageStats::response_slot_delta();
-        debug_assert!(self.stats_ok());
+        // Best-effort responses do not need a reserved slot.
+        if !request.is_best_effort() {
+            let (input_queue, _output_queue) = self.get_or_insert_queues(&request.receiver);
+            input_queue.reserve_slot()?;
+            self.input_queues_stats.reserved_slots += 1;
+            self.memory_usage_stats += MemoryUsageStats::response_slot_delta();
+            debug_assert!(self.stats_ok());
+        }
 
         let response = RequestOrResponse::Response(Arc::new(Response {
             originator: request.sender,
             respondent: IC_00,
             originator_reply_callback: request.sender_reply_callback,
+            deadline: request.deadline,
             refund: request.payment,
             response_payload: Payload::Reject(reject_context),
         }));
@@ -639,18 +672,32 @@ impl CanisterQueues {
             .collect()
     }
 
-    /// Pushes a `Response` type message into the relevant output queue. The
-    /// protocol should have already reserved a slot, so this cannot fail.
+    /// Pushes a `Response` type message into the relevant output queue. For
+    /// guaranteed responses the protocol should have already reserved a slot,
+    /// so this cannot fail. Best-effort responses have no reserved slot and are
+    /// silently dropped if the output queue is full (the caller eventually
+    /// gets a `SYS_UNKNOWN` reject once its callback expires).
     ///
     /// # Panics
     ///
-    /// Panics if the queue does not already exist or there is no reserved slot
-    /// to push the `Response` into.
+    /// Panics if the response is a guaranteed response and the queue does not
+    /// already exist or there is no reserved slot to push the `Response` into.
     pub fn push_output_response(&mut self, msg: Arc<Response>) {
         let mu_stats_delta = MemoryUsageStats::response_stats_delta(QueueOp::Push, &msg);
         let oq_stats_delta =
             OutputQueuesStats::stats_delta(&RequestOrResponse::Response(msg.clone()));
 
+        if msg.is_best_effort() {
+            let (_, output_queue) = self.get_or_insert_queues(&msg.originator);
+            if output_queue.push_best_effort_response(msg).is_err() {
+                return;
+            }
+            self.memory_usage_stats += mu_stats_delta;
+            self.output_queues_stats += oq_stats_delta;
+            debug_assert!(self.stats_ok());
+            return;
+        }
+
         // Since we make an output queue reservation whenever we induct a request; and
         // we would never garbage collect a non-empty queue (including one with just a
         // reservation); we are guaranteed that the output queue exists.
@@ -772,6 +819,12 @@ impl CanisterQueues {
         self.memory_usage_stats.oversized_requests_extra_bytes as usize
     }
 
+    /// Returns the total byte size of best-effort requests and responses
+    /// across input and output queues.
+    pub fn best_effort_message_bytes(&self) -> usize {
+        self.memory_usage_stats.best_effort_message_bytes
+    }
+
     /// Sets the (transient) size in bytes of responses routed from
     /// `output_queues` into streams and not yet garbage collected.
     pub(super) fn set_stream_responses_size_bytes(&mut self, size_bytes: usize) {
@@ -955,29 +1008,43 @@ impl CanisterQueues {
     fn calculate_memory_usage_stats(
         canister_queues: &BTreeMap<CanisterId, (InputQueue, OutputQueue)>,
     ) -> MemoryUsageStats {
-        // Actual byte size for responses, 0 for requests.
+        // Actual byte size for guaranteed responses, 0 for requests and
+        // best-effort responses.
         let response_size_bytes = |msg: &RequestOrResponse| match *msg {
+            _ if msg.is_best_effort() => 0,
             RequestOrResponse::Request(_) => 0,
             RequestOrResponse::Response(_) => msg.count_bytes(),
         };
-        // `max(0, msg.count_bytes() - MAX_RESPONSE_COUNT_BYTES)` for requests, 0 for
-        // responses.
+        // `max(0, msg.count_bytes() - MAX_RESPONSE_COUNT_BYTES)` for guaranteed
+        // response requests, 0 for best-effort requests and responses.
         let request_overhead_bytes = |msg: &RequestOrResponse| match *msg {
+            _ if msg.is_best_effort() => 0,
             RequestOrResponse::Request(_) => {
                 msg.count_bytes().saturating_sub(MAX_RESPONSE_COUNT_BYTES)
             }
             RequestOrResponse::Response(_) => 0,
         };
+        // Actual byte size for best-effort messages, 0 for guaranteed response
+        // messages.
+        let best_effort_message_bytes = |msg: &RequestOrResponse| {
+            if msg.is_best_effort() {
+                msg.count_bytes()
+            } else {
+                0
+            }
+        };
 
         let mut stats = MemoryUsageStats::default();
         for (iq, oq) in canister_queues.values() {
             stats.responses_size_bytes += iq.calculate_stat_sum(response_size_bytes);
             stats.reserved_slots += iq.reserved_slots() as i64;
             stats.oversized_requests_extra_bytes += iq.calculate_stat_sum(request_overhead_bytes);
+            stats.best_effort_message_bytes += iq.calculate_stat_sum(best_effort_message_bytes);
 
             stats.responses_size_bytes += oq.calculate_stat_sum(response_size_bytes);
             stats.reserved_slots += oq.reserved_slots() as i64;
-            stats.oversized_requests_extra_bytes += oq.calculate_stat_sum(request_overhead_bytes)
+            stats.oversized_requests_extra_bytes += oq.calculate_stat_sum(request_overhead_bytes);
+            stats.best_effort_message_bytes += oq.calculate_stat_sum(best_effort_message_bytes);
         }
         stats
     }
@@ -993,6 +1060,11 @@ impl CanisterQueues {
     /// Times out requests in `OutputQueues` given a current time, enqueuing a reject response
     /// for each into the matching `InputQueue`.
     ///
+    /// Best-effort requests are dropped silently, without a reject response (and
+    /// have no reserved slot to release): the caller gets a `SYS_UNKNOWN` reject
+    /// once the matching callback expires. Any cycles attached to a timed out
+    /// best-effort request are lost.
+    ///
     /// Updating the correct input queues schedule after enqueuing a reject response into a
     /// previously empty queue also requires the full set of local canisters to decide whether
     /// the destination canister was local or remote.
@@ -1007,12 +1079,23 @@ impl CanisterQueues {
         let mut timed_out_requests_count = 0;
         for (canister_id, (input_queue, output_queue)) in self.canister_queues.iter_mut() {
             for request in output_queue.time_out_requests(current_time) {
-                let response = generate_timeout_response(&request);
+                // Best-effort requests are dropped without a reject response.
+                let response = if request.is_best_effort() {
+                    None
+                } else {
+                    Some(generate_timeout_response(&request))
+                };
 
                 // Request was dropped, update stats.
                 let request = RequestOrResponse::Request(request);
                 self.memory_usage_stats -= MemoryUsageStats::stats_delta(QueueOp::Pop, &request);
                 self.output_queues_stats -= OutputQueuesStats::stats_delta(&request);
+                timed_out_requests_count += 1;
+
+                let response = match response {
+                    Some(response) => response,
+                    None => continue,
+                };
 
                 // Push response, update stats.
                 let iq_stats_delta = InputQueuesStats::stats_delta(QueueOp::Push, &response);
@@ -1029,8 +1112,6 @@ impl CanisterQueues {
                         self.remote_subnet_input_schedule.push_back(*canister_id);
                     }
                 }
-
-                timed_out_requests_count += 1;
             }
         }
 
@@ -1039,6 +1120,85 @@ impl CanisterQueues {
 
         timed_out_requests_count
     }
+
+    /// Enqueues a `SYS_UNKNOWN` reject response for an expired best-effort
+    /// callback into the input queue from `respondent`. Best-effort callbacks
+    /// have no reserved slot, so this fails if the input queue is full.
+    ///
+    /// Updating the correct input queues schedule after enqueuing the reject into a
+    /// previously empty queue also requires the full set of local canisters to decide
+    /// whether `respondent` is local or remote.
+    ///
+    /// # Errors
+    ///
+    /// Returns `QueueFull` if the input queue from `respondent` is full.
+    pub(crate) fn push_expired_callback_reject(
+        &mut self,
+        response: Arc<Response>,
+        own_canister_id: &CanisterId,
+        local_canisters: &BTreeMap<CanisterId, CanisterState>,
+    ) -> Result<(), StateError> {
+        debug_assert!(response.is_best_effort());
+        let respondent = response.respondent;
+        let response = RequestOrResponse::Response(response);
+        let (input_queue, _) = self.get_or_insert_queues(&respondent);
+
+        let iq_stats_delta = InputQueuesStats::stats_delta(QueueOp::Push, &response);
+        let mu_stats_delta = MemoryUsageStats::stats_delta(QueueOp::Push, &response);
+        input_queue.push(response).map_err(|(e, _)| e)?;
+        self.input_queues_stats += iq_stats_delta;
+        self.memory_usage_stats += mu_stats_delta;
+
+        // If this was a previously empty input queue, add it to input queue schedule.
+        if input_queue.num_messages() == 1 {
+            if &respondent == own_canister_id || local_canisters.contains_key(&respondent) {
+                self.local_subnet_input_schedule.push_back(respondent);
+            } else {
+                self.remote_subnet_input_schedule.push_back(respondent);
+            }
+        }
+
+        debug_assert!(self.stats_ok());
+        debug_assert!(self.schedules_ok(own_canister_id, local_canisters));
+        Ok(())
+    }
+
+    /// Sheds all best-effort requests from input and output queues and all
+    /// best-effort responses from output queues, in order to free up memory.
+    /// Returns the number of bytes freed.
+    ///
+    /// Best-effort responses in input queues are retained, as their callbacks
+    /// have already been closed. For everything else, the respective callback
+    /// eventually expires and the caller gets a `SYS_UNKNOWN` reject. Any
+    /// cycles attached to shed messages are lost.
+    ///
+    /// Time complexity: O(num_messages).
+    pub(crate) fn shed_best_effort_messages(&mut self) -> usize {
+        let memory_usage_before = self.memory_usage();
+
+        for (canister_id, (input_queue, output_queue)) in self.canister_queues.iter_mut() {
+            let input_queue_had_messages = input_queue.num_messages() > 0;
+            for msg in input_queue.shed_best_effort_requests() {
+                self.input_queues_stats -= InputQueuesStats::stats_delta(QueueOp::Pop, &msg);
+                self.memory_usage_stats -= MemoryUsageStats::stats_delta(QueueOp::Pop, &msg);
+            }
+            // Unschedule the input queue if it was emptied.
+            if input_queue_had_messages && input_queue.num_messages() == 0 {
+                self.local_subnet_input_schedule
+                    .retain(|sender| sender != canister_id);
+                self.remote_subnet_input_schedule
+                    .retain(|sender| sender != canister_id);
+            }
+
+            for msg in output_queue.shed_best_effort_messages() {
+                self.output_queues_stats -= OutputQueuesStats::stats_delta(&msg);
+                self.memory_usage_stats -= MemoryUsageStats::stats_delta(QueueOp::Pop, &msg);
+            }
+        }
+        debug_assert!(self.stats_ok());
+
+        memory_usage_before - self.memory_usage()
+    }
 }
 
 /// Generates a timeout reject response from a request, refunding its payment.
@@ -1047,6 +1207,7 @@ fn generate_timeout_response(request: &Arc<Request>) -> RequestOrResponse {
         originator: request.sender,
         respondent: request.receiver,
         originator_reply_callback: request.sender_reply_callback,
+        deadline: request.deadline,
         refund: request.payment,
         response_payload: Payload::Reject(RejectContext::new_with_message_length_limit(
             RejectCode::SysTransient,
@@ -1202,9 +1363,9 @@ impl InputQueuesStats {
             RequestOrResponse::Response(_) => 1,
             RequestOrResponse::Request(_) => 0,
         };
-        // Consume one reservation iff pushing a response.
+        // Consume one reservation iff pushing a guaranteed response.
         let reserved_slots = match (op, msg) {
-            (QueueOp::Push, RequestOrResponse::Response(_)) => -1,
+            (QueueOp::Push, RequestOrResponse::Response(rep)) if !rep.is_best_effort() => -1,
             _ => 0,
         };
 
@@ -1278,8 +1439,9 @@ impl SubAssign<OutputQueuesStats> for OutputQueuesStats {
 }
 
 /// Running memory utilization stats for input and output queues: total byte
-/// size of all responses in input and output queues; and total reservations in
-/// input and output queues.
+/// size of all guaranteed responses in input and output queues; total
+/// reservations in input and output queues; and total byte size of all
+/// best-effort messages in input and output queues.
 ///
 /// Memory allocation of output responses in streams is tracked separately, at
 /// the replicated state level (as the canister may be migrated to a different
@@ -1291,24 +1453,29 @@ impl SubAssign<OutputQueuesStats> for OutputQueuesStats {
 /// adding lots of zeros in lots of places.
 #[derive(Clone, Debug, Default, Eq)]
 struct MemoryUsageStats {
-    /// Sum total of the byte size of every response across input and output
-    /// queues.
+    /// Sum total of the byte size of every guaranteed response across input
+    /// and output queues.
     responses_size_bytes: usize,
 
     /// Sum total of reserved slots across input and output queues. This is
-    /// equivalent to the number of outstanding (input and output) requests
-    /// (across queues and streams) and is used for computing message memory
-    /// allocation (as `MAX_RESPONSE_COUNT_BYTES` per request).
+    /// equivalent to the number of outstanding (input and output) guaranteed
+    /// response requests (across queues and streams) and is used for computing
+    /// message memory allocation (as `MAX_RESPONSE_COUNT_BYTES` per request).
     ///
     /// `i64` because we need to be able to add negative amounts (e.g. pushing a
     /// response consumes a reservation) and it's less verbose this way.
     reserved_slots: i64,
 
     /// Sum total of bytes above `MAX_RESPONSE_COUNT_BYTES` per oversized
-    /// request. Execution allows local-subnet requests larger than
-    /// `MAX_RESPONSE_COUNT_BYTES`.
+    /// guaranteed response request. Execution allows local-subnet requests
+    /// larger than `MAX_RESPONSE_COUNT_BYTES`.
     oversized_requests_extra_bytes: usize,
 
+    /// Sum total of the byte size of every best-effort request and response
+    /// across input and output queues. Best-effort messages do not reserve
+    /// memory for responses, so they only account for their actual size.
+    best_effort_message_bytes: usize,
+
     /// Transient: size in bytes of responses routed from `output_queues` into
     /// streams and not yet garbage collected.
     ///
@@ -1323,6 +1490,7 @@ impl MemoryUsageStats {
         self.responses_size_bytes
             + self.reserved_slots as usize * MAX_RESPONSE_COUNT_BYTES
             + self.oversized_requests_extra_bytes
+            + self.best_effort_message_bytes
             + self.transient_stream_responses_size_bytes
     }
 
@@ -1338,6 +1506,13 @@ impl MemoryUsageStats {
     /// Calculates the change in stats caused by pushing (+) or popping (-) a
     /// request.
     fn request_stats_delta(op: QueueOp, req: &Request) -> MemoryUsageStats {
+        if req.is_best_effort() {
+            return MemoryUsageStats {
+                best_effort_message_bytes: req.count_bytes(),
+                ..Default::default()
+            };
+        }
+
         MemoryUsageStats {
             // No change in responses byte size (as this is a request).
             responses_size_bytes: 0,
@@ -1349,6 +1524,7 @@ impl MemoryUsageStats {
             oversized_requests_extra_bytes: req
                 .count_bytes()
                 .saturating_sub(MAX_RESPONSE_COUNT_BYTES),
+            best_effort_message_bytes: 0,
             transient_stream_responses_size_bytes: 0,
         }
     }
@@ -1356,6 +1532,13 @@ impl MemoryUsageStats {
     /// Calculates the change in stats caused by pushing (+) or popping (-) the
     /// given response.
     fn response_stats_delta(op: QueueOp, rep: &Response) -> MemoryUsageStats {
+        if rep.is_best_effort() {
+            return MemoryUsageStats {
+                best_effort_message_bytes: rep.count_bytes(),
+                ..Default::default()
+            };
+        }
+
         MemoryUsageStats {
             // Adjust responses byte size by this response's byte size.
             responses_size_bytes: rep.count_bytes(),
@@ -1366,6 +1549,7 @@ impl MemoryUsageStats {
             },
             // No change in requests overhead (as this is a response).
             oversized_requests_extra_bytes: 0,
+            best_effort_message_bytes: 0,
             transient_stream_responses_size_bytes: 0,
         }
     }
@@ -1377,6 +1561,7 @@ impl MemoryUsageStats {
             responses_size_bytes: 0,
             reserved_slots: 1,
             oversized_requests_extra_bytes: 0,
+            best_effort_message_bytes: 0,
             transient_stream_responses_size_bytes: 0,
         }
     }
@@ -1387,6 +1572,7 @@ impl AddAssign<MemoryUsageStats> for MemoryUsageStats {
         self.responses_size_bytes += rhs.responses_size_bytes;
         self.reserved_slots += rhs.reserved_slots;
         self.oversized_requests_extra_bytes += rhs.oversized_requests_extra_bytes;
+        self.best_effort_message_bytes += rhs.best_effort_message_bytes;
         debug_assert!(self.reserved_slots >= 0);
     }
 }
@@ -1396,6 +1582,7 @@ impl SubAssign<MemoryUsageStats> for MemoryUsageStats {
         self.responses_size_bytes -= rhs.responses_size_bytes;
         self.reserved_slots -= rhs.reserved_slots;
         self.oversized_requests_extra_bytes -= rhs.oversized_requests_extra_bytes;
+        self.best_effort_message_bytes -= rhs.best_effort_message_bytes;
         debug_assert!(self.reserved_slots >= 0);
     }
 }
@@ -1406,6 +1593,7 @@ impl PartialEq for MemoryUsageStats {
         self.responses_size_bytes == rhs.responses_size_bytes
             && self.reserved_slots == rhs.reserved_slots
             && self.oversized_requests_extra_bytes == rhs.oversized_requests_extra_bytes
+            && self.best_effort_message_bytes == rhs.best_effort_message_bytes
     }
 }
 
@@ -1413,28 +1601,35 @@ impl PartialEq for MemoryUsageStats {
 /// an input or output queue.
 ///
 /// Returns:
-///  * `Ok(())` if `msg` is a `Response`, as responses always return memory.
-///  * `Ok(())` if `msg` is a `Request` and `available_memory` is sufficient.
-///  * `Err(required_memory)` if `msg` is a `Request` and `required_memory >
-///    available_memory`.
+///  * `Ok(())` if `msg` is a guaranteed `Response`, as guaranteed responses
+///    always return memory.
+///  * `Ok(())` if `msg` is a `Request` or best-effort `Response` and
+///    `available_memory` is sufficient.
+///  * `Err(required_memory)` if `msg` is a `Request` or best-effort `Response`
+///    and `required_memory > available_memory`.
 pub fn can_push(msg: &RequestOrResponse, available_memory: i64) -> Result<(), usize> {
-    match msg {
-        RequestOrResponse::Request(req) => {
-            let required = memory_required_to_push_request(req);
-            if required as i64 <= available_memory {
-                Ok(())
-            } else {
-                Err(required)
-            }
-        }
-        RequestOrResponse::Response(_) => Ok(()),
+    let required = match msg {
+        RequestOrResponse::Request(req) => memory_required_to_push_request(req),
+        // Best-effort responses have no memory reserved for them.
+        RequestOrResponse::Response(rep) if rep.is_best_effort() => rep.count_bytes(),
+        RequestOrResponse::Response(_) => return Ok(()),
+    };
+    if required as i64 <= available_memory {
+        Ok(())
+    } else {
+        Err(required)
     }
 }
 
 /// Returns the memory required to push `req` onto an input or output queue.
-/// This is the maximum of `MAX_RESPONSE_COUNT_BYTES` (to be reserved for a
-/// response) and `req.count_bytes()` (if larger).
+/// For guaranteed response requests, this is the maximum of
+/// `MAX_RESPONSE_COUNT_BYTES` (to be reserved for a response) and
+/// `req.count_bytes()` (if larger). Best-effort requests do not reserve memory
+/// for a response, so this is just `req.count_bytes()`.
 pub fn memory_required_to_push_request(req: &Request) -> usize {
+    if req.is_best_effort() {
+        return req.count_bytes();
+    }
     req.count_bytes().max(MAX_RESPONSE_COUNT_BYTES)
 }
 
@@ -235,8 +235,10 @@ impl InputQueue {
         &mut self,
         msg: RequestOrResponse,
     ) -> Result<(), (StateError, RequestOrResponse)> {
-        match msg {
+        match &msg {
             RequestOrResponse::Request(_) => self.queue.push(msg),
+            // Best-effort responses do not have a reserved slot.
+            RequestOrResponse::Response(rep) if rep.is_best_effort() => self.queue.push(msg),
             RequestOrResponse::Response(_) => self.queue.push_into_reserved_slot(msg),
         }
     }
@@ -258,6 +260,23 @@ impl InputQueue {
         self.queue.queue.len()
     }
 
+    /// Removes all best-effort requests from the queue and returns them, in
+    /// queue order. Best-effort responses are retained, as their callbacks
+    /// have already been closed.
+    ///
+    /// Time complexity: O(num_messages).
+    pub(super) fn shed_best_effort_requests(&mut self) -> Vec<RequestOrResponse> {
+        let mut shed = Vec::new();
+        self.queue.queue.retain(|msg| match msg {
+            RequestOrResponse::Request(req) if req.is_best_effort() => {
+                shed.push(msg.clone());
+                false
+            }
+            _ => true,
+        });
+        shed
+    }
+
     /// Returns the number of reserved slots in the queue.
     pub(super) fn reserved_slots(&self) -> usize {
         self.queue.reserved_slots()
@@ -417,6 +436,25 @@ impl OutputQueue {
         debug_assert!(self.check_invariants());
     }
 
+    /// Pushes a best-effort response into the queue if not full. Unlike
+    /// guaranteed responses, best-effort responses do not have a reserved slot.
+    pub(super) fn push_best_effort_response(
+        &mut self,
+        msg: Arc<Response>,
+    ) -> Result<(), (StateError, Arc<Response>)> {
+        debug_assert!(msg.is_best_effort());
+        if let Err((err, Some(RequestOrResponse::Response(msg)))) =
+            self.queue.push(Some(RequestOrResponse::Response(msg)))
+        {
+            return Err((err, msg));
+        }
+
+        self.num_messages += 1;
+        debug_assert!(self.check_invariants());
+
+        Ok(())
+    }
+
     pub(super) fn reserve_slot(&mut self) -> Result<(), StateError> {
         self.queue.reserve_slot()
     }
@@ -442,6 +480,25 @@ impl OutputQueue {
         }
     }
 
+    /// Removes all best-effort messages from the queue, leaving `None` in their
+    /// place, and returns them in queue order.
+    ///
+    /// Time complexity: O(num_messages).
+    pub(super) fn shed_best_effort_messages(&mut self) -> Vec<RequestOrResponse> {
+        let mut shed = Vec::new();
+        for item in self.queue.queue.iter_mut() {
+            if matches!(item, Some(msg) if msg.is_best_effort()) {
+                shed.push(item.take().unwrap());
+            }
+        }
+
+        self.num_messages -= shed.len();
+        self.advance_to_next_message();
+        debug_assert!(self.check_invariants());
+
+        shed
+    }
+
     /// Consumes any empty slots at the beginning of the queue and discards consumed deadline ranges.
     fn advance_to_next_message(&mut self) {
         // Remove `None` in the beginning.
@@ -14,7 +14,11 @@ use ic_test_utilities::{
         messages::{IngressBuilder, RequestBuilder, ResponseBuilder},
     },
 };
-use ic_types::{messages::CallbackId, time::current_time_and_expiry_time};
+use ic_types::{
+    messages::{CallbackId, NO_DEADLINE},
+    time::current_time_and_expiry_time,
+    CoarseTime,
+};
 use proptest::prelude::*;
 use std::convert::TryInto;
 
@@ -868,6 +872,7 @@ fn test_stats() {
         reserved_slots: -1,
         responses_size_bytes: msg_size[3],
         oversized_requests_extra_bytes: 0,
+        best_effort_message_bytes: 0,
         transient_stream_responses_size_bytes: 0,
     };
     assert_eq!(expected_mu_stats, queues.memory_usage_stats);
@@ -978,6 +983,7 @@ fn test_stats() {
         reserved_slots: -1,
         responses_size_bytes: msg_size[5],
         oversized_requests_extra_bytes: 0,
+        best_effort_message_bytes: 0,
         transient_stream_responses_size_bytes: 0,
     };
     assert_eq!(expected_mu_stats, queues.memory_usage_stats);
@@ -1120,6 +1126,7 @@ fn test_stats_induct_message_to_self() {
         reserved_slots: -1,
         responses_size_bytes: response_size,
         oversized_requests_extra_bytes: 0,
+        best_effort_message_bytes: 0,
         transient_stream_responses_size_bytes: 0,
     };
     assert_eq!(expected_mu_stats, queues.memory_usage_stats);
@@ -1623,6 +1630,7 @@ fn time_out_requests_pushes_correct_reject_responses() {
                     receiver: canister_id,
                     sender: own_canister_id,
                     sender_reply_callback: CallbackId::from(callback_id),
+                    deadline: NO_DEADLINE,
                     payment: Cycles::from(cycles as u64),
                     method_name: "No-Op".to_string(),
                     method_payload: vec![],
@@ -1676,6 +1684,7 @@ fn time_out_requests_pushes_correct_reject_responses() {
                 originator: own_canister_id,
                 respondent: remote_canister_id,
                 originator_reply_callback: CallbackId::from(2),
+                deadline: NO_DEADLINE,
                 refund: Cycles::from(7_u64),
                 response_payload: Payload::Reject(RejectContext::new_with_message_length_limit(
                     RejectCode::SysTransient,
@@ -1715,3 +1724,262 @@ fn time_out_requests_pushes_correct_reject_responses() {
         VecDeque::from(vec![remote_canister_id]),
     );
 }
+
+/// Tests that a best-effort request times out at its deadline, without a reject
+/// response; and that the expired callback reject is enqueued without a
+/// reserved slot.
+#[test]
+fn time_out_best_effort_request_drops_it_silently() {
+    let mut canister_queues = CanisterQueues::default();
+
+    let own_canister_id = canister_test_id(67);
+    let remote_canister_id = canister_test_id(97);
+    let local_canisters = BTreeMap::new();
+
+    let deadline = CoarseTime::from_secs_since_unix_epoch(10);
+    let request = RequestBuilder::default()
+        .sender(own_canister_id)
+        .receiver(remote_canister_id)
+        .sender_reply_callback(CallbackId::from(1))
+        .deadline(deadline)
+        .build();
+    canister_queues
+        .push_output_request(request.into(), Time::from_nanos_since_unix_epoch(0))
+        .unwrap();
+
+    // The request times out at its deadline, well before `REQUEST_LIFETIME`.
+    assert!(!canister_queues.has_expired_deadlines(deadline.as_time() - Duration::from_nanos(1)));
+    assert!(canister_queues.has_expired_deadlines(deadline.as_time()));
+    assert_eq!(
+        1,
+        canister_queues.time_out_requests(deadline.as_time(), &own_canister_id, &local_canisters),
+    );
+
+    // But no reject response is enqueued and no slot is reserved.
+    let (input_queue, output_queue) = canister_queues
+        .canister_queues
+        .get(&remote_canister_id)
+        .unwrap();
+    assert_eq!(0, output_queue.num_messages());
+    assert_eq!(0, input_queue.num_messages());
+    assert_eq!(0, input_queue.reserved_slots());
+    assert!(canister_queues.remote_subnet_input_schedule.is_empty());
+    assert_eq!(0, canister_queues.memory_usage());
+
+    // The queue pair can be garbage collected.
+    canister_queues.garbage_collect();
+    assert_eq!(CanisterQueues::default(), canister_queues);
+
+    // The expired callback reject is enqueued regardless.
+    let reject = ResponseBuilder::default()
+        .originator(own_canister_id)
+        .respondent(remote_canister_id)
+        .originator_reply_callback(CallbackId::from(1))
+        .deadline(deadline)
+        .build();
+    let reject_size_bytes = reject.count_bytes();
+    canister_queues
+        .push_expired_callback_reject(Arc::new(reject), &own_canister_id, &local_canisters)
+        .unwrap();
+    let (input_queue, _) = canister_queues
+        .canister_queues
+        .get(&remote_canister_id)
+        .unwrap();
+    assert_eq!(1, input_queue.num_messages());
+    assert_eq!(0, input_queue.reserved_slots());
+    assert_eq!(
+        canister_queues.remote_subnet_input_schedule,
+        VecDeque::from(vec![remote_canister_id]),
+    );
+    assert_eq!(reject_size_bytes, canister_queues.memory_usage());
+}
+
+/// Tests that best-effort requests and responses do not reserve slots or
+/// memory for responses, and only account for their actual size.
+#[test]
+fn best_effort_messages_do_not_reserve_slots() {
+    let mut queues = CanisterQueues::default();
+    let this = canister_test_id(13);
+    let other = canister_test_id(14);
+    let deadline = CoarseTime::from_secs_since_unix_epoch(10);
+
+    // Outgoing best-effort request: no input queue reservation.
+    let request = RequestBuilder::default()
+        .sender(this)
+        .receiver(other)
+        .deadline(deadline)
+        .build();
+    let request_size_bytes = request.count_bytes();
+    assert_eq!(
+        request_size_bytes,
+        memory_required_to_push_request(&request)
+    );
+    queues
+        .push_output_request(request.into(), mock_time())
+        .unwrap();
+    assert_eq!(0, queues.input_queues_reservation_count());
+    assert_eq!(0, queues.reserved_slots());
+    assert_eq!(request_size_bytes, queues.memory_usage());
+    assert_eq!(request_size_bytes, queues.best_effort_message_bytes());
+
+    // Incoming best-effort response: no reservation to consume.
+    let response = ResponseBuilder::default()
+        .originator(this)
+        .respondent(other)
+        .deadline(deadline)
+        .build();
+    let response_size_bytes = response.count_bytes();
+    queues
+        .push_input(response.into(), InputQueueType::RemoteSubnet)
+        .unwrap();
+    assert_eq!(0, queues.input_queues_reservation_count());
+    assert_eq!(
+        request_size_bytes + response_size_bytes,
+        queues.memory_usage()
+    );
+
+    // Incoming best-effort request: no output queue reservation.
+    let request = RequestBuilder::default()
+        .sender(other)
+        .receiver(this)
+        .deadline(deadline)
+        .build();
+    queues
+        .push_input(request.into(), InputQueueType::RemoteSubnet)
+        .unwrap();
+    assert_eq!(0, queues.reserved_slots());
+
+    // Outgoing best-effort response: pushed without a reservation.
+    queues.push_output_response(Arc::new(
+        ResponseBuilder::default()
+            .originator(other)
+            .respondent(this)
+            .deadline(deadline)
+            .build(),
+    ));
+    assert_eq!(2, queues.output_queues_message_count());
+    assert_eq!(0, queues.reserved_slots());
+    assert_eq!(queues.best_effort_message_bytes(), queues.memory_usage());
+}
+
+/// Tests that a best-effort response is dropped if the output queue is full,
+/// instead of panicking for lack of a reserved slot.
+#[test]
+fn best_effort_output_response_dropped_if_queue_full() {
+    let mut queues = CanisterQueues::default();
+    let this = canister_test_id(13);
+    let other = canister_test_id(14);
+    let deadline = CoarseTime::from_secs_since_unix_epoch(10);
+
+    for _ in 0..DEFAULT_QUEUE_CAPACITY {
+        queues
+            .push_output_request(
+                RequestBuilder::default()
+                    .sender(this)
+                    .receiver(other)
+                    .deadline(deadline)
+                    .build()
+                    .into(),
+                mock_time(),
+            )
+            .unwrap();
+    }
+    let memory_usage = queues.memory_usage();
+
+    queues.push_output_response(Arc::new(
+        ResponseBuilder::default()
+            .originator(other)
+            .respondent(this)
+            .deadline(deadline)
+            .build(),
+    ));
+    assert_eq!(DEFAULT_QUEUE_CAPACITY, queues.output_queues_message_count());
+    assert_eq!(memory_usage, queues.memory_usage());
+}
+
+/// Tests that shedding drops best-effort requests from input and output queues
+/// and best-effort responses from output queues; and retains everything else.
+#[test]
+fn shed_best_effort_messages_retains_guaranteed_messages() {
+    let mut queues = CanisterQueues::default();
+    let this = canister_test_id(13);
+    let other = canister_test_id(14);
+    let deadline = CoarseTime::from_secs_since_unix_epoch(10);
+
+    // A guaranteed and a best-effort output request.
+    queues
+        .push_output_request(
+            RequestBuilder::default()
+                .sender(this)
+                .receiver(other)
+                .build()
+                .into(),
+            mock_time(),
+        )
+        .unwrap();
+    queues
+        .push_output_request(
+            RequestBuilder::default()
+                .sender(this)
+                .receiver(other)
+                .deadline(deadline)
+                .build()
+                .into(),
+            mock_time(),
+        )
+        .unwrap();
+    // A best-effort input request, followed by a best-effort input response.
+    queues
+        .push_input(
+            RequestBuilder::default()
+                .sender(other)
+                .receiver(this)
+                .deadline(deadline)
+                .build()
+                .into(),
+            InputQueueType::RemoteSubnet,
+        )
+        .unwrap();
+    let response = ResponseBuilder::default()
+        .originator(this)
+        .respondent(other)
+        .deadline(deadline)
+        .build();
+    let response_size_bytes = response.count_bytes();
+    queues
+        .push_input(response.into(), InputQueueType::RemoteSubnet)
+        .unwrap();
+    let memory_usage = queues.memory_usage();
+
+    // Everything but the guaranteed request and the input response is shed.
+    let freed = queues.shed_best_effort_messages();
+    assert_eq!(memory_usage - freed, queues.memory_usage());
+    assert_eq!(response_size_bytes, queues.best_effort_message_bytes());
+    assert_eq!(1, queues.output_queues_message_count());
+    assert_eq!(1, queues.input_queues_message_count());
+    assert_eq!(1, queues.input_queues_reservation_count());
+    match queues.pop_input() {
+        Some(CanisterInputMessage::Response(rep)) => assert!(rep.is_best_effort()),
+        msg => panic!("Expected best-effort response, got {:?}", msg),
+    }
+    match queues.pop_canister_output(&other) {
+        Some(RequestOrResponse::Request(req)) => assert!(!req.is_best_effort()),
+        msg => panic!("Expected guaranteed request, got {:?}", msg),
+    }
+
+    // Shedding an input queue down to empty also unschedules it.
+    queues
+        .push_input(
+            RequestBuilder::default()
+                .sender(other)
+                .receiver(this)
+                .deadline(deadline)
+                .build()
+                .into(),
+            InputQueueType::RemoteSubnet,
+        )
+        .unwrap();
+    queues.shed_best_effort_messages();
+    assert!(queues.remote_subnet_input_schedule.is_empty());
+    assert!(queues.pop_input().is_none());
+}
@@ -1,11 +1,15 @@
 mod call_context_manager;
+pub mod wasm_chunk_store;
 
 use super::queues::can_push;
 pub use super::queues::memory_required_to_push_request;
 pub use crate::canister_state::queues::CanisterOutputQueuesIterator;
+use crate::replicated_state::MR_SYNTHETIC_REJECT_MESSAGE_MAX_LEN;
 use crate::{CanisterQueues, CanisterState, InputQueueType, StateError};
 pub use call_context_manager::{CallContext, CallContextAction, CallContextManager, CallOrigin};
 use ic_base_types::NumSeconds;
+use ic_error_types::RejectCode;
+use ic_ic00_types::{CanisterChange, CanisterChangeDetails, CanisterChangeOrigin, LogVisibility};
 use ic_interfaces::messages::{CanisterInputMessage, RequestOrIngress};
 use ic_logger::{error, ReplicaLogger};
 use ic_protobuf::{
@@ -14,9 +18,12 @@ use ic_protobuf::{
 };
 use ic_registry_subnet_type::SubnetType;
 use ic_types::{
-    messages::{Ingress, RejectContext, Request, RequestOrResponse, Response, StopCanisterContext},
+    messages::{
+        Ingress, Payload, RejectContext, Request, RequestOrResponse, Response, StopCanisterContext,
+    },
     nominal_cycles::NominalCycles,
-    CanisterId, CanisterTimer, Cycles, MemoryAllocation, NumBytes, PrincipalId, Time,
+    CanisterId, CanisterLog, CanisterTimer, CoarseTime, Cycles, MemoryAllocation, NumBytes,
+    PrincipalId, Time,
 };
 use lazy_static::lazy_static;
 use maplit::btreeset;
@@ -27,6 +34,7 @@ use std::{
 };
 use std::{collections::BTreeSet, sync::Arc};
 use std::{collections::VecDeque, str::FromStr};
+use wasm_chunk_store::WasmChunkStore;
 
 lazy_static! {
     static ref DEFAULT_PRINCIPAL_MULTIPLE_CONTROLLERS: PrincipalId =
@@ -35,6 +43,73 @@ lazy_static! {
         PrincipalId::from_str("zrl4w-cqaaa-nocon-troll-eraaa-d5qc").unwrap();
 }
 
+/// Maximum number of changes kept in the history of a canister.
+pub const MAX_CANISTER_HISTORY_CHANGES: u64 = 20;
+
+/// The history of changes to a canister (creation, code deployments and
+/// uninstalls, controller changes), bounded to the most recent
+/// `MAX_CANISTER_HISTORY_CHANGES` entries.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CanisterHistory {
+    /// The changes, ordered from the oldest to the most recent.
+    changes: Arc<VecDeque<CanisterChange>>,
+    /// The total number of changes ever recorded, including the ones that
+    /// have since been dropped from `changes`.
+    total_num_changes: u64,
+}
+
+impl CanisterHistory {
+    /// Records a new change, dropping the oldest one if the history is full.
+    pub fn add_canister_change(&mut self, canister_change: CanisterChange) {
+        let changes = Arc::make_mut(&mut self.changes);
+        if changes.len() >= MAX_CANISTER_HISTORY_CHANGES as usize {
+            changes.pop_front();
+        }
+        changes.push_back(canister_change);
+        self.total_num_changes += 1;
+    }
+
+    /// Returns up to `num_requested_changes` most recent changes, ordered from
+    /// the oldest to the most recent.
+    pub fn get_changes(
+        &self,
+        num_requested_changes: usize,
+    ) -> impl Iterator<Item = &CanisterChange> {
+        let num_all_changes = self.changes.len();
+        let num_skipped = num_all_changes - num_requested_changes.min(num_all_changes);
+        self.changes.iter().skip(num_skipped)
+    }
+
+    pub fn get_total_num_changes(&self) -> u64 {
+        self.total_num_changes
+    }
+}
+
+impl From<&CanisterHistory> for pb::CanisterHistory {
+    fn from(item: &CanisterHistory) -> Self {
+        Self {
+            changes: item.changes.iter().map(|change| change.into()).collect(),
+            total_num_changes: item.total_num_changes,
+        }
+    }
+}
+
+impl TryFrom<pb::CanisterHistory> for CanisterHistory {
+    type Error = ProxyDecodeError;
+
+    fn try_from(value: pb::CanisterHistory) -> Result<Self, Self::Error> {
+        let changes = value
+            .changes
+            .into_iter()
+            .map(CanisterChange::try_from)
+            .collect::<Result<VecDeque<_>, _>>()?;
+        Ok(Self {
+            changes: Arc::new(changes),
+            total_num_changes: value.total_num_changes,
+        })
+    }
+}
+
 #[derive(Clone, Debug, Default, PartialEq, Eq)]
 /// Canister-specific metrics on scheduling, maintained by the scheduler.
 // For semantics of the fields please check
@@ -103,6 +178,16 @@ pub struct SystemState {
     /// it will apply `cycles_debit` to `cycles_balance`.
     cycles_debit: Cycles,
 
+    /// Cycles set aside to pay for the storage of memory that the canister
+    /// allocated while the subnet was under storage pressure. Storage charges
+    /// are paid from this balance before the main balance. Should only be
+    /// modified through `CyclesAccountManager` and `reserve_cycles()`.
+    reserved_balance: Cycles,
+
+    /// The upper limit on `reserved_balance`. Allocations of memory that would
+    /// need to reserve more cycles fail. `None` means that there is no limit.
+    reserved_balance_limit: Option<Cycles>,
+
     /// Tasks to execute before processing input messages.
     /// Currently the task queue is empty outside of execution rounds.
     pub task_queue: VecDeque<ExecutionTask>,
@@ -112,6 +197,50 @@ pub struct SystemState {
 
     /// Canister version.
     pub canister_version: u64,
+
+    /// The local id to be assigned to the next snapshot taken of this
+    /// canister. Never reused, so that snapshot ids remain unique even after
+    /// snapshots are deleted.
+    pub next_snapshot_id: u64,
+
+    /// Total memory used by the snapshots of this canister.
+    ///
+    /// Kept in sync with the snapshots in `ReplicatedState::canister_snapshots`
+    /// by `CanisterManager`, so that snapshots can be accounted for as part of
+    /// the canister's memory usage.
+    pub snapshots_memory_usage: NumBytes,
+
+    /// The most recent changes to the canister. Only modified through
+    /// `add_canister_change`, so that every change carries the canister
+    /// version it produced.
+    canister_history: CanisterHistory,
+
+    /// Who is allowed to read the canister log.
+    pub log_visibility: LogVisibility,
+
+    /// Log records produced by the canister, e.g. via `ic0.debug_print` or
+    /// traps, during replicated execution.
+    pub canister_log: CanisterLog,
+
+    /// The limit on the Wasm memory size of the canister. The limit is not
+    /// enforced during upgrades, so that the canister can migrate its data.
+    pub wasm_memory_limit: Option<NumBytes>,
+
+    /// Chunks of Wasm modules uploaded via `upload_chunk`, for installing
+    /// modules that do not fit into a single message.
+    pub wasm_chunk_store: WasmChunkStore,
+}
+
+/// Errors returned when moving cycles to the reserved balance of a canister.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ReservationError {
+    /// The main balance does not have enough cycles for the reservation.
+    InsufficientCycles {
+        requested: Cycles,
+        available: Cycles,
+    },
+    /// The reserved balance would exceed its limit.
+    ReservedLimitExceeded { requested: Cycles, limit: Cycles },
 }
 
 /// A wrapper around the different canister statuses.
@@ -408,6 +537,8 @@ impl SystemState {
             queues: CanisterQueues::default(),
             cycles_balance: initial_cycles,
             cycles_debit: Cycles::zero(),
+            reserved_balance: Cycles::zero(),
+            reserved_balance_limit: None,
             memory_allocation: MemoryAllocation::BestEffort,
             freeze_threshold,
             status,
@@ -416,6 +547,13 @@ impl SystemState {
             task_queue: Default::default(),
             global_timer: CanisterTimer::Inactive,
             canister_version: 0,
+            next_snapshot_id: 0,
+            snapshots_memory_usage: NumBytes::from(0),
+            canister_history: CanisterHistory::default(),
+            log_visibility: LogVisibility::default(),
+            canister_log: CanisterLog::default(),
+            wasm_memory_limit: None,
+            wasm_chunk_store: WasmChunkStore::new(),
         }
     }
 
@@ -450,6 +588,15 @@ impl SystemState {
         task_queue: VecDeque<ExecutionTask>,
         global_timer: CanisterTimer,
         canister_version: u64,
+        next_snapshot_id: u64,
+        snapshots_memory_usage: NumBytes,
+        canister_history: CanisterHistory,
+        log_visibility: LogVisibility,
+        canister_log: CanisterLog,
+        wasm_memory_limit: Option<NumBytes>,
+        reserved_balance: Cycles,
+        reserved_balance_limit: Option<Cycles>,
+        wasm_chunk_store: WasmChunkStore,
     ) -> Self {
         Self {
             controllers,
@@ -465,6 +612,15 @@ impl SystemState {
             task_queue,
             global_timer,
             canister_version,
+            next_snapshot_id,
+            snapshots_memory_usage,
+            canister_history,
+            log_visibility,
+            canister_log,
+            wasm_memory_limit,
+            reserved_balance,
+            reserved_balance_limit,
+            wasm_chunk_store,
         }
     }
 
@@ -472,6 +628,27 @@ impl SystemState {
         self.canister_id
     }
 
+    /// Records a change to the canister in its history. Must be called after
+    /// `canister_version` has been bumped for the change.
+    pub fn add_canister_change(
+        &mut self,
+        timestamp: Time,
+        origin: CanisterChangeOrigin,
+        details: CanisterChangeDetails,
+    ) {
+        let change = CanisterChange::new(
+            timestamp.as_nanos_since_unix_epoch(),
+            self.canister_version,
+            origin,
+            details,
+        );
+        self.canister_history.add_canister_change(change);
+    }
+
+    pub fn get_canister_history(&self) -> &CanisterHistory {
+        &self.canister_history
+    }
+
     /// Returns a mutable reference to the balance of the canister.
     pub fn balance_mut(&mut self) -> &mut Cycles {
         &mut self.cycles_balance
@@ -494,6 +671,71 @@ impl SystemState {
         self.cycles_debit
     }
 
+    /// Returns the amount of cycles reserved for future storage payments.
+    pub fn reserved_balance(&self) -> Cycles {
+        self.reserved_balance
+    }
+
+    /// Returns the limit on the reserved balance, if any.
+    pub fn reserved_balance_limit(&self) -> Option<Cycles> {
+        self.reserved_balance_limit
+    }
+
+    /// Sets the limit on the reserved balance. The current reserved balance
+    /// is not affected even if it exceeds the new limit.
+    pub fn set_reserved_balance_limit(&mut self, limit: Cycles) {
+        self.reserved_balance_limit = Some(limit);
+    }
+
+    /// Checks that `amount` cycles can be moved from the main balance to the
+    /// reserved balance under the given limit, without changing the state.
+    pub fn check_reservation(
+        &self,
+        amount: Cycles,
+        reserved_balance_limit: Option<Cycles>,
+    ) -> Result<(), ReservationError> {
+        if amount.get() == 0 {
+            return Ok(());
+        }
+        if let Some(limit) = reserved_balance_limit {
+            let requested = self.reserved_balance + amount;
+            if requested > limit {
+                return Err(ReservationError::ReservedLimitExceeded { requested, limit });
+            }
+        }
+        let available = self.debited_balance();
+        if amount > available {
+            return Err(ReservationError::InsufficientCycles {
+                requested: amount,
+                available,
+            });
+        }
+        Ok(())
+    }
+
+    /// Moves `amount` cycles from the main balance to the reserved balance.
+    ///
+    /// Fails and leaves the state unchanged if the main balance (after the
+    /// pending debit) is too small or if the new reserved balance would exceed
+    /// the reserved balance limit.
+    pub fn reserve_cycles(&mut self, amount: Cycles) -> Result<(), ReservationError> {
+        self.check_reservation(amount, self.reserved_balance_limit)?;
+        self.cycles_balance -= amount;
+        self.reserved_balance += amount;
+        Ok(())
+    }
+
+    /// Removes `amount` cycles from the reserved balance. Should only be
+    /// called by `CyclesAccountManager` when charging for storage.
+    ///
+    /// Precondition:
+    /// - `amount <= self.reserved_balance()`.
+    pub fn remove_reserved_cycles(&mut self, amount: Cycles) {
+        debug_assert!(amount <= self.reserved_balance);
+        // We rely on saturating operations of `Cycles` here.
+        self.reserved_balance -= amount;
+    }
+
     /// Records the given amount as debit that will be charged from the balance
     /// at some point in the future.
     ///
@@ -581,12 +823,14 @@ impl SystemState {
     /// This is preceded by withdrawing the cycles for sending the `Request` and
     /// receiving and processing the corresponding `Response`.
     /// If cycles withdrawal succeeds, the function also reserves a slot on the
-    /// matching input queue for the `Response`.
+    /// matching input queue for the `Response` (for guaranteed response
+    /// requests only).
     ///
     /// # Errors
     ///
     /// Returns a `QueueFull` error along with the provided message if either
-    /// the output queue or the matching input queue is full.
+    /// the output queue or (for guaranteed response requests) the matching
+    /// input queue is full.
     pub fn push_output_request(
         &mut self,
         msg: Arc<Request>,
@@ -623,15 +867,17 @@ impl SystemState {
         self.queues.available_output_request_slots()
     }
 
-    /// Pushes a `Response` type message into the relevant output queue. The
-    /// protocol should have already reserved a slot, so this cannot fail. The
-    /// canister is also refunded the excess cycles that was reserved for
-    /// sending this response when the original request was received.
+    /// Pushes a `Response` type message into the relevant output queue. For
+    /// guaranteed responses, the protocol should have already reserved a slot,
+    /// so this cannot fail. Best-effort responses are dropped if the output
+    /// queue is full. The canister is also refunded the excess cycles that was
+    /// reserved for sending this response when the original request was
+    /// received.
     ///
     /// # Panics
     ///
-    /// Panics if the queue does not already exist or there is no reserved slot
-    /// to push the `Response` into.
+    /// Panics if a guaranteed `Response` is pushed and the queue does not
+    /// already exist or there is no reserved slot to push it into.
     pub fn push_output_response(&mut self, msg: Arc<Response>) {
         assert_eq!(
             msg.respondent, self.canister_id,
@@ -653,10 +899,16 @@ impl SystemState {
 
     /// Pushes a `RequestOrResponse` into the induction pool.
     ///
-    /// If the message is a `Request`, reserves a slot in the corresponding
-    /// output queue for the eventual response; and the maximum memory size and
-    /// cycles cost for sending the `Response` back. If it is a `Response`,
-    /// the protocol should have already reserved a slot and memory for it.
+    /// If the message is a guaranteed response `Request`, reserves a slot in
+    /// the corresponding output queue for the eventual response; and the
+    /// maximum memory size and cycles cost for sending the `Response` back. If
+    /// it is a guaranteed `Response`, the protocol should have already reserved
+    /// a slot and memory for it. Best-effort messages only take up their actual
+    /// size and reserve nothing.
+    ///
+    /// If a guaranteed response `Request` does not fit into the available
+    /// memory, the canister's best-effort messages are shed (see
+    /// `CanisterQueues::shed_best_effort_messages()`) and the push is retried.
     ///
     /// Updates `subnet_available_memory` to reflect any change in memory usage.
     ///
@@ -664,6 +916,10 @@ impl SystemState {
     ///  * `Running` system states accept requests and responses.
     ///  * `Stopping` system states accept responses only.
     ///  * `Stopped` system states accept neither.
+    ///  * Responses to best-effort calls are silently dropped (and `Ok(())`
+    ///    returned) if a response or a `SYS_UNKNOWN` reject was already
+    ///    enqueued for the same callback; or if the callback no longer exists
+    ///    (including because the canister is stopped).
     ///
     /// # Errors
     ///
@@ -697,15 +953,23 @@ impl SystemState {
             msg.receiver()
         );
 
-        match (&msg, &self.status) {
-            // Requests and responses are both rejected when stopped.
+        let canister_id = self.canister_id;
+        match (&msg, &mut self.status) {
+            // Best-effort responses are silently dropped when stopped.
+            (RequestOrResponse::Response(response), CanisterStatus::Stopped { .. })
+                if response.is_best_effort() =>
+            {
+                Ok(())
+            }
+
+            // Requests and guaranteed responses are both rejected when stopped.
             (_, CanisterStatus::Stopped { .. }) => {
-                Err((StateError::CanisterStopped(self.canister_id()), msg))
+                Err((StateError::CanisterStopped(canister_id), msg))
             }
 
             // Requests (only) are rejected while stopping.
             (RequestOrResponse::Request(_), CanisterStatus::Stopping { .. }) => {
-                Err((StateError::CanisterStopping(self.canister_id()), msg))
+                Err((StateError::CanisterStopping(canister_id), msg))
             }
 
             // Everything else is accepted iff there is available memory and queue slots.
@@ -722,19 +986,49 @@ impl SystemState {
                     ..
                 },
             ) => {
-                if let RequestOrResponse::Response(response) = &msg {
-                    call_context_manager
-                        .validate_response(response)
-                        .map_err(|err| (err, msg.clone()))?;
-                }
-                push_input(
+                let callback_id = match &msg {
+                    RequestOrResponse::Response(response) => {
+                        if call_context_manager.should_drop_response(response) {
+                            return Ok(());
+                        }
+                        call_context_manager
+                            .validate_response(response)
+                            .map_err(|err| (err, msg.clone()))?;
+                        Some(response.originator_reply_callback)
+                    }
+                    RequestOrResponse::Request(_) => None,
+                };
+                let res = push_input(
                     &mut self.queues,
                     msg,
                     canister_available_memory,
                     subnet_available_memory,
                     own_subnet_type,
                     input_queue_type,
-                )
+                );
+                match res {
+                    // Make room for a guaranteed response request by shedding
+                    // best-effort messages.
+                    Err((StateError::OutOfMemory { .. }, msg))
+                        if !msg.is_best_effort() && self.queues.best_effort_message_bytes() > 0 =>
+                    {
+                        let freed = self.queues.shed_best_effort_messages() as i64;
+                        *subnet_available_memory += freed;
+                        push_input(
+                            &mut self.queues,
+                            msg,
+                            canister_available_memory + freed,
+                            subnet_available_memory,
+                            own_subnet_type,
+                            input_queue_type,
+                        )?;
+                    }
+                    res => res?,
+                }
+                if let Some(callback_id) = callback_id {
+                    call_context_manager.on_response_enqueued(callback_id);
+                }
+                Ok(())
             }
         }
     }
@@ -878,6 +1172,14 @@ impl SystemState {
         }
     }
 
+    /// Sheds the canister's best-effort messages, returning the number of bytes
+    /// freed.
+    ///
+    /// See `CanisterQueues::shed_best_effort_messages` for further details.
+    pub(crate) fn shed_best_effort_messages(&mut self) -> usize {
+        self.queues.shed_best_effort_messages()
+    }
+
     /// Garbage collects empty input and output queue pairs.
     pub fn garbage_collect_canister_queues(&mut self) {
         self.queues.garbage_collect();
@@ -902,6 +1204,61 @@ impl SystemState {
         self.queues
             .time_out_requests(current_time, own_canister_id, local_canisters)
     }
+
+    /// Queries whether any best-effort callbacks awaiting a response have expired.
+    pub fn has_expired_callbacks(&self, current_time: CoarseTime) -> bool {
+        self.call_context_manager()
+            .map_or(false, |ccm| ccm.has_expired_callbacks(current_time))
+    }
+
+    /// Expires best-effort callbacks whose deadline is at or before
+    /// `current_time`, enqueuing a `SYS_UNKNOWN` reject response for each into
+    /// the matching input queue. Callbacks whose reject could not be enqueued
+    /// (because the input queue is full) are retried on the next call. Returns
+    /// the number of callbacks that were expired.
+    ///
+    /// See `CanisterQueues::push_expired_callback_reject` for further details.
+    pub fn time_out_callbacks(
+        &mut self,
+        current_time: CoarseTime,
+        own_canister_id: &CanisterId,
+        local_canisters: &BTreeMap<CanisterId, CanisterState>,
+    ) -> u64 {
+        let expired_callbacks = match self.call_context_manager() {
+            Some(call_context_manager) => call_context_manager.expired_callbacks(current_time),
+            None => return 0,
+        };
+
+        let mut expired_callbacks_count = 0;
+        for (callback_id, callback) in expired_callbacks {
+            let response = Response {
+                originator: *own_canister_id,
+                respondent: callback
+                    .respondent
+                    .expect("Best-effort callbacks always record the respondent"),
+                originator_reply_callback: callback_id,
+                deadline: callback.deadline,
+                refund: Cycles::zero(),
+                response_payload: Payload::Reject(RejectContext::new_with_message_length_limit(
+                    RejectCode::SysUnknown,
+                    "Call deadline has expired.".to_string(),
+                    MR_SYNTHETIC_REJECT_MESSAGE_MAX_LEN,
+                )),
+            };
+            if self
+                .queues
+                .push_expired_callback_reject(Arc::new(response), own_canister_id, local_canisters)
+                .is_err()
+            {
+                continue;
+            }
+            if let Some(call_context_manager) = self.call_context_manager_mut() {
+                call_context_manager.on_response_enqueued(callback_id);
+            }
+            expired_callbacks_count += 1;
+        }
+        expired_callbacks_count
+    }
 }
 
 /// Implements memory limits verification for pushing a canister-to-canister
@@ -7,15 +7,15 @@ use ic_protobuf::proxy::{try_from_option_field, ProxyDecodeError};
 use ic_protobuf::state::canister_state_bits::v1 as pb;
 use ic_protobuf::types::v1 as pb_types;
 use ic_types::messages::Response;
-use ic_types::Time;
 use ic_types::{
     ingress::WasmResult,
     messages::{CallContextId, CallbackId, MessageId},
     methods::Callback,
     user_id_into_protobuf, user_id_try_from_protobuf, CanisterId, Cycles, Funds, UserId,
 };
+use ic_types::{CoarseTime, Time};
 use serde::{Deserialize, Serialize};
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::convert::{From, TryFrom, TryInto};
 use std::time::Duration;
 
@@ -210,12 +210,25 @@ pub struct CallContextManager {
     // maps call context to its responded status
     call_contexts: BTreeMap<CallContextId, CallContext>,
     callbacks: BTreeMap<CallbackId, Callback>,
+    /// Best-effort callbacks for which a response has already been enqueued:
+    /// either the actual response; or, if the callback expired first, a
+    /// synthetic `SYS_UNKNOWN` reject. Any further responses are dropped.
+    closed_callbacks: BTreeSet<CallbackId>,
+    /// Deadline-ordered index of the best-effort callbacks still awaiting a
+    /// response (i.e. not in `closed_callbacks`), so that expired callbacks
+    /// can be found without scanning all callbacks.
+    ///
+    /// Not included in the protobuf encoding, rebuilt from `callbacks` and
+    /// `closed_callbacks` on decoding.
+    unexpired_callbacks: BTreeSet<(CoarseTime, CallbackId)>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum CallOrigin {
     Ingress(UserId, MessageId),
-    CanisterUpdate(CanisterId, CallbackId),
+    /// A call from a canister, with the deadline of the call (`NO_DEADLINE`
+    /// for guaranteed response calls).
+    CanisterUpdate(CanisterId, CallbackId, CoarseTime),
     Query(UserId),
     CanisterQuery(CanisterId, CallbackId),
     /// System task is either a Heartbeat or a GlobalTimer.
@@ -229,10 +242,11 @@ impl From<&CallOrigin> for pb::call_context::CallOrigin {
                 user_id: Some(user_id_into_protobuf(*user_id)),
                 message_id: message_id.as_bytes().to_vec(),
             }),
-            CallOrigin::CanisterUpdate(canister_id, callback_id) => {
+            CallOrigin::CanisterUpdate(canister_id, callback_id, deadline) => {
                 Self::CanisterUpdate(pb::call_context::CanisterUpdateOrQuery {
                     canister_id: Some(pb_types::CanisterId::from(*canister_id)),
                     callback_id: callback_id.get(),
+                    deadline_seconds: deadline.as_secs_since_unix_epoch(),
                 })
             }
             CallOrigin::Query(user_id) => Self::Query(user_id_into_protobuf(*user_id)),
@@ -240,6 +254,7 @@ impl From<&CallOrigin> for pb::call_context::CallOrigin {
                 Self::CanisterQuery(pb::call_context::CanisterUpdateOrQuery {
                     canister_id: Some(pb_types::CanisterId::from(*canister_id)),
                     callback_id: callback_id.get(),
+                    deadline_seconds: 0,
                 })
             }
             CallOrigin::SystemTask => Self::SystemTask(pb::call_context::SystemTask {}),
@@ -265,10 +280,12 @@ impl TryFrom<pb::call_context::CallOrigin> for CallOrigin {
                 pb::call_context::CanisterUpdateOrQuery {
                     canister_id,
                     callback_id,
+                    deadline_seconds,
                 },
             ) => Self::CanisterUpdate(
                 try_from_option_field(canister_id, "CallOrigin::CanisterUpdate::canister_id")?,
                 callback_id.into(),
+                CoarseTime::from_secs_since_unix_epoch(deadline_seconds),
             ),
             pb::call_context::CallOrigin::Query(user_id) => {
                 Self::Query(user_id_try_from_protobuf(user_id)?)
@@ -277,6 +294,7 @@ impl TryFrom<pb::call_context::CallOrigin> for CallOrigin {
                 pb::call_context::CanisterUpdateOrQuery {
                     canister_id,
                     callback_id,
+                    ..
                 },
             ) => Self::CanisterQuery(
                 try_from_option_field(canister_id, "CallOrigin::CanisterQuery::canister_id")?,
@@ -384,6 +402,58 @@ impl CallContextManager {
         }
     }
 
+    /// Returns `true` if `response` should be silently dropped instead of being
+    /// inducted: it is a response to a best-effort call and either a response
+    /// (or a `SYS_UNKNOWN` reject) was already enqueued for its callback; or
+    /// the callback is gone.
+    pub(crate) fn should_drop_response(&self, response: &Response) -> bool {
+        let callback_id = response.originator_reply_callback;
+        match self.callbacks.get(&callback_id) {
+            Some(callback) => {
+                callback.is_best_effort() && self.closed_callbacks.contains(&callback_id)
+            }
+            None => response.is_best_effort(),
+        }
+    }
+
+    /// Records that a response for the given callback was enqueued. For
+    /// best-effort callbacks, this means that no further responses are
+    /// accepted and the callback will no longer expire.
+    pub(crate) fn on_response_enqueued(&mut self, callback_id: CallbackId) {
+        if let Some(callback) = self.callbacks.get(&callback_id) {
+            if callback.is_best_effort() {
+                self.closed_callbacks.insert(callback_id);
+                self.unexpired_callbacks
+                    .remove(&(callback.deadline, callback_id));
+            }
+        }
+    }
+
+    /// Returns all best-effort callbacks whose deadline is at or before `now`
+    /// and that are still awaiting a response, in deadline order. A
+    /// `SYS_UNKNOWN` reject should be enqueued for each, followed by a call to
+    /// `on_response_enqueued()`.
+    ///
+    /// Time complexity: O(log(num_callbacks) + num_expired_callbacks).
+    pub(crate) fn expired_callbacks(&self, now: CoarseTime) -> Vec<(CallbackId, Callback)> {
+        self.unexpired_callbacks
+            .iter()
+            .take_while(|(deadline, _)| *deadline <= now)
+            .map(|(_, callback_id)| (*callback_id, self.callbacks[callback_id].clone()))
+            .collect()
+    }
+
+    /// Returns `true` if any best-effort callback still awaiting a response
+    /// has a deadline at or before `now`.
+    ///
+    /// Time complexity: O(log(num_callbacks)).
+    pub(crate) fn has_expired_callbacks(&self, now: CoarseTime) -> bool {
+        match self.unexpired_callbacks.iter().next() {
+            Some((deadline, _)) => *deadline <= now,
+            None => false,
+        }
+    }
+
     /// Accepts a canister result and produces an action that should be taken
     /// by the caller.
     pub fn on_canister_result(
@@ -488,6 +558,10 @@ impl CallContextManager {
     pub fn register_callback(&mut self, callback: Callback) -> CallbackId {
         self.next_callback_id += 1;
         let callback_id = CallbackId::from(self.next_callback_id);
+        if callback.is_best_effort() {
+            self.unexpired_callbacks
+                .insert((callback.deadline, callback_id));
+        }
         self.callbacks.insert(callback_id, callback);
         callback_id
     }
@@ -500,7 +574,11 @@ impl CallContextManager {
     /// If we get a response for one of the outstanding calls, we unregister
     /// the callback and return it.
     pub fn unregister_callback(&mut self, callback_id: CallbackId) -> Option<Callback> {
-        self.callbacks.remove(&callback_id)
+        self.closed_callbacks.remove(&callback_id);
+        let callback = self.callbacks.remove(&callback_id)?;
+        self.unexpired_callbacks
+            .remove(&(callback.deadline, callback_id));
+        Some(callback)
     }
 
     /// Returns the call origin, which is either the message id of the ingress
@@ -575,9 +653,11 @@ impl CallContextManager {
 impl From<&RequestOrIngress> for CallOrigin {
     fn from(msg: &RequestOrIngress) -> Self {
         match msg {
-            RequestOrIngress::Request(request) => {
-                CallOrigin::CanisterUpdate(request.sender, request.sender_reply_callback)
-            }
+            RequestOrIngress::Request(request) => CallOrigin::CanisterUpdate(
+                request.sender,
+                request.sender_reply_callback,
+                request.deadline,
+            ),
             RequestOrIngress::Ingress(ingress) => {
                 CallOrigin::Ingress(ingress.source, ingress.message_id.clone())
             }
@@ -606,6 +686,7 @@ impl From<&CallContextManager> for pb::CallContextManager {
                     callback: Some(callback.into()),
                 })
                 .collect(),
+            closed_callbacks: item.closed_callbacks.iter().map(|id| id.get()).collect(),
         }
     }
 }
@@ -636,11 +717,23 @@ impl TryFrom<pb::CallContextManager> for CallContextManager {
             );
         }
 
+        let closed_callbacks: BTreeSet<CallbackId> =
+            value.closed_callbacks.into_iter().map(Into::into).collect();
+        let unexpired_callbacks = callbacks
+            .iter()
+            .filter(|(callback_id, callback)| {
+                callback.is_best_effort() && !closed_callbacks.contains(callback_id)
+            })
+            .map(|(callback_id, callback)| (callback.deadline, *callback_id))
+            .collect();
+
         Ok(Self {
             next_call_context_id: value.next_call_context_id,
             next_callback_id: value.next_callback_id,
             call_contexts,
             callbacks,
+            closed_callbacks,
+            unexpired_callbacks,
         })
     }
 }
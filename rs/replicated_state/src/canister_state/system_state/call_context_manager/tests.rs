@@ -1,5 +1,7 @@
 use super::*;
 use ic_test_utilities::types::ids::canister_test_id;
+use ic_test_utilities::types::messages::ResponseBuilder;
+use ic_types::messages::NO_DEADLINE;
 use ic_types::methods::WasmClosure;
 
 #[test]
@@ -8,13 +10,13 @@ fn call_context_origin() {
     let id = canister_test_id(42);
     let cb_id = CallbackId::from(1);
     let cc_id = ccm.new_call_context(
-        CallOrigin::CanisterUpdate(id, cb_id),
+        CallOrigin::CanisterUpdate(id, cb_id, NO_DEADLINE),
         Cycles::new(10),
         Time::from_nanos_since_unix_epoch(0),
     );
     assert_eq!(
         ccm.call_contexts().get(&cc_id).unwrap().call_origin,
-        CallOrigin::CanisterUpdate(id, cb_id)
+        CallOrigin::CanisterUpdate(id, cb_id, NO_DEADLINE)
     );
 }
 
@@ -26,18 +28,18 @@ fn call_context_handling() {
 
     // On two incoming calls
     let call_context_id1 = call_context_manager.new_call_context(
-        CallOrigin::CanisterUpdate(canister_test_id(123), CallbackId::from(1)),
+        CallOrigin::CanisterUpdate(canister_test_id(123), CallbackId::from(1), NO_DEADLINE),
         Cycles::zero(),
         Time::from_nanos_since_unix_epoch(0),
     );
     let call_context_id2 = call_context_manager.new_call_context(
-        CallOrigin::CanisterUpdate(canister_test_id(123), CallbackId::from(2)),
+        CallOrigin::CanisterUpdate(canister_test_id(123), CallbackId::from(2), NO_DEADLINE),
         Cycles::zero(),
         Time::from_nanos_since_unix_epoch(0),
     );
 
     let call_context_id3 = call_context_manager.new_call_context(
-        CallOrigin::CanisterUpdate(canister_test_id(123), CallbackId::from(3)),
+        CallOrigin::CanisterUpdate(canister_test_id(123), CallbackId::from(3), NO_DEADLINE),
         Cycles::zero(),
         Time::from_nanos_since_unix_epoch(0),
     );
@@ -78,6 +80,7 @@ fn call_context_handling() {
         WasmClosure::new(0, 1),
         WasmClosure::new(2, 3),
         None,
+        NO_DEADLINE,
     ));
     let callback_id2 = call_context_manager.register_callback(Callback::new(
         call_context_id1,
@@ -89,6 +92,7 @@ fn call_context_handling() {
         WasmClosure::new(4, 5),
         WasmClosure::new(6, 7),
         None,
+        NO_DEADLINE,
     ));
 
     // There are 2 ougoing calls
@@ -105,6 +109,7 @@ fn call_context_handling() {
         WasmClosure::new(8, 9),
         WasmClosure::new(10, 11),
         None,
+        NO_DEADLINE,
     ));
     // There is 1 outgoing call
     assert_eq!(call_context_manager.outstanding_calls(call_context_id2), 1);
@@ -254,7 +259,7 @@ fn withdraw_cycles_fails_when_not_enough_available_cycles() {
     let id = canister_test_id(42);
     let cb_id = CallbackId::from(1);
     let cc_id = ccm.new_call_context(
-        CallOrigin::CanisterUpdate(id, cb_id),
+        CallOrigin::CanisterUpdate(id, cb_id, NO_DEADLINE),
         Cycles::new(30),
         Time::from_nanos_since_unix_epoch(0),
     );
@@ -276,7 +281,7 @@ fn withdraw_cycles_succeeds_when_enough_available_cycles() {
     let id = canister_test_id(42);
     let cb_id = CallbackId::from(1);
     let cc_id = ccm.new_call_context(
-        CallOrigin::CanisterUpdate(id, cb_id),
+        CallOrigin::CanisterUpdate(id, cb_id, NO_DEADLINE),
         Cycles::new(30),
         Time::from_nanos_since_unix_epoch(0),
     );
@@ -288,3 +293,94 @@ fn withdraw_cycles_succeeds_when_enough_available_cycles() {
         Ok(())
     );
 }
+
+fn best_effort_callback(call_context_id: CallContextId, deadline: CoarseTime) -> Callback {
+    Callback::new(
+        call_context_id,
+        Some(canister_test_id(1)),
+        Some(canister_test_id(2)),
+        Cycles::zero(),
+        Some(Cycles::zero()),
+        Some(Cycles::zero()),
+        WasmClosure::new(0, 1),
+        WasmClosure::new(2, 3),
+        None,
+        deadline,
+    )
+}
+
+#[test]
+fn expired_callbacks_are_returned_until_a_response_is_enqueued() {
+    let mut ccm = CallContextManager::default();
+    let cc_id = ccm.new_call_context(
+        CallOrigin::CanisterUpdate(canister_test_id(1), CallbackId::from(1), NO_DEADLINE),
+        Cycles::zero(),
+        Time::from_nanos_since_unix_epoch(0),
+    );
+    let deadline = CoarseTime::from_secs_since_unix_epoch(10);
+    let best_effort_id = ccm.register_callback(best_effort_callback(cc_id, deadline));
+    let guaranteed_id = ccm.register_callback(best_effort_callback(cc_id, NO_DEADLINE));
+
+    // Nothing expires before the deadline.
+    let before = CoarseTime::from_secs_since_unix_epoch(9);
+    assert!(!ccm.has_expired_callbacks(before));
+    assert!(ccm.expired_callbacks(before).is_empty());
+
+    // Only the best-effort callback expires.
+    assert!(ccm.has_expired_callbacks(deadline));
+    let expired = ccm.expired_callbacks(deadline);
+    assert_eq!(
+        vec![best_effort_id],
+        expired.into_iter().map(|(id, _)| id).collect::<Vec<_>>()
+    );
+
+    // Round trip through protobuf rebuilds the deadline index.
+    let pb_ccm: pb::CallContextManager = (&ccm).into();
+    let decoded = CallContextManager::try_from(pb_ccm).unwrap();
+    assert_eq!(ccm, decoded);
+    assert!(decoded.has_expired_callbacks(deadline));
+
+    // It keeps expiring until a response (e.g. the `SYS_UNKNOWN` reject) is
+    // enqueued for it.
+    assert!(ccm.has_expired_callbacks(deadline));
+    ccm.on_response_enqueued(best_effort_id);
+    assert!(!ccm.has_expired_callbacks(deadline));
+    assert!(ccm.expired_callbacks(deadline).is_empty());
+
+    // Both callbacks are still registered.
+    assert!(ccm.peek_callback(best_effort_id).is_some());
+    assert!(ccm.peek_callback(guaranteed_id).is_some());
+
+    // Round trip through protobuf preserves the closed callback.
+    let pb_ccm: pb::CallContextManager = (&ccm).into();
+    assert_eq!(ccm, CallContextManager::try_from(pb_ccm).unwrap());
+}
+
+#[test]
+fn should_drop_response_after_callback_closed() {
+    let mut ccm = CallContextManager::default();
+    let cc_id = ccm.new_call_context(
+        CallOrigin::CanisterUpdate(canister_test_id(1), CallbackId::from(1), NO_DEADLINE),
+        Cycles::zero(),
+        Time::from_nanos_since_unix_epoch(0),
+    );
+    let deadline = CoarseTime::from_secs_since_unix_epoch(10);
+    let callback_id = ccm.register_callback(best_effort_callback(cc_id, deadline));
+    let response = ResponseBuilder::new()
+        .originator(canister_test_id(1))
+        .respondent(canister_test_id(2))
+        .originator_reply_callback(callback_id)
+        .deadline(deadline)
+        .build();
+
+    // The first response is accepted.
+    assert!(!ccm.should_drop_response(&response));
+    ccm.on_response_enqueued(callback_id);
+
+    // Any further response is dropped.
+    assert!(ccm.should_drop_response(&response));
+
+    // As is a best-effort response for an unknown callback, once unregistered.
+    ccm.unregister_callback(callback_id);
+    assert!(ccm.should_drop_response(&response));
+}
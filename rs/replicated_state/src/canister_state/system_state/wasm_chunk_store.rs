@@ -0,0 +1,191 @@
+use crate::page_map::{Buffer, PageMap};
+use ic_crypto_sha::Sha256;
+use ic_protobuf::{proxy::ProxyDecodeError, state::canister_state_bits::v1 as pb};
+use ic_types::NumBytes;
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+
+/// The maximum size of a single chunk, in bytes.
+pub const CHUNK_SIZE: u64 = 1024 * 1024;
+
+/// The maximum number of chunks a canister may keep in its chunk store.
+pub const DEFAULT_MAX_NUMBER_OF_CHUNKS: u64 = 100;
+
+/// The SHA-256 hash of a chunk, used to identify it in the chunk store.
+pub type WasmChunkHash = [u8; 32];
+
+/// Where in the chunk store `PageMap` a chunk is stored.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct ChunkInfo {
+    /// The chunk is stored at offset `index * CHUNK_SIZE`.
+    index: u64,
+    /// The actual length of the chunk, at most `CHUNK_SIZE`.
+    length: u64,
+}
+
+/// The part of a `WasmChunkStore` that is not stored in the `PageMap`, i.e.
+/// the location of each chunk. Checkpointed as part of the canister state
+/// bits.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WasmChunkStoreMetadata {
+    chunks: BTreeMap<WasmChunkHash, ChunkInfo>,
+}
+
+impl WasmChunkStoreMetadata {
+    /// The memory used by the chunk store. Every chunk is accounted as
+    /// `CHUNK_SIZE` bytes, regardless of its actual length.
+    pub fn memory_usage(&self) -> NumBytes {
+        NumBytes::from(self.chunks.len() as u64 * CHUNK_SIZE)
+    }
+}
+
+impl From<&WasmChunkStoreMetadata> for pb::WasmChunkStoreMetadata {
+    fn from(item: &WasmChunkStoreMetadata) -> Self {
+        Self {
+            chunks: item
+                .chunks
+                .iter()
+                .map(|(hash, info)| pb::WasmChunkData {
+                    hash: hash.to_vec(),
+                    index: info.index,
+                    length: info.length,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<pb::WasmChunkStoreMetadata> for WasmChunkStoreMetadata {
+    type Error = ProxyDecodeError;
+
+    fn try_from(value: pb::WasmChunkStoreMetadata) -> Result<Self, Self::Error> {
+        let mut chunks = BTreeMap::new();
+        for chunk in value.chunks {
+            let hash = WasmChunkHash::try_from(chunk.hash.as_slice()).map_err(|_| {
+                ProxyDecodeError::InvalidDigestLength {
+                    expected: 32,
+                    actual: chunk.hash.len(),
+                }
+            })?;
+            chunks.insert(
+                hash,
+                ChunkInfo {
+                    index: chunk.index,
+                    length: chunk.length,
+                },
+            );
+        }
+        Ok(Self { chunks })
+    }
+}
+
+/// A per-canister store of Wasm chunks, used to install modules that are too
+/// large to fit into a single message. Chunks are identified by their hash and
+/// laid out back to back, `CHUNK_SIZE` bytes apart, in a `PageMap`.
+///
+/// Chunks can only be added or cleared all at once, so the index of a new
+/// chunk is always the number of chunks already stored.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WasmChunkStore {
+    data: PageMap,
+    metadata: WasmChunkStoreMetadata,
+}
+
+impl WasmChunkStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reassembles a chunk store from its checkpointed parts.
+    pub fn from_checkpoint(data: PageMap, metadata: WasmChunkStoreMetadata) -> Self {
+        Self { data, metadata }
+    }
+
+    pub fn page_map(&self) -> &PageMap {
+        &self.data
+    }
+
+    pub fn page_map_mut(&mut self) -> &mut PageMap {
+        &mut self.data
+    }
+
+    pub fn metadata(&self) -> &WasmChunkStoreMetadata {
+        &self.metadata
+    }
+
+    pub fn memory_usage(&self) -> NumBytes {
+        self.metadata.memory_usage()
+    }
+
+    /// Hashes of all stored chunks, in ascending order.
+    pub fn keys(&self) -> impl Iterator<Item = &WasmChunkHash> {
+        self.metadata.chunks.keys()
+    }
+
+    /// Returns the contents of the chunk with the given hash, if present.
+    pub fn get_chunk_data(&self, hash: &WasmChunkHash) -> Option<Vec<u8>> {
+        self.metadata.chunks.get(hash).map(|info| {
+            let mut data = vec![0; info.length as usize];
+            Buffer::new(self.data.clone()).read(&mut data, (info.index * CHUNK_SIZE) as usize);
+            data
+        })
+    }
+
+    /// Checks whether `chunk` can be added to a store holding at most
+    /// `max_number_of_chunks` chunks. Returns a user-facing error otherwise.
+    pub fn can_insert_chunk(&self, max_number_of_chunks: u64, chunk: &[u8]) -> Result<(), String> {
+        if chunk.len() as u64 > CHUNK_SIZE {
+            return Err(format!(
+                "Wasm chunk size {} exceeds the maximum chunk size of {} bytes.",
+                chunk.len(),
+                CHUNK_SIZE
+            ));
+        }
+        if self.metadata.chunks.len() as u64 >= max_number_of_chunks
+            && !self.metadata.chunks.contains_key(&Sha256::hash(chunk))
+        {
+            return Err(format!(
+                "Wasm chunk store already contains the maximum of {} chunks.",
+                max_number_of_chunks
+            ));
+        }
+        Ok(())
+    }
+
+    /// Adds `chunk` to the store and returns its hash. Uploading a chunk that
+    /// is already present is a no-op.
+    pub fn insert_chunk(
+        &mut self,
+        max_number_of_chunks: u64,
+        chunk: &[u8],
+    ) -> Result<WasmChunkHash, String> {
+        self.can_insert_chunk(max_number_of_chunks, chunk)?;
+
+        let hash = Sha256::hash(chunk);
+        if self.metadata.chunks.contains_key(&hash) {
+            return Ok(hash);
+        }
+
+        let index = self.metadata.chunks.len() as u64;
+        let mut buffer = Buffer::new(self.data.clone());
+        buffer.write(chunk, (index * CHUNK_SIZE) as usize);
+        self.data.update(&buffer.dirty_pages().collect::<Vec<_>>());
+        self.metadata.chunks.insert(
+            hash,
+            ChunkInfo {
+                index,
+                length: chunk.len() as u64,
+            },
+        );
+        Ok(hash)
+    }
+
+    /// Removes all chunks. The `PageMap` is replaced by a fresh one, so that
+    /// the state manager also truncates the backing file.
+    pub fn clear(&mut self) {
+        *self = Self::new();
+    }
+}
+
+#[cfg(test)]
+mod tests;
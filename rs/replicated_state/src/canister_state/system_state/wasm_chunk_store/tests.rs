@@ -0,0 +1,80 @@
+use super::*;
+
+#[test]
+fn insert_and_get_chunks() {
+    let mut store = WasmChunkStore::new();
+    let chunk1 = vec![1; 10];
+    let chunk2 = vec![2; CHUNK_SIZE as usize];
+
+    let hash1 = store
+        .insert_chunk(DEFAULT_MAX_NUMBER_OF_CHUNKS, &chunk1)
+        .unwrap();
+    let hash2 = store
+        .insert_chunk(DEFAULT_MAX_NUMBER_OF_CHUNKS, &chunk2)
+        .unwrap();
+
+    assert_eq!(hash1, Sha256::hash(&chunk1));
+    assert_eq!(Some(chunk1), store.get_chunk_data(&hash1));
+    assert_eq!(Some(chunk2), store.get_chunk_data(&hash2));
+    assert_eq!(None, store.get_chunk_data(&[0; 32]));
+    assert_eq!(NumBytes::from(2 * CHUNK_SIZE), store.memory_usage());
+}
+
+#[test]
+fn inserting_existing_chunk_is_noop() {
+    let mut store = WasmChunkStore::new();
+    let chunk = vec![7; 1000];
+
+    let hash = store.insert_chunk(1, &chunk).unwrap();
+    // The store is full, but the chunk is already present.
+    assert_eq!(Ok(hash), store.insert_chunk(1, &chunk));
+    assert_eq!(1, store.keys().count());
+    assert_eq!(NumBytes::from(CHUNK_SIZE), store.memory_usage());
+}
+
+#[test]
+fn insert_fails_for_oversized_chunk_or_full_store() {
+    let mut store = WasmChunkStore::new();
+
+    assert!(store
+        .insert_chunk(
+            DEFAULT_MAX_NUMBER_OF_CHUNKS,
+            &vec![0; CHUNK_SIZE as usize + 1]
+        )
+        .is_err());
+
+    store.insert_chunk(1, &[1]).unwrap();
+    assert!(store.insert_chunk(1, &[2]).is_err());
+    assert_eq!(1, store.keys().count());
+}
+
+#[test]
+fn clear_removes_all_chunks() {
+    let mut store = WasmChunkStore::new();
+    let hash = store
+        .insert_chunk(DEFAULT_MAX_NUMBER_OF_CHUNKS, &[1, 2, 3])
+        .unwrap();
+
+    store.clear();
+
+    assert_eq!(0, store.keys().count());
+    assert_eq!(None, store.get_chunk_data(&hash));
+    assert_eq!(NumBytes::from(0), store.memory_usage());
+}
+
+#[test]
+fn metadata_proto_round_trip() {
+    let mut store = WasmChunkStore::new();
+    store
+        .insert_chunk(DEFAULT_MAX_NUMBER_OF_CHUNKS, &[1, 2, 3])
+        .unwrap();
+    store
+        .insert_chunk(DEFAULT_MAX_NUMBER_OF_CHUNKS, &[4; 5000])
+        .unwrap();
+
+    let pb_metadata = pb::WasmChunkStoreMetadata::from(store.metadata());
+    assert_eq!(
+        store.metadata(),
+        &WasmChunkStoreMetadata::try_from(pb_metadata).unwrap()
+    );
+}
@@ -8,7 +8,7 @@ use ic_test_utilities::types::{
     ids::user_test_id,
     messages::{RequestBuilder, ResponseBuilder},
 };
-use ic_types::messages::CallContextId;
+use ic_types::messages::{CallContextId, NO_DEADLINE};
 use ic_types::{
     messages::CallbackId,
     methods::{Callback, WasmClosure},
@@ -64,7 +64,7 @@ fn canister_state_push_input_response_no_reservation() {
             .call_context_manager_mut()
             .unwrap()
             .new_call_context(
-                CallOrigin::CanisterUpdate(CANISTER_ID, CallbackId::from(1)),
+                CallOrigin::CanisterUpdate(CANISTER_ID, CallbackId::from(1), NO_DEADLINE),
                 Cycles::zero(),
                 Time::from_nanos_since_unix_epoch(0),
             );
@@ -82,6 +82,7 @@ fn canister_state_push_input_response_no_reservation() {
                 WasmClosure::new(0, 2),
                 WasmClosure::new(0, 2),
                 None,
+                NO_DEADLINE,
             ));
 
         let response: RequestOrResponse = ResponseBuilder::default()
@@ -125,7 +126,7 @@ fn canister_state_push_input_response_success() {
             .call_context_manager_mut()
             .unwrap()
             .new_call_context(
-                CallOrigin::CanisterUpdate(CANISTER_ID, CallbackId::from(1)),
+                CallOrigin::CanisterUpdate(CANISTER_ID, CallbackId::from(1), NO_DEADLINE),
                 Cycles::zero(),
                 Time::from_nanos_since_unix_epoch(0),
             );
@@ -143,6 +144,7 @@ fn canister_state_push_input_response_success() {
                 WasmClosure::new(0, 2),
                 WasmClosure::new(0, 2),
                 None,
+                NO_DEADLINE,
             ));
 
         canister_state
@@ -456,7 +458,7 @@ fn canister_state_push_input_response_memory_limit_test_impl(
             .call_context_manager_mut()
             .unwrap()
             .new_call_context(
-                CallOrigin::CanisterUpdate(CANISTER_ID, CallbackId::from(1)),
+                CallOrigin::CanisterUpdate(CANISTER_ID, CallbackId::from(1), NO_DEADLINE),
                 Cycles::zero(),
                 Time::from_nanos_since_unix_epoch(0),
             );
@@ -474,6 +476,7 @@ fn canister_state_push_input_response_memory_limit_test_impl(
                 WasmClosure::new(0, 2),
                 WasmClosure::new(0, 2),
                 None,
+                NO_DEADLINE,
             ));
 
         let response: RequestOrResponse = ResponseBuilder::default()
@@ -589,6 +592,7 @@ fn canister_state_callback_round_trip() {
         WasmClosure::new(0, 2),
         WasmClosure::new(0, 2),
         None,
+        NO_DEADLINE,
     );
 
     let pb_callback = pb::Callback::from(&callback);
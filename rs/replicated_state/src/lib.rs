@@ -1,5 +1,6 @@
 mod bitcoin;
 pub mod bitcoin_state;
+pub mod canister_snapshots;
 pub mod canister_state;
 pub mod metadata_state;
 pub mod page_map;
@@ -11,15 +12,18 @@ pub mod testing {
     pub use super::replicated_state::testing::ReplicatedStateTesting;
 }
 pub use bitcoin_state::{BitcoinState, BitcoinStateError};
+pub use canister_snapshots::{CanisterSnapshot, CanisterSnapshots, SnapshotId};
 pub use canister_state::{
     execution_state::Memory,
     num_bytes_try_from,
     system_state::{
-        memory_required_to_push_request, CallContext, CallContextAction, CallContextManager,
-        CallOrigin, CanisterMetrics, CanisterStatus, ExecutionTask, SystemState,
+        memory_required_to_push_request,
+        wasm_chunk_store::{WasmChunkStore, WasmChunkStoreMetadata},
+        CallContext, CallContextAction, CallContextManager, CallOrigin, CanisterHistory,
+        CanisterMetrics, CanisterStatus, ExecutionTask, ReservationError, SystemState,
     },
     CanisterQueues, CanisterState, EmbedderCache, ExecutionState, ExportedFunctions, Global,
-    NumWasmPages, SchedulerState,
+    NumWasmPages, SchedulerState, TotalQueryStats,
 };
 pub use metadata_state::{NetworkTopology, NodeTopology, Stream, SubnetTopology, SystemMetadata};
 pub use page_map::{PageIndex, PageMap};
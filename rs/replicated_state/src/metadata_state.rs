@@ -1,8 +1,11 @@
+pub mod query_stats;
 pub mod subnet_call_context_manager;
 #[cfg(test)]
 mod tests;
 
-use crate::metadata_state::subnet_call_context_manager::SubnetCallContextManager;
+use crate::metadata_state::{
+    query_stats::RawQueryStats, subnet_call_context_manager::SubnetCallContextManager,
+};
 use ic_base_types::CanisterId;
 use ic_btc_types::Network as BitcoinNetwork;
 use ic_btc_types_internal::BlockBlob;
@@ -141,6 +144,10 @@ pub struct SystemMetadata {
     /// response limit. To work around this limitation, large responses are paginated
     /// and are stored here temporarily until they're fetched by the calling canister.
     pub bitcoin_get_successors_follow_up_responses: BTreeMap<CanisterId, Vec<BlockBlob>>,
+
+    /// Query statistics contributed by the replicas of this subnet that have
+    /// not yet been aggregated into the canisters' totals.
+    pub raw_query_stats: RawQueryStats,
 }
 
 /// Full description of the IC network toplogy.
@@ -480,6 +487,7 @@ impl From<&SystemMetadata> for pb_metadata::SystemMetadata {
                     },
                 )
                 .collect(),
+            raw_query_stats: Some((&item.raw_query_stats).into()),
         }
     }
 }
@@ -576,6 +584,11 @@ impl TryFrom<pb_metadata::SystemMetadata> for SystemMetadata {
             },
             expected_compiled_wasms: BTreeSet::new(),
             bitcoin_get_successors_follow_up_responses,
+            raw_query_stats: item
+                .raw_query_stats
+                .map(RawQueryStats::try_from)
+                .transpose()?
+                .unwrap_or_default(),
         })
     }
 }
@@ -609,6 +622,7 @@ impl SystemMetadata {
             subnet_metrics: Default::default(),
             expected_compiled_wasms: BTreeSet::new(),
             bitcoin_get_successors_follow_up_responses: BTreeMap::default(),
+            raw_query_stats: RawQueryStats::default(),
         }
     }
 
@@ -621,6 +635,42 @@ impl SystemMetadata {
         &self.streams
     }
 
+    /// Splits the metadata of a subnet when the canisters for which
+    /// `is_migrated` holds are moved to the new subnet `new_subnet_id`. Returns
+    /// the metadata of the retaining subnet, which keeps the subnet id, and the
+    /// metadata of the receiving subnet.
+    ///
+    /// Everything that is addressed to the subnet itself stays on the retaining
+    /// subnet: streams, canister ID allocation ranges and subnet call contexts.
+    /// Responses to the latter are routed to migrated canisters like to any
+    /// other remote canister. The ingress history is split by receiver, with
+    /// messages to the management canister staying on the retaining subnet.
+    pub fn split<F>(self, new_subnet_id: SubnetId, is_migrated: F) -> (Self, Self)
+    where
+        F: Fn(&CanisterId) -> bool,
+    {
+        let mut received = SystemMetadata::new(new_subnet_id, self.own_subnet_type);
+        received.ingress_history = self.ingress_history.clone();
+        received.ingress_history.retain(|status| {
+            status
+                .receiver()
+                .map_or(false, |receiver| is_migrated(&receiver))
+        });
+        received.batch_time = self.batch_time;
+        received.network_topology = self.network_topology.clone();
+        received.own_subnet_features = self.own_subnet_features;
+        received.state_sync_version = self.state_sync_version;
+        received.certification_version = self.certification_version;
+
+        let mut retained = self;
+        retained.ingress_history.retain(|status| {
+            !status
+                .receiver()
+                .map_or(false, |receiver| is_migrated(&receiver))
+        });
+        (retained, received)
+    }
+
     /// One-off initialization: populate `canister_allocation_ranges` with the only
     /// `[N * 2^20, (N+1) * 2^20 - 1]` range fully hosted by the subnet as per the
     /// routing table; and initialize `last_generated_canister_id` based on
@@ -1386,6 +1436,21 @@ impl IngressHistoryState {
         self.statuses.is_empty()
     }
 
+    /// Retains only the entries whose status satisfies the predicate.
+    pub fn retain<F>(&mut self, f: F)
+    where
+        F: Fn(&IngressStatus) -> bool,
+    {
+        let statuses = Arc::make_mut(&mut self.statuses);
+        statuses.retain(|_, status| f(status));
+        let pruning_times = Arc::make_mut(&mut self.pruning_times);
+        for message_ids in pruning_times.values_mut() {
+            message_ids.retain(|message_id| statuses.contains_key(message_id));
+        }
+        pruning_times.retain(|_, message_ids| !message_ids.is_empty());
+        self.memory_usage = Self::compute_memory_usage(&self.statuses);
+    }
+
     /// Removes ingress history entries that are associated with a pruning_time
     /// that's older than the given time.
     pub fn prune(&mut self, time: Time) {
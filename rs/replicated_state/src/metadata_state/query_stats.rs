@@ -0,0 +1,139 @@
+use crate::canister_state::TotalQueryStats;
+use ic_base_types::{CanisterId, NodeId};
+use ic_protobuf::{
+    proxy::{try_from_option_field, ProxyDecodeError},
+    state::system_metadata::v1 as pb_metadata,
+    types::v1 as pb_types,
+};
+use ic_types::{
+    batch::{QueryStats, QueryStatsEpoch},
+    node_id_into_protobuf, node_id_try_from_protobuf,
+};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    convert::TryFrom,
+};
+
+/// The query statistics contributed by the replicas of this subnet for the
+/// epoch that is currently being aggregated.
+///
+/// Each replica contributes the statistics of the queries it executed locally
+/// at most once per epoch. The contributions are kept until the first
+/// contribution for a later epoch is delivered, at which point they are
+/// aggregated into the totals of the respective canisters.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RawQueryStats {
+    pub epoch: QueryStatsEpoch,
+    pub stats: BTreeMap<NodeId, BTreeMap<CanisterId, QueryStats>>,
+}
+
+impl RawQueryStats {
+    /// Returns whether `node_id` already contributed statistics for the
+    /// current epoch.
+    pub fn has_contribution(&self, node_id: &NodeId) -> bool {
+        self.stats.contains_key(node_id)
+    }
+
+    /// Deterministically aggregates the contributions into per-canister
+    /// totals.
+    ///
+    /// For every counter, the median of the values reported by all
+    /// contributors (a missing report counts as zero) is taken and multiplied
+    /// by the number of contributors. This estimates the subnet-wide total
+    /// while bounding the influence of any minority of faulty replicas.
+    pub fn aggregate(&self) -> BTreeMap<CanisterId, TotalQueryStats> {
+        let canister_ids: BTreeSet<CanisterId> = self
+            .stats
+            .values()
+            .flat_map(|canister_stats| canister_stats.keys().cloned())
+            .collect();
+
+        canister_ids
+            .into_iter()
+            .map(|canister_id| {
+                let reported: Vec<QueryStats> = self
+                    .stats
+                    .values()
+                    .map(|canister_stats| {
+                        canister_stats
+                            .get(&canister_id)
+                            .cloned()
+                            .unwrap_or_default()
+                    })
+                    .collect();
+                let scaled_median = |field: fn(&QueryStats) -> u64| {
+                    let mut values: Vec<u64> = reported.iter().map(field).collect();
+                    values.sort_unstable();
+                    values[values.len() / 2].saturating_mul(values.len() as u64)
+                };
+                (
+                    canister_id,
+                    TotalQueryStats {
+                        num_calls: scaled_median(|stats| stats.num_calls as u64),
+                        num_instructions: scaled_median(|stats| stats.num_instructions),
+                        ingress_payload_size: scaled_median(|stats| stats.ingress_payload_size),
+                        egress_payload_size: scaled_median(|stats| stats.egress_payload_size),
+                    },
+                )
+            })
+            .collect()
+    }
+}
+
+impl From<&RawQueryStats> for pb_metadata::RawQueryStats {
+    fn from(item: &RawQueryStats) -> Self {
+        Self {
+            epoch: item.epoch.get(),
+            node_stats: item
+                .stats
+                .iter()
+                .map(|(node_id, canister_stats)| pb_metadata::NodeQueryStats {
+                    node_id: Some(node_id_into_protobuf(*node_id)),
+                    canister_stats: canister_stats
+                        .iter()
+                        .map(|(canister_id, stats)| pb_metadata::CanisterQueryStats {
+                            canister_id: Some(pb_types::CanisterId::from(*canister_id)),
+                            num_calls: stats.num_calls,
+                            num_instructions: stats.num_instructions,
+                            ingress_payload_size: stats.ingress_payload_size,
+                            egress_payload_size: stats.egress_payload_size,
+                        })
+                        .collect(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<pb_metadata::RawQueryStats> for RawQueryStats {
+    type Error = ProxyDecodeError;
+
+    fn try_from(item: pb_metadata::RawQueryStats) -> Result<Self, Self::Error> {
+        let mut stats = BTreeMap::new();
+        for node_stats in item.node_stats {
+            let node_id = node_id_try_from_protobuf(try_from_option_field(
+                node_stats.node_id,
+                "NodeQueryStats::node_id",
+            )?)?;
+            let mut canister_stats = BTreeMap::new();
+            for entry in node_stats.canister_stats {
+                let canister_id: pb_types::CanisterId =
+                    try_from_option_field(entry.canister_id, "CanisterQueryStats::canister_id")?;
+                canister_stats.insert(
+                    CanisterId::try_from(canister_id)?,
+                    QueryStats {
+                        num_calls: entry.num_calls,
+                        num_instructions: entry.num_instructions,
+                        ingress_payload_size: entry.ingress_payload_size,
+                        egress_payload_size: entry.egress_payload_size,
+                    },
+                );
+            }
+            stats.insert(node_id, canister_stats);
+        }
+        Ok(Self {
+            epoch: QueryStatsEpoch::from(item.epoch),
+            stats,
+        })
+    }
+}
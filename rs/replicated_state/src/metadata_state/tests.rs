@@ -89,6 +89,61 @@ fn can_prune_old_ingress_history_entries() {
     assert!(ingress_history.get(&message_id3).is_some());
 }
 
+#[test]
+fn split_moves_ingress_history_of_migrated_canisters() {
+    let mut metadata = SystemMetadata::new(SUBNET_0, SubnetType::Application);
+    let time = mock_time();
+    metadata.batch_time = time;
+
+    let receivers = [
+        canister_test_id(1),
+        canister_test_id(2),
+        CanisterId::ic_00(),
+    ];
+    for (i, receiver) in receivers.iter().enumerate() {
+        metadata.ingress_history.insert(
+            message_test_id(i as u64),
+            IngressStatus::Known {
+                receiver: receiver.get(),
+                user_id: user_test_id(1),
+                time,
+                state: IngressState::Completed(WasmResult::Reply(vec![])),
+            },
+            time,
+            NumBytes::from(u64::MAX),
+        );
+    }
+
+    let (retained, received) =
+        metadata.split(SUBNET_1, |canister_id| *canister_id == canister_test_id(2));
+
+    assert_eq!(retained.own_subnet_id, SUBNET_0);
+    assert_eq!(received.own_subnet_id, SUBNET_1);
+    assert_eq!(received.batch_time, time);
+
+    let message_ids = |metadata: &SystemMetadata| {
+        metadata
+            .ingress_history
+            .statuses()
+            .map(|(id, _)| id.clone())
+            .collect::<Vec<_>>()
+    };
+    // Messages to the management canister stay on the retaining subnet.
+    assert_eq!(
+        message_ids(&retained),
+        vec![message_test_id(0), message_test_id(2)]
+    );
+    assert_eq!(message_ids(&received), vec![message_test_id(1)]);
+
+    // Pruning times only refer to the retained messages.
+    let pruned: Vec<_> = received
+        .ingress_history
+        .pruning_times()
+        .flat_map(|(_, ids)| ids.iter().cloned())
+        .collect();
+    assert_eq!(pruned, vec![message_test_id(1)]);
+}
+
 #[test]
 fn entries_sorted_lexicographically() {
     let mut ingress_history = IngressHistoryState::new();
@@ -1,5 +1,6 @@
 mod checkpoint;
 pub mod int_map;
+mod overlay;
 mod page_allocator;
 
 use checkpoint::Checkpoint;
@@ -7,6 +8,8 @@ pub use checkpoint::{CheckpointSerialization, MappingSerialization};
 use ic_sys::PageBytes;
 pub use ic_sys::{PageIndex, PAGE_SIZE};
 use ic_utils::{deterministic_operations::deterministic_copy_from_slice, fs::write_all_vectored};
+use overlay::Overlay;
+pub use overlay::OverlaySerialization;
 pub use page_allocator::{
     allocated_pages_count, PageAllocator, PageAllocatorSerialization, PageDeltaSerialization,
     PageSerialization,
@@ -25,7 +28,7 @@ use std::collections::HashMap;
 use std::fs::{File, OpenOptions};
 use std::ops::Range;
 use std::os::unix::io::RawFd;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 // When persisting PageDeltas, the maximum gap between dirty pages
 // that can be combined into a single vectorized write
@@ -199,6 +202,8 @@ pub enum PersistenceError {
     },
     /// (Slice) size is not equal to page size.
     BadPageSize { expected: usize, actual: usize },
+    /// Overlay file is malformed.
+    InvalidOverlayFile { path: String, message: String },
 }
 
 impl PersistenceError {
@@ -244,6 +249,9 @@ impl std::fmt::Display for PersistenceError {
                 "Bad slice size: expected {}, actual {}",
                 expected, actual
             ),
+            PersistenceError::InvalidOverlayFile { path, message } => {
+                write!(f, "Invalid overlay file {}: {}", path, message)
+            }
         }
     }
 }
@@ -264,8 +272,9 @@ pub type FileOffset = off_t;
 /// pages share the same backing store. There are three possible cases:
 /// - The page is not in the current `PageMap` and it is zero initialized.
 /// - The page maps to the checkpoint file.
-/// - The page is in the page delta of the current `PageMap`. In this case the
-///   range is a singleton and its contents need to be copied out.
+/// - The page is in the page delta of the current `PageMap` or in one of the
+///   overlay files of its checkpoint. In this case the range is a singleton and
+///   its contents need to be copied out.
 pub enum MemoryRegion<'a> {
     Zeros(Range<PageIndex>),
     BackedByFile(Range<PageIndex>, FileDescriptor),
@@ -327,7 +336,19 @@ impl PageMap {
     ///
     /// Note that the file is assumed to be read-only.
     pub fn open(heap_file: &Path, base_height: Height) -> Result<Self, PersistenceError> {
-        let checkpoint = Checkpoint::open(heap_file)?;
+        Self::open_with_overlays(heap_file, &[], base_height)
+    }
+
+    /// Creates a page map backed by the provided heap file and the given
+    /// overlay files on top of it, ordered from the oldest to the newest.
+    ///
+    /// Note that all the files are assumed to be read-only.
+    pub fn open_with_overlays(
+        heap_file: &Path,
+        overlays: &[PathBuf],
+        base_height: Height,
+    ) -> Result<Self, PersistenceError> {
+        let checkpoint = Checkpoint::open_with_overlays(heap_file, overlays)?;
         Ok(Self {
             checkpoint,
             base_height: Some(base_height),
@@ -410,6 +431,54 @@ impl PageMap {
         self.persist_to_file(&self.round_delta, dst)
     }
 
+    /// Persists the heap delta contained in this page map as a new overlay
+    /// file at the specified destination. Unlike `persist_delta()`, the base
+    /// file of the page map is not modified.
+    pub fn persist_overlay(&self, dst: &Path) -> Result<(), PersistenceError> {
+        Overlay::write(
+            dst,
+            self.page_delta
+                .iter()
+                .map(|(index, page)| (index, page.contents())),
+        )
+    }
+
+    /// Applies the pages of all overlays of this page map to the specified
+    /// destination, which must contain a copy of the base file. Afterwards,
+    /// the destination alone holds the checkpointed contents of this page
+    /// map. The page delta is not persisted.
+    pub fn persist_overlays(&self, dst: &Path) -> Result<(), PersistenceError> {
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .open(dst)
+            .map_err(|err| PersistenceError::FileSystemError {
+                path: dst.display().to_string(),
+                context: "Failed to open file".to_string(),
+                internal_error: err.to_string(),
+            })?;
+
+        let mut opt_buffer: Option<WriteBuffer> = None;
+        for index in self.checkpoint.overlay_page_indices() {
+            let content = self.checkpoint.get_page(index);
+            if let Some(buffer) = &mut opt_buffer {
+                if buffer.start_index.get() + buffer.content.len() as u64 == index.get() {
+                    buffer.content.push(content);
+                    continue;
+                }
+                buffer.apply_to_file(&mut file, dst)?;
+            }
+            opt_buffer = Some(WriteBuffer {
+                content: vec![content],
+                start_index: index,
+            });
+        }
+        if let Some(buffer) = &mut opt_buffer {
+            buffer.apply_to_file(&mut file, dst)?;
+        }
+        Ok(())
+    }
+
     /// Returns the iterator over host pages managed by this `PageMap`.
     pub fn host_pages_iter(&self) -> impl Iterator<Item = (PageIndex, &PageBytes)> + '_ {
         (0..self.num_host_pages()).map(move |i| {
@@ -503,6 +572,18 @@ impl PageMap {
         self.has_stripped_round_deltas
     }
 
+    /// Returns the number of overlay files of the checkpoint backing this
+    /// page map.
+    pub fn num_overlays(&self) -> usize {
+        self.checkpoint.num_overlays()
+    }
+
+    /// Returns the sorted indices of the pages stored in the overlay files of
+    /// the checkpoint backing this page map.
+    pub fn get_overlay_page_indices(&self) -> Vec<PageIndex> {
+        self.checkpoint.overlay_page_indices()
+    }
+
     /// Returns the length of the modified prefix in host pages.
     ///
     /// Also, the following property holds:
@@ -1,3 +1,4 @@
+use crate::page_map::overlay::{Overlay, OverlaySerialization};
 use crate::page_map::{FileDescriptor, MemoryRegion, PageIndex, PersistenceError};
 use ic_sys::{mmap::ScopedMmap, PAGE_SIZE};
 use ic_sys::{page_bytes_from_ptr, PageBytes};
@@ -7,7 +8,7 @@ use std::fs::{File, OpenOptions};
 use std::ops::Range;
 use std::os::unix::io::AsRawFd;
 use std::os::unix::prelude::FromRawFd;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use super::FileOffset;
@@ -19,21 +20,25 @@ lazy_static! {
 /// Checkpoint represents a full snapshot of the heap of a single Wasm
 /// module.
 ///
-/// Conceptually it's an immutable byte array backed by a file and
-/// aligned to a page boundary.
+/// Conceptually it's an immutable byte array backed by a base file and
+/// a stack of overlay files, aligned to a page boundary.
 #[derive(Clone)]
 pub(crate) struct Checkpoint {
     mapping: Option<Arc<Mapping>>,
+    /// The overlays on top of the base file, from the oldest to the newest.
+    /// A page in a newer overlay shadows the same page in older overlays
+    /// and in the base file.
+    overlays: Vec<Arc<Overlay>>,
 }
 
-struct Mapping {
+pub(super) struct Mapping {
     mmap: ScopedMmap,
     _file: File, // It is not used but it keeps the `file_descriptor` alive.
     file_descriptor: FileDescriptor,
 }
 
 impl Mapping {
-    fn new(
+    pub(super) fn new(
         file: File,
         len: usize,
         path: Option<&Path>,
@@ -91,7 +96,7 @@ impl Mapping {
     }
 
     /// Returns a serialization-friendly representation of `Mapping`.
-    fn serialize(&self) -> MappingSerialization {
+    pub(super) fn serialize(&self) -> MappingSerialization {
         MappingSerialization {
             file_descriptor: self.file_descriptor.clone(),
             file_len: self.mmap.len() as FileOffset,
@@ -99,7 +104,7 @@ impl Mapping {
     }
 
     /// Creates `Mapping` from the given serialization-friendly representation.
-    fn deserialize(
+    pub(super) fn deserialize(
         serialized_mapping: MappingSerialization,
     ) -> Result<Option<Mapping>, PersistenceError> {
         // SAFETY: the file descriptor is valid because `serialized_mapping` is
@@ -108,7 +113,7 @@ impl Mapping {
         Mapping::new(file, serialized_mapping.file_len as usize, None)
     }
 
-    fn get_page(&self, page_index: PageIndex) -> &PageBytes {
+    pub(super) fn get_page(&self, page_index: PageIndex) -> &PageBytes {
         let num_pages = self.mmap.len() / PAGE_SIZE;
         if page_index.get() < num_pages as u64 {
             let page_start = (page_index.get() as usize * PAGE_SIZE) as isize;
@@ -130,7 +135,7 @@ impl Mapping {
         let num_pages = (self.mmap.len() / PAGE_SIZE) as u64;
         if page_index.get() >= num_pages {
             MemoryRegion::Zeros(Range {
-                start: PageIndex::new(num_pages),
+                start: PageIndex::new(num_pages.max(page_range.start.get())),
                 end: page_range.end,
             })
         } else {
@@ -153,13 +158,31 @@ impl Checkpoint {
     /// Returns an empty checkpoint, not backed by any file. It serves
     /// zeroed pages.
     pub fn empty() -> Checkpoint {
-        Checkpoint { mapping: None }
+        Checkpoint {
+            mapping: None,
+            overlays: Vec::new(),
+        }
     }
 
     /// Opens an existing heap file located at the specified path.
     pub fn open(path: &Path) -> Result<Checkpoint, PersistenceError> {
-        Mapping::open(path).map(|mapping| Checkpoint {
+        Self::open_with_overlays(path, &[])
+    }
+
+    /// Opens an existing heap file and the given overlay files on top of it.
+    /// The overlays must be ordered from the oldest to the newest.
+    pub fn open_with_overlays(
+        path: &Path,
+        overlays: &[PathBuf],
+    ) -> Result<Checkpoint, PersistenceError> {
+        let mapping = Mapping::open(path)?;
+        let overlays = overlays
+            .iter()
+            .map(|overlay| Overlay::open(overlay).map(Arc::new))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Checkpoint {
             mapping: mapping.map(Arc::new),
+            overlays,
         })
     }
 
@@ -167,6 +190,11 @@ impl Checkpoint {
     pub fn serialize(&self) -> CheckpointSerialization {
         CheckpointSerialization {
             mapping: self.mapping.as_ref().map(|mapping| mapping.serialize()),
+            overlays: self
+                .overlays
+                .iter()
+                .map(|overlay| overlay.serialize())
+                .collect(),
         }
     }
 
@@ -179,13 +207,24 @@ impl Checkpoint {
             None => None,
             Some(mapping) => Mapping::deserialize(mapping)?,
         };
+        let overlays = serialized_checkpoint
+            .overlays
+            .into_iter()
+            .map(|overlay| Overlay::deserialize(overlay).map(Arc::new))
+            .collect::<Result<Vec<_>, _>>()?;
         Ok(Checkpoint {
             mapping: mapping.map(Arc::new),
+            overlays,
         })
     }
 
     /// Returns the page with the specified `page_number`.
     pub fn get_page(&self, page_index: PageIndex) -> &PageBytes {
+        for overlay in self.overlays.iter().rev() {
+            if let Some(page) = overlay.get_page(page_index) {
+                return page;
+            }
+        }
         match self.mapping {
             Some(ref mapping) => mapping.get_page(page_index),
             None => &ZEROED_PAGE,
@@ -199,6 +238,23 @@ impl Checkpoint {
         page_range: Range<PageIndex>,
     ) -> MemoryRegion {
         assert!(page_range.contains(&page_index));
+        for overlay in self.overlays.iter().rev() {
+            if let Some(page) = overlay.get_page(page_index) {
+                return MemoryRegion::BackedByPage(page);
+            }
+        }
+        // Shrink the range so that it does not cover any overlay page, because
+        // those pages are not backed by the base file.
+        let mut page_range = page_range;
+        for overlay in self.overlays.iter() {
+            let (below, above) = overlay.bounds(page_index);
+            if let Some(below) = below {
+                page_range.start = page_range.start.max(PageIndex::new(below.get() + 1));
+            }
+            if let Some(above) = above {
+                page_range.end = page_range.end.min(above);
+            }
+        }
         match self.mapping {
             Some(ref mapping) => mapping.get_memory_region(page_index, page_range),
             None => MemoryRegion::Zeros(page_range),
@@ -208,10 +264,31 @@ impl Checkpoint {
     /// Returns the max number of (possibly) non-zero pages in this
     /// checkpoint.
     pub fn num_pages(&self) -> usize {
-        match self.mapping {
+        let pages_in_base = match self.mapping {
             Some(ref mapping) => mapping.num_pages(),
             None => 0,
-        }
+        };
+        self.overlays
+            .iter()
+            .map(|overlay| overlay.num_pages())
+            .fold(pages_in_base, usize::max)
+    }
+
+    /// Returns the number of overlays on top of the base file.
+    pub fn num_overlays(&self) -> usize {
+        self.overlays.len()
+    }
+
+    /// Returns the sorted indices of all pages contained in the overlays.
+    pub fn overlay_page_indices(&self) -> Vec<PageIndex> {
+        let mut indices: Vec<PageIndex> = self
+            .overlays
+            .iter()
+            .flat_map(|overlay| overlay.indices().iter().copied())
+            .collect();
+        indices.sort_unstable();
+        indices.dedup();
+        indices
     }
 }
 
@@ -238,4 +315,5 @@ pub struct MappingSerialization {
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct CheckpointSerialization {
     pub mapping: Option<MappingSerialization>,
+    pub overlays: Vec<OverlaySerialization>,
 }
@@ -0,0 +1,223 @@
+//! Overlay files store the pages of a `PageMap` that changed between two
+//! checkpoints. Writing an overlay instead of applying the page delta to the
+//! base file makes the cost of a checkpoint proportional to the number of
+//! dirty pages rather than to the size of the memory.
+//!
+//! An overlay file containing `n` pages has the following layout:
+//!
+//! ```text
+//! ┌───────────────────────┬───────────────────────┬───────────────────┐
+//! │ page data             │ page indices          │ footer            │
+//! │ n * PAGE_SIZE bytes   │ n * u64 little-endian │ num_pages: u64 LE │
+//! │                       │ strictly ascending    │ version: u32 LE   │
+//! └───────────────────────┴───────────────────────┴───────────────────┘
+//! ```
+//!
+//! The data section comes first so that it starts at a page boundary and can
+//! be memory-mapped directly. The `i`-th page of the data section holds the
+//! contents of the page with the `i`-th index.
+
+use super::checkpoint::{Mapping, MappingSerialization};
+use super::{PageIndex, PersistenceError};
+use ic_sys::{PageBytes, PAGE_SIZE};
+use serde::{Deserialize, Serialize};
+use std::convert::TryInto;
+use std::fs::OpenOptions;
+use std::io::{BufWriter, Write};
+use std::os::unix::fs::FileExt;
+use std::path::Path;
+
+/// The version of the overlay file format written by this replica.
+const OVERLAY_VERSION: u32 = 0;
+
+/// The size of an encoded page index.
+const INDEX_SIZE: usize = std::mem::size_of::<u64>();
+
+/// The size of the footer: the number of pages followed by the version.
+const FOOTER_SIZE: usize = std::mem::size_of::<u64>() + std::mem::size_of::<u32>();
+
+/// An immutable overlay file mapped into memory.
+pub(crate) struct Overlay {
+    /// The data section of the file. It is `None` if the overlay is empty.
+    mapping: Option<Mapping>,
+    /// The indices of the pages in the data section in ascending order.
+    indices: Vec<PageIndex>,
+}
+
+impl Overlay {
+    /// Writes the given pages as a new overlay file to `path`. The pages must
+    /// be sorted by their index in strictly ascending order.
+    pub fn write<'a, I>(path: &Path, pages: I) -> Result<(), PersistenceError>
+    where
+        I: IntoIterator<Item = (PageIndex, &'a PageBytes)>,
+    {
+        let io_error = |context: &str, err: std::io::Error| PersistenceError::FileSystemError {
+            path: path.display().to_string(),
+            context: context.to_string(),
+            internal_error: err.to_string(),
+        };
+
+        let file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)
+            .map_err(|err| io_error("Failed to open file", err))?;
+        let mut writer = BufWriter::new(file);
+
+        let mut indices: Vec<PageIndex> = Vec::new();
+        for (index, contents) in pages {
+            assert!(
+                indices.last().map_or(true, |last| *last < index),
+                "overlay pages must be sorted by index"
+            );
+            writer
+                .write_all(contents)
+                .map_err(|err| io_error(&format!("Failed to write page #{}", index), err))?;
+            indices.push(index);
+        }
+        for index in indices.iter() {
+            writer
+                .write_all(&index.get().to_le_bytes())
+                .map_err(|err| io_error("Failed to write page indices", err))?;
+        }
+        writer
+            .write_all(&(indices.len() as u64).to_le_bytes())
+            .and_then(|()| writer.write_all(&OVERLAY_VERSION.to_le_bytes()))
+            .and_then(|()| writer.flush())
+            .map_err(|err| io_error("Failed to write footer", err))?;
+        Ok(())
+    }
+
+    /// Opens an existing overlay file located at the specified path.
+    pub fn open(path: &Path) -> Result<Overlay, PersistenceError> {
+        let io_error = |context: &str, err: std::io::Error| PersistenceError::FileSystemError {
+            path: path.display().to_string(),
+            context: context.to_string(),
+            internal_error: err.to_string(),
+        };
+        let invalid = |message: String| PersistenceError::InvalidOverlayFile {
+            path: path.display().to_string(),
+            message,
+        };
+
+        let file = OpenOptions::new()
+            .read(true)
+            .open(path)
+            .map_err(|err| io_error("Failed to open file", err))?;
+        let len = file
+            .metadata()
+            .map_err(|err| io_error("Failed to retrieve file metadata", err))?
+            .len() as usize;
+        if len < FOOTER_SIZE {
+            return Err(invalid(format!(
+                "file size {} is smaller than the footer size {}",
+                len, FOOTER_SIZE
+            )));
+        }
+
+        let mut footer = [0u8; FOOTER_SIZE];
+        file.read_exact_at(&mut footer, (len - FOOTER_SIZE) as u64)
+            .map_err(|err| io_error("Failed to read footer", err))?;
+        let num_pages = u64::from_le_bytes(footer[..INDEX_SIZE].try_into().unwrap()) as usize;
+        let version = u32::from_le_bytes(footer[INDEX_SIZE..].try_into().unwrap());
+        if version > OVERLAY_VERSION {
+            return Err(invalid(format!(
+                "unsupported version {}, the latest supported version is {}",
+                version, OVERLAY_VERSION
+            )));
+        }
+        let expected_len = num_pages
+            .checked_mul(PAGE_SIZE + INDEX_SIZE)
+            .and_then(|size| size.checked_add(FOOTER_SIZE));
+        if expected_len != Some(len) {
+            return Err(invalid(format!(
+                "file size {} does not match the number of pages {}",
+                len, num_pages
+            )));
+        }
+
+        let data_len = num_pages * PAGE_SIZE;
+        let mut raw_indices = vec![0u8; num_pages * INDEX_SIZE];
+        file.read_exact_at(&mut raw_indices, data_len as u64)
+            .map_err(|err| io_error("Failed to read page indices", err))?;
+        let indices: Vec<PageIndex> = raw_indices
+            .chunks_exact(INDEX_SIZE)
+            .map(|bytes| PageIndex::new(u64::from_le_bytes(bytes.try_into().unwrap())))
+            .collect();
+        if indices.windows(2).any(|w| w[0] >= w[1]) {
+            return Err(invalid(
+                "page indices are not in strictly ascending order".to_string(),
+            ));
+        }
+
+        let mapping = Mapping::new(file, data_len, Some(path))?;
+        Ok(Overlay { mapping, indices })
+    }
+
+    /// Returns a serialization-friendly representation of `Overlay`.
+    pub fn serialize(&self) -> OverlaySerialization {
+        OverlaySerialization {
+            mapping: self.mapping.as_ref().map(|mapping| mapping.serialize()),
+            indices: self.indices.clone(),
+        }
+    }
+
+    /// Creates `Overlay` from the given serialization-friendly representation.
+    pub fn deserialize(
+        serialized_overlay: OverlaySerialization,
+    ) -> Result<Overlay, PersistenceError> {
+        let mapping = match serialized_overlay.mapping {
+            None => None,
+            Some(mapping) => Mapping::deserialize(mapping)?,
+        };
+        Ok(Overlay {
+            mapping,
+            indices: serialized_overlay.indices,
+        })
+    }
+
+    /// Returns the page with the given index if this overlay contains it.
+    pub fn get_page(&self, page_index: PageIndex) -> Option<&PageBytes> {
+        let position = self.indices.binary_search(&page_index).ok()?;
+        self.mapping
+            .as_ref()
+            .map(|mapping| mapping.get_page(PageIndex::new(position as u64)))
+    }
+
+    /// Returns the closest pages of this overlay below and above the given
+    /// page, excluding the page itself.
+    pub fn bounds(&self, page_index: PageIndex) -> (Option<PageIndex>, Option<PageIndex>) {
+        let below = self.indices.partition_point(|index| *index < page_index);
+        let above = self.indices.partition_point(|index| *index <= page_index);
+        (
+            below.checked_sub(1).map(|position| self.indices[position]),
+            self.indices.get(above).copied(),
+        )
+    }
+
+    /// Returns the indices of the pages stored in this overlay in ascending
+    /// order.
+    pub fn indices(&self) -> &[PageIndex] {
+        &self.indices
+    }
+
+    /// Returns the number of pages up to and including the last page of this
+    /// overlay.
+    pub fn num_pages(&self) -> usize {
+        self.indices
+            .last()
+            .map(|index| index.get() as usize + 1)
+            .unwrap_or(0)
+    }
+}
+
+/// Serialization-friendly representation of `Overlay`.
+///
+/// It contains sufficient information to reconstruct `Overlay`
+/// in another process.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct OverlaySerialization {
+    pub mapping: Option<MappingSerialization>,
+    pub indices: Vec<PageIndex>,
+}
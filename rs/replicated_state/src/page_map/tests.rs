@@ -1,7 +1,8 @@
 use super::{
     checkpoint::{Checkpoint, MappingSerialization},
     page_allocator::PageAllocatorSerialization,
-    Buffer, FileDescriptor, PageAllocator, PageDelta, PageIndex, PageMap, PageMapSerialization,
+    Buffer, FileDescriptor, MemoryRegion, PageAllocator, PageDelta, PageIndex, PageMap,
+    PageMapSerialization, PersistenceError,
 };
 use ic_sys::PAGE_SIZE;
 use ic_types::{Height, MAX_STABLE_MEMORY_IN_BYTES};
@@ -33,6 +34,13 @@ fn duplicate_file_descriptors(
                 },
                 ..mapping
             });
+    for overlay in serialized_page_map.checkpoint.overlays.iter_mut() {
+        if let Some(mapping) = overlay.mapping.as_mut() {
+            mapping.file_descriptor = FileDescriptor {
+                fd: dup(mapping.file_descriptor.fd).unwrap(),
+            };
+        }
+    }
     serialized_page_map.page_allocator = PageAllocatorSerialization {
         id: serialized_page_map.page_allocator.id,
         fd: FileDescriptor {
@@ -190,6 +198,155 @@ fn returns_an_error_if_file_size_is_not_a_multiple_of_page_size() {
     }
 }
 
+#[test]
+fn page_map_with_overlays_is_equivalent_to_the_original() {
+    let tmp = tempfile::Builder::new()
+        .prefix("checkpoints")
+        .tempdir()
+        .unwrap();
+    let heap_file = tmp.path().join("heap");
+    let overlay_1 = tmp.path().join("overlay_1");
+    let overlay_2 = tmp.path().join("overlay_2");
+
+    // Each delta map holds the pages modified since the previous checkpoint,
+    // while `original_map` accumulates all of them.
+    let mut original_map = PageMap::default();
+
+    let base_page = [42u8; PAGE_SIZE];
+    let base_pages = &[
+        (PageIndex::new(0), &base_page),
+        (PageIndex::new(1), &base_page),
+        (PageIndex::new(2), &base_page),
+    ];
+    let mut delta_map = PageMap::default();
+    delta_map.update(base_pages);
+    delta_map.persist_delta(&heap_file).unwrap();
+    original_map.update(base_pages);
+
+    let page_1 = [1u8; PAGE_SIZE];
+    let page_10 = [10u8; PAGE_SIZE];
+    let pages_1 = &[(PageIndex::new(1), &page_1), (PageIndex::new(10), &page_10)];
+    let mut delta_map = PageMap::default();
+    delta_map.update(pages_1);
+    delta_map.persist_overlay(&overlay_1).unwrap();
+    original_map.update(pages_1);
+
+    let page_5 = [5u8; PAGE_SIZE];
+    let page_10_new = [11u8; PAGE_SIZE];
+    let pages_2 = &[
+        (PageIndex::new(5), &page_5),
+        (PageIndex::new(10), &page_10_new),
+    ];
+    let mut delta_map = PageMap::default();
+    delta_map.update(pages_2);
+    delta_map.persist_overlay(&overlay_2).unwrap();
+    original_map.update(pages_2);
+
+    let persisted_map = PageMap::open_with_overlays(
+        &heap_file,
+        &[overlay_1.clone(), overlay_2.clone()],
+        Height::new(0),
+    )
+    .unwrap();
+
+    assert_eq!(persisted_map.num_host_pages(), 11);
+    assert_eq!(persisted_map, original_map);
+    assert_eq!(persisted_map.num_overlays(), 2);
+    assert_eq!(
+        persisted_map.get_overlay_page_indices(),
+        vec![PageIndex::new(1), PageIndex::new(5), PageIndex::new(10)]
+    );
+
+    // The page map must survive being sent to the sandbox process.
+    let serialized_page_map = duplicate_file_descriptors(persisted_map.serialize());
+    let deserialized_page_map = PageMap::deserialize(serialized_page_map).unwrap();
+    assert_equal_page_maps(&persisted_map, &deserialized_page_map);
+
+    // Merging the overlays into a copy of the base file yields the same page map.
+    let merged_file = tmp.path().join("merged");
+    std::fs::copy(&heap_file, &merged_file).unwrap();
+    persisted_map.persist_overlays(&merged_file).unwrap();
+    let merged_map = PageMap::open(&merged_file, Height::new(0)).unwrap();
+    assert_eq!(merged_map, persisted_map);
+}
+
+#[test]
+fn memory_regions_do_not_cover_overlay_pages() {
+    let tmp = tempfile::Builder::new()
+        .prefix("checkpoints")
+        .tempdir()
+        .unwrap();
+    let heap_file = tmp.path().join("heap");
+    let overlay = tmp.path().join("overlay");
+
+    let base_page = [42u8; PAGE_SIZE];
+    let mut base_map = PageMap::default();
+    base_map.update(&[
+        (PageIndex::new(0), &base_page),
+        (PageIndex::new(9), &base_page),
+    ]);
+    base_map.persist_delta(&heap_file).unwrap();
+
+    let mut overlay_map = PageMap::default();
+    let page_4 = [4u8; PAGE_SIZE];
+    let page_20 = [20u8; PAGE_SIZE];
+    overlay_map.update(&[(PageIndex::new(4), &page_4), (PageIndex::new(20), &page_20)]);
+    overlay_map.persist_overlay(&overlay).unwrap();
+
+    let page_map = PageMap::open_with_overlays(&heap_file, &[overlay], Height::new(0)).unwrap();
+
+    match page_map.get_memory_region(PageIndex::new(4)) {
+        MemoryRegion::BackedByPage(contents) => assert_eq!(contents, &page_4),
+        _ => panic!("Expected the overlay page to be backed by a page"),
+    }
+    match page_map.get_memory_region(PageIndex::new(2)) {
+        MemoryRegion::BackedByFile(range, _) => {
+            assert_eq!(range, PageIndex::new(0)..PageIndex::new(4))
+        }
+        _ => panic!("Expected a page of the base file to be backed by the file"),
+    }
+    match page_map.get_memory_region(PageIndex::new(7)) {
+        MemoryRegion::BackedByFile(range, _) => {
+            assert_eq!(range, PageIndex::new(5)..PageIndex::new(10))
+        }
+        _ => panic!("Expected a page of the base file to be backed by the file"),
+    }
+    match page_map.get_memory_region(PageIndex::new(15)) {
+        MemoryRegion::Zeros(range) => {
+            assert_eq!(range, PageIndex::new(10)..PageIndex::new(20))
+        }
+        _ => panic!("Expected a page beyond the base file to be zeros"),
+    }
+    match page_map.get_memory_region(PageIndex::new(30)) {
+        MemoryRegion::Zeros(range) => assert_eq!(range.start, PageIndex::new(21)),
+        _ => panic!("Expected a page beyond the last overlay page to be zeros"),
+    }
+}
+
+#[test]
+fn returns_an_error_if_overlay_file_is_truncated() {
+    let tmp = tempfile::Builder::new()
+        .prefix("checkpoints")
+        .tempdir()
+        .unwrap();
+    let heap_file = tmp.path().join("heap");
+    let overlay = tmp.path().join("overlay");
+
+    let mut page_map = PageMap::default();
+    page_map.persist_delta(&heap_file).unwrap();
+    page_map.update(&[(PageIndex::new(3), &[3u8; PAGE_SIZE])]);
+    page_map.persist_overlay(&overlay).unwrap();
+
+    let file = OpenOptions::new().write(true).open(&overlay).unwrap();
+    file.set_len(PAGE_SIZE as u64).unwrap();
+
+    match PageMap::open_with_overlays(&heap_file, &[overlay], Height::new(0)) {
+        Err(PersistenceError::InvalidOverlayFile { .. }) => (),
+        Err(err) => panic!("Expected an invalid overlay file error, got {:?}", err),
+        Ok(_) => panic!("Expected an invalid overlay file error, got Ok(_)"),
+    }
+}
+
 #[test]
 fn can_use_buffer_to_modify_page_map() {
     let page_1 = [1u8; PAGE_SIZE];
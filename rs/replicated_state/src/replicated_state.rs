@@ -4,9 +4,10 @@ use super::{
 };
 use crate::{
     bitcoin_state::{BitcoinState, BitcoinStateError},
+    canister_snapshots::CanisterSnapshots,
     canister_state::queues::CanisterQueuesLoopDetector,
     canister_state::system_state::{push_input, CanisterOutputQueuesIterator},
-    metadata_state::StreamMap,
+    metadata_state::{query_stats::RawQueryStats, StreamMap},
     CanisterQueues,
 };
 use ic_base_types::PrincipalId;
@@ -15,20 +16,21 @@ use ic_error_types::{ErrorCode, UserError};
 use ic_interfaces::{
     execution_environment::CanisterOutOfCyclesError, messages::CanisterInputMessage,
 };
-use ic_registry_routing_table::RoutingTable;
+use ic_registry_routing_table::{CanisterIdRanges, RoutingTable};
 use ic_registry_subnet_features::BitcoinFeatureStatus;
 use ic_registry_subnet_type::SubnetType;
 use ic_types::messages::Ingress;
 use ic_types::{
+    batch::QueryStatsPayload,
     ingress::IngressStatus,
     messages::{CallbackId, MessageId, RequestOrResponse, Response},
     xnet::QueueId,
-    CanisterId, MemoryAllocation, NumBytes, SubnetId, Time,
+    CanisterId, CoarseTime, MemoryAllocation, NumBytes, SubnetId, Time,
 };
 use rand::{Rng, SeedableRng};
 use rand_chacha::ChaChaRng;
 use serde::{Deserialize, Serialize};
-use std::collections::{BTreeMap, VecDeque};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
 use std::sync::Arc;
 
 /// Maximum message length of a synthetic reject response produced by message
@@ -331,6 +333,9 @@ pub struct ReplicatedState {
     pub consensus_queue: Vec<Response>,
 
     bitcoin: BitcoinState,
+
+    /// Snapshots of canisters taken through the management canister.
+    pub canister_snapshots: CanisterSnapshots,
 }
 
 impl ReplicatedState {
@@ -342,6 +347,7 @@ impl ReplicatedState {
             subnet_queues: CanisterQueues::default(),
             consensus_queue: Vec::new(),
             bitcoin: BitcoinState::default(),
+            canister_snapshots: CanisterSnapshots::default(),
         }
     }
 
@@ -351,6 +357,7 @@ impl ReplicatedState {
         subnet_queues: CanisterQueues,
         consensus_queue: Vec<Response>,
         bitcoin: BitcoinState,
+        canister_snapshots: CanisterSnapshots,
     ) -> Self {
         let mut res = Self {
             canister_states,
@@ -358,6 +365,7 @@ impl ReplicatedState {
             subnet_queues,
             consensus_queue,
             bitcoin,
+            canister_snapshots,
         };
         res.update_stream_responses_size_bytes();
         res
@@ -490,6 +498,48 @@ impl ReplicatedState {
         self.metadata.ingress_history.prune(self.time());
     }
 
+    /// Records the query statistics contributed by a replica of this subnet.
+    ///
+    /// Contributions for a past epoch and repeated contributions of the same
+    /// replica for the current epoch are ignored. The first contribution for
+    /// a later epoch causes the contributions for the current epoch to be
+    /// aggregated and added to the totals of the respective canisters.
+    pub fn deliver_query_stats(&mut self, payload: QueryStatsPayload) {
+        if payload.epoch < self.metadata.raw_query_stats.epoch {
+            return;
+        }
+        if payload.epoch > self.metadata.raw_query_stats.epoch {
+            let previous = std::mem::replace(
+                &mut self.metadata.raw_query_stats,
+                RawQueryStats {
+                    epoch: payload.epoch,
+                    stats: BTreeMap::new(),
+                },
+            );
+            for (canister_id, totals) in previous.aggregate() {
+                // Canisters deleted in the meantime are skipped.
+                if let Some(canister) = self.canister_states.get_mut(&canister_id) {
+                    canister
+                        .scheduler_state
+                        .total_query_stats
+                        .saturating_accumulate(&totals);
+                }
+            }
+        }
+        let raw_query_stats = &mut self.metadata.raw_query_stats;
+        if raw_query_stats.has_contribution(&payload.proposer) {
+            return;
+        }
+        raw_query_stats.stats.insert(
+            payload.proposer,
+            payload
+                .stats
+                .into_iter()
+                .map(|entry| (entry.canister_id, entry.stats))
+                .collect(),
+        );
+    }
+
     /// Returns all subnets for which a stream is available.
     pub fn subnets_with_available_streams(&self) -> Vec<SubnetId> {
         self.metadata.streams.keys().cloned().collect()
@@ -585,6 +635,11 @@ impl ReplicatedState {
     /// On failure (queue full, canister not found, out of memory), returns the
     /// corresponding error and the original message.
     ///
+    /// If a guaranteed response `Request` does not fit into the subnet's
+    /// available memory, even after shedding the receiver's own best-effort
+    /// messages, the best-effort messages of other canisters are shed (largest
+    /// first) until it does; and the push is retried.
+    ///
     /// Updates `subnet_available_memory` to reflect any change in memory usage.
     pub fn push_input(
         &mut self,
@@ -600,14 +655,50 @@ impl ReplicatedState {
         } else {
             InputQueueType::RemoteSubnet
         };
-        match self.canister_state_mut(&msg.receiver()) {
-            Some(receiver_canister) => receiver_canister.push_input(
-                msg,
-                max_canister_memory_size,
-                subnet_available_memory,
-                own_subnet_type,
-                input_queue_type,
-            ),
+        let receiver = msg.receiver();
+        match self.canister_state_mut(&receiver) {
+            Some(receiver_canister) => {
+                let res = receiver_canister.push_input(
+                    msg,
+                    max_canister_memory_size,
+                    subnet_available_memory,
+                    own_subnet_type,
+                    input_queue_type,
+                );
+                match res {
+                    // Subnet memory is the binding constraint: make room for a
+                    // guaranteed response request by shedding the best-effort
+                    // messages of other canisters.
+                    Err((
+                        StateError::OutOfMemory {
+                            requested,
+                            available,
+                        },
+                        msg,
+                    )) if !msg.is_best_effort() && available == *subnet_available_memory => {
+                        if !self.shed_best_effort_messages(
+                            requested.get() as i64,
+                            subnet_available_memory,
+                        ) {
+                            return Err((
+                                StateError::OutOfMemory {
+                                    requested,
+                                    available,
+                                },
+                                msg,
+                            ));
+                        }
+                        self.canister_state_mut(&receiver).unwrap().push_input(
+                            msg,
+                            max_canister_memory_size,
+                            subnet_available_memory,
+                            own_subnet_type,
+                            input_queue_type,
+                        )
+                    }
+                    res => res,
+                }
+            }
             None => {
                 let subnet_id = self.metadata.own_subnet_id.get_ref();
                 if msg.receiver().get_ref() == subnet_id {
@@ -627,6 +718,41 @@ impl ReplicatedState {
         }
     }
 
+    /// Sheds the best-effort messages of canisters in decreasing order of
+    /// best-effort message byte size, until `subnet_available_memory` is at
+    /// least `required_memory` (crediting it with the freed memory). Returns
+    /// `false` if not enough memory could be freed.
+    ///
+    /// Time complexity: O(num_canisters * log(num_canisters) + num_messages).
+    fn shed_best_effort_messages(
+        &mut self,
+        required_memory: i64,
+        subnet_available_memory: &mut i64,
+    ) -> bool {
+        let mut canisters_by_best_effort_bytes: Vec<_> = self
+            .canister_states
+            .iter()
+            .map(|(canister_id, canister)| {
+                (
+                    canister.system_state.queues().best_effort_message_bytes(),
+                    *canister_id,
+                )
+            })
+            .filter(|(best_effort_message_bytes, _)| *best_effort_message_bytes > 0)
+            .collect();
+        // Largest first.
+        canisters_by_best_effort_bytes.sort_unstable_by_key(|&entry| std::cmp::Reverse(entry));
+
+        for (_, canister_id) in canisters_by_best_effort_bytes {
+            if *subnet_available_memory >= required_memory {
+                break;
+            }
+            let canister = self.canister_states.get_mut(&canister_id).unwrap();
+            *subnet_available_memory += canister.system_state.shed_best_effort_messages() as i64;
+        }
+        *subnet_available_memory >= required_memory
+    }
+
     /// Pushes an ingress message into the induction pool (canister or subnet
     /// ingress queue).
     pub fn push_ingress(&mut self, msg: Ingress) -> Result<(), StateError> {
@@ -726,6 +852,86 @@ impl ReplicatedState {
         }
     }
 
+    /// Splits the state of a subnet whose canisters within `migrated_ranges`
+    /// are moved to the new subnet `new_subnet_id`. Returns the state of the
+    /// retaining subnet and the state of the receiving subnet.
+    ///
+    /// Canister snapshots follow their canisters; the subnet queues, the
+    /// consensus queue and the bitcoin state stay on the retaining subnet. See
+    /// `SystemMetadata::split()` for how the subnet metadata is split. The
+    /// state must not have any unflushed snapshot operations, i.e. it must have
+    /// been loaded from a checkpoint.
+    pub fn split(
+        self,
+        migrated_ranges: &CanisterIdRanges,
+        new_subnet_id: SubnetId,
+    ) -> Result<(Self, Self), String> {
+        if new_subnet_id == self.metadata.own_subnet_id {
+            return Err(format!(
+                "The new subnet {} must differ from the subnet being split",
+                new_subnet_id
+            ));
+        }
+        if self.canister_snapshots.has_unflushed_changes() {
+            return Err("Cannot split a state with unflushed snapshot operations".to_string());
+        }
+        let is_migrated = |canister_id: &CanisterId| {
+            migrated_ranges
+                .iter()
+                .any(|range| range.contains(canister_id))
+        };
+
+        let ReplicatedState {
+            canister_states,
+            metadata,
+            subnet_queues,
+            consensus_queue,
+            bitcoin,
+            canister_snapshots,
+        } = self;
+
+        let (received_canisters, retained_canisters): (BTreeMap<_, _>, BTreeMap<_, _>) =
+            canister_states
+                .into_iter()
+                .partition(|(canister_id, _)| is_migrated(canister_id));
+
+        // Removing the snapshots records their deletion, so that the state
+        // manager also removes them from the checkpoint files of either subnet.
+        let snapshot_owners: BTreeSet<CanisterId> = canister_snapshots
+            .iter()
+            .map(|(_, snapshot)| snapshot.canister_id())
+            .collect();
+        let mut retained_snapshots = canister_snapshots.clone();
+        let mut received_snapshots = canister_snapshots;
+        for canister_id in snapshot_owners {
+            if is_migrated(&canister_id) {
+                retained_snapshots.remove_snapshots(canister_id);
+            } else {
+                received_snapshots.remove_snapshots(canister_id);
+            }
+        }
+
+        let (retained_metadata, received_metadata) = metadata.split(new_subnet_id, is_migrated);
+
+        let retained = ReplicatedState::new_from_checkpoint(
+            retained_canisters,
+            retained_metadata,
+            subnet_queues,
+            consensus_queue,
+            bitcoin,
+            retained_snapshots,
+        );
+        let received = ReplicatedState::new_from_checkpoint(
+            received_canisters,
+            received_metadata,
+            CanisterQueues::default(),
+            Vec::new(),
+            BitcoinState::default(),
+            received_snapshots,
+        );
+        Ok((retained, received))
+    }
+
     /// Returns the number of canisters in this `ReplicatedState`.
     pub fn num_canisters(&self) -> usize {
         self.canister_states.len()
@@ -821,6 +1027,42 @@ impl ReplicatedState {
 
         timed_out_requests_count
     }
+
+    /// Expires best-effort callbacks whose deadline is at or before `current_time`
+    /// across all canisters, enqueuing a `SYS_UNKNOWN` reject response for each.
+    /// Returns the number of callbacks that were expired.
+    ///
+    /// See `SystemState::time_out_callbacks` for further details.
+    #[allow(clippy::needless_collect)]
+    pub fn time_out_callbacks(&mut self, current_time: Time) -> u64 {
+        let current_time = CoarseTime::floor(current_time);
+
+        // As with `time_out_requests()`, only remove and replace the (usually few)
+        // canisters that actually have expired callbacks.
+        let canister_ids_with_expired_callbacks = self
+            .canister_states
+            .iter()
+            .filter(|(_, canister_state)| {
+                canister_state
+                    .system_state
+                    .has_expired_callbacks(current_time)
+            })
+            .map(|(canister_id, _)| *canister_id)
+            .collect::<Vec<_>>();
+
+        let mut expired_callbacks_count = 0;
+        for canister_id in canister_ids_with_expired_callbacks {
+            let mut canister = self.canister_states.remove(&canister_id).unwrap();
+            expired_callbacks_count += canister.system_state.time_out_callbacks(
+                current_time,
+                &canister_id,
+                &self.canister_states,
+            );
+            self.canister_states.insert(canister_id, canister);
+        }
+
+        expired_callbacks_count
+    }
 }
 
 /// A trait exposing `ReplicatedState` functionality for the exclusive use of
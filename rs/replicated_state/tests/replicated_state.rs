@@ -6,6 +6,7 @@ use ic_btc_types_internal::{
     GetSuccessorsRequest, GetSuccessorsResponse,
 };
 use ic_ic00_types::{BitcoinGetSuccessorsResponse, Payload as _};
+use ic_registry_routing_table::{CanisterIdRange, CanisterIdRanges};
 use ic_registry_subnet_features::SubnetFeatures;
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::replicated_state::testing::ReplicatedStateTesting;
@@ -20,14 +21,15 @@ use ic_test_utilities::mock_time;
 use ic_test_utilities::state::{
     arb_replicated_state_with_queues, get_running_canister, register_callback,
 };
-use ic_test_utilities::types::ids::canister_test_id;
+use ic_test_utilities::types::ids::{canister_test_id, node_test_id};
 use ic_test_utilities::types::{
     ids::{subnet_test_id, user_test_id},
     messages::{RequestBuilder, ResponseBuilder},
 };
 use ic_types::{
+    batch::{CanisterQueryStats, QueryStats, QueryStatsEpoch, QueryStatsPayload},
     messages::{CallbackId, Payload, RequestOrResponse, MAX_RESPONSE_COUNT_BYTES},
-    CountBytes, Cycles, Time,
+    CoarseTime, CountBytes, Cycles, Time,
 };
 use proptest::prelude::*;
 use std::collections::VecDeque;
@@ -411,6 +413,82 @@ fn push_subnet_queues_input_respects_subnet_available_memory() {
     })
 }
 
+#[test]
+fn push_input_sheds_best_effort_messages_when_out_of_subnet_memory() {
+    replicated_state_test(|mut state| {
+        // A second local canister, with a best-effort request in its output queue.
+        let local_canister_id = canister_test_id(7);
+        state.put_canister_state(CanisterState::new(
+            SystemState::new_running(
+                local_canister_id,
+                user_test_id(24).get(),
+                INITIAL_CYCLES,
+                NumSeconds::from(100_000),
+            ),
+            None,
+            SchedulerState::default(),
+        ));
+        let best_effort_request = RequestBuilder::default()
+            .sender(local_canister_id)
+            .receiver(OTHER_CANISTER_ID)
+            .deadline(CoarseTime::from_secs_since_unix_epoch(10))
+            .build();
+        let best_effort_bytes = best_effort_request.count_bytes();
+        state
+            .canister_state_mut(&local_canister_id)
+            .unwrap()
+            .push_output_request(best_effort_request.into(), mock_time())
+            .unwrap();
+
+        let initial_available_memory = MAX_RESPONSE_COUNT_BYTES as i64 - 1;
+        let mut subnet_available_memory = initial_available_memory;
+
+        // A best-effort request does not cause any shedding.
+        let request: RequestOrResponse = RequestBuilder::default()
+            .sender(OTHER_CANISTER_ID)
+            .receiver(CANISTER_ID)
+            .deadline(CoarseTime::from_secs_since_unix_epoch(10))
+            .method_payload(vec![0; MAX_RESPONSE_COUNT_BYTES])
+            .build()
+            .into();
+        assert!(matches!(
+            state.push_input(
+                request,
+                MAX_CANISTER_MEMORY_SIZE,
+                &mut subnet_available_memory
+            ),
+            Err((StateError::OutOfMemory { .. }, _))
+        ));
+        assert!(state
+            .canister_state(&local_canister_id)
+            .unwrap()
+            .has_output());
+
+        // A guaranteed response request sheds the best-effort request and is
+        // inducted.
+        state
+            .push_input(
+                RequestBuilder::default()
+                    .sender(OTHER_CANISTER_ID)
+                    .receiver(CANISTER_ID)
+                    .build()
+                    .into(),
+                MAX_CANISTER_MEMORY_SIZE,
+                &mut subnet_available_memory,
+            )
+            .unwrap();
+        assert!(!state
+            .canister_state(&local_canister_id)
+            .unwrap()
+            .has_output());
+        assert_eq!(
+            initial_available_memory + best_effort_bytes as i64 - MAX_RESPONSE_COUNT_BYTES as i64,
+            subnet_available_memory
+        );
+        assert_message_memory_taken(MAX_RESPONSE_COUNT_BYTES, &state);
+    })
+}
+
 #[test]
 fn push_input_queues_respects_local_remote_subnet() {
     // Local and remote IDs.
@@ -891,6 +969,99 @@ fn time_out_requests_updates_subnet_input_schedules_correctly() {
     assert_eq!(remote_schedule, &VecDeque::from(vec![remote_canister_id]));
 }
 
+fn query_stats_payload(epoch: u64, node: u64, num_calls: u32) -> QueryStatsPayload {
+    QueryStatsPayload {
+        epoch: QueryStatsEpoch::from(epoch),
+        proposer: node_test_id(node),
+        stats: vec![CanisterQueryStats {
+            canister_id: CANISTER_ID,
+            stats: QueryStats {
+                num_calls,
+                num_instructions: 1_000 * num_calls as u64,
+                ingress_payload_size: 10,
+                egress_payload_size: 20,
+            },
+        }],
+    }
+}
+
+#[test]
+fn query_stats_are_aggregated_once_epoch_ends() {
+    replicated_state_test(|mut state| {
+        state.deliver_query_stats(query_stats_payload(1, 1, 3));
+        state.deliver_query_stats(query_stats_payload(1, 2, 5));
+        // Repeated contributions of the same node are ignored.
+        state.deliver_query_stats(query_stats_payload(1, 2, 1_000));
+        state.deliver_query_stats(query_stats_payload(1, 3, 100));
+
+        // Nothing is aggregated while the epoch is still in progress.
+        let totals = &state
+            .canister_state(&CANISTER_ID)
+            .unwrap()
+            .scheduler_state
+            .total_query_stats;
+        assert_eq!(totals.num_calls, 0);
+
+        // The first contribution for the next epoch finalizes the current one.
+        state.deliver_query_stats(query_stats_payload(2, 1, 7));
+        let totals = &state
+            .canister_state(&CANISTER_ID)
+            .unwrap()
+            .scheduler_state
+            .total_query_stats;
+        // The median of 3, 5 and 100, scaled by the number of contributors.
+        assert_eq!(totals.num_calls, 15);
+        assert_eq!(totals.num_instructions, 15_000);
+        assert_eq!(totals.ingress_payload_size, 30);
+        assert_eq!(totals.egress_payload_size, 60);
+
+        // Contributions for past epochs are ignored.
+        state.deliver_query_stats(query_stats_payload(1, 4, 9));
+        assert_eq!(
+            state.metadata.raw_query_stats.epoch,
+            QueryStatsEpoch::from(2)
+        );
+        assert_eq!(state.metadata.raw_query_stats.stats.len(), 1);
+    })
+}
+
+#[test]
+fn split_moves_migrated_canisters_to_the_new_subnet() {
+    replicated_state_test(|mut state| {
+        state.put_canister_state(CanisterState::new(
+            SystemState::new_running(
+                OTHER_CANISTER_ID,
+                user_test_id(24).get(),
+                INITIAL_CYCLES,
+                NumSeconds::from(100_000),
+            ),
+            None,
+            SchedulerState::default(),
+        ));
+        let migrated_ranges = CanisterIdRanges::try_from(vec![CanisterIdRange {
+            start: CANISTER_ID,
+            end: CANISTER_ID,
+        }])
+        .unwrap();
+        let new_subnet_id = subnet_test_id(2);
+
+        assert!(state.clone().split(&migrated_ranges, SUBNET_ID).is_err());
+
+        let (retained, received) = state.split(&migrated_ranges, new_subnet_id).unwrap();
+
+        assert_eq!(retained.metadata.own_subnet_id, SUBNET_ID);
+        assert_eq!(
+            retained.canister_states.keys().collect::<Vec<_>>(),
+            vec![&OTHER_CANISTER_ID]
+        );
+        assert_eq!(received.metadata.own_subnet_id, new_subnet_id);
+        assert_eq!(
+            received.canister_states.keys().collect::<Vec<_>>(),
+            vec![&CANISTER_ID]
+        );
+    })
+}
+
 proptest! {
     #[test]
     fn peek_and_next_consistent(
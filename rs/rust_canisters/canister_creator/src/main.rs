@@ -25,6 +25,7 @@ async fn create_canisters_in_batch(
                     controller: Some(dfn_core::api::id().get()),
                     ..ic_ic00_types::CanisterSettingsArgs::default()
                 }),
+                sender_canister_version: None,
             }
             .encode(),
             dfn_core::api::Funds::new(INITIAL_CYCLES_BALANCE),
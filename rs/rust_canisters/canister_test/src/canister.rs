@@ -673,7 +673,11 @@ impl<'a> Canister<'a> {
                         compute_allocation: None,
                         memory_allocation: None,
                         freezing_threshold: None,
+                        log_visibility: None,
+                        wasm_memory_limit: None,
+                        reserved_cycles_limit: None,
                     },
+                    sender_canister_version: None,
                 },),
             )
             .await
@@ -85,6 +85,7 @@ pub async fn install_code(
         compute_allocation: None,
         memory_allocation: Some(candid::Nat::from(MEMORY_ALLOCATION_BYTES)),
         query_allocation: None,
+        sender_canister_version: None,
     };
 
     env.call_canister(
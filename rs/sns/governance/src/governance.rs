@@ -4431,6 +4431,7 @@ mod tests {
     use ic_canister_client_sender::Sender;
     use ic_ic00_types::{
         CanisterIdRecord, CanisterInstallMode, CanisterStatusResultV2, CanisterStatusType,
+        QueryStats,
     };
     use ic_nervous_system_common::ledger::compute_neuron_staking_subaccount_bytes;
     use ic_nervous_system_common::{
@@ -5082,7 +5083,11 @@ mod tests {
             0,
             Some(0),
             0,
+            None,
             0,
+            None,
+            0,
+            QueryStats::default(),
         )
     }
 
@@ -5763,6 +5768,7 @@ mod tests {
                         compute_allocation: None,
                         memory_allocation: Some(candid::Nat::from(1_u64 << 30)), // local const in install_code()
                         query_allocation: None,
+                        sender_canister_version: None,
                     })
                     .unwrap(),
                     Some(Ok(vec![])),
@@ -1018,8 +1018,8 @@ mod tests {
     use ic_base_types::NumBytes;
     use ic_base_types::PrincipalId;
     use ic_crypto_sha::Sha256;
-    use ic_ic00_types::CanisterStatusResultV2;
     use ic_ic00_types::CanisterStatusType;
+    use ic_ic00_types::{CanisterStatusResultV2, QueryStats};
     use ic_nns_constants::SNS_WASM_CANISTER_ID;
     use ic_test_utilities::types::ids::canister_test_id;
     use lazy_static::lazy_static;
@@ -1571,7 +1571,11 @@ mod tests {
             0,
             Some(0),
             0,
+            None,
             0,
+            None,
+            0,
+            QueryStats::default(),
         )
     }
 
@@ -95,6 +95,7 @@ fn set_controllers(
     let request = Encode!(&UpdateSettingsArgs {
         canister_id: target.into(),
         settings: CanisterSettingsArgs::new(None, Some(controllers), None, None, None,),
+        sender_canister_version: None,
     })
     .unwrap();
 
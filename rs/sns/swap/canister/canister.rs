@@ -501,7 +501,7 @@ fn main() {}
 #[cfg(test)]
 mod tests {
     use super::*;
-    use ic_ic00_types::CanisterStatusType;
+    use ic_ic00_types::{CanisterStatusType, QueryStats};
 
     /// A test that fails if the API was updated but the candid definition was not.
     #[test]
@@ -537,7 +537,11 @@ mod tests {
             0,
             None,
             0,
+            None,
+            0,
+            None,
             0,
+            QueryStats::default(),
         )
     }
 
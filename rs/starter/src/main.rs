@@ -325,6 +325,9 @@ struct CliArgs {
             "bitcoin_regtest",
             "bitcoin_regtest_syncing",
             "bitcoin_regtest_paused",
+            "quic_transport",
+            "compressed_state_sync",
+            "overlay_checkpoints",
         ],
         multiple_values(true))]
     subnet_features: Vec<String>,
@@ -561,6 +564,11 @@ impl CliArgs {
 fn to_subnet_features(features: &[String]) -> SubnetFeatures {
     let canister_sandboxing = features.iter().any(|s| s.as_str() == "canister_sandboxing");
     let http_requests = features.iter().any(|s| s.as_str() == "http_requests");
+    let quic_transport = features.iter().any(|s| s.as_str() == "quic_transport");
+    let compressed_state_sync = features
+        .iter()
+        .any(|s| s.as_str() == "compressed_state_sync");
+    let overlay_checkpoints = features.iter().any(|s| s.as_str() == "overlay_checkpoints");
     let bitcoin = if features.iter().any(|s| s.as_str() == "bitcoin_testnet") {
         Some(BitcoinFeatureInfo {
             network: BitcoinNetwork::Testnet.into(),
@@ -654,6 +662,9 @@ fn to_subnet_features(features: &[String]) -> SubnetFeatures {
         bitcoin_testnet_feature: None,
         bitcoin,
         sev_status,
+        quic_transport,
+        compressed_state_sync,
+        overlay_checkpoints,
     }
 }
 
@@ -0,0 +1,144 @@
+//! The report of a garbage collection of the state layout, i.e. of the removal
+//! of files and directories in the state root that are no longer part of any
+//! state. The report of the latest garbage collection is stored in the state
+//! root, so that operators can verify what was removed, and why.
+
+use crate::error::LayoutError;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Name of the file in the state root holding the latest `GcReport`.
+pub const GC_REPORT_FILE: &str = "gc_report.json";
+
+/// Why a file or directory is garbage.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GcReason {
+    /// A leftover of a checkpoint operation in `fs_tmp`.
+    StaleFsTmp,
+    /// A state sync scratchpad or cache in `tmp`, i.e. an unverified
+    /// checkpoint, for a height not above the latest checkpoint.
+    AbandonedStateSync,
+    /// A diverged checkpoint beyond the configured retention.
+    DivergedCheckpoint,
+    /// A backup of a checkpoint beyond the configured retention.
+    Backup,
+    /// A diverged state marker beyond the configured retention.
+    DivergedStateMarker,
+}
+
+impl GcReason {
+    pub const ALL: [GcReason; 5] = [
+        GcReason::StaleFsTmp,
+        GcReason::AbandonedStateSync,
+        GcReason::DivergedCheckpoint,
+        GcReason::Backup,
+        GcReason::DivergedStateMarker,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GcReason::StaleFsTmp => "stale_fs_tmp",
+            GcReason::AbandonedStateSync => "abandoned_state_sync",
+            GcReason::DivergedCheckpoint => "diverged_checkpoint",
+            GcReason::Backup => "backup",
+            GcReason::DivergedStateMarker => "diverged_state_marker",
+        }
+    }
+}
+
+/// What the garbage collection did with a file or directory.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GcAction {
+    Removed,
+    /// Kept because of a `keep_for_forensics` marker, see
+    /// `StateLayout::is_kept_for_forensics()`.
+    KeptForForensics,
+    /// The removal failed, the entry is retried by the next collection.
+    Failed,
+}
+
+impl GcAction {
+    pub const ALL: [GcAction; 3] = [
+        GcAction::Removed,
+        GcAction::KeptForForensics,
+        GcAction::Failed,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GcAction::Removed => "removed",
+            GcAction::KeptForForensics => "kept_for_forensics",
+            GcAction::Failed => "failed",
+        }
+    }
+}
+
+/// A file or directory collected as garbage.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GcEntry {
+    pub path: PathBuf,
+    /// The total size of the files below `path`.
+    pub size_bytes: u64,
+    /// The time since `path` was created, in seconds.
+    pub age_secs: u64,
+    pub reason: GcReason,
+    pub action: GcAction,
+}
+
+/// The report of one garbage collection.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GcReport {
+    /// The time of the collection, in seconds since the UNIX epoch.
+    pub timestamp_secs: u64,
+    pub entries: Vec<GcEntry>,
+}
+
+impl GcReport {
+    /// Returns the total size of the entries with the given reason and action.
+    pub fn size_bytes(&self, reason: GcReason, action: GcAction) -> u64 {
+        self.entries
+            .iter()
+            .filter(|e| e.reason == reason && e.action == action)
+            .map(|e| e.size_bytes)
+            .sum()
+    }
+
+    /// Returns the total size of the entries with the given action.
+    pub fn total_size_bytes(&self, action: GcAction) -> u64 {
+        GcReason::ALL
+            .iter()
+            .map(|reason| self.size_bytes(*reason, action))
+            .sum()
+    }
+
+    /// Reads a report stored by `store()`.
+    pub fn load(path: &Path) -> Result<Self, LayoutError> {
+        let bytes = std::fs::read(path).map_err(|io_err| LayoutError::IoError {
+            path: path.to_path_buf(),
+            message: "Failed to read GC report".to_string(),
+            io_err,
+        })?;
+        serde_json::from_slice(&bytes).map_err(|err| LayoutError::CorruptedLayout {
+            path: path.to_path_buf(),
+            message: format!("Failed to deserialize GC report: {}", err),
+        })
+    }
+
+    /// Atomically replaces the report stored at `path` by this one.
+    pub fn store(&self, path: &Path) -> Result<(), LayoutError> {
+        let tmp_path = path.with_extension("tmp");
+        let bytes = serde_json::to_vec_pretty(self).expect("Failed to serialize GC report");
+        std::fs::write(&tmp_path, bytes).map_err(|io_err| LayoutError::IoError {
+            path: tmp_path.clone(),
+            message: "Failed to write GC report".to_string(),
+            io_err,
+        })?;
+        std::fs::rename(&tmp_path, path).map_err(|io_err| LayoutError::IoError {
+            path: path.to_path_buf(),
+            message: "Failed to replace GC report".to_string(),
+            io_err,
+        })
+    }
+}
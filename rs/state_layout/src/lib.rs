@@ -1,4 +1,5 @@
 pub mod error;
+pub mod gc_report;
 pub mod state_layout;
 pub mod utils;
 
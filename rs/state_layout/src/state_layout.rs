@@ -1,8 +1,10 @@
 use crate::error::LayoutError;
+use crate::gc_report::GC_REPORT_FILE;
 use crate::utils::do_copy;
 
 use bitcoin::{hashes::Hash, Network, OutPoint, Script, TxOut, Txid};
 use ic_base_types::{NumBytes, NumSeconds};
+use ic_ic00_types::LogVisibility;
 use ic_logger::{error, ReplicaLogger};
 use ic_protobuf::{
     bitcoin::v1 as pb_bitcoin,
@@ -11,15 +13,17 @@ use ic_protobuf::{
         canister_state_bits::v1 as pb_canister_state_bits, queues::v1 as pb_queues,
         system_metadata::v1 as pb_metadata,
     },
+    types::v1 as pb_types,
 };
 use ic_replicated_state::{
     bitcoin_state, canister_state::execution_state::WasmMetadata, CallContextManager,
-    CanisterStatus, ExecutionTask, ExportedFunctions, Global, NumWasmPages,
+    CanisterHistory, CanisterStatus, ExecutionTask, ExportedFunctions, Global, NumWasmPages,
+    SnapshotId, TotalQueryStats, WasmChunkStoreMetadata,
 };
 use ic_sys::mmap::ScopedMmap;
 use ic_types::{
-    nominal_cycles::NominalCycles, AccumulatedPriority, CanisterId, ComputeAllocation, Cycles,
-    ExecutionRound, Height, MemoryAllocation, NumInstructions, PrincipalId,
+    nominal_cycles::NominalCycles, AccumulatedPriority, CanisterId, CanisterLog, ComputeAllocation,
+    Cycles, ExecutionRound, Height, MemoryAllocation, NumInstructions, PrincipalId, Time,
 };
 use ic_utils::fs::sync_path;
 use ic_utils::thread::parallel_map;
@@ -35,6 +39,10 @@ use std::sync::{
     Arc,
 };
 
+/// Name of the marker file that keeps the directory containing it from being
+/// garbage collected, or all garbage if it is placed in the state root.
+pub const KEEP_FOR_FORENSICS_MARKER: &str = "keep_for_forensics";
+
 /// `ReadOnly` is the access policy used for reading checkpoints. We
 /// don't want to ever modify persisted states.
 pub enum ReadOnly {}
@@ -131,6 +139,28 @@ pub struct CanisterStateBits {
     pub time_of_last_allocation_charge_nanos: u64,
     pub global_timer_nanos: Option<u64>,
     pub canister_version: u64,
+    pub next_snapshot_id: u64,
+    pub snapshots_memory_usage: NumBytes,
+    pub canister_history: CanisterHistory,
+    pub log_visibility: LogVisibility,
+    pub canister_log: CanisterLog,
+    pub wasm_memory_limit: Option<NumBytes>,
+    pub reserved_balance: Cycles,
+    pub reserved_balance_limit: Option<Cycles>,
+    pub wasm_chunk_store_metadata: WasmChunkStoreMetadata,
+    pub total_query_stats: TotalQueryStats,
+}
+
+/// This struct contains bits of a `CanisterSnapshot` that are not already
+/// covered by the memory and Wasm files of the snapshot.
+#[derive(Debug)]
+pub struct CanisterSnapshotBits {
+    pub canister_id: CanisterId,
+    pub taken_at_timestamp: Time,
+    pub canister_version: u64,
+    pub certified_data: Vec<u8>,
+    pub execution_state_bits: ExecutionStateBits,
+    pub stable_memory_size: NumWasmPages,
 }
 
 /// This struct contains bits of the `BitcoinState` that are not already
@@ -178,13 +208,21 @@ impl Default for BitcoinStateBits {
 /// |   |       └── utxos_small.bin
 /// |   |       └── utxos_medium.bin
 /// |   |       └── address_outpoints.bin
-/// │   └── canister_states
+/// │   ├── canister_states
+/// │   │   └── <hex(canister_id)>
+/// │   │       ├── queues.pbuf
+/// │   │       ├── vmemory_0.bin
+/// │   │       ├── vmemory_0_<hex(round)>.overlay
+/// │   │       ├── canister.pbuf
+/// │   │       ├── stable_memory.(pbuf|bin)
+/// │   │       └── software.wasm
+/// │   └── snapshots
 /// │       └── <hex(canister_id)>
-/// │           ├── queues.pbuf
-/// │           ├── vmemory_0.bin
-/// │           ├── canister.pbuf
-/// │           ├── stable_memory.(pbuf|bin)
-/// │           └── software.wasm
+/// │           └── <hex(snapshot_id)>
+/// │               ├── snapshot.pbuf
+/// │               ├── vmemory_0.bin
+/// │               ├── stable_memory.bin
+/// │               └── software.wasm
 /// │
 /// ├── [checkpoints, backups, diverged_checkpoints]
 /// │   └──<hex(round)>
@@ -196,13 +234,21 @@ impl Default for BitcoinStateBits {
 /// |      |       └── utxos_small.bin
 /// |      |       └── utxos_medium.bin
 /// |      |       └── address_outpoints.bin
-/// │      └── canister_states
+/// │      ├── canister_states
+/// │      │   └── <hex(canister_id)>
+/// │      │       ├── queues.pbuf
+/// │      │       ├── vmemory_0.bin
+/// │      │       ├── vmemory_0_<hex(round)>.overlay
+/// │      │       ├── canister.pbuf
+/// │      │       ├── stable_memory.(pbuf|bin)
+/// │      │       └── software.wasm
+/// │      └── snapshots
 /// │          └── <hex(canister_id)>
-/// │              ├── queues.pbuf
-/// │              ├── vmemory_0.bin
-/// │              ├── canister.pbuf
-/// │              ├── stable_memory.(pbuf|bin)
-/// │              └── software.wasm
+/// │              └── <hex(snapshot_id)>
+/// │                  ├── snapshot.pbuf
+/// │                  ├── vmemory_0.bin
+/// │                  ├── stable_memory.bin
+/// │                  └── software.wasm
 /// │
 /// └── diverged_state_markers
 /// │   └──<hex(round)>
@@ -213,6 +259,17 @@ impl Default for BitcoinStateBits {
 ///
 /// Needs to be pub for criterion performance regression tests.
 ///
+/// ## Overlay files
+///
+/// Every page map file (`*.bin`) may be accompanied by overlay files named
+/// `<file stem>_<hex(round)>.overlay`, see `overlay_path()`. An overlay holds
+/// the pages of the page map modified in the checkpoint interval ending at
+/// `round`. The contents of the page map are given by the base file with all
+/// its overlays applied in ascending order of rounds. Overlays are written
+/// instead of rewriting base files only if the state manager is configured to
+/// do so. In this case page map files are never modified in place, so they
+/// can be hard-linked into the tip instead of being copied.
+///
 /// Checkpoints management
 ///
 /// Checkpoints are created under "checkpoints" directory. fs_tmp directory
@@ -282,6 +339,7 @@ impl TipHandler {
         &mut self,
         state_layout: &StateLayout,
         height: Height,
+        page_map_files: PageMapFilesCopy,
         thread_pool: Option<&mut scoped_threadpool::Pool>,
     ) -> Result<(), LayoutError> {
         let cp_name = state_layout.checkpoint_name(height);
@@ -305,6 +363,7 @@ impl TipHandler {
             &tip,
             FilePermissions::ReadWrite,
             FSync::No,
+            page_map_files,
             thread_pool,
         ) {
             Ok(()) => Ok(()),
@@ -409,6 +468,55 @@ impl StateLayout {
         self.root.join("fs_tmp")
     }
 
+    /// Returns the paths of all entries of the temporary directory.
+    pub fn tmp_entries(&self) -> Result<Vec<PathBuf>, LayoutError> {
+        let tmp = self.tmp();
+        collect_subdirs(&tmp, |name| tmp.join(name))
+    }
+
+    /// Returns the paths of all entries of fs_tmp.
+    pub fn fs_tmp_entries(&self) -> Result<Vec<PathBuf>, LayoutError> {
+        let fs_tmp = self.fs_tmp();
+        collect_subdirs(&fs_tmp, |name| fs_tmp.join(name))
+    }
+
+    /// Removes an entry of the temporary directory or of fs_tmp.
+    pub fn remove_tmp_entry(&self, path: &Path) -> Result<(), LayoutError> {
+        let result = if path.is_dir() {
+            std::fs::remove_dir_all(path)
+        } else {
+            std::fs::remove_file(path)
+        };
+        result.map_err(|err| LayoutError::IoError {
+            path: path.to_path_buf(),
+            message: "Failed to remove temporary file".to_string(),
+            io_err: err,
+        })
+    }
+
+    /// Returns the height of the state sync using the scratchpad or cache at
+    /// `path`, or `None` if `path` is neither.
+    pub fn state_sync_height(path: &Path) -> Option<Height> {
+        let name = path.file_name()?.to_str()?;
+        let hex = name
+            .strip_prefix("state_sync_scratchpad_")
+            .or_else(|| name.strip_prefix("state_sync_cache_"))?;
+        u64::from_str_radix(hex, 16).ok().map(Height::from)
+    }
+
+    /// Returns the path to the report of the latest garbage collection.
+    pub fn gc_report(&self) -> PathBuf {
+        self.root.join(GC_REPORT_FILE)
+    }
+
+    /// Returns true if the file or directory at `path` must be kept for
+    /// forensics, i.e. if it contains a `KEEP_FOR_FORENSICS_MARKER` or the
+    /// state root does.
+    pub fn is_kept_for_forensics(&self, path: &Path) -> bool {
+        self.root.join(KEEP_FOR_FORENSICS_MARKER).exists()
+            || path.join(KEEP_FOR_FORENSICS_MARKER).exists()
+    }
+
     /// Removes the tmp directory and all its contents.
     fn cleanup_tmp(&self) -> Result<(), LayoutError> {
         let tmp = self.tmp();
@@ -507,6 +615,24 @@ impl StateLayout {
         Ok(())
     }
 
+    /// Copies the checkpoint rooted at `src`, which may belong to another state
+    /// root, into this layout as the checkpoint with the given height.
+    pub fn import_checkpoint(
+        &self,
+        src: &Path,
+        height: Height,
+    ) -> Result<CheckpointLayout<ReadOnly>, LayoutError> {
+        let cp_name = self.checkpoint_name(height);
+        let dst = self.checkpoints().join(&cp_name);
+        self.copy_and_sync_checkpoint(&cp_name, src, &dst, None)
+            .map_err(|io_err| LayoutError::IoError {
+                path: dst,
+                message: format!("Failed to import checkpoint {}", height),
+                io_err,
+            })?;
+        self.checkpoint(height)
+    }
+
     /// Returns the layout of the checkpoint with the given height (if
     /// there is one).
     pub fn checkpoint(&self, height: Height) -> Result<CheckpointLayout<ReadOnly>, LayoutError> {
@@ -838,6 +964,7 @@ impl StateLayout {
                 scratchpad.as_path(),
                 FilePermissions::ReadOnly,
                 FSync::Yes,
+                PageMapFilesCopy::Copy,
                 thread_pool,
             )?;
             std::fs::rename(&scratchpad, dst)?;
@@ -992,6 +1119,43 @@ impl<Permissions: AccessPolicy> CheckpointLayout<Permissions> {
         )
     }
 
+    /// Returns the ids of all canister snapshots stored in this checkpoint.
+    pub fn snapshot_ids(&self) -> Result<Vec<SnapshotId>, LayoutError> {
+        let snapshots_dir = self.root.join("snapshots");
+        Permissions::check_dir(&snapshots_dir)?;
+        let canister_dirs = collect_subdirs(snapshots_dir.as_path(), |p| p.to_string())?;
+        let mut snapshot_ids = Vec::new();
+        for canister_dir in canister_dirs {
+            snapshot_ids.extend(collect_subdirs(
+                snapshots_dir.join(canister_dir).as_path(),
+                |p| {
+                    let blob = hex::decode(p).unwrap_or_else(|err| {
+                        panic!(
+                            "Failed to convert directory name {} into a snapshot id: {}",
+                            p, err
+                        )
+                    });
+                    SnapshotId::try_from(&blob[..]).expect("failed to parse snapshot id")
+                },
+            )?);
+        }
+        Ok(snapshot_ids)
+    }
+
+    pub fn snapshot(
+        &self,
+        snapshot_id: &SnapshotId,
+    ) -> Result<SnapshotLayout<Permissions>, LayoutError> {
+        SnapshotLayout::new(
+            self.root
+                .join("snapshots")
+                .join(hex::encode(
+                    snapshot_id.get_canister_id().get_ref().as_slice(),
+                ))
+                .join(hex::encode(snapshot_id.to_vec())),
+        )
+    }
+
     pub fn bitcoin(&self) -> Result<BitcoinStateLayout<Permissions>, LayoutError> {
         // TODO(EXC-1113): Rename this path to "bitcoin", as it stores data for either network.
         BitcoinStateLayout::new(self.root.join("bitcoin").join("testnet"))
@@ -1045,6 +1209,106 @@ impl<Permissions: AccessPolicy> CanisterLayout<Permissions> {
     pub fn stable_memory_blob(&self) -> PathBuf {
         self.canister_root.join("stable_memory.bin")
     }
+
+    pub fn wasm_chunk_store(&self) -> PathBuf {
+        self.canister_root.join("wasm_chunk_store.bin")
+    }
+}
+
+/// The extension of the overlay files of page maps.
+const OVERLAY_EXTENSION: &str = "overlay";
+
+/// Returns the path of the overlay file written at the checkpoint `height` for
+/// the page map stored in `base_file`. For example, the overlay of
+/// `vmemory_0.bin` at height 300 is `vmemory_0_000000000000012c.overlay`.
+pub fn overlay_path(base_file: &Path, height: Height) -> PathBuf {
+    base_file.with_file_name(format!(
+        "{}_{:016x}.{}",
+        file_stem(base_file),
+        height.get(),
+        OVERLAY_EXTENSION
+    ))
+}
+
+/// Returns the overlay files of the page map stored in `base_file` together
+/// with the heights they were written at, sorted from the oldest to the newest.
+pub fn list_overlays(base_file: &Path) -> Result<Vec<(Height, PathBuf)>, LayoutError> {
+    let dir = match base_file.parent() {
+        Some(dir) => dir,
+        None => return Ok(vec![]),
+    };
+    let prefix = format!("{}_", file_stem(base_file));
+    let suffix = format!(".{}", OVERLAY_EXTENSION);
+    let names = dir_file_names(dir).map_err(|err| LayoutError::IoError {
+        path: dir.to_path_buf(),
+        message: "failed to list overlay files".to_string(),
+        io_err: err,
+    })?;
+    let mut overlays: Vec<(Height, PathBuf)> = names
+        .into_iter()
+        .filter_map(|name| {
+            let hex_height = name.strip_prefix(&prefix)?.strip_suffix(&suffix)?;
+            if hex_height.len() != 16 {
+                return None;
+            }
+            let height = u64::from_str_radix(hex_height, 16).ok()?;
+            Some((Height::new(height), dir.join(&name)))
+        })
+        .collect();
+    overlays.sort();
+    Ok(overlays)
+}
+
+fn file_stem(path: &Path) -> std::borrow::Cow<'_, str> {
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy())
+        .unwrap_or_default()
+}
+
+/// Returns true if the file at `path` stores (a part of) a page map, i.e. if
+/// it is either a base file or an overlay file.
+fn is_page_map_file(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|extension| extension.to_str()),
+        Some("bin") | Some(OVERLAY_EXTENSION)
+    )
+}
+
+pub struct SnapshotLayout<Permissions: AccessPolicy> {
+    snapshot_root: PathBuf,
+    permissions_tag: PhantomData<Permissions>,
+}
+
+impl<Permissions: AccessPolicy> SnapshotLayout<Permissions> {
+    pub fn new(snapshot_root: PathBuf) -> Result<Self, LayoutError> {
+        Permissions::check_dir(&snapshot_root)?;
+        Ok(Self {
+            snapshot_root,
+            permissions_tag: PhantomData,
+        })
+    }
+
+    pub fn raw_path(&self) -> PathBuf {
+        self.snapshot_root.clone()
+    }
+
+    pub fn snapshot(
+        &self,
+    ) -> ProtoFileWith<pb_canister_state_bits::CanisterSnapshotBits, Permissions> {
+        self.snapshot_root.join("snapshot.pbuf").into()
+    }
+
+    pub fn wasm(&self) -> WasmFile<Permissions> {
+        self.snapshot_root.join("software.wasm").into()
+    }
+
+    pub fn vmemory_0(&self) -> PathBuf {
+        self.snapshot_root.join("vmemory_0.bin")
+    }
+
+    pub fn stable_memory_blob(&self) -> PathBuf {
+        self.snapshot_root.join("stable_memory.bin")
+    }
 }
 
 pub struct BitcoinStateLayout<Permissions: AccessPolicy> {
@@ -1309,6 +1573,22 @@ impl From<CanisterStateBits> for pb_canister_state_bits::CanisterStateBits {
             task_queue: item.task_queue.iter().map(|v| v.into()).collect(),
             global_timer_nanos: item.global_timer_nanos,
             canister_version: item.canister_version,
+            next_snapshot_id: item.next_snapshot_id,
+            snapshots_memory_usage: item.snapshots_memory_usage.get(),
+            canister_history: Some((&item.canister_history).into()),
+            log_visibility: pb_canister_state_bits::LogVisibility::from(item.log_visibility).into(),
+            canister_log_records: item
+                .canister_log
+                .records()
+                .iter()
+                .map(|record| record.into())
+                .collect(),
+            next_canister_log_record_idx: item.canister_log.next_idx(),
+            wasm_memory_limit: item.wasm_memory_limit.map(|limit| limit.get()),
+            reserved_balance: Some(item.reserved_balance.into()),
+            reserved_balance_limit: item.reserved_balance_limit.map(|limit| limit.into()),
+            wasm_chunk_store_metadata: Some((&item.wasm_chunk_store_metadata).into()),
+            total_query_stats: Some((&item.total_query_stats).into()),
         }
     }
 }
@@ -1348,6 +1628,17 @@ impl TryFrom<pb_canister_state_bits::CanisterStateBits> for CanisterStateBits {
             .transpose()?
             .unwrap_or_else(Cycles::zero);
 
+        let reserved_balance = value
+            .reserved_balance
+            .map(|c| c.try_into())
+            .transpose()?
+            .unwrap_or_else(Cycles::zero);
+
+        let reserved_balance_limit = value
+            .reserved_balance_limit
+            .map(|c| c.try_into())
+            .transpose()?;
+
         let task_queue = value
             .task_queue
             .into_iter()
@@ -1394,6 +1685,81 @@ impl TryFrom<pb_canister_state_bits::CanisterStateBits> for CanisterStateBits {
             task_queue,
             global_timer_nanos: value.global_timer_nanos,
             canister_version: value.canister_version,
+            next_snapshot_id: value.next_snapshot_id,
+            snapshots_memory_usage: NumBytes::from(value.snapshots_memory_usage),
+            // Checkpoints written before canister history was introduced do
+            // not have this field.
+            canister_history: value
+                .canister_history
+                .map(CanisterHistory::try_from)
+                .transpose()?
+                .unwrap_or_default(),
+            // Checkpoints written before log visibility was introduced have
+            // it unspecified.
+            log_visibility: match pb_canister_state_bits::LogVisibility::from_i32(
+                value.log_visibility,
+            ) {
+                None | Some(pb_canister_state_bits::LogVisibility::Unspecified) => {
+                    LogVisibility::default()
+                }
+                Some(log_visibility) => LogVisibility::try_from(log_visibility)?,
+            },
+            canister_log: CanisterLog::new(
+                value.next_canister_log_record_idx,
+                value
+                    .canister_log_records
+                    .into_iter()
+                    .map(|record| record.into())
+                    .collect(),
+            ),
+            wasm_memory_limit: value.wasm_memory_limit.map(NumBytes::from),
+            reserved_balance,
+            reserved_balance_limit,
+            // Checkpoints written before the chunk store was introduced do
+            // not have this field.
+            wasm_chunk_store_metadata: value
+                .wasm_chunk_store_metadata
+                .map(WasmChunkStoreMetadata::try_from)
+                .transpose()?
+                .unwrap_or_default(),
+            total_query_stats: value
+                .total_query_stats
+                .map(TotalQueryStats::from)
+                .unwrap_or_default(),
+        })
+    }
+}
+
+impl From<CanisterSnapshotBits> for pb_canister_state_bits::CanisterSnapshotBits {
+    fn from(item: CanisterSnapshotBits) -> Self {
+        Self {
+            canister_id: Some(pb_types::CanisterId::from(item.canister_id)),
+            taken_at_timestamp: item.taken_at_timestamp.as_nanos_since_unix_epoch(),
+            canister_version: item.canister_version,
+            certified_data: item.certified_data,
+            execution_state_bits: Some((&item.execution_state_bits).into()),
+            stable_memory_size: item.stable_memory_size.get() as u64,
+        }
+    }
+}
+
+impl TryFrom<pb_canister_state_bits::CanisterSnapshotBits> for CanisterSnapshotBits {
+    type Error = ProxyDecodeError;
+
+    fn try_from(value: pb_canister_state_bits::CanisterSnapshotBits) -> Result<Self, Self::Error> {
+        let canister_id: CanisterId =
+            try_from_option_field(value.canister_id, "CanisterSnapshotBits::canister_id")?;
+        let execution_state_bits = try_from_option_field(
+            value.execution_state_bits,
+            "CanisterSnapshotBits::execution_state_bits",
+        )?;
+        Ok(Self {
+            canister_id,
+            taken_at_timestamp: Time::from_nanos_since_unix_epoch(value.taken_at_timestamp),
+            canister_version: value.canister_version,
+            certified_data: value.certified_data,
+            execution_state_bits,
+            stable_memory_size: NumWasmPages::from(value.stable_memory_size as usize),
         })
     }
 }
@@ -1635,9 +2001,22 @@ enum FSync {
     No,
 }
 
+/// Specifies how page map files are transferred by a recursive copy.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PageMapFilesCopy {
+    /// Page map files are copied like all other files.
+    Copy,
+    /// Page map files are hard-linked to the source files. This is only valid
+    /// if page map files are never modified in place in the destination, which
+    /// holds if page deltas are written to overlay files.
+    HardLink,
+}
+
 /// Recursively copies `src` to `dst` using the given permission policy for
 /// files. If a thread-pool is provided then files are copied in parallel.
-/// Syncs the target files if `fsync` is set to true.
+/// Syncs the target files if `fsync` is set to true. Page map files are
+/// hard-linked instead of copied if `page_map_files` says so; their
+/// permissions are left untouched in this case.
 ///
 /// NOTE: If the function returns an error, the changes to the file
 /// system applied by this function are not undone.
@@ -1647,14 +2026,16 @@ fn copy_recursively(
     root_dst: &Path,
     dst_permissions: FilePermissions,
     fsync: FSync,
+    page_map_files: PageMapFilesCopy,
     thread_pool: Option<&mut scoped_threadpool::Pool>,
 ) -> std::io::Result<()> {
     let mut copy_plan = CopyPlan {
         create_and_sync_dir: vec![],
         copy_and_sync_file: vec![],
+        link_file: vec![],
     };
 
-    build_copy_plan(root_src, root_dst, &mut copy_plan)?;
+    build_copy_plan(root_src, root_dst, page_map_files, &mut copy_plan)?;
 
     // Ensure that the target root directory exists.
     // Note: all the files and directories below the target root (including the
@@ -1679,6 +2060,10 @@ fn copy_recursively(
                 copy_file_and_set_permissions(log, &op.src, &op.dst, dst_permissions, fsync)
             });
             results.into_iter().try_for_each(identity)?;
+            let results = parallel_map(thread_pool, copy_plan.link_file.iter(), |op| {
+                std::fs::hard_link(&op.src, &op.dst)
+            });
+            results.into_iter().try_for_each(identity)?;
             if let FSync::Yes = fsync {
                 let results =
                     parallel_map(thread_pool, copy_plan.create_and_sync_dir.iter(), |op| {
@@ -1694,6 +2079,9 @@ fn copy_recursively(
             for op in copy_plan.copy_and_sync_file.into_iter() {
                 copy_file_and_set_permissions(log, &op.src, &op.dst, dst_permissions, fsync)?;
             }
+            for op in copy_plan.link_file.into_iter() {
+                std::fs::hard_link(&op.src, &op.dst)?;
+            }
             if let FSync::Yes = fsync {
                 for op in copy_plan.create_and_sync_dir.iter() {
                     sync_path(&op.dst)?;
@@ -1734,11 +2122,12 @@ fn copy_file_and_set_permissions(
 // Describes how to copy one directory to another.
 // The order of operations is improtant:
 // 1. All directories should be created first.
-// 2. After that files can be copied in _any_ order.
+// 2. After that files can be copied or linked in _any_ order.
 // 3. Finally, directories should be synced.
 struct CopyPlan {
     create_and_sync_dir: Vec<CreateAndSyncDir>,
     copy_and_sync_file: Vec<CopyAndSyncFile>,
+    link_file: Vec<CopyAndSyncFile>,
 }
 
 // Describes an operation for creating and syncing a directory.
@@ -1756,7 +2145,12 @@ struct CopyAndSyncFile {
 /// Traverse the source file tree and constructs a copy-plan:
 /// a collection of I/O operations that need to be performed to copy the source
 /// to the destination.
-fn build_copy_plan(src: &Path, dst: &Path, plan: &mut CopyPlan) -> std::io::Result<()> {
+fn build_copy_plan(
+    src: &Path,
+    dst: &Path,
+    page_map_files: PageMapFilesCopy,
+    plan: &mut CopyPlan,
+) -> std::io::Result<()> {
     let src_metadata = src.metadata()?;
 
     if src_metadata.is_dir() {
@@ -1770,8 +2164,13 @@ fn build_copy_plan(src: &Path, dst: &Path, plan: &mut CopyPlan) -> std::io::Resu
         for entry_result in entries {
             let entry = entry_result?;
             let dst_entry = dst.join(entry.file_name());
-            build_copy_plan(&entry.path(), &dst_entry, plan)?;
+            build_copy_plan(&entry.path(), &dst_entry, page_map_files, plan)?;
         }
+    } else if page_map_files == PageMapFilesCopy::HardLink && is_page_map_file(src) {
+        plan.link_file.push(CopyAndSyncFile {
+            src: PathBuf::from(src),
+            dst: PathBuf::from(dst),
+        });
     } else {
         plan.copy_and_sync_file.push(CopyAndSyncFile {
             src: PathBuf::from(src),
@@ -1785,12 +2184,14 @@ fn build_copy_plan(src: &Path, dst: &Path, plan: &mut CopyPlan) -> std::io::Resu
 mod test {
     use super::*;
 
-    use ic_ic00_types::IC_00;
+    use ic_ic00_types::{
+        CanisterChange, CanisterChangeDetails, CanisterChangeOrigin, CanisterInstallMode, IC_00,
+    };
     use ic_interfaces::messages::{CanisterInputMessage, RequestOrIngress};
     use ic_test_utilities::{
         mock_time,
         types::{
-            ids::canister_test_id,
+            ids::{canister_test_id, user_test_id},
             messages::{IngressBuilder, RequestBuilder, ResponseBuilder},
         },
     };
@@ -1824,6 +2225,16 @@ mod test {
             task_queue: vec![],
             global_timer_nanos: None,
             canister_version: 0,
+            next_snapshot_id: 0,
+            snapshots_memory_usage: NumBytes::from(0),
+            canister_history: CanisterHistory::default(),
+            log_visibility: LogVisibility::default(),
+            canister_log: CanisterLog::default(),
+            wasm_memory_limit: None,
+            reserved_balance: Cycles::zero(),
+            reserved_balance_limit: None,
+            wasm_chunk_store_metadata: WasmChunkStoreMetadata::default(),
+            total_query_stats: TotalQueryStats::default(),
         }
     }
 
@@ -1850,6 +2261,76 @@ mod test {
         });
     }
 
+    #[test]
+    fn test_state_layout_gc_helpers() {
+        with_test_replica_logger(|log| {
+            let tempdir = tmpdir("state_layout");
+            let root_path = tempdir.path().to_path_buf();
+            let state_layout = StateLayout::try_new(log, root_path.clone()).unwrap();
+
+            let scratchpad = state_layout.state_sync_scratchpad(Height::new(42)).unwrap();
+            let cache = state_layout.state_sync_cache(Height::new(43)).unwrap();
+            std::fs::create_dir_all(&scratchpad).unwrap();
+            std::fs::create_dir_all(&cache).unwrap();
+            assert_eq!(
+                StateLayout::state_sync_height(&scratchpad),
+                Some(Height::new(42))
+            );
+            assert_eq!(
+                StateLayout::state_sync_height(&cache),
+                Some(Height::new(43))
+            );
+            assert_eq!(StateLayout::state_sync_height(&state_layout.fs_tmp()), None);
+            let mut entries = state_layout.tmp_entries().unwrap();
+            entries.sort();
+            assert_eq!(entries, vec![cache, scratchpad.clone()]);
+
+            assert!(!state_layout.is_kept_for_forensics(&scratchpad));
+            std::fs::write(scratchpad.join(KEEP_FOR_FORENSICS_MARKER), b"").unwrap();
+            assert!(state_layout.is_kept_for_forensics(&scratchpad));
+
+            state_layout.remove_tmp_entry(&scratchpad).unwrap();
+            assert!(!scratchpad.exists());
+        });
+    }
+
+    #[test]
+    fn test_list_overlays_of_page_map() {
+        let tempdir = tmpdir("state_layout");
+        let base_file = tempdir.path().join("vmemory_0.bin");
+        assert_eq!(
+            overlay_path(&base_file, Height::new(300)),
+            tempdir.path().join("vmemory_0_000000000000012c.overlay")
+        );
+
+        for height in [500, 300, 1000] {
+            std::fs::write(overlay_path(&base_file, Height::new(height)), b"").unwrap();
+        }
+        // Files of other page maps must not be listed.
+        let stable_memory = tempdir.path().join("stable_memory.bin");
+        std::fs::write(overlay_path(&stable_memory, Height::new(400)), b"").unwrap();
+        std::fs::write(&base_file, b"").unwrap();
+
+        assert_eq!(
+            list_overlays(&base_file).unwrap(),
+            vec![
+                (Height::new(300), overlay_path(&base_file, Height::new(300))),
+                (Height::new(500), overlay_path(&base_file, Height::new(500))),
+                (
+                    Height::new(1000),
+                    overlay_path(&base_file, Height::new(1000))
+                ),
+            ]
+        );
+        assert_eq!(
+            list_overlays(&stable_memory).unwrap(),
+            vec![(
+                Height::new(400),
+                overlay_path(&stable_memory, Height::new(400))
+            )]
+        );
+    }
+
     #[test]
     fn test_encode_decode_empty_controllers() {
         // A canister state with empty controllers.
@@ -1923,4 +2404,83 @@ mod test {
         let canister_state_bits = CanisterStateBits::try_from(pb_bits).unwrap();
         assert_eq!(canister_state_bits.task_queue, task_queue);
     }
+
+    #[test]
+    fn test_encode_decode_canister_history() {
+        let mut canister_history = CanisterHistory::default();
+        canister_history.add_canister_change(CanisterChange::new(
+            42,
+            0,
+            CanisterChangeOrigin::from_user(user_test_id(1).get()),
+            CanisterChangeDetails::canister_creation(vec![user_test_id(1).get()]),
+        ));
+        canister_history.add_canister_change(CanisterChange::new(
+            123,
+            1,
+            CanisterChangeOrigin::from_canister(canister_test_id(2).get(), Some(7)),
+            CanisterChangeDetails::code_deployment(CanisterInstallMode::Upgrade, [1; 32]),
+        ));
+        canister_history.add_canister_change(CanisterChange::new(
+            456,
+            2,
+            CanisterChangeOrigin::from_canister(canister_test_id(2).get(), None),
+            CanisterChangeDetails::CanisterCodeUninstall,
+        ));
+
+        let canister_state_bits = CanisterStateBits {
+            canister_history: canister_history.clone(),
+            ..default_canister_state_bits()
+        };
+
+        let pb_bits = pb_canister_state_bits::CanisterStateBits::from(canister_state_bits);
+        let canister_state_bits = CanisterStateBits::try_from(pb_bits).unwrap();
+        assert_eq!(canister_state_bits.canister_history, canister_history);
+    }
+
+    #[test]
+    fn test_encode_decode_canister_log() {
+        let mut canister_log = CanisterLog::new_with_next_index(7);
+        canister_log.add_record(100, b"first");
+        canister_log.add_record(200, b"second");
+
+        let canister_state_bits = CanisterStateBits {
+            log_visibility: LogVisibility::Public,
+            canister_log: canister_log.clone(),
+            ..default_canister_state_bits()
+        };
+
+        let pb_bits = pb_canister_state_bits::CanisterStateBits::from(canister_state_bits);
+        let canister_state_bits = CanisterStateBits::try_from(pb_bits).unwrap();
+        assert_eq!(canister_state_bits.log_visibility, LogVisibility::Public);
+        assert_eq!(canister_state_bits.canister_log, canister_log);
+    }
+
+    #[test]
+    fn test_encode_decode_reserved_balance() {
+        let canister_state_bits = CanisterStateBits {
+            reserved_balance: Cycles::new(1_000),
+            reserved_balance_limit: Some(Cycles::new(5_000)),
+            ..default_canister_state_bits()
+        };
+
+        let pb_bits = pb_canister_state_bits::CanisterStateBits::from(canister_state_bits);
+        let canister_state_bits = CanisterStateBits::try_from(pb_bits).unwrap();
+        assert_eq!(canister_state_bits.reserved_balance, Cycles::new(1_000));
+        assert_eq!(
+            canister_state_bits.reserved_balance_limit,
+            Some(Cycles::new(5_000))
+        );
+    }
+
+    #[test]
+    fn test_decode_unspecified_log_visibility() {
+        let mut pb_bits =
+            pb_canister_state_bits::CanisterStateBits::from(default_canister_state_bits());
+        pb_bits.log_visibility = pb_canister_state_bits::LogVisibility::Unspecified as i32;
+        let canister_state_bits = CanisterStateBits::try_from(pb_bits).unwrap();
+        assert_eq!(
+            canister_state_bits.log_visibility,
+            LogVisibility::Controllers
+        );
+    }
 }
@@ -1046,6 +1046,7 @@ impl StateMachine {
             UpdateSettingsArgs {
                 canister_id: canister_id.get(),
                 settings,
+                sender_canister_version: None,
             }
             .encode(),
         )
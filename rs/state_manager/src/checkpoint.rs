@@ -1,6 +1,6 @@
 use crate::{
-    CheckpointError, CheckpointMetrics, CheckpointRef, PageMapType, PersistenceError, TipRequest,
-    NUMBER_OF_CHECKPOINT_THREADS,
+    overlay_checkpoints, should_merge_overlays, CheckpointError, CheckpointMetrics, CheckpointRef,
+    PageMapType, TipRequest, NUMBER_OF_CHECKPOINT_THREADS,
 };
 use crossbeam_channel::{unbounded, Sender};
 use ic_base_types::CanisterId;
@@ -8,20 +8,24 @@ use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::Memory;
 use ic_replicated_state::{
     bitcoin_state::{BitcoinState, UtxoSet},
+    canister_snapshots::ExecutionStateSnapshot,
     canister_state::execution_state::WasmBinary,
     page_map::PageMap,
-    CanisterMetrics, CanisterState, ExecutionState, ReplicatedState, SchedulerState, SystemState,
+    CanisterMetrics, CanisterSnapshot, CanisterSnapshots, CanisterState, ExecutionState,
+    ReplicatedState, SchedulerState, SnapshotId, SystemState, WasmChunkStore,
 };
 use ic_state_layout::{
-    BitcoinStateBits, CanisterLayout, CanisterStateBits, CheckpointLayout, ReadOnly, ReadPolicy,
+    list_overlays, BitcoinStateBits, CanisterLayout, CanisterSnapshotBits, CanisterStateBits,
+    CheckpointLayout, ReadOnly, ReadPolicy,
 };
 use ic_types::{CanisterTimer, Height, LongExecutionMode, Time};
 use ic_utils::thread::parallel_map;
 use std::collections::BTreeMap;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 use std::{
     convert::{From, TryFrom},
-    path::Path,
+    path::{Path, PathBuf},
 };
 
 /// Creates a checkpoint of the node state using specified directory
@@ -70,6 +74,7 @@ pub fn make_checkpoint(
         tip_channel
             .send(TipRequest::TipToCheckpoint {
                 height,
+                overlay_checkpoints: overlay_checkpoints(state),
                 sender: send,
             })
             .unwrap();
@@ -85,6 +90,7 @@ pub fn make_checkpoint(
         .send(TipRequest::DefragTip {
             height,
             page_map_types: PageMapType::list_all(state),
+            overlay_checkpoints: overlay_checkpoints(state),
         })
         .unwrap();
 
@@ -101,6 +107,22 @@ pub fn make_checkpoint(
         )?
     };
 
+    // The overlays of the checkpoint are merged into the base files of the tip
+    // in the background. The state hashing relies on the same decision being
+    // made on the page maps loaded from this checkpoint, see `get_dirty_pages`.
+    let page_map_types: Vec<PageMapType> = PageMapType::list_all(&state)
+        .into_iter()
+        .filter(|entry| entry.get(&state).map_or(false, should_merge_overlays))
+        .collect();
+    if !page_map_types.is_empty() {
+        tip_channel
+            .send(TipRequest::MergeOverlays {
+                height,
+                page_map_types,
+            })
+            .unwrap();
+    }
+
     Ok((cp_ref, state))
 }
 /// Calls [load_checkpoint] with a newly created thread pool.
@@ -207,6 +229,20 @@ pub fn load_checkpoint<P: ReadPolicy + Send + Sync>(
         load_bitcoin_state(checkpoint_layout)?
     };
 
+    let canister_snapshots = {
+        let _timer = metrics
+            .load_checkpoint_step_duration
+            .with_label_values(&["canister_snapshots"])
+            .start_timer();
+
+        let mut canister_snapshots = BTreeMap::new();
+        for snapshot_id in checkpoint_layout.snapshot_ids()? {
+            let snapshot = load_snapshot_from_checkpoint(checkpoint_layout, &snapshot_id)?;
+            canister_snapshots.insert(snapshot_id, Arc::new(snapshot));
+        }
+        CanisterSnapshots::new(canister_snapshots)
+    };
+
     let state = ReplicatedState::new_from_checkpoint(
         canister_states,
         metadata,
@@ -214,6 +250,7 @@ pub fn load_checkpoint<P: ReadPolicy + Send + Sync>(
         // Consensus queue needs to be empty at the end of every round.
         Vec::new(),
         bitcoin,
+        canister_snapshots,
     );
 
     Ok(state)
@@ -265,14 +302,14 @@ pub fn load_canister_state<P: ReadPolicy>(
         Some(execution_state_bits) => {
             let starting_time = Instant::now();
             let wasm_memory = Memory::new(
-                PageMap::open(&canister_layout.vmemory_0(), height)?,
+                open_page_map(&canister_layout.vmemory_0(), height)?,
                 execution_state_bits.heap_size,
             );
             durations.insert("wasm_memory", starting_time.elapsed());
 
             let starting_time = Instant::now();
             let stable_memory = Memory::new(
-                PageMap::open(&canister_layout.stable_memory_blob(), height)?,
+                open_page_map(&canister_layout.stable_memory_blob(), height)?,
                 canister_state_bits.stable_memory_size,
             );
             durations.insert("stable_memory", starting_time.elapsed());
@@ -314,6 +351,20 @@ pub fn load_canister_state<P: ReadPolicy>(
             })?;
     durations.insert("canister_queues", starting_time.elapsed());
 
+    let starting_time = Instant::now();
+    // Checkpoints written before the chunk store was introduced have no file.
+    let wasm_chunk_store_path = canister_layout.wasm_chunk_store();
+    let wasm_chunk_store_data = if wasm_chunk_store_path.exists() {
+        open_page_map(&wasm_chunk_store_path, height)?
+    } else {
+        PageMap::new()
+    };
+    let wasm_chunk_store = WasmChunkStore::from_checkpoint(
+        wasm_chunk_store_data,
+        canister_state_bits.wasm_chunk_store_metadata,
+    );
+    durations.insert("wasm_chunk_store", starting_time.elapsed());
+
     let canister_metrics = CanisterMetrics {
         scheduled_as_first: canister_state_bits.scheduled_as_first,
         skipped_round_due_to_no_messages: canister_state_bits.skipped_round_due_to_no_messages,
@@ -336,6 +387,15 @@ pub fn load_canister_state<P: ReadPolicy>(
         canister_state_bits.task_queue.into_iter().collect(),
         CanisterTimer::from_nanos_since_unix_epoch(canister_state_bits.global_timer_nanos),
         canister_state_bits.canister_version,
+        canister_state_bits.next_snapshot_id,
+        canister_state_bits.snapshots_memory_usage,
+        canister_state_bits.canister_history,
+        canister_state_bits.log_visibility,
+        canister_state_bits.canister_log,
+        canister_state_bits.wasm_memory_limit,
+        canister_state_bits.reserved_balance,
+        canister_state_bits.reserved_balance_limit,
+        wasm_chunk_store,
     );
 
     let canister_state = CanisterState {
@@ -354,6 +414,7 @@ pub fn load_canister_state<P: ReadPolicy>(
             time_of_last_allocation_charge: Time::from_nanos_since_unix_epoch(
                 canister_state_bits.time_of_last_allocation_charge_nanos,
             ),
+            total_query_stats: canister_state_bits.total_query_stats,
         },
     };
 
@@ -370,6 +431,54 @@ fn load_canister_state_from_checkpoint<P: ReadPolicy>(
     load_canister_state::<P>(&canister_layout, canister_id, checkpoint_layout.height())
 }
 
+/// Loads the canister snapshot with the given id from the checkpoint.
+pub fn load_snapshot_from_checkpoint<P: ReadPolicy>(
+    checkpoint_layout: &CheckpointLayout<P>,
+    snapshot_id: &SnapshotId,
+) -> Result<CanisterSnapshot, CheckpointError> {
+    let snapshot_layout = checkpoint_layout.snapshot(snapshot_id)?;
+    let height = checkpoint_layout.height();
+
+    let snapshot_bits: CanisterSnapshotBits = CanisterSnapshotBits::try_from(
+        snapshot_layout.snapshot().deserialize()?,
+    )
+    .map_err(|err| CheckpointError::ProtoError {
+        path: snapshot_layout.raw_path(),
+        field: format!("snapshots[{}]::snapshot_bits", snapshot_id),
+        proto_err: err.to_string(),
+    })?;
+    let execution_state_bits = snapshot_bits.execution_state_bits;
+
+    let wasm_memory = Memory::new(
+        open_page_map(&snapshot_layout.vmemory_0(), height)?,
+        execution_state_bits.heap_size,
+    );
+    let stable_memory = Memory::new(
+        open_page_map(&snapshot_layout.stable_memory_blob(), height)?,
+        snapshot_bits.stable_memory_size,
+    );
+    let wasm_binary = WasmBinary::new(
+        snapshot_layout
+            .wasm()
+            .deserialize(execution_state_bits.binary_hash)?,
+    );
+
+    Ok(CanisterSnapshot::new(
+        snapshot_bits.canister_id,
+        snapshot_bits.taken_at_timestamp,
+        snapshot_bits.canister_version,
+        snapshot_bits.certified_data,
+        ExecutionStateSnapshot {
+            wasm_binary,
+            exported_globals: execution_state_bits.exported_globals,
+            exports: execution_state_bits.exports,
+            metadata: execution_state_bits.metadata,
+            wasm_memory,
+            stable_memory,
+        },
+    ))
+}
+
 fn load_bitcoin_state<P: ReadPolicy>(
     checkpoint_layout: &CheckpointLayout<P>,
 ) -> Result<BitcoinState, CheckpointError> {
@@ -408,14 +517,23 @@ fn load_bitcoin_state<P: ReadPolicy>(
     })
 }
 
-fn load_or_create_pagemap(path: &Path, height: Height) -> Result<PageMap, PersistenceError> {
+fn load_or_create_pagemap(path: &Path, height: Height) -> Result<PageMap, CheckpointError> {
     if path.exists() {
-        PageMap::open(path, height)
+        open_page_map(path, height)
     } else {
         Ok(PageMap::default())
     }
 }
 
+/// Opens the page map stored in `base_file` together with its overlays.
+fn open_page_map(base_file: &Path, height: Height) -> Result<PageMap, CheckpointError> {
+    let overlays: Vec<PathBuf> = list_overlays(base_file)?
+        .into_iter()
+        .map(|(_, path)| path)
+        .collect();
+    Ok(PageMap::open_with_overlays(base_file, &overlays, height)?)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -692,6 +810,82 @@ mod tests {
         });
     }
 
+    #[test]
+    fn can_recover_from_a_checkpoint_with_overlays() {
+        with_test_replica_logger(|log| {
+            let tmp = tmpdir("checkpoint");
+            let root = tmp.path().to_path_buf();
+            let layout = StateLayout::try_new(log.clone(), root.clone()).unwrap();
+            let tip_handler = layout.capture_tip_handler();
+            let state_manager_metrics = state_manager_metrics();
+            let (_tip_thread, tip_channel) = spawn_tip_thread(
+                log,
+                tip_handler,
+                layout.clone(),
+                state_manager_metrics.clone(),
+            );
+
+            const HEIGHT: Height = Height::new(42);
+            let canister_id: CanisterId = canister_test_id(10);
+            let wasm_memory = one_page_of(1);
+
+            let mut canister_state = new_canister_state(
+                canister_id,
+                user_test_id(24).get(),
+                INITIAL_CYCLES,
+                NumSeconds::from(100_000),
+            );
+            canister_state.execution_state = Some(ExecutionState {
+                canister_root: "NOT_USED".into(),
+                session_nonce: None,
+                wasm_binary: WasmBinary::new(empty_wasm()),
+                wasm_memory: wasm_memory.clone(),
+                stable_memory: Memory::default(),
+                exported_globals: vec![],
+                exports: ExportedFunctions::new(BTreeSet::new()),
+                metadata: WasmMetadata::default(),
+                last_executed_round: ExecutionRound::from(0),
+            });
+
+            let own_subnet_type = SubnetType::Application;
+            let mut state = ReplicatedState::new(subnet_test_id(1), own_subnet_type);
+            state.metadata.own_subnet_features.overlay_checkpoints = true;
+            state.put_canister_state(canister_state);
+            let _state = make_checkpoint_and_get_state(&state, HEIGHT, &tip_channel);
+
+            // The dirty page is written to an overlay next to an empty base file.
+            let canister_path = root
+                .join("checkpoints")
+                .join("000000000000002a")
+                .join("canister_states")
+                .join("000000000000000a0101");
+            assert_eq!(
+                canister_path
+                    .join("vmemory_0.bin")
+                    .metadata()
+                    .unwrap()
+                    .len(),
+                0
+            );
+            assert!(canister_path
+                .join("vmemory_0_000000000000002a.overlay")
+                .exists());
+
+            let recovered_state = load_checkpoint(
+                &layout.checkpoint(HEIGHT).unwrap(),
+                own_subnet_type,
+                &state_manager_metrics.checkpoint_metrics,
+                Some(&mut thread_pool()),
+            )
+            .unwrap();
+            let canister = recovered_state.canister_state(&canister_id).unwrap();
+            assert_eq!(
+                canister.execution_state.as_ref().unwrap().wasm_memory,
+                wasm_memory
+            );
+        });
+    }
+
     #[test]
     fn can_recover_an_empty_state() {
         with_test_replica_logger(|log| {
@@ -0,0 +1,243 @@
+//! Garbage collection of the state layout. Removes leftovers of checkpoint
+//! operations in `fs_tmp`, abandoned state syncs in `tmp`, as well as diverged
+//! checkpoints, backups and diverged state markers beyond the configured
+//! retention. Entries holding a `KEEP_FOR_FORENSICS_MARKER` are kept.
+//!
+//! Each collection produces a `GcReport` listing every entry considered with
+//! its size and the reason it is garbage. The report is exported as metrics and
+//! stored in the state root, where the orchestrator dashboard picks it up.
+
+use crate::{path_age, StateLayoutGcMetrics};
+use ic_config::state_manager::StateLayoutGcConfig;
+use ic_logger::{info, warn, ReplicaLogger};
+use ic_state_layout::{
+    error::LayoutError,
+    gc_report::{GcAction, GcEntry, GcReason, GcReport},
+    StateLayout,
+};
+use ic_types::Height;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// A file or directory that is garbage.
+struct Candidate {
+    path: PathBuf,
+    reason: GcReason,
+    /// The height the entry belongs to, for entries named after a height.
+    height: Option<Height>,
+    age: Duration,
+}
+
+/// Collects the garbage in `layout` according to `config`, then stores the
+/// report in the state root and exports it as metrics.
+pub(crate) fn collect_garbage(
+    log: &ReplicaLogger,
+    layout: &StateLayout,
+    config: &StateLayoutGcConfig,
+    metrics: &StateLayoutGcMetrics,
+) -> GcReport {
+    let tmp_max_age = Duration::from_secs(config.tmp_max_age_secs);
+    let archived_max_age = Duration::from_secs(config.archived_checkpoint_max_age_secs);
+    let mut candidates = Vec::new();
+
+    match layout.fs_tmp_entries() {
+        Ok(paths) => {
+            for path in paths {
+                let age = path_age(log, &path);
+                if age > tmp_max_age {
+                    candidates.push(Candidate {
+                        path,
+                        reason: GcReason::StaleFsTmp,
+                        height: None,
+                        age,
+                    });
+                }
+            }
+        }
+        Err(err) => warn!(log, "Failed to enumerate fs_tmp: {}", err),
+    }
+
+    // State syncs for heights above the latest checkpoint may still complete.
+    let latest_checkpoint = layout
+        .checkpoint_heights()
+        .ok()
+        .and_then(|heights| heights.last().copied());
+    match (layout.tmp_entries(), latest_checkpoint) {
+        (Ok(paths), Some(latest_checkpoint)) => {
+            for path in paths {
+                let height = match StateLayout::state_sync_height(&path) {
+                    Some(height) if height <= latest_checkpoint => height,
+                    _ => continue,
+                };
+                let age = path_age(log, &path);
+                if age > tmp_max_age {
+                    candidates.push(Candidate {
+                        path,
+                        reason: GcReason::AbandonedStateSync,
+                        height: Some(height),
+                        age,
+                    });
+                }
+            }
+        }
+        (Ok(_), None) => (),
+        (Err(err), _) => warn!(log, "Failed to enumerate tmp: {}", err),
+    }
+
+    push_archived(
+        log,
+        &mut candidates,
+        layout.diverged_checkpoint_heights(),
+        |h| layout.diverged_checkpoint_path(h),
+        config.max_archived_checkpoints,
+        archived_max_age,
+        GcReason::DivergedCheckpoint,
+    );
+    push_archived(
+        log,
+        &mut candidates,
+        layout.backup_heights(),
+        |h| layout.backup_checkpoint_path(h),
+        config.max_archived_checkpoints,
+        archived_max_age,
+        GcReason::Backup,
+    );
+    push_archived(
+        log,
+        &mut candidates,
+        layout.diverged_state_heights(),
+        |h| layout.diverged_state_marker_path(h),
+        config.max_diverged_state_markers,
+        archived_max_age,
+        GcReason::DivergedStateMarker,
+    );
+
+    let entries = candidates
+        .into_iter()
+        .map(|candidate| {
+            let size_bytes = size_on_disk(&candidate.path);
+            let action = if layout.is_kept_for_forensics(&candidate.path) {
+                info!(
+                    log,
+                    "Keeping {} for forensics ({} bytes)",
+                    candidate.path.display(),
+                    size_bytes
+                );
+                GcAction::KeptForForensics
+            } else {
+                match remove(layout, &candidate) {
+                    Ok(()) => {
+                        info!(
+                            log,
+                            "Removed {} {} ({} bytes)",
+                            candidate.reason.as_str(),
+                            candidate.path.display(),
+                            size_bytes
+                        );
+                        GcAction::Removed
+                    }
+                    Err(err) => {
+                        warn!(
+                            log,
+                            "Failed to remove {} {}: {}",
+                            candidate.reason.as_str(),
+                            candidate.path.display(),
+                            err
+                        );
+                        GcAction::Failed
+                    }
+                }
+            };
+            GcEntry {
+                path: candidate.path,
+                size_bytes,
+                age_secs: candidate.age.as_secs(),
+                reason: candidate.reason,
+                action,
+            }
+        })
+        .collect();
+
+    let report = GcReport {
+        timestamp_secs: SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs(),
+        entries,
+    };
+    metrics.observe(&report);
+    if let Err(err) = report.store(&layout.gc_report()) {
+        warn!(log, "Failed to store the state layout GC report: {}", err);
+    }
+    report
+}
+
+/// Adds the entries with the given `heights` to the candidates, except for
+/// the latest `max_to_keep` ones that are younger than `max_age`.
+fn push_archived<F>(
+    log: &ReplicaLogger,
+    candidates: &mut Vec<Candidate>,
+    heights: Result<Vec<Height>, LayoutError>,
+    path_of: F,
+    max_to_keep: usize,
+    max_age: Duration,
+    reason: GcReason,
+) where
+    F: Fn(Height) -> PathBuf,
+{
+    let heights = match heights {
+        Ok(heights) => heights,
+        Err(err) => {
+            warn!(
+                log,
+                "Failed to enumerate {} entries: {}",
+                reason.as_str(),
+                err
+            );
+            return;
+        }
+    };
+    let to_remove = heights.len().saturating_sub(max_to_keep);
+    for (i, height) in heights.into_iter().enumerate() {
+        let path = path_of(height);
+        let age = path_age(log, &path);
+        if i < to_remove || age > max_age {
+            candidates.push(Candidate {
+                path,
+                reason,
+                height: Some(height),
+                age,
+            });
+        }
+    }
+}
+
+fn remove(layout: &StateLayout, candidate: &Candidate) -> Result<(), LayoutError> {
+    match (candidate.reason, candidate.height) {
+        (GcReason::DivergedCheckpoint, Some(height)) => layout.remove_diverged_checkpoint(height),
+        (GcReason::Backup, Some(height)) => layout.remove_backup(height),
+        (GcReason::DivergedStateMarker, Some(height)) => {
+            layout.remove_diverged_state_marker(height)
+        }
+        _ => layout.remove_tmp_entry(&candidate.path),
+    }
+}
+
+/// Returns the total size of the files below `path`, not following symlinks.
+fn size_on_disk(path: &Path) -> u64 {
+    let metadata = match path.symlink_metadata() {
+        Ok(metadata) => metadata,
+        Err(_) => return 0,
+    };
+    if !metadata.is_dir() {
+        return metadata.len();
+    }
+    std::fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| size_on_disk(&entry.path()))
+                .sum()
+        })
+        .unwrap_or(0)
+}
@@ -3,12 +3,15 @@
 pub mod checkpoint;
 pub mod labeled_tree_visitor;
 pub mod manifest;
+pub mod split;
 pub mod state_sync;
 pub mod stream_encoding;
 pub mod tip;
 pub mod tree_diff;
 pub mod tree_hash;
 
+mod gc;
+
 use crate::state_sync::chunkable::cache::StateSyncCache;
 use crate::tip::{spawn_tip_thread, TipRequest};
 use crossbeam_channel::{unbounded, Sender};
@@ -17,7 +20,10 @@ use ic_canonical_state::{
     hash_tree::{hash_lazy_tree, HashTree},
     lazy_tree::{materialize::materialize_partial, LazyTree},
 };
-use ic_config::state_manager::Config;
+use ic_config::{
+    flag_status::FlagStatus,
+    state_manager::{Config, StateLayoutGcConfig},
+};
 use ic_crypto_tree_hash::{recompute_digest, Digest, LabeledTree, MixedHashTree, Witness};
 use ic_interfaces::certification::Verifier;
 use ic_interfaces_certified_stream_store::{
@@ -34,22 +40,26 @@ use ic_protobuf::proxy::{ProtoProxy, ProxyDecodeError};
 use ic_protobuf::{messaging::xnet::v1, state::v1 as pb};
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::{
-    canister_state::execution_state::SandboxMemory, page_map::PersistenceError, PageIndex, PageMap,
-    ReplicatedState,
+    canister_snapshots::SnapshotOperation, canister_state::execution_state::SandboxMemory,
+    page_map::PersistenceError, PageIndex, PageMap, ReplicatedState, SnapshotId,
+};
+use ic_state_layout::{
+    error::LayoutError,
+    gc_report::{GcAction, GcReason, GcReport},
+    AccessPolicy, CheckpointLayout, StateLayout,
 };
-use ic_state_layout::{error::LayoutError, AccessPolicy, CheckpointLayout, StateLayout};
 use ic_types::{
     artifact::StateSyncArtifactId,
     chunkable::Chunkable,
     consensus::certification::Certification,
     crypto::CryptoHash,
     malicious_flags::MaliciousFlags,
-    state_sync::{FileGroupChunks, Manifest},
+    state_sync::{CompressedChunkCache, FileGroupChunks, Manifest},
     xnet::{CertifiedStreamSlice, StreamIndex, StreamSlice},
     CryptoHashOfPartialState, CryptoHashOfState, Height, RegistryVersion, SubnetId,
 };
 use ic_utils::thread::JoinOnDrop;
-use prometheus::{HistogramVec, IntCounter, IntCounterVec, IntGauge};
+use prometheus::{HistogramVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec};
 use prost::Message;
 use std::convert::{From, TryFrom};
 use std::fmt;
@@ -77,8 +87,9 @@ const CRITICAL_ERROR_REUSED_CHUNK_HASH: &str =
 /// Critical error tracking unexpectedly corrupted chunks.
 const CRITICAL_ERROR_STATE_SYNC_CORRUPTED_CHUNKS: &str = "state_sync_corrupted_chunks";
 
-/// How long to keep diverged state.
-const ARCHIVED_CHECKPOINT_MAX_AGE: Duration = Duration::from_secs(30 * 24 * 60 * 60); // 30 days
+/// The number of overlay files a page map may accumulate before they are merged
+/// into its base file after a checkpoint.
+const MAX_OVERLAYS_PER_PAGE_MAP: usize = 8;
 
 /// Labels for manifest metrics
 const LABEL_TYPE: &str = "type";
@@ -110,6 +121,14 @@ pub struct StateManagerMetrics {
     states_metadata_pbuf_size: IntGauge,
     checkpoint_metrics: CheckpointMetrics,
     manifest_metrics: ManifestMetrics,
+    state_layout_gc_metrics: StateLayoutGcMetrics,
+}
+
+#[derive(Clone)]
+pub struct StateLayoutGcMetrics {
+    size_bytes: IntGaugeVec,
+    removed_bytes: IntCounterVec,
+    last_run_timestamp: IntGauge,
 }
 
 #[derive(Clone)]
@@ -283,10 +302,57 @@ impl StateManagerMetrics {
             states_metadata_pbuf_size,
             checkpoint_metrics: CheckpointMetrics::new(metrics_registry),
             manifest_metrics: ManifestMetrics::new(metrics_registry),
+            state_layout_gc_metrics: StateLayoutGcMetrics::new(metrics_registry),
         }
     }
 }
 
+impl StateLayoutGcMetrics {
+    pub fn new(metrics_registry: &MetricsRegistry) -> Self {
+        let size_bytes = metrics_registry.int_gauge_vec(
+            "state_manager_state_layout_gc_size_bytes",
+            "Size of the garbage found by the latest state layout GC in bytes, by reason and action taken.",
+            &["reason", "action"],
+        );
+        let removed_bytes = metrics_registry.int_counter_vec(
+            "state_manager_state_layout_gc_removed_bytes_total",
+            "Total size of the garbage removed by the state layout GC in bytes, by reason.",
+            &["reason"],
+        );
+        // See note [Metrics preallocation]
+        for reason in GcReason::ALL.iter() {
+            removed_bytes.with_label_values(&[reason.as_str()]);
+            for action in GcAction::ALL.iter() {
+                size_bytes.with_label_values(&[reason.as_str(), action.as_str()]);
+            }
+        }
+        let last_run_timestamp = metrics_registry.int_gauge(
+            "state_manager_state_layout_gc_last_run_timestamp_seconds",
+            "The (UTC) timestamp of the latest state layout GC.",
+        );
+
+        Self {
+            size_bytes,
+            removed_bytes,
+            last_run_timestamp,
+        }
+    }
+
+    fn observe(&self, report: &GcReport) {
+        for reason in GcReason::ALL.iter() {
+            for action in GcAction::ALL.iter() {
+                self.size_bytes
+                    .with_label_values(&[reason.as_str(), action.as_str()])
+                    .set(report.size_bytes(*reason, *action) as i64);
+            }
+            self.removed_bytes
+                .with_label_values(&[reason.as_str()])
+                .inc_by(report.size_bytes(*reason, GcAction::Removed));
+        }
+        self.last_run_timestamp.set(report.timestamp_secs as i64);
+    }
+}
+
 impl ManifestMetrics {
     pub fn new(metrics_registry: &MetricsRegistry) -> Self {
         let chunk_bytes = metrics_registry.int_counter_vec(
@@ -422,6 +488,8 @@ struct StateMetadata {
     manifest: Option<Manifest>,
     // The field is set as `None` until we serve a state sync for the first time.
     state_sync_file_group: Option<Arc<FileGroupChunks>>,
+    // Compressed chunks recently served from this state.
+    compressed_chunks: Arc<CompressedChunkCache>,
 }
 
 impl From<&StateMetadata> for pb::StateMetadata {
@@ -448,6 +516,7 @@ impl TryFrom<pb::StateMetadata> for StateMetadata {
                     manifest: Some(manifest),
                     root_hash: Some(root_hash),
                     state_sync_file_group: None,
+                    compressed_chunks: Default::default(),
                 })
             }
         }
@@ -656,12 +725,6 @@ type Deallocation = Box<dyn std::any::Any + Send + 'static>;
 // deallocation objects goes above the threshold.
 const DEALLOCATION_BACKLOG_THRESHOLD: usize = 500;
 
-/// The number of diverged states to keep before we start deleting the old ones.
-const MAX_ARCHIVED_CHECKPOINTS_TO_KEEP: usize = 2;
-
-/// The number of diverged state markers to keep.
-const MAX_DIVERGED_STATE_MARKERS_TO_KEEP: usize = 100;
-
 /// The number of extra checkpoints to keep for state sync.
 const EXTRA_CHECKPOINTS_TO_KEEP: usize = 1;
 
@@ -689,6 +752,7 @@ pub struct StateManagerImpl {
     persist_metadata_guard: Arc<Mutex<()>>,
     tip_channel: Sender<TipRequest>,
     _tip_thread_handle: JoinOnDrop<()>,
+    state_layout_gc: StateLayoutGcConfig,
 }
 
 fn load_checkpoint(
@@ -752,6 +816,7 @@ fn initialize_tip(
     tip_channel
         .send(TipRequest::ResetTipTo {
             checkpoint_ref: checkpoint_ref.clone(),
+            overlay_checkpoints: overlay_checkpoints(&snapshot.state),
         })
         .unwrap();
 
@@ -763,6 +828,38 @@ fn initialize_tip(
     ReplicatedState::clone(&snapshot.state)
 }
 
+/// Sends the snapshot operations recorded in the given state since the last
+/// flush to the tip thread.
+pub(crate) fn flush_snapshot_operations(
+    tip_channel: &Sender<TipRequest>,
+    state: &mut ReplicatedState,
+    height: Height,
+) {
+    for operation in state.canister_snapshots.take_unflushed_changes() {
+        let request = match operation {
+            SnapshotOperation::Backup(canister_id, snapshot_id) => {
+                TipRequest::BackupCanisterSnapshot {
+                    height,
+                    canister_id,
+                    snapshot_id,
+                }
+            }
+            SnapshotOperation::Restore(canister_id, snapshot_id) => {
+                TipRequest::RestoreCanisterSnapshot {
+                    height,
+                    canister_id,
+                    snapshot_id,
+                }
+            }
+            SnapshotOperation::Delete(snapshot_id) => TipRequest::DeleteCanisterSnapshot {
+                height,
+                snapshot_id,
+            },
+        };
+        tip_channel.send(request).unwrap();
+    }
+}
+
 /// Return duration since path creation (or modification, if no creation)
 /// Return zero duration and log a warning on failure.
 fn path_age(log: &ReplicaLogger, path: &Path) -> Duration {
@@ -791,58 +888,6 @@ fn path_age(log: &ReplicaLogger, path: &Path) -> Duration {
     }
 }
 
-/// Deletes obsolete diverged states and state backups, keeping at most
-/// MAX_ARCHIVED_CHECKPOINTS_TO_KEEP checkpoints and backups no older than
-/// ARCHIVED_CHECKPOINT_MAX_AGE
-fn cleanup_diverged_states(log: &ReplicaLogger, layout: &StateLayout) {
-    if let Ok(diverged_heights) = layout.diverged_checkpoint_heights() {
-        let to_remove = diverged_heights
-            .len()
-            .saturating_sub(MAX_ARCHIVED_CHECKPOINTS_TO_KEEP);
-        for (i, h) in diverged_heights.iter().enumerate() {
-            if i < to_remove
-                || path_age(log, &layout.diverged_checkpoint_path(*h)) > ARCHIVED_CHECKPOINT_MAX_AGE
-            {
-                match layout.remove_diverged_checkpoint(*h) {
-                    Ok(()) => info!(log, "Successfully removed diverged state {}", *h),
-                    Err(err) => info!(log, "{}", err),
-                }
-            }
-        }
-    }
-    if let Ok(backup_heights) = layout.backup_heights() {
-        let to_remove = backup_heights
-            .len()
-            .saturating_sub(MAX_ARCHIVED_CHECKPOINTS_TO_KEEP);
-        for (i, h) in backup_heights.iter().enumerate() {
-            if i < to_remove
-                || path_age(log, &layout.backup_checkpoint_path(*h)) > ARCHIVED_CHECKPOINT_MAX_AGE
-            {
-                match layout.remove_backup(*h) {
-                    Ok(()) => info!(log, "Successfully removed backup {}", *h),
-                    Err(err) => info!(log, "Failed to remove backup {}", err),
-                }
-            }
-        }
-    }
-    if let Ok(state_heights) = layout.diverged_state_heights() {
-        let to_remove = state_heights
-            .len()
-            .saturating_sub(MAX_DIVERGED_STATE_MARKERS_TO_KEEP);
-        for (i, h) in state_heights.iter().enumerate() {
-            if i < to_remove
-                || path_age(log, &layout.diverged_state_marker_path(*h))
-                    > ARCHIVED_CHECKPOINT_MAX_AGE
-            {
-                match layout.remove_diverged_state_marker(*h) {
-                    Ok(()) => info!(log, "Successfully removed diverged state marker {}", h),
-                    Err(err) => info!(log, "{}", err),
-                }
-            }
-        }
-    }
-}
-
 fn report_last_diverged_state(
     log: &ReplicaLogger,
     metrics: &StateManagerMetrics,
@@ -907,6 +952,9 @@ struct PopulatedMetadata {
 pub enum PageMapType {
     WasmMemory(CanisterId),
     StableMemory(CanisterId),
+    WasmChunkStore(CanisterId),
+    SnapshotWasmMemory(SnapshotId),
+    SnapshotStableMemory(SnapshotId),
     Bitcoin(BitcoinPageMap),
 }
 
@@ -927,6 +975,12 @@ impl PageMapType {
                 result.push(Self::WasmMemory(id.to_owned()));
                 result.push(Self::StableMemory(id.to_owned()));
             }
+            result.push(Self::WasmChunkStore(id.to_owned()));
+        }
+
+        for (id, _) in state.canister_snapshots.iter() {
+            result.push(Self::SnapshotWasmMemory(id.to_owned()));
+            result.push(Self::SnapshotStableMemory(id.to_owned()));
         }
 
         result.push(Self::Bitcoin(BitcoinPageMap::UtxosSmall));
@@ -944,6 +998,9 @@ impl PageMapType {
         match &self {
             PageMapType::WasmMemory(id) => Ok(layout.canister(id)?.vmemory_0()),
             PageMapType::StableMemory(id) => Ok(layout.canister(id)?.stable_memory_blob()),
+            PageMapType::WasmChunkStore(id) => Ok(layout.canister(id)?.wasm_chunk_store()),
+            PageMapType::SnapshotWasmMemory(id) => Ok(layout.snapshot(id)?.vmemory_0()),
+            PageMapType::SnapshotStableMemory(id) => Ok(layout.snapshot(id)?.stable_memory_blob()),
             PageMapType::Bitcoin(BitcoinPageMap::UtxosSmall) => Ok(layout.bitcoin()?.utxos_small()),
             PageMapType::Bitcoin(BitcoinPageMap::UtxosMedium) => {
                 Ok(layout.bitcoin()?.utxos_medium())
@@ -967,6 +1024,17 @@ impl PageMapType {
                     .as_ref()
                     .map(|ex| &ex.stable_memory.page_map)
             }),
+            PageMapType::WasmChunkStore(id) => state
+                .canister_state(id)
+                .map(|can| can.system_state.wasm_chunk_store.page_map()),
+            PageMapType::SnapshotWasmMemory(id) => state
+                .canister_snapshots
+                .get(id)
+                .map(|snapshot| &snapshot.execution_snapshot().wasm_memory.page_map),
+            PageMapType::SnapshotStableMemory(id) => state
+                .canister_snapshots
+                .get(id)
+                .map(|snapshot| &snapshot.execution_snapshot().stable_memory.page_map),
             PageMapType::Bitcoin(BitcoinPageMap::UtxosSmall) => {
                 Some(&state.bitcoin().utxo_set.utxos_small)
             }
@@ -992,6 +1060,25 @@ impl PageMapType {
                     .as_mut()
                     .map(|ex| &mut ex.stable_memory.page_map)
             }),
+            PageMapType::WasmChunkStore(id) => state
+                .canister_state_mut(id)
+                .map(|can| can.system_state.wasm_chunk_store.page_map_mut()),
+            PageMapType::SnapshotWasmMemory(id) => {
+                state.canister_snapshots.get_mut(id).map(|snapshot| {
+                    &mut Arc::make_mut(snapshot)
+                        .execution_snapshot_mut()
+                        .wasm_memory
+                        .page_map
+                })
+            }
+            PageMapType::SnapshotStableMemory(id) => {
+                state.canister_snapshots.get_mut(id).map(|snapshot| {
+                    &mut Arc::make_mut(snapshot)
+                        .execution_snapshot_mut()
+                        .stable_memory
+                        .page_map
+                })
+            }
             PageMapType::Bitcoin(BitcoinPageMap::UtxosSmall) => {
                 Some(&mut state.bitcoin_mut().utxo_set.utxos_small)
             }
@@ -1020,21 +1107,63 @@ pub enum FileType {
 
 pub type DirtyPages = Vec<DirtyPageMap>;
 
+/// Returns whether checkpoints of the given state only write the dirty pages
+/// of page maps to overlay files. The subnet feature is part of the replicated
+/// state, so all replicas of a subnet lay out their checkpoints the same way.
+pub(crate) fn overlay_checkpoints(state: &ReplicatedState) -> FlagStatus {
+    if state.metadata.own_subnet_features.overlay_checkpoints {
+        FlagStatus::Enabled
+    } else {
+        FlagStatus::Disabled
+    }
+}
+
+/// Returns true if the overlays of the given page map, which was loaded from a
+/// checkpoint, are merged into its base file in the tip after that checkpoint.
+pub(crate) fn should_merge_overlays(page_map: &PageMap) -> bool {
+    page_map.num_overlays() >= MAX_OVERLAYS_PER_PAGE_MAP
+}
+
 /// Get dirty pages of all PageMaps backed by a checkpoint
 /// file.
+///
+/// With overlay checkpoints, the page delta is written to a new overlay file
+/// and the base file only changes when the overlays of the previous checkpoint
+/// are merged into it. In that case the dirty pages of the base file are the
+/// pages of the merged overlays.
+///
+/// Round deltas are flushed into the base files of the tip until the subnet
+/// enables overlay checkpoints, so the page delta only counts as unchanged if
+/// the feature was already enabled in the state of `previous_snapshot`.
 pub fn get_dirty_pages(
     state: &ReplicatedState,
     previous_snapshot: Option<&Snapshot>,
 ) -> DirtyPages {
+    let base_overlay_checkpoints =
+        previous_snapshot.map(|snapshot| overlay_checkpoints(&snapshot.state));
     let mut result: DirtyPages = PageMapType::list_all(state)
         .into_iter()
         .filter_map(|entry| {
+            // The memories of a canister restored from a snapshot are backed by the
+            // snapshot's files, so they cannot be compared to the canister's files.
+            if let PageMapType::WasmMemory(id) | PageMapType::StableMemory(id) = &entry {
+                if state.canister_snapshots.restored_canisters().contains(id) {
+                    return None;
+                }
+            }
             let page_map = entry.get(state)?;
             let height = page_map.base_height?;
+            let mut page_delta_indices = match base_overlay_checkpoints {
+                Some(FlagStatus::Enabled) => vec![],
+                _ => page_map.get_page_delta_indices(),
+            };
+            if should_merge_overlays(page_map) {
+                page_delta_indices.extend(page_map.get_overlay_page_indices());
+            }
             Some(DirtyPageMap {
                 height,
                 file_type: FileType::PageMap(entry),
-                page_delta_indices: page_map.get_page_delta_indices(),
+                page_delta_indices,
             })
         })
         .collect();
@@ -1331,10 +1460,15 @@ impl StateManagerImpl {
         );
 
         let starting_time = Instant::now();
-        cleanup_diverged_states(&log, &state_layout);
+        gc::collect_garbage(
+            &log,
+            &state_layout,
+            config.state_layout_gc(),
+            &metrics.state_layout_gc_metrics,
+        );
         info!(
             log,
-            "Cleaning up diverged states took {:?}",
+            "Collecting state layout garbage took {:?}",
             starting_time.elapsed()
         );
 
@@ -1517,6 +1651,7 @@ impl StateManagerImpl {
             persist_metadata_guard,
             tip_channel,
             _tip_thread_handle,
+            state_layout_gc: config.state_layout_gc().clone(),
         }
     }
 
@@ -1864,6 +1999,7 @@ impl StateManagerImpl {
                         manifest,
                         root_hash,
                         state_sync_file_group: None,
+                        compressed_chunks: Default::default(),
                     },
                 );
             } else {
@@ -1879,6 +2015,7 @@ impl StateManagerImpl {
                         manifest: None,
                         root_hash: None,
                         state_sync_file_group: None,
+                        compressed_chunks: Default::default(),
                     },
                 );
             }
@@ -1901,7 +2038,14 @@ impl StateManagerImpl {
     }
 
     fn populate_extra_metadata(&self, state: &mut ReplicatedState, height: Height) {
-        state.metadata.state_sync_version = manifest::CURRENT_STATE_SYNC_VERSION;
+        // Compressed chunks are only produced once the subnet enables them in the
+        // registry, so that all replicas of the subnet agree on the manifest.
+        state.metadata.state_sync_version =
+            if state.metadata.own_subnet_features.compressed_state_sync {
+                manifest::STATE_SYNC_V2
+            } else {
+                manifest::CURRENT_STATE_SYNC_VERSION
+            };
         state.metadata.certification_version = ic_canonical_state::CURRENT_CERTIFICATION_VERSION;
 
         if height == Self::INITIAL_STATE_HEIGHT {
@@ -1934,6 +2078,12 @@ impl StateManagerImpl {
     /// Flushes to disk all the canister heap deltas accumulated in memory
     /// during one round of execution.
     fn flush_page_maps(&self, tip_state: &mut ReplicatedState, height: Height) {
+        let overlay_checkpoints = overlay_checkpoints(tip_state);
+        // Snapshot operations must be applied to the tip before any round deltas are
+        // flushed, as they copy files that the round deltas are applied on top of.
+        // The tip thread processes requests in order, so sending them first suffices.
+        flush_snapshot_operations(&self.tip_channel, tip_state, height);
+
         for entry in PageMapType::list_all(tip_state) {
             if let Some(page_map) = entry.get_mut(tip_state) {
                 // In cases where a PageMap's data has to be wiped, execution will replace the PageMap with a newly
@@ -1950,10 +2100,13 @@ impl StateManagerImpl {
                         .send(TipRequest::TruncatePageMapsPath {
                             height,
                             page_map_type: entry,
+                            overlay_checkpoints,
                         })
                         .unwrap();
                 }
-                if !page_map.round_delta_is_empty() {
+                // With overlay checkpoints, the files in the tip are never modified in place and
+                // the page delta is written to an overlay file at the next checkpoint instead.
+                if !page_map.round_delta_is_empty() && overlay_checkpoints == FlagStatus::Disabled {
                     // Clone and send page map for asynchornous flushing to disc. The round deltas are
                     // emptied in the original to ensure we don't flush twice.
                     self.tip_channel
@@ -2060,6 +2213,7 @@ impl StateManagerImpl {
                 checkpoint_ref: Some(self.new_checkpoint_ref(height)),
                 root_hash: Some(root_hash),
                 state_sync_file_group: None,
+                compressed_chunks: Default::default(),
             },
         );
 
@@ -2828,7 +2982,12 @@ impl StateManager for StateManagerImpl {
 
                 // We don't need to persist the deltas to the tip because we
                 // flush deltas separately every round, see flush_page_maps.
-                strip_page_map_deltas(&mut state);
+                // Overlay checkpoints persist the deltas as overlay files
+                // instead, so they are only stripped after the checkpoint.
+                if overlay_checkpoints(&state) == FlagStatus::Disabled {
+                    strip_page_map_deltas(&mut state);
+                }
+                state.canister_snapshots.clear_restored_canisters();
                 let result = {
                     checkpoint::make_checkpoint(
                         &state,
@@ -2889,6 +3048,8 @@ impl StateManager for StateManagerImpl {
                         err
                     ),
                 };
+                // All deltas are part of the checkpoint at this point.
+                strip_page_map_deltas(&mut state);
                 switch_to_checkpoint(&mut state, &checkpointed_state);
                 (Some(cp_ref), checkpointed_state)
             }
@@ -2968,6 +3129,7 @@ impl StateManager for StateManagerImpl {
                             manifest: None,
                             root_hash: None,
                             state_sync_file_group: None,
+                            compressed_chunks: Default::default(),
                         },
                     );
 
@@ -3005,6 +3167,13 @@ impl StateManager for StateManagerImpl {
 
         if scope == CertificationScope::Full {
             self.release_lock_and_persist_metadata(states);
+            // The tip thread skips the collection if the previous one is too
+            // recent.
+            self.tip_channel
+                .send(TipRequest::CollectGarbage {
+                    config: self.state_layout_gc.clone(),
+                })
+                .expect("failed to send CollectGarbage message");
         }
     }
 
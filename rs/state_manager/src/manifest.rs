@@ -31,10 +31,24 @@ use std::sync::{Arc, Mutex, Weak};
 
 pub const STATE_SYNC_V1: u32 = 1;
 
+/// Same manifest hashing procedure as `STATE_SYNC_V1`, but all chunks except
+/// for the manifest chunk are transmitted zstd-compressed. Chunk hashes are
+/// still computed over the uncompressed bytes.
+pub const STATE_SYNC_V2: u32 = 2;
+
 /// The version of StateSync protocol that should be used for all newly produced
-/// states.
+/// states, unless the subnet enables the `compressed_state_sync` feature in the
+/// registry, in which case `STATE_SYNC_V2` is used.
+///
+/// This is bumped separately from `MAX_SUPPORTED_STATE_SYNC_VERSION`: a new
+/// version must first be supported by all replicas of a subnet before any of
+/// them starts producing states with it.
 pub const CURRENT_STATE_SYNC_VERSION: u32 = STATE_SYNC_V1;
 
+/// The highest version of StateSync protocol this replica can compute
+/// manifests for and fetch states with.
+pub const MAX_SUPPORTED_STATE_SYNC_VERSION: u32 = STATE_SYNC_V2;
+
 pub const DEFAULT_CHUNK_SIZE: u32 = 1 << 20; // 1 MiB.
 
 /// When computing a manifest, we recompute the hash of every
@@ -70,6 +84,10 @@ pub enum ManifestValidationError {
         expected_hash: Vec<u8>,
         actual_hash: Vec<u8>,
     },
+    UnsupportedManifestVersion {
+        manifest_version: u32,
+        max_supported_version: u32,
+    },
 }
 
 impl fmt::Display for ManifestValidationError {
@@ -95,6 +113,14 @@ impl fmt::Display for ManifestValidationError {
                 hex::encode(&expected_hash[..]),
                 hex::encode(&actual_hash[..])
             ),
+            Self::UnsupportedManifestVersion {
+                manifest_version,
+                max_supported_version,
+            } => write!(
+                f,
+                "unsupported manifest version {}, max supported version {}",
+                manifest_version, max_supported_version
+            ),
         }
     }
 }
@@ -693,7 +719,11 @@ fn dirty_chunks_of_file(
             // As the chunk size is a multiple of the page size, at most one chunk could
             // possibly be affected.
             let chunk_index = PAGE_SIZE * page_index.get() as usize / max_chunk_size as usize;
-            chunks_bitmap.set(chunk_index, true);
+            // Pages that were written to an overlay file rather than to this file may lie
+            // beyond its end.
+            if chunk_index < num_chunks {
+                chunks_bitmap.set(chunk_index, true);
+            }
         }
 
         // NB. The code below handles the case when the file size increased, but the
@@ -801,6 +831,15 @@ pub fn compute_manifest(
     max_chunk_size: u32,
     opt_manifest_delta: Option<ManifestDelta>,
 ) -> Result<Manifest, CheckpointError> {
+    if version > MAX_SUPPORTED_STATE_SYNC_VERSION {
+        fatal!(
+            log,
+            "Unsupported state sync version {}, max supported version {}",
+            version,
+            MAX_SUPPORTED_STATE_SYNC_VERSION
+        );
+    }
+
     let mut files = Vec::new();
     files_with_sizes(checkpoint_root_path, "".into(), &mut files)?;
     // We sort the table to make sure that the table is the same on all replicas
@@ -878,6 +917,13 @@ pub fn validate_manifest(
     manifest: &Manifest,
     root_hash: &CryptoHashOfState,
 ) -> Result<(), ManifestValidationError> {
+    if manifest.version > MAX_SUPPORTED_STATE_SYNC_VERSION {
+        return Err(ManifestValidationError::UnsupportedManifestVersion {
+            manifest_version: manifest.version,
+            max_supported_version: MAX_SUPPORTED_STATE_SYNC_VERSION,
+        });
+    }
+
     let mut chunk_start: usize = 0;
 
     for (file_index, f) in manifest.file_table.iter().enumerate() {
@@ -2,7 +2,8 @@ use super::{
     build_file_group_chunks, compute_manifest, diff_manifest, file_chunk_range,
     filter_out_zero_chunks, hash::ManifestHash, manifest_hash, validate_chunk, validate_manifest,
     ChunkValidationError, DiffScript, ManifestValidationError, CURRENT_STATE_SYNC_VERSION,
-    DEFAULT_CHUNK_SIZE, MAX_FILE_SIZE_TO_GROUP, STATE_SYNC_V1,
+    DEFAULT_CHUNK_SIZE, MAX_FILE_SIZE_TO_GROUP, MAX_SUPPORTED_STATE_SYNC_VERSION, STATE_SYNC_V1,
+    STATE_SYNC_V2,
 };
 use crate::ManifestMetrics;
 
@@ -11,8 +12,8 @@ use ic_metrics::MetricsRegistry;
 use ic_types::{
     crypto::CryptoHash,
     state_sync::{
-        decode_manifest, encode_manifest, ChunkInfo, FileGroupChunks, FileInfo, Manifest,
-        FILE_GROUP_CHUNK_ID_OFFSET,
+        compress_chunk, decode_manifest, decompress_chunk, encode_manifest, ChunkInfo,
+        FileGroupChunks, FileInfo, Manifest, FILE_GROUP_CHUNK_ID_OFFSET,
     },
     CryptoHashOfState,
 };
@@ -334,6 +335,48 @@ fn orphan_chunk_detected() {
     }
 }
 
+#[test]
+fn unsupported_manifest_version_detected() {
+    let (manifest_hash, manifest) = simple_manifest();
+    let manifest = Manifest::new(
+        MAX_SUPPORTED_STATE_SYNC_VERSION + 1,
+        manifest.file_table.to_owned(),
+        manifest.chunk_table.to_owned(),
+    );
+    let root_hash = CryptoHashOfState::from(CryptoHash(manifest_hash.to_vec()));
+    assert_eq!(
+        validate_manifest(&manifest, &root_hash),
+        Err(ManifestValidationError::UnsupportedManifestVersion {
+            manifest_version: MAX_SUPPORTED_STATE_SYNC_VERSION + 1,
+            max_supported_version: MAX_SUPPORTED_STATE_SYNC_VERSION,
+        })
+    );
+}
+
+#[test]
+fn compressed_chunk_is_validated_against_uncompressed_hash() {
+    let (_, manifest) = simple_manifest();
+    assert!(!manifest.has_compressed_chunks());
+
+    let manifest = Manifest::new(
+        STATE_SYNC_V2,
+        manifest.file_table.to_owned(),
+        manifest.chunk_table.to_owned(),
+    );
+    assert!(manifest.has_compressed_chunks());
+
+    let chunk_0 = vec![0u8; 1000];
+    let compressed = compress_chunk(&chunk_0).unwrap();
+    assert!(compressed.len() < chunk_0.len());
+    assert!(validate_chunk(0, &compressed, &manifest).is_err());
+
+    let decompressed = decompress_chunk(&compressed, DEFAULT_CHUNK_SIZE as usize).unwrap();
+    assert_eq!(Ok(()), validate_chunk(0, &decompressed, &manifest));
+
+    // Decompression must not produce more than the allowed number of bytes.
+    assert!(decompress_chunk(&compressed, chunk_0.len() - 1).is_err());
+}
+
 #[test]
 fn test_diff_simple_manifest() {
     let (_, manifest_old) = simple_manifest();
@@ -0,0 +1,212 @@
+//! Splits the latest checkpoint of a subnet into the checkpoints of two
+//! subnets: the retaining subnet, which keeps the subnet id, and a new subnet
+//! receiving a set of canister ID ranges. See `ReplicatedState::split()` for
+//! how the state itself is split.
+//!
+//! Each resulting checkpoint is written to its own state root at the height
+//! following the one of the split checkpoint, together with the manifest that
+//! the recovery CUP of the respective subnet has to refer to.
+
+use crate::{
+    checkpoint::{load_checkpoint, make_checkpoint},
+    flush_snapshot_operations,
+    manifest::{compute_manifest, manifest_hash, DEFAULT_CHUNK_SIZE},
+    overlay_checkpoints,
+    tip::{spawn_tip_thread, TipRequest},
+    CheckpointRef, PageMapType, StateManagerMetrics, NUMBER_OF_CHECKPOINT_THREADS,
+};
+use crossbeam_channel::{unbounded, Sender};
+use ic_logger::{info, ReplicaLogger};
+use ic_metrics::MetricsRegistry;
+use ic_registry_routing_table::CanisterIdRanges;
+use ic_registry_subnet_type::SubnetType;
+use ic_replicated_state::ReplicatedState;
+use ic_state_layout::{CheckpointLayout, ReadOnly, StateLayout};
+use ic_types::{state_sync::Manifest, Height, SubnetId};
+use std::path::PathBuf;
+
+/// A checkpoint written for one of the subnets resulting from a split.
+pub struct SplitCheckpoint {
+    /// The height of the checkpoint.
+    pub height: Height,
+    /// The path of the checkpoint directory.
+    pub path: PathBuf,
+    /// The manifest of the checkpoint.
+    pub manifest: Manifest,
+    /// The root hash of the manifest.
+    pub root_hash: [u8; 32],
+}
+
+/// Loads the latest checkpoint in `src_root` and splits it into a checkpoint
+/// retaining all canisters outside of `migrated_ranges`, written to
+/// `retaining_root`, and a checkpoint of the subnet `new_subnet_id` hosting
+/// the canisters within `migrated_ranges`, written to `receiving_root`.
+///
+/// The output state roots must not contain any checkpoints yet.
+pub fn split_checkpoint(
+    src_root: PathBuf,
+    own_subnet_type: SubnetType,
+    migrated_ranges: &CanisterIdRanges,
+    new_subnet_id: SubnetId,
+    retaining_root: PathBuf,
+    receiving_root: PathBuf,
+    log: ReplicaLogger,
+) -> Result<(SplitCheckpoint, SplitCheckpoint), String> {
+    let src_layout = StateLayout::try_new(log.clone(), src_root.clone())
+        .map_err(|e| format!("Failed to open state root {}: {}", src_root.display(), e))?;
+    let height = src_layout
+        .checkpoint_heights()
+        .map_err(|e| format!("Failed to list checkpoints: {}", e))?
+        .last()
+        .copied()
+        .ok_or_else(|| format!("No checkpoints found in {}", src_root.display()))?;
+    let src_cp = src_layout
+        .checkpoint(height)
+        .map_err(|e| format!("Failed to open checkpoint {}: {}", height, e))?;
+
+    let metrics_registry = MetricsRegistry::new();
+    let metrics = StateManagerMetrics::new(&metrics_registry);
+    let mut thread_pool = scoped_threadpool::Pool::new(NUMBER_OF_CHECKPOINT_THREADS);
+
+    let state = load_checkpoint(
+        &src_cp,
+        own_subnet_type,
+        &metrics.checkpoint_metrics,
+        Some(&mut thread_pool),
+    )
+    .map_err(|e| format!("Failed to load checkpoint {}: {}", height, e))?;
+    let (retained, received) = state.split(migrated_ranges, new_subnet_id)?;
+    info!(
+        log,
+        "Split checkpoint @{}: retaining {} canisters, moving {} canisters to subnet {}",
+        height,
+        retained.num_canisters(),
+        received.num_canisters(),
+        new_subnet_id
+    );
+
+    let retaining = write_split_checkpoint(
+        retained,
+        &src_cp,
+        retaining_root,
+        &metrics,
+        &mut thread_pool,
+        &log,
+    )?;
+    let receiving = write_split_checkpoint(
+        received,
+        &src_cp,
+        receiving_root,
+        &metrics,
+        &mut thread_pool,
+        &log,
+    )?;
+    Ok((retaining, receiving))
+}
+
+/// Writes `state`, which was split off the checkpoint `src_cp`, as a new
+/// checkpoint into the state root `root`.
+///
+/// The tip is initialized from a copy of `src_cp`, so that files that are not
+/// affected by the split are reused rather than rewritten. The copy is removed
+/// once the new checkpoint is written.
+fn write_split_checkpoint(
+    mut state: ReplicatedState,
+    src_cp: &CheckpointLayout<ReadOnly>,
+    root: PathBuf,
+    metrics: &StateManagerMetrics,
+    thread_pool: &mut scoped_threadpool::Pool,
+    log: &ReplicaLogger,
+) -> Result<SplitCheckpoint, String> {
+    let layout = StateLayout::try_new(log.clone(), root.clone())
+        .map_err(|e| format!("Failed to open state root {}: {}", root.display(), e))?;
+    let existing_heights = layout
+        .checkpoint_heights()
+        .map_err(|e| format!("Failed to list checkpoints: {}", e))?;
+    if !existing_heights.is_empty() {
+        return Err(format!(
+            "State root {} already contains checkpoints {:?}",
+            root.display(),
+            existing_heights
+        ));
+    }
+
+    let base_height = src_cp.height();
+    layout
+        .import_checkpoint(src_cp.raw_path(), base_height)
+        .map_err(|e| format!("Failed to copy checkpoint {}: {}", base_height, e))?;
+
+    let (_tip_thread, tip_channel) = spawn_tip_thread(
+        log.clone(),
+        layout.capture_tip_handler(),
+        layout.clone(),
+        metrics.clone(),
+    );
+    let base_ref = CheckpointRef::new(log.clone(), metrics.clone(), layout.clone(), base_height);
+    tip_channel
+        .send(TipRequest::ResetTipTo {
+            checkpoint_ref: base_ref.clone(),
+            overlay_checkpoints: overlay_checkpoints(&state),
+        })
+        .unwrap();
+
+    let height = base_height.increment();
+    flush_snapshot_operations(&tip_channel, &mut state, height);
+    // Page maps that did not come from the split checkpoint, e.g. the bitcoin
+    // state of the receiving subnet, must not inherit its files.
+    for entry in PageMapType::list_all(&state) {
+        if let Some(page_map) = entry.get(&state) {
+            if page_map.base_height.is_none() {
+                tip_channel
+                    .send(TipRequest::TruncatePageMapsPath {
+                        height,
+                        page_map_type: entry,
+                        overlay_checkpoints: overlay_checkpoints(&state),
+                    })
+                    .unwrap();
+            }
+        }
+    }
+
+    let (_cp_ref, _) = make_checkpoint(
+        &state,
+        height,
+        &tip_channel,
+        &metrics.checkpoint_metrics,
+        thread_pool,
+    )
+    .map_err(|e| format!("Failed to write checkpoint {}: {}", height, e))?;
+
+    wait_for_tip_thread(&tip_channel);
+    drop(base_ref);
+    layout
+        .force_remove_checkpoint(base_height)
+        .map_err(|e| format!("Failed to remove checkpoint {}: {}", base_height, e))?;
+
+    let cp_layout = layout
+        .checkpoint(height)
+        .map_err(|e| format!("Failed to open checkpoint {}: {}", height, e))?;
+    let manifest = compute_manifest(
+        thread_pool,
+        &metrics.manifest_metrics,
+        log,
+        state.metadata.state_sync_version,
+        cp_layout.raw_path(),
+        DEFAULT_CHUNK_SIZE,
+        None,
+    )
+    .map_err(|e| format!("Failed to compute manifest of checkpoint {}: {}", height, e))?;
+
+    Ok(SplitCheckpoint {
+        height,
+        path: cp_layout.raw_path().to_path_buf(),
+        root_hash: manifest_hash(&manifest),
+        manifest,
+    })
+}
+
+fn wait_for_tip_thread(tip_channel: &Sender<TipRequest>) {
+    let (send, recv) = unbounded();
+    tip_channel.send(TipRequest::Wait { sender: send }).unwrap();
+    recv.recv().unwrap();
+}
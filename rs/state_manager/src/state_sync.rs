@@ -111,6 +111,7 @@ impl ArtifactClient<StateSyncArtifact> for StateManagerImpl {
                             checkpoint_root: checkpoint_root.raw_path().to_path_buf(),
                             manifest: manifest.clone(),
                             state_sync_file_group,
+                            compressed_chunks: Arc::clone(&metadata.compressed_chunks),
                         })
                     } else {
                         None
@@ -166,6 +167,7 @@ impl ArtifactClient<StateSyncArtifact> for StateManagerImpl {
                         checkpoint_root: checkpoint_root.raw_path().to_path_buf(),
                         manifest: manifest.clone(),
                         state_sync_file_group: Default::default(),
+                        compressed_chunks: Default::default(),
                     };
                     Some(StateSyncArtifact::message_to_advert(&msg))
                 } else {
@@ -17,7 +17,8 @@ use ic_types::{
         ChunkId, Chunkable,
     },
     state_sync::{
-        decode_manifest, FileGroupChunks, Manifest, FILE_GROUP_CHUNK_ID_OFFSET, MANIFEST_CHUNK,
+        decode_manifest, decompress_chunk, FileGroupChunks, Manifest, FILE_GROUP_CHUNK_ID_OFFSET,
+        MANIFEST_CHUNK,
     },
     CryptoHashOfState, Height,
 };
@@ -746,6 +747,7 @@ impl IncompleteState {
             // `state_sync_file_group` and `checkpoint_root` are not included in the integrity hash of this artifact.
             // Therefore it is OK to pass a default value here as it is only used when fetching chunks.
             state_sync_file_group: Default::default(),
+            compressed_chunks: Default::default(),
         })
     }
 
@@ -1261,34 +1263,66 @@ impl Chunkable for IncompleteState {
                     return Err(ChunksMoreNeeded);
                 }
 
+                // The indices in the chunk table of the chunks contained in the payload.
+                let chunk_table_indices = if ix < FILE_GROUP_CHUNK_ID_OFFSET as usize {
+                    // A normal chunk contains only itself.
+                    vec![ix as u32 - 1]
+                } else {
+                    // A file group chunk contains the chunks listed in `FileGroupChunks`.
+                    state_sync_file_group
+                        .get(&(ix as u32))
+                        .ok_or(ChunkVerificationFailed)?
+                        .clone()
+                };
+
+                // Starting with `STATE_SYNC_V2`, chunks are sent compressed. Once
+                // decompressed, the payload is exactly as large as the chunks it
+                // contains according to the manifest.
+                let decompressed_payload;
+                let payload = if manifest.has_compressed_chunks() {
+                    let max_size = chunk_table_indices
+                        .iter()
+                        .map(|index| manifest.chunk_table[*index as usize].size_bytes as usize)
+                        .sum();
+                    decompressed_payload = decompress_chunk(payload, max_size).map_err(|err| {
+                        warn!(
+                            self.log,
+                            "Received invalid compressed chunk {}: {}", ix, err
+                        );
+                        self.metrics
+                            .state_sync_metrics
+                            .corrupted_chunks
+                            .with_label_values(&[LABEL_FETCH])
+                            .inc();
+                        ChunkVerificationFailed
+                    })?;
+                    &decompressed_payload
+                } else {
+                    payload
+                };
+
                 // Each index in `chunk_table_indices` is mapped to a piece of payload bytes
                 // with its corresponding start and end position.
-                let (chunk_table_indices, payload_pieces) =
-                    if ix < FILE_GROUP_CHUNK_ID_OFFSET as usize {
-                        // If it is a normal chunk, there is only one index mapped to the whole payload.
-                        (vec![ix as u32 - 1], vec![(0, payload.len())])
-                    } else {
-                        // If it is a file group chunk, divide it into pieces according to the `FileGroupChunks`.
-                        let chunk_table_indices = state_sync_file_group
-                            .get(&(ix as u32))
-                            .ok_or(ChunkVerificationFailed)?
-                            .clone();
-
-                        let mut cur_offset = 0;
-                        let mut payload_pieces: Vec<(usize, usize)> = Vec::new();
-                        for chunk_table_index in &chunk_table_indices {
-                            let chunk_size = manifest.chunk_table[*chunk_table_index as usize]
-                                .size_bytes as usize;
-                            payload_pieces.push((cur_offset, cur_offset + chunk_size));
-                            cur_offset += chunk_size;
-                        }
+                let payload_pieces = if ix < FILE_GROUP_CHUNK_ID_OFFSET as usize {
+                    // If it is a normal chunk, the index is mapped to the whole payload.
+                    vec![(0, payload.len())]
+                } else {
+                    // If it is a file group chunk, divide it into pieces according to the `FileGroupChunks`.
+                    let mut cur_offset = 0;
+                    let mut payload_pieces: Vec<(usize, usize)> = Vec::new();
+                    for chunk_table_index in &chunk_table_indices {
+                        let chunk_size =
+                            manifest.chunk_table[*chunk_table_index as usize].size_bytes as usize;
+                        payload_pieces.push((cur_offset, cur_offset + chunk_size));
+                        cur_offset += chunk_size;
+                    }
 
-                        if cur_offset != payload.len() {
-                            warn!(self.log, "Received invalid file group chunk {}", ix);
-                            return Err(ChunkVerificationFailed);
-                        }
-                        (chunk_table_indices, payload_pieces)
-                    };
+                    if cur_offset != payload.len() {
+                        warn!(self.log, "Received invalid file group chunk {}", ix);
+                        return Err(ChunkVerificationFailed);
+                    }
+                    payload_pieces
+                };
 
                 let log = &self.log;
                 let metrics = &self.metrics;
@@ -59,6 +59,7 @@ fn fake_complete() -> DownloadState {
         checkpoint_root: PathBuf::new(),
         manifest,
         state_sync_file_group: Default::default(),
+        compressed_chunks: Default::default(),
     });
     DownloadState::Complete(Box::new(artifact))
 }
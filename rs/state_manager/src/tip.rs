@@ -1,18 +1,21 @@
 use crate::{
-    CheckpointError, CheckpointRef, PageMapType, StateManagerMetrics, NUMBER_OF_CHECKPOINT_THREADS,
+    gc, overlay_checkpoints, CheckpointError, CheckpointRef, PageMapType, StateManagerMetrics,
+    NUMBER_OF_CHECKPOINT_THREADS,
 };
 use crossbeam_channel::{unbounded, Sender};
+use ic_config::{flag_status::FlagStatus, state_manager::StateLayoutGcConfig};
 use ic_logger::{fatal, ReplicaLogger};
 #[allow(unused)]
 use ic_replicated_state::{
-    canister_state::execution_state::SandboxMemory, BitcoinState, CanisterState, NumWasmPages,
-    PageMap, ReplicatedState,
+    canister_state::execution_state::SandboxMemory, BitcoinState, CanisterSnapshot, CanisterState,
+    NumWasmPages, PageMap, ReplicatedState, SnapshotId,
 };
 use ic_state_layout::{
-    error::LayoutError, BitcoinStateBits, BitcoinStateLayout, CanisterStateBits, CheckpointLayout,
-    ExecutionStateBits, ReadOnly, RwPolicy, StateLayout, TipHandler,
+    error::LayoutError, list_overlays, overlay_path, BitcoinStateBits, BitcoinStateLayout,
+    CanisterSnapshotBits, CanisterStateBits, CheckpointLayout, ExecutionStateBits,
+    PageMapFilesCopy, ReadOnly, RwPolicy, StateLayout, TipHandler,
 };
-use ic_types::{CanisterId, Height};
+use ic_types::{CanisterId, ExecutionRound, Height};
 use ic_utils::fs::defrag_file_partially;
 use ic_utils::thread::parallel_map;
 use ic_utils::thread::JoinOnDrop;
@@ -23,6 +26,7 @@ use rand_chacha::ChaChaRng;
 use std::collections::BTreeSet;
 use std::os::unix::prelude::MetadataExt;
 use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
 const DEFRAG_SIZE: u64 = 1 << 29; // 500 MB
 const DEFRAG_SAMPLE: usize = 100;
@@ -33,6 +37,7 @@ pub enum TipRequest {
     /// Return the created checkpoint or error into the sender.
     TipToCheckpoint {
         height: Height,
+        overlay_checkpoints: FlagStatus,
         sender: Sender<Result<(CheckpointRef, CheckpointLayout<ReadOnly>), LayoutError>>,
     },
     /// Filter canisters in tip. Remove ones not present in the set.
@@ -44,6 +49,7 @@ pub enum TipRequest {
     TruncatePageMapsPath {
         height: Height,
         page_map_type: PageMapType,
+        overlay_checkpoints: FlagStatus,
     },
     /// Flush PageMaps's round delta on disc.
     FlushRoundDelta {
@@ -51,9 +57,29 @@ pub enum TipRequest {
         page_map: PageMap,
         page_map_type: PageMapType,
     },
+    /// Copy the memory files of a canister into the directory of a newly
+    /// taken snapshot of it.
+    BackupCanisterSnapshot {
+        height: Height,
+        canister_id: CanisterId,
+        snapshot_id: SnapshotId,
+    },
+    /// Replace the memory files of a canister by the ones of the snapshot it
+    /// was restored from.
+    RestoreCanisterSnapshot {
+        height: Height,
+        canister_id: CanisterId,
+        snapshot_id: SnapshotId,
+    },
+    /// Delete the directory of a snapshot.
+    DeleteCanisterSnapshot {
+        height: Height,
+        snapshot_id: SnapshotId,
+    },
     /// Reset tip folder to the checkpoint with given height.
     ResetTipTo {
         checkpoint_ref: CheckpointRef,
+        overlay_checkpoints: FlagStatus,
     },
     /// Serialize the data from ReplicatedState to the tip folder.
     SerializeToTip {
@@ -64,6 +90,17 @@ pub enum TipRequest {
     DefragTip {
         height: Height,
         page_map_types: Vec<PageMapType>,
+        overlay_checkpoints: FlagStatus,
+    },
+    /// Merge the overlay files of the given page maps into their base files.
+    MergeOverlays {
+        height: Height,
+        page_map_types: Vec<PageMapType>,
+    },
+    /// Collect the garbage in the state layout, unless the previous
+    /// collection happened less than `config.interval_secs` ago.
+    CollectGarbage {
+        config: StateLayoutGcConfig,
     },
     Wait {
         sender: Sender<()>,
@@ -105,6 +142,7 @@ pub fn spawn_tip_thread(
         std::thread::Builder::new()
             .name("TipThread".to_string())
             .spawn(move || {
+                let mut last_gc: Option<Instant> = None;
                 while let Ok(req) = tip_receiver.recv() {
                     match req {
                         TipRequest::FilterTipCanisters { height, ids } => {
@@ -120,7 +158,11 @@ pub fn spawn_tip_thread(
                                     )
                                 });
                         }
-                        TipRequest::TipToCheckpoint { height, sender } => {
+                        TipRequest::TipToCheckpoint {
+                            height,
+                            overlay_checkpoints,
+                            sender,
+                        } => {
                             let _timer =
                                 request_timer(&metrics, "tip_to_checkpoint_send_checkpoint");
                             let tip = tip_handler.tip(height);
@@ -163,7 +205,12 @@ pub fn spawn_tip_thread(
                             std::mem::drop(_timer);
                             let _timer = request_timer(&metrics, "tip_to_checkpoint_reset_tip_to");
                             tip_handler
-                                .reset_tip_to(&state_layout, height, Some(&mut thread_pool))
+                                .reset_tip_to(
+                                    &state_layout,
+                                    height,
+                                    page_map_files(overlay_checkpoints),
+                                    Some(&mut thread_pool),
+                                )
                                 .unwrap_or_else(|err| {
                                     fatal!(
                                         log,
@@ -176,11 +223,12 @@ pub fn spawn_tip_thread(
                         TipRequest::TruncatePageMapsPath {
                             height,
                             page_map_type,
+                            overlay_checkpoints,
                         } => {
                             let _timer = request_timer(&metrics, "truncate_page_maps_path");
                             let path =
                                 page_map_path(&log, &mut tip_handler, height, &page_map_type);
-                            truncate_path(&log, &path);
+                            wipe_page_map(&log, &path, overlay_checkpoints);
                         }
 
                         TipRequest::FlushRoundDelta {
@@ -197,6 +245,67 @@ pub fn spawn_tip_thread(
                                 });
                             }
                         }
+                        TipRequest::BackupCanisterSnapshot {
+                            height,
+                            canister_id,
+                            snapshot_id,
+                        } => {
+                            let _timer = request_timer(&metrics, "backup_canister_snapshot");
+                            backup_canister_snapshot(
+                                &log,
+                                &mut tip_handler,
+                                height,
+                                canister_id,
+                                snapshot_id,
+                            )
+                            .unwrap_or_else(|err| {
+                                fatal!(
+                                    log,
+                                    "Failed to backup canister {} to snapshot {}: {}",
+                                    canister_id,
+                                    snapshot_id,
+                                    err
+                                );
+                            });
+                        }
+                        TipRequest::RestoreCanisterSnapshot {
+                            height,
+                            canister_id,
+                            snapshot_id,
+                        } => {
+                            let _timer = request_timer(&metrics, "restore_canister_snapshot");
+                            restore_canister_snapshot(
+                                &log,
+                                &mut tip_handler,
+                                height,
+                                canister_id,
+                                snapshot_id,
+                            )
+                            .unwrap_or_else(|err| {
+                                fatal!(
+                                    log,
+                                    "Failed to restore canister {} from snapshot {}: {}",
+                                    canister_id,
+                                    snapshot_id,
+                                    err
+                                );
+                            });
+                        }
+                        TipRequest::DeleteCanisterSnapshot {
+                            height,
+                            snapshot_id,
+                        } => {
+                            let _timer = request_timer(&metrics, "delete_canister_snapshot");
+                            delete_canister_snapshot(&mut tip_handler, height, snapshot_id)
+                                .unwrap_or_else(|err| {
+                                    fatal!(
+                                        log,
+                                        "Failed to delete snapshot {}: {}",
+                                        snapshot_id,
+                                        err
+                                    );
+                                });
+                        }
                         TipRequest::SerializeToTip {
                             height,
                             replicated_state,
@@ -205,6 +314,7 @@ pub fn spawn_tip_thread(
                             serialize_to_tip(
                                 &log,
                                 &replicated_state,
+                                overlay_checkpoints(&replicated_state),
                                 &tip_handler.tip(height).unwrap_or_else(|err| {
                                     fatal!(
                                         log,
@@ -219,12 +329,16 @@ pub fn spawn_tip_thread(
                                 fatal!(log, "Failed to serialize to tip @{}: {}", height, err);
                             });
                         }
-                        TipRequest::ResetTipTo { checkpoint_ref } => {
+                        TipRequest::ResetTipTo {
+                            checkpoint_ref,
+                            overlay_checkpoints,
+                        } => {
                             let _timer = request_timer(&metrics, "reset_tip_to");
                             tip_handler
                                 .reset_tip_to(
                                     &state_layout,
                                     checkpoint_ref.0.height,
+                                    page_map_files(overlay_checkpoints),
                                     Some(&mut thread_pool),
                                 )
                                 .unwrap_or_else(|err| {
@@ -239,7 +353,12 @@ pub fn spawn_tip_thread(
                         TipRequest::DefragTip {
                             height,
                             page_map_types,
+                            overlay_checkpoints,
                         } => {
+                            // Page map files shared with checkpoints must not be rewritten.
+                            if overlay_checkpoints == FlagStatus::Enabled {
+                                continue;
+                            }
                             let _timer = request_timer(&metrics, "defrag_tip");
                             defrag_tip(
                                 &tip_handler.tip(height).unwrap_or_else(|err| {
@@ -254,6 +373,48 @@ pub fn spawn_tip_thread(
                                 fatal!(log, "Failed to defrag tip @{}: {}", height, err);
                             });
                         }
+                        TipRequest::MergeOverlays {
+                            height,
+                            page_map_types,
+                        } => {
+                            let _timer = request_timer(&metrics, "merge_overlays");
+                            let tip = tip_handler.tip(height).unwrap_or_else(|err| {
+                                fatal!(
+                                    log,
+                                    "Failed to get tip @{} to merge overlays: {}",
+                                    height,
+                                    err
+                                );
+                            });
+                            let results =
+                                parallel_map(&mut thread_pool, page_map_types.iter(), |entry| {
+                                    merge_overlays(&log, &tip, entry)
+                                });
+                            for result in results.into_iter() {
+                                result.unwrap_or_else(|err| {
+                                    fatal!(
+                                        log,
+                                        "Failed to merge overlays in tip @{}: {}",
+                                        height,
+                                        err
+                                    );
+                                });
+                            }
+                        }
+                        TipRequest::CollectGarbage { config } => {
+                            let interval = Duration::from_secs(config.interval_secs);
+                            if last_gc.map_or(false, |t| t.elapsed() < interval) {
+                                continue;
+                            }
+                            let _timer = request_timer(&metrics, "collect_garbage");
+                            last_gc = Some(Instant::now());
+                            gc::collect_garbage(
+                                &log,
+                                &state_layout,
+                                &config,
+                                &metrics.state_layout_gc_metrics,
+                            );
+                        }
 
                         TipRequest::Wait { sender } => {
                             let _timer = request_timer(&metrics, "wait");
@@ -267,9 +428,19 @@ pub fn spawn_tip_thread(
     (tip_handle, tip_sender)
 }
 
+/// With overlay checkpoints, page map files are never modified in place, so
+/// the tip can share them with the checkpoints.
+fn page_map_files(overlay_checkpoints: FlagStatus) -> PageMapFilesCopy {
+    match overlay_checkpoints {
+        FlagStatus::Enabled => PageMapFilesCopy::HardLink,
+        FlagStatus::Disabled => PageMapFilesCopy::Copy,
+    }
+}
+
 fn serialize_to_tip(
     log: &ReplicaLogger,
     state: &ReplicatedState,
+    overlay_checkpoints: FlagStatus,
     tip: &CheckpointLayout<RwPolicy>,
     thread_pool: &mut scoped_threadpool::Pool,
 ) -> Result<(), CheckpointError> {
@@ -280,21 +451,284 @@ fn serialize_to_tip(
         .serialize((state.subnet_queues()).into())?;
 
     let results = parallel_map(thread_pool, state.canisters_iter(), |canister_state| {
-        serialize_canister_to_tip(log, canister_state, tip)
+        serialize_canister_to_tip(log, canister_state, overlay_checkpoints, tip)
     });
 
     for result in results.into_iter() {
         result?;
     }
 
-    serialize_bitcoin_state_to_tip(state.bitcoin(), &tip.bitcoin()?)?;
+    let results = parallel_map(
+        thread_pool,
+        state.canister_snapshots.iter(),
+        |(snapshot_id, snapshot)| {
+            serialize_snapshot_to_tip(log, snapshot_id, snapshot, overlay_checkpoints, tip)
+        },
+    );
+
+    for result in results.into_iter() {
+        result?;
+    }
+
+    serialize_bitcoin_state_to_tip(
+        state.bitcoin(),
+        overlay_checkpoints,
+        tip.height(),
+        &tip.bitcoin()?,
+    )?;
 
     Ok(())
 }
 
+fn serialize_snapshot_to_tip(
+    log: &ReplicaLogger,
+    snapshot_id: &SnapshotId,
+    snapshot: &CanisterSnapshot,
+    overlay_checkpoints: FlagStatus,
+    tip: &CheckpointLayout<RwPolicy>,
+) -> Result<(), CheckpointError> {
+    let snapshot_layout = tip.snapshot(snapshot_id)?;
+    let execution_snapshot = snapshot.execution_snapshot();
+
+    let wasm_binary = &execution_snapshot.wasm_binary.binary;
+    let wasm = snapshot_layout.wasm();
+    if !wasm.raw_path().exists() {
+        match wasm_binary.file() {
+            Some(path) => {
+                ic_state_layout::utils::do_copy(log, path, wasm.raw_path()).map_err(|io_err| {
+                    CheckpointError::IoError {
+                        path: path.to_path_buf(),
+                        message: "failed to copy Wasm file".to_string(),
+                        io_err: io_err.to_string(),
+                    }
+                })?;
+            }
+            None => wasm.serialize(wasm_binary)?,
+        }
+    }
+
+    persist_page_map(
+        &execution_snapshot.wasm_memory.page_map,
+        &snapshot_layout.vmemory_0(),
+        overlay_checkpoints,
+        tip.height(),
+    )?;
+    persist_page_map(
+        &execution_snapshot.stable_memory.page_map,
+        &snapshot_layout.stable_memory_blob(),
+        overlay_checkpoints,
+        tip.height(),
+    )?;
+
+    snapshot_layout
+        .snapshot()
+        .serialize(
+            CanisterSnapshotBits {
+                canister_id: snapshot.canister_id(),
+                taken_at_timestamp: *snapshot.taken_at_timestamp(),
+                canister_version: snapshot.canister_version(),
+                certified_data: snapshot.certified_data().clone(),
+                execution_state_bits: ExecutionStateBits {
+                    exported_globals: execution_snapshot.exported_globals.clone(),
+                    heap_size: execution_snapshot.wasm_memory.size,
+                    exports: execution_snapshot.exports.clone(),
+                    last_executed_round: ExecutionRound::from(0),
+                    metadata: execution_snapshot.metadata.clone(),
+                    binary_hash: Some(wasm_binary.module_hash().into()),
+                },
+                stable_memory_size: execution_snapshot.stable_memory.size,
+            }
+            .into(),
+        )
+        .map_err(CheckpointError::from)
+}
+
+/// Copies the memory files of the canister in the tip into the directory of
+/// the snapshot. Any deltas of the snapshot's page maps not yet flushed are
+/// applied on top of these files afterwards.
+fn backup_canister_snapshot(
+    log: &ReplicaLogger,
+    tip_handler: &mut TipHandler,
+    height: Height,
+    canister_id: CanisterId,
+    snapshot_id: SnapshotId,
+) -> Result<(), CheckpointError> {
+    let tip = tip_handler.tip(height)?;
+    let canister_layout = tip.canister(&canister_id)?;
+    let snapshot_layout = tip.snapshot(&snapshot_id)?;
+    for (src, dst) in [
+        (canister_layout.vmemory_0(), snapshot_layout.vmemory_0()),
+        (
+            canister_layout.stable_memory_blob(),
+            snapshot_layout.stable_memory_blob(),
+        ),
+    ] {
+        copy_page_map_file(log, &src, &dst)?;
+    }
+    Ok(())
+}
+
+/// Replaces the memory files of the canister in the tip by the ones of the
+/// snapshot. The Wasm file of the canister is removed so that the snapshot's
+/// module gets written at the next checkpoint.
+fn restore_canister_snapshot(
+    log: &ReplicaLogger,
+    tip_handler: &mut TipHandler,
+    height: Height,
+    canister_id: CanisterId,
+    snapshot_id: SnapshotId,
+) -> Result<(), CheckpointError> {
+    let tip = tip_handler.tip(height)?;
+    let canister_layout = tip.canister(&canister_id)?;
+    let snapshot_layout = tip.snapshot(&snapshot_id)?;
+    for (src, dst) in [
+        (snapshot_layout.vmemory_0(), canister_layout.vmemory_0()),
+        (
+            snapshot_layout.stable_memory_blob(),
+            canister_layout.stable_memory_blob(),
+        ),
+    ] {
+        copy_page_map_file(log, &src, &dst)?;
+    }
+    let wasm = canister_layout.wasm();
+    remove_file_if_exists(wasm.raw_path())
+}
+
+fn delete_canister_snapshot(
+    tip_handler: &mut TipHandler,
+    height: Height,
+    snapshot_id: SnapshotId,
+) -> Result<(), CheckpointError> {
+    let path = tip_handler.tip(height)?.snapshot(&snapshot_id)?.raw_path();
+    if path.exists() {
+        std::fs::remove_dir_all(&path).map_err(|err| CheckpointError::IoError {
+            path,
+            message: "failed to remove snapshot directory".to_string(),
+            io_err: err.to_string(),
+        })?;
+    }
+    Ok(())
+}
+
+/// Replaces `dst` by a copy of `src`, including the overlay files of `src`.
+/// A missing `src` means that the page map has no pages on disk yet, so `dst`
+/// is removed as well.
+fn copy_page_map_file(log: &ReplicaLogger, src: &Path, dst: &Path) -> Result<(), CheckpointError> {
+    remove_page_map_files(dst)?;
+    if src.exists() {
+        copy_file(log, src, dst)?;
+    }
+    for (height, overlay) in list_overlays(src)? {
+        copy_file(log, &overlay, &overlay_path(dst, height))?;
+    }
+    Ok(())
+}
+
+fn copy_file(log: &ReplicaLogger, src: &Path, dst: &Path) -> Result<(), CheckpointError> {
+    ic_state_layout::utils::do_copy(log, src, dst).map_err(|io_err| CheckpointError::IoError {
+        path: src.to_path_buf(),
+        message: "failed to copy page map file".to_string(),
+        io_err: io_err.to_string(),
+    })
+}
+
+/// Removes the base file of a page map together with its overlay files.
+fn remove_page_map_files(base_file: &Path) -> Result<(), CheckpointError> {
+    remove_file_if_exists(base_file)?;
+    for (_, overlay) in list_overlays(base_file)? {
+        remove_file_if_exists(&overlay)?;
+    }
+    Ok(())
+}
+
+/// Persists the page delta of `page_map` to the page map files in the tip
+/// whose base file is `base_file`. With overlay checkpoints, the delta is
+/// written to a new overlay file for the checkpoint at `height` and the base
+/// file is left untouched.
+fn persist_page_map(
+    page_map: &PageMap,
+    base_file: &Path,
+    overlay_checkpoints: FlagStatus,
+    height: Height,
+) -> Result<(), CheckpointError> {
+    match overlay_checkpoints {
+        FlagStatus::Disabled => page_map.persist_delta(base_file)?,
+        FlagStatus::Enabled => {
+            // The base file must exist for the page map to be loaded.
+            if !base_file.exists() {
+                std::fs::File::create(base_file).map_err(|err| CheckpointError::IoError {
+                    path: base_file.to_path_buf(),
+                    message: "failed to create page map file".to_string(),
+                    io_err: err.to_string(),
+                })?;
+            }
+            if !page_map.page_delta_is_empty() {
+                page_map.persist_overlay(&overlay_path(base_file, height))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Merges the overlay files of the given page map in the tip into its base
+/// file. The base file may be shared with checkpoints, so the merged contents
+/// are written to a copy that replaces the base file afterwards.
+fn merge_overlays(
+    log: &ReplicaLogger,
+    tip: &CheckpointLayout<RwPolicy>,
+    page_map_type: &PageMapType,
+) -> Result<(), CheckpointError> {
+    let base_file = page_map_type.path(tip)?;
+    let overlays: Vec<PathBuf> = list_overlays(&base_file)?
+        .into_iter()
+        .map(|(_, path)| path)
+        .collect();
+    if overlays.is_empty() || !base_file.exists() {
+        return Ok(());
+    }
+    let page_map = PageMap::open_with_overlays(&base_file, &overlays, tip.height())?;
+
+    let merged_file = base_file.with_extension("merged");
+    remove_file_if_exists(&merged_file)?;
+    copy_file(log, &base_file, &merged_file)?;
+    let io_error = |message: &str, err: std::io::Error| CheckpointError::IoError {
+        path: merged_file.clone(),
+        message: message.to_string(),
+        io_err: err.to_string(),
+    };
+    let mut permissions = merged_file
+        .metadata()
+        .map_err(|err| io_error("failed to get metadata", err))?
+        .permissions();
+    permissions.set_readonly(false);
+    std::fs::set_permissions(&merged_file, permissions)
+        .map_err(|err| io_error("failed to make file writable", err))?;
+    page_map.persist_overlays(&merged_file)?;
+    std::fs::rename(&merged_file, &base_file)
+        .map_err(|err| io_error("failed to replace base file", err))?;
+
+    for overlay in overlays.iter() {
+        remove_file_if_exists(overlay)?;
+    }
+    Ok(())
+}
+
+fn remove_file_if_exists(path: &Path) -> Result<(), CheckpointError> {
+    match std::fs::remove_file(path) {
+        Ok(()) => Ok(()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(err) => Err(CheckpointError::IoError {
+            path: path.to_path_buf(),
+            message: "failed to remove file".to_string(),
+            io_err: err.to_string(),
+        }),
+    }
+}
+
 fn serialize_canister_to_tip(
     log: &ReplicaLogger,
     canister_state: &CanisterState,
+    overlay_checkpoints: FlagStatus,
     tip: &CheckpointLayout<RwPolicy>,
 ) -> Result<(), CheckpointError> {
     let canister_layout = tip.canister(&canister_state.canister_id())?;
@@ -325,14 +759,18 @@ fn serialize_canister_to_tip(
                         .serialize(&execution_state.wasm_binary.binary)?;
                 }
             }
-            execution_state
-                .wasm_memory
-                .page_map
-                .persist_delta(&canister_layout.vmemory_0())?;
-            execution_state
-                .stable_memory
-                .page_map
-                .persist_delta(&canister_layout.stable_memory_blob())?;
+            persist_page_map(
+                &execution_state.wasm_memory.page_map,
+                &canister_layout.vmemory_0(),
+                overlay_checkpoints,
+                tip.height(),
+            )?;
+            persist_page_map(
+                &execution_state.stable_memory.page_map,
+                &canister_layout.stable_memory_blob(),
+                overlay_checkpoints,
+                tip.height(),
+            )?;
 
             Some(ExecutionStateBits {
                 exported_globals: execution_state.exported_globals.clone(),
@@ -344,11 +782,22 @@ fn serialize_canister_to_tip(
             })
         }
         None => {
-            truncate_path(log, &canister_layout.vmemory_0());
-            truncate_path(log, &canister_layout.stable_memory_blob());
+            wipe_page_map(log, &canister_layout.vmemory_0(), overlay_checkpoints);
+            wipe_page_map(
+                log,
+                &canister_layout.stable_memory_blob(),
+                overlay_checkpoints,
+            );
             None
         }
     };
+    persist_page_map(
+        canister_state.system_state.wasm_chunk_store.page_map(),
+        &canister_layout.wasm_chunk_store(),
+        overlay_checkpoints,
+        tip.height(),
+    )?;
+
     // Priority credit must be zero at this point
     assert_eq!(canister_state.scheduler_state.priority_credit.get(), 0);
     canister_layout
@@ -406,6 +855,20 @@ fn serialize_canister_to_tip(
                     .global_timer
                     .to_nanos_since_unix_epoch(),
                 canister_version: canister_state.system_state.canister_version,
+                next_snapshot_id: canister_state.system_state.next_snapshot_id,
+                snapshots_memory_usage: canister_state.system_state.snapshots_memory_usage,
+                canister_history: canister_state.system_state.get_canister_history().clone(),
+                log_visibility: canister_state.system_state.log_visibility,
+                canister_log: canister_state.system_state.canister_log.clone(),
+                wasm_memory_limit: canister_state.system_state.wasm_memory_limit,
+                reserved_balance: canister_state.system_state.reserved_balance(),
+                reserved_balance_limit: canister_state.system_state.reserved_balance_limit(),
+                wasm_chunk_store_metadata: canister_state
+                    .system_state
+                    .wasm_chunk_store
+                    .metadata()
+                    .clone(),
+                total_query_stats: canister_state.scheduler_state.total_query_stats.clone(),
             }
             .into(),
         )
@@ -414,22 +877,30 @@ fn serialize_canister_to_tip(
 
 fn serialize_bitcoin_state_to_tip(
     state: &BitcoinState,
+    overlay_checkpoints: FlagStatus,
+    height: Height,
     layout: &BitcoinStateLayout<RwPolicy>,
 ) -> Result<(), CheckpointError> {
-    state
-        .utxo_set
-        .utxos_small
-        .persist_delta(&layout.utxos_small())?;
+    persist_page_map(
+        &state.utxo_set.utxos_small,
+        &layout.utxos_small(),
+        overlay_checkpoints,
+        height,
+    )?;
 
-    state
-        .utxo_set
-        .utxos_medium
-        .persist_delta(&layout.utxos_medium())?;
+    persist_page_map(
+        &state.utxo_set.utxos_medium,
+        &layout.utxos_medium(),
+        overlay_checkpoints,
+        height,
+    )?;
 
-    state
-        .utxo_set
-        .address_outpoints
-        .persist_delta(&layout.address_outpoints())?;
+    persist_page_map(
+        &state.utxo_set.address_outpoints,
+        &layout.address_outpoints(),
+        overlay_checkpoints,
+        height,
+    )?;
 
     layout
         .bitcoin_state()
@@ -512,6 +983,23 @@ pub fn defrag_tip(
     Ok(())
 }
 
+/// Wipes the contents of the page map stored in `base_file`. With overlay
+/// checkpoints the base file may be shared with checkpoints, so the page map
+/// files are removed instead of truncated.
+fn wipe_page_map(log: &ReplicaLogger, base_file: &Path, overlay_checkpoints: FlagStatus) {
+    match overlay_checkpoints {
+        FlagStatus::Disabled => truncate_path(log, base_file),
+        FlagStatus::Enabled => remove_page_map_files(base_file).unwrap_or_else(|err| {
+            fatal!(
+                log,
+                "failed to remove page map stored at {}: {}",
+                base_file.display(),
+                err
+            )
+        }),
+    }
+}
+
 fn truncate_path(log: &ReplicaLogger, path: &Path) {
     if let Err(err) = nix::unistd::truncate(path, 0) {
         // It's OK if the file doesn't exist, everything else is a fatal error.
@@ -14,7 +14,7 @@ use ic_replicated_state::{
     ReplicatedState, Stream,
 };
 use ic_state_manager::{BitcoinPageMap, DirtyPageMap, FileType, PageMapType, StateManagerImpl};
-use ic_sys::PAGE_SIZE;
+use ic_sys::{PageBytes, PAGE_SIZE};
 use ic_test_utilities::{
     consensus::fake::FakeVerifier,
     mock_time,
@@ -33,12 +33,12 @@ use ic_types::{
     crypto::CryptoHash,
     ingress::{IngressState, IngressStatus, WasmResult},
     messages::CallbackId,
-    state_sync::FILE_GROUP_CHUNK_ID_OFFSET,
+    state_sync::{FileInfo, Manifest, FILE_GROUP_CHUNK_ID_OFFSET},
     xnet::{StreamIndex, StreamIndexedQueue},
     CanisterId, CryptoHashOfPartialState, CryptoHashOfState, Height, PrincipalId,
 };
 use proptest::prelude::*;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::{
     collections::HashSet,
@@ -1729,6 +1729,120 @@ fn can_do_simple_state_sync_transfer() {
     })
 }
 
+#[test]
+fn can_do_compressed_state_sync_transfer_once_enabled_for_subnet() {
+    use ic_state_manager::manifest::STATE_SYNC_V2;
+
+    state_manager_test(|src_metrics, src_state_manager| {
+        let (_height, mut state) = src_state_manager.take_tip();
+        insert_dummy_canister(&mut state, canister_test_id(100));
+        state.metadata.own_subnet_features.compressed_state_sync = true;
+
+        src_state_manager.commit_and_certify(state, height(1), CertificationScope::Full);
+        let hash = wait_for_checkpoint(&src_state_manager, height(1));
+        let id = StateSyncArtifactId {
+            height: height(1),
+            hash,
+        };
+
+        let state = src_state_manager.get_latest_state().take();
+
+        let msg = src_state_manager
+            .get_validated_by_identifier(&id)
+            .expect("failed to get state sync messages");
+        assert_eq!(msg.manifest.version, STATE_SYNC_V2);
+
+        assert_error_counters(src_metrics);
+
+        state_manager_test(|dst_metrics, dst_state_manager| {
+            let chunkable = dst_state_manager.create_chunkable_state(&id);
+
+            let dst_msg = pipe_state_sync(msg, chunkable);
+            dst_state_manager
+                .check_artifact_acceptance(dst_msg, &node_test_id(0))
+                .expect("Failed to process state sync artifact");
+
+            let recovered_state = dst_state_manager
+                .get_state_at(height(1))
+                .expect("Destination state manager didn't receive the state")
+                .take();
+
+            assert_eq!(height(1), dst_state_manager.latest_state_height());
+            assert_eq!(state, recovered_state);
+            assert_error_counters(dst_metrics);
+        })
+    })
+}
+
+#[test]
+fn can_do_state_sync_transfer_of_overlay_checkpoints() {
+    let canister_id = canister_test_id(100);
+    state_manager_test(|src_metrics, src_state_manager| {
+        for h in 1..=2 {
+            let (_height, mut state) = src_state_manager.take_tip();
+            if h == 1 {
+                state.metadata.own_subnet_features.overlay_checkpoints = true;
+                insert_dummy_canister(&mut state, canister_id);
+            }
+            write_wasm_page(&mut state, canister_id, h, h as u8);
+            src_state_manager.commit_and_certify(state, height(h), CertificationScope::Full);
+        }
+        let hash = wait_for_checkpoint(&src_state_manager, height(2));
+        let id = StateSyncArtifactId {
+            height: height(2),
+            hash,
+        };
+
+        let state = src_state_manager.get_latest_state().take();
+
+        let msg = src_state_manager
+            .get_validated_by_identifier(&id)
+            .expect("failed to get state sync messages");
+        for file_name in [
+            "vmemory_0_0000000000000001.overlay",
+            "vmemory_0_0000000000000002.overlay",
+        ] {
+            assert!(manifest_file(&msg.manifest, file_name).is_some());
+        }
+
+        assert_error_counters(src_metrics);
+
+        state_manager_test(|dst_metrics, dst_state_manager| {
+            let chunkable = dst_state_manager.create_chunkable_state(&id);
+
+            let dst_msg = pipe_state_sync(msg, chunkable);
+            dst_state_manager
+                .check_artifact_acceptance(dst_msg, &node_test_id(0))
+                .expect("Failed to process state sync artifact");
+
+            let recovered_state = dst_state_manager
+                .get_state_at(height(2))
+                .expect("Destination state manager didn't receive the state")
+                .take();
+
+            assert_eq!(height(2), dst_state_manager.latest_state_height());
+            assert_eq!(state, recovered_state);
+            assert_eq!(wasm_page(&recovered_state, canister_id, 1), [1; PAGE_SIZE]);
+            assert_eq!(wasm_page(&recovered_state, canister_id, 2), [2; PAGE_SIZE]);
+
+            // The synced checkpoint can be the base of further overlay checkpoints.
+            let (_height, mut tip) = dst_state_manager.take_tip();
+            write_wasm_page(&mut tip, canister_id, 3, 3);
+            dst_state_manager.commit_and_certify(tip, height(3), CertificationScope::Full);
+            wait_for_checkpoint(&dst_state_manager, height(3));
+
+            let state = dst_state_manager.get_latest_state().take();
+            for page in 1..=3 {
+                assert_eq!(
+                    wasm_page(&state, canister_id, page),
+                    [page as u8; PAGE_SIZE]
+                );
+            }
+            assert_error_counters(dst_metrics);
+        })
+    })
+}
+
 #[test]
 fn can_state_sync_from_cache() {
     state_manager_test(|src_metrics, src_state_manager| {
@@ -2697,6 +2811,274 @@ fn can_reuse_chunk_hashes_when_computing_manifest() {
     });
 }
 
+/// Overwrites the wasm memory page `page` of the given canister with `byte`.
+fn write_wasm_page(state: &mut ReplicatedState, canister_id: CanisterId, page: u64, byte: u8) {
+    state
+        .canister_state_mut(&canister_id)
+        .unwrap()
+        .execution_state
+        .as_mut()
+        .unwrap()
+        .wasm_memory
+        .page_map
+        .update(&[(PageIndex::new(page), &[byte; PAGE_SIZE])]);
+}
+
+fn wasm_page(state: &ReplicatedState, canister_id: CanisterId, page: u64) -> PageBytes {
+    *state
+        .canister_state(&canister_id)
+        .unwrap()
+        .execution_state
+        .as_ref()
+        .unwrap()
+        .wasm_memory
+        .page_map
+        .get_page(PageIndex::new(page))
+}
+
+/// Returns the path of the wasm memory base file of the given canister in the
+/// checkpoint at height `h`.
+fn checkpoint_vmemory_0(
+    state_manager: &StateManagerImpl,
+    h: Height,
+    canister_id: CanisterId,
+) -> PathBuf {
+    state_manager
+        .state_layout()
+        .checkpoint(h)
+        .unwrap()
+        .canister(&canister_id)
+        .unwrap()
+        .vmemory_0()
+}
+
+fn compute_manifest_from_scratch(state_manager: &StateManagerImpl, h: Height) -> Manifest {
+    use ic_state_manager::manifest::{
+        compute_manifest, CURRENT_STATE_SYNC_VERSION, DEFAULT_CHUNK_SIZE,
+    };
+    use ic_state_manager::ManifestMetrics;
+
+    let checkpoint_root = state_manager
+        .state_layout()
+        .checkpoint(h)
+        .unwrap()
+        .raw_path()
+        .to_path_buf();
+    compute_manifest(
+        &mut scoped_threadpool::Pool::new(NUM_THREADS),
+        &ManifestMetrics::new(&MetricsRegistry::new()),
+        &no_op_logger(),
+        CURRENT_STATE_SYNC_VERSION,
+        &checkpoint_root,
+        DEFAULT_CHUNK_SIZE,
+        None,
+    )
+    .expect("failed to compute manifest")
+}
+
+fn manifest_file<'a>(manifest: &'a Manifest, file_name: &str) -> Option<&'a FileInfo> {
+    manifest
+        .file_table
+        .iter()
+        .find(|file_info| file_info.relative_path.ends_with(file_name))
+}
+
+#[test]
+fn overlay_checkpoints_share_page_map_files_with_tip() {
+    use ic_state_layout::overlay_path;
+    use std::os::unix::fs::MetadataExt;
+
+    state_manager_test(|metrics, state_manager| {
+        let canister_id = canister_test_id(100);
+        let (_height, mut state) = state_manager.take_tip();
+        state.metadata.own_subnet_features.overlay_checkpoints = true;
+        insert_dummy_canister(&mut state, canister_id);
+        write_wasm_page(&mut state, canister_id, 1, 1);
+        state_manager.commit_and_certify(state, height(1), CertificationScope::Full);
+        wait_for_checkpoint(&state_manager, height(1));
+
+        let base_file = checkpoint_vmemory_0(&state_manager, height(1), canister_id);
+        let overlay = overlay_path(&base_file, height(1));
+        assert!(overlay.exists());
+
+        // The page map files of the tip are hard links to the ones of the checkpoint.
+        let layout = state_manager.state_layout();
+        let checkpoint_root = layout
+            .checkpoint(height(1))
+            .unwrap()
+            .raw_path()
+            .to_path_buf();
+        for file in [&base_file, &overlay] {
+            let tip_file = layout
+                .raw_path()
+                .join("tip")
+                .join(file.strip_prefix(&checkpoint_root).unwrap());
+            assert_eq!(
+                tip_file.metadata().unwrap().ino(),
+                file.metadata().unwrap().ino()
+            );
+        }
+
+        // The next checkpoint adds an overlay and keeps sharing the existing files.
+        let (_height, mut state) = state_manager.take_tip();
+        write_wasm_page(&mut state, canister_id, 2, 2);
+        state_manager.commit_and_certify(state, height(2), CertificationScope::Full);
+        wait_for_checkpoint(&state_manager, height(2));
+
+        let next_base_file = checkpoint_vmemory_0(&state_manager, height(2), canister_id);
+        assert_eq!(
+            next_base_file.metadata().unwrap().ino(),
+            base_file.metadata().unwrap().ino()
+        );
+        assert_eq!(
+            overlay_path(&next_base_file, height(1))
+                .metadata()
+                .unwrap()
+                .ino(),
+            overlay.metadata().unwrap().ino()
+        );
+        assert!(overlay_path(&next_base_file, height(2)).exists());
+
+        let state = state_manager.get_latest_state().take();
+        assert_eq!(wasm_page(&state, canister_id, 1), [1; PAGE_SIZE]);
+        assert_eq!(wasm_page(&state, canister_id, 2), [2; PAGE_SIZE]);
+
+        assert_error_counters(metrics);
+    });
+}
+
+#[test]
+fn overlay_checkpoints_merge_overlays_into_base_file() {
+    use ic_state_layout::{list_overlays, overlay_path};
+    use ic_state_manager::manifest::validate_manifest;
+    use std::os::unix::fs::MetadataExt;
+
+    // The number of overlays after which they are merged into the base file,
+    // see `MAX_OVERLAYS_PER_PAGE_MAP`.
+    const MAX_OVERLAYS: u64 = 8;
+
+    state_manager_test(|metrics, state_manager| {
+        let canister_id = canister_test_id(100);
+        for h in 1..=MAX_OVERLAYS {
+            let (_height, mut state) = state_manager.take_tip();
+            if h == 1 {
+                state.metadata.own_subnet_features.overlay_checkpoints = true;
+                insert_dummy_canister(&mut state, canister_id);
+            }
+            write_wasm_page(&mut state, canister_id, h, h as u8);
+            state_manager.commit_and_certify(state, height(h), CertificationScope::Full);
+            wait_for_checkpoint(&state_manager, height(h));
+        }
+
+        let base_file = checkpoint_vmemory_0(&state_manager, height(MAX_OVERLAYS), canister_id);
+        assert_eq!(
+            list_overlays(&base_file).unwrap().len(),
+            MAX_OVERLAYS as usize
+        );
+        assert_eq!(base_file.metadata().unwrap().len(), 0);
+
+        // The overlays are merged into the base file of the tip after the checkpoint,
+        // so the next checkpoint has a single overlay on top of a new base file.
+        let merge_height = height(MAX_OVERLAYS + 1);
+        let (_height, mut state) = state_manager.take_tip();
+        write_wasm_page(&mut state, canister_id, MAX_OVERLAYS + 1, 0xff);
+        state_manager.commit_and_certify(state, merge_height, CertificationScope::Full);
+        let hash = wait_for_checkpoint(&state_manager, merge_height);
+
+        let merged_file = checkpoint_vmemory_0(&state_manager, merge_height, canister_id);
+        assert_ne!(
+            merged_file.metadata().unwrap().ino(),
+            base_file.metadata().unwrap().ino()
+        );
+        assert_eq!(
+            merged_file.metadata().unwrap().len(),
+            (MAX_OVERLAYS + 1) * PAGE_SIZE as u64
+        );
+        assert_eq!(
+            list_overlays(&merged_file).unwrap(),
+            vec![(merge_height, overlay_path(&merged_file, merge_height))]
+        );
+
+        // The manifest computed incrementally accounts for the merged pages.
+        let manifest = compute_manifest_from_scratch(&state_manager, merge_height);
+        validate_manifest(&manifest, &hash).unwrap();
+
+        let state = state_manager.get_latest_state().take();
+        for page in 1..=MAX_OVERLAYS {
+            assert_eq!(
+                wasm_page(&state, canister_id, page),
+                [page as u8; PAGE_SIZE]
+            );
+        }
+        assert_eq!(
+            wasm_page(&state, canister_id, MAX_OVERLAYS + 1),
+            [0xff; PAGE_SIZE]
+        );
+
+        assert_error_counters(metrics);
+    });
+}
+
+#[test]
+fn incremental_manifest_is_correct_once_overlay_checkpoints_are_enabled() {
+    use ic_state_manager::manifest::validate_manifest;
+
+    state_manager_test(|metrics, state_manager| {
+        let canister_id = canister_test_id(100);
+        let (_height, mut state) = state_manager.take_tip();
+        insert_dummy_canister(&mut state, canister_id);
+        write_wasm_page(&mut state, canister_id, 1, 1);
+        state_manager.commit_and_certify(state, height(1), CertificationScope::Full);
+        wait_for_checkpoint(&state_manager, height(1));
+
+        // Without overlay checkpoints, the round delta is flushed into the base file
+        // of the tip.
+        let (_height, mut state) = state_manager.take_tip();
+        write_wasm_page(&mut state, canister_id, 2, 2);
+        state_manager.commit_and_certify(state, height(2), CertificationScope::Metadata);
+
+        // The subnet enables overlay checkpoints in the middle of the interval.
+        let (_height, mut state) = state_manager.take_tip();
+        state.metadata.own_subnet_features.overlay_checkpoints = true;
+        write_wasm_page(&mut state, canister_id, 3, 3);
+        state_manager.commit_and_certify(state, height(3), CertificationScope::Full);
+        let hash_3 = wait_for_checkpoint(&state_manager, height(3));
+
+        let manifest_3 = compute_manifest_from_scratch(&state_manager, height(3));
+        validate_manifest(&manifest_3, &hash_3).unwrap();
+        assert!(manifest_file(&manifest_3, "vmemory_0_0000000000000003.overlay").is_some());
+
+        // Further checkpoints only add overlays, the base file stays the same.
+        let (_height, mut state) = state_manager.take_tip();
+        write_wasm_page(&mut state, canister_id, 4, 4);
+        state_manager.commit_and_certify(state, height(4), CertificationScope::Full);
+        let hash_4 = wait_for_checkpoint(&state_manager, height(4));
+
+        let manifest_4 = compute_manifest_from_scratch(&state_manager, height(4));
+        validate_manifest(&manifest_4, &hash_4).unwrap();
+        for file_name in [
+            "vmemory_0_0000000000000003.overlay",
+            "vmemory_0_0000000000000004.overlay",
+        ] {
+            assert!(manifest_file(&manifest_4, file_name).is_some());
+        }
+        assert_eq!(
+            manifest_file(&manifest_3, "vmemory_0.bin").unwrap(),
+            manifest_file(&manifest_4, "vmemory_0.bin").unwrap()
+        );
+
+        let state = state_manager.get_latest_state().take();
+        for page in 1..=4 {
+            assert_eq!(
+                wasm_page(&state, canister_id, page),
+                [page as u8; PAGE_SIZE]
+            );
+        }
+
+        assert_error_counters(metrics);
+    });
+}
+
 #[test]
 fn certified_read_can_certify_ingress_history_entry() {
     use LabeledTree::*;
@@ -3031,6 +3413,64 @@ fn remove_too_many_diverged_checkpoints() {
     );
 }
 
+#[test]
+fn diverged_checkpoint_kept_for_forensics_is_reported_but_not_removed() {
+    use ic_state_layout::{
+        gc_report::{GcAction, GcReason, GcReport},
+        KEEP_FOR_FORENSICS_MARKER,
+    };
+
+    fn diverge_at(state_manager: StateManagerImpl, divergence: u64) {
+        for i in 1..divergence {
+            let (_, state) = state_manager.take_tip();
+            state_manager.commit_and_certify(state, height(i), CertificationScope::Full);
+            wait_for_checkpoint(&state_manager, height(i));
+        }
+
+        let (_, state) = state_manager.take_tip();
+        state_manager.commit_and_certify(state, height(divergence), CertificationScope::Full);
+        state_manager.report_diverged_checkpoint(height(divergence))
+    }
+    state_manager_crash_test(
+        vec![
+            Box::new(|state_manager: StateManagerImpl| diverge_at(state_manager, 1)),
+            Box::new(|state_manager: StateManagerImpl| {
+                let marker = state_manager
+                    .state_layout()
+                    .diverged_checkpoint_path(height(1))
+                    .join(KEEP_FOR_FORENSICS_MARKER);
+                std::fs::write(marker, b"").unwrap();
+                diverge_at(state_manager, 2)
+            }),
+            Box::new(|state_manager: StateManagerImpl| diverge_at(state_manager, 3)),
+        ],
+        |metrics, state_manager| {
+            let layout = state_manager.state_layout();
+            assert_eq!(
+                vec![height(1), height(2), height(3)],
+                layout.diverged_checkpoint_heights().unwrap()
+            );
+
+            let report = GcReport::load(&layout.gc_report()).unwrap();
+            assert_eq!(report.entries.len(), 1);
+            assert_eq!(
+                report.entries[0].path,
+                layout.diverged_checkpoint_path(height(1))
+            );
+            assert_eq!(report.entries[0].reason, GcReason::DivergedCheckpoint);
+            assert_eq!(report.entries[0].action, GcAction::KeptForForensics);
+
+            assert_eq!(
+                fetch_int_gauge(
+                    metrics,
+                    "state_manager_state_layout_gc_last_run_timestamp_seconds"
+                ),
+                Some(report.timestamp_secs)
+            );
+        },
+    );
+}
+
 #[test]
 fn remove_too_many_diverged_states() {
     fn diverge_state_at(state_manager: StateManagerImpl, divergence: u64) {
@@ -3302,6 +3742,58 @@ fn can_uninstall_code() {
     });
 }
 
+#[test]
+fn can_split_checkpoint_into_two_subnets() {
+    use ic_registry_routing_table::{CanisterIdRange, CanisterIdRanges};
+    use ic_state_layout::{CheckpointLayout, ReadOnly, StateLayout};
+    use ic_state_manager::split::split_checkpoint;
+
+    state_manager_test(|metrics, state_manager| {
+        let (_height, mut state) = state_manager.take_tip();
+        insert_dummy_canister(&mut state, canister_test_id(1));
+        insert_dummy_canister(&mut state, canister_test_id(2));
+        state_manager.commit_and_certify(state, height(1), CertificationScope::Full);
+
+        let retaining_root = tmpdir("retaining");
+        let receiving_root = tmpdir("receiving");
+        let migrated_ranges = CanisterIdRanges::try_from(vec![CanisterIdRange {
+            start: canister_test_id(2),
+            end: canister_test_id(2),
+        }])
+        .unwrap();
+        let (retaining, receiving) = split_checkpoint(
+            state_manager.state_layout().raw_path().to_path_buf(),
+            SubnetType::Application,
+            &migrated_ranges,
+            subnet_test_id(43),
+            retaining_root.path().to_path_buf(),
+            receiving_root.path().to_path_buf(),
+            no_op_logger(),
+        )
+        .unwrap();
+
+        let canister_ids = |path: &Path| {
+            CheckpointLayout::<ReadOnly>::new(path.to_path_buf(), height(2))
+                .unwrap()
+                .canister_ids()
+                .unwrap()
+        };
+        assert_eq!(retaining.height, height(2));
+        assert_eq!(canister_ids(&retaining.path), vec![canister_test_id(1)]);
+        assert_eq!(receiving.height, height(2));
+        assert_eq!(canister_ids(&receiving.path), vec![canister_test_id(2)]);
+        assert_ne!(retaining.root_hash, receiving.root_hash);
+
+        // Only the split checkpoints remain in the output state roots.
+        for root in [&retaining_root, &receiving_root] {
+            let layout = StateLayout::try_new(no_op_logger(), root.path().to_path_buf()).unwrap();
+            assert_eq!(layout.checkpoint_heights().unwrap(), vec![height(2)]);
+        }
+
+        assert_error_counters(metrics);
+    });
+}
+
 proptest! {
     #[test]
     fn stream_store_encode_decode(stream in arb_stream(0, 10, 0, 10), size_limit in 0..20usize) {
@@ -3,8 +3,11 @@ pub mod cdiff;
 pub mod chash;
 pub mod convert_ids;
 pub mod decode;
+pub mod extract_canister;
 pub mod import_state;
 pub mod list;
 pub mod manifest;
+pub mod splice_canister;
+pub mod split;
 mod utils;
 pub mod verify_manifest;
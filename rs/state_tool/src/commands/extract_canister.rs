@@ -0,0 +1,192 @@
+//! Exports a single canister from a checkpoint for offline inspection.
+
+use ic_replicated_state::{CanisterState, PageMap};
+use ic_state_layout::{CanisterLayout, CheckpointLayout, ReadOnly, RwPolicy};
+use ic_state_manager::checkpoint::load_canister_state;
+use ic_types::{CanisterId, Height};
+use serde_json::json;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// The name of the file holding the JSON summary of an exported canister.
+pub const SUMMARY_FILE: &str = "summary.json";
+
+/// Prints a JSON summary of the canister `canister_id` stored in the
+/// checkpoint rooted at `path`.
+pub fn do_inspect_canister(path: PathBuf, canister_id: String) -> Result<(), String> {
+    let (_, canister_state) = load_canister(&path, &canister_id)?;
+    println!("{}", canister_summary(&canister_state));
+    Ok(())
+}
+
+/// Exports the canister `canister_id` stored in the checkpoint rooted at `path`
+/// into the directory `output`.
+///
+/// The output directory uses the layout of a canister directory within a
+/// checkpoint, so that it can be spliced back with `splice_canister`. Page
+/// maps are written as single files with all overlays applied. In addition,
+/// the directory contains a JSON summary of the canister state.
+pub fn do_extract_canister(
+    path: PathBuf,
+    canister_id: String,
+    output: PathBuf,
+) -> Result<(), String> {
+    if output.exists() {
+        return Err(format!("Output path {} already exists", output.display()));
+    }
+    let (src, canister_state) = load_canister(&path, &canister_id)?;
+    let dst = CanisterLayout::<RwPolicy>::new(output.clone())
+        .map_err(|e| format!("Failed to create output directory: {}", e))?;
+
+    copy_if_exists(src.canister().raw_path(), dst.canister().raw_path())?;
+    copy_if_exists(src.queues().raw_path(), dst.queues().raw_path())?;
+    copy_if_exists(src.wasm().raw_path(), dst.wasm().raw_path())?;
+
+    let execution_state = canister_state.execution_state.as_ref();
+    let page_maps = [
+        (
+            src.vmemory_0(),
+            dst.vmemory_0(),
+            execution_state.map(|e| &e.wasm_memory.page_map),
+        ),
+        (
+            src.stable_memory_blob(),
+            dst.stable_memory_blob(),
+            execution_state.map(|e| &e.stable_memory.page_map),
+        ),
+        (
+            src.wasm_chunk_store(),
+            dst.wasm_chunk_store(),
+            Some(canister_state.system_state.wasm_chunk_store.page_map()),
+        ),
+    ];
+    for (src_file, dst_file, page_map) in page_maps.iter() {
+        if let Some(page_map) = page_map {
+            if src_file.exists() {
+                flatten_page_map(page_map, src_file, dst_file)?;
+            }
+        }
+    }
+
+    let summary_path = output.join(SUMMARY_FILE);
+    fs::write(
+        &summary_path,
+        format!("{:#}\n", canister_summary(&canister_state)),
+    )
+    .map_err(|e| format!("Failed to write {}: {}", summary_path.display(), e))?;
+
+    println!(
+        "Successfully exported canister {} to {}",
+        canister_state.canister_id(),
+        output.display()
+    );
+    Ok(())
+}
+
+/// Parses `canister_id` from its textual representation.
+pub fn parse_canister_id(canister_id: &str) -> Result<CanisterId, String> {
+    CanisterId::from_str(canister_id)
+        .map_err(|e| format!("Failed to parse canister id {}: {}", canister_id, e))
+}
+
+/// Loads the state of the canister `canister_id` from the checkpoint rooted at
+/// `path`.
+fn load_canister(
+    path: &Path,
+    canister_id: &str,
+) -> Result<(CanisterLayout<ReadOnly>, CanisterState), String> {
+    let canister_id = parse_canister_id(canister_id)?;
+    let cp_layout = CheckpointLayout::<ReadOnly>::new(path.to_path_buf(), Height::new(0))
+        .map_err(|e| format!("Failed to create checkpoint layout: {}", e))?;
+    let canister_ids = cp_layout
+        .canister_ids()
+        .map_err(|e| format!("Failed to list canisters: {}", e))?;
+    if !canister_ids.contains(&canister_id) {
+        return Err(format!(
+            "Canister {} not found in checkpoint {}",
+            canister_id,
+            path.display()
+        ));
+    }
+    let canister_layout = cp_layout
+        .canister(&canister_id)
+        .map_err(|e| format!("Failed to create canister layout: {}", e))?;
+    let (canister_state, _) =
+        load_canister_state(&canister_layout, &canister_id, Height::new(0))
+            .map_err(|e| format!("Failed to load canister {}: {}", canister_id, e))?;
+    Ok((canister_layout, canister_state))
+}
+
+/// Copies `src` to `dst` unless `src` does not exist.
+fn copy_if_exists(src: &Path, dst: &Path) -> Result<(), String> {
+    if src.exists() {
+        fs::copy(src, dst).map_err(|e| {
+            format!(
+                "Failed to copy {} -> {}: {}",
+                src.display(),
+                dst.display(),
+                e
+            )
+        })?;
+    }
+    Ok(())
+}
+
+/// Writes the checkpointed contents of `page_map`, whose base file is `src`,
+/// to `dst` as a single file without overlays.
+fn flatten_page_map(page_map: &PageMap, src: &Path, dst: &Path) -> Result<(), String> {
+    copy_if_exists(src, dst)?;
+    let mut permissions = dst
+        .metadata()
+        .map_err(|e| format!("Failed to get metadata of {}: {}", dst.display(), e))?
+        .permissions();
+    permissions.set_readonly(false);
+    fs::set_permissions(dst, permissions)
+        .map_err(|e| format!("Failed to make {} writable: {}", dst.display(), e))?;
+    page_map
+        .persist_overlays(dst)
+        .map_err(|e| format!("Failed to apply overlays to {}: {}", dst.display(), e))
+}
+
+/// Returns a JSON summary of the given canister state.
+fn canister_summary(canister_state: &CanisterState) -> serde_json::Value {
+    let system_state = &canister_state.system_state;
+    let scheduler_state = &canister_state.scheduler_state;
+    let queues = system_state.queues();
+    let execution_state = canister_state.execution_state.as_ref().map(|e| {
+        json!({
+            "wasm_module_hash": hex::encode(e.wasm_binary.binary.module_hash()),
+            "wasm_module_size": e.wasm_binary.binary.len(),
+            "heap_size_wasm_pages": e.wasm_memory.size.get(),
+            "stable_memory_size_wasm_pages": e.stable_memory.size.get(),
+            "exported_globals": e.exported_globals.len(),
+            "last_executed_round": e.last_executed_round.get(),
+        })
+    });
+    json!({
+        "canister_id": canister_state.canister_id().to_string(),
+        "status": system_state.status_string(),
+        "controllers": system_state
+            .controllers
+            .iter()
+            .map(|controller| controller.to_string())
+            .collect::<Vec<_>>(),
+        // Cycles do not fit into a JSON number.
+        "cycles_balance": system_state.balance().get().to_string(),
+        "freeze_threshold_seconds": system_state.freeze_threshold.get(),
+        "memory_allocation_bytes": system_state.memory_allocation.bytes().get(),
+        "compute_allocation_percent": scheduler_state.compute_allocation.as_percent(),
+        "canister_version": system_state.canister_version,
+        "certified_data": hex::encode(&system_state.certified_data),
+        "wasm_chunk_store_bytes": system_state.wasm_chunk_store.memory_usage().get(),
+        "last_full_execution_round": scheduler_state.last_full_execution_round.get(),
+        "queues": {
+            "ingress_messages": queues.ingress_queue_message_count(),
+            "input_messages": queues.input_queues_message_count(),
+            "output_messages": queues.output_queues_message_count(),
+            "memory_usage_bytes": queues.memory_usage(),
+        },
+        "execution_state": execution_state,
+    })
+}
@@ -7,12 +7,24 @@ use ic_state_manager::{
     manifest::{compute_manifest, manifest_hash, DEFAULT_CHUNK_SIZE},
     ManifestMetrics,
 };
-use ic_types::Height;
+use ic_types::{state_sync::Manifest, Height};
 use std::path::PathBuf;
 
 /// Computes the manifest (chunk hashes, file hashes and root hash) of the
 /// checkpoint rooted at `path`.
 pub fn do_compute_manifest(path: PathBuf) -> Result<(), String> {
+    let manifest = compute_checkpoint_manifest(path)?;
+
+    println!("{}", manifest);
+    println!();
+    println!("ROOT HASH: {}", hex::encode(manifest_hash(&manifest)));
+
+    Ok(())
+}
+
+/// Computes the manifest of the checkpoint rooted at `path`, using the state
+/// sync version recorded in its system metadata.
+pub fn compute_checkpoint_manifest(path: PathBuf) -> Result<Manifest, String> {
     let cp_layout = CheckpointLayout::<ReadOnly>::new(path, Height::new(0))
         .map_err(|e| format!("Failed to create checkpoint layout: {}", e))?;
 
@@ -27,7 +39,7 @@ pub fn do_compute_manifest(path: PathBuf) -> Result<(), String> {
         scoped_threadpool::Pool::new(ic_state_manager::NUMBER_OF_CHECKPOINT_THREADS);
     let metrics_registry = MetricsRegistry::new();
     let manifest_metrics = ManifestMetrics::new(&metrics_registry);
-    let manifest = compute_manifest(
+    compute_manifest(
         &mut thread_pool,
         &manifest_metrics,
         &no_op_logger(),
@@ -42,11 +54,5 @@ pub fn do_compute_manifest(path: PathBuf) -> Result<(), String> {
             cp_layout.raw_path().display(),
             e
         )
-    })?;
-
-    println!("{}", manifest);
-    println!();
-    println!("ROOT HASH: {}", hex::encode(manifest_hash(&manifest)));
-
-    Ok(())
+    })
 }
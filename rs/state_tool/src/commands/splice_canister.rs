@@ -0,0 +1,261 @@
+//! Splices an exported canister into a copy of a checkpoint.
+
+use crate::commands::{
+    extract_canister::{parse_canister_id, SUMMARY_FILE},
+    manifest::compute_checkpoint_manifest,
+    utils,
+};
+use ic_state_layout::{CheckpointLayout, ReadOnly, RwPolicy};
+use ic_state_manager::{checkpoint::load_canister_state, manifest::manifest_hash};
+use ic_types::Height;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Copies the checkpoint rooted at `path` to `output` and replaces the
+/// canister `canister_id` in the copy with the canister exported to
+/// `canister_dir` by `extract_canister`. The canister is added if the
+/// checkpoint does not contain it yet.
+///
+/// Only the canister directory is rewritten: the routing table and the other
+/// subnet-level metadata of the checkpoint are left untouched. The resulting
+/// manifest and its root hash are printed.
+pub fn do_splice_canister(
+    path: PathBuf,
+    canister_id: String,
+    canister_dir: PathBuf,
+    output: PathBuf,
+) -> Result<(), String> {
+    let canister_id = parse_canister_id(&canister_id)?;
+    if output.exists() {
+        return Err(format!("Output path {} already exists", output.display()));
+    }
+    if !canister_dir.join("canister.pbuf").exists() {
+        return Err(format!(
+            "{} does not contain an exported canister",
+            canister_dir.display()
+        ));
+    }
+
+    utils::copy_recursively(&path, &output)?;
+
+    let cp_layout = CheckpointLayout::<RwPolicy>::new(output.clone(), Height::new(0))
+        .map_err(|e| format!("Failed to create checkpoint layout: {}", e))?;
+    let canister_root = cp_layout
+        .canister(&canister_id)
+        .map_err(|e| format!("Failed to create canister layout: {}", e))?
+        .raw_path();
+    fs::remove_dir_all(&canister_root)
+        .and_then(|()| fs::create_dir(&canister_root))
+        .map_err(|e| format!("Failed to clear {}: {}", canister_root.display(), e))?;
+    copy_canister_files(&canister_dir, &canister_root)?;
+
+    // Make sure that the spliced canister can be loaded by the replica.
+    let cp_layout = CheckpointLayout::<ReadOnly>::new(output.clone(), Height::new(0))
+        .map_err(|e| format!("Failed to create checkpoint layout: {}", e))?;
+    let canister_layout = cp_layout
+        .canister(&canister_id)
+        .map_err(|e| format!("Failed to create canister layout: {}", e))?;
+    load_canister_state(&canister_layout, &canister_id, Height::new(0))
+        .map_err(|e| format!("Failed to load spliced canister {}: {}", canister_id, e))?;
+
+    let manifest = compute_checkpoint_manifest(output.clone())?;
+    println!("{}", manifest);
+    println!();
+    println!("ROOT HASH: {}", hex::encode(manifest_hash(&manifest)));
+    println!(
+        "Successfully spliced canister {} into {}",
+        canister_id,
+        output.display()
+    );
+    Ok(())
+}
+
+/// Copies the state files of an exported canister from `src` to `dst`,
+/// skipping the summary.
+fn copy_canister_files(src: &Path, dst: &Path) -> Result<(), String> {
+    let entries = src
+        .read_dir()
+        .map_err(|e| format!("Failed to read directory {}: {}", src.display(), e))?;
+    for entry in entries {
+        let entry = entry
+            .map_err(|e| format!("Failed to read entry of directory {}: {}", src.display(), e))?;
+        if entry.file_name() == SUMMARY_FILE {
+            continue;
+        }
+        let dst_file = dst.join(entry.file_name());
+        fs::copy(entry.path(), &dst_file).map_err(|e| {
+            format!(
+                "Failed to copy {} -> {}: {}",
+                entry.path().display(),
+                dst_file.display(),
+                e
+            )
+        })?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::extract_canister::do_extract_canister;
+    use ic_base_types::NumSeconds;
+    use ic_config::state_manager::Config;
+    use ic_interfaces_state_manager::{CertificationScope, StateManager};
+    use ic_metrics::MetricsRegistry;
+    use ic_registry_routing_table::{CanisterIdRange, RoutingTable};
+    use ic_registry_subnet_type::SubnetType;
+    use ic_replicated_state::{
+        canister_state::execution_state::WasmBinary, PageIndex, ReplicatedState,
+    };
+    use ic_state_manager::{checkpoint::load_checkpoint, CheckpointMetrics, StateManagerImpl};
+    use ic_sys::PAGE_SIZE;
+    use ic_test_utilities::{
+        consensus::fake::FakeVerifier,
+        state::{initial_execution_state, new_canister_state},
+        types::ids::{canister_test_id, subnet_test_id, user_test_id},
+    };
+    use ic_types::{CanisterId, Cycles};
+    use ic_wasm_types::CanisterModule;
+    use std::sync::Arc;
+
+    const EMPTY_WASM: &[u8] = &[
+        0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, 0x00, 0x08, 0x04, 0x6e, 0x61, 0x6d, 0x65,
+        0x02, 0x01, 0x00,
+    ];
+
+    fn insert_canister(
+        state: &mut ReplicatedState,
+        canister_id: CanisterId,
+        cycles: u128,
+        memory_byte: u8,
+    ) {
+        let mut canister_state = new_canister_state(
+            canister_id,
+            user_test_id(24).get(),
+            Cycles::new(cycles),
+            NumSeconds::from(100_000),
+        );
+        let mut execution_state = initial_execution_state();
+        execution_state.wasm_binary = WasmBinary::new(CanisterModule::new(EMPTY_WASM.to_vec()));
+        execution_state
+            .wasm_memory
+            .page_map
+            .update(&[(PageIndex::from(0), &[memory_byte; PAGE_SIZE])]);
+        canister_state.execution_state = Some(execution_state);
+        state.put_canister_state(canister_state);
+    }
+
+    /// Commits the state produced by `populate` as a checkpoint at height 1 in
+    /// `root` and returns the path of the checkpoint.
+    fn make_checkpoint(root: &Path, populate: impl FnOnce(&mut ReplicatedState)) -> PathBuf {
+        let state_manager = StateManagerImpl::new(
+            Arc::new(FakeVerifier::new()),
+            subnet_test_id(42),
+            SubnetType::Application,
+            ic_logger::replica_logger::no_op_logger(),
+            &MetricsRegistry::new(),
+            &Config::new(root.to_path_buf()),
+            None,
+            ic_types::malicious_flags::MaliciousFlags::default(),
+        );
+        let (_height, mut state) = state_manager.take_tip();
+        populate(&mut state);
+        state_manager.commit_and_certify(state, Height::new(1), CertificationScope::Full);
+        state_manager
+            .state_layout()
+            .checkpoint(Height::new(1))
+            .unwrap()
+            .raw_path()
+            .to_path_buf()
+    }
+
+    fn load_state(path: &Path) -> ReplicatedState {
+        let layout = CheckpointLayout::<ReadOnly>::new(path.to_path_buf(), Height::new(0)).unwrap();
+        load_checkpoint(
+            &layout,
+            SubnetType::Application,
+            &CheckpointMetrics::new(&MetricsRegistry::new()),
+            None,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn extracted_canister_can_be_spliced_into_another_state() {
+        let tmp = tempfile::tempdir().unwrap();
+        let spliced_canister = canister_test_id(1);
+        let other_canister = canister_test_id(2);
+
+        let src = make_checkpoint(&tmp.path().join("src"), |state| {
+            insert_canister(state, spliced_canister, 1 << 40, 1);
+        });
+        let dst = make_checkpoint(&tmp.path().join("dst"), |state| {
+            insert_canister(state, spliced_canister, 1 << 30, 2);
+            insert_canister(state, other_canister, 1 << 30, 3);
+            let mut routing_table = RoutingTable::new();
+            routing_table
+                .insert(
+                    CanisterIdRange {
+                        start: canister_test_id(0),
+                        end: canister_test_id(0xff),
+                    },
+                    subnet_test_id(42),
+                )
+                .unwrap();
+            state.metadata.network_topology.routing_table = Arc::new(routing_table);
+        });
+
+        let export_dir = tmp.path().join("export");
+        do_extract_canister(
+            src.clone(),
+            spliced_canister.to_string(),
+            export_dir.clone(),
+        )
+        .unwrap();
+        assert!(export_dir.join(SUMMARY_FILE).exists());
+
+        let output = tmp.path().join("spliced");
+        do_splice_canister(
+            dst.clone(),
+            spliced_canister.to_string(),
+            export_dir,
+            output.clone(),
+        )
+        .unwrap();
+
+        let src_state = load_state(&src);
+        let dst_state = load_state(&dst);
+        let spliced_state = load_state(&output);
+        assert_eq!(
+            spliced_state.canister_state(&spliced_canister),
+            src_state.canister_state(&spliced_canister)
+        );
+        assert_eq!(
+            spliced_state.canister_state(&other_canister),
+            dst_state.canister_state(&other_canister)
+        );
+        assert_eq!(
+            spliced_state.metadata.network_topology.routing_table,
+            dst_state.metadata.network_topology.routing_table
+        );
+        assert_eq!(
+            fs::read(output.join("system_metadata.pbuf")).unwrap(),
+            fs::read(dst.join("system_metadata.pbuf")).unwrap()
+        );
+    }
+
+    #[test]
+    fn splicing_into_an_existing_output_fails() {
+        let tmp = tempfile::tempdir().unwrap();
+        let output = tmp.path().join("spliced");
+        fs::create_dir(&output).unwrap();
+        assert!(do_splice_canister(
+            tmp.path().join("checkpoint"),
+            canister_test_id(1).to_string(),
+            tmp.path().join("export"),
+            output,
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,62 @@
+//! Splits a subnet state into the states of two subnets.
+
+use ic_logger::replica_logger::no_op_logger;
+use ic_registry_routing_table::{CanisterIdRange, CanisterIdRanges};
+use ic_registry_subnet_type::SubnetType;
+use ic_state_manager::split::{split_checkpoint, SplitCheckpoint};
+use ic_types::{PrincipalId, SubnetId};
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// Splits the latest checkpoint in `state_root` into one checkpoint retaining
+/// all canisters outside of `migrated_ranges`, written to `retaining_root`, and
+/// one checkpoint of subnet `new_subnet_id` with the canisters within
+/// `migrated_ranges`, written to `receiving_root`.
+///
+/// Prints the height and manifest root hash of both checkpoints, as needed
+/// for the recovery CUPs of the two subnets.
+pub fn do_split(
+    state_root: PathBuf,
+    subnet_type: String,
+    new_subnet_id: String,
+    migrated_ranges: Vec<String>,
+    retaining_root: PathBuf,
+    receiving_root: PathBuf,
+) -> Result<(), String> {
+    let subnet_type = SubnetType::from_str(&subnet_type)
+        .map_err(|e| format!("Failed to parse subnet type {}: {}", subnet_type, e))?;
+    let new_subnet_id = PrincipalId::from_str(&new_subnet_id)
+        .map(SubnetId::from)
+        .map_err(|e| format!("Failed to parse subnet id {}: {}", new_subnet_id, e))?;
+    let migrated_ranges = migrated_ranges
+        .iter()
+        .map(|range| {
+            CanisterIdRange::from_str(range)
+                .map_err(|e| format!("Failed to parse canister ID range {}: {}", range, e))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let migrated_ranges = CanisterIdRanges::try_from(migrated_ranges)
+        .map_err(|e| format!("Invalid canister ID ranges: {:?}", e))?;
+
+    let (retaining, receiving) = split_checkpoint(
+        state_root,
+        subnet_type,
+        &migrated_ranges,
+        new_subnet_id,
+        retaining_root,
+        receiving_root,
+        no_op_logger(),
+    )?;
+
+    print_checkpoint("Retaining subnet", &retaining);
+    println!();
+    print_checkpoint(&format!("Subnet {}", new_subnet_id), &receiving);
+    Ok(())
+}
+
+fn print_checkpoint(title: &str, checkpoint: &SplitCheckpoint) {
+    println!("{}", title);
+    println!("CHECKPOINT: {}", checkpoint.path.display());
+    println!("HEIGHT: {}", checkpoint.height);
+    println!("ROOT HASH: {}", hex::encode(checkpoint.root_hash));
+}
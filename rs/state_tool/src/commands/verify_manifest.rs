@@ -1,7 +1,7 @@
 use ic_crypto_sha::Sha256;
 use ic_state_manager::manifest::{
     hash::{file_hasher, manifest_hasher},
-    STATE_SYNC_V1,
+    MAX_SUPPORTED_STATE_SYNC_VERSION, STATE_SYNC_V1,
 };
 use ic_types::state_sync::{ChunkInfo, FileInfo};
 use std::{
@@ -274,10 +274,10 @@ fn parse_manifest(file: File) -> (BTreeMap<u32, FileInfo>, Vec<ChunkInfo>, [u8;
 }
 
 fn verify_manifest(file: File, version: u32) -> Result<(), String> {
-    if version > STATE_SYNC_V1 {
+    if version > MAX_SUPPORTED_STATE_SYNC_VERSION {
         panic!(
             "Unsupported state sync version provided {}. Max supported version {}",
-            version, STATE_SYNC_V1
+            version, MAX_SUPPORTED_STATE_SYNC_VERSION
         );
     }
 
@@ -2,7 +2,8 @@
 //!
 //! A command-line tool to manage Internet Computer replicated states (decode
 //! persisted state files, diff checkpoints, compute partial state hashes and
-//! checkpoint manifests, import state trees).
+//! checkpoint manifests, import state trees, export and splice individual
+//! canisters, split subnet states).
 
 use clap::Parser;
 use std::path::PathBuf;
@@ -121,6 +122,76 @@ enum Opt {
         file: PathBuf,
     },
 
+    /// Prints a JSON summary of a canister stored in a checkpoint.
+    #[clap(name = "inspect_canister")]
+    InspectCanister {
+        /// Path to a checkpoint.
+        #[clap(long = "state")]
+        path: PathBuf,
+        /// The textual representation of the canister id.
+        #[clap(long = "canister")]
+        canister: String,
+    },
+
+    /// Exports the Wasm module, memories, queues and system state of a
+    /// canister stored in a checkpoint, together with a JSON summary.
+    #[clap(name = "extract_canister")]
+    ExtractCanister {
+        /// Path to a checkpoint.
+        #[clap(long = "state")]
+        path: PathBuf,
+        /// The textual representation of the canister id.
+        #[clap(long = "canister")]
+        canister: String,
+        /// The directory to export the canister to. Must not exist.
+        #[clap(long = "output")]
+        output: PathBuf,
+    },
+
+    /// Copies a checkpoint, replaces a canister in the copy with one exported
+    /// by `extract_canister` and prints the manifest of the result.
+    #[clap(name = "splice_canister")]
+    SpliceCanister {
+        /// Path to a checkpoint.
+        #[clap(long = "state")]
+        path: PathBuf,
+        /// The textual representation of the canister id.
+        #[clap(long = "canister")]
+        canister: String,
+        /// The directory the canister was exported to.
+        #[clap(long = "canister_dir")]
+        canister_dir: PathBuf,
+        /// The path to write the new checkpoint to. Must not exist.
+        #[clap(long = "output")]
+        output: PathBuf,
+    },
+
+    /// Splits the latest checkpoint of a subnet into a checkpoint retaining
+    /// the canisters outside of the given ranges and a checkpoint of a new
+    /// subnet hosting the canisters within them.
+    #[clap(name = "split")]
+    Split {
+        /// Path to the state root containing the checkpoint to split.
+        #[clap(long = "state_root")]
+        state_root: PathBuf,
+        /// The type of the subnet being split.
+        #[clap(long = "subnet_type", default_value = "application")]
+        subnet_type: String,
+        /// The textual representation of the id of the new subnet.
+        #[clap(long = "new_subnet_id")]
+        new_subnet_id: String,
+        /// The canister ID ranges to move to the new subnet, each given as
+        /// `<start>:<end>` with textual canister ids.
+        #[clap(long = "migrated_ranges", multiple_values = true, required = true)]
+        migrated_ranges: Vec<String>,
+        /// The state root to write the checkpoint of the retaining subnet to.
+        #[clap(long = "retaining_root")]
+        retaining_root: PathBuf,
+        /// The state root to write the checkpoint of the new subnet to.
+        #[clap(long = "receiving_root")]
+        receiving_root: PathBuf,
+    },
+
     /// Converts textual principal representation to hex.
     #[clap(name = "canister_id_to_hex")]
     CanisterIdToHex {
@@ -163,6 +234,35 @@ fn main() {
         }
         Opt::ListStates { config } => commands::list::do_list(config),
         Opt::Decode { file } => commands::decode::do_decode(file),
+        Opt::InspectCanister { path, canister } => {
+            commands::extract_canister::do_inspect_canister(path, canister)
+        }
+        Opt::ExtractCanister {
+            path,
+            canister,
+            output,
+        } => commands::extract_canister::do_extract_canister(path, canister, output),
+        Opt::SpliceCanister {
+            path,
+            canister,
+            canister_dir,
+            output,
+        } => commands::splice_canister::do_splice_canister(path, canister, canister_dir, output),
+        Opt::Split {
+            state_root,
+            subnet_type,
+            new_subnet_id,
+            migrated_ranges,
+            retaining_root,
+            receiving_root,
+        } => commands::split::do_split(
+            state_root,
+            subnet_type,
+            new_subnet_id,
+            migrated_ranges,
+            retaining_root,
+            receiving_root,
+        ),
         Opt::CanisterIdToHex { canister_id } => {
             commands::convert_ids::do_canister_id_to_hex(canister_id)
         }
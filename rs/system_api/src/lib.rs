@@ -16,14 +16,20 @@ use ic_interfaces::execution_environment::{
 };
 use ic_logger::{error, ReplicaLogger};
 use ic_registry_subnet_type::SubnetType;
-use ic_replicated_state::{memory_required_to_push_request, Memory, NumWasmPages, PageIndex};
+use ic_replicated_state::{
+    canister_state::WASM_PAGE_SIZE_IN_BYTES, memory_required_to_push_request, Memory, NumWasmPages,
+    PageIndex,
+};
 use ic_sys::PageBytes;
 use ic_types::{
     ingress::WasmResult,
-    messages::{CallContextId, RejectContext, Request, MAX_INTER_CANISTER_PAYLOAD_IN_BYTES},
+    messages::{
+        CallContextId, RejectContext, Request, MAX_INTER_CANISTER_PAYLOAD_IN_BYTES, NO_DEADLINE,
+    },
     methods::{Callback, SystemMethod, WasmClosure},
-    CanisterId, CanisterTimer, ComputeAllocation, Cycles, NumBytes, NumInstructions, NumPages,
-    PrincipalId, SubnetId, Time,
+    time::UNIX_EPOCH,
+    CanisterId, CanisterLog, CanisterTimer, ComputeAllocation, Cycles, NumBytes, NumInstructions,
+    NumPages, PrincipalId, ResourceSaturation, SubnetId, Time,
 };
 use ic_utils::deterministic_operations::deterministic_copy_from_slice;
 use request_in_prep::{into_request, RequestInPrep};
@@ -144,6 +150,12 @@ impl InstructionLimits {
 pub struct ExecutionParameters {
     pub instruction_limits: InstructionLimits,
     pub canister_memory_limit: NumBytes,
+    /// The limit on the Wasm memory size of the canister. Growing the Wasm
+    /// memory beyond it fails with `HypervisorError::WasmMemoryLimitExceeded`.
+    pub wasm_memory_limit: Option<NumBytes>,
+    /// The memory usage of the subnet at the start of the execution. Used to
+    /// reserve cycles for memory allocated under storage pressure.
+    pub subnet_memory_saturation: ResourceSaturation,
     pub compute_allocation: ComputeAllocation,
     pub subnet_type: SubnetType,
     pub execution_mode: ExecutionMode,
@@ -690,6 +702,11 @@ impl MemoryUsage {
     }
 }
 
+/// Converts a number of Wasm pages to bytes, saturating on overflow.
+fn wasm_pages_to_bytes(pages: u64) -> NumBytes {
+    NumBytes::new(pages.saturating_mul(WASM_PAGE_SIZE_IN_BYTES as u64))
+}
+
 /// Struct that implements the SystemApi trait. This trait enables a canister to
 /// have mediated access to its system state.
 pub struct SystemApiImpl {
@@ -767,6 +784,35 @@ impl SystemApiImpl {
         }
     }
 
+    /// Takes the log records produced by the current execution.
+    pub fn take_canister_log(&mut self) -> CanisterLog {
+        self.sandbox_safe_system_state.take_canister_log()
+    }
+
+    /// Adds a record to the canister log. Records are only kept in replicated
+    /// execution, using the batch time as timestamp. Only `Start` has no time,
+    /// in which case the Unix epoch is used.
+    fn append_canister_log(&mut self, content: &[u8]) {
+        if let ExecutionMode::NonReplicated = self.execution_parameters.execution_mode {
+            return;
+        }
+        let time = match &self.api_type {
+            ApiType::Start { .. } => UNIX_EPOCH,
+            ApiType::Init { time, .. }
+            | ApiType::SystemTask { time, .. }
+            | ApiType::Update { time, .. }
+            | ApiType::Cleanup { time, .. }
+            | ApiType::NonReplicatedQuery { time, .. }
+            | ApiType::ReplicatedQuery { time, .. }
+            | ApiType::PreUpgrade { time, .. }
+            | ApiType::ReplyCallback { time, .. }
+            | ApiType::RejectCallback { time, .. }
+            | ApiType::InspectMessage { time, .. } => *time,
+        };
+        self.sandbox_safe_system_state
+            .append_canister_log(&time, content);
+    }
+
     /// Gets the result of execution, assuming there is no error from
     /// running the canister. Returns any cycles used for an outgoing request
     /// that doesn't get sent and returns allocated memory to the subnet if the
@@ -805,6 +851,12 @@ impl SystemApiImpl {
             .cloned()
             .or_else(|| self.execution_error.take())
         {
+            // Keep the reason of the failure in the canister log.
+            let message = match &err {
+                CalledTrap(msg) => format!("[TRAP]: {}", msg),
+                err => format!("{}", err),
+            };
+            self.append_canister_log(message.as_bytes());
             // Return allocated memory in case of failed message execution.
             self.memory_usage.deallocate_memory(
                 self.memory_usage.total_allocated_memory,
@@ -859,6 +911,24 @@ impl SystemApiImpl {
         self.memory_usage.total_allocated_memory
     }
 
+    /// Reserves cycles for the storage of `allocated_bytes` of memory that
+    /// have just been allocated, if the subnet is under storage pressure.
+    /// Does nothing in non-replicated mode, because the changes are discarded.
+    fn reserve_storage_cycles(&mut self, allocated_bytes: NumBytes) -> HypervisorResult<()> {
+        match self.execution_parameters.execution_mode {
+            ExecutionMode::NonReplicated => Ok(()),
+            ExecutionMode::Replicated => {
+                // The bytes have already been added to `total_allocated_memory`.
+                let subnet_memory_saturation = self
+                    .execution_parameters
+                    .subnet_memory_saturation
+                    .add((self.memory_usage.total_allocated_memory - allocated_bytes).get());
+                self.sandbox_safe_system_state
+                    .reserve_storage_cycles(allocated_bytes, &subnet_memory_saturation)
+            }
+        }
+    }
+
     /// Bytes allocated in messages.
     pub fn get_allocated_message_bytes(&self) -> NumBytes {
         self.memory_usage.allocated_message_memory
@@ -1796,6 +1866,7 @@ impl SystemApi for SystemApiImpl {
                             on_reply,
                             on_reject,
                             None,
+                            NO_DEADLINE,
                         ))?;
 
                 let msg = Request {
@@ -1804,6 +1875,7 @@ impl SystemApi for SystemApiImpl {
                     method_name,
                     method_payload: payload,
                     sender_reply_callback: callback_id,
+                    deadline: NO_DEADLINE,
                     payment: Cycles::zero(),
                 };
                 self.push_output_request(
@@ -2001,6 +2073,64 @@ impl SystemApi for SystemApiImpl {
         result
     }
 
+    fn ic0_call_with_best_effort_response(&mut self, timeout_seconds: u32) -> HypervisorResult<()> {
+        let result = match &mut self.api_type {
+            ApiType::Start { .. }
+            | ApiType::Init { .. }
+            | ApiType::ReplicatedQuery { .. }
+            | ApiType::NonReplicatedQuery {
+                query_kind: NonReplicatedQueryKind::Pure,
+                ..
+            }
+            | ApiType::Cleanup { .. }
+            | ApiType::PreUpgrade { .. }
+            | ApiType::InspectMessage { .. } => {
+                Err(self.error_for("ic0_call_with_best_effort_response"))
+            }
+            ApiType::Update {
+                time,
+                outgoing_request,
+                ..
+            }
+            | ApiType::NonReplicatedQuery {
+                time,
+                query_kind:
+                    NonReplicatedQueryKind::Stateful {
+                        outgoing_request, ..
+                    },
+                ..
+            }
+            | ApiType::SystemTask {
+                time,
+                outgoing_request,
+                ..
+            }
+            | ApiType::ReplyCallback {
+                time,
+                outgoing_request,
+                ..
+            }
+            | ApiType::RejectCallback {
+                time,
+                outgoing_request,
+                ..
+            } => match outgoing_request {
+                None => Err(HypervisorError::ContractViolation(
+                    "ic0.call_with_best_effort_response called when no call is under construction."
+                        .to_string(),
+                )),
+                Some(request) => request.set_timeout(timeout_seconds, *time),
+            },
+        };
+        trace_syscall!(
+            self,
+            ic0_call_with_best_effort_response,
+            result,
+            timeout_seconds
+        );
+        result
+    }
+
     fn ic0_call_cycles_add(&mut self, amount: u64) -> HypervisorResult<()> {
         let result = self.ic0_call_cycles_add_helper("ic0_call_cycles_add", Cycles::from(amount));
         trace_syscall!(self, ic0_call_cycles_add, result, amount);
@@ -2120,13 +2250,18 @@ impl SystemApi for SystemApiImpl {
                 match self.memory_usage.allocate_pages(additional_pages as usize) {
                     Ok(()) => {
                         let res = self.stable_memory.stable_grow(additional_pages);
-                        match &res {
-                            Err(_) | Ok(-1) => self
-                                .memory_usage
-                                .deallocate_pages(additional_pages as usize),
-                            _ => {}
+                        match res {
+                            Err(_) | Ok(-1) => {
+                                self.memory_usage
+                                    .deallocate_pages(additional_pages as usize);
+                                res
+                            }
+                            Ok(_) => self
+                                .reserve_storage_cycles(wasm_pages_to_bytes(
+                                    additional_pages as u64,
+                                ))
+                                .and(res),
                         }
-                        res
                     }
                     Err(_err) => Ok(-1),
                 }
@@ -2238,13 +2373,16 @@ impl SystemApi for SystemApiImpl {
                 match self.memory_usage.allocate_pages(additional_pages as usize) {
                     Ok(()) => {
                         let res = self.stable_memory.stable64_grow(additional_pages);
-                        match &res {
-                            Err(_) | Ok(-1) => self
-                                .memory_usage
-                                .deallocate_pages(additional_pages as usize),
-                            _ => {}
+                        match res {
+                            Err(_) | Ok(-1) => {
+                                self.memory_usage
+                                    .deallocate_pages(additional_pages as usize);
+                                res
+                            }
+                            Ok(_) => self
+                                .reserve_storage_cycles(wasm_pages_to_bytes(additional_pages))
+                                .and(res),
                         }
-                        res
                     }
                     Err(_err) => Ok(-1),
                 }
@@ -2408,6 +2546,51 @@ impl SystemApi for SystemApiImpl {
         result
     }
 
+    fn ic0_is_controller(&self, src: u32, size: u32, heap: &[u8]) -> HypervisorResult<i32> {
+        let result = match &self.api_type {
+            ApiType::Start { .. } => Err(self.error_for("ic0_is_controller")),
+            ApiType::Init { .. }
+            | ApiType::SystemTask { .. }
+            | ApiType::Update { .. }
+            | ApiType::Cleanup { .. }
+            | ApiType::NonReplicatedQuery { .. }
+            | ApiType::ReplicatedQuery { .. }
+            | ApiType::PreUpgrade { .. }
+            | ApiType::ReplyCallback { .. }
+            | ApiType::RejectCallback { .. }
+            | ApiType::InspectMessage { .. } => {
+                let bytes = valid_subslice("ic0.is_controller", src, size, heap)?;
+                PrincipalId::try_from(bytes)
+                    .map(|principal_id| {
+                        self.sandbox_safe_system_state
+                            .is_controller(&principal_id)
+                            .into()
+                    })
+                    .map_err(|err| {
+                        ContractViolation(format!("ic0.is_controller failed with error: {}", err))
+                    })
+            }
+        };
+        trace_syscall!(
+            self,
+            ic0_is_controller,
+            result,
+            src,
+            size,
+            summarize(heap, src, size)
+        );
+        result
+    }
+
+    fn ic0_in_replicated_execution(&self) -> HypervisorResult<i32> {
+        let result = match self.execution_parameters.execution_mode {
+            ExecutionMode::Replicated => Ok(1),
+            ExecutionMode::NonReplicated => Ok(0),
+        };
+        trace_syscall!(self, ic0_in_replicated_execution, result);
+        result
+    }
+
     fn out_of_instructions(&mut self, instruction_counter: i64) -> HypervisorResult<i64> {
         let result = self
             .out_of_instructions_handler
@@ -2434,8 +2617,22 @@ impl SystemApi for SystemApiImpl {
             if native_memory_grow_res == -1 {
                 return Ok(-1);
             }
+            if let Some(limit) = self.execution_parameters.wasm_memory_limit {
+                // `memory.grow` returns the previous size in pages.
+                let new_size =
+                    wasm_pages_to_bytes(native_memory_grow_res as u64 + additional_pages as u64);
+                if new_size > limit {
+                    return Err(HypervisorError::WasmMemoryLimitExceeded {
+                        bytes: new_size,
+                        limit,
+                    });
+                }
+            }
             match self.memory_usage.allocate_pages(additional_pages as usize) {
-                Ok(()) => Ok(native_memory_grow_res),
+                Ok(()) => {
+                    self.reserve_storage_cycles(wasm_pages_to_bytes(additional_pages as u64))?;
+                    Ok(native_memory_grow_res)
+                }
                 Err(_err) => Err(HypervisorError::OutOfMemory),
             }
         };
@@ -2807,6 +3004,14 @@ impl SystemApi for SystemApiImpl {
         Ok(())
     }
 
+    fn save_log_message(&mut self, src: u32, size: u32, heap: &[u8]) {
+        match valid_subslice("save_log_message", src, size, heap) {
+            Ok(content) => self.append_canister_log(content),
+            // Like `ic0.debug_print`, this never fails.
+            Err(_) => self.append_canister_log(b"(debug message out of memory bounds)"),
+        }
+    }
+
     fn ic0_trap(&self, src: u32, size: u32, heap: &[u8]) -> HypervisorResult<()> {
         const MAX_ERROR_MESSAGE_SIZE: u32 = 16 * 1024;
         let size = size.min(MAX_ERROR_MESSAGE_SIZE);
@@ -2,9 +2,9 @@ use crate::{sandbox_safe_system_state::SandboxSafeSystemState, valid_subslice};
 use ic_interfaces::execution_environment::{HypervisorError, HypervisorResult};
 use ic_logger::ReplicaLogger;
 use ic_types::{
-    messages::{CallContextId, Request},
+    messages::{CallContextId, Request, MAX_CALL_TIMEOUT_SECONDS, NO_DEADLINE},
     methods::{Callback, WasmClosure},
-    CanisterId, Cycles, NumBytes, PrincipalId,
+    CanisterId, CoarseTime, Cycles, NumBytes, PrincipalId, Time,
 };
 use serde::{Deserialize, Serialize};
 use std::convert::TryFrom;
@@ -37,6 +37,8 @@ pub struct RequestInPrep {
     cycles: Cycles,
     method_name: String,
     method_payload: Vec<u8>,
+    /// `NO_DEADLINE` unless `ic0.call_with_best_effort_response` was called.
+    deadline: CoarseTime,
     /// The maximum size of a message that will go to a canister on another
     /// subnet.
     max_size_remote_subnet: NumBytes,
@@ -94,6 +96,7 @@ impl RequestInPrep {
             cycles: Cycles::zero(),
             method_name,
             method_payload: Vec::new(),
+            deadline: NO_DEADLINE,
             max_size_remote_subnet,
             multiplier_max_size_local_subnet,
         })
@@ -111,6 +114,28 @@ impl RequestInPrep {
         }
     }
 
+    /// Turns the call into a best-effort call, with a deadline of `timeout_seconds`
+    /// (capped at `MAX_CALL_TIMEOUT_SECONDS`) after `current_time`.
+    pub(crate) fn set_timeout(
+        &mut self,
+        timeout_seconds: u32,
+        current_time: Time,
+    ) -> HypervisorResult<()> {
+        if self.deadline != NO_DEADLINE {
+            return Err(HypervisorError::ContractViolation(
+                "ic0.call_with_best_effort_response can be called at most once between `ic0.call_new` and `ic0.call_perform`"
+                    .to_string(),
+            ));
+        }
+        let timeout_seconds = timeout_seconds.min(MAX_CALL_TIMEOUT_SECONDS);
+        self.deadline = CoarseTime::from_secs_since_unix_epoch(
+            CoarseTime::floor(current_time)
+                .as_secs_since_unix_epoch()
+                .saturating_add(timeout_seconds),
+        );
+        Ok(())
+    }
+
     pub(crate) fn take_cycles(self) -> Cycles {
         self.cycles
     }
@@ -159,6 +184,7 @@ pub(crate) fn into_request(
         cycles,
         method_name,
         method_payload,
+        deadline,
         max_size_remote_subnet,
         multiplier_max_size_local_subnet,
     }: RequestInPrep,
@@ -195,6 +221,7 @@ pub(crate) fn into_request(
         on_reply,
         on_reject,
         on_cleanup,
+        deadline,
     ))?;
 
     let req = Request {
@@ -203,6 +230,7 @@ pub(crate) fn into_request(
         method_name,
         method_payload,
         sender_reply_callback: callback_id,
+        deadline,
         payment: cycles,
     };
     // We cannot call `Request::payload_size_bytes()` before constructing the
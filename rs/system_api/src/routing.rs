@@ -6,9 +6,11 @@ use ic_base_types::{CanisterId, PrincipalId, SubnetId};
 use ic_btc_types::NetworkInRequest as BitcoinNetwork;
 use ic_ic00_types::{
     BitcoinGetBalanceArgs, BitcoinGetCurrentFeePercentilesArgs, BitcoinGetUtxosArgs,
-    BitcoinSendTransactionArgs, CanisterIdRecord, ComputeInitialEcdsaDealingsArgs,
-    ECDSAPublicKeyArgs, EcdsaKeyId, InstallCodeArgs, Method as Ic00Method, Payload,
-    ProvisionalTopUpCanisterArgs, SetControllerArgs, SignWithECDSAArgs, UpdateSettingsArgs,
+    BitcoinSendTransactionArgs, CanisterIdRecord, CanisterInfoRequest, CanisterSnapshotArgs,
+    ComputeInitialEcdsaDealingsArgs, ECDSAPublicKeyArgs, EcdsaKeyId, FetchCanisterLogsRequest,
+    InstallChunkedCodeArgs, InstallCodeArgs, Method as Ic00Method, Payload,
+    ProvisionalTopUpCanisterArgs, SetControllerArgs, SignWithECDSAArgs, TakeCanisterSnapshotArgs,
+    UpdateSettingsArgs, UploadChunkArgs,
 };
 use ic_replicated_state::NetworkTopology;
 
@@ -75,6 +77,32 @@ pub(super) fn resolve_destination(
                     ResolveDestinationError::SubnetNotFound(canister_id, Ic00Method::InstallCode)
                 })
         }
+        Ok(Ic00Method::InstallChunkedCode) => {
+            // Find the destination canister from the payload.
+            let args = InstallChunkedCodeArgs::decode(payload)?;
+            let canister_id = args.target_canister_id();
+            network_topology
+                .routing_table
+                .route(canister_id.get())
+                .map(|subnet_id| subnet_id.get())
+                .ok_or({
+                    ResolveDestinationError::SubnetNotFound(
+                        canister_id,
+                        Ic00Method::InstallChunkedCode,
+                    )
+                })
+        }
+        Ok(Ic00Method::UploadChunk) => {
+            let args = UploadChunkArgs::decode(payload)?;
+            let canister_id = args.get_canister_id();
+            network_topology
+                .routing_table
+                .route(canister_id.get())
+                .map(|subnet_id| subnet_id.get())
+                .ok_or({
+                    ResolveDestinationError::SubnetNotFound(canister_id, Ic00Method::UploadChunk)
+                })
+        }
         Ok(Ic00Method::SetController) => {
             let args = Decode!(payload, SetControllerArgs)?;
             let canister_id = args.get_canister_id();
@@ -91,7 +119,10 @@ pub(super) fn resolve_destination(
         | Ok(Ic00Method::StopCanister)
         | Ok(Ic00Method::DeleteCanister)
         | Ok(Ic00Method::UninstallCode)
-        | Ok(Ic00Method::DepositCycles) => {
+        | Ok(Ic00Method::DepositCycles)
+        | Ok(Ic00Method::ListCanisterSnapshots)
+        | Ok(Ic00Method::StoredChunks)
+        | Ok(Ic00Method::ClearChunkStore) => {
             let args = Decode!(payload, CanisterIdRecord)?;
             let canister_id = args.get_canister_id();
             network_topology
@@ -102,6 +133,56 @@ pub(super) fn resolve_destination(
                     ResolveDestinationError::SubnetNotFound(canister_id, method.unwrap())
                 })
         }
+        Ok(Ic00Method::CanisterInfo) => {
+            let args = CanisterInfoRequest::decode(payload)?;
+            let canister_id = args.canister_id();
+            network_topology
+                .routing_table
+                .route(canister_id.get())
+                .map(|subnet_id| subnet_id.get())
+                .ok_or({
+                    ResolveDestinationError::SubnetNotFound(canister_id, Ic00Method::CanisterInfo)
+                })
+        }
+        Ok(Ic00Method::FetchCanisterLogs) => {
+            let args = FetchCanisterLogsRequest::decode(payload)?;
+            let canister_id = args.get_canister_id();
+            network_topology
+                .routing_table
+                .route(canister_id.get())
+                .map(|subnet_id| subnet_id.get())
+                .ok_or({
+                    ResolveDestinationError::SubnetNotFound(
+                        canister_id,
+                        Ic00Method::FetchCanisterLogs,
+                    )
+                })
+        }
+        Ok(Ic00Method::TakeCanisterSnapshot) => {
+            let args = TakeCanisterSnapshotArgs::decode(payload)?;
+            let canister_id = args.get_canister_id();
+            network_topology
+                .routing_table
+                .route(canister_id.get())
+                .map(|subnet_id| subnet_id.get())
+                .ok_or_else(|| {
+                    ResolveDestinationError::SubnetNotFound(
+                        canister_id,
+                        Ic00Method::TakeCanisterSnapshot,
+                    )
+                })
+        }
+        Ok(Ic00Method::LoadCanisterSnapshot) | Ok(Ic00Method::DeleteCanisterSnapshot) => {
+            let args = CanisterSnapshotArgs::decode(payload)?;
+            let canister_id = args.get_canister_id();
+            network_topology
+                .routing_table
+                .route(canister_id.get())
+                .map(|subnet_id| subnet_id.get())
+                .ok_or_else(|| {
+                    ResolveDestinationError::SubnetNotFound(canister_id, method.unwrap())
+                })
+        }
         Ok(Ic00Method::ProvisionalTopUpCanister) => {
             let args = ProvisionalTopUpCanisterArgs::decode(payload)?;
             let canister_id = args.get_canister_id();
@@ -17,7 +17,8 @@ use ic_types::{
     messages::{CallContextId, CallbackId, RejectContext, Request},
     methods::Callback,
     nominal_cycles::NominalCycles,
-    CanisterTimer, ComputeAllocation, Cycles, MemoryAllocation, NumInstructions, NumPages, Time,
+    CanisterLog, CanisterTimer, ComputeAllocation, Cycles, MemoryAllocation, NumInstructions,
+    NumPages, ResourceSaturation, Time,
 };
 use ic_wasm_types::WasmEngineError;
 use serde::{Deserialize, Serialize};
@@ -55,6 +56,9 @@ pub struct SystemStateChanges {
     pub(super) callback_updates: Vec<CallbackUpdate>,
     cycles_balance_change: CyclesBalanceChange,
     cycles_consumed: Cycles,
+    /// Cycles moved from the main balance to the reserved balance. They are
+    /// also included in `cycles_balance_change`.
+    reserved_cycles: Cycles,
     call_context_balance_taken: BTreeMap<CallContextId, Cycles>,
     request_slots_used: BTreeMap<CanisterId, usize>,
     requests: Vec<Request>,
@@ -68,6 +72,7 @@ impl Default for SystemStateChanges {
             callback_updates: vec![],
             cycles_balance_change: CyclesBalanceChange::zero(),
             cycles_consumed: Cycles::zero(),
+            reserved_cycles: Cycles::zero(),
             call_context_balance_taken: BTreeMap::new(),
             request_slots_used: BTreeMap::new(),
             requests: vec![],
@@ -124,8 +129,13 @@ impl SystemStateChanges {
 
         // Verify total cycle change is not positive and update cycles balance.
         self.validate_cycle_change(system_state.canister_id == CYCLES_MINTING_CANISTER_ID)?;
-        self.cycles_balance_change
+        // The reserved cycles are moved separately, so that the reservation
+        // limit is checked against the actual system state.
+        (self.cycles_balance_change + CyclesBalanceChange::added(self.reserved_cycles))
             .apply_ref(system_state.balance_mut());
+        system_state
+            .reserve_cycles(self.reserved_cycles)
+            .map_err(|err| error(format!("Failed to reserve cycles: {:?}", err)))?;
 
         // Observe consumed cycles.
         system_state
@@ -149,7 +159,7 @@ impl SystemStateChanges {
                 })?;
                 if (*amount_taken).get() > LOG_CANISTER_OPERATION_CYCLES_THRESHOLD {
                     match call_context.call_origin() {
-                        CallOrigin::CanisterUpdate(origin_canister_id, _)
+                        CallOrigin::CanisterUpdate(origin_canister_id, _, _)
                         | CallOrigin::CanisterQuery(origin_canister_id, _) => info!(
                             logger,
                             "Canister {} accepted {} cycles from canister {}.",
@@ -287,6 +297,7 @@ pub struct SandboxSafeSystemState {
     pub system_state_changes: SystemStateChanges,
     pub(super) canister_id: CanisterId,
     pub(super) controller: PrincipalId,
+    controllers: BTreeSet<PrincipalId>,
     pub(super) status: CanisterStatusView,
     pub(super) subnet_type: SubnetType,
     pub(super) subnet_size: usize,
@@ -294,6 +305,8 @@ pub struct SandboxSafeSystemState {
     freeze_threshold: NumSeconds,
     memory_allocation: MemoryAllocation,
     initial_cycles_balance: Cycles,
+    initial_reserved_balance: Cycles,
+    reserved_balance_limit: Option<Cycles>,
     call_context_balances: BTreeMap<CallContextId, Cycles>,
     cycles_account_manager: CyclesAccountManager,
     // None indicates that we are in a context where the canister cannot
@@ -305,6 +318,8 @@ pub struct SandboxSafeSystemState {
     ic00_aliases: BTreeSet<CanisterId>,
     global_timer: CanisterTimer,
     canister_version: u64,
+    /// The log records produced by the current execution.
+    canister_log: CanisterLog,
 }
 
 impl SandboxSafeSystemState {
@@ -314,6 +329,7 @@ impl SandboxSafeSystemState {
     pub fn new_internal(
         canister_id: CanisterId,
         controller: PrincipalId,
+        controllers: BTreeSet<PrincipalId>,
         status: CanisterStatusView,
         freeze_threshold: NumSeconds,
         memory_allocation: MemoryAllocation,
@@ -328,10 +344,14 @@ impl SandboxSafeSystemState {
         dirty_page_overhead: NumInstructions,
         global_timer: CanisterTimer,
         canister_version: u64,
+        next_canister_log_record_idx: u64,
+        initial_reserved_balance: Cycles,
+        reserved_balance_limit: Option<Cycles>,
     ) -> Self {
         Self {
             canister_id,
             controller,
+            controllers,
             status,
             subnet_type: cycles_account_manager.subnet_type(),
             subnet_size,
@@ -340,6 +360,8 @@ impl SandboxSafeSystemState {
             memory_allocation,
             system_state_changes: SystemStateChanges::default(),
             initial_cycles_balance,
+            initial_reserved_balance,
+            reserved_balance_limit,
             call_context_balances,
             cycles_account_manager,
             next_callback_id,
@@ -348,6 +370,7 @@ impl SandboxSafeSystemState {
             ic00_aliases,
             global_timer,
             canister_version,
+            canister_log: CanisterLog::new_with_next_index(next_canister_log_record_idx),
         }
     }
 
@@ -392,6 +415,7 @@ impl SandboxSafeSystemState {
         Self::new_internal(
             system_state.canister_id,
             *system_state.controller(),
+            system_state.controllers.clone(),
             CanisterStatusView::from_full_status(&system_state.status),
             system_state.freeze_threshold,
             system_state.memory_allocation,
@@ -408,6 +432,9 @@ impl SandboxSafeSystemState {
             dirty_page_overhead,
             system_state.global_timer,
             system_state.canister_version,
+            system_state.canister_log.next_idx(),
+            system_state.reserved_balance(),
+            system_state.reserved_balance_limit(),
         )
     }
 
@@ -423,6 +450,26 @@ impl SandboxSafeSystemState {
         self.canister_version
     }
 
+    pub fn is_controller(&self, principal_id: &PrincipalId) -> bool {
+        self.controllers.contains(principal_id)
+    }
+
+    /// Adds a record with the given content to the log of the current
+    /// execution.
+    pub fn append_canister_log(&mut self, time: &Time, content: &[u8]) {
+        self.canister_log
+            .add_record(time.as_nanos_since_unix_epoch(), content);
+    }
+
+    /// Takes the log records produced by the current execution.
+    pub fn take_canister_log(&mut self) -> CanisterLog {
+        let next_idx = self.canister_log.next_idx();
+        std::mem::replace(
+            &mut self.canister_log,
+            CanisterLog::new_with_next_index(next_idx),
+        )
+    }
+
     pub fn set_global_timer(&mut self, timer: CanisterTimer) {
         // Update both sandbox global timer and the changes.
         self.system_state_changes.new_global_timer = Some(timer);
@@ -468,6 +515,56 @@ impl SandboxSafeSystemState {
         cycles_change.apply(self.initial_cycles_balance)
     }
 
+    /// Returns the reserved balance including the cycles reserved during the
+    /// current execution.
+    pub(super) fn reserved_balance(&self) -> Cycles {
+        self.initial_reserved_balance + self.system_state_changes.reserved_cycles
+    }
+
+    /// Moves the cycles needed to pay for the storage of `allocated_bytes` of
+    /// newly allocated memory from the main balance to the reserved balance.
+    /// The amount depends on how saturated the subnet memory is. Canisters
+    /// with a reserved memory allocation have already paid for their memory
+    /// when the allocation was made.
+    pub(super) fn reserve_storage_cycles(
+        &mut self,
+        allocated_bytes: NumBytes,
+        subnet_memory_saturation: &ResourceSaturation,
+    ) -> HypervisorResult<()> {
+        if let MemoryAllocation::Reserved(_) = self.memory_allocation {
+            return Ok(());
+        }
+        let cycles_to_reserve = self.cycles_account_manager.storage_reservation_cycles(
+            allocated_bytes,
+            subnet_memory_saturation,
+            self.subnet_size,
+        );
+        if cycles_to_reserve.get() == 0 {
+            return Ok(());
+        }
+        if let Some(limit) = self.reserved_balance_limit {
+            let requested = self.reserved_balance() + cycles_to_reserve;
+            if requested > limit {
+                return Err(HypervisorError::ReservedCyclesLimitExceededInMemoryGrow {
+                    bytes: allocated_bytes,
+                    requested,
+                    limit,
+                });
+            }
+        }
+        let available = self.cycles_balance();
+        if cycles_to_reserve > available {
+            return Err(HypervisorError::InsufficientCyclesInMemoryGrow {
+                bytes: allocated_bytes,
+                available,
+                required: cycles_to_reserve,
+            });
+        }
+        self.update_balance_change(available - cycles_to_reserve);
+        self.system_state_changes.reserved_cycles += cycles_to_reserve;
+        Ok(())
+    }
+
     pub(super) fn msg_cycles_available(&self, call_context_id: CallContextId) -> Cycles {
         let initial_available = *self
             .call_context_balances
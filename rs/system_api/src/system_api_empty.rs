@@ -130,6 +130,9 @@ impl SystemApi for SystemApiEmpty {
     fn ic0_debug_print(&self, _: u32, _: u32, _: &[u8]) -> HypervisorResult<()> {
         unimplemented!("{}", MESSAGE_UNIMPLEMENTED)
     }
+    fn save_log_message(&mut self, _: u32, _: u32, _: &[u8]) {
+        unimplemented!("{}", MESSAGE_UNIMPLEMENTED)
+    }
     fn ic0_trap(&self, _: u32, _: u32, _: &[u8]) -> HypervisorResult<()> {
         unimplemented!("{}", MESSAGE_UNIMPLEMENTED)
     }
@@ -169,6 +172,9 @@ impl SystemApi for SystemApiEmpty {
     fn ic0_call_on_cleanup(&mut self, _: u32, _: u32) -> HypervisorResult<()> {
         unimplemented!("{}", MESSAGE_UNIMPLEMENTED)
     }
+    fn ic0_call_with_best_effort_response(&mut self, _: u32) -> HypervisorResult<()> {
+        unimplemented!("{}", MESSAGE_UNIMPLEMENTED)
+    }
     fn ic0_call_cycles_add(&mut self, _: u64) -> HypervisorResult<()> {
         unimplemented!("{}", MESSAGE_UNIMPLEMENTED)
     }
@@ -217,6 +223,12 @@ impl SystemApi for SystemApiEmpty {
     fn ic0_canister_version(&self) -> HypervisorResult<u64> {
         unimplemented!("{}", MESSAGE_UNIMPLEMENTED)
     }
+    fn ic0_is_controller(&self, _: u32, _: u32, _: &[u8]) -> HypervisorResult<i32> {
+        unimplemented!("{}", MESSAGE_UNIMPLEMENTED)
+    }
+    fn ic0_in_replicated_execution(&self) -> HypervisorResult<i32> {
+        unimplemented!("{}", MESSAGE_UNIMPLEMENTED)
+    }
     fn out_of_instructions(&mut self, _instruction_counter: i64) -> Result<i64, HypervisorError> {
         unimplemented!("{}", MESSAGE_UNIMPLEMENTED)
     }
@@ -19,9 +19,9 @@ use ic_test_utilities::{
 };
 use ic_test_utilities::{state::SystemStateBuilder, types::ids::canister_test_id};
 use ic_types::{
-    messages::{CallContextId, CallbackId, RejectContext},
+    messages::{CallContextId, CallbackId, RejectContext, NO_DEADLINE},
     methods::SystemMethod,
-    ComputeAllocation, Cycles, NumInstructions, Time,
+    ComputeAllocation, Cycles, NumInstructions, ResourceSaturation, Time,
 };
 use maplit::btreemap;
 
@@ -35,6 +35,8 @@ pub fn execution_parameters() -> ExecutionParameters {
             NumInstructions::from(5_000_000_000),
         ),
         canister_memory_limit: NumBytes::new(4 << 30),
+        wasm_memory_limit: None,
+        subnet_memory_saturation: ResourceSaturation::default(),
         compute_allocation: ComputeAllocation::default(),
         subnet_type: SubnetType::Application,
         execution_mode: ExecutionMode::Replicated,
@@ -139,7 +141,7 @@ pub fn get_system_state() -> SystemState {
         .call_context_manager_mut()
         .unwrap()
         .new_call_context(
-            CallOrigin::CanisterUpdate(canister_test_id(33), CallbackId::from(5)),
+            CallOrigin::CanisterUpdate(canister_test_id(33), CallbackId::from(5), NO_DEADLINE),
             Cycles::new(50),
             Time::from_nanos_since_unix_epoch(0),
         );
@@ -3,8 +3,8 @@ use ic_config::subnet_config::SchedulerConfig;
 use ic_constants::SMALL_APP_SUBNET_MAX_SIZE;
 use ic_error_types::RejectCode;
 use ic_interfaces::execution_environment::{
-    CanisterOutOfCyclesError, HypervisorError, HypervisorResult, PerformanceCounterType,
-    SubnetAvailableMemory, SystemApi, TrapCode,
+    CanisterOutOfCyclesError, ExecutionMode, HypervisorError, HypervisorResult,
+    PerformanceCounterType, SubnetAvailableMemory, SystemApi, TrapCode,
 };
 use ic_logger::replica_logger::no_op_logger;
 use ic_registry_subnet_type::SubnetType;
@@ -14,7 +14,7 @@ use ic_replicated_state::{
 };
 use ic_system_api::{
     sandbox_safe_system_state::SandboxSafeSystemState, ApiType, DefaultOutOfInstructionsHandler,
-    NonReplicatedQueryKind, SystemApiImpl,
+    ExecutionParameters, NonReplicatedQueryKind, SystemApiImpl,
 };
 use ic_test_utilities::{
     cycles_account_manager::CyclesAccountManagerBuilder,
@@ -26,7 +26,7 @@ use ic_test_utilities::{
     },
 };
 use ic_types::{
-    messages::{CallContextId, CallbackId, RejectContext, MAX_RESPONSE_COUNT_BYTES},
+    messages::{CallContextId, CallbackId, RejectContext, MAX_RESPONSE_COUNT_BYTES, NO_DEADLINE},
     methods::{Callback, WasmClosure},
     time, CanisterTimer, CountBytes, Cycles, NumBytes, NumInstructions, Time,
 };
@@ -96,6 +96,7 @@ fn test_canister_init_support() {
     assert_api_not_supported(api.ic0_call_new(0, 0, 0, 0, 0, 0, 0, 0, &[]));
     assert_api_not_supported(api.ic0_call_data_append(0, 0, &[]));
     assert_api_not_supported(api.ic0_call_on_cleanup(0, 0));
+    assert_api_not_supported(api.ic0_call_with_best_effort_response(0));
     assert_api_not_supported(api.ic0_call_cycles_add(0));
     assert_api_not_supported(api.ic0_call_cycles_add128(Cycles::new(0)));
     assert_api_not_supported(api.ic0_call_perform());
@@ -109,6 +110,8 @@ fn test_canister_init_support() {
     assert_api_supported(api.ic0_stable64_write(0, 0, 0, &[]));
     assert_api_supported(api.ic0_time());
     assert_api_supported(api.ic0_canister_version());
+    assert_api_supported(api.ic0_is_controller(0, 0, &[]));
+    assert_api_supported(api.ic0_in_replicated_execution());
     assert_api_supported(api.ic0_global_timer_set(time::UNIX_EPOCH));
     assert_api_supported(
         api.ic0_performance_counter(PerformanceCounterType::Instructions(0.into())),
@@ -161,6 +164,7 @@ fn test_canister_update_support() {
     assert_api_supported(api.ic0_call_new(0, 0, 0, 0, 0, 0, 0, 0, &[]));
     assert_api_supported(api.ic0_call_data_append(0, 0, &[]));
     assert_api_supported(api.ic0_call_on_cleanup(0, 0));
+    assert_api_supported(api.ic0_call_with_best_effort_response(0));
     assert_api_supported(api.ic0_call_cycles_add(0));
     assert_api_supported(api.ic0_call_cycles_add128(Cycles::new(0)));
     assert_api_supported(api.ic0_call_perform());
@@ -174,6 +178,8 @@ fn test_canister_update_support() {
     assert_api_supported(api.ic0_stable64_write(0, 0, 0, &[]));
     assert_api_supported(api.ic0_time());
     assert_api_supported(api.ic0_canister_version());
+    assert_api_supported(api.ic0_is_controller(0, 0, &[]));
+    assert_api_supported(api.ic0_in_replicated_execution());
     assert_api_supported(api.ic0_global_timer_set(time::UNIX_EPOCH));
     assert_api_supported(
         api.ic0_performance_counter(PerformanceCounterType::Instructions(0.into())),
@@ -226,6 +232,7 @@ fn test_canister_replicated_query_support() {
     assert_api_not_supported(api.ic0_call_new(0, 0, 0, 0, 0, 0, 0, 0, &[]));
     assert_api_not_supported(api.ic0_call_data_append(0, 0, &[]));
     assert_api_not_supported(api.ic0_call_on_cleanup(0, 0));
+    assert_api_not_supported(api.ic0_call_with_best_effort_response(0));
     assert_api_not_supported(api.ic0_call_cycles_add(0));
     assert_api_not_supported(api.ic0_call_cycles_add128(Cycles::new(0)));
     assert_api_not_supported(api.ic0_call_perform());
@@ -239,6 +246,8 @@ fn test_canister_replicated_query_support() {
     assert_api_supported(api.ic0_stable64_write(0, 0, 0, &[]));
     assert_api_supported(api.ic0_time());
     assert_api_supported(api.ic0_canister_version());
+    assert_api_supported(api.ic0_is_controller(0, 0, &[]));
+    assert_api_supported(api.ic0_in_replicated_execution());
     assert_api_not_supported(api.ic0_global_timer_set(time::UNIX_EPOCH));
     assert_api_supported(
         api.ic0_performance_counter(PerformanceCounterType::Instructions(0.into())),
@@ -291,6 +300,7 @@ fn test_canister_pure_query_support() {
     assert_api_not_supported(api.ic0_call_new(0, 0, 0, 0, 0, 0, 0, 0, &[]));
     assert_api_not_supported(api.ic0_call_data_append(0, 0, &[]));
     assert_api_not_supported(api.ic0_call_on_cleanup(0, 0));
+    assert_api_not_supported(api.ic0_call_with_best_effort_response(0));
     assert_api_not_supported(api.ic0_call_cycles_add(0));
     assert_api_not_supported(api.ic0_call_cycles_add128(Cycles::new(0)));
     assert_api_not_supported(api.ic0_call_perform());
@@ -304,6 +314,8 @@ fn test_canister_pure_query_support() {
     assert_api_supported(api.ic0_stable64_write(0, 0, 0, &[]));
     assert_api_supported(api.ic0_time());
     assert_api_supported(api.ic0_canister_version());
+    assert_api_supported(api.ic0_is_controller(0, 0, &[]));
+    assert_api_supported(api.ic0_in_replicated_execution());
     assert_api_not_supported(api.ic0_global_timer_set(time::UNIX_EPOCH));
     assert_api_supported(
         api.ic0_performance_counter(PerformanceCounterType::Instructions(0.into())),
@@ -366,6 +378,7 @@ fn test_canister_stateful_query_support() {
     assert_api_supported(api.ic0_call_new(0, 0, 0, 0, 0, 0, 0, 0, &[]));
     assert_api_supported(api.ic0_call_data_append(0, 0, &[]));
     assert_api_supported(api.ic0_call_on_cleanup(0, 0));
+    assert_api_supported(api.ic0_call_with_best_effort_response(0));
     assert_api_not_supported(api.ic0_call_cycles_add(0));
     assert_api_not_supported(api.ic0_call_cycles_add128(Cycles::new(0)));
     assert_api_supported(api.ic0_call_perform());
@@ -379,6 +392,8 @@ fn test_canister_stateful_query_support() {
     assert_api_supported(api.ic0_stable64_write(0, 0, 0, &[]));
     assert_api_supported(api.ic0_time());
     assert_api_supported(api.ic0_canister_version());
+    assert_api_supported(api.ic0_is_controller(0, 0, &[]));
+    assert_api_supported(api.ic0_in_replicated_execution());
     assert_api_not_supported(api.ic0_global_timer_set(time::UNIX_EPOCH));
     assert_api_supported(
         api.ic0_performance_counter(PerformanceCounterType::Instructions(0.into())),
@@ -431,6 +446,7 @@ fn test_reply_api_support_on_nns() {
     assert_api_supported(api.ic0_call_new(0, 0, 0, 0, 0, 0, 0, 0, &[]));
     assert_api_supported(api.ic0_call_data_append(0, 0, &[]));
     assert_api_supported(api.ic0_call_on_cleanup(0, 0));
+    assert_api_supported(api.ic0_call_with_best_effort_response(0));
     assert_api_supported(api.ic0_call_cycles_add(0));
     assert_api_supported(api.ic0_call_cycles_add128(Cycles::new(0)));
     assert_api_supported(api.ic0_call_perform());
@@ -444,6 +460,8 @@ fn test_reply_api_support_on_nns() {
     assert_api_supported(api.ic0_stable64_write(0, 0, 0, &[]));
     assert_api_supported(api.ic0_time());
     assert_api_supported(api.ic0_canister_version());
+    assert_api_supported(api.ic0_is_controller(0, 0, &[]));
+    assert_api_supported(api.ic0_in_replicated_execution());
     assert_api_supported(api.ic0_global_timer_set(time::UNIX_EPOCH));
     assert_api_supported(
         api.ic0_performance_counter(PerformanceCounterType::Instructions(0.into())),
@@ -496,6 +514,7 @@ fn test_reply_api_support_non_nns() {
     assert_api_supported(api.ic0_call_new(0, 0, 0, 0, 0, 0, 0, 0, &[]));
     assert_api_supported(api.ic0_call_data_append(0, 0, &[]));
     assert_api_supported(api.ic0_call_on_cleanup(0, 0));
+    assert_api_supported(api.ic0_call_with_best_effort_response(0));
     assert_api_supported(api.ic0_call_cycles_add(0));
     assert_api_supported(api.ic0_call_cycles_add128(Cycles::new(0)));
     assert_api_supported(api.ic0_call_perform());
@@ -509,6 +528,8 @@ fn test_reply_api_support_non_nns() {
     assert_api_supported(api.ic0_stable64_read(0, 0, 0, &mut []));
     assert_api_supported(api.ic0_time());
     assert_api_supported(api.ic0_canister_version());
+    assert_api_supported(api.ic0_is_controller(0, 0, &[]));
+    assert_api_supported(api.ic0_in_replicated_execution());
     assert_api_supported(api.ic0_global_timer_set(time::UNIX_EPOCH));
     assert_api_supported(
         api.ic0_performance_counter(PerformanceCounterType::Instructions(0.into())),
@@ -564,6 +585,7 @@ fn test_reject_api_support_on_nns() {
     assert_api_supported(api.ic0_call_new(0, 0, 0, 0, 0, 0, 0, 0, &[]));
     assert_api_supported(api.ic0_call_data_append(0, 0, &[]));
     assert_api_supported(api.ic0_call_on_cleanup(0, 0));
+    assert_api_supported(api.ic0_call_with_best_effort_response(0));
     assert_api_supported(api.ic0_call_cycles_add(0));
     assert_api_supported(api.ic0_call_cycles_add128(Cycles::new(0)));
     assert_api_supported(api.ic0_call_perform());
@@ -577,6 +599,8 @@ fn test_reject_api_support_on_nns() {
     assert_api_supported(api.ic0_stable64_write(0, 0, 0, &[]));
     assert_api_supported(api.ic0_time());
     assert_api_supported(api.ic0_canister_version());
+    assert_api_supported(api.ic0_is_controller(0, 0, &[]));
+    assert_api_supported(api.ic0_in_replicated_execution());
     assert_api_supported(api.ic0_global_timer_set(time::UNIX_EPOCH));
     assert_api_supported(
         api.ic0_performance_counter(PerformanceCounterType::Instructions(0.into())),
@@ -632,6 +656,7 @@ fn test_reject_api_support_non_nns() {
     assert_api_supported(api.ic0_call_new(0, 0, 0, 0, 0, 0, 0, 0, &[]));
     assert_api_supported(api.ic0_call_data_append(0, 0, &[]));
     assert_api_supported(api.ic0_call_on_cleanup(0, 0));
+    assert_api_supported(api.ic0_call_with_best_effort_response(0));
     assert_api_supported(api.ic0_call_cycles_add(0));
     assert_api_supported(api.ic0_call_cycles_add128(Cycles::new(0)));
     assert_api_supported(api.ic0_call_perform());
@@ -645,6 +670,8 @@ fn test_reject_api_support_non_nns() {
     assert_api_supported(api.ic0_stable64_write(0, 0, 0, &[]));
     assert_api_supported(api.ic0_time());
     assert_api_supported(api.ic0_canister_version());
+    assert_api_supported(api.ic0_is_controller(0, 0, &[]));
+    assert_api_supported(api.ic0_in_replicated_execution());
     assert_api_supported(api.ic0_global_timer_set(time::UNIX_EPOCH));
     assert_api_supported(
         api.ic0_performance_counter(PerformanceCounterType::Instructions(0.into())),
@@ -697,6 +724,7 @@ fn test_pre_upgrade_support() {
     assert_api_not_supported(api.ic0_call_new(0, 0, 0, 0, 0, 0, 0, 0, &[]));
     assert_api_not_supported(api.ic0_call_data_append(0, 0, &[]));
     assert_api_not_supported(api.ic0_call_on_cleanup(0, 0));
+    assert_api_not_supported(api.ic0_call_with_best_effort_response(0));
     assert_api_not_supported(api.ic0_call_cycles_add(0));
     assert_api_not_supported(api.ic0_call_cycles_add128(Cycles::new(0)));
     assert_api_not_supported(api.ic0_call_perform());
@@ -710,6 +738,8 @@ fn test_pre_upgrade_support() {
     assert_api_supported(api.ic0_stable64_write(0, 0, 0, &[]));
     assert_api_supported(api.ic0_time());
     assert_api_supported(api.ic0_canister_version());
+    assert_api_supported(api.ic0_is_controller(0, 0, &[]));
+    assert_api_supported(api.ic0_in_replicated_execution());
     assert_api_not_supported(api.ic0_global_timer_set(time::UNIX_EPOCH));
     assert_api_supported(
         api.ic0_performance_counter(PerformanceCounterType::Instructions(0.into())),
@@ -762,6 +792,7 @@ fn test_start_support() {
     assert_api_not_supported(api.ic0_call_new(0, 0, 0, 0, 0, 0, 0, 0, &[]));
     assert_api_not_supported(api.ic0_call_data_append(0, 0, &[]));
     assert_api_not_supported(api.ic0_call_on_cleanup(0, 0));
+    assert_api_not_supported(api.ic0_call_with_best_effort_response(0));
     assert_api_not_supported(api.ic0_call_cycles_add(0));
     assert_api_not_supported(api.ic0_call_cycles_add128(Cycles::new(0)));
     assert_api_not_supported(api.ic0_call_perform());
@@ -775,6 +806,8 @@ fn test_start_support() {
     assert_api_not_supported(api.ic0_stable64_write(0, 0, 0, &[]));
     assert_api_not_supported(api.ic0_time());
     assert_api_not_supported(api.ic0_canister_version());
+    assert_api_not_supported(api.ic0_is_controller(0, 0, &[]));
+    assert_api_supported(api.ic0_in_replicated_execution());
     assert_api_not_supported(api.ic0_global_timer_set(time::UNIX_EPOCH));
     assert_api_supported(
         api.ic0_performance_counter(PerformanceCounterType::Instructions(0.into())),
@@ -827,6 +860,7 @@ fn test_cleanup_support() {
     assert_api_not_supported(api.ic0_call_new(0, 0, 0, 0, 0, 0, 0, 0, &[]));
     assert_api_not_supported(api.ic0_call_data_append(0, 0, &[]));
     assert_api_not_supported(api.ic0_call_on_cleanup(0, 0));
+    assert_api_not_supported(api.ic0_call_with_best_effort_response(0));
     assert_api_not_supported(api.ic0_call_cycles_add(0));
     assert_api_not_supported(api.ic0_call_cycles_add128(Cycles::new(0)));
     assert_api_not_supported(api.ic0_call_perform());
@@ -840,6 +874,8 @@ fn test_cleanup_support() {
     assert_api_supported(api.ic0_stable64_write(0, 0, 0, &[]));
     assert_api_supported(api.ic0_time());
     assert_api_supported(api.ic0_canister_version());
+    assert_api_supported(api.ic0_is_controller(0, 0, &[]));
+    assert_api_supported(api.ic0_in_replicated_execution());
     assert_api_supported(api.ic0_global_timer_set(time::UNIX_EPOCH));
     assert_api_supported(
         api.ic0_performance_counter(PerformanceCounterType::Instructions(0.into())),
@@ -897,6 +933,7 @@ fn test_inspect_message_support() {
     assert_api_not_supported(api.ic0_call_new(0, 0, 0, 0, 0, 0, 0, 0, &[]));
     assert_api_not_supported(api.ic0_call_data_append(0, 0, &[]));
     assert_api_not_supported(api.ic0_call_on_cleanup(0, 0));
+    assert_api_not_supported(api.ic0_call_with_best_effort_response(0));
     assert_api_not_supported(api.ic0_call_cycles_add(0));
     assert_api_not_supported(api.ic0_call_cycles_add128(Cycles::new(0)));
     assert_api_not_supported(api.ic0_call_perform());
@@ -910,6 +947,8 @@ fn test_inspect_message_support() {
     assert_api_supported(api.ic0_stable64_write(0, 0, 0, &[]));
     assert_api_supported(api.ic0_time());
     assert_api_supported(api.ic0_canister_version());
+    assert_api_supported(api.ic0_is_controller(0, 0, &[]));
+    assert_api_supported(api.ic0_in_replicated_execution());
     assert_api_not_supported(api.ic0_global_timer_set(time::UNIX_EPOCH));
     assert_api_supported(
         api.ic0_performance_counter(PerformanceCounterType::Instructions(0.into())),
@@ -963,6 +1002,7 @@ fn test_canister_heartbeat_support() {
     assert_api_supported(api.ic0_call_new(0, 0, 0, 0, 0, 0, 0, 0, &[]));
     assert_api_supported(api.ic0_call_data_append(0, 0, &[]));
     assert_api_supported(api.ic0_call_on_cleanup(0, 0));
+    assert_api_supported(api.ic0_call_with_best_effort_response(0));
     assert_api_supported(api.ic0_call_cycles_add(0));
     assert_api_supported(api.ic0_call_cycles_add128(Cycles::new(0)));
     assert_api_supported(api.ic0_call_perform());
@@ -976,6 +1016,8 @@ fn test_canister_heartbeat_support() {
     assert_api_supported(api.ic0_stable64_write(0, 0, 0, &[]));
     assert_api_supported(api.ic0_time());
     assert_api_supported(api.ic0_canister_version());
+    assert_api_supported(api.ic0_is_controller(0, 0, &[]));
+    assert_api_supported(api.ic0_in_replicated_execution());
     assert_api_supported(api.ic0_global_timer_set(time::UNIX_EPOCH));
     assert_api_supported(
         api.ic0_performance_counter(PerformanceCounterType::Instructions(0.into())),
@@ -1028,6 +1070,7 @@ fn test_canister_heartbeat_support_nns() {
     assert_api_supported(api.ic0_call_new(0, 0, 0, 0, 0, 0, 0, 0, &[]));
     assert_api_supported(api.ic0_call_data_append(0, 0, &[]));
     assert_api_supported(api.ic0_call_on_cleanup(0, 0));
+    assert_api_supported(api.ic0_call_with_best_effort_response(0));
     assert_api_supported(api.ic0_call_cycles_add(0));
     assert_api_supported(api.ic0_call_cycles_add128(Cycles::new(0)));
     assert_api_supported(api.ic0_call_perform());
@@ -1041,6 +1084,8 @@ fn test_canister_heartbeat_support_nns() {
     assert_api_supported(api.ic0_stable64_write(0, 0, 0, &[]));
     assert_api_supported(api.ic0_time());
     assert_api_supported(api.ic0_canister_version());
+    assert_api_supported(api.ic0_is_controller(0, 0, &[]));
+    assert_api_supported(api.ic0_in_replicated_execution());
     assert_api_supported(api.ic0_global_timer_set(time::UNIX_EPOCH));
     assert_api_supported(
         api.ic0_performance_counter(PerformanceCounterType::Instructions(0.into())),
@@ -1208,7 +1253,7 @@ fn test_canister_balance() {
         .call_context_manager_mut()
         .unwrap()
         .new_call_context(
-            CallOrigin::CanisterUpdate(canister_test_id(33), CallbackId::from(5)),
+            CallOrigin::CanisterUpdate(canister_test_id(33), CallbackId::from(5), NO_DEADLINE),
             Cycles::new(50),
             Time::from_nanos_since_unix_epoch(0),
         );
@@ -1236,7 +1281,7 @@ fn test_canister_cycle_balance() {
         .call_context_manager_mut()
         .unwrap()
         .new_call_context(
-            CallOrigin::CanisterUpdate(canister_test_id(33), CallbackId::from(5)),
+            CallOrigin::CanisterUpdate(canister_test_id(33), CallbackId::from(5), NO_DEADLINE),
             Cycles::new(50),
             Time::from_nanos_since_unix_epoch(0),
         );
@@ -1270,7 +1315,7 @@ fn test_msg_cycles_available_traps() {
         .call_context_manager_mut()
         .unwrap()
         .new_call_context(
-            CallOrigin::CanisterUpdate(canister_test_id(33), CallbackId::from(5)),
+            CallOrigin::CanisterUpdate(canister_test_id(33), CallbackId::from(5), NO_DEADLINE),
             available_cycles,
             Time::from_nanos_since_unix_epoch(0),
         );
@@ -1421,6 +1466,77 @@ fn canister_status() {
     assert_eq!(api.ic0_canister_status(), Ok(3));
 }
 
+#[test]
+fn is_controller() {
+    let cycles_account_manager = CyclesAccountManagerBuilder::new().build();
+    let mut system_state = get_system_state_with_cycles(INITIAL_CYCLES);
+    system_state.controllers.insert(user_test_id(25).get());
+    let api = get_system_api(
+        ApiTypeBuilder::build_update_api(),
+        &system_state,
+        cycles_account_manager,
+    );
+
+    let controller = user_test_id(24).get().to_vec();
+    assert_eq!(
+        api.ic0_is_controller(0, controller.len() as u32, &controller),
+        Ok(1)
+    );
+    let controller = user_test_id(25).get().to_vec();
+    assert_eq!(
+        api.ic0_is_controller(0, controller.len() as u32, &controller),
+        Ok(1)
+    );
+    let other = user_test_id(26).get().to_vec();
+    assert_eq!(api.ic0_is_controller(0, other.len() as u32, &other), Ok(0));
+
+    // Out of bounds or invalid principals trap.
+    assert!(api
+        .ic0_is_controller(1, controller.len() as u32, &controller)
+        .is_err());
+    let too_long = vec![0; 30];
+    assert!(api.ic0_is_controller(0, 30, &too_long).is_err());
+}
+
+#[test]
+fn in_replicated_execution() {
+    let cycles_account_manager = CyclesAccountManagerBuilder::new().build();
+    let system_state = get_system_state_with_cycles(INITIAL_CYCLES);
+    let api = get_system_api(
+        ApiTypeBuilder::build_update_api(),
+        &system_state,
+        cycles_account_manager,
+    );
+    assert_eq!(api.ic0_in_replicated_execution(), Ok(1));
+
+    let api = SystemApiImpl::new(
+        ApiType::non_replicated_query(
+            mock_time(),
+            user_test_id(1).get(),
+            subnet_test_id(1),
+            vec![],
+            None,
+            NonReplicatedQueryKind::Pure,
+        ),
+        SandboxSafeSystemState::new(
+            &system_state,
+            cycles_account_manager,
+            &NetworkTopology::default(),
+            SchedulerConfig::application_subnet().dirty_page_overhead,
+        ),
+        CANISTER_CURRENT_MEMORY_USAGE,
+        ExecutionParameters {
+            execution_mode: ExecutionMode::NonReplicated,
+            ..execution_parameters()
+        },
+        SubnetAvailableMemory::new(i64::MAX / 2, i64::MAX / 2),
+        Memory::default(),
+        Arc::new(DefaultOutOfInstructionsHandler {}),
+        no_op_logger(),
+    );
+    assert_eq!(api.ic0_in_replicated_execution(), Ok(0));
+}
+
 /// msg_cycles_accept() can accept all cycles in call context
 #[test]
 fn msg_cycles_accept_all_cycles_in_call_context() {
@@ -1431,7 +1547,7 @@ fn msg_cycles_accept_all_cycles_in_call_context() {
         .call_context_manager_mut()
         .unwrap()
         .new_call_context(
-            CallOrigin::CanisterUpdate(canister_test_id(33), CallbackId::from(5)),
+            CallOrigin::CanisterUpdate(canister_test_id(33), CallbackId::from(5), NO_DEADLINE),
             Cycles::from(amount),
             Time::from_nanos_since_unix_epoch(0),
         );
@@ -1454,7 +1570,7 @@ fn msg_cycles_accept_all_cycles_in_call_context_when_more_asked() {
         .call_context_manager_mut()
         .unwrap()
         .new_call_context(
-            CallOrigin::CanisterUpdate(canister_test_id(33), CallbackId::from(5)),
+            CallOrigin::CanisterUpdate(canister_test_id(33), CallbackId::from(5), NO_DEADLINE),
             Cycles::new(40),
             Time::from_nanos_since_unix_epoch(0),
         );
@@ -1485,7 +1601,7 @@ fn call_perform_not_enough_cycles_resets_state() {
         .call_context_manager_mut()
         .unwrap()
         .new_call_context(
-            CallOrigin::CanisterUpdate(canister_test_id(33), CallbackId::from(5)),
+            CallOrigin::CanisterUpdate(canister_test_id(33), CallbackId::from(5), NO_DEADLINE),
             Cycles::new(40),
             Time::from_nanos_since_unix_epoch(0),
         );
@@ -1645,6 +1761,7 @@ fn take_execution_result_properly_frees_memory() {
             WasmClosure::new(0, 0),
             WasmClosure::new(0, 0),
             None,
+            NO_DEADLINE,
         ))
         .unwrap();
     let mut api = SystemApiImpl::new(
@@ -1709,6 +1826,7 @@ fn push_output_request_respects_memory_limits() {
                 WasmClosure::new(0, 0),
                 WasmClosure::new(0, 0),
                 None,
+                NO_DEADLINE,
             ))
             .unwrap();
         let mut api = SystemApiImpl::new(
@@ -1812,6 +1930,7 @@ fn push_output_request_oversized_request_memory_limits() {
             WasmClosure::new(0, 0),
             WasmClosure::new(0, 0),
             None,
+            NO_DEADLINE,
         ))
         .unwrap();
     let mut api = SystemApiImpl::new(
@@ -1,13 +1,14 @@
 use async_trait::async_trait;
 use ic_crypto_tls_interfaces::{
-    AllowedClients, AuthenticatedPeer, TlsClientHandshakeError, TlsHandshake,
-    TlsServerHandshakeError, TlsStream,
+    AllowedClients, AuthenticatedPeer, RegistryVersionProvider, TlsClientHandshakeError, TlsConfig,
+    TlsConfigError, TlsHandshake, TlsServerHandshakeError, TlsStream,
 };
 use ic_types::{NodeId, RegistryVersion};
 use tokio::net::TcpStream;
+use tokio_rustls::rustls::{Certificate, ClientConfig, ServerConfig};
 
-/// This implementation of TlsHandshake is so fake that it panics if
-/// you try to call any of the methods.
+/// This implementation of TlsHandshake and TlsConfig is so fake that it
+/// panics if you try to call any of the methods.
 pub struct FakeTlsHandshake;
 
 impl FakeTlsHandshake {
@@ -50,3 +51,28 @@ impl TlsHandshake for FakeTlsHandshake {
         unimplemented!()
     }
 }
+
+impl TlsConfig for FakeTlsHandshake {
+    fn server_config(
+        &self,
+        _allowed_clients: AllowedClients,
+        _registry_version: RegistryVersionProvider,
+    ) -> Result<ServerConfig, TlsConfigError> {
+        unimplemented!()
+    }
+
+    fn client_config(
+        &self,
+        _server: NodeId,
+        _registry_version: RegistryVersion,
+    ) -> Result<ClientConfig, TlsConfigError> {
+        unimplemented!()
+    }
+
+    fn authenticated_peer(
+        &self,
+        _peer_certificates: &[Certificate],
+    ) -> Result<AuthenticatedPeer, TlsServerHandshakeError> {
+        unimplemented!()
+    }
+}
@@ -24,7 +24,7 @@ use ic_replicated_state::{
     CallContext, CallOrigin, CanisterState, CanisterStatus, ExecutionState, ExportedFunctions,
     InputQueueType, Memory, NumWasmPages, ReplicatedState, SchedulerState, SystemState,
 };
-use ic_types::messages::CallbackId;
+use ic_types::messages::{CallbackId, NO_DEADLINE};
 use ic_types::methods::{Callback, WasmClosure};
 use ic_types::time::UNIX_EPOCH;
 use ic_types::{
@@ -676,7 +676,7 @@ pub fn register_callback(
         .call_context_manager_mut()
         .unwrap();
     let call_context_id = call_context_manager.new_call_context(
-        CallOrigin::CanisterUpdate(originator, callback_id),
+        CallOrigin::CanisterUpdate(originator, callback_id, NO_DEADLINE),
         Cycles::zero(),
         Time::from_nanos_since_unix_epoch(0),
     );
@@ -691,6 +691,7 @@ pub fn register_callback(
         WasmClosure::new(0, 2),
         WasmClosure::new(0, 2),
         None,
+        NO_DEADLINE,
     ));
 }
 
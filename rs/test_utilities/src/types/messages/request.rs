@@ -1,7 +1,7 @@
 use crate::types::ids::canister_test_id;
 use ic_types::{
-    messages::{CallbackId, Request},
-    CanisterId, Cycles,
+    messages::{CallbackId, Request, NO_DEADLINE},
+    CanisterId, CoarseTime, Cycles,
 };
 
 pub struct RequestBuilder {
@@ -17,6 +17,7 @@ impl Default for RequestBuilder {
                 receiver: canister_test_id(0),
                 sender: canister_test_id(1),
                 sender_reply_callback: CallbackId::from(0),
+                deadline: NO_DEADLINE,
                 payment: Cycles::zero(),
                 method_name: name.to_string(),
                 method_payload: Vec::new(),
@@ -67,6 +68,12 @@ impl RequestBuilder {
         self
     }
 
+    /// Sets the deadline attribute.
+    pub fn deadline(mut self, deadline: CoarseTime) -> Self {
+        self.request.deadline = deadline;
+        self
+    }
+
     pub fn build(self) -> Request {
         self.request
     }
@@ -1,7 +1,7 @@
 use crate::types::ids::canister_test_id;
 use ic_types::{
-    messages::{CallbackId, Payload, Response},
-    CanisterId, Cycles,
+    messages::{CallbackId, Payload, Response, NO_DEADLINE},
+    CanisterId, CoarseTime, Cycles,
 };
 
 pub struct ResponseBuilder {
@@ -17,6 +17,7 @@ impl Default for ResponseBuilder {
                 originator: canister_test_id(0),
                 respondent: canister_test_id(1),
                 originator_reply_callback: CallbackId::from(0),
+                deadline: NO_DEADLINE,
                 refund: Cycles::zero(),
                 response_payload: rpb.build(),
             },
@@ -59,6 +60,12 @@ impl ResponseBuilder {
         self
     }
 
+    /// Sets the deadline field.
+    pub fn deadline(mut self, deadline: CoarseTime) -> Self {
+        self.response.deadline = deadline;
+        self
+    }
+
     pub fn build(&self) -> Response {
         self.response.clone()
     }
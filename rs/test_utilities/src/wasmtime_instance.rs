@@ -11,7 +11,7 @@ use ic_system_api::{
     sandbox_safe_system_state::SandboxSafeSystemState, ExecutionParameters, InstructionLimits,
     ModificationTracking, SystemApiImpl,
 };
-use ic_types::{ComputeAllocation, NumInstructions};
+use ic_types::{ComputeAllocation, NumInstructions, ResourceSaturation};
 use ic_wasm_types::BinaryEncodedWasm;
 
 use crate::{
@@ -114,6 +114,8 @@ impl WasmtimeInstanceBuilder {
                     self.num_instructions,
                 ),
                 canister_memory_limit: ic_types::NumBytes::from(4 << 30),
+                wasm_memory_limit: None,
+                subnet_memory_saturation: ResourceSaturation::default(),
                 compute_allocation: ComputeAllocation::default(),
                 subnet_type: self.subnet_type,
                 execution_mode: ExecutionMode::Replicated,
@@ -1230,6 +1230,7 @@ pub fn create_canister_with_empty_settings(env: TestEnv) {
     let settings = CanisterSettingsArgs::default();
     let records = CreateCanisterArgs {
         settings: Some(settings),
+        sender_canister_version: None,
     };
     let payload = records.encode();
     create_canister_test(env, payload);
@@ -1243,6 +1244,7 @@ pub fn create_canister_with_settings(env: TestEnv) {
     };
     let records = CreateCanisterArgs {
         settings: Some(settings),
+        sender_canister_version: None,
     };
     let payload = records.encode();
     create_canister_test(env, payload);
@@ -1304,6 +1306,7 @@ pub fn create_canister_with_freezing_threshold(env: TestEnv) {
                                 freezing_threshold: Some(candid::Nat::from(*valid_value)),
                                 ..Default::default()
                             }),
+                            sender_canister_version: None,
                         }
                         .encode(),
                         Cycles::from(2_000_000_000_000u64),
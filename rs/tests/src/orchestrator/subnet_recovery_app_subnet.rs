@@ -219,6 +219,7 @@ pub fn app_subnet_recovery_test(env: TestEnv, upgrade: bool, ecdsa: bool) {
         nns_url: nns_node.get_public_url(),
         replica_version: Some(master_version.clone()),
         key_file: Some(ssh_authorized_priv_keys_dir.join(ADMIN)),
+        local_nodes: None,
     };
 
     let mut unassigned_nodes = env.topology_snapshot().unassigned_nodes();
@@ -162,6 +162,7 @@ pub fn test(env: TestEnv) {
         nns_url: parent_nns_node.get_public_url(),
         replica_version: Some(ic_version.clone()),
         key_file: Some(ssh_authorized_priv_keys_dir.join(ADMIN)),
+        local_nodes: None,
     };
     let subnet_args = NNSRecoveryFailoverNodesArgs {
         subnet_id: topo_broken_ic.root_subnet_id(),
@@ -114,6 +114,7 @@ pub fn test(env: TestEnv) {
         nns_url: upload_node.get_public_url(),
         replica_version: Some(ic_version),
         key_file: Some(ssh_authorized_priv_keys_dir.join(ADMIN)),
+        local_nodes: None,
     };
 
     // unlike during a production recovery using the CLI, here we already know all of parameters ahead of time.
@@ -66,6 +66,9 @@ impl Backup {
                 start_height,
             })),
             data_root: None,
+            step: None,
+            step_canisters: vec![],
+            dump_instructions: false,
         };
         self.print_contents_of_dir(&self.local_store_path());
         self.print_contents_of_dir(&self.backup_dir);
@@ -91,6 +91,7 @@ pub(crate) fn empty_subnet_update() -> UpdateSubnetPayload {
         pfn_evaluation_period_ms: None,
         registry_poll_period_ms: None,
         retransmission_request_ms: None,
+        artifact_push_threshold_bytes: None,
         set_gossip_config_to_default: false,
         start_as_nns: None,
         subnet_type: None,
@@ -26,6 +26,26 @@ impl Drop for IntGaugeResource {
     }
 }
 
+/// All metrics of a transport implementation. A process that runs both the
+/// TLS/TCP and the QUIC implementation registers the metrics once and passes
+/// a clone to each implementation.
+#[derive(Clone)]
+pub(crate) struct TransportMetrics {
+    pub(crate) data_plane: DataPlaneMetrics,
+    pub(crate) control_plane: ControlPlaneMetrics,
+    pub(crate) send_queue: SendQueueMetrics,
+}
+
+impl TransportMetrics {
+    pub(crate) fn new(metrics_registry: MetricsRegistry) -> Self {
+        Self {
+            data_plane: DataPlaneMetrics::new(metrics_registry.clone()),
+            control_plane: ControlPlaneMetrics::new(metrics_registry.clone()),
+            send_queue: SendQueueMetrics::new(metrics_registry),
+        }
+    }
+}
+
 #[derive(Clone)]
 pub(crate) struct ControlPlaneMetrics {
     pub(crate) flow_state: IntGaugeVec,
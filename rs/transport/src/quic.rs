@@ -0,0 +1,848 @@
+//! QUIC based transport.
+//!
+//! As with the TLS/TCP transport, there is a single connection per peer,
+//! which is initiated by the node with the smaller node ID (see
+//! `connection_role`). Unlike the TLS/TCP transport, every transport channel
+//! (flow) is sent over its own unidirectional QUIC stream. A large message on
+//! one flow (e.g. a state sync chunk) therefore does not delay the messages
+//! of the other flows (e.g. consensus adverts).
+//!
+//! The write task of a flow is created when the first message is sent on the
+//! flow. It opens a stream, writes the channel ID (4 bytes, little endian)
+//! and then writes the messages framed with the transport header. The read
+//! task of a connection accepts the streams opened by the peer and reads the
+//! messages of all of them concurrently.
+//!
+//! The TLS configurations are obtained from crypto, so the node TLS
+//! certificates from the registry are used exactly as for TLS/TCP. The server
+//! configuration is created once, when the event handler is set, but it looks
+//! up the client certificates at the latest registry version passed to
+//! `start_connection`, at the time of each handshake. It accepts every node in
+//! the registry; connections from nodes that are not peers are closed after
+//! the handshake. Liveness of the connections is tracked with QUIC keep-alives
+//! and the idle timeout, hence no heartbeats are sent.
+
+use crate::{
+    control_plane::connection_role,
+    data_plane::{pack_header, unpack_header, DEQUEUE_BYTES},
+    metrics::{
+        ControlPlaneMetrics, DataPlaneMetrics, IntGaugeResource, SendQueueMetrics,
+        TransportMetrics, STATUS_SUCCESS,
+    },
+    types::{ConnectionRole, QueueSize, SendQueue, SendQueueReader, TRANSPORT_HEADER_SIZE},
+    utils::{get_peer_label, SendQueueImpl},
+};
+use futures::stream::{FuturesUnordered, StreamExt};
+use ic_base_types::{NodeId, RegistryVersion};
+use ic_config::transport::TransportConfig;
+use ic_crypto_tls_interfaces::{
+    AllowedClients, AuthenticatedPeer, SomeOrAllNodes, TlsConfig, TlsConfigError,
+    TlsServerHandshakeError,
+};
+use ic_interfaces_transport::{
+    Transport, TransportChannelId, TransportError, TransportEvent, TransportEventHandler,
+    TransportMessage, TransportPayload,
+};
+use ic_logger::{info, warn, ReplicaLogger};
+use quinn::{
+    Connecting, Connection, Endpoint, Incoming, IncomingUniStreams, NewConnection, RecvStream,
+    VarInt,
+};
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+use std::sync::{Arc, Weak};
+use strum::AsRefStr;
+use tokio::{
+    runtime::Handle,
+    sync::{Mutex, RwLock},
+    task::JoinHandle,
+    time::{sleep, Duration},
+};
+use tower::Service;
+
+/// Label of the QUIC transport in the `transport_api` metrics label
+const QUIC_TRANSPORT_API: &str = "quic";
+
+/// ALPN protocol ID of the transport
+const ALPN_IC_TRANSPORT: &[u8] = b"ic-transport";
+
+/// The server name used when connecting. Nodes are authenticated by their
+/// certificate in the registry, so the name is not verified.
+const SERVER_NAME: &str = "domain.is-irrelevant-as-hostname-verification-is.disabled";
+
+/// Time to wait before retrying an unsuccessful connection attempt
+const CONNECT_RETRY_SECONDS: u64 = 3;
+
+/// Time to wait for the QUIC handshake (for both client/server sides)
+const HANDSHAKE_TIMEOUT_SECONDS: u64 = 30;
+
+/// Interval in which keep-alive packets are sent on idle connections
+const KEEP_ALIVE_INTERVAL_MS: u64 = 200;
+
+/// Time after which a connection without any packets from the peer is closed
+const IDLE_TIMEOUT_MS: u64 = 5000;
+
+/// Time the write task waits for messages in one dequeue call
+const DEQUEUE_TIMEOUT_MS: u64 = 1000;
+
+/// Error code sent to the peer when a connection is closed
+const CLOSE_CODE: u32 = 0;
+
+const CONNECT_TASK_NAME: &str = "quic_connect";
+const ACCEPT_TASK_NAME: &str = "quic_accept";
+const READ_TASK_NAME: &str = "quic_read";
+const WRITE_TASK_NAME: &str = "quic_write";
+
+#[derive(Debug, AsRefStr)]
+#[strum(serialize_all = "snake_case")]
+enum QuicHandshakeError {
+    DeadlineExceeded,
+    TlsConfig(TlsConfigError),
+    Connect(quinn::ConnectError),
+    Connection(quinn::ConnectionError),
+    MissingPeerCertificates,
+    UnauthenticatedPeer(TlsServerHandshakeError),
+}
+
+/// The connection state with a peer
+enum QuicConnectionState {
+    /// We are the server, waiting for the peer to connect
+    Listening,
+    /// We are the client, connection in progress
+    Connecting(JoinHandle<()>),
+    /// Connection established
+    Connected(QuicConnected),
+}
+
+/// Info about a connection in QuicConnectionState::Connected
+struct QuicConnected {
+    /// The QUIC connection
+    connection: Connection,
+    /// The task reading the streams opened by the peer
+    read_task: JoinHandle<()>,
+    /// The tasks writing the flows to the peer, one per flow
+    write_tasks: Vec<JoinHandle<()>>,
+}
+
+impl Drop for QuicConnectionState {
+    fn drop(&mut self) {
+        match &self {
+            Self::Connecting(connecting_task) => connecting_task.abort(),
+            Self::Connected(connected) => {
+                connected.read_task.abort();
+                for write_task in &connected.write_tasks {
+                    write_task.abort();
+                }
+                connected
+                    .connection
+                    .close(VarInt::from_u32(CLOSE_CODE), b"");
+            }
+            Self::Listening => (),
+        }
+    }
+}
+
+/// Per-peer state of the QUIC transport
+struct QuicPeerState {
+    /// Peer label, used for metrics
+    peer_label: String,
+    /// Address of the peer
+    peer_addr: SocketAddr,
+    /// The send queues, one per flow. Queues are created when the first
+    /// message is sent on the flow.
+    send_queues: HashMap<TransportChannelId, Box<dyn SendQueue + Send + Sync>>,
+    /// Connection state
+    connection_state: QuicConnectionState,
+}
+
+impl QuicPeerState {
+    fn is_connected(&self) -> bool {
+        matches!(self.connection_state, QuicConnectionState::Connected(_))
+    }
+}
+
+/// QUIC transport state struct
+pub(crate) struct QuicTransportImpl {
+    /// The node ID of this replica
+    node_id: NodeId,
+    /// The IP address of this node
+    node_ip: IpAddr,
+    /// Configuration
+    config: TransportConfig,
+
+    /// The QUIC endpoint used for incoming and outgoing connections
+    endpoint: std::sync::Mutex<Option<Endpoint>>,
+    /// Handle to the task accepting incoming connections
+    accept_task: std::sync::Mutex<Option<JoinHandle<()>>>,
+    /// Mapping of peers to their corresponding state
+    peer_map: RwLock<HashMap<NodeId, RwLock<QuicPeerState>>>,
+    /// Event handler to report back to the transport client
+    event_handler: Mutex<Option<TransportEventHandler>>,
+
+    /// The registry version that is used for the handshakes. It is shared with
+    /// the certificate verifier of the server configuration.
+    registry_version: Arc<std::sync::RwLock<RegistryVersion>>,
+    /// Reference to the crypto component
+    crypto: Arc<dyn TlsConfig + Send + Sync>,
+
+    /// Data plane metrics
+    data_plane_metrics: DataPlaneMetrics,
+    /// Control plane metrics
+    control_plane_metrics: ControlPlaneMetrics,
+    /// Send queue metrics
+    send_queue_metrics: SendQueueMetrics,
+
+    /// The tokio runtime
+    rt_handle: Handle,
+    /// Logger
+    log: ReplicaLogger,
+    /// Guarded self weak-reference
+    weak_self: std::sync::RwLock<Weak<QuicTransportImpl>>,
+}
+
+impl QuicTransportImpl {
+    /// Creates a new QUIC transport instance
+    pub(crate) fn new(
+        node_id: NodeId,
+        config: TransportConfig,
+        registry_version: RegistryVersion,
+        metrics: TransportMetrics,
+        crypto: Arc<dyn TlsConfig + Send + Sync>,
+        rt_handle: Handle,
+        log: ReplicaLogger,
+    ) -> Arc<Self> {
+        let node_ip = IpAddr::from_str(&config.node_ip)
+            .unwrap_or_else(|_| panic!("Invalid node IP: {}", &config.node_ip));
+        let arc = Arc::new(Self {
+            node_id,
+            node_ip,
+            config,
+            endpoint: std::sync::Mutex::new(None),
+            accept_task: std::sync::Mutex::new(None),
+            peer_map: RwLock::new(HashMap::new()),
+            event_handler: Mutex::new(None),
+            registry_version: Arc::new(std::sync::RwLock::new(registry_version)),
+            crypto,
+            data_plane_metrics: metrics.data_plane,
+            control_plane_metrics: metrics.control_plane,
+            send_queue_metrics: metrics.send_queue,
+            rt_handle,
+            log,
+            weak_self: std::sync::RwLock::new(Weak::new()),
+        });
+        *arc.weak_self.write().unwrap() = Arc::downgrade(&arc);
+        arc
+    }
+
+    /// Binds the QUIC endpoint and starts accepting connections
+    fn init_client(&self, event_handler: TransportEventHandler) {
+        // Binding the endpoint requires that we are within a tokio runtime context.
+        let _rt_enter_guard = self.rt_handle.enter();
+        let registry_version = Arc::clone(&self.registry_version);
+        let allowed_clients =
+            AllowedClients::new(SomeOrAllNodes::All).expect("Allowing all nodes is never empty");
+        let mut tls_config = self
+            .crypto
+            .server_config(
+                allowed_clients,
+                Arc::new(move || *registry_version.read().unwrap()),
+            )
+            .unwrap_or_else(|err| panic!("Failed to create the QUIC server config: {:?}", err));
+        tls_config.alpn_protocols = vec![ALPN_IC_TRANSPORT.to_vec()];
+        let mut server_config = quinn::ServerConfig::default();
+        server_config.transport = quic_transport_config();
+        server_config.crypto = Arc::new(tls_config);
+
+        let server_addr = SocketAddr::new(self.node_ip, self.config.listening_port);
+        let mut endpoint_builder = Endpoint::builder();
+        endpoint_builder.listen(server_config);
+        let (endpoint, incoming) = endpoint_builder.bind(&server_addr).unwrap_or_else(|err| {
+            panic!(
+                "Failed to bind the QUIC endpoint to {:?}: {:?}",
+                server_addr, err
+            )
+        });
+
+        *self.event_handler.blocking_lock() = Some(event_handler);
+        *self.endpoint.lock().unwrap() = Some(endpoint);
+        *self.accept_task.lock().unwrap() = Some(self.spawn_accept_task(incoming));
+    }
+
+    /// Starts the connection to a peer
+    fn start_peer_connection(
+        &self,
+        peer_id: &NodeId,
+        peer_addr: SocketAddr,
+        registry_version: RegistryVersion,
+    ) -> Result<(), TransportError> {
+        *self.registry_version.write().unwrap() = registry_version;
+        let mut peer_map = self.peer_map.blocking_write();
+        if peer_map.contains_key(peer_id) {
+            return Err(TransportError::AlreadyExists);
+        }
+
+        let connection_state = match connection_role(&self.node_id, peer_id) {
+            ConnectionRole::Server => QuicConnectionState::Listening,
+            ConnectionRole::Client => {
+                QuicConnectionState::Connecting(self.spawn_connect_task(*peer_id, peer_addr))
+            }
+        };
+        let peer_state = QuicPeerState {
+            peer_label: get_peer_label(&peer_addr.ip().to_string(), peer_id),
+            peer_addr,
+            send_queues: HashMap::new(),
+            connection_state,
+        };
+        peer_map.insert(*peer_id, RwLock::new(peer_state));
+        Ok(())
+    }
+
+    /// Starts the async task to accept incoming connections
+    fn spawn_accept_task(&self, mut incoming: Incoming) -> JoinHandle<()> {
+        let weak_self = self.weak_self.read().unwrap().clone();
+        let async_tasks_gauge_vec = self.control_plane_metrics.async_tasks.clone();
+        self.rt_handle.spawn(async move {
+            let task_gauge = async_tasks_gauge_vec.with_label_values(&[ACCEPT_TASK_NAME]);
+            let _gauge_guard = IntGaugeResource::new(task_gauge);
+            while let Some(connecting) = incoming.next().await {
+                // If the QuicTransportImpl has been deleted, abort.
+                let arc_self = match weak_self.upgrade() {
+                    Some(arc_self) => arc_self,
+                    _ => return,
+                };
+                let remote_addr = connecting.remote_address();
+                arc_self.rt_handle.clone().spawn(async move {
+                    match arc_self.accept(connecting).await {
+                        Ok((peer_id, new_connection)) => {
+                            arc_self.control_plane_metrics
+                                .tls_handshakes
+                                .with_label_values(&[ConnectionRole::Server.as_ref(), STATUS_SUCCESS])
+                                .inc();
+                            arc_self.on_connect(peer_id, new_connection).await;
+                        }
+                        Err(err) => {
+                            arc_self.control_plane_metrics
+                                .tls_handshakes
+                                .with_label_values(&[ConnectionRole::Server.as_ref(), err.as_ref()])
+                                .inc();
+                            warn!(
+                                arc_self.log,
+                                "QuicTransport::spawn_accept_task(): handshake failed: error = {:?}, \
+                                peer_addr = {:?}",
+                                err,
+                                remote_addr,
+                            );
+                        }
+                    }
+                });
+            }
+        })
+    }
+
+    /// Spawns a task that tries to connect to a peer (forever, or until the
+    /// connection is established or the peer is removed)
+    fn spawn_connect_task(&self, peer_id: NodeId, peer_addr: SocketAddr) -> JoinHandle<()> {
+        let endpoint = self
+            .endpoint
+            .lock()
+            .unwrap()
+            .clone()
+            .expect("The event handler must be set before starting connections");
+        let weak_self = self.weak_self.read().unwrap().clone();
+        let async_tasks_gauge_vec = self.control_plane_metrics.async_tasks.clone();
+        self.rt_handle.spawn(async move {
+            let task_gauge = async_tasks_gauge_vec.with_label_values(&[CONNECT_TASK_NAME]);
+            let _gauge_guard = IntGaugeResource::new(task_gauge);
+            let mut retries: u32 = 0;
+            loop {
+                retries += 1;
+                // If the QuicTransportImpl has been deleted, abort.
+                let arc_self = match weak_self.upgrade() {
+                    Some(arc_self) => arc_self,
+                    _ => return,
+                };
+                match arc_self.connect(&endpoint, peer_id, peer_addr).await {
+                    Ok(new_connection) => {
+                        arc_self
+                            .control_plane_metrics
+                            .tls_handshakes
+                            .with_label_values(&[ConnectionRole::Client.as_ref(), STATUS_SUCCESS])
+                            .inc();
+                        // Stop this task, the connection is either established now or the
+                        // peer was removed in the meantime.
+                        arc_self.on_connect(peer_id, new_connection).await;
+                        return;
+                    }
+                    Err(err) => {
+                        arc_self
+                            .control_plane_metrics
+                            .tls_handshakes
+                            .with_label_values(&[ConnectionRole::Client.as_ref(), err.as_ref()])
+                            .inc();
+                        warn!(
+                            arc_self.log,
+                            "QuicTransport::spawn_connect_task(): connect failed: error = {:?}, \
+                            peer = {:?}/{:?}, retries = {}",
+                            err,
+                            peer_id,
+                            peer_addr,
+                            retries,
+                        );
+                    }
+                }
+                sleep(Duration::from_secs(CONNECT_RETRY_SECONDS)).await;
+            }
+        })
+    }
+
+    /// Connects to a peer as the client
+    async fn connect(
+        &self,
+        endpoint: &Endpoint,
+        peer_id: NodeId,
+        peer_addr: SocketAddr,
+    ) -> Result<NewConnection, QuicHandshakeError> {
+        let registry_version = *self.registry_version.read().unwrap();
+        let mut tls_config = self
+            .crypto
+            .client_config(peer_id, registry_version)
+            .map_err(QuicHandshakeError::TlsConfig)?;
+        tls_config.alpn_protocols = vec![ALPN_IC_TRANSPORT.to_vec()];
+        let client_config = quinn::ClientConfig {
+            transport: quic_transport_config(),
+            crypto: Arc::new(tls_config),
+        };
+        let connecting = endpoint
+            .connect_with(client_config, &peer_addr, SERVER_NAME)
+            .map_err(QuicHandshakeError::Connect)?;
+        complete_handshake(connecting).await
+    }
+
+    /// Completes an incoming connection as the server and authenticates the
+    /// peer
+    async fn accept(
+        &self,
+        connecting: Connecting,
+    ) -> Result<(NodeId, NewConnection), QuicHandshakeError> {
+        let new_connection = complete_handshake(connecting).await?;
+        let peer_certificates: Vec<_> = new_connection
+            .connection
+            .authentication_data()
+            .peer_certificates
+            .ok_or(QuicHandshakeError::MissingPeerCertificates)?
+            .iter()
+            .cloned()
+            .collect();
+        let AuthenticatedPeer::Node(peer_id) = self
+            .crypto
+            .authenticated_peer(&peer_certificates)
+            .map_err(QuicHandshakeError::UnauthenticatedPeer)?;
+        Ok((peer_id, new_connection))
+    }
+
+    /// Sets up the read and write tasks of a new connection. The connection
+    /// is closed if the peer is unknown or not expected to connect to us.
+    async fn on_connect(&self, peer_id: NodeId, new_connection: NewConnection) {
+        let NewConnection {
+            connection,
+            uni_streams,
+            ..
+        } = new_connection;
+        let close = |reason: &str| {
+            info!(
+                self.log,
+                "QuicTransport::on_connect(): closing connection: peer_id = {:?}, reason = {}",
+                peer_id,
+                reason
+            );
+            connection.close(VarInt::from_u32(CLOSE_CODE), reason.as_bytes());
+        };
+
+        let peer_map = self.peer_map.read().await;
+        let peer_state_mu = match peer_map.get(&peer_id) {
+            Some(peer_state) => peer_state,
+            None => return close("unknown peer"),
+        };
+        let mut peer_state = peer_state_mu.write().await;
+        let peer_state = &mut *peer_state;
+        let expected = match (
+            connection_role(&self.node_id, &peer_id),
+            &peer_state.connection_state,
+        ) {
+            // The peer reconnects, so it considers the existing connection dead.
+            (ConnectionRole::Server, _) => true,
+            (ConnectionRole::Client, QuicConnectionState::Connecting(_)) => true,
+            (ConnectionRole::Client, _) => false,
+        };
+        if !expected {
+            return close("unexpected connection");
+        }
+        let mut event_handler = match self.event_handler.lock().await.as_ref() {
+            Some(event_handler) => event_handler.clone(),
+            None => return close("no event handler"),
+        };
+        if peer_state.is_connected() {
+            event_handler
+                .call(TransportEvent::PeerDown(peer_id))
+                .await
+                .expect("Can't panic on infallible");
+        }
+
+        let read_task = self.spawn_read_task(peer_id, uni_streams, event_handler.clone());
+        let write_tasks = peer_state
+            .send_queues
+            .iter_mut()
+            .map(|(channel_id, send_queue)| {
+                self.spawn_write_task(
+                    peer_id,
+                    *channel_id,
+                    connection.clone(),
+                    send_queue.get_reader(),
+                )
+            })
+            .collect();
+        event_handler
+            .call(TransportEvent::PeerUp(peer_id))
+            .await
+            .expect("Can't panic on infallible");
+        peer_state.connection_state = QuicConnectionState::Connected(QuicConnected {
+            connection,
+            read_task,
+            write_tasks,
+        });
+    }
+
+    /// Tears down the connection to a peer and, if we are the client,
+    /// reconnects
+    async fn on_disconnect(&self, peer_id: NodeId) {
+        let peer_map = self.peer_map.read().await;
+        let peer_state_mu = match peer_map.get(&peer_id) {
+            Some(peer_state) => peer_state,
+            None => return,
+        };
+        let mut peer_state = peer_state_mu.write().await;
+        if !peer_state.is_connected() {
+            return;
+        }
+        let mut event_handler = match self.event_handler.lock().await.as_ref() {
+            Some(event_handler) => event_handler.clone(),
+            None => return,
+        };
+        self.control_plane_metrics
+            .retry_connection
+            .with_label_values(&[&peer_id.to_string(), "", QUIC_TRANSPORT_API])
+            .inc();
+
+        let connection_state = match connection_role(&self.node_id, &peer_id) {
+            ConnectionRole::Server => {
+                warn!(
+                    self.log,
+                    "QuicTransport::on_disconnect(): waiting for peer to reconnect: peer_id = {:?}",
+                    peer_id
+                );
+                QuicConnectionState::Listening
+            }
+            ConnectionRole::Client => {
+                warn!(
+                    self.log,
+                    "QuicTransport::on_disconnect(): spawning reconnect task: peer = {:?}/{:?}",
+                    peer_id,
+                    peer_state.peer_addr,
+                );
+                QuicConnectionState::Connecting(
+                    self.spawn_connect_task(peer_id, peer_state.peer_addr),
+                )
+            }
+        };
+        event_handler
+            .call(TransportEvent::PeerDown(peer_id))
+            .await
+            .expect("Can't panic on infallible");
+        peer_state.connection_state = connection_state;
+    }
+
+    /// Spawns the task that accepts the streams opened by the peer and reads
+    /// the messages from them
+    fn spawn_read_task(
+        &self,
+        peer_id: NodeId,
+        mut uni_streams: IncomingUniStreams,
+        event_handler: TransportEventHandler,
+    ) -> JoinHandle<()> {
+        let weak_self = self.weak_self.read().unwrap().clone();
+        let data_plane_metrics = self.data_plane_metrics.clone();
+        let log = self.log.clone();
+        let async_tasks_gauge_vec = self.control_plane_metrics.async_tasks.clone();
+        self.rt_handle.spawn(async move {
+            let task_gauge = async_tasks_gauge_vec.with_label_values(&[READ_TASK_NAME]);
+            let _gauge_guard = IntGaugeResource::new(task_gauge);
+            let mut streams = FuturesUnordered::new();
+            loop {
+                tokio::select! {
+                    incoming = uni_streams.next() => match incoming {
+                        Some(Ok(recv_stream)) => streams.push(read_stream(
+                            peer_id,
+                            recv_stream,
+                            event_handler.clone(),
+                            data_plane_metrics.clone(),
+                        )),
+                        Some(Err(err)) => {
+                            info!(
+                                log,
+                                "QuicTransport::spawn_read_task(): connection lost: \
+                                peer_id = {:?}, error = {:?}",
+                                peer_id,
+                                err,
+                            );
+                            break;
+                        }
+                        None => break,
+                    },
+                    Some((channel_id, err)) = streams.next(), if !streams.is_empty() => {
+                        data_plane_metrics
+                            .message_read_errors_total
+                            .with_label_values(&[
+                                &channel_id.map(|id| id.to_string()).unwrap_or_default(),
+                                read_error_label(&err),
+                                QUIC_TRANSPORT_API,
+                            ])
+                            .inc();
+                    }
+                }
+            }
+            if let Some(arc_self) = weak_self.upgrade() {
+                arc_self.on_disconnect(peer_id).await;
+            }
+        })
+    }
+
+    /// Spawns the task that writes the messages of a flow to a stream
+    fn spawn_write_task(
+        &self,
+        peer_id: NodeId,
+        channel_id: TransportChannelId,
+        connection: Connection,
+        mut send_queue_reader: Box<dyn SendQueueReader + Send + Sync>,
+    ) -> JoinHandle<()> {
+        let data_plane_metrics = self.data_plane_metrics.clone();
+        let log = self.log.clone();
+        let async_tasks_gauge_vec = self.control_plane_metrics.async_tasks.clone();
+        self.rt_handle.spawn(async move {
+            let task_gauge = async_tasks_gauge_vec.with_label_values(&[WRITE_TASK_NAME]);
+            let _gauge_guard = IntGaugeResource::new(task_gauge);
+            let channel_id_str = channel_id.to_string();
+            let mut send_stream = match connection.open_uni().await {
+                Ok(send_stream) => send_stream,
+                Err(err) => {
+                    info!(
+                        log,
+                        "QuicTransport::spawn_write_task(): failed to open stream: \
+                        peer_id = {:?}, channel_id = {:?}, error = {:?}",
+                        peer_id,
+                        channel_id,
+                        err,
+                    );
+                    return;
+                }
+            };
+            let mut bytes_to_send = channel_id.get().to_le_bytes().to_vec();
+            loop {
+                for mut payload in send_queue_reader
+                    .dequeue(DEQUEUE_BYTES, Duration::from_millis(DEQUEUE_TIMEOUT_MS))
+                    .await
+                {
+                    bytes_to_send.append(&mut pack_header(Some(&payload), false));
+                    bytes_to_send.append(&mut payload.0);
+                }
+                if bytes_to_send.is_empty() {
+                    continue;
+                }
+                let start_time = std::time::Instant::now();
+                if let Err(err) = send_stream.write_all(&bytes_to_send).await {
+                    // The read task detects lost connections, so just stop writing.
+                    info!(
+                        log,
+                        "QuicTransport::spawn_write_task(): failed to write payload: \
+                        peer_id = {:?}, channel_id = {:?}, error = {:?}",
+                        peer_id,
+                        channel_id,
+                        err,
+                    );
+                    return;
+                }
+                data_plane_metrics
+                    .send_message_duration
+                    .with_label_values(&[&channel_id_str, QUIC_TRANSPORT_API])
+                    .observe(start_time.elapsed().as_secs_f64());
+                data_plane_metrics
+                    .write_bytes_total
+                    .with_label_values(&[&channel_id_str, QUIC_TRANSPORT_API])
+                    .inc_by(bytes_to_send.len() as u64);
+                bytes_to_send.clear();
+            }
+        })
+    }
+}
+
+impl Transport for QuicTransportImpl {
+    fn set_event_handler(&self, event_handler: TransportEventHandler) {
+        self.init_client(event_handler)
+    }
+
+    fn start_connection(
+        &self,
+        peer_id: &NodeId,
+        peer_addr: SocketAddr,
+        registry_version: RegistryVersion,
+    ) -> Result<(), TransportError> {
+        info!(
+            self.log,
+            "QuicTransport::start_connection(): peer_id = {:?}", peer_id
+        );
+        self.start_peer_connection(peer_id, peer_addr, registry_version)
+    }
+
+    fn stop_connection(&self, peer_id: &NodeId) {
+        info!(
+            self.log,
+            "QuicTransport::stop_connection(): peer_id = {:?}", peer_id
+        );
+        self.peer_map.blocking_write().remove(peer_id);
+    }
+
+    fn send(
+        &self,
+        peer_id: &NodeId,
+        channel_id: TransportChannelId,
+        message: TransportPayload,
+    ) -> Result<(), TransportError> {
+        let peer_map = self.peer_map.blocking_read();
+        let peer_state_mu = match peer_map.get(peer_id) {
+            Some(peer_state) => peer_state,
+            None => return Err(TransportError::NotFound),
+        };
+        let unsent = match peer_state_mu.blocking_read().send_queues.get(&channel_id) {
+            Some(send_queue) => send_queue.enqueue(message),
+            None => {
+                // First message on this flow, so create its queue and, if the
+                // peer is connected, its write task.
+                let mut peer_state = peer_state_mu.blocking_write();
+                let peer_state = &mut *peer_state;
+                if !peer_state.send_queues.contains_key(&channel_id) {
+                    let mut send_queue: Box<dyn SendQueue + Send + Sync> =
+                        Box::new(SendQueueImpl::new(
+                            peer_state.peer_label.clone(),
+                            channel_id,
+                            QueueSize::from(self.config.send_queue_size),
+                            self.send_queue_metrics.clone(),
+                        ));
+                    if let QuicConnectionState::Connected(connected) =
+                        &mut peer_state.connection_state
+                    {
+                        connected.write_tasks.push(self.spawn_write_task(
+                            *peer_id,
+                            channel_id,
+                            connected.connection.clone(),
+                            send_queue.get_reader(),
+                        ));
+                    }
+                    peer_state.send_queues.insert(channel_id, send_queue);
+                }
+                peer_state.send_queues[&channel_id].enqueue(message)
+            }
+        };
+        match unsent {
+            Some(unsent) => Err(TransportError::SendQueueFull(unsent)),
+            None => Ok(()),
+        }
+    }
+
+    fn clear_send_queues(&self, peer_id: &NodeId) {
+        let peer_map = self.peer_map.blocking_read();
+        if let Some(peer_state) = peer_map.get(peer_id) {
+            for send_queue in peer_state.blocking_write().send_queues.values_mut() {
+                send_queue.clear();
+            }
+        }
+    }
+}
+
+impl Drop for QuicTransportImpl {
+    fn drop(&mut self) {
+        if let Some(accept_task) = self.accept_task.lock().unwrap().take() {
+            accept_task.abort();
+        }
+    }
+}
+
+/// Returns the QUIC transport parameters used for all connections
+fn quic_transport_config() -> Arc<quinn::TransportConfig> {
+    let mut transport_config = quinn::TransportConfig::default();
+    transport_config
+        .max_idle_timeout(Some(Duration::from_millis(IDLE_TIMEOUT_MS)))
+        .expect("The idle timeout is a valid QUIC variable-length integer");
+    transport_config.keep_alive_interval(Some(Duration::from_millis(KEEP_ALIVE_INTERVAL_MS)));
+    Arc::new(transport_config)
+}
+
+/// Waits for the QUIC handshake of a connection to complete
+async fn complete_handshake(connecting: Connecting) -> Result<NewConnection, QuicHandshakeError> {
+    match tokio::time::timeout(Duration::from_secs(HANDSHAKE_TIMEOUT_SECONDS), connecting).await {
+        Err(_) => Err(QuicHandshakeError::DeadlineExceeded),
+        Ok(Ok(new_connection)) => Ok(new_connection),
+        Ok(Err(err)) => Err(QuicHandshakeError::Connection(err)),
+    }
+}
+
+/// Reads the channel ID and then the messages of a stream until the stream
+/// is finished or fails. Returns the channel ID, if it was read, and the
+/// reason the stream ended.
+async fn read_stream(
+    peer_id: NodeId,
+    mut recv_stream: RecvStream,
+    mut event_handler: TransportEventHandler,
+    data_plane_metrics: DataPlaneMetrics,
+) -> (Option<TransportChannelId>, quinn::ReadExactError) {
+    let mut channel_id_buffer = [0u8; 4];
+    if let Err(err) = recv_stream.read_exact(&mut channel_id_buffer).await {
+        return (None, err);
+    }
+    let channel_id = TransportChannelId::from(u32::from_le_bytes(channel_id_buffer));
+    let channel_id_str = channel_id.to_string();
+    loop {
+        let mut header_buffer = vec![0u8; TRANSPORT_HEADER_SIZE];
+        if let Err(err) = recv_stream.read_exact(&mut header_buffer).await {
+            return (Some(channel_id), err);
+        }
+        let header = unpack_header(header_buffer);
+        let mut payload_buffer = vec![0u8; header.payload_length as usize];
+        if let Err(err) = recv_stream.read_exact(&mut payload_buffer).await {
+            return (Some(channel_id), err);
+        }
+
+        data_plane_metrics
+            .read_bytes_total
+            .with_label_values(&[&channel_id_str, QUIC_TRANSPORT_API])
+            .inc_by(payload_buffer.len() as u64);
+        let _callback_start_time = data_plane_metrics
+            .event_handler_message_duration
+            .with_label_values(&[&channel_id_str, QUIC_TRANSPORT_API])
+            .start_timer();
+        event_handler
+            .call(TransportEvent::Message(TransportMessage {
+                peer_id,
+                payload: TransportPayload(payload_buffer),
+            }))
+            .await
+            .expect("Can't panic on infallible");
+    }
+}
+
+/// Returns the metrics label for the reason a stream ended
+fn read_error_label(err: &quinn::ReadExactError) -> &'static str {
+    match err {
+        quinn::ReadExactError::FinishedEarly => "finished",
+        quinn::ReadExactError::ReadError(_) => "failed",
+    }
+}
@@ -0,0 +1,167 @@
+//! Transport that delegates to either the TLS/TCP or the QUIC transport.
+//!
+//! The `quic_transport` subnet feature can be toggled in the registry at any
+//! time, so the owner of the transport calls `set_use_quic` whenever it
+//! observes a new value. When the selected implementation changes, the
+//! connections to all peers are stopped on the previously selected
+//! implementation and started on the newly selected one, with the peer
+//! addresses and registry versions of the original `start_connection` calls.
+//! As on a reconnect, messages that are still queued for sending on the
+//! previously selected implementation are dropped.
+//!
+//! An implementation is initialized, i.e. its event handler is set and it
+//! starts listening, the first time it is selected.
+
+use ic_base_types::{NodeId, RegistryVersion};
+use ic_interfaces_transport::{
+    Transport, TransportChannelId, TransportError, TransportEventHandler, TransportPayload,
+};
+use ic_logger::{info, warn, ReplicaLogger};
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, RwLock};
+
+/// See the module documentation.
+pub struct SwitchingTransport {
+    tcp: Arc<dyn Transport>,
+    quic: Arc<dyn Transport>,
+    state: RwLock<SwitchState>,
+    log: ReplicaLogger,
+}
+
+struct SwitchState {
+    use_quic: bool,
+    /// The event handler, set by the transport client.
+    event_handler: Option<TransportEventHandler>,
+    /// Whether the event handler was passed to the TLS/TCP implementation.
+    tcp_initialized: bool,
+    /// Whether the event handler was passed to the QUIC implementation.
+    quic_initialized: bool,
+    /// The address and registry version of each connected peer.
+    peers: BTreeMap<NodeId, (SocketAddr, RegistryVersion)>,
+}
+
+impl SwitchingTransport {
+    pub(crate) fn new(
+        tcp: Arc<dyn Transport>,
+        quic: Arc<dyn Transport>,
+        use_quic: bool,
+        log: ReplicaLogger,
+    ) -> Self {
+        Self {
+            tcp,
+            quic,
+            state: RwLock::new(SwitchState {
+                use_quic,
+                event_handler: None,
+                tcp_initialized: false,
+                quic_initialized: false,
+                peers: BTreeMap::new(),
+            }),
+            log,
+        }
+    }
+
+    /// Selects the QUIC implementation if `use_quic` is true and the TLS/TCP
+    /// implementation otherwise, moving the connections to all peers to the
+    /// selected implementation if it changes.
+    pub fn set_use_quic(&self, use_quic: bool) {
+        let mut state = self.state.write().unwrap();
+        if state.use_quic == use_quic {
+            return;
+        }
+        info!(
+            self.log,
+            "SwitchingTransport: moving {} peer connections to {}",
+            state.peers.len(),
+            if use_quic { "QUIC" } else { "TLS/TCP" }
+        );
+        for peer_id in state.peers.keys() {
+            self.selected(&state).stop_connection(peer_id);
+        }
+        state.use_quic = use_quic;
+        self.initialize_selected(&mut state);
+        for (peer_id, (peer_addr, registry_version)) in state.peers.iter() {
+            if let Err(err) =
+                self.selected(&state)
+                    .start_connection(peer_id, *peer_addr, *registry_version)
+            {
+                warn!(
+                    self.log,
+                    "SwitchingTransport: failed to start the connection to {:?}: {:?}",
+                    peer_id,
+                    err
+                );
+            }
+        }
+    }
+
+    /// Returns the selected implementation.
+    fn selected(&self, state: &SwitchState) -> &Arc<dyn Transport> {
+        if state.use_quic {
+            &self.quic
+        } else {
+            &self.tcp
+        }
+    }
+
+    /// Passes the event handler to the selected implementation, unless this
+    /// happened before or the event handler is not set yet.
+    fn initialize_selected(&self, state: &mut SwitchState) {
+        let event_handler = match &state.event_handler {
+            Some(event_handler) => event_handler.clone(),
+            None => return,
+        };
+        let initialized = if state.use_quic {
+            &mut state.quic_initialized
+        } else {
+            &mut state.tcp_initialized
+        };
+        if !*initialized {
+            *initialized = true;
+            self.selected(state).set_event_handler(event_handler);
+        }
+    }
+}
+
+impl Transport for SwitchingTransport {
+    fn set_event_handler(&self, event_handler: TransportEventHandler) {
+        let mut state = self.state.write().unwrap();
+        state.event_handler = Some(event_handler);
+        self.initialize_selected(&mut state);
+    }
+
+    fn start_connection(
+        &self,
+        peer_id: &NodeId,
+        peer_addr: SocketAddr,
+        registry_version: RegistryVersion,
+    ) -> Result<(), TransportError> {
+        let mut state = self.state.write().unwrap();
+        self.selected(&state)
+            .start_connection(peer_id, peer_addr, registry_version)?;
+        state.peers.insert(*peer_id, (peer_addr, registry_version));
+        Ok(())
+    }
+
+    fn stop_connection(&self, peer_id: &NodeId) {
+        let mut state = self.state.write().unwrap();
+        state.peers.remove(peer_id);
+        self.selected(&state).stop_connection(peer_id);
+    }
+
+    fn send(
+        &self,
+        peer_id: &NodeId,
+        channel_id: TransportChannelId,
+        message: TransportPayload,
+    ) -> Result<(), TransportError> {
+        let state = self.state.read().unwrap();
+        self.selected(&state).send(peer_id, channel_id, message)
+    }
+
+    fn clear_send_queues(&self, peer_id: &NodeId) {
+        let state = self.state.read().unwrap();
+        self.selected(&state).clear_send_queues(peer_id);
+    }
+}
@@ -66,11 +66,13 @@
 //!                              +-------------------------------+
 //! ```
 
-use crate::metrics::{ControlPlaneMetrics, DataPlaneMetrics, SendQueueMetrics};
+use crate::metrics::TransportMetrics;
+use crate::quic::QuicTransportImpl;
+pub use crate::switch::SwitchingTransport;
 use crate::types::TransportImpl;
 use ic_base_types::{NodeId, RegistryVersion};
 use ic_config::transport::TransportConfig;
-use ic_crypto_tls_interfaces::TlsHandshake;
+use ic_crypto_tls_interfaces::{TlsConfig, TlsHandshake};
 use ic_interfaces_transport::{
     Transport, TransportChannelId, TransportError, TransportEventHandler, TransportPayload,
 };
@@ -92,7 +94,7 @@ impl TransportImpl {
         node_id: NodeId,
         config: TransportConfig,
         registry_version: RegistryVersion,
-        metrics_registry: MetricsRegistry,
+        metrics: TransportMetrics,
         crypto: Arc<dyn TlsHandshake + Send + Sync>,
         rt_handle: Handle,
         log: ReplicaLogger,
@@ -108,9 +110,9 @@ impl TransportImpl {
             crypto,
             registry_version: Arc::new(RwLock::new(registry_version)),
             rt_handle,
-            data_plane_metrics: DataPlaneMetrics::new(metrics_registry.clone()),
-            control_plane_metrics: ControlPlaneMetrics::new(metrics_registry.clone()),
-            send_queue_metrics: SendQueueMetrics::new(metrics_registry),
+            data_plane_metrics: metrics.data_plane,
+            control_plane_metrics: metrics.control_plane,
+            send_queue_metrics: metrics.send_queue,
             log,
             peer_map: tokio::sync::RwLock::new(HashMap::new()),
             accept_port: Mutex::new(None),
@@ -138,7 +140,7 @@ pub fn create_transport(
         node_id,
         transport_config,
         registry_version,
-        metrics_registry,
+        TransportMetrics::new(metrics_registry),
         crypto,
         rt_handle,
         log,
@@ -146,6 +148,67 @@ pub fn create_transport(
     )
 }
 
+/// Returns the QUIC implementation of the `Transport` interfaces. Each
+/// `TransportChannelId` is sent over its own QUIC stream, see the `quic`
+/// module for details.
+pub fn create_quic_transport(
+    node_id: NodeId,
+    transport_config: TransportConfig,
+    registry_version: RegistryVersion,
+    metrics_registry: MetricsRegistry,
+    crypto: Arc<dyn TlsConfig + Send + Sync>,
+    rt_handle: Handle,
+    log: ReplicaLogger,
+) -> Arc<dyn Transport> {
+    QuicTransportImpl::new(
+        node_id,
+        transport_config,
+        registry_version,
+        TransportMetrics::new(metrics_registry),
+        crypto,
+        rt_handle,
+        log,
+    )
+}
+
+/// Returns a transport that uses the QUIC implementation if `use_quic` is
+/// true and the TLS/TCP implementation otherwise. The implementation can be
+/// changed at runtime with `SwitchingTransport::set_use_quic`.
+#[allow(clippy::too_many_arguments)]
+pub fn create_switching_transport(
+    node_id: NodeId,
+    transport_config: TransportConfig,
+    registry_version: RegistryVersion,
+    metrics_registry: MetricsRegistry,
+    tls_handshake: Arc<dyn TlsHandshake + Send + Sync>,
+    tls_config: Arc<dyn TlsConfig + Send + Sync>,
+    rt_handle: Handle,
+    log: ReplicaLogger,
+    use_quic: bool,
+) -> Arc<SwitchingTransport> {
+    let metrics = TransportMetrics::new(metrics_registry);
+    let tcp = TransportImpl::new(
+        node_id,
+        transport_config.clone(),
+        registry_version,
+        metrics.clone(),
+        tls_handshake,
+        rt_handle.clone(),
+        log.clone(),
+        false,
+    );
+    let quic = QuicTransportImpl::new(
+        node_id,
+        transport_config,
+        registry_version,
+        metrics,
+        tls_config,
+        rt_handle,
+        log.clone(),
+    );
+    Arc::new(SwitchingTransport::new(tcp, quic, use_quic, log))
+}
+
 /// Trait implementation for
 /// [`Transport`](../../ic_interfaces/transport/trait.Transport.html).
 impl Transport for TransportImpl {
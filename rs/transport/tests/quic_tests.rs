@@ -0,0 +1,285 @@
+mod common;
+
+use common::{
+    create_mock_event_handler, get_free_localhost_port, setup_peer_up_ack_event_handler,
+    temp_crypto_component_with_tls_keys_in_registry, RegistryAndDataProvider, REG_V1,
+};
+use ic_base_types::NodeId;
+use ic_config::transport::TransportConfig;
+use ic_interfaces_transport::{
+    Transport, TransportChannelId, TransportEvent, TransportEventHandler, TransportPayload,
+};
+use ic_logger::ReplicaLogger;
+use ic_metrics::MetricsRegistry;
+use ic_test_utilities_logger::with_test_replica_logger;
+use ic_transport::transport::{
+    create_quic_transport, create_switching_transport, SwitchingTransport,
+};
+use ic_types_test_utils::ids::{NODE_1, NODE_2};
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::mpsc::{channel, Sender};
+
+const NODE_ID_1: NodeId = NODE_1;
+const NODE_ID_2: NodeId = NODE_2;
+
+const TRANSPORT_CHANNEL_ID: u32 = 1234;
+
+/*
+Establish a QUIC connection between peers A and B and confirm both see the peer come up
+*/
+#[test]
+fn test_quic_start_connection_between_two_peers() {
+    with_test_replica_logger(|logger| {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+
+        let (peer_a_sender, mut peer_a_receiver) = channel(1);
+        let event_handler_1 = setup_peer_up_ack_event_handler(rt.handle().clone(), peer_a_sender);
+
+        let (peer_b_sender, mut peer_b_receiver) = channel(1);
+        let event_handler_2 = setup_peer_up_ack_event_handler(rt.handle().clone(), peer_b_sender);
+
+        let (_peer_a, _peer_b) = start_quic_connection_between_two_peers(
+            rt.handle().clone(),
+            logger,
+            event_handler_1,
+            event_handler_2,
+        );
+
+        assert_eq!(peer_a_receiver.blocking_recv(), Some(true));
+        assert_eq!(peer_b_receiver.blocking_recv(), Some(true));
+    });
+}
+
+/*
+Send messages on two channels from A->B and B->A and confirm each message is received on the
+channel it was sent on
+*/
+#[test]
+fn test_quic_send_on_multiple_channels() {
+    with_test_replica_logger(|logger| {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+
+        let (peer_a_sender, mut peer_a_receiver) = channel(2);
+        let peer_a_event_handler =
+            setup_message_ack_event_handler(rt.handle().clone(), peer_a_sender);
+
+        let (peer_b_sender, mut peer_b_receiver) = channel(2);
+        let peer_b_event_handler =
+            setup_message_ack_event_handler(rt.handle().clone(), peer_b_sender);
+
+        let (peer_a, peer_b) = start_quic_connection_between_two_peers(
+            rt.handle().clone(),
+            logger,
+            peer_a_event_handler,
+            peer_b_event_handler,
+        );
+
+        let small_msg = TransportPayload(vec![0xa; 100]);
+        // A state sync chunk sized message
+        let big_msg = TransportPayload(vec![0xb; 30_000_000]);
+        let channel_1 = TransportChannelId::from(1);
+        let channel_2 = TransportChannelId::from(2);
+
+        peer_a
+            .send(&NODE_ID_2, channel_1, big_msg.clone())
+            .expect("send");
+        peer_a
+            .send(&NODE_ID_2, channel_2, small_msg.clone())
+            .expect("send");
+        let mut received_by_b = vec![
+            peer_b_receiver.blocking_recv().unwrap(),
+            peer_b_receiver.blocking_recv().unwrap(),
+        ];
+        received_by_b.sort_by_key(|payload| payload.0.len());
+        assert_eq!(received_by_b, vec![small_msg.clone(), big_msg.clone()]);
+
+        peer_b
+            .send(&NODE_ID_1, channel_1, small_msg.clone())
+            .expect("send");
+        peer_b
+            .send(&NODE_ID_1, channel_2, big_msg.clone())
+            .expect("send");
+        let mut received_by_a = vec![
+            peer_a_receiver.blocking_recv().unwrap(),
+            peer_a_receiver.blocking_recv().unwrap(),
+        ];
+        received_by_a.sort_by_key(|payload| payload.0.len());
+        assert_eq!(received_by_a, vec![small_msg, big_msg]);
+    });
+}
+
+/*
+Start peers A and B on TLS/TCP, switch both to QUIC and confirm that messages from A->B are
+delivered before and after the switch
+*/
+#[test]
+fn test_switching_transport_moves_connections_to_quic() {
+    with_test_replica_logger(|logger| {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+
+        let (peer_a_sender, _peer_a_receiver) = channel(1);
+        let peer_a_event_handler =
+            setup_message_ack_event_handler(rt.handle().clone(), peer_a_sender);
+
+        let (peer_b_sender, mut peer_b_receiver) = channel(1);
+        let peer_b_event_handler =
+            setup_message_ack_event_handler(rt.handle().clone(), peer_b_sender);
+
+        let (peer_a, peer_b) = start_switching_connection_between_two_peers(
+            rt.handle().clone(),
+            logger,
+            peer_a_event_handler,
+            peer_b_event_handler,
+        );
+
+        let msg = TransportPayload(vec![0xa; 100]);
+        let channel_id = TransportChannelId::from(TRANSPORT_CHANNEL_ID);
+        peer_a
+            .send(&NODE_ID_2, channel_id, msg.clone())
+            .expect("send");
+        assert_eq!(peer_b_receiver.blocking_recv(), Some(msg.clone()));
+
+        peer_a.set_use_quic(true);
+        peer_b.set_use_quic(true);
+
+        peer_a
+            .send(&NODE_ID_2, channel_id, msg.clone())
+            .expect("send");
+        assert_eq!(peer_b_receiver.blocking_recv(), Some(msg));
+    });
+}
+
+fn setup_message_ack_event_handler(
+    rt: tokio::runtime::Handle,
+    connected: Sender<TransportPayload>,
+) -> TransportEventHandler {
+    let (event_handler, mut handle) = create_mock_event_handler();
+
+    rt.spawn(async move {
+        loop {
+            let (event, rsp) = handle.next_request().await.unwrap();
+            if let TransportEvent::Message(msg) = event {
+                connected.send(msg.payload).await.expect("Channel busy");
+            }
+            rsp.send_response(());
+        }
+    });
+    event_handler
+}
+
+fn start_quic_connection_between_two_peers(
+    rt_handle: tokio::runtime::Handle,
+    logger: ReplicaLogger,
+    event_handler_1: TransportEventHandler,
+    event_handler_2: TransportEventHandler,
+) -> (Arc<dyn Transport>, Arc<dyn Transport>) {
+    // Setup registry and crypto component
+    let registry_and_data = RegistryAndDataProvider::new();
+    let crypto_1 = temp_crypto_component_with_tls_keys_in_registry(&registry_and_data, NODE_ID_1);
+    let crypto_2 = temp_crypto_component_with_tls_keys_in_registry(&registry_and_data, NODE_ID_2);
+    registry_and_data.registry.update_to_latest_version();
+
+    let peer_1_port = get_free_localhost_port().expect("Failed to get free localhost port");
+    let peer_a = create_quic_transport(
+        NODE_ID_1,
+        transport_config(peer_1_port),
+        REG_V1,
+        MetricsRegistry::new(),
+        Arc::new(crypto_1),
+        rt_handle.clone(),
+        logger.clone(),
+    );
+    peer_a.set_event_handler(event_handler_1);
+
+    let peer_2_port = get_free_localhost_port().expect("Failed to get free localhost port");
+    let peer_b = create_quic_transport(
+        NODE_ID_2,
+        transport_config(peer_2_port),
+        REG_V1,
+        MetricsRegistry::new(),
+        Arc::new(crypto_2),
+        rt_handle,
+        logger,
+    );
+    peer_b.set_event_handler(event_handler_2);
+
+    let peer_1_addr = SocketAddr::from_str(&format!("127.0.0.1:{}", peer_1_port)).unwrap();
+    let peer_2_addr = SocketAddr::from_str(&format!("127.0.0.1:{}", peer_2_port)).unwrap();
+    peer_a
+        .start_connection(&NODE_ID_2, peer_2_addr, REG_V1)
+        .expect("start_connection");
+    peer_b
+        .start_connection(&NODE_ID_1, peer_1_addr, REG_V1)
+        .expect("start_connection");
+
+    (peer_a, peer_b)
+}
+
+fn start_switching_connection_between_two_peers(
+    rt_handle: tokio::runtime::Handle,
+    logger: ReplicaLogger,
+    event_handler_1: TransportEventHandler,
+    event_handler_2: TransportEventHandler,
+) -> (Arc<SwitchingTransport>, Arc<SwitchingTransport>) {
+    // Setup registry and crypto component
+    let registry_and_data = RegistryAndDataProvider::new();
+    let crypto_1 = Arc::new(temp_crypto_component_with_tls_keys_in_registry(
+        &registry_and_data,
+        NODE_ID_1,
+    ));
+    let crypto_2 = Arc::new(temp_crypto_component_with_tls_keys_in_registry(
+        &registry_and_data,
+        NODE_ID_2,
+    ));
+    registry_and_data.registry.update_to_latest_version();
+
+    let peer_1_port = get_free_localhost_port().expect("Failed to get free localhost port");
+    let peer_a = create_switching_transport(
+        NODE_ID_1,
+        transport_config(peer_1_port),
+        REG_V1,
+        MetricsRegistry::new(),
+        Arc::clone(&crypto_1) as Arc<_>,
+        crypto_1 as Arc<_>,
+        rt_handle.clone(),
+        logger.clone(),
+        false,
+    );
+    peer_a.set_event_handler(event_handler_1);
+
+    let peer_2_port = get_free_localhost_port().expect("Failed to get free localhost port");
+    let peer_b = create_switching_transport(
+        NODE_ID_2,
+        transport_config(peer_2_port),
+        REG_V1,
+        MetricsRegistry::new(),
+        Arc::clone(&crypto_2) as Arc<_>,
+        crypto_2 as Arc<_>,
+        rt_handle,
+        logger,
+        false,
+    );
+    peer_b.set_event_handler(event_handler_2);
+
+    let peer_1_addr = SocketAddr::from_str(&format!("127.0.0.1:{}", peer_1_port)).unwrap();
+    let peer_2_addr = SocketAddr::from_str(&format!("127.0.0.1:{}", peer_2_port)).unwrap();
+    peer_a
+        .start_connection(&NODE_ID_2, peer_2_addr, REG_V1)
+        .expect("start_connection");
+    peer_b
+        .start_connection(&NODE_ID_1, peer_1_addr, REG_V1)
+        .expect("start_connection");
+
+    (peer_a, peer_b)
+}
+
+fn transport_config(listening_port: u16) -> TransportConfig {
+    TransportConfig {
+        node_ip: "127.0.0.1".to_string(),
+        listening_port,
+        legacy_flow_tag: TRANSPORT_CHANNEL_ID,
+        send_queue_size: 10,
+    }
+}
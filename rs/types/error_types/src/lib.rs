@@ -22,6 +22,7 @@ pub enum RejectCode {
     DestinationInvalid = 3,
     CanisterReject = 4,
     CanisterError = 5,
+    SysUnknown = 6,
 }
 
 impl ToString for RejectCode {
@@ -38,6 +39,7 @@ impl RejectCode {
             RejectCode::DestinationInvalid => "DESTINATION_INVALID",
             RejectCode::CanisterReject => "CANISTER_REJECT",
             RejectCode::CanisterError => "CANISTER_ERROR",
+            RejectCode::SysUnknown => "SYS_UNKNOWN",
         }
     }
 }
@@ -51,6 +53,7 @@ impl TryFrom<u64> for RejectCode {
             3 => Ok(RejectCode::DestinationInvalid),
             4 => Ok(RejectCode::CanisterReject),
             5 => Ok(RejectCode::CanisterError),
+            6 => Ok(RejectCode::SysUnknown),
             _ => Err(TryFromError::ValueOutOfRange(code)),
         }
     }
@@ -68,6 +71,7 @@ impl From<ErrorCode> for RejectCode {
             CanisterMethodNotFound => DestinationInvalid,
             CanisterFunctionNotFound => CanisterError,
             CanisterWasmModuleNotFound => DestinationInvalid,
+            CanisterSnapshotNotFound => DestinationInvalid,
             CanisterAlreadyInstalled => DestinationInvalid,
             CanisterNonEmpty => CanisterError,
             CanisterOutOfCycles => CanisterError,
@@ -100,6 +104,11 @@ impl From<ErrorCode> for RejectCode {
             QueryCallGraphTooDeep => CanisterError,
             QueryCallGraphTotalInstructionLimitExceeded => CanisterError,
             CompositeQueryCalledInReplicatedMode => CanisterError,
+            CanisterWasmMemoryLimitExceeded => CanisterError,
+            ReservedCyclesLimitExceededInMemoryAllocation => CanisterError,
+            ReservedCyclesLimitExceededInMemoryGrow => CanisterError,
+            InsufficientCyclesInMemoryGrow => CanisterError,
+            InsufficientCyclesInMemoryAllocation => CanisterError,
             CanisterNotHostedBySubnet => CanisterReject,
         }
     }
@@ -122,6 +131,7 @@ pub enum ErrorCode {
     CanisterMethodNotFound = 302,
     CanisterAlreadyInstalled = 303,
     CanisterWasmModuleNotFound = 304,
+    CanisterSnapshotNotFound = 305,
     InsufficientMemoryAllocation = 402,
     InsufficientCyclesForCreateCanister = 403,
     SubnetNotFound = 404,
@@ -153,6 +163,11 @@ pub enum ErrorCode {
     QueryCallGraphTooDeep = 525,
     QueryCallGraphTotalInstructionLimitExceeded = 526,
     CompositeQueryCalledInReplicatedMode = 527,
+    CanisterWasmMemoryLimitExceeded = 528,
+    ReservedCyclesLimitExceededInMemoryAllocation = 529,
+    ReservedCyclesLimitExceededInMemoryGrow = 530,
+    InsufficientCyclesInMemoryGrow = 531,
+    InsufficientCyclesInMemoryAllocation = 532,
 }
 
 impl TryFrom<u64> for ErrorCode {
@@ -168,6 +183,7 @@ impl TryFrom<u64> for ErrorCode {
             302 => Ok(ErrorCode::CanisterMethodNotFound),
             303 => Ok(ErrorCode::CanisterAlreadyInstalled),
             304 => Ok(ErrorCode::CanisterWasmModuleNotFound),
+            305 => Ok(ErrorCode::CanisterSnapshotNotFound),
             402 => Ok(ErrorCode::InsufficientMemoryAllocation),
             403 => Ok(ErrorCode::InsufficientCyclesForCreateCanister),
             404 => Ok(ErrorCode::SubnetNotFound),
@@ -199,6 +215,11 @@ impl TryFrom<u64> for ErrorCode {
             525 => Ok(ErrorCode::QueryCallGraphTooDeep),
             526 => Ok(ErrorCode::QueryCallGraphTotalInstructionLimitExceeded),
             527 => Ok(ErrorCode::CompositeQueryCalledInReplicatedMode),
+            528 => Ok(ErrorCode::CanisterWasmMemoryLimitExceeded),
+            529 => Ok(ErrorCode::ReservedCyclesLimitExceededInMemoryAllocation),
+            530 => Ok(ErrorCode::ReservedCyclesLimitExceededInMemoryGrow),
+            531 => Ok(ErrorCode::InsufficientCyclesInMemoryGrow),
+            532 => Ok(ErrorCode::InsufficientCyclesInMemoryAllocation),
             _ => Err(TryFromError::ValueOutOfRange(err)),
         }
     }
@@ -7,7 +7,11 @@ use ic_base_types::{CanisterId, NodeId, NumBytes, PrincipalId, RegistryVersion,
 use ic_error_types::{ErrorCode, UserError};
 use ic_protobuf::registry::crypto::v1::PublicKey;
 use ic_protobuf::registry::subnet::v1::{InitialIDkgDealings, InitialNiDkgTranscriptRecord};
-use ic_protobuf::{proxy::ProxyDecodeError, registry::crypto::v1 as pb_registry_crypto};
+use ic_protobuf::{
+    proxy::{try_from_option_field, ProxyDecodeError},
+    registry::crypto::v1 as pb_registry_crypto,
+    state::canister_state_bits::v1 as pb_canister_state_bits,
+};
 use num_traits::cast::ToPrimitive;
 use serde::Serialize;
 use std::{collections::BTreeSet, convert::TryFrom, fmt, slice::Iter, str::FromStr};
@@ -42,6 +46,20 @@ pub enum Method {
     UninstallCode,
     UpdateSettings,
     ComputeInitialEcdsaDealings,
+    CanisterInfo,
+    FetchCanisterLogs,
+
+    // Canister snapshots.
+    TakeCanisterSnapshot,
+    LoadCanisterSnapshot,
+    ListCanisterSnapshots,
+    DeleteCanisterSnapshot,
+
+    // Chunked code installation.
+    UploadChunk,
+    StoredChunks,
+    ClearChunkStore,
+    InstallChunkedCode,
 
     // Bitcoin Interface.
     BitcoinGetBalance,
@@ -99,6 +117,8 @@ impl From<CanisterId> for CanisterIdRecord {
 ///     controller : principal;
 ///     compute_allocation: nat;
 ///     memory_allocation: opt nat;
+///     wasm_memory_limit: nat;
+///     reserved_cycles_limit: opt nat;
 /// })`
 #[derive(CandidType, Deserialize, Debug, Eq, PartialEq)]
 pub struct DefiniteCanisterSettingsArgs {
@@ -107,6 +127,8 @@ pub struct DefiniteCanisterSettingsArgs {
     compute_allocation: candid::Nat,
     memory_allocation: candid::Nat,
     freezing_threshold: candid::Nat,
+    wasm_memory_limit: candid::Nat,
+    reserved_cycles_limit: Option<candid::Nat>,
 }
 
 impl DefiniteCanisterSettingsArgs {
@@ -116,6 +138,8 @@ impl DefiniteCanisterSettingsArgs {
         compute_allocation: u64,
         memory_allocation: Option<u64>,
         freezing_threshold: u64,
+        wasm_memory_limit: Option<u64>,
+        reserved_cycles_limit: Option<u128>,
     ) -> Self {
         let memory_allocation = match memory_allocation {
             None => candid::Nat::from(0),
@@ -127,12 +151,26 @@ impl DefiniteCanisterSettingsArgs {
             compute_allocation: candid::Nat::from(compute_allocation),
             memory_allocation,
             freezing_threshold: candid::Nat::from(freezing_threshold),
+            wasm_memory_limit: candid::Nat::from(wasm_memory_limit.unwrap_or(0)),
+            reserved_cycles_limit: reserved_cycles_limit.map(candid::Nat::from),
         }
     }
 
     pub fn controllers(&self) -> Vec<PrincipalId> {
         self.controllers.clone()
     }
+
+    /// Returns the Wasm memory limit, where 0 means that there is no limit.
+    pub fn wasm_memory_limit(&self) -> u64 {
+        self.wasm_memory_limit.0.to_u64().unwrap()
+    }
+
+    /// Returns the limit on the reserved cycles balance, if any.
+    pub fn reserved_cycles_limit(&self) -> Option<u128> {
+        self.reserved_cycles_limit
+            .as_ref()
+            .map(|limit| limit.0.to_u128().unwrap())
+    }
 }
 
 impl Payload<'_> for DefiniteCanisterSettingsArgs {}
@@ -202,6 +240,8 @@ impl Payload<'_> for CanisterStatusResult {}
 ///     memory_size: nat;
 ///     cycles: nat;
 ///     idle_cycles_burned_per_day: nat;
+///     reserved_cycles: nat;
+///     query_stats: query_stats;
 /// })`
 #[derive(CandidType, Debug, Deserialize, Eq, PartialEq)]
 pub struct CanisterStatusResultV2 {
@@ -215,6 +255,8 @@ pub struct CanisterStatusResultV2 {
     balance: Vec<(Vec<u8>, candid::Nat)>,
     freezing_threshold: candid::Nat,
     idle_cycles_burned_per_day: candid::Nat,
+    reserved_cycles: candid::Nat,
+    query_stats: QueryStats,
 }
 
 impl CanisterStatusResultV2 {
@@ -229,7 +271,11 @@ impl CanisterStatusResultV2 {
         compute_allocation: u64,
         memory_allocation: Option<u64>,
         freezing_threshold: u64,
+        wasm_memory_limit: Option<u64>,
+        reserved_cycles: u128,
+        reserved_cycles_limit: Option<u128>,
         idle_cycles_burned_per_day: u128,
+        query_stats: QueryStats,
     ) -> Self {
         Self {
             status,
@@ -246,9 +292,13 @@ impl CanisterStatusResultV2 {
                 compute_allocation,
                 memory_allocation,
                 freezing_threshold,
+                wasm_memory_limit,
+                reserved_cycles_limit,
             ),
             freezing_threshold: candid::Nat::from(freezing_threshold),
             idle_cycles_burned_per_day: candid::Nat::from(idle_cycles_burned_per_day),
+            reserved_cycles: candid::Nat::from(reserved_cycles),
+            query_stats,
         }
     }
 
@@ -280,9 +330,76 @@ impl CanisterStatusResultV2 {
         self.freezing_threshold.0.to_u64().unwrap()
     }
 
+    /// Returns the Wasm memory limit, where 0 means that there is no limit.
+    pub fn wasm_memory_limit(&self) -> u64 {
+        self.settings.wasm_memory_limit()
+    }
+
+    /// Returns the limit on the reserved cycles balance, if any.
+    pub fn reserved_cycles_limit(&self) -> Option<u128> {
+        self.settings.reserved_cycles_limit()
+    }
+
+    /// Returns the cycles reserved for future storage payments.
+    pub fn reserved_cycles(&self) -> u128 {
+        self.reserved_cycles.0.to_u128().unwrap()
+    }
+
     pub fn idle_cycles_burned_per_day(&self) -> u128 {
         self.idle_cycles_burned_per_day.0.to_u128().unwrap()
     }
+
+    /// Returns the query statistics aggregated over all replicas of the subnet.
+    pub fn query_stats(&self) -> &QueryStats {
+        &self.query_stats
+    }
+}
+
+/// Struct used for encoding/decoding
+/// `(record {
+///     num_calls_total: nat;
+///     num_instructions_total: nat;
+///     request_payload_bytes_total: nat;
+///     response_payload_bytes_total: nat;
+/// })`
+#[derive(CandidType, Clone, Debug, Default, Deserialize, Eq, PartialEq)]
+pub struct QueryStats {
+    num_calls_total: candid::Nat,
+    num_instructions_total: candid::Nat,
+    request_payload_bytes_total: candid::Nat,
+    response_payload_bytes_total: candid::Nat,
+}
+
+impl QueryStats {
+    pub fn new(
+        num_calls_total: u64,
+        num_instructions_total: u64,
+        request_payload_bytes_total: u64,
+        response_payload_bytes_total: u64,
+    ) -> Self {
+        Self {
+            num_calls_total: candid::Nat::from(num_calls_total),
+            num_instructions_total: candid::Nat::from(num_instructions_total),
+            request_payload_bytes_total: candid::Nat::from(request_payload_bytes_total),
+            response_payload_bytes_total: candid::Nat::from(response_payload_bytes_total),
+        }
+    }
+
+    pub fn num_calls_total(&self) -> u64 {
+        self.num_calls_total.0.to_u64().unwrap()
+    }
+
+    pub fn num_instructions_total(&self) -> u64 {
+        self.num_instructions_total.0.to_u64().unwrap()
+    }
+
+    pub fn request_payload_bytes_total(&self) -> u64 {
+        self.request_payload_bytes_total.0.to_u64().unwrap()
+    }
+
+    pub fn response_payload_bytes_total(&self) -> u64 {
+        self.response_payload_bytes_total.0.to_u64().unwrap()
+    }
 }
 
 /// Indicates whether the canister is running, stopping, or stopped.
@@ -377,6 +494,601 @@ impl From<CanisterInstallMode> for String {
 
 impl Payload<'_> for CanisterStatusResultV2 {}
 
+impl From<CanisterInstallMode> for pb_canister_state_bits::CanisterInstallMode {
+    fn from(item: CanisterInstallMode) -> Self {
+        match item {
+            CanisterInstallMode::Install => pb_canister_state_bits::CanisterInstallMode::Install,
+            CanisterInstallMode::Reinstall => {
+                pb_canister_state_bits::CanisterInstallMode::Reinstall
+            }
+            CanisterInstallMode::Upgrade => pb_canister_state_bits::CanisterInstallMode::Upgrade,
+        }
+    }
+}
+
+impl TryFrom<pb_canister_state_bits::CanisterInstallMode> for CanisterInstallMode {
+    type Error = ProxyDecodeError;
+
+    fn try_from(item: pb_canister_state_bits::CanisterInstallMode) -> Result<Self, Self::Error> {
+        match item {
+            pb_canister_state_bits::CanisterInstallMode::Install => {
+                Ok(CanisterInstallMode::Install)
+            }
+            pb_canister_state_bits::CanisterInstallMode::Reinstall => {
+                Ok(CanisterInstallMode::Reinstall)
+            }
+            pb_canister_state_bits::CanisterInstallMode::Upgrade => {
+                Ok(CanisterInstallMode::Upgrade)
+            }
+            pb_canister_state_bits::CanisterInstallMode::Unspecified => {
+                Err(ProxyDecodeError::ValueOutOfRange {
+                    typ: "CanisterInstallMode",
+                    err: format!("Unable to convert {:?} to a CanisterInstallMode", item),
+                })
+            }
+        }
+    }
+}
+
+/// `CandidType` for `CanisterChangeFromUser`
+/// ```text
+/// record {
+///    user_id : principal;
+/// }
+/// ```
+#[derive(Clone, CandidType, Deserialize, Debug, Eq, PartialEq)]
+pub struct CanisterChangeFromUser {
+    user_id: PrincipalId,
+}
+
+/// `CandidType` for `CanisterChangeFromCanister`
+/// ```text
+/// record {
+///    canister_id : principal;
+///    canister_version : opt nat64;
+/// }
+/// ```
+#[derive(Clone, CandidType, Deserialize, Debug, Eq, PartialEq)]
+pub struct CanisterChangeFromCanister {
+    canister_id: PrincipalId,
+    canister_version: Option<u64>,
+}
+
+/// `CandidType` for `CanisterChangeOrigin`
+/// ```text
+/// variant {
+///   from_user : record {
+///     user_id : principal;
+///   };
+///   from_canister : record {
+///     canister_id : principal;
+///     canister_version : opt nat64;
+///   };
+/// }
+/// ```
+#[derive(Clone, CandidType, Deserialize, Debug, Eq, PartialEq)]
+pub enum CanisterChangeOrigin {
+    #[serde(rename = "from_user")]
+    CanisterChangeFromUser(CanisterChangeFromUser),
+    #[serde(rename = "from_canister")]
+    CanisterChangeFromCanister(CanisterChangeFromCanister),
+}
+
+impl CanisterChangeOrigin {
+    pub fn from_user(user_id: PrincipalId) -> CanisterChangeOrigin {
+        CanisterChangeOrigin::CanisterChangeFromUser(CanisterChangeFromUser { user_id })
+    }
+
+    pub fn from_canister(
+        canister_id: PrincipalId,
+        canister_version: Option<u64>,
+    ) -> CanisterChangeOrigin {
+        CanisterChangeOrigin::CanisterChangeFromCanister(CanisterChangeFromCanister {
+            canister_id,
+            canister_version,
+        })
+    }
+
+    /// The principal (user or canister) initiating the change.
+    pub fn origin(&self) -> PrincipalId {
+        match self {
+            CanisterChangeOrigin::CanisterChangeFromUser(change) => change.user_id,
+            CanisterChangeOrigin::CanisterChangeFromCanister(change) => change.canister_id,
+        }
+    }
+}
+
+/// `CandidType` for `CanisterCreationRecord`
+/// ```text
+/// record {
+///   controllers : vec principal;
+/// }
+/// ```
+#[derive(Clone, CandidType, Deserialize, Debug, Eq, PartialEq)]
+pub struct CanisterCreationRecord {
+    controllers: Vec<PrincipalId>,
+}
+
+impl CanisterCreationRecord {
+    pub fn controllers(&self) -> &[PrincipalId] {
+        &self.controllers
+    }
+}
+
+/// `CandidType` for `CanisterCodeDeploymentRecord`
+/// ```text
+/// record {
+///   mode : variant { install; reinstall; upgrade };
+///   module_hash : blob;
+/// }
+/// ```
+#[derive(Clone, CandidType, Deserialize, Debug, Eq, PartialEq)]
+pub struct CanisterCodeDeploymentRecord {
+    mode: CanisterInstallMode,
+    module_hash: [u8; 32],
+}
+
+impl CanisterCodeDeploymentRecord {
+    pub fn mode(&self) -> CanisterInstallMode {
+        self.mode
+    }
+
+    pub fn module_hash(&self) -> [u8; 32] {
+        self.module_hash
+    }
+}
+
+/// `CandidType` for `CanisterControllersChangeRecord`
+/// ```text
+/// record {
+///   controllers : vec principal;
+/// }
+/// ```
+#[derive(Clone, CandidType, Deserialize, Debug, Eq, PartialEq)]
+pub struct CanisterControllersChangeRecord {
+    controllers: Vec<PrincipalId>,
+}
+
+impl CanisterControllersChangeRecord {
+    pub fn controllers(&self) -> &[PrincipalId] {
+        &self.controllers
+    }
+}
+
+/// `CandidType` for `CanisterChangeDetails`
+/// ```text
+/// variant {
+///   creation : record {
+///     controllers : vec principal;
+///   };
+///   code_uninstall;
+///   code_deployment : record {
+///     mode : variant { install; reinstall; upgrade };
+///     module_hash : blob;
+///   };
+///   controllers_change : record {
+///     controllers : vec principal;
+///   };
+/// }
+/// ```
+#[derive(Clone, CandidType, Deserialize, Debug, Eq, PartialEq)]
+pub enum CanisterChangeDetails {
+    #[serde(rename = "creation")]
+    CanisterCreation(CanisterCreationRecord),
+    #[serde(rename = "code_uninstall")]
+    CanisterCodeUninstall,
+    #[serde(rename = "code_deployment")]
+    CanisterCodeDeployment(CanisterCodeDeploymentRecord),
+    #[serde(rename = "controllers_change")]
+    CanisterControllersChange(CanisterControllersChangeRecord),
+}
+
+impl CanisterChangeDetails {
+    pub fn canister_creation(controllers: Vec<PrincipalId>) -> CanisterChangeDetails {
+        CanisterChangeDetails::CanisterCreation(CanisterCreationRecord { controllers })
+    }
+
+    pub fn code_deployment(
+        mode: CanisterInstallMode,
+        module_hash: [u8; 32],
+    ) -> CanisterChangeDetails {
+        CanisterChangeDetails::CanisterCodeDeployment(CanisterCodeDeploymentRecord {
+            mode,
+            module_hash,
+        })
+    }
+
+    pub fn controllers_change(controllers: Vec<PrincipalId>) -> CanisterChangeDetails {
+        CanisterChangeDetails::CanisterControllersChange(CanisterControllersChangeRecord {
+            controllers,
+        })
+    }
+}
+
+/// `CandidType` for `CanisterChange`
+/// ```text
+/// record {
+///   timestamp_nanos : nat64;
+///   canister_version : nat64;
+///   origin : change_origin;
+///   details : change_details;
+/// }
+/// ```
+#[derive(Clone, CandidType, Deserialize, Debug, Eq, PartialEq)]
+pub struct CanisterChange {
+    timestamp_nanos: u64,
+    canister_version: u64,
+    origin: CanisterChangeOrigin,
+    details: CanisterChangeDetails,
+}
+
+impl CanisterChange {
+    pub fn new(
+        timestamp_nanos: u64,
+        canister_version: u64,
+        origin: CanisterChangeOrigin,
+        details: CanisterChangeDetails,
+    ) -> CanisterChange {
+        CanisterChange {
+            timestamp_nanos,
+            canister_version,
+            origin,
+            details,
+        }
+    }
+
+    pub fn timestamp_nanos(&self) -> u64 {
+        self.timestamp_nanos
+    }
+
+    pub fn canister_version(&self) -> u64 {
+        self.canister_version
+    }
+
+    pub fn origin(&self) -> &CanisterChangeOrigin {
+        &self.origin
+    }
+
+    pub fn details(&self) -> &CanisterChangeDetails {
+        &self.details
+    }
+}
+
+impl From<&CanisterChangeOrigin> for pb_canister_state_bits::canister_change::ChangeOrigin {
+    fn from(item: &CanisterChangeOrigin) -> Self {
+        match item {
+            CanisterChangeOrigin::CanisterChangeFromUser(change_from_user) => {
+                pb_canister_state_bits::canister_change::ChangeOrigin::CanisterChangeFromUser(
+                    pb_canister_state_bits::CanisterChangeFromUser {
+                        user_id: Some(change_from_user.user_id.into()),
+                    },
+                )
+            }
+            CanisterChangeOrigin::CanisterChangeFromCanister(change_from_canister) => {
+                pb_canister_state_bits::canister_change::ChangeOrigin::CanisterChangeFromCanister(
+                    pb_canister_state_bits::CanisterChangeFromCanister {
+                        canister_id: Some(change_from_canister.canister_id.into()),
+                        canister_version: change_from_canister.canister_version,
+                    },
+                )
+            }
+        }
+    }
+}
+
+impl TryFrom<pb_canister_state_bits::canister_change::ChangeOrigin> for CanisterChangeOrigin {
+    type Error = ProxyDecodeError;
+
+    fn try_from(
+        value: pb_canister_state_bits::canister_change::ChangeOrigin,
+    ) -> Result<Self, Self::Error> {
+        match value {
+            pb_canister_state_bits::canister_change::ChangeOrigin::CanisterChangeFromUser(
+                change_from_user,
+            ) => Ok(CanisterChangeOrigin::from_user(try_from_option_field(
+                change_from_user.user_id,
+                "user_id",
+            )?)),
+            pb_canister_state_bits::canister_change::ChangeOrigin::CanisterChangeFromCanister(
+                change_from_canister,
+            ) => Ok(CanisterChangeOrigin::from_canister(
+                try_from_option_field(change_from_canister.canister_id, "canister_id")?,
+                change_from_canister.canister_version,
+            )),
+        }
+    }
+}
+
+impl From<&CanisterChangeDetails> for pb_canister_state_bits::canister_change::ChangeDetails {
+    fn from(item: &CanisterChangeDetails) -> Self {
+        match item {
+            CanisterChangeDetails::CanisterCreation(canister_creation) => {
+                pb_canister_state_bits::canister_change::ChangeDetails::CanisterCreation(
+                    pb_canister_state_bits::CanisterCreation {
+                        controllers: canister_creation
+                            .controllers
+                            .iter()
+                            .map(|c| (*c).into())
+                            .collect(),
+                    },
+                )
+            }
+            CanisterChangeDetails::CanisterCodeUninstall => {
+                pb_canister_state_bits::canister_change::ChangeDetails::CanisterCodeUninstall(
+                    pb_canister_state_bits::CanisterCodeUninstall {},
+                )
+            }
+            CanisterChangeDetails::CanisterCodeDeployment(canister_code_deployment) => {
+                pb_canister_state_bits::canister_change::ChangeDetails::CanisterCodeDeployment(
+                    pb_canister_state_bits::CanisterCodeDeployment {
+                        mode: pb_canister_state_bits::CanisterInstallMode::from(
+                            canister_code_deployment.mode,
+                        )
+                        .into(),
+                        module_hash: canister_code_deployment.module_hash.to_vec(),
+                    },
+                )
+            }
+            CanisterChangeDetails::CanisterControllersChange(canister_controllers_change) => {
+                pb_canister_state_bits::canister_change::ChangeDetails::CanisterControllersChange(
+                    pb_canister_state_bits::CanisterControllersChange {
+                        controllers: canister_controllers_change
+                            .controllers
+                            .iter()
+                            .map(|c| (*c).into())
+                            .collect(),
+                    },
+                )
+            }
+        }
+    }
+}
+
+impl TryFrom<pb_canister_state_bits::canister_change::ChangeDetails> for CanisterChangeDetails {
+    type Error = ProxyDecodeError;
+
+    fn try_from(
+        item: pb_canister_state_bits::canister_change::ChangeDetails,
+    ) -> Result<Self, Self::Error> {
+        match item {
+            pb_canister_state_bits::canister_change::ChangeDetails::CanisterCreation(
+                canister_creation,
+            ) => {
+                let controllers = canister_creation
+                    .controllers
+                    .into_iter()
+                    .map(PrincipalId::try_from)
+                    .collect::<Result<Vec<PrincipalId>, _>>()?;
+                Ok(CanisterChangeDetails::canister_creation(controllers))
+            }
+            pb_canister_state_bits::canister_change::ChangeDetails::CanisterCodeUninstall(_) => {
+                Ok(CanisterChangeDetails::CanisterCodeUninstall)
+            }
+            pb_canister_state_bits::canister_change::ChangeDetails::CanisterCodeDeployment(
+                canister_code_deployment,
+            ) => {
+                let mode = pb_canister_state_bits::CanisterInstallMode::from_i32(
+                    canister_code_deployment.mode,
+                )
+                .ok_or(ProxyDecodeError::ValueOutOfRange {
+                    typ: "CanisterInstallMode",
+                    err: format!(
+                        "Unable to convert {} to a CanisterInstallMode",
+                        canister_code_deployment.mode
+                    ),
+                })?;
+                let module_hash: [u8; 32] = canister_code_deployment
+                    .module_hash
+                    .try_into()
+                    .map_err(|hash: Vec<u8>| ProxyDecodeError::InvalidDigestLength {
+                        expected: 32,
+                        actual: hash.len(),
+                    })?;
+                Ok(CanisterChangeDetails::code_deployment(
+                    CanisterInstallMode::try_from(mode)?,
+                    module_hash,
+                ))
+            }
+            pb_canister_state_bits::canister_change::ChangeDetails::CanisterControllersChange(
+                canister_controllers_change,
+            ) => {
+                let controllers = canister_controllers_change
+                    .controllers
+                    .into_iter()
+                    .map(PrincipalId::try_from)
+                    .collect::<Result<Vec<PrincipalId>, _>>()?;
+                Ok(CanisterChangeDetails::controllers_change(controllers))
+            }
+        }
+    }
+}
+
+impl From<&CanisterChange> for pb_canister_state_bits::CanisterChange {
+    fn from(item: &CanisterChange) -> Self {
+        Self {
+            timestamp_nanos: item.timestamp_nanos,
+            canister_version: item.canister_version,
+            change_origin: Some((&item.origin).into()),
+            change_details: Some((&item.details).into()),
+        }
+    }
+}
+
+impl TryFrom<pb_canister_state_bits::CanisterChange> for CanisterChange {
+    type Error = ProxyDecodeError;
+
+    fn try_from(value: pb_canister_state_bits::CanisterChange) -> Result<Self, Self::Error> {
+        let origin = try_from_option_field(value.change_origin, "origin")?;
+        let details = try_from_option_field(value.change_details, "details")?;
+        Ok(Self {
+            timestamp_nanos: value.timestamp_nanos,
+            canister_version: value.canister_version,
+            origin,
+            details,
+        })
+    }
+}
+
+/// Struct used for encoding/decoding
+/// `(record {
+///     canister_id : principal;
+///     num_requested_changes : opt nat64;
+/// })`
+#[derive(CandidType, Deserialize, Debug, Eq, PartialEq)]
+pub struct CanisterInfoRequest {
+    canister_id: PrincipalId,
+    num_requested_changes: Option<u64>,
+}
+
+impl CanisterInfoRequest {
+    pub fn new(canister_id: CanisterId, num_requested_changes: Option<u64>) -> Self {
+        Self {
+            canister_id: canister_id.get(),
+            num_requested_changes,
+        }
+    }
+
+    pub fn canister_id(&self) -> CanisterId {
+        CanisterId::new(self.canister_id).unwrap()
+    }
+
+    pub fn num_requested_changes(&self) -> Option<u64> {
+        self.num_requested_changes
+    }
+}
+
+impl Payload<'_> for CanisterInfoRequest {}
+
+/// Struct used for encoding/decoding
+/// `(record {
+///     total_num_changes : nat64;
+///     recent_changes : vec change;
+///     module_hash : opt blob;
+///     controllers : vec principal;
+/// })`
+#[derive(CandidType, Deserialize, Debug, Eq, PartialEq)]
+pub struct CanisterInfoResponse {
+    total_num_changes: u64,
+    recent_changes: Vec<CanisterChange>,
+    module_hash: Option<Vec<u8>>,
+    controllers: Vec<PrincipalId>,
+}
+
+impl CanisterInfoResponse {
+    pub fn new(
+        total_num_changes: u64,
+        recent_changes: Vec<CanisterChange>,
+        module_hash: Option<Vec<u8>>,
+        controllers: Vec<PrincipalId>,
+    ) -> Self {
+        Self {
+            total_num_changes,
+            recent_changes,
+            module_hash,
+            controllers,
+        }
+    }
+
+    pub fn total_num_changes(&self) -> u64 {
+        self.total_num_changes
+    }
+
+    pub fn changes(&self) -> Vec<CanisterChange> {
+        self.recent_changes.clone()
+    }
+
+    pub fn module_hash(&self) -> Option<Vec<u8>> {
+        self.module_hash.clone()
+    }
+
+    pub fn controllers(&self) -> Vec<PrincipalId> {
+        self.controllers.clone()
+    }
+}
+
+impl Payload<'_> for CanisterInfoResponse {}
+
+/// `CandidType` for `CanisterLogRecord`
+/// ```text
+/// record {
+///     idx: nat64;
+///     timestamp_nanos: nat64;
+///     content: blob;
+/// }
+/// ```
+#[derive(Clone, CandidType, Deserialize, Debug, Eq, PartialEq, Serialize)]
+pub struct CanisterLogRecord {
+    pub idx: u64,
+    pub timestamp_nanos: u64,
+    #[serde(with = "serde_bytes")]
+    pub content: Vec<u8>,
+}
+
+impl CanisterLogRecord {
+    /// Returns the number of bytes the record occupies in the log buffer.
+    pub fn data_size(&self) -> usize {
+        std::mem::size_of::<u64>() * 2 + self.content.len()
+    }
+}
+
+impl From<&CanisterLogRecord> for pb_canister_state_bits::CanisterLogRecord {
+    fn from(item: &CanisterLogRecord) -> Self {
+        Self {
+            idx: item.idx,
+            timestamp_nanos: item.timestamp_nanos,
+            content: item.content.clone(),
+        }
+    }
+}
+
+impl From<pb_canister_state_bits::CanisterLogRecord> for CanisterLogRecord {
+    fn from(item: pb_canister_state_bits::CanisterLogRecord) -> Self {
+        Self {
+            idx: item.idx,
+            timestamp_nanos: item.timestamp_nanos,
+            content: item.content,
+        }
+    }
+}
+
+/// `CandidType` for `FetchCanisterLogsRequest`
+/// ```text
+/// record {
+///     canister_id: principal;
+/// }
+/// ```
+#[derive(Clone, CandidType, Deserialize, Debug, Eq, PartialEq)]
+pub struct FetchCanisterLogsRequest {
+    pub canister_id: PrincipalId,
+}
+
+impl FetchCanisterLogsRequest {
+    pub fn new(canister_id: CanisterId) -> Self {
+        Self {
+            canister_id: canister_id.get(),
+        }
+    }
+
+    pub fn get_canister_id(&self) -> CanisterId {
+        CanisterId::new(self.canister_id).unwrap()
+    }
+}
+
+impl Payload<'_> for FetchCanisterLogsRequest {}
+
+/// `CandidType` for `FetchCanisterLogsResponse`
+/// ```text
+/// record {
+///     canister_log_records: vec canister_log_record;
+/// }
+/// ```
+#[derive(Clone, CandidType, Deserialize, Debug, Eq, PartialEq)]
+pub struct FetchCanisterLogsResponse {
+    pub canister_log_records: Vec<CanisterLogRecord>,
+}
+
+impl Payload<'_> for FetchCanisterLogsResponse {}
+
 /// Struct used for encoding/decoding
 /// `(record {
 ///     mode : variant { install; reinstall; upgrade };
@@ -386,6 +1098,7 @@ impl Payload<'_> for CanisterStatusResultV2 {}
 ///     compute_allocation: opt nat;
 ///     memory_allocation: opt nat;
 ///     query_allocation: opt nat;
+///     sender_canister_version : opt nat64;
 /// })`
 #[derive(Clone, CandidType, Deserialize, Debug)]
 pub struct InstallCodeArgs {
@@ -397,6 +1110,7 @@ pub struct InstallCodeArgs {
     pub compute_allocation: Option<candid::Nat>,
     pub memory_allocation: Option<candid::Nat>,
     pub query_allocation: Option<candid::Nat>,
+    pub sender_canister_version: Option<u64>,
 }
 
 impl std::fmt::Display for InstallCodeArgs {
@@ -430,6 +1144,11 @@ impl std::fmt::Display for InstallCodeArgs {
                 .as_ref()
                 .map(|value| format!("{}", value))
         )?;
+        writeln!(
+            f,
+            "  sender_canister_version: {:?}",
+            self.sender_canister_version
+        )?;
         writeln!(f, "}}")
     }
 }
@@ -454,6 +1173,7 @@ impl InstallCodeArgs {
             compute_allocation: compute_allocation.map(candid::Nat::from),
             memory_allocation: memory_allocation.map(candid::Nat::from),
             query_allocation: query_allocation.map(candid::Nat::from),
+            sender_canister_version: None,
         }
     }
 
@@ -461,8 +1181,43 @@ impl InstallCodeArgs {
         // Safe as this was converted from CanisterId when Self was constructed.
         CanisterId::new(self.canister_id).unwrap()
     }
+
+    pub fn get_sender_canister_version(&self) -> Option<u64> {
+        self.sender_canister_version
+    }
 }
 
+/// Struct used for encoding/decoding
+/// `(record {
+///     canister_id : principal;
+///     sender_canister_version : opt nat64;
+/// })`
+#[derive(CandidType, Deserialize, Debug)]
+pub struct UninstallCodeArgs {
+    canister_id: PrincipalId,
+    sender_canister_version: Option<u64>,
+}
+
+impl UninstallCodeArgs {
+    pub fn new(canister_id: CanisterId, sender_canister_version: Option<u64>) -> Self {
+        Self {
+            canister_id: canister_id.into(),
+            sender_canister_version,
+        }
+    }
+
+    pub fn get_canister_id(&self) -> CanisterId {
+        // Safe as this was converted from CanisterId when Self was constructed.
+        CanisterId::new(self.canister_id).unwrap()
+    }
+
+    pub fn get_sender_canister_version(&self) -> Option<u64> {
+        self.sender_canister_version
+    }
+}
+
+impl Payload<'_> for UninstallCodeArgs {}
+
 /// Represents the empty blob.
 #[derive(CandidType, Deserialize)]
 pub struct EmptyBlob;
@@ -481,11 +1236,13 @@ impl<'a> Payload<'a> for EmptyBlob {
 /// `(record {
 ///     canister_id : principal;
 ///     settings: canister_settings;
+///     sender_canister_version : opt nat64;
 /// })`
 #[derive(CandidType, Deserialize)]
 pub struct UpdateSettingsArgs {
     pub canister_id: PrincipalId,
     pub settings: CanisterSettingsArgs,
+    pub sender_canister_version: Option<u64>,
 }
 
 impl UpdateSettingsArgs {
@@ -493,17 +1250,76 @@ impl UpdateSettingsArgs {
         // Safe as this was converted from CanisterId when Self was constructed.
         CanisterId::new(self.canister_id).unwrap()
     }
+
+    pub fn get_sender_canister_version(&self) -> Option<u64> {
+        self.sender_canister_version
+    }
 }
 
 impl Payload<'_> for UpdateSettingsArgs {}
 
+/// Log visibility for a canister.
+/// ```text
+/// variant {
+///    controllers;
+///    public;
+/// }
+/// ```
+#[derive(Clone, Copy, CandidType, Deserialize, Debug, Eq, PartialEq, Serialize)]
+pub enum LogVisibility {
+    #[serde(rename = "controllers")]
+    Controllers,
+    #[serde(rename = "public")]
+    Public,
+}
+
+impl Default for LogVisibility {
+    fn default() -> Self {
+        Self::Controllers
+    }
+}
+
+impl From<LogVisibility> for pb_canister_state_bits::LogVisibility {
+    fn from(item: LogVisibility) -> Self {
+        match item {
+            LogVisibility::Controllers => pb_canister_state_bits::LogVisibility::Controllers,
+            LogVisibility::Public => pb_canister_state_bits::LogVisibility::Public,
+        }
+    }
+}
+
+impl TryFrom<pb_canister_state_bits::LogVisibility> for LogVisibility {
+    type Error = ProxyDecodeError;
+
+    fn try_from(item: pb_canister_state_bits::LogVisibility) -> Result<Self, Self::Error> {
+        match item {
+            pb_canister_state_bits::LogVisibility::Controllers => Ok(LogVisibility::Controllers),
+            pb_canister_state_bits::LogVisibility::Public => Ok(LogVisibility::Public),
+            pb_canister_state_bits::LogVisibility::Unspecified => {
+                Err(ProxyDecodeError::ValueOutOfRange {
+                    typ: "LogVisibility",
+                    err: format!("Unable to convert {:?} to a LogVisibility", item),
+                })
+            }
+        }
+    }
+}
+
 /// Struct used for encoding/decoding
 /// `(record {
 ///     controller : opt principal;
 ///     controllers: opt vec principal;
 ///     compute_allocation: opt nat;
 ///     memory_allocation: opt nat;
+///     freezing_threshold: opt nat;
+///     log_visibility: opt log_visibility;
+///     wasm_memory_limit: opt nat;
+///     reserved_cycles_limit: opt nat;
 /// })`
+///
+/// A `wasm_memory_limit` of 0 removes the limit. A `reserved_cycles_limit` of
+/// 0 disables storage reservations: allocations that would require reserving
+/// cycles fail instead.
 #[derive(Default, Clone, CandidType, Deserialize, Debug)]
 pub struct CanisterSettingsArgs {
     pub controller: Option<PrincipalId>,
@@ -511,6 +1327,9 @@ pub struct CanisterSettingsArgs {
     pub compute_allocation: Option<candid::Nat>,
     pub memory_allocation: Option<candid::Nat>,
     pub freezing_threshold: Option<candid::Nat>,
+    pub log_visibility: Option<LogVisibility>,
+    pub wasm_memory_limit: Option<candid::Nat>,
+    pub reserved_cycles_limit: Option<candid::Nat>,
 }
 
 impl Payload<'_> for CanisterSettingsArgs {}
@@ -529,6 +1348,9 @@ impl CanisterSettingsArgs {
             compute_allocation: compute_allocation.map(candid::Nat::from),
             memory_allocation: memory_allocation.map(candid::Nat::from),
             freezing_threshold: freezing_threshold.map(candid::Nat::from),
+            log_visibility: None,
+            wasm_memory_limit: None,
+            reserved_cycles_limit: None,
         }
     }
 }
@@ -536,10 +1358,12 @@ impl CanisterSettingsArgs {
 /// Struct used for encoding/decoding
 /// `(record {
 ///     settings : opt canister_settings;
+///     sender_canister_version : opt nat64;
 /// })`
 #[derive(Default, Clone, CandidType, Deserialize)]
 pub struct CreateCanisterArgs {
     pub settings: Option<CanisterSettingsArgs>,
+    pub sender_canister_version: Option<u64>,
 }
 
 impl CreateCanisterArgs {
@@ -560,6 +1384,10 @@ impl CreateCanisterArgs {
             Ok(settings) => Ok(settings),
         }
     }
+
+    pub fn get_sender_canister_version(&self) -> Option<u64> {
+        self.sender_canister_version
+    }
 }
 
 /// Struct used for encoding/decoding
@@ -696,7 +1524,7 @@ impl SetupInitialDKGResponse {
 
 /// Types of curves that can be used for ECDSA signing.
 /// ```text
-/// (variant { secp256k1; })
+/// (variant { secp256k1; secp256r1; })
 /// ```
 #[derive(
     CandidType, Copy, Clone, Debug, PartialOrd, Ord, PartialEq, Eq, Serialize, Deserialize, Hash,
@@ -704,6 +1532,8 @@ impl SetupInitialDKGResponse {
 pub enum EcdsaCurve {
     #[serde(rename = "secp256k1")]
     Secp256k1,
+    #[serde(rename = "secp256r1")]
+    Secp256r1,
 }
 
 impl TryFrom<pb_registry_crypto::EcdsaCurve> for EcdsaCurve {
@@ -712,6 +1542,7 @@ impl TryFrom<pb_registry_crypto::EcdsaCurve> for EcdsaCurve {
     fn try_from(item: pb_registry_crypto::EcdsaCurve) -> Result<Self, Self::Error> {
         match item {
             pb_registry_crypto::EcdsaCurve::Secp256k1 => Ok(EcdsaCurve::Secp256k1),
+            pb_registry_crypto::EcdsaCurve::Secp256r1 => Ok(EcdsaCurve::Secp256r1),
             pb_registry_crypto::EcdsaCurve::Unspecified => Err(ProxyDecodeError::ValueOutOfRange {
                 typ: "EcdsaCurve",
                 err: format!("Unable to convert {:?} to an EcdsaCurve", item),
@@ -724,6 +1555,7 @@ impl From<EcdsaCurve> for pb_registry_crypto::EcdsaCurve {
     fn from(item: EcdsaCurve) -> Self {
         match item {
             EcdsaCurve::Secp256k1 => pb_registry_crypto::EcdsaCurve::Secp256k1,
+            EcdsaCurve::Secp256r1 => pb_registry_crypto::EcdsaCurve::Secp256r1,
         }
     }
 }
@@ -740,6 +1572,7 @@ impl FromStr for EcdsaCurve {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
             "Secp256k1" => Ok(Self::Secp256k1),
+            "Secp256r1" => Ok(Self::Secp256r1),
             _ => Err(format!("{} is not a recognized ECDSA curve", s)),
         }
     }
@@ -747,12 +1580,13 @@ impl FromStr for EcdsaCurve {
 
 #[test]
 fn ecdsa_curve_round_trip() {
-    assert_eq!(
-        format!("{}", EcdsaCurve::Secp256k1)
-            .parse::<EcdsaCurve>()
-            .unwrap(),
-        EcdsaCurve::Secp256k1
-    );
+    for curve in [EcdsaCurve::Secp256k1, EcdsaCurve::Secp256r1] {
+        assert_eq!(format!("{}", curve).parse::<EcdsaCurve>().unwrap(), curve);
+        assert_eq!(
+            EcdsaCurve::try_from(pb_registry_crypto::EcdsaCurve::from(curve)).unwrap(),
+            curve
+        );
+    }
 }
 
 /// Unique identifier for a key that can be used for ECDSA signatures. The name
@@ -971,6 +1805,223 @@ impl ComputeInitialEcdsaDealingsResponse {
     }
 }
 
+/// Struct used for encoding/decoding
+/// `(record {
+///     canister_id : principal;
+///     replace_snapshot : opt blob;
+/// })`
+#[derive(CandidType, Deserialize, Debug, Eq, PartialEq)]
+pub struct TakeCanisterSnapshotArgs {
+    canister_id: PrincipalId,
+    replace_snapshot: Option<Vec<u8>>,
+}
+
+impl TakeCanisterSnapshotArgs {
+    pub fn new(canister_id: CanisterId, replace_snapshot: Option<Vec<u8>>) -> Self {
+        Self {
+            canister_id: canister_id.get(),
+            replace_snapshot,
+        }
+    }
+
+    pub fn get_canister_id(&self) -> CanisterId {
+        CanisterId::new(self.canister_id).unwrap()
+    }
+
+    pub fn replace_snapshot(&self) -> Option<&[u8]> {
+        self.replace_snapshot.as_deref()
+    }
+}
+
+impl Payload<'_> for TakeCanisterSnapshotArgs {}
+
+/// Struct used for encoding/decoding
+/// `(record {
+///     canister_id : principal;
+///     snapshot_id : blob;
+/// })`
+///
+/// Used as the argument of both `load_canister_snapshot` and
+/// `delete_canister_snapshot`.
+#[derive(CandidType, Deserialize, Debug, Eq, PartialEq)]
+pub struct CanisterSnapshotArgs {
+    canister_id: PrincipalId,
+    snapshot_id: Vec<u8>,
+}
+
+impl CanisterSnapshotArgs {
+    pub fn new(canister_id: CanisterId, snapshot_id: Vec<u8>) -> Self {
+        Self {
+            canister_id: canister_id.get(),
+            snapshot_id,
+        }
+    }
+
+    pub fn get_canister_id(&self) -> CanisterId {
+        CanisterId::new(self.canister_id).unwrap()
+    }
+
+    pub fn snapshot_id(&self) -> &[u8] {
+        &self.snapshot_id
+    }
+}
+
+impl Payload<'_> for CanisterSnapshotArgs {}
+
+pub type LoadCanisterSnapshotArgs = CanisterSnapshotArgs;
+pub type DeleteCanisterSnapshotArgs = CanisterSnapshotArgs;
+
+/// Struct used for encoding/decoding
+/// `(record {
+///     id : blob;
+///     taken_at_timestamp : nat64;
+///     total_size : nat64;
+/// })`
+#[derive(CandidType, Deserialize, Debug, Eq, PartialEq, Clone)]
+pub struct CanisterSnapshotResponse {
+    pub id: Vec<u8>,
+    pub taken_at_timestamp: u64,
+    pub total_size: u64,
+}
+
+impl CanisterSnapshotResponse {
+    pub fn new(id: Vec<u8>, taken_at_timestamp: u64, total_size: NumBytes) -> Self {
+        Self {
+            id,
+            taken_at_timestamp,
+            total_size: total_size.get(),
+        }
+    }
+}
+
+impl Payload<'_> for CanisterSnapshotResponse {}
+
+/// The result of `list_canister_snapshots`: `(vec snapshot)`.
+pub type ListCanisterSnapshotsResponse = Vec<CanisterSnapshotResponse>;
+
+impl Payload<'_> for ListCanisterSnapshotsResponse {}
+
+/// Struct used for encoding/decoding
+/// `(record {
+///     canister_id : principal;
+///     chunk : blob;
+/// })`
+#[derive(CandidType, Deserialize, Debug, Eq, PartialEq)]
+pub struct UploadChunkArgs {
+    pub canister_id: PrincipalId,
+    #[serde(with = "serde_bytes")]
+    pub chunk: Vec<u8>,
+}
+
+impl UploadChunkArgs {
+    pub fn get_canister_id(&self) -> CanisterId {
+        CanisterId::new(self.canister_id).unwrap()
+    }
+}
+
+impl Payload<'_> for UploadChunkArgs {}
+
+/// Struct used for encoding/decoding
+/// `(record {
+///     hash : blob;
+/// })`
+///
+/// Returned by `upload_chunk`, and used to refer to stored chunks in
+/// `stored_chunks` and `install_chunked_code`.
+#[derive(CandidType, Deserialize, Debug, Eq, PartialEq, Clone)]
+pub struct ChunkHash {
+    #[serde(with = "serde_bytes")]
+    pub hash: Vec<u8>,
+}
+
+impl Payload<'_> for ChunkHash {}
+
+pub type UploadChunkReply = ChunkHash;
+
+/// The result of `stored_chunks`: `(vec chunk_hash)`.
+#[derive(CandidType, Deserialize, Debug, Eq, PartialEq, Clone)]
+pub struct StoredChunksReply(pub Vec<ChunkHash>);
+
+impl Payload<'_> for StoredChunksReply {}
+
+/// Struct used for encoding/decoding
+/// `(record {
+///     mode : canister_install_mode;
+///     target_canister : principal;
+///     store_canister : opt principal;
+///     chunk_hashes_list : vec chunk_hash;
+///     wasm_module_hash : blob;
+///     arg : blob;
+///     sender_canister_version : opt nat64;
+/// })`
+///
+/// If `store_canister` is not set, the chunks are taken from the chunk store
+/// of `target_canister`.
+#[derive(CandidType, Deserialize, Debug, Eq, PartialEq)]
+pub struct InstallChunkedCodeArgs {
+    pub mode: CanisterInstallMode,
+    pub target_canister: PrincipalId,
+    pub store_canister: Option<PrincipalId>,
+    pub chunk_hashes_list: Vec<ChunkHash>,
+    #[serde(with = "serde_bytes")]
+    pub wasm_module_hash: Vec<u8>,
+    #[serde(with = "serde_bytes")]
+    pub arg: Vec<u8>,
+    pub sender_canister_version: Option<u64>,
+}
+
+impl std::fmt::Display for InstallChunkedCodeArgs {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "InstallChunkedCodeArgs {{")?;
+        writeln!(f, "  mode: {:?}", &self.mode)?;
+        writeln!(f, "  target_canister: {:?}", &self.target_canister)?;
+        writeln!(f, "  store_canister: {:?}", &self.store_canister)?;
+        writeln!(
+            f,
+            "  chunk_hashes_list: <{:?} chunks>",
+            self.chunk_hashes_list.len()
+        )?;
+        writeln!(f, "  wasm_module_hash: {:?}", &self.wasm_module_hash)?;
+        writeln!(f, "  arg: <{:?} bytes>", self.arg.len())?;
+        writeln!(f, "}}")
+    }
+}
+
+impl InstallChunkedCodeArgs {
+    pub fn new(
+        mode: CanisterInstallMode,
+        target_canister: CanisterId,
+        store_canister: Option<CanisterId>,
+        chunk_hashes_list: Vec<Vec<u8>>,
+        wasm_module_hash: Vec<u8>,
+        arg: Vec<u8>,
+    ) -> Self {
+        Self {
+            mode,
+            target_canister: target_canister.get(),
+            store_canister: store_canister.map(|c| c.get()),
+            chunk_hashes_list: chunk_hashes_list
+                .into_iter()
+                .map(|hash| ChunkHash { hash })
+                .collect(),
+            wasm_module_hash,
+            arg,
+            sender_canister_version: None,
+        }
+    }
+
+    pub fn target_canister_id(&self) -> CanisterId {
+        CanisterId::new(self.target_canister).unwrap()
+    }
+
+    /// The canister holding the chunks, which defaults to the target.
+    pub fn store_canister_id(&self) -> CanisterId {
+        CanisterId::new(self.store_canister.unwrap_or(self.target_canister)).unwrap()
+    }
+}
+
+impl Payload<'_> for InstallChunkedCodeArgs {}
+
 // Export the bitcoin types.
 pub use ic_btc_types::{
     GetBalanceRequest as BitcoinGetBalanceArgs,
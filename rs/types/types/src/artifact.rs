@@ -505,6 +505,9 @@ pub struct StateSyncMessage {
     #[serde(serialize_with = "ic_utils::serde_arc::serialize_arc")]
     #[serde(deserialize_with = "ic_utils::serde_arc::deserialize_arc")]
     pub state_sync_file_group: Arc<crate::state_sync::FileGroupChunks>,
+    /// Compressed chunks served so far, shared by all messages for this state.
+    #[serde(skip)]
+    pub compressed_chunks: Arc<crate::state_sync::CompressedChunkCache>,
 }
 
 impl ChunkableArtifact for StateSyncMessage {
@@ -530,19 +533,33 @@ impl ChunkableArtifact for StateSyncMessage {
                 Some(buf)
             };
 
-            let mut payload: Vec<u8> = Vec::new();
-            if _chunk_id == crate::state_sync::MANIFEST_CHUNK {
-                payload = crate::state_sync::encode_manifest(&self.manifest);
-            } else if _chunk_id.get() < FILE_GROUP_CHUNK_ID_OFFSET
-                || self.state_sync_file_group.get(&_chunk_id.get()).is_none()
-            {
-                payload = get_single_chunk((_chunk_id.get() - 1) as usize)?;
-            } else {
-                let chunk_table_indices = self.state_sync_file_group.get(&_chunk_id.get())?;
-                for chunk_table_index in chunk_table_indices {
-                    payload.extend(get_single_chunk(*chunk_table_index as usize)?);
+            let get_payload = || -> Option<Vec<u8>> {
+                let mut payload: Vec<u8> = Vec::new();
+                if _chunk_id.get() < FILE_GROUP_CHUNK_ID_OFFSET
+                    || self.state_sync_file_group.get(&_chunk_id.get()).is_none()
+                {
+                    payload = get_single_chunk((_chunk_id.get() - 1) as usize)?;
+                } else {
+                    let chunk_table_indices = self.state_sync_file_group.get(&_chunk_id.get())?;
+                    for chunk_table_index in chunk_table_indices {
+                        payload.extend(get_single_chunk(*chunk_table_index as usize)?);
+                    }
                 }
-            }
+                Some(payload)
+            };
+
+            let payload = if _chunk_id == crate::state_sync::MANIFEST_CHUNK {
+                crate::state_sync::encode_manifest(&self.manifest)
+            } else if self.manifest.has_compressed_chunks() {
+                // All chunks except for the manifest are sent compressed starting
+                // with `STATE_SYNC_V2`.
+                let compressed = self.compressed_chunks.get_or_insert_with(_chunk_id, || {
+                    crate::state_sync::compress_chunk(&get_payload()?).ok()
+                })?;
+                compressed.as_ref().clone()
+            } else {
+                get_payload()?
+            };
 
             Some(ArtifactChunk {
                 chunk_id: _chunk_id,
@@ -0,0 +1,171 @@
+use crate::{node_id_into_protobuf, node_id_try_from_protobuf, CanisterId, CountBytes, Height};
+use ic_base_types::NodeId;
+use ic_protobuf::types::v1 as pb;
+use phantom_newtype::AmountOf;
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+
+/// Number of blocks over which query statistics are collected locally before
+/// they are sent to the other replicas via a [`QueryStatsPayload`].
+pub const QUERY_STATS_EPOCH_LENGTH: u64 = 2000;
+
+pub struct QueryStatsEpochTag;
+/// The epoch a set of query statistics was collected in.
+pub type QueryStatsEpoch = AmountOf<QueryStatsEpochTag, u64>;
+
+/// Returns the query stats epoch the given height belongs to.
+pub fn epoch_from_height(height: Height) -> QueryStatsEpoch {
+    QueryStatsEpoch::from(height.get() / QUERY_STATS_EPOCH_LENGTH)
+}
+
+/// Statistics about the query calls executed by a canister.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct QueryStats {
+    /// Number of query calls.
+    pub num_calls: u32,
+    /// Number of instructions executed by the query calls.
+    pub num_instructions: u64,
+    /// Total size of the query call arguments in bytes.
+    pub ingress_payload_size: u64,
+    /// Total size of the query call replies in bytes.
+    pub egress_payload_size: u64,
+}
+
+impl QueryStats {
+    /// Adds `other` to these statistics, saturating on overflow.
+    pub fn saturating_accumulate(&mut self, other: &QueryStats) {
+        self.num_calls = self.num_calls.saturating_add(other.num_calls);
+        self.num_instructions = self.num_instructions.saturating_add(other.num_instructions);
+        self.ingress_payload_size = self
+            .ingress_payload_size
+            .saturating_add(other.ingress_payload_size);
+        self.egress_payload_size = self
+            .egress_payload_size
+            .saturating_add(other.egress_payload_size);
+    }
+}
+
+/// The query statistics collected by a single replica for a single canister.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct CanisterQueryStats {
+    pub canister_id: CanisterId,
+    pub stats: QueryStats,
+}
+
+/// Payload that contains the query statistics collected locally by
+/// `proposer` during `epoch`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct QueryStatsPayload {
+    pub epoch: QueryStatsEpoch,
+    pub proposer: NodeId,
+    pub stats: Vec<CanisterQueryStats>,
+}
+
+impl CountBytes for QueryStatsPayload {
+    fn count_bytes(&self) -> usize {
+        // Canister id (up to 29 bytes), 4 bytes for the number of calls and
+        // 3 * 8 bytes for the remaining counters.
+        const CANISTER_QUERY_STATS_SIZE: usize = 29 + 4 + 3 * 8;
+        std::mem::size_of::<QueryStatsEpoch>()
+            + self.proposer.get_ref().as_slice().len()
+            + self.stats.len() * CANISTER_QUERY_STATS_SIZE
+    }
+}
+
+impl From<&QueryStatsPayload> for pb::QueryStatsPayload {
+    fn from(payload: &QueryStatsPayload) -> Self {
+        Self {
+            epoch: payload.epoch.get(),
+            proposer: Some(node_id_into_protobuf(payload.proposer)),
+            canister_stats: payload
+                .stats
+                .iter()
+                .map(|entry| pb::CanisterQueryStats {
+                    canister_id: Some(pb::CanisterId::from(entry.canister_id)),
+                    num_calls: entry.stats.num_calls,
+                    num_instructions: entry.stats.num_instructions,
+                    ingress_payload_size: entry.stats.ingress_payload_size,
+                    egress_payload_size: entry.stats.egress_payload_size,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<pb::QueryStatsPayload> for QueryStatsPayload {
+    type Error = String;
+
+    fn try_from(payload: pb::QueryStatsPayload) -> Result<Self, Self::Error> {
+        let proposer = node_id_try_from_protobuf(
+            payload
+                .proposer
+                .ok_or("Error: query_stats_payload does not contain a proposer")?,
+        )
+        .map_err(|e| format!("Proxy decode error {:?}", e))?;
+        let stats = payload
+            .canister_stats
+            .into_iter()
+            .map(|entry| -> Result<CanisterQueryStats, String> {
+                let canister_id = entry
+                    .canister_id
+                    .ok_or_else(|| "No canister id on canister query stats".to_string())
+                    .and_then(|canister_id| {
+                        CanisterId::try_from(canister_id)
+                            .map_err(|e| format!("Proxy decode error {:?}", e))
+                    })?;
+                Ok(CanisterQueryStats {
+                    canister_id,
+                    stats: QueryStats {
+                        num_calls: entry.num_calls,
+                        num_instructions: entry.num_instructions,
+                        ingress_payload_size: entry.ingress_payload_size,
+                        egress_payload_size: entry.egress_payload_size,
+                    },
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self {
+            epoch: QueryStatsEpoch::from(payload.epoch),
+            proposer,
+            stats,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_base_types::PrincipalId;
+
+    #[test]
+    fn query_stats_payload_round_trips_through_protobuf() {
+        let payload = QueryStatsPayload {
+            epoch: QueryStatsEpoch::from(3),
+            proposer: NodeId::from(PrincipalId::new_node_test_id(7)),
+            stats: vec![CanisterQueryStats {
+                canister_id: CanisterId::from(1),
+                stats: QueryStats {
+                    num_calls: 2,
+                    num_instructions: 1_000,
+                    ingress_payload_size: 10,
+                    egress_payload_size: 20,
+                },
+            }],
+        };
+        let pb_payload = pb::QueryStatsPayload::from(&payload);
+        assert_eq!(QueryStatsPayload::try_from(pb_payload).unwrap(), payload);
+    }
+
+    #[test]
+    fn epoch_from_height_rounds_down() {
+        assert_eq!(epoch_from_height(Height::from(0)), QueryStatsEpoch::from(0));
+        assert_eq!(
+            epoch_from_height(Height::from(QUERY_STATS_EPOCH_LENGTH - 1)),
+            QueryStatsEpoch::from(0)
+        );
+        assert_eq!(
+            epoch_from_height(Height::from(QUERY_STATS_EPOCH_LENGTH)),
+            QueryStatsEpoch::from(1)
+        );
+    }
+}
@@ -32,7 +32,7 @@
 
 use crate::{
     crypto::{CryptoHashOf, Signed},
-    messages::{CallbackId, RejectContext, Request},
+    messages::{CallbackId, RejectContext, Request, NO_DEADLINE},
     signature::*,
     CanisterId, CountBytes, RegistryVersion, Time,
 };
@@ -604,6 +604,7 @@ mod tests {
                 receiver: CanisterId::ic_00(),
                 sender: CanisterId::ic_00(),
                 sender_reply_callback: CallbackId::from(3),
+                deadline: NO_DEADLINE,
                 payment: Cycles::new(10),
                 method_name: "tansform".to_string(),
                 method_payload: Vec::new(),
@@ -644,6 +645,7 @@ mod tests {
                 receiver: CanisterId::ic_00(),
                 sender: CanisterId::ic_00(),
                 sender_reply_callback: CallbackId::from(3),
+                deadline: NO_DEADLINE,
                 payment: Cycles::new(10),
                 method_name: "tansform".to_string(),
                 method_payload: Vec::new(),
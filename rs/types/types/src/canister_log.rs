@@ -0,0 +1,169 @@
+//! Bounded buffer of log records produced by a canister, e.g. via
+//! `ic0.debug_print` or traps.
+use ic_ic00_types::CanisterLogRecord;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// The maximum total size in bytes of the records kept in a canister log.
+/// When exceeded, the oldest records are evicted.
+pub const MAX_ALLOWED_CANISTER_LOG_BUFFER_SIZE: usize = 4 * 1024;
+
+/// A ring buffer of canister log records, bounded by the total size of the
+/// records it holds.
+///
+/// Each record gets a unique, monotonically increasing index. Since records are
+/// only added during replicated execution using the batch time as timestamp,
+/// the contents of the buffer are deterministic.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CanisterLog {
+    /// The index to be assigned to the next record.
+    next_idx: u64,
+    /// The records, ordered from the oldest to the most recent.
+    records: VecDeque<CanisterLogRecord>,
+    /// The total size in bytes of `records`.
+    records_size: usize,
+}
+
+impl CanisterLog {
+    /// Creates a canister log from a checkpointed `next_idx` and records.
+    pub fn new(next_idx: u64, records: Vec<CanisterLogRecord>) -> Self {
+        let records_size = records.iter().map(|r| r.data_size()).sum();
+        Self {
+            next_idx,
+            records: records.into(),
+            records_size,
+        }
+    }
+
+    /// Creates an empty canister log whose first record gets index `next_idx`.
+    ///
+    /// Used for collecting the records produced by a single execution before
+    /// they are appended to the canister's log with `append_delta_log()`.
+    pub fn new_with_next_index(next_idx: u64) -> Self {
+        Self {
+            next_idx,
+            ..Default::default()
+        }
+    }
+
+    /// Returns the index to be assigned to the next record.
+    pub fn next_idx(&self) -> u64 {
+        self.next_idx
+    }
+
+    /// Returns the records, ordered from the oldest to the most recent.
+    pub fn records(&self) -> &VecDeque<CanisterLogRecord> {
+        &self.records
+    }
+
+    /// Returns the total size in bytes of the records in the buffer.
+    pub fn used_space(&self) -> usize {
+        self.records_size
+    }
+
+    /// Adds a new record, evicting the oldest records if the buffer is full.
+    /// The content is truncated to fit in an otherwise empty buffer.
+    pub fn add_record(&mut self, timestamp_nanos: u64, content: &[u8]) {
+        let max_content_size = MAX_ALLOWED_CANISTER_LOG_BUFFER_SIZE
+            - CanisterLogRecord {
+                idx: 0,
+                timestamp_nanos: 0,
+                content: vec![],
+            }
+            .data_size();
+        let content = &content[..content.len().min(max_content_size)];
+        self.push_back(CanisterLogRecord {
+            idx: self.next_idx,
+            timestamp_nanos,
+            content: content.to_vec(),
+        });
+        self.next_idx += 1;
+    }
+
+    /// Moves all records of `delta_log` to the end of this log, evicting the
+    /// oldest records if the buffer is full.
+    pub fn append_delta_log(&mut self, delta_log: &mut CanisterLog) {
+        self.next_idx = self.next_idx.max(delta_log.next_idx);
+        delta_log.records_size = 0;
+        for record in delta_log.records.drain(..) {
+            self.push_back(record);
+        }
+    }
+
+    /// Removes all records. The index of the next record is not reset.
+    pub fn clear(&mut self) {
+        self.records.clear();
+        self.records_size = 0;
+    }
+
+    fn push_back(&mut self, record: CanisterLogRecord) {
+        self.records_size += record.data_size();
+        self.records.push_back(record);
+        while self.records_size > MAX_ALLOWED_CANISTER_LOG_BUFFER_SIZE {
+            match self.records.pop_front() {
+                Some(evicted) => self.records_size -= evicted.data_size(),
+                None => break,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_get_consecutive_indices() {
+        let mut log = CanisterLog::new_with_next_index(5);
+        log.add_record(100, b"a");
+        log.add_record(200, b"b");
+        let indices: Vec<_> = log.records().iter().map(|r| r.idx).collect();
+        assert_eq!(indices, vec![5, 6]);
+        assert_eq!(log.next_idx(), 7);
+    }
+
+    #[test]
+    fn oldest_records_are_evicted_when_full() {
+        let mut log = CanisterLog::default();
+        let content = vec![b'x'; 1000];
+        for i in 0..10 {
+            log.add_record(i, &content);
+        }
+        assert!(log.used_space() <= MAX_ALLOWED_CANISTER_LOG_BUFFER_SIZE);
+        assert_eq!(log.records().back().unwrap().idx, 9);
+        assert_eq!(log.records().front().unwrap().idx, 6);
+        assert_eq!(log.next_idx(), 10);
+    }
+
+    #[test]
+    fn oversized_record_is_truncated() {
+        let mut log = CanisterLog::default();
+        log.add_record(0, &vec![b'x'; 2 * MAX_ALLOWED_CANISTER_LOG_BUFFER_SIZE]);
+        assert_eq!(log.records().len(), 1);
+        assert_eq!(log.used_space(), MAX_ALLOWED_CANISTER_LOG_BUFFER_SIZE);
+    }
+
+    #[test]
+    fn append_delta_log_moves_records() {
+        let mut log = CanisterLog::default();
+        log.add_record(0, b"first");
+        let mut delta = CanisterLog::new_with_next_index(log.next_idx());
+        delta.add_record(1, b"second");
+        log.append_delta_log(&mut delta);
+
+        assert!(delta.records().is_empty());
+        assert_eq!(delta.used_space(), 0);
+        let contents: Vec<_> = log.records().iter().map(|r| r.content.clone()).collect();
+        assert_eq!(contents, vec![b"first".to_vec(), b"second".to_vec()]);
+        assert_eq!(log.next_idx(), 2);
+    }
+
+    #[test]
+    fn new_restores_used_space() {
+        let mut log = CanisterLog::default();
+        log.add_record(0, b"abc");
+        log.add_record(1, b"defg");
+        let restored = CanisterLog::new(log.next_idx(), log.records().iter().cloned().collect());
+        assert_eq!(restored, log);
+    }
+}
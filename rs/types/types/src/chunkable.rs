@@ -27,7 +27,8 @@ pub enum ArtifactErrorCode {
 
 /// The chunk type.
 pub type ChunkId = Id<ArtifactChunk, u32>;
-pub(crate) const CHUNKID_UNIT_CHUNK: u32 = 0;
+/// The ID of the only chunk of single-chunked artifacts.
+pub const CHUNKID_UNIT_CHUNK: u32 = 0;
 
 /// The data contained in an artifact chunk.
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
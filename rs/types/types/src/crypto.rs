@@ -143,6 +143,7 @@ pub enum AlgorithmId {
     RsaSha256 = 14,
     ThresholdEcdsaSecp256k1 = 15,
     MegaSecp256k1 = 16,
+    ThresholdEcdsaSecp256r1 = 17,
 }
 
 impl AlgorithmId {
@@ -199,6 +200,7 @@ impl From<i32> for AlgorithmId {
             14 => AlgorithmId::RsaSha256,
             15 => AlgorithmId::ThresholdEcdsaSecp256k1,
             16 => AlgorithmId::MegaSecp256k1,
+            17 => AlgorithmId::ThresholdEcdsaSecp256r1,
             _ => AlgorithmId::Placeholder,
         }
     }
@@ -411,6 +411,22 @@ fn should_not_create_with_wrong_algid() {
     );
 }
 
+#[test]
+fn should_create_random_with_secp256r1_algid() {
+    let nodes = set_of_nodes(&[1]);
+
+    let result = IDkgTranscriptParams::new(
+        random_transcript_id(),
+        nodes.clone(),
+        nodes,
+        RegistryVersion::from(0),
+        AlgorithmId::ThresholdEcdsaSecp256r1,
+        IDkgTranscriptOperation::Random,
+    );
+
+    assert!(result.is_ok());
+}
+
 #[test]
 fn should_not_create_reshare_masked_with_wrong_original_type() {
     let previous_transcript = mock_transcript(
@@ -66,6 +66,7 @@
 pub mod artifact;
 pub mod batch;
 pub mod canister_http;
+pub mod canister_log;
 pub mod chunkable;
 pub mod consensus;
 pub mod crypto;
@@ -87,8 +88,9 @@ pub mod state_sync;
 pub mod time;
 pub mod xnet;
 
+pub use crate::canister_log::CanisterLog;
 pub use crate::replica_version::ReplicaVersion;
-pub use crate::time::Time;
+pub use crate::time::{CoarseTime, Time};
 pub use funds::*;
 pub use ic_base_types::{
     subnet_id_into_protobuf, subnet_id_try_from_protobuf, CanisterId, CanisterIdBlobParseError,
@@ -543,6 +545,11 @@ pub const MAX_STABLE_MEMORY_IN_BYTES: u64 = 48 * GB;
 /// it is public and `u64` (`NumBytes` cannot be used in const expressions).
 pub const MAX_WASM_MEMORY_IN_BYTES: u64 = 4 * GB;
 
+/// The upper limit on the size of a 64-bit Wasm memory (memory64).
+/// This constant is used by other crates to define other constants, that's why
+/// it is public and `u64` (`NumBytes` cannot be used in const expressions).
+pub const MAX_WASM64_MEMORY_IN_BYTES: u64 = 16 * GB;
+
 const MIN_MEMORY_ALLOCATION: NumBytes = NumBytes::new(0);
 pub const MAX_MEMORY_ALLOCATION: NumBytes =
     NumBytes::new(MAX_STABLE_MEMORY_IN_BYTES + MAX_WASM_MEMORY_IN_BYTES);
@@ -576,6 +583,59 @@ impl TryFrom<NumBytes> for MemoryAllocation {
     }
 }
 
+/// Describes the usage of a subnet resource, such as memory, relative to a
+/// threshold and to the capacity of the subnet. Once the usage exceeds the
+/// threshold, the resource is considered scarce and allocating more of it
+/// becomes more expensive.
+#[derive(Copy, Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ResourceSaturation {
+    usage: u64,
+    threshold: u64,
+    capacity: u64,
+}
+
+impl ResourceSaturation {
+    /// Creates a new `ResourceSaturation`. The threshold is capped by the
+    /// capacity.
+    pub fn new(usage: u64, threshold: u64, capacity: u64) -> Self {
+        Self {
+            usage,
+            threshold: threshold.min(capacity),
+            capacity,
+        }
+    }
+
+    pub fn usage(&self) -> u64 {
+        self.usage
+    }
+
+    pub fn threshold(&self) -> u64 {
+        self.threshold
+    }
+
+    pub fn capacity(&self) -> u64 {
+        self.capacity
+    }
+
+    /// Returns the part of the usage that exceeds the threshold.
+    pub fn usage_above_threshold(&self) -> u64 {
+        self.usage.saturating_sub(self.threshold)
+    }
+
+    /// Returns the distance between the threshold and the capacity.
+    pub fn capacity_above_threshold(&self) -> u64 {
+        self.capacity - self.threshold
+    }
+
+    /// Returns a copy with the usage increased by `delta`.
+    pub fn add(&self, delta: u64) -> Self {
+        Self {
+            usage: self.usage.saturating_add(delta),
+            ..*self
+        }
+    }
+}
+
 /// Allow an object to report its own byte size. It is only meant to be an
 /// estimate, and not an exact measure of its heap usage or length of serialized
 /// bytes.
@@ -15,7 +15,10 @@ pub use self::http::{
     HttpRequestEnvelope, HttpRequestError, HttpStatusResponse, HttpUserQuery, RawHttpRequestVal,
     ReplicaHealthStatus, SignedDelegation,
 };
-use crate::{user_id_into_protobuf, user_id_try_from_protobuf, Cycles, Funds, NumBytes, UserId};
+use crate::{
+    time::CoarseTime, user_id_into_protobuf, user_id_try_from_protobuf, Cycles, Funds, NumBytes,
+    UserId,
+};
 pub use blob::Blob;
 use ic_base_types::{CanisterId, PrincipalId};
 use ic_protobuf::proxy::{try_from_option_field, ProxyDecodeError};
@@ -67,6 +70,13 @@ pub const MAX_XNET_PAYLOAD_IN_BYTES: NumBytes =
 pub const MAX_RESPONSE_COUNT_BYTES: usize =
     size_of::<RequestOrResponse>() + MAX_INTER_CANISTER_PAYLOAD_IN_BYTES_U64 as usize;
 
+/// The deadline of guaranteed response calls, i.e. calls without a deadline.
+pub const NO_DEADLINE: CoarseTime = CoarseTime::from_secs_since_unix_epoch(0);
+
+/// Upper bound on the timeout (in seconds) that a canister may specify for a
+/// best-effort call via `ic0.call_with_best_effort_response`.
+pub const MAX_CALL_TIMEOUT_SECONDS: u32 = 300;
+
 /// An end user's signature.
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct UserSignature {
@@ -95,6 +105,8 @@ pub enum StopCanisterContext {
         /// here so that they can be returned to the caller in the eventual
         /// reply.
         cycles: Cycles,
+        /// The deadline of the request, if it was a best-effort call.
+        deadline: CoarseTime,
     },
 }
 
@@ -129,6 +141,7 @@ impl From<&StopCanisterContext> for pb::StopCanisterContext {
                 sender,
                 reply_callback,
                 cycles,
+                deadline,
             } => Self {
                 context: Some(pb::stop_canister_context::Context::Canister(
                     pb::stop_canister_context::Canister {
@@ -136,6 +149,7 @@ impl From<&StopCanisterContext> for pb::StopCanisterContext {
                         reply_callback: reply_callback.get(),
                         funds: Some((&Funds::new(*cycles)).into()),
                         cycles: Some((*cycles).into()),
+                        deadline_seconds: deadline.as_secs_since_unix_epoch(),
                     },
                 )),
             },
@@ -163,6 +177,7 @@ impl TryFrom<pb::StopCanisterContext> for StopCanisterContext {
                         reply_callback,
                         funds,
                         cycles,
+                        deadline_seconds,
                     },
                 ) => {
                     // To maintain backwards compatibility we fall back to reading from `funds` if
@@ -188,6 +203,7 @@ impl TryFrom<pb::StopCanisterContext> for StopCanisterContext {
                         )?,
                         reply_callback: CallbackId::from(reply_callback),
                         cycles,
+                        deadline: CoarseTime::from_secs_since_unix_epoch(deadline_seconds),
                     }
                 }
             };
@@ -11,7 +11,8 @@ use crate::{
 };
 use ic_error_types::{ErrorCode, UserError};
 use ic_ic00_types::{
-    CanisterIdRecord, InstallCodeArgs, Method, Payload, SetControllerArgs, UpdateSettingsArgs,
+    CanisterIdRecord, CanisterSnapshotArgs, InstallChunkedCodeArgs, InstallCodeArgs, Method,
+    Payload, SetControllerArgs, TakeCanisterSnapshotArgs, UpdateSettingsArgs, UploadChunkArgs,
 };
 use ic_protobuf::{
     log::ingress_message_log_entry::v1::IngressMessageLogEntry,
@@ -449,6 +450,9 @@ pub fn extract_effective_canister_id(
         | Ok(Method::CanisterStatus)
         | Ok(Method::DeleteCanister)
         | Ok(Method::UninstallCode)
+        | Ok(Method::ListCanisterSnapshots)
+        | Ok(Method::StoredChunks)
+        | Ok(Method::ClearChunkStore)
         | Ok(Method::StopCanister) => match CanisterIdRecord::decode(ingress.arg()) {
             Ok(record) => Ok(Some(record.get_canister_id())),
             Err(err) => Err(ParseIngressError::InvalidSubnetPayload(err.to_string())),
@@ -465,6 +469,24 @@ pub fn extract_effective_canister_id(
             Ok(record) => Ok(Some(record.get_canister_id())),
             Err(err) => Err(ParseIngressError::InvalidSubnetPayload(err.to_string())),
         },
+        Ok(Method::InstallChunkedCode) => match InstallChunkedCodeArgs::decode(ingress.arg()) {
+            Ok(record) => Ok(Some(record.target_canister_id())),
+            Err(err) => Err(ParseIngressError::InvalidSubnetPayload(err.to_string())),
+        },
+        Ok(Method::UploadChunk) => match UploadChunkArgs::decode(ingress.arg()) {
+            Ok(record) => Ok(Some(record.get_canister_id())),
+            Err(err) => Err(ParseIngressError::InvalidSubnetPayload(err.to_string())),
+        },
+        Ok(Method::TakeCanisterSnapshot) => match TakeCanisterSnapshotArgs::decode(ingress.arg()) {
+            Ok(record) => Ok(Some(record.get_canister_id())),
+            Err(err) => Err(ParseIngressError::InvalidSubnetPayload(err.to_string())),
+        },
+        Ok(Method::LoadCanisterSnapshot) | Ok(Method::DeleteCanisterSnapshot) => {
+            match CanisterSnapshotArgs::decode(ingress.arg()) {
+                Ok(record) => Ok(Some(record.get_canister_id())),
+                Err(err) => Err(ParseIngressError::InvalidSubnetPayload(err.to_string())),
+            }
+        }
         Ok(Method::CreateCanister)
         | Ok(Method::SetupInitialDKG)
         | Ok(Method::DepositCycles)
@@ -473,6 +495,8 @@ pub fn extract_effective_canister_id(
         | Ok(Method::ECDSAPublicKey)
         | Ok(Method::SignWithECDSA)
         | Ok(Method::ComputeInitialEcdsaDealings)
+        | Ok(Method::CanisterInfo)
+        | Ok(Method::FetchCanisterLogs)
         | Ok(Method::BitcoinGetBalance)
         | Ok(Method::BitcoinGetUtxos)
         | Ok(Method::BitcoinSendTransaction)
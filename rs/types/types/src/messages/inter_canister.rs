@@ -1,8 +1,12 @@
-use crate::{ingress::WasmResult, CanisterId, CountBytes, Cycles, Funds, NumBytes};
+use super::NO_DEADLINE;
+use crate::{
+    ingress::WasmResult, time::CoarseTime, CanisterId, CountBytes, Cycles, Funds, NumBytes,
+};
 use ic_error_types::{RejectCode, TryFromError, UserError};
 use ic_ic00_types::{
-    CanisterIdRecord, InstallCodeArgs, Method, Payload as _, ProvisionalTopUpCanisterArgs,
-    SetControllerArgs, UpdateSettingsArgs,
+    CanisterIdRecord, CanisterInfoRequest, CanisterSnapshotArgs, FetchCanisterLogsRequest,
+    InstallChunkedCodeArgs, InstallCodeArgs, Method, Payload as _, ProvisionalTopUpCanisterArgs,
+    SetControllerArgs, TakeCanisterSnapshotArgs, UpdateSettingsArgs, UploadChunkArgs,
 };
 use ic_protobuf::{
     proxy::{try_from_option_field, ProxyDecodeError},
@@ -44,6 +48,10 @@ pub struct Request {
     pub method_name: String,
     #[serde(with = "serde_bytes")]
     pub method_payload: Vec<u8>,
+    /// If non-zero, this is a best-effort call: the request and its response
+    /// may be dropped after this deadline (or earlier, under memory
+    /// pressure), in which case the caller gets a `SYS_UNKNOWN` reject.
+    pub deadline: CoarseTime,
 }
 
 impl Request {
@@ -57,6 +65,12 @@ impl Request {
         self.payment.take()
     }
 
+    /// Returns `true` if this is the request of a best-effort call (i.e. it
+    /// has a deadline).
+    pub fn is_best_effort(&self) -> bool {
+        self.deadline != NO_DEADLINE
+    }
+
     /// Returns this `Request`s payload.
     pub fn method_payload(&self) -> &[u8] {
         &self.method_payload
@@ -78,6 +92,9 @@ impl Request {
             | Ok(Method::DeleteCanister)
             | Ok(Method::UninstallCode)
             | Ok(Method::DepositCycles)
+            | Ok(Method::ListCanisterSnapshots)
+            | Ok(Method::StoredChunks)
+            | Ok(Method::ClearChunkStore)
             | Ok(Method::StopCanister) => match CanisterIdRecord::decode(&self.method_payload) {
                 Ok(record) => Some(record.get_canister_id()),
                 Err(_) => None,
@@ -94,12 +111,44 @@ impl Request {
                 Ok(record) => Some(record.get_canister_id()),
                 Err(_) => None,
             },
+            Ok(Method::InstallChunkedCode) => {
+                match InstallChunkedCodeArgs::decode(&self.method_payload) {
+                    Ok(record) => Some(record.target_canister_id()),
+                    Err(_) => None,
+                }
+            }
+            Ok(Method::UploadChunk) => match UploadChunkArgs::decode(&self.method_payload) {
+                Ok(record) => Some(record.get_canister_id()),
+                Err(_) => None,
+            },
             Ok(Method::ProvisionalTopUpCanister) => {
                 match ProvisionalTopUpCanisterArgs::decode(&self.method_payload) {
                     Ok(record) => Some(record.get_canister_id()),
                     Err(_) => None,
                 }
             }
+            Ok(Method::TakeCanisterSnapshot) => {
+                match TakeCanisterSnapshotArgs::decode(&self.method_payload) {
+                    Ok(record) => Some(record.get_canister_id()),
+                    Err(_) => None,
+                }
+            }
+            Ok(Method::LoadCanisterSnapshot) | Ok(Method::DeleteCanisterSnapshot) => {
+                match CanisterSnapshotArgs::decode(&self.method_payload) {
+                    Ok(record) => Some(record.get_canister_id()),
+                    Err(_) => None,
+                }
+            }
+            Ok(Method::CanisterInfo) => match CanisterInfoRequest::decode(&self.method_payload) {
+                Ok(record) => Some(record.canister_id()),
+                Err(_) => None,
+            },
+            Ok(Method::FetchCanisterLogs) => {
+                match FetchCanisterLogsRequest::decode(&self.method_payload) {
+                    Ok(record) => Some(record.get_canister_id()),
+                    Err(_) => None,
+                }
+            }
             Ok(Method::CreateCanister)
             | Ok(Method::SetupInitialDKG)
             | Ok(Method::HttpRequest)
@@ -131,6 +180,9 @@ impl std::fmt::Debug for Request {
             self.sender_reply_callback
         )?;
         write!(f, "payment: {:?}, ", self.payment)?;
+        if self.deadline != NO_DEADLINE {
+            write!(f, "deadline: {:?}, ", self.deadline)?;
+        }
         if self.method_name.len() <= 103 {
             write!(f, "method_name: {:?}, ", self.method_name)?;
         } else {
@@ -270,9 +322,17 @@ pub struct Response {
     pub originator_reply_callback: CallbackId,
     pub refund: Cycles,
     pub response_payload: Payload,
+    /// The deadline of the best-effort call this is a response to; or
+    /// `NO_DEADLINE` for responses to guaranteed response calls.
+    pub deadline: CoarseTime,
 }
 
 impl Response {
+    /// Returns `true` if this is the response to a best-effort call.
+    pub fn is_best_effort(&self) -> bool {
+        self.deadline != NO_DEADLINE
+    }
+
     /// Returns the size in bytes of this `Response`'s payload.
     pub fn payload_size_bytes(&self) -> NumBytes {
         self.response_payload.size_bytes()
@@ -304,6 +364,14 @@ impl RequestOrResponse {
         }
     }
 
+    /// Returns `true` if this message belongs to a best-effort call.
+    pub fn is_best_effort(&self) -> bool {
+        match self {
+            RequestOrResponse::Request(req) => req.is_best_effort(),
+            RequestOrResponse::Response(resp) => resp.is_best_effort(),
+        }
+    }
+
     /// Returns the size of the user-controlled part of this message (payload,
     /// method name) in bytes.
     ///
@@ -381,6 +449,7 @@ impl From<&Request> for pb_queues::Request {
             method_name: req.method_name.clone(),
             method_payload: req.method_payload.clone(),
             cycles_payment: Some((req.payment).into()),
+            deadline_seconds: req.deadline.as_secs_since_unix_epoch(),
         }
     }
 }
@@ -404,6 +473,7 @@ impl TryFrom<pb_queues::Request> for Request {
             payment,
             method_name: req.method_name,
             method_payload: req.method_payload,
+            deadline: CoarseTime::from_secs_since_unix_epoch(req.deadline_seconds),
         })
     }
 }
@@ -446,6 +516,7 @@ impl From<&Response> for pb_queues::Response {
             refund: Some((&Funds::new(rep.refund)).into()),
             response_payload: Some(p),
             cycles_refund: Some((rep.refund).into()),
+            deadline_seconds: rep.deadline.as_secs_since_unix_epoch(),
         }
     }
 }
@@ -476,6 +547,7 @@ impl TryFrom<pb_queues::Response> for Response {
             originator_reply_callback: rep.originator_reply_callback.into(),
             refund,
             response_payload,
+            deadline: CoarseTime::from_secs_since_unix_epoch(rep.deadline_seconds),
         })
     }
 }
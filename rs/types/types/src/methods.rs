@@ -1,7 +1,11 @@
 //! This module contains a collection of types and structs that define the
 //! various types of methods in the IC.
 
-use crate::{messages::CallContextId, Cycles};
+use crate::{
+    messages::{CallContextId, NO_DEADLINE},
+    time::CoarseTime,
+    Cycles,
+};
 use ic_base_types::CanisterId;
 use ic_protobuf::proxy::{try_from_option_field, ProxyDecodeError};
 use ic_protobuf::state::{canister_state_bits::v1 as pb, queues::v1::Cycles as PbCycles};
@@ -248,6 +252,9 @@ pub struct Callback {
     /// An optional closure to be executed if the execution of `on_reply` or
     /// `on_reject` traps.
     pub on_cleanup: Option<WasmClosure>,
+    /// If non-zero, this is a best-effort call and the callback expires (with
+    /// a `SYS_UNKNOWN` reject) at this deadline.
+    pub deadline: CoarseTime,
 }
 
 impl Callback {
@@ -261,6 +268,7 @@ impl Callback {
         on_reply: WasmClosure,
         on_reject: WasmClosure,
         on_cleanup: Option<WasmClosure>,
+        deadline: CoarseTime,
     ) -> Self {
         Self {
             call_context_id,
@@ -272,8 +280,14 @@ impl Callback {
             on_reply,
             on_reject,
             on_cleanup,
+            deadline,
         }
     }
+
+    /// Returns `true` if this is the callback of a best-effort call.
+    pub fn is_best_effort(&self) -> bool {
+        self.deadline != NO_DEADLINE
+    }
 }
 
 impl From<&Callback> for pb::Callback {
@@ -307,6 +321,7 @@ impl From<&Callback> for pb::Callback {
                 func_idx: on_cleanup.func_idx,
                 env: on_cleanup.env,
             }),
+            deadline_seconds: item.deadline.as_secs_since_unix_epoch(),
         }
     }
 }
@@ -351,6 +366,7 @@ impl TryFrom<pb::Callback> for Callback {
                 func_idx: on_cleanup.func_idx,
                 env: on_cleanup.env,
             }),
+            deadline: CoarseTime::from_secs_since_unix_epoch(value.deadline_seconds),
         })
     }
 }
@@ -66,6 +66,10 @@ pub const REGISTRY_POLL_PERIOD_MS: u32 = 3_000;
 /// Period for sending a retransmission request in milliseconds
 pub const RETRANSMISSION_REQUEST_MS: u32 = 60_000;
 
+/// Maximum size in bytes of an artifact that is pushed together with its
+/// advert. Pushing is disabled by default.
+pub const ARTIFACT_PUSH_THRESHOLD_BYTES: u32 = 0;
+
 /// Helper function to build a gossip config using default values.
 pub fn build_default_gossip_config() -> GossipConfig {
     GossipConfig {
@@ -77,6 +81,7 @@ pub fn build_default_gossip_config() -> GossipConfig {
         pfn_evaluation_period_ms: PFN_EVALUATION_PERIOD_MS,
         registry_poll_period_ms: REGISTRY_POLL_PERIOD_MS,
         retransmission_request_ms: RETRANSMISSION_REQUEST_MS,
+        artifact_push_threshold_bytes: ARTIFACT_PUSH_THRESHOLD_BYTES,
     }
 }
 
@@ -30,6 +30,8 @@
 //! of a manifest:
 //!
 //! * The hash in the chunk table is simply the hash of the raw chunk content.
+//!   Starting with version 2, chunks are transmitted zstd-compressed, but the
+//!   hash is still computed over the uncompressed bytes.
 //! ```text
 //!   chunk_hash := hash(dsep("ic-state-chunk") · file[offset:offset + size_bytes])
 //! ```
@@ -68,15 +70,20 @@ use crate::chunkable::ChunkId;
 use ic_protobuf::state::sync::v1 as pb;
 use serde::{Deserialize, Serialize};
 use std::{
-    collections::BTreeMap,
+    collections::{BTreeMap, VecDeque},
     fmt,
     ops::{Deref, Range},
-    sync::Arc,
+    sync::{Arc, Mutex},
 };
 
 /// Id of the manifest chunk in StateSync artifact.
 pub const MANIFEST_CHUNK: ChunkId = ChunkId::new(0);
 
+/// The first manifest version whose chunks (except for the manifest chunk
+/// itself) are transmitted zstd-compressed. Corresponds to `STATE_SYNC_V2` in
+/// the state manager.
+const MIN_COMPRESSED_CHUNKS_VERSION: u32 = 2;
+
 /// Some small files are grouped into chunks during state sync and
 /// they need to use a separate range of chunk id to avoid conflicts with normal chunks.
 //
@@ -254,6 +261,92 @@ impl fmt::Display for Manifest {
     }
 }
 
+impl ManifestData {
+    /// Returns true if the chunks of the state described by this manifest are
+    /// transmitted compressed.
+    pub fn has_compressed_chunks(&self) -> bool {
+        self.version >= MIN_COMPRESSED_CHUNKS_VERSION
+    }
+}
+
+/// Compresses the payload of a state sync chunk.
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub fn compress_chunk(bytes: &[u8]) -> Result<Vec<u8>, String> {
+    zstd::bulk::compress(bytes, zstd::DEFAULT_COMPRESSION_LEVEL)
+        .map_err(|err| format!("failed to compress chunk: {}", err))
+}
+
+/// Decompresses the payload of a state sync chunk, failing if the
+/// decompressed payload would be larger than `max_size` bytes.
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub fn decompress_chunk(bytes: &[u8], max_size: usize) -> Result<Vec<u8>, String> {
+    zstd::bulk::decompress(bytes, max_size)
+        .map_err(|err| format!("failed to decompress chunk: {}", err))
+}
+
+/// Maximum total size of the payloads kept by a [`CompressedChunkCache`].
+const COMPRESSED_CHUNK_CACHE_MAX_BYTES: usize = 128 << 20; // 128 MiB.
+
+/// Compressed payloads of the chunks of a state that were served recently, so
+/// that a chunk fetched by several peers is only compressed once. Once the
+/// payloads exceed `COMPRESSED_CHUNK_CACHE_MAX_BYTES`, the oldest ones are
+/// evicted.
+#[derive(Debug, Default)]
+pub struct CompressedChunkCache {
+    chunks: Mutex<CompressedChunks>,
+}
+
+#[derive(Debug, Default)]
+struct CompressedChunks {
+    payloads: BTreeMap<ChunkId, Arc<Vec<u8>>>,
+    insertion_order: VecDeque<ChunkId>,
+    size_bytes: usize,
+}
+
+impl CompressedChunkCache {
+    /// Returns the compressed payload of the given chunk, computing it with
+    /// `compress` if it is not cached yet.
+    pub fn get_or_insert_with<F>(&self, chunk_id: ChunkId, compress: F) -> Option<Arc<Vec<u8>>>
+    where
+        F: FnOnce() -> Option<Vec<u8>>,
+    {
+        if let Some(payload) = self.chunks.lock().unwrap().payloads.get(&chunk_id) {
+            return Some(Arc::clone(payload));
+        }
+
+        // Compress without holding the lock, so that different chunks can be
+        // compressed concurrently.
+        let payload = Arc::new(compress()?);
+
+        let mut chunks = self.chunks.lock().unwrap();
+        match chunks.payloads.insert(chunk_id, Arc::clone(&payload)) {
+            Some(previous) => chunks.size_bytes -= previous.len(),
+            None => chunks.insertion_order.push_back(chunk_id),
+        }
+        chunks.size_bytes += payload.len();
+        while chunks.size_bytes > COMPRESSED_CHUNK_CACHE_MAX_BYTES {
+            let oldest = match chunks.insertion_order.pop_front() {
+                Some(oldest) => oldest,
+                None => break,
+            };
+            if let Some(evicted) = chunks.payloads.remove(&oldest) {
+                chunks.size_bytes -= evicted.len();
+            }
+        }
+        Some(payload)
+    }
+}
+
+// A cache holds no information of its own: two messages for the same state
+// are equal no matter which chunks they have served.
+impl PartialEq for CompressedChunkCache {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for CompressedChunkCache {}
+
 /// Serializes the manifest into a byte array.
 pub fn encode_manifest(manifest: &Manifest) -> Vec<u8> {
     use prost::Message;
@@ -104,6 +104,37 @@ impl fmt::Display for Time {
     }
 }
 
+/// Time since UNIX_EPOCH with a granularity of seconds. Used for message
+/// deadlines, where nanosecond precision is not needed and a compact
+/// representation is preferable.
+#[derive(
+    Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Debug, Hash, Serialize, Deserialize,
+)]
+#[cfg_attr(test, derive(Arbitrary))]
+pub struct CoarseTime(u32);
+
+impl CoarseTime {
+    pub const fn from_secs_since_unix_epoch(secs: u32) -> Self {
+        CoarseTime(secs)
+    }
+
+    pub fn as_secs_since_unix_epoch(self) -> u32 {
+        self.0
+    }
+
+    /// Returns the `CoarseTime` closest to but no later than `time`,
+    /// saturating at `u32::MAX` seconds.
+    pub fn floor(time: Time) -> Self {
+        let secs = time.as_nanos_since_unix_epoch() / 1_000_000_000;
+        CoarseTime(secs.min(u32::MAX as u64) as u32)
+    }
+
+    /// Returns this `CoarseTime` as a nanosecond precision `Time`.
+    pub fn as_time(self) -> Time {
+        Time::from_nanos_since_unix_epoch(self.0 as u64 * 1_000_000_000)
+    }
+}
+
 /// Returns the current time.
 ///
 /// WARNING: this function should not be used in any deterministic part of the
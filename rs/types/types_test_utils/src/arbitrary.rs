@@ -2,7 +2,7 @@ use crate::ids::{canister_test_id, node_test_id, subnet_test_id, user_test_id};
 use ic_types::{
     crypto::{AlgorithmId, KeyPurpose, UserPublicKey},
     messages::{CallbackId, Payload, RejectContext, Request, RequestOrResponse, Response},
-    time::UNIX_EPOCH,
+    time::{CoarseTime, UNIX_EPOCH},
     xnet::StreamIndex,
     CanisterId, Cycles, Height, IDkgId, NodeId, RegistryVersion, SubnetId, Time, UserId,
 };
@@ -105,20 +105,37 @@ prop_compose! {
     }
 }
 
+/// Produces an arbitrary message deadline: `NO_DEADLINE` for guaranteed
+/// response messages; or a non-zero deadline for best-effort messages.
+pub fn deadline(best_effort: bool) -> impl Strategy<Value = CoarseTime> {
+    let secs = if best_effort { 1..=u32::MAX } else { 0..=0 };
+    secs.prop_map(CoarseTime::from_secs_since_unix_epoch)
+}
+
+prop_compose! {
+    /// Returns an arbitrary guaranteed response [`Request`].
+    pub fn request()(request in request_with_config(false)) -> Request {
+        request
+    }
+}
+
 prop_compose! {
-    /// Returns an arbitrary [`Request`].
-    pub fn request()(
+    /// Returns an arbitrary [`Request`], best-effort (i.e. with a deadline) iff
+    /// `best_effort` is set.
+    pub fn request_with_config(best_effort: bool)(
         receiver in canister_id(),
         sender in canister_id(),
         cycles_payment in any::<u64>(),
         method_name in "[a-zA-Z]{1,6}",
         callback in any::<u64>(),
         method_payload in prop::collection::vec(any::<u8>(), 0..16),
+        deadline in deadline(best_effort),
     ) -> Request {
         Request {
             receiver,
             sender,
             sender_reply_callback: CallbackId::from(callback),
+            deadline,
             payment: Cycles::from(cycles_payment),
             method_name,
             method_payload,
@@ -126,13 +143,15 @@ prop_compose! {
     }
 }
 
-/// Produces an arbitrary response [`Payload`].
-pub fn response_payload() -> impl Strategy<Value = Payload> {
+/// Produces an arbitrary response [`Payload`]. `SYS_UNKNOWN` rejects are only
+/// produced for best-effort responses.
+pub fn response_payload(best_effort: bool) -> impl Strategy<Value = Payload> {
+    let reject_codes = if best_effort { 1u64..7 } else { 1u64..5 };
     prop_oneof![
         // Data payload.
         prop::collection::vec(any::<u8>(), 0..16).prop_flat_map(|data| Just(Payload::Data(data))),
         // Reject payload.
-        (1u64..5, "[a-zA-Z]{1,6}").prop_flat_map(|(code, message)| Just(Payload::Reject(
+        (reject_codes, "[a-zA-Z]{1,6}").prop_flat_map(|(code, message)| Just(Payload::Reject(
             RejectContext {
                 code: code.try_into().unwrap(),
                 message
@@ -142,29 +161,47 @@ pub fn response_payload() -> impl Strategy<Value = Payload> {
 }
 
 prop_compose! {
-    /// Returns an arbitrary [`Response`].
-    pub fn response()(
+    /// Returns an arbitrary guaranteed response [`Response`].
+    pub fn response()(response in response_with_config(false)) -> Response {
+        response
+    }
+}
+
+prop_compose! {
+    /// Returns an arbitrary [`Response`], best-effort (i.e. with a deadline)
+    /// iff `best_effort` is set.
+    pub fn response_with_config(best_effort: bool)(
         originator in canister_id(),
         respondent in canister_id(),
         callback in any::<u64>(),
         cycles_refund in any::<u64>(),
-        response_payload in response_payload(),
+        response_payload in response_payload(best_effort),
+        deadline in deadline(best_effort),
     ) -> Response {
         Response {
             originator,
             respondent,
             originator_reply_callback: CallbackId::from(callback),
+            deadline,
             refund: Cycles::from(cycles_refund),
             response_payload
         }
     }
 }
 
-/// Produces an arbitrary [`RequestOrResponse`].
+/// Produces an arbitrary guaranteed response [`RequestOrResponse`].
 pub fn request_or_response() -> impl Strategy<Value = RequestOrResponse> {
+    request_or_response_with_config(false)
+}
+
+/// Produces an arbitrary [`RequestOrResponse`], best-effort (i.e. with a
+/// deadline) iff `best_effort` is set.
+pub fn request_or_response_with_config(
+    best_effort: bool,
+) -> impl Strategy<Value = RequestOrResponse> {
     prop_oneof![
-        request().prop_flat_map(|req| Just(req.into())),
-        response().prop_flat_map(|rep| Just(rep.into())),
+        request_with_config(best_effort).prop_flat_map(|req| Just(req.into())),
+        response_with_config(best_effort).prop_flat_map(|rep| Just(rep.into())),
     ]
 }
 
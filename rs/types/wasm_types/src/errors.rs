@@ -34,6 +34,8 @@ pub enum WasmValidationError {
     InvalidExportSection(String),
     /// Module contains an invalid data section
     InvalidDataSection(String),
+    /// Module contains an invalid memory section
+    InvalidMemorySection(String),
     /// Module contains an invalid custom section
     InvalidCustomSection(String),
     /// Module contains too many globals.
@@ -82,6 +84,9 @@ impl std::fmt::Display for WasmValidationError {
             Self::InvalidDataSection(err) => {
                 write!(f, "Wasm module has an invalid data section. {}", err)
             }
+            Self::InvalidMemorySection(err) => {
+                write!(f, "Wasm module has an invalid memory section. {}", err)
+            }
             Self::InvalidCustomSection(err) => {
                 write!(f, "Wasm module has an invalid custom section. {}", err)
             }